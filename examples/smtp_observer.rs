@@ -0,0 +1,86 @@
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use lettre::{
+    transport::smtp::{
+        observer::{CloseReason, TransportObserver},
+        response::Response,
+        Error,
+    },
+    Message, SmtpTransport, Transport,
+};
+
+/// Counters which could be exported to Prometheus or statsd, for example with
+/// the `metrics` crate
+#[derive(Debug, Default)]
+struct Metrics {
+    connections: AtomicU64,
+    connect_micros: AtomicU64,
+    sent: AtomicU64,
+    transient_failures: AtomicU64,
+    permanent_failures: AtomicU64,
+    other_failures: AtomicU64,
+    bytes: AtomicU64,
+    broken_connections: AtomicU64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Observer(Arc<Metrics>);
+
+impl TransportObserver for Observer {
+    fn on_connect(&self, duration: Duration, _tls: bool) {
+        self.0.connections.fetch_add(1, Ordering::Relaxed);
+        self.0
+            .connect_micros
+            .fetch_add(duration.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    fn on_send(&self, result: Result<&Response, &Error>, _duration: Duration, size: usize) {
+        let counter = match result {
+            Ok(_) => {
+                self.0.bytes.fetch_add(size as u64, Ordering::Relaxed);
+                &self.0.sent
+            }
+            Err(err) if err.is_transient() => &self.0.transient_failures,
+            Err(err) if err.is_permanent() => &self.0.permanent_failures,
+            Err(_) => &self.0.other_failures,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn on_connection_closed(&self, reason: CloseReason) {
+        if reason == CloseReason::Broken {
+            self.0.broken_connections.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+fn main() {
+    tracing_subscriber::fmt::init();
+
+    let email = Message::builder()
+        .from("NoBody <nobody@domain.tld>".parse().unwrap())
+        .reply_to("Yuin <yuin@domain.tld>".parse().unwrap())
+        .to("Hei <hei@domain.tld>".parse().unwrap())
+        .subject("Happy new year")
+        .body(String::from("Be happy!"))
+        .unwrap();
+
+    // Open a local connection on port 25, observed to collect metrics
+    let observer = Observer::default();
+    let mailer = SmtpTransport::builder_dangerous("localhost")
+        .observer(observer.clone())
+        .build();
+
+    // Send the email
+    match mailer.send(&email) {
+        Ok(_) => println!("Email sent successfully!"),
+        Err(e) => println!("Could not send email: {:?}", e),
+    }
+    println!("{:#?}", observer.0);
+}
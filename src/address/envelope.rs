@@ -1,5 +1,5 @@
 #[cfg(feature = "builder")]
-use std::convert::TryFrom;
+use std::{collections::HashSet, convert::TryFrom};
 
 use super::Address;
 #[cfg(feature = "builder")]
@@ -106,10 +106,12 @@ impl Envelope {
 }
 
 #[cfg(feature = "builder")]
-impl TryFrom<&Headers> for Envelope {
-    type Error = Error;
-
-    fn try_from(headers: &Headers) -> Result<Self, Self::Error> {
+impl Envelope {
+    /// Derives the envelope from the `Sender`, `From`, `To`, `Cc` and `Bcc` headers
+    ///
+    /// The recipients found several times are only kept once, unless `keep_duplicates`
+    /// is set.
+    pub(crate) fn from_headers(headers: &Headers, keep_duplicates: bool) -> Result<Self, Error> {
         let from = match headers.get::<header::Sender>() {
             // If there is a Sender, use it
             Some(sender) => Some(Mailbox::from(sender).email),
@@ -139,7 +141,61 @@ impl TryFrom<&Headers> for Envelope {
         add_addresses_from_mailboxes(&mut to, headers.get::<header::To>().map(|h| h.0));
         add_addresses_from_mailboxes(&mut to, headers.get::<header::Cc>().map(|h| h.0));
         add_addresses_from_mailboxes(&mut to, headers.get::<header::Bcc>().map(|h| h.0));
+        if !keep_duplicates {
+            dedup_recipients(&mut to);
+        }
 
         Self::new(from, to)
     }
 }
+
+/// Removes the recipients found several times, keeping their first occurrence
+///
+/// The domains are compared case-insensitively, and the local parts exactly.
+#[cfg(feature = "builder")]
+pub(crate) fn dedup_recipients(addresses: &mut Vec<Address>) {
+    let mut seen = HashSet::new();
+    addresses.retain(|address| {
+        seen.insert((address.user().to_owned(), address.domain().to_lowercase()))
+    });
+}
+
+#[cfg(feature = "builder")]
+impl TryFrom<&Headers> for Envelope {
+    type Error = Error;
+
+    /// Derives the envelope from the headers, see [`MessageBuilder::keep_duplicate_recipients`]
+    ///
+    /// [`MessageBuilder::keep_duplicate_recipients`]: crate::message::MessageBuilder::keep_duplicate_recipients
+    fn try_from(headers: &Headers) -> Result<Self, Self::Error> {
+        Self::from_headers(headers, false)
+    }
+}
+
+#[cfg(all(test, feature = "builder"))]
+mod test {
+    use super::*;
+
+    fn addresses(addresses: &[&str]) -> Vec<Address> {
+        addresses
+            .iter()
+            .map(|address| address.parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn dedup_recipients_domain_case() {
+        let mut to = addresses(&[
+            "hei@domain.tld",
+            "yuin@domain.tld",
+            "hei@DOMAIN.tld",
+            "Hei@domain.tld",
+            "yuin@domain.tld",
+        ]);
+        dedup_recipients(&mut to);
+        assert_eq!(
+            to,
+            addresses(&["hei@domain.tld", "yuin@domain.tld", "Hei@domain.tld"])
+        );
+    }
+}
@@ -6,6 +6,8 @@ mod serde;
 mod envelope;
 mod types;
 
+#[cfg(feature = "builder")]
+pub(crate) use self::envelope::dedup_recipients;
 pub use self::{
     envelope::Envelope,
     types::{Address, AddressError},
@@ -15,6 +15,8 @@ pub enum Error {
     MissingTo,
     /// Can only be one from in envelope
     TooManyFrom,
+    /// The `Sender` header is the same mailbox as the single `From` mailbox
+    SenderIsFrom,
     /// Invalid email: missing at
     EmailMissingAt,
     /// Invalid email: missing local part
@@ -27,6 +29,35 @@ pub enum Error {
     Io(std::io::Error),
     /// Non-ASCII chars
     NonAsciiChars,
+    /// A header value contains a CR or LF character
+    HeaderLineBreak,
+    /// A middleware tried to change a header the envelope was built from.
+    /// Contains the name of the header.
+    EnvelopeHeader(String),
+    /// A line of the header block of a parsed message isn't a header field.
+    /// Contains the number of the line, starting at 1.
+    MalformedHeader(usize),
+    /// A line of the body is longer than the maximum line length.
+    /// Contains the number of the line in the body, or in the body of the part, starting at 1.
+    LineTooLong(usize),
+    /// The charset isn't known, or can't be used to encode text
+    UnknownCharset(String),
+    /// The text contains characters which can't be represented in the charset
+    UnrepresentableChars(String),
+    /// A `text/x-amp-html` part is the last part of a `multipart/alternative`, hiding the
+    /// HTML part
+    AmpHtmlLast,
+    /// The message is larger than the maximum size set with `MessageBuilder::max_size`
+    TooLarge {
+        /// Size of the formatted message in bytes
+        size: usize,
+        /// Maximum size of the message in bytes
+        max_size: usize,
+        /// Content type of the part with the largest body, with its `Content-Disposition`
+        largest_part: String,
+        /// Size of the body of the largest part in bytes
+        largest_part_size: usize,
+    },
 }
 
 impl Display for Error {
@@ -35,11 +66,35 @@ impl Display for Error {
             Error::MissingFrom => f.write_str("missing source address, invalid envelope"),
             Error::MissingTo => f.write_str("missing destination address, invalid envelope"),
             Error::TooManyFrom => f.write_str("there can only be one source address"),
+            Error::SenderIsFrom => f.write_str("the sender is the same as the author"),
             Error::EmailMissingAt => f.write_str("missing @ in email address"),
             Error::EmailMissingLocalPart => f.write_str("missing local part in email address"),
             Error::EmailMissingDomain => f.write_str("missing domain in email address"),
             Error::CannotParseFilename => f.write_str("could not parse attachment filename"),
             Error::NonAsciiChars => f.write_str("contains non-ASCII chars"),
+            Error::HeaderLineBreak => f.write_str("header value contains a line break"),
+            Error::EnvelopeHeader(name) => {
+                write!(f, "the envelope was built from the {} header", name)
+            }
+            Error::MalformedHeader(line) => {
+                write!(f, "line {} of the headers isn't a header field", line)
+            }
+            Error::LineTooLong(line) => write!(f, "line {} of the body is too long", line),
+            Error::UnknownCharset(charset) => write!(f, "unknown charset {}", charset),
+            Error::UnrepresentableChars(charset) => {
+                write!(f, "text can't be represented in charset {}", charset)
+            }
+            Error::AmpHtmlLast => f.write_str("the AMP part is the last alternative"),
+            Error::TooLarge {
+                size,
+                max_size,
+                largest_part,
+                largest_part_size,
+            } => write!(
+                f,
+                "message of {} bytes exceeds the maximum size of {} bytes, its largest part is {} of {} bytes",
+                size, max_size, largest_part, largest_part_size
+            ),
             Error::Io(e) => e.fmt(f),
         }
     }
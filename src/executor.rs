@@ -1,16 +1,22 @@
 use async_trait::async_trait;
-#[cfg(all(feature = "smtp-transport", feature = "async-std1"))]
+#[cfg(feature = "async-std1")]
 use futures_util::future::BoxFuture;
 
-use std::fmt::Debug;
-#[cfg(feature = "smtp-transport")]
-use std::future::Future;
 #[cfg(feature = "file-transport")]
 use std::io::Result as IoResult;
-#[cfg(feature = "file-transport")]
+#[cfg(any(feature = "file-transport", all(feature = "smtp-transport", unix)))]
 use std::path::Path;
+use std::{fmt::Debug, future::Future, time::Duration};
 #[cfg(feature = "smtp-transport")]
-use std::time::Duration;
+use std::{io, net::SocketAddr, ops::RangeInclusive};
+#[cfg(all(
+    feature = "tokio1",
+    any(feature = "builder", feature = "smtp-transport")
+))]
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+};
 
 #[cfg(all(
     feature = "smtp-transport",
@@ -21,7 +27,7 @@ use crate::transport::smtp::client::AsyncSmtpConnection;
     feature = "smtp-transport",
     any(feature = "tokio1", feature = "async-std1")
 ))]
-use crate::transport::smtp::client::Tls;
+use crate::transport::smtp::client::{ProxyProtocol, Tls};
 #[cfg(all(
     feature = "smtp-transport",
     any(feature = "tokio1", feature = "async-std1")
@@ -31,6 +37,11 @@ use crate::transport::smtp::extension::ClientId;
     feature = "smtp-transport",
     any(feature = "tokio1", feature = "async-std1")
 ))]
+use crate::transport::smtp::transcript::TranscriptConfig;
+#[cfg(all(
+    feature = "smtp-transport",
+    any(feature = "tokio1", feature = "async-std1")
+))]
 use crate::transport::smtp::Error;
 
 /// Async executor abstraction trait
@@ -46,7 +57,6 @@ use crate::transport::smtp::Error;
 pub trait Executor: Debug + Send + Sync + 'static + private::Sealed {
     #[cfg(feature = "smtp-transport")]
     type Handle: SpawnHandle;
-    #[cfg(feature = "smtp-transport")]
     type Sleep: Future<Output = ()> + Send + 'static;
 
     #[doc(hidden)]
@@ -57,17 +67,34 @@ pub trait Executor: Debug + Send + Sync + 'static + private::Sealed {
         F::Output: Send + 'static;
 
     #[doc(hidden)]
-    #[cfg(feature = "smtp-transport")]
     fn sleep(duration: Duration) -> Self::Sleep;
 
     #[doc(hidden)]
     #[cfg(feature = "smtp-transport")]
+    async fn resolve(hostname: &str, port: u16) -> io::Result<Vec<SocketAddr>>;
+
+    #[doc(hidden)]
+    #[cfg(feature = "smtp-transport")]
+    #[allow(clippy::too_many_arguments)]
     async fn connect(
-        hostname: &str,
-        port: u16,
+        addrs: &[SocketAddr],
         timeout: Option<Duration>,
-        hello_name: &ClientId,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        source_ports: Option<&RangeInclusive<u16>>,
+        proxy_protocol: Option<&ProxyProtocol>,
         tls: &Tls,
+        transcript: Option<&TranscriptConfig>,
+    ) -> Result<AsyncSmtpConnection, Error>;
+
+    #[doc(hidden)]
+    #[cfg(all(feature = "smtp-transport", unix))]
+    async fn connect_unix(
+        path: &Path,
+        timeout: Option<Duration>,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        transcript: Option<&TranscriptConfig>,
     ) -> Result<AsyncSmtpConnection, Error>;
 
     #[doc(hidden)]
@@ -106,7 +133,6 @@ pub struct Tokio1Executor;
 impl Executor for Tokio1Executor {
     #[cfg(feature = "smtp-transport")]
     type Handle = tokio1_crate::task::JoinHandle<()>;
-    #[cfg(feature = "smtp-transport")]
     type Sleep = tokio1_crate::time::Sleep;
 
     #[doc(hidden)]
@@ -120,19 +146,30 @@ impl Executor for Tokio1Executor {
     }
 
     #[doc(hidden)]
-    #[cfg(feature = "smtp-transport")]
     fn sleep(duration: Duration) -> Self::Sleep {
         tokio1_crate::time::sleep(duration)
     }
 
     #[doc(hidden)]
     #[cfg(feature = "smtp-transport")]
+    async fn resolve(hostname: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        Ok(tokio1_crate::net::lookup_host((hostname, port))
+            .await?
+            .collect())
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "smtp-transport")]
+    #[allow(clippy::too_many_arguments)]
     async fn connect(
-        hostname: &str,
-        port: u16,
+        addrs: &[SocketAddr],
         timeout: Option<Duration>,
-        hello_name: &ClientId,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        source_ports: Option<&RangeInclusive<u16>>,
+        proxy_protocol: Option<&ProxyProtocol>,
         tls: &Tls,
+        transcript: Option<&TranscriptConfig>,
     ) -> Result<AsyncSmtpConnection, Error> {
         #[allow(clippy::match_single_binding)]
         let tls_parameters = match tls {
@@ -141,23 +178,31 @@ impl Executor for Tokio1Executor {
             _ => None,
         };
         #[allow(unused_mut)]
-        let mut conn = AsyncSmtpConnection::connect_tokio1(
-            (hostname, port),
+        let mut conn = AsyncSmtpConnection::connect_tokio1_with(
+            addrs,
             timeout,
+            greeting_timeout,
             hello_name,
+            source_ports,
+            proxy_protocol,
             tls_parameters,
+            transcript,
         )
         .await?;
 
         #[cfg(any(feature = "tokio1-native-tls", feature = "tokio1-rustls-tls"))]
         match tls {
-            Tls::Opportunistic(ref tls_parameters) => {
-                if conn.can_starttls() {
-                    conn.starttls(tls_parameters.clone(), hello_name).await?;
-                }
+            Tls::Opportunistic(ref tls_parameters) if conn.can_starttls() => {
+                let hello_name = conn.hello_name().clone();
+                conn.starttls(tls_parameters.clone(), &hello_name)
+                    .await
+                    .map_err(|err| conn.attach_transcript(err))?;
             }
             Tls::Required(ref tls_parameters) => {
-                conn.starttls(tls_parameters.clone(), hello_name).await?;
+                let hello_name = conn.hello_name().clone();
+                conn.starttls(tls_parameters.clone(), &hello_name)
+                    .await
+                    .map_err(|err| conn.attach_transcript(err))?;
             }
             _ => (),
         }
@@ -165,6 +210,25 @@ impl Executor for Tokio1Executor {
         Ok(conn)
     }
 
+    #[doc(hidden)]
+    #[cfg(all(feature = "smtp-transport", unix))]
+    async fn connect_unix(
+        path: &Path,
+        timeout: Option<Duration>,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        transcript: Option<&TranscriptConfig>,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        AsyncSmtpConnection::connect_tokio1_unix_with(
+            path,
+            timeout,
+            greeting_timeout,
+            hello_name,
+            transcript,
+        )
+        .await
+    }
+
     #[doc(hidden)]
     #[cfg(feature = "file-transport-envelope")]
     async fn fs_read(path: &Path) -> IoResult<Vec<u8>> {
@@ -206,7 +270,6 @@ pub struct AsyncStd1Executor;
 impl Executor for AsyncStd1Executor {
     #[cfg(feature = "smtp-transport")]
     type Handle = async_std::task::JoinHandle<()>;
-    #[cfg(feature = "smtp-transport")]
     type Sleep = BoxFuture<'static, ()>;
 
     #[doc(hidden)]
@@ -220,7 +283,6 @@ impl Executor for AsyncStd1Executor {
     }
 
     #[doc(hidden)]
-    #[cfg(feature = "smtp-transport")]
     fn sleep(duration: Duration) -> Self::Sleep {
         let fut = async move { async_std::task::sleep(duration).await };
         Box::pin(fut)
@@ -228,12 +290,24 @@ impl Executor for AsyncStd1Executor {
 
     #[doc(hidden)]
     #[cfg(feature = "smtp-transport")]
+    async fn resolve(hostname: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+        use async_std::net::ToSocketAddrs;
+
+        Ok((hostname, port).to_socket_addrs().await?.collect())
+    }
+
+    #[doc(hidden)]
+    #[cfg(feature = "smtp-transport")]
+    #[allow(clippy::too_many_arguments)]
     async fn connect(
-        hostname: &str,
-        port: u16,
+        addrs: &[SocketAddr],
         timeout: Option<Duration>,
-        hello_name: &ClientId,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        source_ports: Option<&RangeInclusive<u16>>,
+        proxy_protocol: Option<&ProxyProtocol>,
         tls: &Tls,
+        transcript: Option<&TranscriptConfig>,
     ) -> Result<AsyncSmtpConnection, Error> {
         #[allow(clippy::match_single_binding)]
         let tls_parameters = match tls {
@@ -242,23 +316,31 @@ impl Executor for AsyncStd1Executor {
             _ => None,
         };
         #[allow(unused_mut)]
-        let mut conn = AsyncSmtpConnection::connect_asyncstd1(
-            (hostname, port),
+        let mut conn = AsyncSmtpConnection::connect_asyncstd1_with(
+            addrs,
             timeout,
+            greeting_timeout,
             hello_name,
+            source_ports,
+            proxy_protocol,
             tls_parameters,
+            transcript,
         )
         .await?;
 
         #[cfg(any(feature = "async-std1-native-tls", feature = "async-std1-rustls-tls"))]
         match tls {
-            Tls::Opportunistic(ref tls_parameters) => {
-                if conn.can_starttls() {
-                    conn.starttls(tls_parameters.clone(), hello_name).await?;
-                }
+            Tls::Opportunistic(ref tls_parameters) if conn.can_starttls() => {
+                let hello_name = conn.hello_name().clone();
+                conn.starttls(tls_parameters.clone(), &hello_name)
+                    .await
+                    .map_err(|err| conn.attach_transcript(err))?;
             }
             Tls::Required(ref tls_parameters) => {
-                conn.starttls(tls_parameters.clone(), hello_name).await?;
+                let hello_name = conn.hello_name().clone();
+                conn.starttls(tls_parameters.clone(), &hello_name)
+                    .await
+                    .map_err(|err| conn.attach_transcript(err))?;
             }
             _ => (),
         }
@@ -266,6 +348,25 @@ impl Executor for AsyncStd1Executor {
         Ok(conn)
     }
 
+    #[doc(hidden)]
+    #[cfg(all(feature = "smtp-transport", unix))]
+    async fn connect_unix(
+        path: &Path,
+        timeout: Option<Duration>,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        transcript: Option<&TranscriptConfig>,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        AsyncSmtpConnection::connect_asyncstd1_unix_with(
+            path,
+            timeout,
+            greeting_timeout,
+            hello_name,
+            transcript,
+        )
+        .await
+    }
+
     #[doc(hidden)]
     #[cfg(feature = "file-transport-envelope")]
     async fn fs_read(path: &Path) -> IoResult<Vec<u8>> {
@@ -287,6 +388,32 @@ impl SpawnHandle for async_std::task::JoinHandle<()> {
     }
 }
 
+/// Reads a tokio reader through the futures `AsyncRead` trait
+#[cfg(all(
+    feature = "tokio1",
+    any(feature = "builder", feature = "smtp-transport")
+))]
+pub(crate) struct Tokio1Read<R>(pub(crate) R);
+
+#[cfg(all(
+    feature = "tokio1",
+    any(feature = "builder", feature = "smtp-transport")
+))]
+impl<R: tokio1_crate::io::AsyncRead + Unpin> futures_io::AsyncRead for Tokio1Read<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<std::io::Result<usize>> {
+        let mut b = tokio1_crate::io::ReadBuf::new(buf);
+        match Pin::new(&mut self.0).poll_read(cx, &mut b) {
+            Poll::Ready(Ok(())) => Poll::Ready(Ok(b.filled().len())),
+            Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
 mod private {
     use super::*;
 
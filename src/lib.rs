@@ -21,6 +21,10 @@
 //!
 //! * **builder** 📫: Enable the [`Message`] builder
 //! * **hostname** 📫: Try to use the actual system hostname in the `Message-ID` header
+//! * **charset**: Encode text parts in other charsets than UTF-8, see
+//!   [`SinglePartBuilder::body_with_charset`](message::SinglePartBuilder::body_with_charset)
+//! * **default-user-agent**: Add a `User-Agent: lettre/<version>` header to the messages
+//!   which have none, see [`MessageBuilder::no_user_agent`](message::MessageBuilder::no_user_agent)
 //!
 //! ### SMTP transport
 //!
@@ -30,6 +34,11 @@
 //! * **r2d2** 📫: Connection pool for SMTP transport
 //! * **pool** 📫: Async connection pool for SMTP transport
 //! * **hostname** 📫: Try to use the actual system hostname for the SMTP `CLIENTID`
+//! * **socket2**: Connect from a range of source ports, and send emails over a socket
+//!   connected by the caller, see
+//!   [`SmtpTransportBuilder::source_port_range`](transport::smtp::SmtpTransportBuilder::source_port_range)
+//!   and [`SmtpTransport::from_socket`](transport::smtp::SmtpTransport::from_socket).
+//!   Requires Rust 1.70, unlike the other features
 //!
 //! #### SMTP over TLS via the native-tls crate
 //!
@@ -84,7 +93,10 @@
 //! _Additional features_
 //!
 //! * **serde**: Serialization/Deserialization of entities
-//! * **tracing**: Logging using the `tracing` crate
+//! * **tracing**: Logging using the `tracing` crate. The SMTP transport opens a span for
+//!   each connection and each message, with the connection and transfer timings, and logs the
+//!   commands and replies at the debug level, without credentials or message contents
+//! * **test-server**: Mock SMTP server for integration tests, see [`test_server`]
 //!
 //! [`SMTP`]: crate::transport::smtp
 //! [`sendmail`]: crate::transport::sendmail
@@ -117,15 +129,18 @@ mod executor;
 #[cfg(feature = "builder")]
 #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
 pub mod message;
+#[cfg(feature = "test-server")]
+#[cfg_attr(docsrs, doc(cfg(feature = "test-server")))]
+pub mod test_server;
 pub mod transport;
 
 #[cfg(feature = "async-std1")]
 pub use self::executor::AsyncStd1Executor;
-#[cfg(all(any(feature = "tokio1", feature = "async-std1")))]
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
 pub use self::executor::Executor;
 #[cfg(feature = "tokio1")]
 pub use self::executor::Tokio1Executor;
-#[cfg(all(any(feature = "tokio1", feature = "async-std1")))]
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
 #[doc(inline)]
 pub use self::transport::AsyncTransport;
 pub use crate::address::Address;
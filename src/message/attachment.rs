@@ -1,14 +1,27 @@
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use std::io;
+
+#[cfg(feature = "tokio1")]
+use crate::executor::Tokio1Read;
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use crate::message::body::Base64Encoder;
 use crate::message::{
-    header::{self, ContentType},
+    body::{assert_base64_line_length, BASE64_LINE_LENGTH},
+    header::{self, ContentTransferEncoding, ContentType},
     IntoBody, SinglePart,
 };
 
+/// Size of the chunks read from async readers
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+const READ_CHUNK_SIZE: usize = 8 * 1024;
+
 /// `SinglePart` builder for attachments
 ///
 /// Allows building attachment parts easily.
 #[derive(Clone)]
 pub struct Attachment {
     disposition: Disposition,
+    base64_line_length: Option<usize>,
 }
 
 #[derive(Clone)]
@@ -24,6 +37,7 @@ impl Attachment {
     pub fn new(filename: String) -> Self {
         Attachment {
             disposition: Disposition::Attached(filename),
+            base64_line_length: Some(BASE64_LINE_LENGTH),
         }
     }
 
@@ -31,9 +45,24 @@ impl Attachment {
     pub fn new_inline(content_id: String) -> Self {
         Attachment {
             disposition: Disposition::Inline(content_id),
+            base64_line_length: Some(BASE64_LINE_LENGTH),
         }
     }
 
+    /// Set the length of the lines of the content when it's encoded as `base64`
+    ///
+    /// Defaults to the 76 octets defined by MIME, with `None` not wrapping the lines.
+    /// See [`Body::new_base64_with_line_length`](crate::message::Body::new_base64_with_line_length).
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_length` is 0 or longer than 998.
+    pub fn base64_line_length(mut self, line_length: Option<usize>) -> Self {
+        assert_base64_line_length(line_length);
+        self.base64_line_length = line_length;
+        self
+    }
+
     /// Build the attachment part
     pub fn body<T: IntoBody>(self, content: T, content_type: ContentType) -> SinglePart {
         let mut builder = SinglePart::builder();
@@ -46,7 +75,60 @@ impl Attachment {
                 .header(header::ContentDisposition::inline()),
         };
         builder = builder.header(content_type);
-        builder.body(content)
+
+        let mut body = content.into_body(None);
+        if body.encoding() == ContentTransferEncoding::Base64
+            && self.base64_line_length != Some(BASE64_LINE_LENGTH)
+        {
+            body = body.rewrap_base64(self.base64_line_length);
+        }
+        builder.body(body)
+    }
+
+    /// Build the attachment part, reading the whole content from an async reader
+    ///
+    /// The reader is read to the end before returning, and the base64 encoded content
+    /// is kept in memory like with [`Attachment::body`]. Only the reading is done
+    /// asynchronously.
+    #[cfg(any(feature = "tokio1", feature = "async-std1"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "tokio1", feature = "async-std1"))))]
+    pub async fn body_from_async_read<R>(
+        self,
+        mut reader: R,
+        content_type: ContentType,
+    ) -> io::Result<SinglePart>
+    where
+        R: futures_io::AsyncRead + Unpin,
+    {
+        use futures_util::io::AsyncReadExt;
+
+        let mut encoder = Base64Encoder::with_line_length(READ_CHUNK_SIZE, self.base64_line_length);
+        let mut chunk = vec![0; READ_CHUNK_SIZE];
+        loop {
+            match reader.read(&mut chunk).await? {
+                0 => break,
+                n => encoder.update(&chunk[..n]),
+            }
+        }
+
+        Ok(self.body(encoder.finish(), content_type))
+    }
+
+    /// Build the attachment part, reading the whole content from a tokio reader
+    ///
+    /// See [`Attachment::body_from_async_read`].
+    #[cfg(feature = "tokio1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio1")))]
+    pub async fn body_from_tokio1_read<R>(
+        self,
+        reader: R,
+        content_type: ContentType,
+    ) -> io::Result<SinglePart>
+    where
+        R: tokio1_crate::io::AsyncRead + Unpin,
+    {
+        self.body_from_async_read(Tokio1Read(reader), content_type)
+            .await
     }
 }
 
@@ -88,4 +170,66 @@ mod tests {
             )
         );
     }
+
+    #[test]
+    fn attachment_base64_line_length() {
+        let content: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let encoded_lines = |line_length| {
+            let part = super::Attachment::new(String::from("data.bin"))
+                .base64_line_length(line_length)
+                .body(
+                    content.clone(),
+                    ContentType::parse("application/octet-stream").unwrap(),
+                );
+            let formatted = String::from_utf8(part.formatted()).unwrap();
+            let (_headers, body) = formatted.split_at(formatted.find("\r\n\r\n").unwrap() + 4);
+            assert_eq!(base64::decode(body.replace("\r\n", "")).unwrap(), content);
+            body.lines().map(str::len).collect::<Vec<_>>()
+        };
+
+        let lines = encoded_lines(Some(64));
+        assert_eq!(lines.len(), 21);
+        assert!(lines[..20].iter().all(|&len| len == 64));
+        assert_eq!(encoded_lines(None), [1336]);
+        assert!(encoded_lines(Some(76)).iter().all(|&len| len <= 76));
+    }
+
+    #[cfg(feature = "async-std1")]
+    #[async_std::test]
+    async fn attachment_body_from_async_read() {
+        let content: Vec<u8> = (0..=255).cycle().take(20_000).collect();
+        let expected = super::Attachment::new(String::from("data.bin")).body(
+            content.clone(),
+            ContentType::parse("application/octet-stream").unwrap(),
+        );
+
+        let part = super::Attachment::new(String::from("data.bin"))
+            .body_from_async_read(
+                futures_util::io::Cursor::new(content),
+                ContentType::parse("application/octet-stream").unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(part.formatted(), expected.formatted());
+    }
+
+    #[cfg(feature = "tokio1")]
+    #[tokio1_crate::test(crate = "tokio1_crate")]
+    async fn attachment_body_from_tokio1_read() {
+        let content: &[u8] = b"Hello world!";
+
+        let part = super::Attachment::new(String::from("test.txt"))
+            .body_from_tokio1_read(content, ContentType::parse("text/plain").unwrap())
+            .await
+            .unwrap();
+        assert_eq!(
+            &String::from_utf8_lossy(&part.formatted()),
+            concat!(
+                "Content-Disposition: attachment; filename=\"test.txt\"\r\n",
+                "Content-Type: text/plain\r\n",
+                "Content-Transfer-Encoding: base64\r\n\r\n",
+                "SGVsbG8gd29ybGQh\r\n",
+            )
+        );
+    }
 }
@@ -1,8 +1,4 @@
-use std::{
-    io::{self, Write},
-    mem,
-    ops::Deref,
-};
+use std::{mem, ops::Deref};
 
 use crate::message::header::ContentTransferEncoding;
 
@@ -39,9 +35,19 @@ impl Body {
     /// can be encoded as `7bit` or `quoted-printable`, while `Vec<u8>` always
     /// get encoded as `base64`.
     pub fn new<B: Into<MaybeString>>(buf: B) -> Self {
+        Self::new_with_max_line_length(buf, MAX_LINE_LENGTH)
+    }
+
+    /// Encode the supplied `buf` like [`Body::new`], choosing an encoding which doesn't
+    /// produce lines longer than `max_line_length` octets, excluding the line ending.
+    ///
+    /// `String`s with longer lines are encoded as `quoted-printable`, which wraps lines
+    /// at 76 octets. `max_line_length` can't be lower than that, and defaults to
+    /// the 998 octets allowed by [RFC 5321](https://tools.ietf.org/html/rfc5321#section-4.5.3.1.6).
+    pub fn new_with_max_line_length<B: Into<MaybeString>>(buf: B, max_line_length: usize) -> Self {
         let mut buf: MaybeString = buf.into();
 
-        let encoding = buf.encoding();
+        let encoding = buf.encoding_with_max_line_length(max_line_length);
         buf.encode_crlf();
         Self::new_impl(buf.into(), encoding)
     }
@@ -69,6 +75,55 @@ impl Body {
         Ok(Self::new_impl(buf.into(), encoding))
     }
 
+    /// Encode the supplied `buf` as `base64`, with lines of `line_length` octets
+    ///
+    /// [`Body::new_with_encoding`] wraps the lines at [`BASE64_LINE_LENGTH`], the 76 octets
+    /// defined by MIME, while some tools expect shorter lines, like 64 octets. With `None`
+    /// the encoded body is a single line, which SMTP servers may reject if it's longer
+    /// than 998 octets.
+    ///
+    /// If `String` is passed, line endings are converted to `CRLF` before encoding.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_length` is 0 or longer than 998.
+    pub fn new_base64_with_line_length<B: Into<MaybeString>>(
+        buf: B,
+        line_length: Option<usize>,
+    ) -> Self {
+        let mut buf: MaybeString = buf.into();
+        buf.encode_crlf();
+
+        let mut encoder = Base64Encoder::with_line_length(buf.len(), line_length);
+        encoder.update(&buf);
+        encoder.finish()
+    }
+
+    /// Wraps the lines of a `base64` encoded body at `line_length` instead
+    pub(super) fn rewrap_base64(self, line_length: Option<usize>) -> Self {
+        let mut encoder = Base64Encoder::with_line_length(self.buf.len() * 3 / 4, line_length);
+        let encoded = self
+            .buf
+            .into_iter()
+            .filter(|&b| b != b'\r' && b != b'\n')
+            .collect::<Vec<_>>();
+        encoder.write_encoded(&encoded);
+        encoder.finish()
+    }
+
+    /// Encodes a `7bit` or `8bit` body as `quoted-printable` if it has lines longer
+    /// than `max_line_length` octets
+    pub(super) fn rewrap_long_lines(self, max_line_length: usize) -> Self {
+        match self.encoding {
+            ContentTransferEncoding::SevenBit | ContentTransferEncoding::EightBit
+                if contains_too_long_lines(&self.buf, max_line_length) =>
+            {
+                Self::new_impl(self.buf, ContentTransferEncoding::QuotedPrintable)
+            }
+            _ => self,
+        }
+    }
+
     /// Builds a new `Body` using a pre-encoded buffer.
     ///
     /// **Generally not you want.**
@@ -91,36 +146,9 @@ impl Body {
                 Self::dangerous_pre_encoded(encoded, ContentTransferEncoding::QuotedPrintable)
             }
             ContentTransferEncoding::Base64 => {
-                let base64_len = buf.len() * 4 / 3 + 4;
-                let base64_endings_len = base64_len + base64_len / LINE_MAX_LENGTH;
-
-                let mut out = Vec::with_capacity(base64_endings_len);
-                {
-                    let writer = LineWrappingWriter::new(&mut out, LINE_MAX_LENGTH);
-                    let mut writer = base64::write::EncoderWriter::new(writer, base64::STANDARD);
-
-                    // TODO: use writer.write_all(self.as_ref()).expect("base64 encoding never fails");
-
-                    // modified Write::write_all to work around base64 crate bug
-                    // TODO: remove once https://github.com/marshallpierce/rust-base64/issues/148 is fixed
-                    {
-                        let mut buf: &[u8] = buf.as_ref();
-                        while !buf.is_empty() {
-                            match writer.write(buf) {
-                                Ok(0) => {
-                                    // ignore 0 writes
-                                }
-                                Ok(n) => {
-                                    buf = &buf[n..];
-                                }
-                                Err(ref e) if e.kind() == std::io::ErrorKind::Interrupted => {}
-                                Err(e) => panic!("base64 encoding never fails: {}", e),
-                            }
-                        }
-                    }
-                }
-
-                Self::dangerous_pre_encoded(out, ContentTransferEncoding::Base64)
+                let mut encoder = Base64Encoder::with_capacity(buf.len());
+                encoder.update(&buf);
+                encoder.finish()
             }
         }
     }
@@ -163,8 +191,19 @@ impl MaybeString {
     /// `8bit` and `binary` encodings are never returned, as they may not be
     /// supported by all SMTP servers.
     pub fn encoding(&self) -> ContentTransferEncoding {
+        self.encoding_with_max_line_length(MAX_LINE_LENGTH)
+    }
+
+    /// Suggests the best `Content-Transfer-Encoding` like [`MaybeString::encoding`],
+    /// with lines no longer than `max_line_length` octets for 7bit encoding
+    fn encoding_with_max_line_length(&self, max_line_length: usize) -> ContentTransferEncoding {
+        let max_line_length = std::cmp::max(max_line_length, LINE_MAX_LENGTH);
         match &self {
-            Self::String(s) if is_7bit_encoded(s.as_ref()) => ContentTransferEncoding::SevenBit,
+            Self::String(s)
+                if s.is_ascii() && !contains_too_long_lines(s.as_ref(), max_line_length) =>
+            {
+                ContentTransferEncoding::SevenBit
+            }
             // TODO: consider when base64 would be a better option because of output size
             Self::String(_) => ContentTransferEncoding::QuotedPrintable,
             Self::Binary(_) => ContentTransferEncoding::Base64,
@@ -273,75 +312,170 @@ impl Deref for MaybeString {
     }
 }
 
+/// Maximum length of a line, excluding the `CRLF` line ending, allowed by RFC 5321
+pub(crate) const MAX_LINE_LENGTH: usize = 998;
+
 /// Checks whether it contains only US-ASCII characters,
-/// and no lines are longer than 1000 characters including the `\n` character.
+/// and no lines are longer than 1000 characters including the line ending.
 ///
 /// Most efficient content encoding available
 fn is_7bit_encoded(buf: &[u8]) -> bool {
-    buf.is_ascii() && !contains_too_long_lines(buf)
+    buf.is_ascii() && !contains_too_long_lines(buf, MAX_LINE_LENGTH)
 }
 
 /// Checks that no lines are longer than 1000 characters,
-/// including the `\n` character.
+/// including the line ending.
 /// NOTE: 8bit isn't supported by all SMTP servers.
 fn is_8bit_encoded(buf: &[u8]) -> bool {
-    !contains_too_long_lines(buf)
+    !contains_too_long_lines(buf, MAX_LINE_LENGTH)
+}
+
+/// Checks if there are lines that are longer than `max_line_length`,
+/// excluding the line ending.
+fn contains_too_long_lines(buf: &[u8], max_line_length: usize) -> bool {
+    first_too_long_line(buf, max_line_length).is_some()
 }
 
-/// Checks if there are lines that are longer than 1000 characters,
-/// including the `\n` character.
-fn contains_too_long_lines(buf: &[u8]) -> bool {
-    buf.len() > 1000 && buf.split(|&b| b == b'\n').any(|line| line.len() > 999)
+/// Returns the number, starting at 1, of the first line longer than `max_line_length`,
+/// excluding the line ending
+pub(crate) fn first_too_long_line(buf: &[u8], max_line_length: usize) -> Option<usize> {
+    if buf.len() <= max_line_length {
+        return None;
+    }
+
+    buf.split(|&b| b == b'\n')
+        .position(|line| {
+            let line = match line.split_last() {
+                Some((b'\r', line)) => line,
+                _ => line,
+            };
+            line.len() > max_line_length
+        })
+        .map(|index| index + 1)
 }
 
 const LINE_SEPARATOR: &[u8] = b"\r\n";
-const LINE_MAX_LENGTH: usize = 78 - LINE_SEPARATOR.len();
+pub(crate) const LINE_MAX_LENGTH: usize = 78 - LINE_SEPARATOR.len();
 
-/// A `Write`r that inserts a line separator `\r\n` every `max_line_length` bytes.
-struct LineWrappingWriter<'a, W> {
-    writer: &'a mut W,
+/// Default length of the lines of `base64` encoded bodies, excluding the line ending,
+/// as defined in [RFC 2045](https://tools.ietf.org/html/rfc2045#section-6.8)
+pub const BASE64_LINE_LENGTH: usize = LINE_MAX_LENGTH;
+
+/// Checks that `line_length` can be used to wrap `base64` encoded bodies
+///
+/// # Panics
+///
+/// Panics if `line_length` is 0 or longer than 998.
+pub(crate) fn assert_base64_line_length(line_length: Option<usize>) {
+    if let Some(line_length) = line_length {
+        assert!(
+            line_length > 0 && line_length <= MAX_LINE_LENGTH,
+            "the base64 line length must be between 1 and {}",
+            MAX_LINE_LENGTH
+        );
+    }
+}
+
+/// Incrementally base64 encodes data, inserting a line separator `\r\n` every
+/// `line_length` bytes of output
+pub(crate) struct Base64Encoder {
+    out: Vec<u8>,
+    /// Input bytes not encoded yet, as base64 encodes groups of 3 bytes
+    pending: Vec<u8>,
+    line_length: Option<usize>,
     current_line_length: usize,
-    max_line_length: usize,
 }
 
-impl<'a, W> LineWrappingWriter<'a, W> {
-    pub fn new(writer: &'a mut W, max_line_length: usize) -> Self {
+impl Base64Encoder {
+    /// Creates an encoder, expecting about `len` input bytes
+    pub(crate) fn with_capacity(len: usize) -> Self {
+        Self::with_line_length(len, Some(BASE64_LINE_LENGTH))
+    }
+
+    /// Creates an encoder wrapping lines at `line_length`, or not wrapping them if `None`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `line_length` is 0 or longer than 998.
+    pub(crate) fn with_line_length(len: usize, line_length: Option<usize>) -> Self {
+        assert_base64_line_length(line_length);
+
+        let base64_len = len * 4 / 3 + 4;
+        let base64_endings_len = base64_len
+            + line_length.map_or(0, |line_length| {
+                base64_len / line_length * LINE_SEPARATOR.len()
+            });
+
         Self {
-            writer,
+            out: Vec::with_capacity(base64_endings_len),
+            pending: Vec::with_capacity(2),
+            line_length,
             current_line_length: 0,
-            max_line_length,
         }
     }
-}
 
-impl<'a, W> Write for LineWrappingWriter<'a, W>
-where
-    W: Write,
-{
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        let remaining_line_len = self.max_line_length - self.current_line_length;
-        let write_len = std::cmp::min(buf.len(), remaining_line_len);
+    /// Encodes the next bytes of the input
+    pub(crate) fn update(&mut self, mut buf: &[u8]) {
+        if !self.pending.is_empty() {
+            let missing = std::cmp::min(3 - self.pending.len(), buf.len());
+            self.pending.extend_from_slice(&buf[..missing]);
+            buf = &buf[missing..];
 
-        self.writer.write_all(&buf[..write_len])?;
+            if self.pending.len() < 3 {
+                return;
+            }
+            let group = mem::take(&mut self.pending);
+            self.write(&group);
+        }
 
-        if remaining_line_len == write_len {
-            self.writer.write_all(LINE_SEPARATOR)?;
+        let complete = buf.len() - buf.len() % 3;
+        self.write(&buf[..complete]);
+        self.pending.extend_from_slice(&buf[complete..]);
+    }
 
-            self.current_line_length = 0;
-        } else {
-            self.current_line_length += write_len;
-        }
+    /// Encodes the end of the input, and returns the encoded body
+    pub(crate) fn finish(mut self) -> Body {
+        let pending = mem::take(&mut self.pending);
+        self.write(&pending);
+
+        Body::dangerous_pre_encoded(self.out, ContentTransferEncoding::Base64)
+    }
 
-        Ok(write_len)
+    fn write(&mut self, buf: &[u8]) {
+        let encoded = base64::encode(buf);
+        self.write_encoded(encoded.as_bytes());
     }
 
-    fn flush(&mut self) -> io::Result<()> {
-        self.writer.flush()
+    /// Writes already encoded bytes, wrapping the lines
+    fn write_encoded(&mut self, mut encoded: &[u8]) {
+        let line_length = match self.line_length {
+            Some(line_length) => line_length,
+            None => {
+                self.out.extend_from_slice(encoded);
+                return;
+            }
+        };
+
+        while !encoded.is_empty() {
+            let remaining_line_len = line_length - self.current_line_length;
+            let write_len = std::cmp::min(encoded.len(), remaining_line_len);
+
+            let (line, rest) = encoded.split_at(write_len);
+            self.out.extend_from_slice(line);
+            encoded = rest;
+
+            if remaining_line_len == write_len {
+                self.out.extend_from_slice(LINE_SEPARATOR);
+                self.current_line_length = 0;
+            } else {
+                self.current_line_length += write_len;
+            }
+        }
     }
 }
 
 /// In place conversion to CRLF line endings
-fn in_place_crlf_line_endings(string: &mut String) {
+pub(super) fn in_place_crlf_line_endings(string: &mut String) {
     let indices = find_all_lf_char_indices(string);
 
     for i in indices {
@@ -377,7 +511,10 @@ fn find_all_lf_char_indices(s: &str) -> Vec<usize> {
 
 #[cfg(test)]
 mod test {
-    use super::{in_place_crlf_line_endings, Body, ContentTransferEncoding};
+    use super::{
+        first_too_long_line, in_place_crlf_line_endings, Base64Encoder, Body,
+        ContentTransferEncoding, BASE64_LINE_LENGTH,
+    };
 
     #[test]
     fn seven_bit_detect() {
@@ -475,6 +612,37 @@ mod test {
         );
     }
 
+    #[test]
+    fn seven_bit_max_line_length() {
+        let body = format!("{}\nHello, world!", "a".repeat(100));
+
+        let encoded = Body::new_with_max_line_length(body.clone(), 100);
+        assert_eq!(encoded.encoding(), ContentTransferEncoding::SevenBit);
+
+        let encoded = Body::new_with_max_line_length(body, 80);
+        assert_eq!(encoded.encoding(), ContentTransferEncoding::QuotedPrintable);
+        assert_eq!(first_too_long_line(encoded.as_ref(), 76), None);
+    }
+
+    #[test]
+    fn first_too_long_line_long_body() {
+        let body = "a".repeat(2000);
+        assert_eq!(first_too_long_line(body.as_bytes(), 998), Some(1));
+
+        let encoded = Body::new(body);
+        assert_eq!(encoded.encoding(), ContentTransferEncoding::QuotedPrintable);
+        assert_eq!(first_too_long_line(encoded.as_ref(), 998), None);
+        assert_eq!(first_too_long_line(encoded.as_ref(), 76), None);
+    }
+
+    #[test]
+    fn first_too_long_line_number() {
+        let body = format!("short\r\n{}\r\n{}\r\n", "a".repeat(10), "b".repeat(11));
+
+        assert_eq!(first_too_long_line(body.as_bytes(), 10), Some(3));
+        assert_eq!(first_too_long_line(body.as_bytes(), 11), None);
+    }
+
     #[test]
     fn seven_bit_invalid() {
         let result = Body::new_with_encoding(
@@ -582,7 +750,7 @@ mod test {
     #[test]
     fn base64_encode_bytes_wrapping() {
         let encoded = Body::new_with_encoding(
-            vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9].repeat(20),
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9].repeat(20),
             ContentTransferEncoding::Base64,
         )
         .unwrap();
@@ -600,6 +768,65 @@ mod test {
         );
     }
 
+    #[test]
+    fn base64_encode_chunked() {
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let expected =
+            Body::new_with_encoding(data.clone(), ContentTransferEncoding::Base64).unwrap();
+
+        for chunk_size in &[1, 2, 3, 4, 57, 100, 1000] {
+            let mut encoder = Base64Encoder::with_capacity(0);
+            for chunk in data.chunks(*chunk_size) {
+                encoder.update(chunk);
+            }
+            assert_eq!(encoder.finish().as_ref(), expected.as_ref());
+        }
+    }
+
+    #[test]
+    fn base64_encode_line_length() {
+        let encoded =
+            Body::new_base64_with_line_length([0, 1, 2, 3, 4, 5, 6, 7, 8, 9].repeat(10), Some(64));
+        assert_eq!(encoded.encoding(), ContentTransferEncoding::Base64);
+        assert_eq!(
+            encoded.as_ref(),
+            concat!(
+                "AAECAwQFBgcICQABAgMEBQYHCAkAAQIDBAUGBwgJAAECAwQFBgcICQABAgMEBQYH\r\n",
+                "CAkAAQIDBAUGBwgJAAECAwQFBgcICQABAgMEBQYHCAkAAQIDBAUGBwgJAAECAwQF\r\n",
+                "BgcICQ=="
+            )
+            .as_bytes()
+        );
+
+        let encoded =
+            Body::new_base64_with_line_length([0, 1, 2, 3, 4, 5, 6, 7, 8, 9].repeat(20), None);
+        assert_eq!(encoded.as_ref().len(), 268);
+        assert!(!encoded.as_ref().contains(&b'\n'));
+
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        let expected = Body::new_base64_with_line_length(data.clone(), Some(10));
+        for chunk_size in &[1, 2, 3, 4, 57, 100, 1000] {
+            let mut encoder = Base64Encoder::with_line_length(0, Some(10));
+            for chunk in data.chunks(*chunk_size) {
+                encoder.update(chunk);
+            }
+            assert_eq!(encoder.finish().as_ref(), expected.as_ref());
+        }
+        assert!(first_too_long_line(expected.as_ref(), 10).is_none());
+        assert_eq!(
+            expected.rewrap_base64(Some(BASE64_LINE_LENGTH)).as_ref(),
+            Body::new_with_encoding(data, ContentTransferEncoding::Base64)
+                .unwrap()
+                .as_ref()
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "the base64 line length must be between 1 and 998")]
+    fn base64_encode_line_length_zero() {
+        Body::new_base64_with_line_length(vec![0, 1, 2], Some(0));
+    }
+
     #[test]
     fn base64_encode_ascii() {
         let encoded = Body::new_with_encoding(
@@ -0,0 +1,124 @@
+//! Encoding of text in other charsets than UTF-8
+
+use std::{borrow::Cow, convert::TryFrom};
+
+use encoding_rs::Encoding;
+
+use crate::{message::body::in_place_crlf_line_endings, Error as EmailError};
+
+/// Labels of US-ASCII, which the WHATWG encoding standard maps to windows-1252
+const ASCII_LABELS: &[&str] = &[
+    "us-ascii",
+    "ascii",
+    "us",
+    "ansi_x3.4-1968",
+    "iso646-us",
+    "csascii",
+];
+
+/// Labels of ISO-8859-1, which the WHATWG encoding standard maps to windows-1252
+const LATIN1_LABELS: &[&str] = &[
+    "iso-8859-1",
+    "iso8859-1",
+    "iso88591",
+    "iso_8859-1",
+    "iso_8859-1:1987",
+    "latin1",
+    "l1",
+    "cp819",
+    "ibm819",
+    "iso-ir-100",
+    "csisolatin1",
+];
+
+/// Encodes `text` with CRLF line endings in `charset`
+///
+/// Returns the encoded text, and the name of the charset for the `Content-Type` header.
+pub(super) fn encode(text: &str, charset: &str) -> Result<(Vec<u8>, &'static str), EmailError> {
+    let mut text = text.to_owned();
+    in_place_crlf_line_endings(&mut text);
+
+    let label = charset.trim().to_ascii_lowercase();
+    if ASCII_LABELS.contains(&label.as_str()) {
+        return if text.is_ascii() {
+            Ok((text.into_bytes(), "us-ascii"))
+        } else {
+            Err(EmailError::UnrepresentableChars(charset.to_owned()))
+        };
+    }
+    if LATIN1_LABELS.contains(&label.as_str()) {
+        return text
+            .chars()
+            .map(|c| u8::try_from(u32::from(c)).ok())
+            .collect::<Option<Vec<u8>>>()
+            .map(|encoded| (encoded, "iso-8859-1"))
+            .ok_or_else(|| EmailError::UnrepresentableChars(charset.to_owned()));
+    }
+
+    let encoding = Encoding::for_label(label.as_bytes())
+        // UTF-16 and the replacement encoding can only decode
+        .filter(|encoding| encoding.output_encoding() == *encoding)
+        .ok_or_else(|| EmailError::UnknownCharset(charset.to_owned()))?;
+    if encoding == encoding_rs::UTF_8 {
+        return Ok((text.into_bytes(), "utf-8"));
+    }
+
+    let (encoded, _, unmappable) = encoding.encode(&text);
+    if unmappable {
+        return Err(EmailError::UnrepresentableChars(charset.to_owned()));
+    }
+    let encoded = match encoded {
+        Cow::Borrowed(encoded) => encoded.to_vec(),
+        Cow::Owned(encoded) => encoded,
+    };
+    Ok((encoded, encoding.name()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn encode_latin1() {
+        assert_eq!(
+            encode("Café\n", "ISO-8859-1").unwrap(),
+            (b"Caf\xe9\r\n".to_vec(), "iso-8859-1")
+        );
+        assert!(matches!(
+            encode("5 €", "latin1"),
+            Err(EmailError::UnrepresentableChars(_))
+        ));
+        assert!(matches!(
+            encode("Café", "us-ascii"),
+            Err(EmailError::UnrepresentableChars(_))
+        ));
+    }
+
+    #[test]
+    fn encode_shift_jis() {
+        assert_eq!(
+            encode("日本", "shift_jis").unwrap(),
+            (b"\x93\xfa\x96\x7b".to_vec(), "Shift_JIS")
+        );
+        assert!(matches!(
+            encode("한국", "shift_jis"),
+            Err(EmailError::UnrepresentableChars(_))
+        ));
+    }
+
+    #[test]
+    fn encode_unknown() {
+        assert!(matches!(
+            encode("text", "utf-16"),
+            Err(EmailError::UnknownCharset(_))
+        ));
+        assert!(matches!(
+            encode("text", "klingon"),
+            Err(EmailError::UnknownCharset(_))
+        ));
+        assert_eq!(
+            encode("text", "UTF-8").unwrap(),
+            (b"text".to_vec(), "utf-8")
+        );
+    }
+}
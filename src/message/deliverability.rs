@@ -0,0 +1,317 @@
+use std::fmt::{self, Display};
+
+use super::{header, Mailboxes};
+use crate::Message;
+
+/// A header which is missing or misconfigured for bulk sending
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DeliverabilityIssue {
+    /// There is no `From` header
+    MissingFrom,
+    /// The `From` header has several mailboxes
+    MultipleFrom,
+    /// There is no `Message-ID` header
+    MissingMessageId,
+    /// The `Message-ID` isn't in the `<left@right>` form
+    InvalidMessageId(String),
+    /// The domain of the `Message-ID` isn't fully qualified, like `localhost`
+    LocalMessageIdDomain(String),
+    /// None of the DKIM signatures is for the domain of the `From` address, or one
+    /// of its parent domains or subdomains
+    DkimNotAligned {
+        /// The domain of the `From` address
+        from: String,
+        /// The domain of the first DKIM signature
+        dkim: String,
+    },
+    /// There is no `List-Unsubscribe` header
+    MissingListUnsubscribe,
+    /// The `List-Unsubscribe` header can't be used for one-click unsubscription, as
+    /// defined in [RFC 8058](https://tools.ietf.org/html/rfc8058): it has no HTTPS URI
+    /// or there is no `List-Unsubscribe-Post: List-Unsubscribe=One-Click` header
+    MissingOneClickUnsubscribe,
+}
+
+impl Display for DeliverabilityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DeliverabilityIssue::MissingFrom => f.write_str("missing From header"),
+            DeliverabilityIssue::MultipleFrom => f.write_str("several mailboxes in From header"),
+            DeliverabilityIssue::MissingMessageId => f.write_str("missing Message-ID header"),
+            DeliverabilityIssue::InvalidMessageId(id) => write!(f, "invalid Message-ID {}", id),
+            DeliverabilityIssue::LocalMessageIdDomain(domain) => {
+                write!(f, "Message-ID domain {} isn't fully qualified", domain)
+            }
+            DeliverabilityIssue::DkimNotAligned { from, dkim } => write!(
+                f,
+                "DKIM signature domain {} isn't aligned with From domain {}",
+                dkim, from
+            ),
+            DeliverabilityIssue::MissingListUnsubscribe => {
+                f.write_str("missing List-Unsubscribe header")
+            }
+            DeliverabilityIssue::MissingOneClickUnsubscribe => {
+                f.write_str("List-Unsubscribe doesn't allow one-click unsubscription")
+            }
+        }
+    }
+}
+
+/// The deliverability issues found in a message by [`deliverability_check`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeliverabilityReport {
+    issues: Vec<DeliverabilityIssue>,
+}
+
+impl DeliverabilityReport {
+    /// Whether no issue was found
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    /// The issues found, in the order of the checks
+    pub fn issues(&self) -> &[DeliverabilityIssue] {
+        &self.issues
+    }
+}
+
+impl Display for DeliverabilityReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, issue) in self.issues.iter().enumerate() {
+            if i > 0 {
+                f.write_str("; ")?;
+            }
+            Display::fmt(issue, f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks the headers bulk senders are required to set by the large mailbox providers
+///
+/// The check is advisory: it doesn't change or reject the message, and the report can
+/// be logged before sending. It looks for a single `From` mailbox, a `Message-ID` with
+/// a fully qualified domain, and a `List-Unsubscribe` header allowing one-click
+/// unsubscription.
+///
+/// DKIM signatures are usually added by the relay, so only the alignment of the
+/// `DKIM-Signature` headers already in the message with the `From` domain is checked.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use lettre::message::{deliverability_check, DeliverabilityIssue, Message};
+///
+/// let message = Message::builder()
+///     .from("NoBody <nobody@domain.tld>".parse()?)
+///     .to("Hei <hei@domain.tld>".parse()?)
+///     .message_id(Some("<newsletter-42@domain.tld>".to_owned()))
+///     .subject("Happy new year")
+///     .body(String::from("Be happy!"))?;
+///
+/// let report = deliverability_check(&message);
+/// assert_eq!(
+///     report.issues(),
+///     &[DeliverabilityIssue::MissingListUnsubscribe]
+/// );
+/// # Ok(())
+/// # }
+/// ```
+pub fn deliverability_check(message: &Message) -> DeliverabilityReport {
+    let headers = message.headers();
+    let mut issues = Vec::new();
+
+    let from_domain = match headers.get::<header::From>() {
+        Some(from) => {
+            let mailboxes = Mailboxes::from(from);
+            let mut mailboxes = mailboxes.iter();
+            let domain = mailboxes
+                .next()
+                .map(|mailbox| mailbox.email.domain().to_ascii_lowercase());
+            if mailboxes.next().is_some() {
+                issues.push(DeliverabilityIssue::MultipleFrom);
+            }
+            domain
+        }
+        None => {
+            issues.push(DeliverabilityIssue::MissingFrom);
+            None
+        }
+    };
+
+    match headers.get_raw("Message-ID").map(str::trim) {
+        Some(id) => match id
+            .strip_prefix('<')
+            .and_then(|id| id.strip_suffix('>'))
+            .and_then(|id| id.find('@').map(|at| (&id[..at], &id[at + 1..])))
+        {
+            Some((left, right)) if !left.is_empty() && !right.is_empty() => {
+                if !right.contains('.') || right.eq_ignore_ascii_case("localhost.localdomain") {
+                    issues.push(DeliverabilityIssue::LocalMessageIdDomain(right.to_owned()));
+                }
+            }
+            _ => issues.push(DeliverabilityIssue::InvalidMessageId(id.to_owned())),
+        },
+        None => issues.push(DeliverabilityIssue::MissingMessageId),
+    }
+
+    let dkim_domains = headers
+        .get_all_raw("DKIM-Signature")
+        .filter_map(dkim_domain)
+        .collect::<Vec<_>>();
+    if let (Some(from), Some(dkim)) = (&from_domain, dkim_domains.first()) {
+        if !dkim_domains.iter().any(|dkim| is_aligned(from, dkim)) {
+            issues.push(DeliverabilityIssue::DkimNotAligned {
+                from: from.clone(),
+                dkim: dkim.clone(),
+            });
+        }
+    }
+
+    match headers.get_raw("List-Unsubscribe") {
+        Some(list_unsubscribe) => {
+            let https = list_unsubscribe
+                .split(',')
+                .filter_map(|uri| {
+                    uri.trim()
+                        .strip_prefix('<')
+                        .and_then(|uri| uri.strip_suffix('>'))
+                })
+                .any(|uri| {
+                    uri.get(..8)
+                        .map_or(false, |scheme| scheme.eq_ignore_ascii_case("https://"))
+                });
+            let one_click = headers
+                .get_raw("List-Unsubscribe-Post")
+                .map_or(false, |post| post.trim() == "List-Unsubscribe=One-Click");
+            if !https || !one_click {
+                issues.push(DeliverabilityIssue::MissingOneClickUnsubscribe);
+            }
+        }
+        None => issues.push(DeliverabilityIssue::MissingListUnsubscribe),
+    }
+
+    DeliverabilityReport { issues }
+}
+
+/// The signing domain, the `d=` tag, of a `DKIM-Signature` header
+fn dkim_domain(signature: &str) -> Option<String> {
+    signature.split(';').find_map(|tag| {
+        let equal = tag.find('=')?;
+        if tag[..equal].trim() == "d" {
+            Some(
+                tag[equal + 1..]
+                    .chars()
+                    .filter(|c| !c.is_whitespace())
+                    .collect::<String>()
+                    .to_ascii_lowercase(),
+            )
+        } else {
+            None
+        }
+    })
+}
+
+/// Relaxed alignment, approximated without the public suffix list: the domains are
+/// the same, or one is a subdomain of the other
+fn is_aligned(from: &str, dkim: &str) -> bool {
+    let is_subdomain = |sub: &str, parent: &str| {
+        sub.strip_suffix(parent)
+            .map_or(false, |prefix| prefix.ends_with('.'))
+    };
+    from == dkim || is_subdomain(from, dkim) || is_subdomain(dkim, from)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{deliverability_check, DeliverabilityIssue};
+    use crate::message::{
+        header::{self, HeaderName},
+        Mailboxes, Message,
+    };
+
+    fn bulk_message(from: &str, message_id: &str, headers: &[(&'static str, &str)]) -> Message {
+        let mut message = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .message_id(Some(message_id.to_owned()))
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+        message
+            .headers_mut()
+            .set(header::From::from(from.parse::<Mailboxes>().unwrap()));
+        for (name, value) in headers {
+            message
+                .headers_mut()
+                .append_raw(HeaderName::new_from_ascii_str(name), (*value).to_owned());
+        }
+        message
+    }
+
+    #[test]
+    fn deliverability_ok() {
+        let message = bulk_message(
+            "NoBody <nobody@news.domain.tld>",
+            "<42@mail.domain.tld>",
+            &[
+                (
+                    "DKIM-Signature",
+                    "v=1; a=rsa-sha256; d=domain.tld; s=default; h=from; bh=AAAA; b=BBBB",
+                ),
+                (
+                    "List-Unsubscribe",
+                    "<mailto:unsubscribe@domain.tld>, <https://domain.tld/unsubscribe/42>",
+                ),
+                ("List-Unsubscribe-Post", "List-Unsubscribe=One-Click"),
+            ],
+        );
+        let report = deliverability_check(&message);
+        assert!(report.is_ok(), "{}", report);
+    }
+
+    #[test]
+    fn deliverability_issues() {
+        let message = bulk_message(
+            "NoBody <nobody@domain.tld>, Hei <hei@domain.tld>",
+            "<42@localhost>",
+            &[
+                (
+                    "DKIM-Signature",
+                    "v=1; a=rsa-sha256; d=mailer.tld; s=default; h=from; bh=AAAA; b=BBBB",
+                ),
+                ("List-Unsubscribe", "<mailto:unsubscribe@domain.tld>"),
+            ],
+        );
+        let report = deliverability_check(&message);
+        assert_eq!(
+            report.issues(),
+            &[
+                DeliverabilityIssue::MultipleFrom,
+                DeliverabilityIssue::LocalMessageIdDomain("localhost".to_owned()),
+                DeliverabilityIssue::DkimNotAligned {
+                    from: "domain.tld".to_owned(),
+                    dkim: "mailer.tld".to_owned(),
+                },
+                DeliverabilityIssue::MissingOneClickUnsubscribe,
+            ]
+        );
+        assert_eq!(
+            report.to_string(),
+            "several mailboxes in From header; \
+             Message-ID domain localhost isn't fully qualified; \
+             DKIM signature domain mailer.tld isn't aligned with From domain domain.tld; \
+             List-Unsubscribe doesn't allow one-click unsubscription"
+        );
+
+        let message = bulk_message("NoBody <nobody@domain.tld>", "42", &[]);
+        assert_eq!(
+            deliverability_check(&message).issues(),
+            &[
+                DeliverabilityIssue::InvalidMessageId("42".to_owned()),
+                DeliverabilityIssue::MissingListUnsubscribe,
+            ]
+        );
+    }
+}
@@ -0,0 +1,503 @@
+use std::fmt::{self, Display};
+
+use super::header::ContentType;
+
+/// A delivery status notification (bounce) parsed from a received message
+///
+/// Bounces are `multipart/report` messages with a `delivery-status` report type, as
+/// defined in [RFC 3464](https://tools.ietf.org/html/rfc3464). They tell, for each
+/// recipient of the original message, whether it failed, was delayed or delivered, with
+/// the status code and diagnostic given by the server.
+///
+/// The parsing is lenient, to accept the reports of the common mail servers: the field
+/// groups of the `message/delivery-status` part don't need to be separated by blank
+/// lines, and the fields which can't be understood are ignored.
+///
+/// ```rust
+/// use lettre::message::{DeliveryAction, DeliveryStatus};
+///
+/// let bounce = concat!(
+///     "Content-Type: multipart/report; report-type=delivery-status; boundary=\"b\"\r\n",
+///     "\r\n",
+///     "--b\r\n",
+///     "Content-Type: message/delivery-status\r\n",
+///     "\r\n",
+///     "Reporting-MTA: dns; mail.domain.tld\r\n",
+///     "\r\n",
+///     "Final-Recipient: rfc822; hei@domain.tld\r\n",
+///     "Action: failed\r\n",
+///     "Status: 5.1.1\r\n",
+///     "\r\n",
+///     "--b--\r\n",
+/// );
+///
+/// let status = DeliveryStatus::parse(bounce).unwrap();
+/// assert_eq!(status.reporting_mta(), Some("mail.domain.tld"));
+/// let recipient = &status.recipients()[0];
+/// assert_eq!(recipient.final_recipient(), "hei@domain.tld");
+/// assert_eq!(recipient.action(), Some(DeliveryAction::Failed));
+/// assert!(recipient.is_permanent_failure());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DeliveryStatus {
+    reporting_mta: Option<String>,
+    original_envelope_id: Option<String>,
+    arrival_date: Option<String>,
+    original_message_id: Option<String>,
+    recipients: Vec<RecipientStatus>,
+}
+
+impl DeliveryStatus {
+    /// Parses the raw bounce message `message`, with its headers
+    ///
+    /// Returns `None` if it isn't a `multipart/report` with the `delivery-status` report
+    /// type, or if it has no `message/delivery-status` part.
+    pub fn parse(message: &str) -> Option<DeliveryStatus> {
+        let (headers, body) = split_headers(message);
+        let content_type = find_content_type(headers)?;
+        let mime = content_type.as_ref();
+        let report_type = mime.get_param("report-type")?;
+        if mime.type_() != "multipart"
+            || mime.subtype() != "report"
+            || !report_type.as_str().eq_ignore_ascii_case("delivery-status")
+        {
+            return None;
+        }
+        let boundary = mime.get_param("boundary")?;
+
+        let mut status = None;
+        let mut original_message_id = None;
+        for part in multipart_parts(body, boundary.as_str()) {
+            let (headers, body) = split_headers(part);
+            let content_type = match find_content_type(headers) {
+                Some(content_type) => content_type,
+                None => continue,
+            };
+            let mime = content_type.as_ref();
+            match (mime.type_().as_str(), mime.subtype().as_str()) {
+                ("message", "delivery-status") | ("message", "global-delivery-status") => {
+                    status = Some(Self::parse_fields(body));
+                }
+                ("message", "rfc822")
+                | ("message", "global")
+                | ("text", "rfc822-headers")
+                | ("message", "global-headers") => {
+                    let (headers, _) = split_headers(body);
+                    original_message_id = fields(headers)
+                        .into_iter()
+                        .find(|(name, _)| name.eq_ignore_ascii_case("Message-ID"))
+                        .map(|(_, value)| value);
+                }
+                _ => {}
+            }
+        }
+
+        status.map(|status| DeliveryStatus {
+            original_message_id,
+            ..status
+        })
+    }
+
+    /// Parses the fields of a `message/delivery-status` part
+    ///
+    /// The fields of a recipient end at a blank line, or at a field they already have.
+    fn parse_fields(text: &str) -> DeliveryStatus {
+        let mut status = DeliveryStatus::default();
+        let mut recipient: Option<RecipientFields> = None;
+
+        for group in text.replace("\r\n", "\n").split("\n\n") {
+            status.push(recipient.take());
+
+            for (name, value) in fields(group) {
+                let name = name.to_ascii_lowercase();
+                match name.as_str() {
+                    "reporting-mta" => status.reporting_mta = Some(without_type(&value)),
+                    "original-envelope-id" => status.original_envelope_id = Some(value),
+                    "arrival-date" => status.arrival_date = Some(value),
+                    "original-recipient" | "final-recipient" | "action" | "status"
+                    | "diagnostic-code" | "remote-mta" => {
+                        if recipient.as_ref().map_or(false, |r| r.has(&name)) {
+                            status.push(recipient.take());
+                        }
+                        recipient
+                            .get_or_insert_with(RecipientFields::default)
+                            .set(&name, &value);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        status.push(recipient);
+
+        status
+    }
+
+    fn push(&mut self, recipient: Option<RecipientFields>) {
+        if let Some(recipient) = recipient.and_then(RecipientFields::build) {
+            self.recipients.push(recipient);
+        }
+    }
+
+    /// Host which generated the report, from `Reporting-MTA`
+    pub fn reporting_mta(&self) -> Option<&str> {
+        self.reporting_mta.as_deref()
+    }
+
+    /// Envelope identifier given with `MAIL FROM` when sending the original message,
+    /// from `Original-Envelope-Id`
+    pub fn original_envelope_id(&self) -> Option<&str> {
+        self.original_envelope_id.as_deref()
+    }
+
+    /// Date at which the original message was received, from `Arrival-Date`
+    pub fn arrival_date(&self) -> Option<&str> {
+        self.arrival_date.as_deref()
+    }
+
+    /// `Message-ID` of the original message, when its headers are returned
+    pub fn original_message_id(&self) -> Option<&str> {
+        self.original_message_id.as_deref()
+    }
+
+    /// Status of each recipient of the report
+    pub fn recipients(&self) -> &[RecipientStatus] {
+        &self.recipients
+    }
+}
+
+/// Delivery status of a recipient of a [`DeliveryStatus`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipientStatus {
+    final_recipient: String,
+    original_recipient: Option<String>,
+    action: Option<DeliveryAction>,
+    status: Option<String>,
+    diagnostic_code: Option<String>,
+    remote_mta: Option<String>,
+}
+
+impl RecipientStatus {
+    /// Address the server tried to deliver to, from `Final-Recipient`
+    ///
+    /// Falls back to `Original-Recipient` when the report has no `Final-Recipient`.
+    pub fn final_recipient(&self) -> &str {
+        &self.final_recipient
+    }
+
+    /// Address given with `RCPT TO` when sending the original message, from
+    /// `Original-Recipient`
+    pub fn original_recipient(&self) -> Option<&str> {
+        self.original_recipient.as_deref()
+    }
+
+    /// What happened to the message, from `Action`
+    pub fn action(&self) -> Option<DeliveryAction> {
+        self.action
+    }
+
+    /// Enhanced status code, like `5.1.1`, from `Status`
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    /// Reply of the remote server, like `550 5.1.1 User unknown`, from `Diagnostic-Code`
+    pub fn diagnostic_code(&self) -> Option<&str> {
+        self.diagnostic_code.as_deref()
+    }
+
+    /// Host which gave the diagnostic, from `Remote-MTA`
+    pub fn remote_mta(&self) -> Option<&str> {
+        self.remote_mta.as_deref()
+    }
+
+    /// Tells whether delivering to the recipient failed permanently, meaning that
+    /// sending to it again will fail too
+    pub fn is_permanent_failure(&self) -> bool {
+        self.action == Some(DeliveryAction::Failed)
+            && self
+                .status
+                .as_ref()
+                .map_or(true, |status| !status.starts_with('4'))
+    }
+}
+
+/// Fields of a recipient being parsed
+#[derive(Default)]
+struct RecipientFields {
+    final_recipient: Option<String>,
+    original_recipient: Option<String>,
+    action: Option<DeliveryAction>,
+    status: Option<String>,
+    diagnostic_code: Option<String>,
+    remote_mta: Option<String>,
+}
+
+impl RecipientFields {
+    fn has(&self, name: &str) -> bool {
+        match name {
+            "original-recipient" => self.original_recipient.is_some(),
+            "final-recipient" => self.final_recipient.is_some(),
+            "action" => self.action.is_some(),
+            "status" => self.status.is_some(),
+            "diagnostic-code" => self.diagnostic_code.is_some(),
+            "remote-mta" => self.remote_mta.is_some(),
+            _ => false,
+        }
+    }
+
+    fn set(&mut self, name: &str, value: &str) {
+        match name {
+            "original-recipient" => self.original_recipient = Some(without_type(value)),
+            "final-recipient" => self.final_recipient = Some(without_type(value)),
+            "action" => self.action = DeliveryAction::parse(value),
+            "status" => self.status = first_word(value),
+            "diagnostic-code" => self.diagnostic_code = Some(without_type(value)),
+            "remote-mta" => self.remote_mta = Some(without_type(value)),
+            _ => {}
+        }
+    }
+
+    fn build(self) -> Option<RecipientStatus> {
+        let final_recipient = match self.final_recipient {
+            Some(final_recipient) => final_recipient,
+            None => self.original_recipient.clone()?,
+        };
+        Some(RecipientStatus {
+            final_recipient,
+            original_recipient: self.original_recipient,
+            action: self.action,
+            status: self.status,
+            diagnostic_code: self.diagnostic_code,
+            remote_mta: self.remote_mta,
+        })
+    }
+}
+
+/// What happened to the message for a recipient
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryAction {
+    /// The message couldn't be delivered
+    Failed,
+    /// The delivery was delayed, the server will try again
+    Delayed,
+    /// The message was delivered
+    Delivered,
+    /// The message was relayed to a server which doesn't send notifications
+    Relayed,
+    /// The message was delivered to the recipient, and forwarded to other addresses
+    Expanded,
+}
+
+impl DeliveryAction {
+    fn parse(value: &str) -> Option<DeliveryAction> {
+        match first_word(value)?.to_ascii_lowercase().as_str() {
+            "failed" => Some(DeliveryAction::Failed),
+            "delayed" => Some(DeliveryAction::Delayed),
+            "delivered" => Some(DeliveryAction::Delivered),
+            "relayed" => Some(DeliveryAction::Relayed),
+            "expanded" => Some(DeliveryAction::Expanded),
+            _ => None,
+        }
+    }
+}
+
+impl Display for DeliveryAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DeliveryAction::Failed => "failed",
+            DeliveryAction::Delayed => "delayed",
+            DeliveryAction::Delivered => "delivered",
+            DeliveryAction::Relayed => "relayed",
+            DeliveryAction::Expanded => "expanded",
+        })
+    }
+}
+
+/// Splits a message or a part into its headers and its body
+fn split_headers(message: &str) -> (&str, &str) {
+    let mut offset = 0;
+    for line in message.split('\n') {
+        let next = (offset + line.len() + 1).min(message.len());
+        if line.trim_end_matches('\r').is_empty() {
+            return (&message[..offset], &message[next..]);
+        }
+        offset = next;
+    }
+    (message, "")
+}
+
+/// Unfolded fields of a header block, ignoring the blank lines
+fn fields(text: &str) -> Vec<(String, String)> {
+    let mut fields: Vec<(String, String)> = Vec::new();
+    for line in text.lines() {
+        let line = line.trim_end_matches('\r');
+        if line.starts_with(' ') || line.starts_with('\t') {
+            if let Some((_, value)) = fields.last_mut() {
+                if !value.is_empty() {
+                    value.push(' ');
+                }
+                value.push_str(line.trim());
+            }
+        } else if let Some(colon) = line.find(':') {
+            fields.push((
+                line[..colon].trim().to_owned(),
+                line[colon + 1..].trim().to_owned(),
+            ));
+        }
+    }
+    fields
+}
+
+fn find_content_type(headers: &str) -> Option<ContentType> {
+    fields(headers)
+        .into_iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("Content-Type"))
+        .and_then(|(_, value)| ContentType::parse(&value).ok())
+}
+
+/// Parts of a multipart body
+fn multipart_parts<'a>(body: &'a str, boundary: &str) -> Vec<&'a str> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut start = None;
+    let mut offset = 0;
+    for line in body.split('\n') {
+        let next = (offset + line.len() + 1).min(body.len());
+        let trimmed = line.trim_end();
+        if let Some(rest) = trimmed.strip_prefix(delimiter.as_str()) {
+            if rest.is_empty() || rest == "--" {
+                if let Some(start) = start {
+                    parts.push(&body[start..offset]);
+                }
+                if rest == "--" {
+                    return parts;
+                }
+                start = Some(next);
+            }
+        }
+        offset = next;
+    }
+    // Tolerates a missing closing delimiter
+    if let Some(start) = start {
+        parts.push(&body[start..]);
+    }
+    parts
+}
+
+/// Removes the type of a field value, like `rfc822;` or `dns;`
+fn without_type(value: &str) -> String {
+    match value.find(';') {
+        Some(semicolon) if !value[..semicolon].trim().contains(char::is_whitespace) => {
+            value[semicolon + 1..].trim().to_owned()
+        }
+        _ => value.trim().to_owned(),
+    }
+}
+
+fn first_word(value: &str) -> Option<String> {
+    value.split_whitespace().next().map(str::to_owned)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn fixture(name: &str) -> DeliveryStatus {
+        let bounce = std::fs::read_to_string(format!("./testdata/{}.eml", name)).unwrap();
+        DeliveryStatus::parse(&bounce).unwrap()
+    }
+
+    #[test]
+    fn delivery_status_postfix() {
+        let status = fixture("bounce_postfix");
+        assert_eq!(status.reporting_mta(), Some("mail.domain.tld"));
+        assert_eq!(
+            status.arrival_date(),
+            Some("Fri, 14 Jan 2022 10:21:03 +0100 (CET)")
+        );
+        assert_eq!(
+            status.original_message_id(),
+            Some("<20220114092102.1234@domain.tld>")
+        );
+
+        let recipients = status.recipients();
+        assert_eq!(recipients.len(), 2);
+        assert_eq!(recipients[0].final_recipient(), "nobody@other.tld");
+        assert_eq!(recipients[0].original_recipient(), Some("nobody@other.tld"));
+        assert_eq!(recipients[0].action(), Some(DeliveryAction::Failed));
+        assert_eq!(recipients[0].status(), Some("5.1.1"));
+        assert_eq!(recipients[0].remote_mta(), Some("mx.other.tld"));
+        assert_eq!(
+            recipients[0].diagnostic_code(),
+            Some("550 5.1.1 <nobody@other.tld>: Recipient address rejected: User unknown in virtual mailbox table")
+        );
+        assert!(recipients[0].is_permanent_failure());
+
+        assert_eq!(recipients[1].final_recipient(), "full@other.tld");
+        assert_eq!(recipients[1].action(), Some(DeliveryAction::Delayed));
+        assert_eq!(recipients[1].status(), Some("4.2.2"));
+        assert!(!recipients[1].is_permanent_failure());
+    }
+
+    #[test]
+    fn delivery_status_exim() {
+        // No blank line between the field groups
+        let status = fixture("bounce_exim");
+        assert_eq!(status.reporting_mta(), Some("smtp.domain.tld"));
+        assert_eq!(status.original_message_id(), Some("<exim-1@domain.tld>"));
+
+        let recipients = status.recipients();
+        assert_eq!(recipients.len(), 2);
+        assert_eq!(recipients[0].final_recipient(), "hei@other.tld");
+        assert_eq!(recipients[0].action(), Some(DeliveryAction::Failed));
+        assert_eq!(recipients[0].status(), Some("5.0.0"));
+        assert_eq!(
+            recipients[0].diagnostic_code(),
+            Some("550 Unrouteable address")
+        );
+        assert_eq!(recipients[1].final_recipient(), "yuin@other.tld");
+        assert_eq!(recipients[1].status(), Some("5.2.2"));
+        assert!(recipients.iter().all(RecipientStatus::is_permanent_failure));
+    }
+
+    #[test]
+    fn delivery_status_exchange() {
+        let status = fixture("bounce_exchange");
+        assert_eq!(status.reporting_mta(), Some("EX01.corp.domain.tld"));
+        assert_eq!(
+            status.original_message_id(),
+            Some("<AM0PR01MB1234@EX01.corp.domain.tld>")
+        );
+
+        let recipients = status.recipients();
+        assert_eq!(recipients.len(), 1);
+        assert_eq!(recipients[0].final_recipient(), "NoBody@other.tld");
+        assert_eq!(recipients[0].action(), Some(DeliveryAction::Failed));
+        assert_eq!(recipients[0].status(), Some("5.4.1"));
+        assert_eq!(
+            recipients[0].diagnostic_code(),
+            Some("550 5.4.1 Recipient address rejected: Access denied.")
+        );
+    }
+
+    #[test]
+    fn delivery_status_not_a_bounce() {
+        assert_eq!(
+            DeliveryStatus::parse("Content-Type: text/plain\r\n\r\nHello\r\n"),
+            None
+        );
+        assert_eq!(
+            DeliveryStatus::parse(concat!(
+                "Content-Type: multipart/report; report-type=disposition-notification;\r\n",
+                " boundary=\"b\"\r\n",
+                "\r\n",
+                "--b\r\n",
+                "Content-Type: message/disposition-notification\r\n",
+                "\r\n",
+                "Final-Recipient: rfc822; hei@domain.tld\r\n",
+                "--b--\r\n",
+            )),
+            None
+        );
+    }
+}
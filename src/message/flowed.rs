@@ -0,0 +1,174 @@
+//! `format=flowed` encoding of plain text, as defined in
+//! [RFC 3676](https://tools.ietf.org/html/rfc3676)
+//!
+//! Long lines are split with soft line breaks, lines ending with a space, so mail clients
+//! can reflow the paragraphs to the width of the screen. With `delsp=yes`, the space ending
+//! a flowed line is removed when unwrapping, which allows splitting words without a space
+//! in them, like URLs.
+
+/// Recommended maximum length of a line, excluding the line ending
+const MAX_LINE_LENGTH: usize = 78;
+
+/// Encodes `text` for a `text/plain; format=flowed; delsp=yes` part
+///
+/// The lines of `text` are considered as paragraphs. Lines longer than 78 characters are
+/// wrapped, preferably after a space, and the lines starting with a space, `>` or `From `
+/// are space-stuffed. Trailing spaces are removed, except in the signature separator.
+pub(super) fn encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + text.len() / MAX_LINE_LENGTH * 3);
+    let mut lines = text.split('\n').peekable();
+
+    while let Some(line) = lines.next() {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        // The last line is only empty if `text` ends with a line break
+        if line.is_empty() && lines.peek().is_none() {
+            break;
+        }
+
+        if line == "-- " {
+            out.push_str(line);
+        } else {
+            encode_paragraph(line.trim_end_matches(' '), &mut out);
+        }
+        if lines.peek().is_some() {
+            out.push_str("\r\n");
+        }
+    }
+
+    out
+}
+
+/// Wraps a line of text, adding soft line breaks
+fn encode_paragraph(mut paragraph: &str, out: &mut String) {
+    loop {
+        let stuffed = needs_stuffing(paragraph);
+        let available = MAX_LINE_LENGTH - usize::from(stuffed);
+        if stuffed {
+            out.push(' ');
+        }
+
+        // Byte offset after the `available`-th char, if the paragraph is longer
+        let limit = match paragraph.char_indices().nth(available) {
+            Some((limit, _)) => limit,
+            None => {
+                out.push_str(paragraph);
+                return;
+            }
+        };
+
+        // Keep room for the soft line break, and break after a space if possible
+        let hard_limit = paragraph[..limit]
+            .char_indices()
+            .last()
+            .map_or(limit, |(idx, _)| idx);
+        let split = match paragraph[..hard_limit].rfind(' ') {
+            Some(idx) if idx > 0 => idx + 1,
+            _ => hard_limit,
+        };
+        let (line, rest) = paragraph.split_at(split);
+        out.push_str(line);
+        out.push_str(" \r\n");
+        paragraph = rest;
+    }
+}
+
+fn needs_stuffing(line: &str) -> bool {
+    line.starts_with(' ') || line.starts_with('>') || line.starts_with("From ")
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Unwraps flowed text with `delsp=yes`, to check the encoding is lossless
+    fn decode(flowed: &str) -> String {
+        let mut out = String::new();
+        for line in flowed.split("\r\n") {
+            let line = line.strip_prefix(' ').unwrap_or(line);
+            if line != "-- " && line.ends_with(' ') {
+                out.push_str(&line[..line.len() - 1]);
+            } else {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+        out.pop();
+        out
+    }
+
+    fn check_line_lengths(flowed: &str) {
+        for line in flowed.split("\r\n") {
+            assert!(line.chars().count() <= MAX_LINE_LENGTH, "{:?}", line);
+        }
+    }
+
+    #[test]
+    fn short_lines() {
+        assert_eq!(
+            encode("Hello,\n\nBe happy!\n"),
+            "Hello,\r\n\r\nBe happy!\r\n"
+        );
+        assert_eq!(encode("Hello,\r\nBe happy!"), "Hello,\r\nBe happy!");
+    }
+
+    #[test]
+    fn trailing_spaces() {
+        assert_eq!(
+            encode("Hello,   \nBe happy!\n\n-- \nNoBody"),
+            "Hello,\r\nBe happy!\r\n\r\n-- \r\nNoBody"
+        );
+    }
+
+    #[test]
+    fn stuffing() {
+        assert_eq!(
+            encode(">not a quote\nFrom the start\n indented\nFromage"),
+            " >not a quote\r\n From the start\r\n  indented\r\nFromage"
+        );
+    }
+
+    #[test]
+    fn wrap_at_spaces() {
+        let text = "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod \
+                    tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam.";
+        let flowed = encode(text);
+        assert_eq!(
+            flowed,
+            "Lorem ipsum dolor sit amet, consectetur adipiscing elit, sed do eiusmod  \r\n\
+             tempor incididunt ut labore et dolore magna aliqua. Ut enim ad minim veniam."
+        );
+        check_line_lengths(&flowed);
+        assert_eq!(decode(&flowed), text);
+    }
+
+    #[test]
+    fn wrap_long_words() {
+        let text = format!("See https://example.com/{}", "a".repeat(200));
+        let flowed = encode(&text);
+        let lines: Vec<&str> = flowed.split("\r\n").collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[0], "See  ");
+        check_line_lengths(&flowed);
+        assert_eq!(decode(&flowed), text);
+    }
+
+    #[test]
+    fn wrap_stuffed_continuation() {
+        let text = format!("{} >{}", "a".repeat(76), "b".repeat(10));
+        let flowed = encode(&text);
+        assert_eq!(
+            flowed,
+            format!("{}  \r\n >{}", "a".repeat(76), "b".repeat(10))
+        );
+        check_line_lengths(&flowed);
+        assert_eq!(decode(&flowed), text);
+    }
+
+    #[test]
+    fn wrap_unicode() {
+        let text = "é".repeat(100);
+        let flowed = encode(&text);
+        assert_eq!(flowed, format!("{} \r\n{}", "é".repeat(77), "é".repeat(23)));
+        assert_eq!(decode(&flowed), text);
+    }
+}
@@ -0,0 +1,383 @@
+use super::{
+    header::{self, ContentTransferEncoding, ContentType, Header},
+    mimebody::Part,
+    reply::{decode_text, has_subject_prefix, is_inline, part_text, references},
+    Body, Message, MessageBody, MessageBuilder, MultiPart, SinglePart,
+};
+use crate::Error as EmailError;
+
+/// Subject prefixes recognized by default as marking a forward, in several languages
+const FORWARD_PREFIXES: &[&str] = &[
+    "Fwd", "Fw", "WG", "Tr", "Rv", "Enc", "VL", "VB", "Doorst", "转发", "轉寄",
+];
+
+/// Line separating the text of the forward from the forwarded message, in inline mode
+const FORWARD_SEPARATOR: &str = "---------- Forwarded message ----------";
+
+/// A builder for forwarding a [`Message`]
+///
+/// The forward gets the `Subject` of the message, prefixed with `Fwd: ` unless it already
+/// starts with a forward prefix, and the `References` of the message followed by its
+/// `Message-ID`. The message is either:
+///
+/// * attached as a `message/rfc822` part, the default, keeping its content byte for byte,
+///   attachments included
+/// * inlined with [`ForwardBuilder::inline`], as a block with its `From`, `Date`,
+///   `Subject`, `To` and `Cc` headers followed by its decoded plain text, all of it
+///   encoded again in the forward. Its attachments can be attached again with
+///   [`ForwardBuilder::with_attachments`].
+///
+/// Created with [`Message::forward`].
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use lettre::Message;
+///
+/// let email = Message::builder()
+///     .from("NoBody <nobody@domain.tld>".parse()?)
+///     .to("Hei <hei@domain.tld>".parse()?)
+///     .message_id(Some("<first@domain.tld>".to_owned()))
+///     .subject("Happy new year")
+///     .body(String::from("Be happy!"))?;
+///
+/// let forward = email
+///     .forward()
+///     .text(String::from("Look at this"))
+///     .build(
+///         Message::builder()
+///             .from("Hei <hei@domain.tld>".parse()?)
+///             .to("Yuin <yuin@domain.tld>".parse()?),
+///     )?;
+/// assert_eq!(forward.headers().get_raw("Subject"), Some("Fwd: Happy new year"));
+/// assert_eq!(forward.headers().get_raw("References"), Some("<first@domain.tld>"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ForwardBuilder<'a> {
+    message: &'a Message,
+    text: Option<String>,
+    inline: bool,
+    attachments: bool,
+    subject_prefixes: Vec<String>,
+}
+
+impl<'a> ForwardBuilder<'a> {
+    pub(super) fn new(message: &'a Message) -> Self {
+        Self {
+            message,
+            text: None,
+            inline: false,
+            attachments: false,
+            subject_prefixes: FORWARD_PREFIXES.iter().map(|&p| p.to_owned()).collect(),
+        }
+    }
+
+    /// Set the plain text written above the forwarded message
+    pub fn text(mut self, text: String) -> Self {
+        self.text = Some(text);
+        self
+    }
+
+    /// Inline the message in the text of the forward, instead of attaching it
+    ///
+    /// The message is still attached when it has no plain text part, or if its charset
+    /// isn't UTF-8.
+    pub fn inline(mut self) -> Self {
+        self.inline = true;
+        self
+    }
+
+    /// Attach the attachments of the message to the forward, when inlining it
+    ///
+    /// The attachments are copied as they are, without being decoded. An attached
+    /// message already contains its attachments.
+    pub fn with_attachments(mut self) -> Self {
+        self.attachments = true;
+        self
+    }
+
+    /// Set the subject prefixes marking a forward, compared case-insensitively
+    ///
+    /// Defaults to `Fwd` and its variants in a few languages, like `Fw` or `WG`.
+    /// A prefix is followed by a colon, and optionally by a counter, like `Fwd[2]:`.
+    pub fn subject_prefixes<I, T>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.subject_prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Build the forward, with the headers of `builder`
+    ///
+    /// The subject is only set if `builder` has none. Fails like
+    /// [`MessageBuilder::multipart`], for example if `builder` has no `From` mailbox.
+    pub fn build(self, builder: MessageBuilder) -> Result<Message, EmailError> {
+        let headers = &self.message.headers;
+        let mut builder = builder;
+        if builder.headers.get::<header::Subject>().is_none() {
+            let subject = headers
+                .get::<header::Subject>()
+                .map(|subject| subject.as_ref().to_owned())
+                .unwrap_or_default();
+            builder = if has_subject_prefix(&subject, &self.subject_prefixes) {
+                builder.subject(subject)
+            } else {
+                builder.subject(format!("Fwd: {}", subject))
+            };
+        }
+        if let Some(references) = references(headers) {
+            builder = builder.references(references);
+        }
+
+        let inlined = if self.inline {
+            self.inlined_text()
+        } else {
+            None
+        };
+        match inlined {
+            Some(text) => {
+                let attachments = match &self.message.body {
+                    MessageBody::Mime(part) if self.attachments => attachments(part),
+                    _ => Vec::new(),
+                };
+                if attachments.is_empty() {
+                    return builder.singlepart(SinglePart::plain(text));
+                }
+
+                let mut mixed = MultiPart::mixed().singlepart(SinglePart::plain(text));
+                for attachment in attachments {
+                    mixed = mixed.singlepart(attachment);
+                }
+                builder.multipart(mixed)
+            }
+            None => {
+                let attached = self.attached_message();
+                let mixed = match self.text {
+                    Some(text) => MultiPart::mixed()
+                        .singlepart(SinglePart::plain(text))
+                        .singlepart(attached),
+                    None => MultiPart::mixed().singlepart(attached),
+                };
+                builder.multipart(mixed)
+            }
+        }
+    }
+
+    /// The text of the forward followed by the headers and the plain text of the
+    /// message, `None` if it has no plain text which can be decoded
+    fn inlined_text(&self) -> Option<String> {
+        let headers = &self.message.headers;
+        let original = match &self.message.body {
+            MessageBody::Mime(part) => part_text(part)?,
+            MessageBody::Raw(body) => decode_text(headers, body)?,
+        };
+
+        let mut text = String::new();
+        if let Some(note) = &self.text {
+            text.push_str(note);
+            text.push_str("\n\n");
+        }
+        text.push_str(FORWARD_SEPARATOR);
+        text.push('\n');
+        let fields = [
+            (
+                "From",
+                headers.get::<header::From>().map(|h| h.0.to_string()),
+            ),
+            (
+                "Date",
+                headers
+                    .get_raw(&header::Date::name())
+                    .map(|date| date.trim().to_owned()),
+            ),
+            (
+                "Subject",
+                headers
+                    .get::<header::Subject>()
+                    .map(|subject| subject.as_ref().to_owned()),
+            ),
+            ("To", headers.get::<header::To>().map(|h| h.0.to_string())),
+            ("Cc", headers.get::<header::Cc>().map(|h| h.0.to_string())),
+        ];
+        for (name, value) in fields.iter() {
+            if let Some(value) = value {
+                text.push_str(name);
+                text.push_str(": ");
+                text.push_str(value);
+                text.push('\n');
+            }
+        }
+        text.push('\n');
+        text.push_str(&original);
+        Some(text)
+    }
+
+    /// The message in a `message/rfc822` part, formatted as it would be sent
+    fn attached_message(&self) -> SinglePart {
+        let formatted = self.message.formatted();
+        // The content of `message/rfc822` parts can't be encoded
+        let encoding = if formatted.is_ascii() {
+            ContentTransferEncoding::SevenBit
+        } else {
+            ContentTransferEncoding::EightBit
+        };
+        SinglePart::builder()
+            .header(ContentType::parse("message/rfc822").expect("valid content type"))
+            .body(Body::dangerous_pre_encoded(formatted, encoding))
+    }
+}
+
+/// The attachments of a part, in order
+fn attachments(part: &Part) -> Vec<SinglePart> {
+    match part {
+        Part::Single(part) if !is_inline(part.headers()) => vec![part.clone()],
+        Part::Single(_) | Part::Frozen(_) => Vec::new(),
+        Part::Multi(part) => part.parts().iter().flat_map(attachments).collect(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::message::{header, Attachment, Message, MessageBuilder, MultiPart, SinglePart};
+
+    fn message() -> Message {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .message_id(Some("<second@domain.tld>".to_owned()))
+            .references("<first@domain.tld>".to_owned())
+            .subject("Happy new year")
+            .multipart(
+                MultiPart::mixed()
+                    .multipart(MultiPart::alternative_plain_html(
+                        String::from("Be happy!\n\nNoBody"),
+                        String::from("<p>Be happy!</p>"),
+                    ))
+                    .singlepart(
+                        Attachment::new(String::from("wishes.txt"))
+                            .body(String::from("Health"), header::ContentType::TEXT_PLAIN),
+                    ),
+            )
+            .unwrap()
+    }
+
+    fn builder() -> MessageBuilder {
+        Message::builder()
+            .from("Hei <hei@domain.tld>".parse().unwrap())
+            .to("Yuin <yuin@domain.tld>".parse().unwrap())
+    }
+
+    #[test]
+    fn forward_attached() {
+        let message = message();
+        let forward = message
+            .forward()
+            .text(String::from("Look at this"))
+            .build(builder())
+            .unwrap();
+        let headers = forward.headers();
+
+        assert_eq!(headers.get_raw("Subject"), Some("Fwd: Happy new year"));
+        assert_eq!(
+            headers.get_raw("References"),
+            Some("<first@domain.tld> <second@domain.tld>")
+        );
+
+        let formatted = String::from_utf8(forward.formatted()).unwrap();
+        assert!(formatted.contains(concat!(
+            "Content-Type: message/rfc822\r\n",
+            "Content-Transfer-Encoding: 7bit\r\n",
+            "\r\n",
+        )));
+        let original = String::from_utf8(message.formatted()).unwrap();
+        assert!(formatted.contains(&original));
+        assert!(formatted.contains("Look at this"));
+    }
+
+    #[test]
+    fn forward_inline() {
+        let message = message();
+        let forward = message
+            .forward()
+            .text(String::from("Look at this"))
+            .inline()
+            .build(builder())
+            .unwrap();
+        let formatted = String::from_utf8(forward.formatted()).unwrap();
+
+        let date = message.headers().get_raw("Date").unwrap().trim();
+        let expected = format!(
+            concat!(
+                "Look at this\r\n",
+                "\r\n",
+                "---------- Forwarded message ----------\r\n",
+                "From: NoBody <nobody@domain.tld>\r\n",
+                "Date: {}\r\n",
+                "Subject: Happy new year\r\n",
+                "To: Hei <hei@domain.tld>\r\n",
+                "\r\n",
+                "Be happy!\r\n",
+                "\r\n",
+                "NoBody\r\n",
+            ),
+            date
+        );
+        assert!(formatted.ends_with(&expected), "{}", formatted);
+        assert!(!formatted.contains("wishes.txt"));
+
+        let forward = message
+            .forward()
+            .inline()
+            .with_attachments()
+            .build(builder())
+            .unwrap();
+        let formatted = String::from_utf8(forward.formatted()).unwrap();
+        assert!(formatted.contains("Content-Type: multipart/mixed"));
+        assert!(formatted.contains(concat!(
+            "Content-Disposition: attachment; filename=\"wishes.txt\"\r\n",
+            "Content-Type: text/plain; charset=utf-8\r\n",
+            "Content-Transfer-Encoding: 7bit\r\n",
+            "\r\n",
+            "Health\r\n",
+        )));
+    }
+
+    #[test]
+    fn forward_inline_without_text() {
+        let message = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .singlepart(SinglePart::html(String::from("<p>Be happy!</p>")))
+            .unwrap();
+        let forward = message.forward().inline().build(builder()).unwrap();
+        let formatted = String::from_utf8(forward.formatted()).unwrap();
+        assert!(formatted.contains("Content-Type: message/rfc822\r\n"));
+    }
+
+    #[test]
+    fn forward_subject_prefix() {
+        for (subject, expected) in &[
+            ("Fwd: Happy new year", "Fwd: Happy new year"),
+            ("FW: Happy new year", "FW: Happy new year"),
+            ("WG: Frohes neues Jahr", "WG: Frohes neues Jahr"),
+            ("Re: Happy new year", "Fwd: Re: Happy new year"),
+        ] {
+            let mut message = message();
+            message
+                .headers_mut()
+                .set(header::Subject::from(subject.to_string()));
+            let forward = message.forward().build(builder()).unwrap();
+            assert_eq!(forward.headers().get_raw("Subject"), Some(*expected));
+        }
+
+        let forward = message()
+            .forward()
+            .build(builder().subject("Wishes"))
+            .unwrap();
+        assert_eq!(forward.headers().get_raw("Subject"), Some("Wishes"));
+    }
+}
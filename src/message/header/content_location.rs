@@ -0,0 +1,169 @@
+use std::{
+    error::Error as StdError,
+    fmt::{self, Display},
+    str::FromStr,
+};
+
+use super::{Header, HeaderName};
+use crate::BoxError;
+
+/// `Content-Location` header, the URI identifying the content of a part
+///
+/// Defined in [RFC2557](https://tools.ietf.org/html/rfc2557#section-4.2). The URI is
+/// either absolute, like `https://example.com/chart.png`, or relative to the base of the
+/// message, like `images/chart.png`. It's used by the parts of a `multipart/related`
+/// HTML message to be matched by the URLs of the resources of the HTML part.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentLocation(String);
+
+impl ContentLocation {
+    /// Parse a `ContentLocation` from a URI
+    ///
+    /// Fails if `uri` isn't a valid URI: characters outside of those allowed by
+    /// [RFC3986](https://tools.ietf.org/html/rfc3986#section-2) have to be
+    /// percent-encoded.
+    pub fn parse(uri: &str) -> Result<ContentLocation, ContentLocationErr> {
+        if is_uri_reference(uri) {
+            Ok(Self(uri.to_owned()))
+        } else {
+            Err(ContentLocationErr(uri.to_owned()))
+        }
+    }
+}
+
+impl Header for ContentLocation {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("Content-Location")
+    }
+
+    fn parse(s: &str) -> Result<Self, BoxError> {
+        // Long URIs can be folded, the whitespace isn't part of them
+        let uri: String = s.split_whitespace().collect();
+        Ok(Self::parse(&uri)?)
+    }
+
+    fn display(&self) -> String {
+        self.0.clone()
+    }
+}
+
+impl FromStr for ContentLocation {
+    type Err = ContentLocationErr;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
+    }
+}
+
+impl AsRef<str> for ContentLocation {
+    #[inline]
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+/// An error occurred while trying to [`ContentLocation::parse`].
+#[derive(Debug)]
+pub struct ContentLocationErr(String);
+
+impl StdError for ContentLocationErr {}
+
+impl Display for ContentLocationErr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid URI in Content-Location: {:?}", self.0)
+    }
+}
+
+/// Checks that `uri` is an absolute URI or a relative reference
+fn is_uri_reference(uri: &str) -> bool {
+    const ALLOWED: &[u8] = b"-._~:/?#[]@!$&'()*+,;=";
+
+    let bytes = uri.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let escaped = bytes.get(i + 1..i + 3);
+                if !escaped.map_or(false, |hex| hex.iter().all(u8::is_ascii_hexdigit)) {
+                    return false;
+                }
+                i += 3;
+                continue;
+            }
+            b if b.is_ascii_alphanumeric() || ALLOWED.contains(&b) => {}
+            _ => return false,
+        }
+        i += 1;
+    }
+
+    // A colon before the path is the end of the scheme
+    match uri.find(&[':', '/', '?', '#'][..]) {
+        Some(end) if bytes[end] == b':' => {
+            let scheme = &bytes[..end];
+            scheme.first().map_or(false, u8::is_ascii_alphabetic)
+                && scheme
+                    .iter()
+                    .all(|&b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'-' | b'.'))
+        }
+        _ => !uri.is_empty(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ContentLocation;
+    use crate::message::header::{HeaderName, Headers};
+
+    #[test]
+    fn format_content_location() {
+        let mut headers = Headers::new();
+        headers.set(ContentLocation::parse("https://example.com/q3/chart.png?size=large").unwrap());
+
+        assert_eq!(
+            headers.to_string(),
+            "Content-Location: https://example.com/q3/chart.png?size=large\r\n"
+        );
+    }
+
+    #[test]
+    fn parse_valid_content_location() {
+        for uri in &[
+            "https://example.com/chart.png",
+            "cid:chart@example.com",
+            "images/chart%20q3.png",
+            "/image.png",
+            "chart.png#top",
+        ] {
+            assert_eq!(ContentLocation::parse(uri).unwrap().as_ref(), *uri);
+        }
+    }
+
+    #[test]
+    fn parse_invalid_content_location() {
+        for uri in &[
+            "",
+            "images/chart q3.png",
+            "https://example.com/graphique-été.png",
+            "chart%2.png",
+            "1http://example.com/",
+            ":chart.png",
+            "\"chart.png\"",
+        ] {
+            assert!(ContentLocation::parse(uri).is_err(), "{}", uri);
+        }
+    }
+
+    #[test]
+    fn parse_folded_content_location() {
+        let mut headers = Headers::new();
+        headers.insert_raw(
+            HeaderName::new_from_ascii_str("Content-Location"),
+            "https://example.com/reports/2021/\r\n q3/chart.png".to_string(),
+        );
+
+        assert_eq!(
+            headers.get::<ContentLocation>(),
+            Some(ContentLocation::parse("https://example.com/reports/2021/q3/chart.png").unwrap())
+        );
+    }
+}
@@ -32,8 +32,62 @@ impl ContentType {
     pub const TEXT_HTML: ContentType = Self::from_mime(mime::TEXT_HTML_UTF_8);
 
     /// Parse `s` into `ContentType`
+    ///
+    /// Fails if `s` isn't a valid `type/subtype` media type, optionally followed by
+    /// `; name=value` parameters, like `text /plain`, `text/` or `text/plain; charset=`.
+    /// The type, the subtype, the names of the parameters and the value of the `charset`
+    /// parameter are lowercased, as they are case-insensitive. The other values are kept
+    /// as they are, as a `boundary` is case-sensitive.
+    ///
+    /// ```
+    /// use lettre::message::header::ContentType;
+    ///
+    /// let content_type = ContentType::parse("Text/HTML; Charset=UTF-8").unwrap();
+    /// assert_eq!(content_type.essence(), "text/html");
+    /// assert_eq!(content_type.get_param("charset"), Some("utf-8"));
+    ///
+    /// assert!(ContentType::parse("text").is_err());
+    /// ```
     pub fn parse(s: &str) -> Result<ContentType, ContentTypeErr> {
-        Ok(Self::from_mime(s.parse().map_err(ContentTypeErr)?))
+        let mime: Mime = s
+            .parse()
+            .map_err(|err| ContentTypeErr(ContentTypeErrKind::Mime(err)))?;
+        if mime.subtype().as_str().is_empty() {
+            return Err(ContentTypeErr(ContentTypeErrKind::MissingSubtype));
+        }
+        if mime.params().any(|(_, value)| value.as_str().is_empty()) {
+            return Err(ContentTypeErr(ContentTypeErrKind::EmptyParam));
+        }
+        Ok(Self::from_mime(mime))
+    }
+
+    /// The top-level type, like `text` in `text/plain`
+    pub fn type_(&self) -> &str {
+        self.0.type_().as_str()
+    }
+
+    /// The subtype, like `plain` in `text/plain`
+    pub fn subtype(&self) -> &str {
+        self.0.subtype().as_str()
+    }
+
+    /// The type and the subtype, without the parameters, like `text/plain`
+    pub fn essence(&self) -> &str {
+        self.0.essence_str()
+    }
+
+    /// The value of the parameter `name`, unquoted
+    ///
+    /// `name` is compared case-insensitively.
+    pub fn get_param(&self, name: &str) -> Option<&str> {
+        self.0.get_param(name).map(|value| value.as_str())
+    }
+
+    /// Iterates over the names and unquoted values of the parameters, in order
+    pub fn params(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.0
+            .params()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
     }
 
     pub(crate) const fn from_mime(mime: Mime) -> Self {
@@ -51,7 +105,7 @@ impl Header for ContentType {
     }
 
     fn parse(s: &str) -> Result<Self, BoxError> {
-        Ok(Self(s.parse()?))
+        Ok(Self::parse(s)?)
     }
 
     fn display(&self) -> String {
@@ -69,17 +123,31 @@ impl FromStr for ContentType {
 
 /// An error occurred while trying to [`ContentType::parse`].
 #[derive(Debug)]
-pub struct ContentTypeErr(mime::FromStrError);
+pub struct ContentTypeErr(ContentTypeErrKind);
+
+#[derive(Debug)]
+enum ContentTypeErrKind {
+    Mime(mime::FromStrError),
+    MissingSubtype,
+    EmptyParam,
+}
 
 impl StdError for ContentTypeErr {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
-        Some(&self.0)
+        match &self.0 {
+            ContentTypeErrKind::Mime(err) => Some(err),
+            _ => None,
+        }
     }
 }
 
 impl Display for ContentTypeErr {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        Display::fmt(&self.0, f)
+        match &self.0 {
+            ContentTypeErrKind::Mime(err) => Display::fmt(err, f),
+            ContentTypeErrKind::MissingSubtype => f.write_str("the subtype is missing"),
+            ContentTypeErrKind::EmptyParam => f.write_str("a parameter has an empty value"),
+        }
     }
 }
 
@@ -163,6 +231,47 @@ mod test {
         );
     }
 
+    #[test]
+    fn parse_normalized_content_type() {
+        let content_type =
+            ContentType::parse("Multipart/Mixed; Boundary=\"AbC-123\"; Charset=UTF-8").unwrap();
+
+        assert_eq!(content_type.type_(), "multipart");
+        assert_eq!(content_type.subtype(), "mixed");
+        assert_eq!(content_type.essence(), "multipart/mixed");
+        assert_eq!(content_type.get_param("boundary"), Some("AbC-123"));
+        assert_eq!(content_type.get_param("BOUNDARY"), Some("AbC-123"));
+        assert_eq!(content_type.get_param("name"), None);
+        assert_eq!(
+            content_type.params().collect::<Vec<_>>(),
+            vec![("boundary", "AbC-123"), ("charset", "utf-8")]
+        );
+
+        let mut headers = Headers::new();
+        headers.set(content_type);
+        assert_eq!(
+            headers.to_string(),
+            "Content-Type: multipart/mixed; boundary=\"AbC-123\"; charset=utf-8\r\n"
+        );
+    }
+
+    #[test]
+    fn parse_invalid_content_type() {
+        for s in &[
+            "",
+            "text",
+            "text/",
+            "/plain",
+            "text /plain",
+            "text/plain html",
+            "text/plain; charset",
+            "text/plain; charset=",
+            "text/plain\r\nBcc: attacker@evil.com",
+        ] {
+            assert!(ContentType::parse(s).is_err(), "{:?}", s);
+        }
+    }
+
     #[test]
     fn parse_content_type() {
         let mut headers = Headers::new();
@@ -5,69 +5,85 @@ use httpdate::HttpDate;
 use super::{Header, HeaderName};
 use crate::BoxError;
 
-/// Message `Date` header
-///
-/// Defined in [RFC2822](https://tools.ietf.org/html/rfc2822#section-3.3)
-#[derive(Debug, Clone, Copy, PartialEq)]
-pub struct Date(HttpDate);
-
-impl Date {
-    /// Build a `Date` from [`SystemTime`]
-    pub fn new(st: SystemTime) -> Self {
-        Self(st.into())
-    }
-
-    /// Get the current date
-    ///
-    /// Shortcut for `Date::new(SystemTime::now())`
-    pub fn now() -> Self {
-        Self::new(SystemTime::now())
-    }
-}
-
-impl Header for Date {
-    fn name() -> HeaderName {
-        HeaderName::new_from_ascii_str("Date")
-    }
-
-    fn parse(s: &str) -> Result<Self, BoxError> {
-        let mut s = String::from(s);
-        if s.ends_with(" -0000") {
-            // The httpdate crate expects the `Date` to end in ` GMT`, but email
-            // uses `-0000`, so we crudely fix this issue here.
-
-            s.truncate(s.len() - "-0000".len());
-            s.push_str("GMT");
+macro_rules! date_header {
+    ($(#[$doc:meta])*($type_name: ident, $header_name: expr)) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        pub struct $type_name(HttpDate);
+
+        impl $type_name {
+            /// Build a `Date` from [`SystemTime`]
+            pub fn new(st: SystemTime) -> Self {
+                Self(st.into())
+            }
+
+            /// Get the current date
+            ///
+            /// Shortcut for `Date::new(SystemTime::now())`
+            pub fn now() -> Self {
+                Self::new(SystemTime::now())
+            }
         }
 
-        Ok(Self(s.parse::<HttpDate>()?))
-    }
+        impl Header for $type_name {
+            fn name() -> HeaderName {
+                HeaderName::new_from_ascii_str($header_name)
+            }
+
+            fn parse(s: &str) -> Result<Self, BoxError> {
+                let mut s = String::from(s);
+                if s.ends_with(" -0000") {
+                    // The httpdate crate expects the `Date` to end in ` GMT`, but email
+                    // uses `-0000`, so we crudely fix this issue here.
+
+                    s.truncate(s.len() - "-0000".len());
+                    s.push_str("GMT");
+                }
+
+                Ok(Self(s.parse::<HttpDate>()?))
+            }
+
+            fn display(&self) -> String {
+                let mut s = self.0.to_string();
+                if s.ends_with(" GMT") {
+                    // The httpdate crate always appends ` GMT` to the end of the string,
+                    // but this is considered an obsolete date format for email
+                    // https://tools.ietf.org/html/rfc2822#appendix-A.6.2,
+                    // so we replace `GMT` with `-0000`
+                    s.truncate(s.len() - "GMT".len());
+                    s.push_str("-0000");
+                }
+
+                s
+            }
+        }
 
-    fn display(&self) -> String {
-        let mut s = self.0.to_string();
-        if s.ends_with(" GMT") {
-            // The httpdate crate always appends ` GMT` to the end of the string,
-            // but this is considered an obsolete date format for email
-            // https://tools.ietf.org/html/rfc2822#appendix-A.6.2,
-            // so we replace `GMT` with `-0000`
-            s.truncate(s.len() - "GMT".len());
-            s.push_str("-0000");
+        impl From<SystemTime> for $type_name {
+            fn from(st: SystemTime) -> Self {
+                Self::new(st)
+            }
         }
 
-        s
-    }
+        impl From<$type_name> for SystemTime {
+            fn from(this: $type_name) -> SystemTime {
+                this.0.into()
+            }
+        }
+    };
 }
 
-impl From<SystemTime> for Date {
-    fn from(st: SystemTime) -> Self {
-        Self::new(st)
-    }
+date_header! {
+    /// Message `Date` header
+    ///
+    /// Defined in [RFC2822](https://tools.ietf.org/html/rfc2822#section-3.3)
+    (Date, "Date")
 }
 
-impl From<Date> for SystemTime {
-    fn from(this: Date) -> SystemTime {
-        this.0.into()
-    }
+date_header! {
+    /// `Resent-Date` header, when the message was resent
+    ///
+    /// Defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.6)
+    (ResentDate, "Resent-Date")
 }
 
 #[cfg(test)]
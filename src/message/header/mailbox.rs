@@ -158,9 +158,69 @@ mailboxes_header! {
     (Bcc, "Bcc")
 }
 
+mailbox_header! {
+    /**
+
+    `Resent-Sender` header
+
+    This header contains the [`Mailbox`][self::Mailbox] which resent the message,
+    as defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.6).
+
+     */
+    (ResentSender, "Resent-Sender")
+}
+
+mailboxes_header! {
+    /**
+
+    `Resent-From` header
+
+    This header contains [`Mailboxes`][self::Mailboxes], as defined in
+    [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.6).
+
+     */
+    (ResentFrom, "Resent-From")
+}
+
+mailboxes_header! {
+    /**
+
+    `Resent-To` header
+
+    This header contains [`Mailboxes`][self::Mailboxes], as defined in
+    [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.6).
+
+     */
+    (ResentTo, "Resent-To")
+}
+
+mailboxes_header! {
+    /**
+
+    `Resent-Cc` header
+
+    This header contains [`Mailboxes`][self::Mailboxes], as defined in
+    [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.6).
+
+     */
+    (ResentCc, "Resent-Cc")
+}
+
+mailboxes_header! {
+    /**
+
+    `Resent-Bcc` header
+
+    This header contains [`Mailboxes`][self::Mailboxes], as defined in
+    [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.6).
+
+     */
+    (ResentBcc, "Resent-Bcc")
+}
+
 #[cfg(test)]
 mod test {
-    use super::{From, Mailbox, Mailboxes};
+    use super::{From, Mailbox, Mailboxes, To};
     use crate::message::header::{HeaderName, Headers};
 
     #[test]
@@ -227,6 +287,97 @@ mod test {
         );
     }
 
+    #[test]
+    fn format_utf8_name_with_specials() {
+        let from = vec!["\"Доу, Джон\" <john@example.com>".parse().unwrap()];
+
+        let mut headers = Headers::new();
+        headers.set(From(from.into()));
+
+        assert_eq!(
+            headers.to_string(),
+            "From: =?utf-8?b?0JTQvtGDLCDQlNC20L7QvQ==?= <john@example.com>\r\n"
+        );
+    }
+
+    #[test]
+    fn format_many_recipients() {
+        let to: Vec<Mailbox> = (0..100)
+            .map(|i| {
+                match i % 4 {
+                    0 => format!("user{}@example.com", i),
+                    1 => format!("\"Doe, John {}\" <john{}@example.com>", i, i),
+                    2 => format!("Очень Длинное Имя Получателя Письма {} <user{}@example.com>", i, i),
+                    _ => format!(
+                        "Some Person {} <a.very.long.local.part.for.testing.{}@subdomain.example.com>",
+                        i, i
+                    ),
+                }
+                .parse()
+                .unwrap()
+            })
+            .collect();
+
+        let mut headers = Headers::new();
+        headers.set(To(to.into()));
+        let formatted = headers.to_string();
+
+        let lines = formatted.split_terminator("\r\n").collect::<Vec<_>>();
+        for line in &lines {
+            // only a mailbox too long for a line by itself makes a longer line
+            assert!(
+                line.len() <= 78 || line.matches('@').count() == 1,
+                "line too long: {:?}",
+                line
+            );
+            // encoded words and mailboxes are never split
+            assert_eq!(line.matches("=?").count(), line.matches("?=").count());
+            assert_eq!(line.matches('<').count(), line.matches('>').count());
+        }
+        // lines are folded after the commas
+        assert!(lines[..lines.len() - 1]
+            .iter()
+            .all(|line| line.ends_with(',')));
+        // unfolding gives back the mailboxes
+        let unfolded = formatted.replace("\r\n", "");
+        assert!(unfolded.contains("\"Doe, John 1\" <john1@example.com>, "));
+        assert!(unfolded.contains(" Some Person 11 <a.very.long.local.part.for.testing.11@"));
+    }
+
+    #[test]
+    fn format_long_display_name() {
+        let name = "Very Long Display Name ".repeat(5);
+        let to: Mailbox = format!("{}<long@example.com>", name).parse().unwrap();
+        let mut headers = Headers::new();
+        headers.set(To(vec![
+            "user@example.com".parse().unwrap(),
+            to.clone(),
+            "other@example.com".parse().unwrap(),
+        ]
+        .into()));
+        assert_eq!(
+            headers.to_string(),
+            format!(
+                "To: user@example.com,\r\n {}<long@example.com>,\r\n other@example.com\r\n",
+                name
+            )
+        );
+
+        // Only folded inside when longer than the limit of 998 bytes
+        let name = "Very Long Display Name ".repeat(50);
+        let to: Mailbox = format!("{}<long@example.com>", name).parse().unwrap();
+        let mut headers = Headers::new();
+        headers.set(To(to.into()));
+        let formatted = headers.to_string();
+        let lines = formatted.split_terminator("\r\n").collect::<Vec<_>>();
+        assert_eq!(lines.len(), 2);
+        assert!(lines.iter().all(|line| line.len() <= 998));
+        assert_eq!(
+            formatted.replace("\r\n", ""),
+            format!("To: {}<long@example.com>", name)
+        );
+    }
+
     #[test]
     fn parse_single_without_name() {
         let from = vec!["kayo@example.com".parse().unwrap()].into();
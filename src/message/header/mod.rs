@@ -10,9 +10,12 @@ use std::{
 pub use self::{
     content::*,
     content_disposition::ContentDisposition,
+    content_location::{ContentLocation, ContentLocationErr},
     content_type::{ContentType, ContentTypeErr},
-    date::Date,
+    date::{Date, ResentDate},
     mailbox::*,
+    received::{Received, ReceivedProtocol},
+    return_path::ReturnPath,
     special::*,
     textual::*,
 };
@@ -20,24 +23,81 @@ use crate::BoxError;
 
 mod content;
 mod content_disposition;
+mod content_location;
 mod content_type;
 mod date;
 mod mailbox;
+mod received;
+mod return_path;
 mod special;
 mod textual;
 
 /// Represents an email header
 ///
 /// Email header as defined in [RFC5322](https://datatracker.ietf.org/doc/html/rfc5322) and extensions.
+///
+/// Implementing it for your own types makes them usable with [`Headers::get`],
+/// [`Headers::set`] and [`MessageBuilder::header`](crate::message::MessageBuilder::header)
+/// like the built-in headers. The [`text_header!`](crate::text_header) and
+/// [`enum_header!`](crate::enum_header) macros define headers holding free text or
+/// one of a few keywords, other headers can validate their value:
+///
+/// ```rust
+/// use std::error::Error;
+///
+/// use lettre::message::header::{Header, HeaderName, Headers};
+///
+/// /// `X-Org-Tenant` header, the numeric identifier of a tenant
+/// #[derive(Debug, Clone, PartialEq)]
+/// struct XOrgTenant(u32);
+///
+/// impl Header for XOrgTenant {
+///     fn name() -> HeaderName {
+///         HeaderName::new_from_ascii_str("X-Org-Tenant")
+///     }
+///
+///     fn parse(s: &str) -> Result<Self, Box<dyn Error + Send + Sync>> {
+///         Ok(Self(s.trim().parse()?))
+///     }
+///
+///     fn display(&self) -> String {
+///         self.0.to_string()
+///     }
+/// }
+///
+/// let mut headers = Headers::new();
+/// headers.set(XOrgTenant(42));
+/// assert_eq!(headers.to_string(), "X-Org-Tenant: 42\r\n");
+/// assert_eq!(headers.get::<XOrgTenant>(), Some(XOrgTenant(42)));
+///
+/// headers.insert_raw(
+///     HeaderName::new_from_ascii_str("X-Org-Tenant"),
+///     "forty-two".to_owned(),
+/// );
+/// assert_eq!(headers.get::<XOrgTenant>(), None);
+/// ```
 pub trait Header: Clone {
+    /// Name of the header
     fn name() -> HeaderName;
 
+    /// Parses the value of the header, which can be folded
     fn parse(s: &str) -> Result<Self, BoxError>;
 
+    /// Value of the header, encoded and folded when the headers are formatted
     fn display(&self) -> String;
 }
 
 /// A set of email headers
+///
+/// Headers are kept in insertion order, which is the order they're formatted in, and
+/// their names are compared case-insensitively. Headers which can only appear once in
+/// a message, like `From`, `Subject`, `Date` or `Message-ID`, are replaced when set
+/// again, while repeatable ones, like `Received` or `Comments`, can be added several
+/// times with [`Headers::prepend`].
+///
+/// Values are folded when formatting `Headers`, and CR and LF characters in a value are
+/// encoded, so a value can't inject additional headers. Building a message whose headers
+/// contain a line break fails.
 #[derive(Debug, Clone, Default)]
 pub struct Headers {
     headers: Vec<(HeaderName, String)>,
@@ -102,10 +162,20 @@ impl Headers {
 
     /// Inserts a raw header into `Headers`, overriding `value` if it
     /// was already present in `Headers`.
+    ///
+    /// The header keeps its position. Other occurrences of a header which can only
+    /// appear once in a message are removed.
     pub fn insert_raw(&mut self, name: HeaderName, value: String) {
-        match self.find_header_mut(&name) {
-            Some((_, current_value)) => {
-                *current_value = value;
+        match self.find_header_index(&name) {
+            Some(i) => {
+                self.headers[i].1 = value;
+                if is_singleton(&name) {
+                    let rest = self.headers.split_off(i + 1);
+                    self.headers.extend(
+                        rest.into_iter()
+                            .filter(|(name_, _value)| !name.eq_ignore_ascii_case(name_)),
+                    );
+                }
             }
             None => {
                 self.headers.push((name, value));
@@ -134,6 +204,67 @@ impl Headers {
         self.find_header_index(name).map(|i| self.headers.remove(i))
     }
 
+    /// Inserts `header` before all the headers, even if one with the same name is
+    /// already present
+    ///
+    /// Used for trace headers like [`Received`], which each host relaying a message adds
+    /// at its top, keeping the ones added before in order. A header which can only appear
+    /// once in a message replaces the present one instead, and is moved to the top.
+    pub fn prepend<H: Header>(&mut self, header: H) {
+        let name = H::name();
+        if is_singleton(&name) {
+            while self.remove_raw(&name).is_some() {}
+        }
+        self.headers.insert(0, (name, header.display()));
+    }
+
+    /// Iterates over all the `Header`s present in `Headers`, in order
+    ///
+    /// Occurrences which can't be parsed as `Header` are skipped.
+    pub fn get_all<H: Header>(&self) -> impl Iterator<Item = H> + '_ {
+        self.headers
+            .iter()
+            .filter(|(name, _value)| H::name().eq_ignore_ascii_case(name))
+            .filter_map(|(_name, value)| H::parse(value).ok())
+    }
+
+    /// Iterates over the raw values of all the headers named `name`, in order
+    pub fn get_all_raw<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> + 'a {
+        self.headers
+            .iter()
+            .filter(move |(name_, _value)| name.eq_ignore_ascii_case(name_))
+            .map(|(_name, value)| value.as_str())
+    }
+
+    /// Iterates over the names and the raw values of the headers, in order
+    pub fn iter(&self) -> impl Iterator<Item = (&HeaderName, &str)> {
+        self.headers
+            .iter()
+            .map(|(name, value)| (name, value.as_str()))
+    }
+
+    /// Inserts a raw header after all the headers, even if one with the same name is
+    /// already present
+    pub(crate) fn push_raw(&mut self, name: HeaderName, value: String) {
+        self.headers.push((name, value));
+    }
+
+    /// Inserts all the headers of `block` before the current ones, keeping their order
+    pub(crate) fn prepend_block(&mut self, mut block: Headers) {
+        block.headers.append(&mut self.headers);
+        self.headers = block.headers;
+    }
+
+    /// Returns true if a value contains a CR or LF character
+    ///
+    /// Those are never needed, as folding is done when formatting `Headers`,
+    /// and could be used to inject additional headers.
+    pub(crate) fn has_line_breaks(&self) -> bool {
+        self.headers
+            .iter()
+            .any(|(_name, value)| value.contains(&['\r', '\n'][..]))
+    }
+
     fn find_header(&self, name: &str) -> Option<(&HeaderName, &str)> {
         self.headers
             .iter()
@@ -163,7 +294,15 @@ impl Display for Headers {
         for (name, value) in &self.headers {
             Display::fmt(name, f)?;
             f.write_str(": ")?;
-            HeaderValueEncoder::encode(name, value, f)?;
+            if is_address_list(name) {
+                AddressListEncoder::encode(name, value, mailbox_words, f)?;
+            } else if is_phrase_list(name) {
+                AddressListEncoder::encode(name, value, phrase_words, f)?;
+            } else if is_trace(name) {
+                AddressListEncoder::encode_units(name, &received::trace_units(value), f)?;
+            } else {
+                HeaderValueEncoder::encode(name, value, f)?;
+            }
             f.write_str("\r\n")?;
         }
 
@@ -293,6 +432,44 @@ impl PartialEq<HeaderName> for &str {
 const ENCODING_START_PREFIX: &str = "=?utf-8?b?";
 const ENCODING_END_SUFFIX: &str = "?=";
 const MAX_LINE_LEN: usize = 76;
+/// Maximum length of a line, which a single address can make longer than `MAX_LINE_LEN`
+const MAX_HARD_LINE_LEN: usize = 998;
+/// Maximum length of the text put into a single encoded word, which keeps
+/// the encoded word shorter than `MAX_LINE_LEN`
+const MAX_ENCODED_WORD_TEXT_LEN: usize = 45;
+/// Headers containing a list of addresses
+const ADDRESS_LIST_HEADERS: &[&str] = &[
+    "From",
+    "Sender",
+    "Reply-To",
+    "To",
+    "Cc",
+    "Bcc",
+    "Resent-From",
+    "Resent-Sender",
+    "Resent-To",
+    "Resent-Cc",
+    "Resent-Bcc",
+];
+/// Headers containing a comma-separated list of phrases
+const PHRASE_LIST_HEADERS: &[&str] = &["Keywords"];
+/// Headers which can appear at most once in a message, as listed in
+/// [RFC 5322](https://tools.ietf.org/html/rfc5322#section-3.6)
+const SINGLETON_HEADERS: &[&str] = &[
+    "Date",
+    "From",
+    "Sender",
+    "Reply-To",
+    "To",
+    "Cc",
+    "Bcc",
+    "Message-ID",
+    "In-Reply-To",
+    "References",
+    "Subject",
+];
+/// Trace headers, made of clauses followed by a date
+const TRACE_HEADERS: &[&str] = &["Received"];
 
 /// [RFC 1522](https://tools.ietf.org/html/rfc1522) header value encoder
 struct HeaderValueEncoder {
@@ -501,6 +678,268 @@ impl HeaderValueEncoder {
     }
 }
 
+fn is_address_list(name: &HeaderName) -> bool {
+    ADDRESS_LIST_HEADERS
+        .iter()
+        .any(|header| name.eq_ignore_ascii_case(header))
+}
+
+fn is_phrase_list(name: &HeaderName) -> bool {
+    PHRASE_LIST_HEADERS
+        .iter()
+        .any(|header| name.eq_ignore_ascii_case(header))
+}
+
+fn is_singleton(name: &HeaderName) -> bool {
+    SINGLETON_HEADERS
+        .iter()
+        .any(|header| name.eq_ignore_ascii_case(header))
+}
+
+fn is_trace(name: &HeaderName) -> bool {
+    TRACE_HEADERS
+        .iter()
+        .any(|header| name.eq_ignore_ascii_case(header))
+}
+
+/// Encoder for headers containing a list of addresses, of phrases, or of clauses
+///
+/// Lines are folded after the comma separating two addresses, or before a clause. An
+/// address which doesn't fit on a line by itself is kept whole on a longer line, only folded
+/// between the words of its display name if it's longer than `MAX_HARD_LINE_LEN`. Clauses
+/// are folded between their words. Encoded words are never split.
+struct AddressListEncoder {
+    line_len: usize,
+    /// Length after which the lines are folded inside an address or a clause
+    unit_line_len: usize,
+}
+
+impl AddressListEncoder {
+    fn encode(
+        name: &str,
+        value: &str,
+        words: fn(&str) -> Vec<String>,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        let mut encoder = Self {
+            line_len: name.len() + ": ".len(),
+            unit_line_len: MAX_HARD_LINE_LEN,
+        };
+
+        let mailboxes = split_address_list(value);
+        let last = mailboxes.len().saturating_sub(1);
+        for (i, mailbox) in mailboxes.into_iter().enumerate() {
+            let mut words = words(mailbox);
+            if i != last {
+                if let Some(word) = words.last_mut() {
+                    word.push(',');
+                }
+            }
+
+            encoder.write_mailbox(&words, i == 0, f)?;
+        }
+
+        Ok(())
+    }
+
+    /// Encodes a header made of units of words, folding the lines between the units
+    fn encode_units(name: &str, units: &[Vec<String>], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut encoder = Self {
+            line_len: name.len() + ": ".len(),
+            unit_line_len: MAX_LINE_LEN,
+        };
+
+        for (i, words) in units.iter().enumerate() {
+            encoder.write_mailbox(words, i == 0, f)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_mailbox(
+        &mut self,
+        words: &[String],
+        first: bool,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        let len = words.iter().map(String::len).sum::<usize>() + words.len().saturating_sub(1);
+
+        if !first {
+            self.write_space(len, MAX_LINE_LEN, f)?;
+        }
+
+        for (i, word) in words.iter().enumerate() {
+            if i != 0 {
+                self.write_space(word.len(), self.unit_line_len, f)?;
+            }
+
+            f.write_str(word)?;
+            self.line_len += word.len();
+        }
+
+        Ok(())
+    }
+
+    /// Writes a space, or folds the line if the next `len` bytes would make it longer than
+    /// `max_line_len`
+    fn write_space(
+        &mut self,
+        len: usize,
+        max_line_len: usize,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        if self.line_len + " ".len() + len > max_line_len {
+            f.write_str("\r\n ")?;
+            self.line_len = 1;
+        } else {
+            f.write_str(" ")?;
+            self.line_len += 1;
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits an address list on the commas which are outside of quoted strings
+/// and angle brackets
+fn split_address_list(value: &str) -> Vec<&str> {
+    let mut mailboxes = Vec::new();
+    let mut start = 0;
+    let mut quoted = false;
+    let mut escaped = false;
+    let mut angle = false;
+
+    for (i, c) in value.char_indices() {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match c {
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            '<' if !quoted => angle = true,
+            '>' if !quoted => angle = false,
+            ',' if !quoted && !angle => {
+                mailboxes.push(&value[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    mailboxes.push(&value[start..]);
+
+    mailboxes
+        .into_iter()
+        .map(str::trim)
+        .filter(|mailbox| !mailbox.is_empty())
+        .collect()
+}
+
+/// Splits a mailbox into the words which can be separated by a line fold,
+/// encoding its display name if needed
+fn mailbox_words(mailbox: &str) -> Vec<String> {
+    let (name, address) = match mailbox.rfind('<') {
+        Some(i) if mailbox.ends_with('>') => (mailbox[..i].trim(), &mailbox[i..]),
+        _ => ("", mailbox),
+    };
+
+    let mut words = phrase_words(name);
+    words.push(address.to_owned());
+    words
+}
+
+/// Splits a phrase, like a display name, into the words which can be separated
+/// by a line fold, encoding it if needed
+fn phrase_words(phrase: &str) -> Vec<String> {
+    if allowed_str(phrase) {
+        phrase
+            .split(' ')
+            .filter(|word| !word.is_empty())
+            .map(str::to_owned)
+            .collect()
+    } else {
+        let phrase = unquote(phrase);
+        if phrase.contains(
+            &[
+                '(', ')', '<', '>', '[', ']', ':', ';', '@', '\\', ',', '.', '"',
+            ][..],
+        ) {
+            // The whole phrase needs to be encoded, as its words can't be left unquoted
+            encoded_words(&phrase)
+        } else {
+            display_name_words(&phrase)
+        }
+    }
+}
+
+/// Splits an unquoted display name into words, encoding the runs of words
+/// containing non ASCII characters
+fn display_name_words(name: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut to_encode: Vec<&str> = Vec::new();
+
+    for word in name.split(' ').filter(|word| !word.is_empty()) {
+        if allowed_str(word) {
+            if !to_encode.is_empty() {
+                words.extend(encoded_words(&to_encode.join(" ")));
+                to_encode.clear();
+            }
+            words.push(word.to_owned());
+        } else {
+            to_encode.push(word);
+        }
+    }
+    if !to_encode.is_empty() {
+        words.extend(encoded_words(&to_encode.join(" ")));
+    }
+
+    words
+}
+
+/// Encodes `text` into as many encoded words as needed to keep them shorter than a line
+fn encoded_words(text: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut text = text;
+
+    while !text.is_empty() {
+        let mut len = text.len().min(MAX_ENCODED_WORD_TEXT_LEN);
+        // avoid slicing on a char boundary
+        while !text.is_char_boundary(len) {
+            len -= 1;
+        }
+
+        let (chunk, rest) = text.split_at(len);
+        words.push(format!(
+            "{}{}{}",
+            ENCODING_START_PREFIX,
+            base64::encode(chunk),
+            ENCODING_END_SUFFIX
+        ));
+        text = rest;
+    }
+
+    words
+}
+
+/// Removes the quotes and escaping of a quoted string
+fn unquote(s: &str) -> Cow<'_, str> {
+    if s.len() < 2 || !s.starts_with('"') || !s.ends_with('"') {
+        return Cow::Borrowed(s);
+    }
+
+    let mut unquoted = String::with_capacity(s.len());
+    let mut chars = s[1..s.len() - 1].chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => unquoted.extend(chars.next()),
+            c => unquoted.push(c),
+        }
+    }
+
+    Cow::Owned(unquoted)
+}
+
 /// Iterator yielding a string split space by space, but including all space
 /// characters between it and the next word
 struct WordsPlusFillIterator<'a> {
@@ -523,7 +962,7 @@ impl<'a> Iterator for WordsPlusFillIterator<'a> {
             .find(|&(_i, c)| !is_space_like(c))
             .map(|(i, _)| i);
 
-        let word = &self.s[..next_word.unwrap_or_else(|| self.s.len())];
+        let word = &self.s[..next_word.unwrap_or(self.s.len())];
         self.s = &self.s[word.len()..];
         Some(word)
     }
@@ -546,7 +985,81 @@ const fn allowed_char(c: char) -> bool {
 
 #[cfg(test)]
 mod tests {
-    use super::{HeaderName, Headers};
+    use std::time::{Duration, SystemTime};
+
+    use super::{Comments, Header, HeaderName, Headers, Received, Subject};
+
+    #[test]
+    fn headers_order_and_case() {
+        let mut headers = Headers::new();
+        headers.set(Subject::from("Happy new year".to_owned()));
+        headers.insert_raw(HeaderName::new_from_ascii_str("X-Custom"), "1".to_owned());
+        headers.set(Comments::from("First".to_owned()));
+        headers.insert_raw(HeaderName::new_from_ascii_str("subject"), "Hi".to_owned());
+
+        assert_eq!(headers.get_raw("SUBJECT"), Some("Hi"));
+        let names = headers
+            .iter()
+            .map(|(name, _value)| name.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["Subject", "X-Custom", "Comments"]);
+        assert_eq!(
+            headers.to_string(),
+            "Subject: Hi\r\nX-Custom: 1\r\nComments: First\r\n"
+        );
+    }
+
+    #[test]
+    fn headers_line_breaks() {
+        let mut headers = Headers::new();
+        headers.set(Subject::from("Hi\r\nBcc: eve@domain.tld".to_owned()));
+        assert!(headers.has_line_breaks());
+        assert!(!headers.to_string().contains("\r\nBcc"));
+
+        headers.set(Subject::from("Hi".to_owned()));
+        assert!(!headers.has_line_breaks());
+
+        headers.insert_raw(
+            HeaderName::new_from_ascii_str("X-Custom"),
+            "folded\r\n value".to_owned(),
+        );
+        assert!(headers.has_line_breaks());
+        headers.remove_raw("X-Custom");
+        assert!(!headers.has_line_breaks());
+    }
+
+    #[test]
+    fn headers_duplicates() {
+        let date = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+        let mut headers = Headers::new();
+        headers.set(Subject::from("Happy new year".to_owned()));
+        headers.prepend(Received::new(date(0)));
+        headers.prepend(Received::new(date(60)));
+        headers.prepend(Comments::from("Second".to_owned()));
+        headers.prepend(Comments::from("First".to_owned()));
+
+        let received = headers
+            .get_all::<Received>()
+            .map(|received| received.date())
+            .collect::<Vec<_>>();
+        assert_eq!(received, [date(60), date(0)]);
+        assert_eq!(headers.get_all_raw("comments").count(), 2);
+
+        // Singletons are replaced, and only kept once
+        headers.prepend(Subject::from("Hi".to_owned()));
+        headers.push_raw(HeaderName::new_from_ascii_str("Subject"), "Hey".to_owned());
+        assert_eq!(
+            headers.get_all_raw("Subject").collect::<Vec<_>>(),
+            ["Hi", "Hey"]
+        );
+        headers.set(Subject::from("Hello".to_owned()));
+        assert_eq!(
+            headers.get_all_raw("Subject").collect::<Vec<_>>(),
+            ["Hello"]
+        );
+        assert_eq!(headers.iter().next(), Some((&Subject::name(), "Hello")));
+        assert_eq!(headers.get_all_raw("Received").count(), 2);
+    }
 
     #[test]
     fn valid_headername() {
@@ -629,9 +1142,10 @@ mod tests {
         assert_eq!(
             headers.to_string(),
             concat!(
-                "To: Ascii <example@example.com>, John Doe <johndoe@example.com, John Smith \r\n",
-                " <johnsmith@example.com>, Pinco Pallino <pincopallino@example.com>, Jemand \r\n",
-                " <jemand@example.com>, Jean Dupont <jean@example.com>\r\n"
+                "To: Ascii <example@example.com>,\r\n",
+                " John Doe <johndoe@example.com, John Smith <johnsmith@example.com>,\r\n",
+                " Pinco Pallino <pincopallino@example.com>, Jemand <jemand@example.com>,\r\n",
+                " Jean Dupont <jean@example.com>\r\n"
             )
         );
     }
@@ -728,10 +1242,10 @@ mod tests {
         assert_eq!(
             headers.to_string(),
             concat!(
-                "To: =?utf-8?b?8J+MjQ==?= <world@example.com>, =?utf-8?b?8J+mhg==?= \r\n",
-                " Everywhere <ducks@example.com>, =?utf-8?b?0JjQstCw0L3QvtCyIA==?=\r\n",
-                " =?utf-8?b?0JjQstCw0L0g0JjQstCw0L3QvtCy0LjRhw==?= <ivanov@example.com>, \r\n",
-                " =?utf-8?b?SsSBbmlzIELEk3J6acWGxaE=?= <janis@example.com>, \r\n",
+                "To: =?utf-8?b?8J+MjQ==?= <world@example.com>,\r\n",
+                " =?utf-8?b?8J+mhg==?= Everywhere <ducks@example.com>,\r\n",
+                " =?utf-8?b?0JjQstCw0L3QvtCyINCY0LLQsNC9INCY0LLQsNC90L7QstC40Yc=?= <ivanov@example.com>,\r\n",
+                " =?utf-8?b?SsSBbmlzIELEk3J6acWGxaE=?= <janis@example.com>,\r\n",
                 " =?utf-8?b?U2XDoW4gw5MgUnVkYcOt?= <sean@example.com>\r\n"
             )
         );
@@ -791,10 +1305,10 @@ mod tests {
                 "Subject: Hello! This is lettre, and this \r\n",
                 " IsAVeryLongLineDoYouKnowWhatsGoingToHappenIGuessWeAreGoingToFindOut. Ok I \r\n",
                 " guess that's it!\r\n",
-                "To: =?utf-8?b?8J+MjQ==?= <world@example.com>, =?utf-8?b?8J+mhg==?= \r\n",
-                " Everywhere <ducks@example.com>, =?utf-8?b?0JjQstCw0L3QvtCyIA==?=\r\n",
-                " =?utf-8?b?0JjQstCw0L0g0JjQstCw0L3QvtCy0LjRhw==?= <ivanov@example.com>, \r\n",
-                " =?utf-8?b?SsSBbmlzIELEk3J6acWGxaE=?= <janis@example.com>, \r\n",
+                "To: =?utf-8?b?8J+MjQ==?= <world@example.com>,\r\n",
+                " =?utf-8?b?8J+mhg==?= Everywhere <ducks@example.com>,\r\n",
+                " =?utf-8?b?0JjQstCw0L3QvtCyINCY0LLQsNC9INCY0LLQsNC90L7QstC40Yc=?= <ivanov@example.com>,\r\n",
+                " =?utf-8?b?SsSBbmlzIELEk3J6acWGxaE=?= <janis@example.com>,\r\n",
                 " =?utf-8?b?U2XDoW4gw5MgUnVkYcOt?= <sean@example.com>\r\n",
                 "From: Someone <somewhere@example.com>\r\n",
                 "Content-Transfer-Encoding: quoted-printable\r\n",
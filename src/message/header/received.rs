@@ -0,0 +1,344 @@
+use std::{
+    fmt::{self, Display},
+    net::IpAddr,
+    time::SystemTime,
+};
+
+use super::{Date, Header, HeaderName};
+use crate::{Address, BoxError};
+
+/// `Received` trace header, added at the top of a message by each host relaying it
+///
+/// Defined in [RFC5321](https://tools.ietf.org/html/rfc5321#section-4.4). Each clause
+/// is optional, the date is always present. The existing `Received` headers of a
+/// message must be kept in order, the new one being inserted before them with
+/// [`Headers::prepend`](super::Headers::prepend).
+///
+/// ```rust
+/// use std::net::{IpAddr, Ipv4Addr};
+///
+/// use lettre::message::header::{Received, ReceivedProtocol};
+///
+/// let received = Received::now()
+///     .from(
+///         "client.example.com",
+///         Some("client.example.com"),
+///         IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+///     )
+///     .by("relay.example.net")
+///     .with(ReceivedProtocol::Esmtps)
+///     .id("4Fq9xK2Vb1z3")
+///     .for_recipient(&"hei@domain.tld".parse().unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Received {
+    from: Option<String>,
+    by: Option<String>,
+    with: Option<String>,
+    id: Option<String>,
+    for_recipient: Option<String>,
+    date: Date,
+}
+
+impl Received {
+    /// Creates a header without any clause, received at `date`
+    pub fn new(date: SystemTime) -> Self {
+        Self {
+            from: None,
+            by: None,
+            with: None,
+            id: None,
+            for_recipient: None,
+            date: Date::new(date),
+        }
+    }
+
+    /// Creates a header without any clause, received now
+    pub fn now() -> Self {
+        Self::new(SystemTime::now())
+    }
+
+    /// Set the `from` clause, the name the client gave in `EHLO`, followed by the name
+    /// found by reverse DNS lookup, if any, and the IP address of the connection
+    pub fn from(mut self, helo: &str, remote_host: Option<&str>, remote_ip: IpAddr) -> Self {
+        debug_assert!(
+            is_clause_word(helo),
+            "helo shouldn't contain spaces or specials"
+        );
+        let address = match remote_ip {
+            IpAddr::V4(ip) => format!("[{}]", ip),
+            IpAddr::V6(ip) => format!("[IPv6:{}]", ip),
+        };
+        self.from = Some(match remote_host {
+            Some(remote_host) => {
+                debug_assert!(
+                    is_clause_word(remote_host),
+                    "host shouldn't contain specials"
+                );
+                format!("{} ({} {})", helo, remote_host, address)
+            }
+            None => format!("{} ({})", helo, address),
+        });
+        self
+    }
+
+    /// Set the `by` clause, the name of the host which received the message
+    pub fn by(mut self, host: &str) -> Self {
+        debug_assert!(
+            is_clause_word(host),
+            "host shouldn't contain spaces or specials"
+        );
+        self.by = Some(host.to_owned());
+        self
+    }
+
+    /// Set the `with` clause, the protocol the message was received with
+    pub fn with(mut self, protocol: ReceivedProtocol) -> Self {
+        self.with = Some(protocol.to_string());
+        self
+    }
+
+    /// Set the `id` clause, the identifier of the message in the queue of the host
+    pub fn id(mut self, id: &str) -> Self {
+        debug_assert!(
+            is_clause_word(id),
+            "id shouldn't contain spaces or specials"
+        );
+        self.id = Some(id.to_owned());
+        self
+    }
+
+    /// Set the `for` clause, the recipient the message was received for
+    ///
+    /// Should only be used for messages with a single recipient, not to disclose the
+    /// other ones.
+    pub fn for_recipient(mut self, address: &Address) -> Self {
+        self.for_recipient = Some(format!("<{}>", address));
+        self
+    }
+
+    /// When the message was received
+    pub fn date(&self) -> SystemTime {
+        self.date.into()
+    }
+}
+
+impl Header for Received {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("Received")
+    }
+
+    fn parse(s: &str) -> Result<Self, BoxError> {
+        let separator = s.rfind(';').ok_or("missing date in Received header")?;
+        let date = Date::parse(s[separator + 1..].trim())?;
+
+        let mut received = Self::new(date.into());
+        for unit in trace_units(&s[..separator]) {
+            let (keyword, words) = match unit.split_first() {
+                Some(clause) => clause,
+                None => continue,
+            };
+            let clause = match keyword.to_ascii_lowercase().as_str() {
+                "from" => &mut received.from,
+                "by" => &mut received.by,
+                "with" => &mut received.with,
+                "id" => &mut received.id,
+                "for" => &mut received.for_recipient,
+                // `via` is obsolete
+                _ => continue,
+            };
+            *clause = Some(words.join(" "));
+        }
+        Ok(received)
+    }
+
+    fn display(&self) -> String {
+        let clauses = [
+            ("from", &self.from),
+            ("by", &self.by),
+            ("with", &self.with),
+            ("id", &self.id),
+            ("for", &self.for_recipient),
+        ];
+
+        let mut value = String::new();
+        for (keyword, clause) in clauses.iter() {
+            if let Some(clause) = clause {
+                value.push_str(keyword);
+                value.push(' ');
+                value.push_str(clause);
+                value.push(' ');
+            }
+        }
+        value.pop();
+        value.push_str("; ");
+        value.push_str(&self.date.display());
+        value
+    }
+}
+
+/// Protocol the message was received with, in the `with` clause of a [`Received`] header
+///
+/// Defined in [RFC3848](https://tools.ietf.org/html/rfc3848).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceivedProtocol {
+    /// SMTP, without any extension
+    Smtp,
+    /// SMTP with extensions, after `EHLO`
+    Esmtp,
+    /// ESMTP with `STARTTLS` or over TLS
+    Esmtps,
+    /// ESMTP with `AUTH`
+    Esmtpa,
+    /// ESMTP with both TLS and `AUTH`
+    Esmtpsa,
+    /// LMTP
+    Lmtp,
+}
+
+impl Display for ReceivedProtocol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReceivedProtocol::Smtp => "SMTP",
+            ReceivedProtocol::Esmtp => "ESMTP",
+            ReceivedProtocol::Esmtps => "ESMTPS",
+            ReceivedProtocol::Esmtpa => "ESMTPA",
+            ReceivedProtocol::Esmtpsa => "ESMTPSA",
+            ReceivedProtocol::Lmtp => "LMTP",
+        })
+    }
+}
+
+/// Splits the value of a trace header into its clauses and its date, each split into
+/// the words which can be separated by a line fold
+pub(super) fn trace_units(value: &str) -> Vec<Vec<String>> {
+    let (clauses, date) = match value.rfind(';') {
+        Some(separator) => (&value[..=separator], Some(&value[separator + 1..])),
+        None => (value, None),
+    };
+
+    let mut units: Vec<Vec<String>> = Vec::new();
+    let mut depth = 0_usize;
+    for word in clauses.split_whitespace() {
+        let keyword = depth == 0 && CLAUSES.contains(&word.to_ascii_lowercase().as_str());
+        depth += word.matches('(').count();
+        depth = depth.saturating_sub(word.matches(')').count());
+        match units.last_mut() {
+            Some(unit) if !keyword => unit.push(word.to_owned()),
+            _ => units.push(vec![word.to_owned()]),
+        }
+    }
+    if let Some(date) = date {
+        units.push(date.split_whitespace().map(str::to_owned).collect());
+    }
+    units
+}
+
+/// Keywords starting the clauses of a `Received` header
+const CLAUSES: &[&str] = &["from", "by", "via", "with", "id", "for"];
+
+/// Checks that `s` can be used as a clause value, a domain, an address literal or an atom
+fn is_clause_word(s: &str) -> bool {
+    !s.is_empty()
+        && s.chars()
+            .all(|c| c.is_ascii_graphic() && !"()<>;@\\,\"".contains(c))
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        net::{IpAddr, Ipv4Addr, Ipv6Addr},
+        time::{Duration, SystemTime},
+    };
+
+    use super::{Received, ReceivedProtocol};
+    use crate::message::header::{HeaderName, Headers};
+
+    fn date() -> SystemTime {
+        // Tue, 15 Nov 1994 08:12:31 GMT
+        SystemTime::UNIX_EPOCH + Duration::from_secs(784887151)
+    }
+
+    fn received() -> Received {
+        Received::new(date())
+            .from(
+                "client.example.com",
+                Some("mail-client.example.com"),
+                IpAddr::V4(Ipv4Addr::new(192, 0, 2, 1)),
+            )
+            .by("relay.example.net")
+            .with(ReceivedProtocol::Esmtps)
+            .id("4Fq9xK2Vb1z3")
+            .for_recipient(&"hei@domain.tld".parse().unwrap())
+    }
+
+    #[test]
+    fn format_received() {
+        let mut headers = Headers::new();
+        headers.set(received());
+
+        assert_eq!(
+            headers.to_string(),
+            concat!(
+                "Received: from client.example.com (mail-client.example.com [192.0.2.1])\r\n",
+                " by relay.example.net with ESMTPS id 4Fq9xK2Vb1z3 for <hei@domain.tld>;\r\n",
+                " Tue, 15 Nov 1994 08:12:31 -0000\r\n",
+            )
+        );
+    }
+
+    #[test]
+    fn format_received_partial() {
+        let mut headers = Headers::new();
+        headers.set(
+            Received::new(date())
+                .from(
+                    "[::1]",
+                    None,
+                    IpAddr::V6(Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1)),
+                )
+                .with(ReceivedProtocol::Smtp),
+        );
+        assert_eq!(
+            headers.to_string(),
+            concat!(
+                "Received: from [::1] ([IPv6:2001:db8::1]) with SMTP;\r\n",
+                " Tue, 15 Nov 1994 08:12:31 -0000\r\n",
+            )
+        );
+
+        headers.set(Received::new(date()));
+        assert_eq!(
+            headers.to_string(),
+            "Received: ; Tue, 15 Nov 1994 08:12:31 -0000\r\n"
+        );
+    }
+
+    #[test]
+    fn parse_received() {
+        let mut headers = Headers::new();
+        headers.insert_raw(
+            HeaderName::new_from_ascii_str("Received"),
+            concat!(
+                "from client.example.com (mail-client.example.com [192.0.2.1])\r\n",
+                " by relay.example.net with ESMTPS id 4Fq9xK2Vb1z3 for <hei@domain.tld>;\r\n",
+                " Tue, 15 Nov 1994 08:12:31 -0000",
+            )
+            .to_string(),
+        );
+        assert_eq!(headers.get::<Received>(), Some(received()));
+
+        headers.insert_raw(
+            HeaderName::new_from_ascii_str("Received"),
+            "by relay.example.net (Postfix, from userid 1000) via HTTP id 12AB; Tue, 15 Nov 1994 08:12:31 -0000".to_string(),
+        );
+        let received = headers.get::<Received>().unwrap();
+        assert_eq!(
+            received.by.as_deref(),
+            Some("relay.example.net (Postfix, from userid 1000)")
+        );
+        assert_eq!(received.from, None);
+        assert_eq!(received.id.as_deref(), Some("12AB"));
+        assert_eq!(received.date(), date());
+    }
+}
@@ -0,0 +1,107 @@
+use super::{Header, HeaderName};
+use crate::{Address, BoxError};
+
+/// `Return-Path` trace header, the reverse-path of the envelope a message was delivered with
+///
+/// Defined in [RFC5321](https://tools.ietf.org/html/rfc5321#section-4.4). It's added
+/// by the server making the final delivery of the message, and must not be sent by a
+/// client submitting it, which is why the SMTP transports strip it by default. It's
+/// set explicitly for local deliveries, like with the file transport.
+///
+/// The reverse-path is empty for messages which must not trigger a bounce, like
+/// delivery status notifications, and is then formatted as `<>`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ReturnPath(Option<Address>);
+
+impl ReturnPath {
+    /// Creates a `Return-Path` header with `address` as reverse-path
+    pub fn new(address: Address) -> Self {
+        Self(Some(address))
+    }
+
+    /// Creates a `Return-Path` header with an empty reverse-path, `<>`
+    pub fn null() -> Self {
+        Self(None)
+    }
+
+    /// The reverse-path, `None` if it's empty
+    pub fn address(&self) -> Option<&Address> {
+        self.0.as_ref()
+    }
+}
+
+impl Header for ReturnPath {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("Return-Path")
+    }
+
+    fn parse(s: &str) -> Result<Self, BoxError> {
+        let s = s.trim();
+        let path = s
+            .strip_prefix('<')
+            .and_then(|s| s.strip_suffix('>'))
+            .ok_or("Return-Path must be enclosed in angle brackets")?
+            .trim();
+
+        if path.is_empty() {
+            Ok(Self::null())
+        } else {
+            Ok(Self::new(path.parse()?))
+        }
+    }
+
+    fn display(&self) -> String {
+        match &self.0 {
+            Some(address) => format!("<{}>", address),
+            None => "<>".to_owned(),
+        }
+    }
+}
+
+impl From<Address> for ReturnPath {
+    #[inline]
+    fn from(address: Address) -> Self {
+        Self::new(address)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ReturnPath;
+    use crate::message::header::{HeaderName, Headers};
+
+    #[test]
+    fn format_return_path() {
+        let mut headers = Headers::new();
+        headers.set(ReturnPath::new("bounces@domain.tld".parse().unwrap()));
+        assert_eq!(headers.to_string(), "Return-Path: <bounces@domain.tld>\r\n");
+
+        headers.set(ReturnPath::null());
+        assert_eq!(headers.to_string(), "Return-Path: <>\r\n");
+    }
+
+    #[test]
+    fn parse_return_path() {
+        let mut headers = Headers::new();
+        headers.insert_raw(
+            HeaderName::new_from_ascii_str("Return-Path"),
+            " <bounces@domain.tld>".to_string(),
+        );
+        assert_eq!(
+            headers.get::<ReturnPath>(),
+            Some(ReturnPath::new("bounces@domain.tld".parse().unwrap()))
+        );
+
+        headers.insert_raw(
+            HeaderName::new_from_ascii_str("Return-Path"),
+            "<>".to_string(),
+        );
+        assert_eq!(headers.get::<ReturnPath>(), Some(ReturnPath::null()));
+
+        headers.insert_raw(
+            HeaderName::new_from_ascii_str("Return-Path"),
+            "bounces@domain.tld".to_string(),
+        );
+        assert_eq!(headers.get::<ReturnPath>(), None);
+    }
+}
@@ -1,3 +1,5 @@
+use std::ops::{BitOr, BitOrAssign};
+
 use crate::{
     message::header::{Header, HeaderName},
     BoxError,
@@ -61,9 +63,273 @@ impl Default for MimeVersion {
     }
 }
 
+/// Maximum number of fields of a `Feedback-ID`, including the sender identifier
+const FEEDBACK_ID_MAX_FIELDS: usize = 4;
+
+/// Maximum length of a `Feedback-ID`, which can't be folded as it has no spaces, so
+/// that the header fits on a 998 octets line
+const FEEDBACK_ID_MAX_LEN: usize = 998 - "Feedback-ID: ".len();
+
+/// `Feedback-ID` header, used by Gmail to report feedback loop statistics
+///
+/// Made of up to three identifiers, like a campaign or a customer, followed by the
+/// identifier of the sender, separated by colons: `Campaign:Customer:MailType:SenderId`.
+/// The fields are made of printable ASCII characters, and the sender identifier is
+/// 5 to 15 characters long. The omitted identifiers are left empty, to keep the
+/// position of the following ones.
+///
+/// See the [Postmaster Tools documentation](https://support.google.com/mail/answer/6254652).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FeedbackId(String);
+
+impl FeedbackId {
+    /// Assembles a `Feedback-ID` from the optional campaign, customer and mail type
+    /// identifiers, and the identifier of the sender
+    ///
+    /// The identifiers are always at the same position, the omitted ones being left
+    /// empty. A given identifier can't be empty, omit it with `None` instead.
+    ///
+    /// ```rust
+    /// use lettre::message::header::{FeedbackId, Header};
+    ///
+    /// let feedback_id = FeedbackId::new(Some("newyear"), None, Some("promo"), "lettre1")?;
+    /// assert_eq!(feedback_id.display(), "newyear::promo:lettre1");
+    /// # Ok::<(), Box<dyn std::error::Error + Send + Sync>>(())
+    /// ```
+    pub fn new(
+        campaign: Option<&str>,
+        customer: Option<&str>,
+        mail_type: Option<&str>,
+        sender_id: &str,
+    ) -> Result<Self, BoxError> {
+        if [campaign, customer, mail_type].contains(&Some("")) {
+            return Err(String::from("a Feedback-ID identifier can't be empty").into());
+        }
+        Self::parse(
+            &[
+                campaign.unwrap_or_default(),
+                customer.unwrap_or_default(),
+                mail_type.unwrap_or_default(),
+                sender_id,
+            ]
+            .join(":"),
+        )
+    }
+
+    /// Returns the identifier of the sender
+    pub fn sender_id(&self) -> &str {
+        self.0
+            .rsplit(':')
+            .next()
+            .expect("The first call to next for a RSplit<char> always succeeds")
+    }
+
+    /// Returns the identifiers preceding the sender identifier, including the empty ones
+    pub fn identifiers(&self) -> impl Iterator<Item = &str> {
+        let mut fields = self.0.split(':');
+        fields.next_back();
+        fields
+    }
+}
+
+impl Header for FeedbackId {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("Feedback-ID")
+    }
+
+    fn parse(s: &str) -> Result<Self, BoxError> {
+        let fields = s.split(':').collect::<Vec<_>>();
+        if fields.len() > FEEDBACK_ID_MAX_FIELDS {
+            return Err(format!(
+                "Feedback-ID can't have more than {} fields",
+                FEEDBACK_ID_MAX_FIELDS
+            )
+            .into());
+        }
+        if s.len() > FEEDBACK_ID_MAX_LEN {
+            return Err(format!(
+                "Feedback-ID can't be longer than {} characters",
+                FEEDBACK_ID_MAX_LEN
+            )
+            .into());
+        }
+        if let Some(field) = fields
+            .iter()
+            .find(|field| !field.bytes().all(|b| b.is_ascii_graphic()))
+        {
+            return Err(format!("invalid Feedback-ID field: {:?}", field).into());
+        }
+
+        let sender_id = fields[fields.len() - 1];
+        if !(5..=15).contains(&sender_id.len()) {
+            return Err(
+                String::from("the Feedback-ID sender id must be 5 to 15 characters long").into(),
+            );
+        }
+        Ok(FeedbackId(s.to_owned()))
+    }
+
+    fn display(&self) -> String {
+        self.0.clone()
+    }
+}
+
+/// `X-Auto-Response-Suppress` header, the automatic replies Exchange shouldn't send
+///
+/// A set of the kinds of automatic replies, combined with `|`. It is formatted in a
+/// canonical order, as `All` when it has all the kinds, and as `None` when it is empty.
+///
+/// ```rust
+/// use lettre::message::header::{AutoResponseSuppress, Header};
+///
+/// let suppress = AutoResponseSuppress::OOF | AutoResponseSuppress::AUTO_REPLY;
+/// assert!(suppress.contains(AutoResponseSuppress::OOF));
+/// assert_eq!(suppress.display(), "OOF, AutoReply");
+/// ```
+///
+/// Defined in the MS-OXCMAIL specification of Exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct AutoResponseSuppress(u8);
+
+/// The keywords of the kinds of automatic replies, in their canonical order
+const AUTO_RESPONSE_SUPPRESS_KEYWORDS: [(&str, AutoResponseSuppress); 6] = [
+    ("DR", AutoResponseSuppress::DR),
+    ("NDR", AutoResponseSuppress::NDR),
+    ("RN", AutoResponseSuppress::RN),
+    ("NRN", AutoResponseSuppress::NRN),
+    ("OOF", AutoResponseSuppress::OOF),
+    ("AutoReply", AutoResponseSuppress::AUTO_REPLY),
+];
+
+impl AutoResponseSuppress {
+    /// No automatic reply is suppressed
+    pub const NONE: Self = AutoResponseSuppress(0);
+    /// Delivery reports
+    pub const DR: Self = AutoResponseSuppress(1);
+    /// Non-delivery reports
+    pub const NDR: Self = AutoResponseSuppress(1 << 1);
+    /// Read notifications
+    pub const RN: Self = AutoResponseSuppress(1 << 2);
+    /// Not read notifications
+    pub const NRN: Self = AutoResponseSuppress(1 << 3);
+    /// Out of office replies
+    pub const OOF: Self = AutoResponseSuppress(1 << 4);
+    /// Automatic replies other than out of office ones
+    pub const AUTO_REPLY: Self = AutoResponseSuppress(1 << 5);
+    /// All the automatic replies
+    pub const ALL: Self = AutoResponseSuppress((1 << 6) - 1);
+
+    /// Whether all the automatic replies of `other` are suppressed
+    pub const fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether no automatic reply is suppressed
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl BitOr for AutoResponseSuppress {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        AutoResponseSuppress(self.0 | rhs.0)
+    }
+}
+
+impl BitOrAssign for AutoResponseSuppress {
+    fn bitor_assign(&mut self, rhs: Self) {
+        self.0 |= rhs.0;
+    }
+}
+
+impl Header for AutoResponseSuppress {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("X-Auto-Response-Suppress")
+    }
+
+    fn parse(s: &str) -> Result<Self, BoxError> {
+        let mut suppress = AutoResponseSuppress::NONE;
+        for keyword in s.split(',').map(str::trim) {
+            if keyword.eq_ignore_ascii_case("All") {
+                suppress = AutoResponseSuppress::ALL;
+            } else if !keyword.eq_ignore_ascii_case("None") {
+                suppress |= AUTO_RESPONSE_SUPPRESS_KEYWORDS
+                    .iter()
+                    .find(|(keyword_, _kind)| keyword.eq_ignore_ascii_case(keyword_))
+                    .map(|(_keyword, kind)| *kind)
+                    .ok_or_else(|| {
+                        format!("unknown X-Auto-Response-Suppress value: {:?}", keyword)
+                    })?;
+            }
+        }
+        Ok(suppress)
+    }
+
+    fn display(&self) -> String {
+        match *self {
+            AutoResponseSuppress::NONE => "None".to_owned(),
+            AutoResponseSuppress::ALL => "All".to_owned(),
+            suppress => AUTO_RESPONSE_SUPPRESS_KEYWORDS
+                .iter()
+                .filter(|(_keyword, kind)| suppress.contains(*kind))
+                .map(|(keyword, _kind)| *keyword)
+                .collect::<Vec<_>>()
+                .join(", "),
+        }
+    }
+}
+
+/// `Precedence` header, telling automatic responders like vacation responses not to
+/// reply to bulk and mailing list messages
+///
+/// The value isn't standardized, [RFC3834](https://tools.ietf.org/html/rfc3834#section-2)
+/// describes its common use. The values are compared case-insensitively when parsing,
+/// and the unknown ones are kept as [`Precedence::Other`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Precedence {
+    /// Bulk message, like a newsletter
+    Bulk,
+    /// Message sent through a mailing list
+    List,
+    /// Message of no importance
+    Junk,
+    /// Any other value
+    Other(String),
+}
+
+impl Header for Precedence {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("Precedence")
+    }
+
+    fn parse(s: &str) -> Result<Self, BoxError> {
+        let s = s.trim();
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "bulk" => Precedence::Bulk,
+            "list" => Precedence::List,
+            "junk" => Precedence::Junk,
+            _ => Precedence::Other(s.to_owned()),
+        })
+    }
+
+    fn display(&self) -> String {
+        match self {
+            Precedence::Bulk => "bulk",
+            Precedence::List => "list",
+            Precedence::Junk => "junk",
+            Precedence::Other(value) => value,
+        }
+        .to_owned()
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::{MimeVersion, MIME_VERSION_1_0};
+    use super::{
+        AutoResponseSuppress, FeedbackId, Header, MimeVersion, Precedence, MIME_VERSION_1_0,
+    };
     use crate::message::header::{HeaderName, Headers};
 
     #[test]
@@ -97,4 +363,132 @@ mod test {
 
         assert_eq!(headers.get::<MimeVersion>(), Some(MimeVersion::new(0, 1)));
     }
+
+    #[test]
+    fn format_feedback_id() {
+        let mut headers = Headers::new();
+
+        headers.set(
+            FeedbackId::new(
+                Some("newsletter"),
+                Some("customer42"),
+                Some("promo"),
+                "lettre1",
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(
+            headers.to_string(),
+            "Feedback-ID: newsletter:customer42:promo:lettre1\r\n"
+        );
+
+        let feedback_id = headers.get::<FeedbackId>().unwrap();
+        assert_eq!(feedback_id.sender_id(), "lettre1");
+        assert_eq!(
+            feedback_id.identifiers().collect::<Vec<_>>(),
+            ["newsletter", "customer42", "promo"]
+        );
+
+        let feedback_id = FeedbackId::parse("lettre1").unwrap();
+        assert_eq!(feedback_id.display(), "lettre1");
+        assert_eq!(feedback_id.identifiers().count(), 0);
+    }
+
+    #[test]
+    fn feedback_id_omitted_identifiers() {
+        let feedback_id = FeedbackId::new(None, Some("customer42"), None, "lettre1").unwrap();
+        assert_eq!(feedback_id.display(), ":customer42::lettre1");
+        assert_eq!(
+            feedback_id.identifiers().collect::<Vec<_>>(),
+            ["", "customer42", ""]
+        );
+        assert_eq!(feedback_id.sender_id(), "lettre1");
+        assert_eq!(
+            FeedbackId::parse(":customer42::lettre1").unwrap(),
+            feedback_id
+        );
+
+        let feedback_id = FeedbackId::new(None, None, None, "lettre1").unwrap();
+        assert_eq!(feedback_id.display(), ":::lettre1");
+    }
+
+    #[test]
+    fn invalid_feedback_id() {
+        // Too many fields
+        assert!(FeedbackId::parse("a:b:c:d:lettre1").is_err());
+        assert!(FeedbackId::new(Some("a:b"), Some("c"), Some("d"), "lettre1").is_err());
+        // Empty field
+        assert!(FeedbackId::new(Some(""), None, None, "lettre1").is_err());
+        assert!(FeedbackId::new(Some("newsletter"), None, None, "").is_err());
+        // Invalid characters
+        assert!(FeedbackId::new(Some("news letter"), None, None, "lettre1").is_err());
+        assert!(FeedbackId::new(Some("campagne-été"), None, None, "lettre1").is_err());
+        // Sender id length
+        assert!(FeedbackId::new(None, None, None, "abcd").is_err());
+        assert!(FeedbackId::new(None, None, None, "abcdefghijklmnop").is_err());
+        assert!(FeedbackId::parse("").is_err());
+        // Total length
+        assert!(FeedbackId::new(Some(&"a".repeat(985)), None, None, "lettre1").is_err());
+    }
+
+    #[test]
+    fn format_auto_response_suppress() {
+        let mut headers = Headers::new();
+
+        headers.set(AutoResponseSuppress::AUTO_REPLY | AutoResponseSuppress::OOF);
+        assert_eq!(
+            headers.to_string(),
+            "X-Auto-Response-Suppress: OOF, AutoReply\r\n"
+        );
+
+        assert_eq!(AutoResponseSuppress::ALL.display(), "All");
+        assert_eq!(AutoResponseSuppress::NONE.display(), "None");
+        assert_eq!(
+            (AutoResponseSuppress::DR
+                | AutoResponseSuppress::NDR
+                | AutoResponseSuppress::RN
+                | AutoResponseSuppress::NRN
+                | AutoResponseSuppress::OOF
+                | AutoResponseSuppress::AUTO_REPLY)
+                .display(),
+            "All"
+        );
+    }
+
+    #[test]
+    fn parse_auto_response_suppress() {
+        assert_eq!(
+            AutoResponseSuppress::parse("oof, AutoReply").unwrap(),
+            AutoResponseSuppress::OOF | AutoResponseSuppress::AUTO_REPLY
+        );
+        assert_eq!(
+            AutoResponseSuppress::parse("All").unwrap(),
+            AutoResponseSuppress::ALL
+        );
+        assert!(AutoResponseSuppress::parse("None").unwrap().is_empty());
+        assert!(AutoResponseSuppress::parse("OOF, Holidays").is_err());
+    }
+
+    #[test]
+    fn format_parse_precedence() {
+        let mut headers = Headers::new();
+
+        headers.set(Precedence::Bulk);
+        assert_eq!(headers.to_string(), "Precedence: bulk\r\n");
+
+        headers.insert_raw(
+            HeaderName::new_from_ascii_str("Precedence"),
+            " LIST".to_string(),
+        );
+        assert_eq!(headers.get::<Precedence>(), Some(Precedence::List));
+
+        headers.insert_raw(
+            HeaderName::new_from_ascii_str("Precedence"),
+            "first-class".to_string(),
+        );
+        let precedence = headers.get::<Precedence>().unwrap();
+        assert_eq!(precedence, Precedence::Other("first-class".to_owned()));
+        assert_eq!(precedence.display(), "first-class");
+    }
 }
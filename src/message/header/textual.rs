@@ -1,18 +1,47 @@
-use super::{Header, HeaderName};
+use std::iter::FromIterator;
+
+use super::{split_address_list, unquote, Header, HeaderName};
 use crate::BoxError;
 
+/// Defines a header holding free text, like `Subject`
+///
+/// Creates a `pub struct` wrapping a [`String`], implementing
+/// [`Header`](crate::message::header::Header), `From<String>` and `AsRef<str>`.
+/// The value is encoded when it contains non ASCII characters, and folded when it's
+/// too long, like the built-in headers.
+///
+/// ```rust
+/// use lettre::message::header::Headers;
+///
+/// lettre::text_header!(
+///     /// `X-Org-Trace` header, the identifier of the request which sent the message
+///     Header(XOrgTrace, "X-Org-Trace")
+/// );
+///
+/// let mut headers = Headers::new();
+/// headers.set(XOrgTrace::from(String::from("4bf92f35")));
+/// assert_eq!(headers.to_string(), "X-Org-Trace: 4bf92f35\r\n");
+/// assert_eq!(headers.get::<XOrgTrace>().unwrap().as_ref(), "4bf92f35");
+/// ```
+#[macro_export]
+#[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
 macro_rules! text_header {
     ($(#[$attr:meta])* Header($type_name: ident, $header_name: expr )) => {
         $(#[$attr])*
         #[derive(Debug, Clone, PartialEq)]
         pub struct $type_name(String);
 
-        impl Header for $type_name {
-            fn name() -> HeaderName {
-                HeaderName::new_from_ascii_str($header_name)
+        impl $crate::message::header::Header for $type_name {
+            fn name() -> $crate::message::header::HeaderName {
+                $crate::message::header::HeaderName::new_from_ascii_str($header_name)
             }
 
-            fn parse(s: &str) -> Result<Self, BoxError> {
+            fn parse(
+                s: &str,
+            ) -> ::std::result::Result<
+                Self,
+                ::std::boxed::Box<dyn ::std::error::Error + Send + Sync>,
+            > {
                 Ok(Self(s.into()))
             }
 
@@ -37,6 +66,72 @@ macro_rules! text_header {
     };
 }
 
+/// Defines a header taking one of a fixed set of keywords
+///
+/// Creates a `pub enum` with a variant for each keyword, implementing
+/// [`Header`](crate::message::header::Header). The keywords are compared
+/// case-insensitively when parsing, and the other values are rejected.
+///
+/// ```rust
+/// use lettre::message::header::Headers;
+///
+/// lettre::enum_header!(
+///     /// `X-Env` header, the environment which sent the message
+///     Header(XEnv, "X-Env") {
+///         /// Sent from production
+///         Production => "production",
+///         /// Sent from staging
+///         Staging => "staging",
+///     }
+/// );
+///
+/// let mut headers = Headers::new();
+/// headers.set(XEnv::Staging);
+/// assert_eq!(headers.to_string(), "X-Env: staging\r\n");
+/// assert_eq!(headers.get::<XEnv>(), Some(XEnv::Staging));
+/// ```
+#[macro_export]
+#[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+macro_rules! enum_header {
+    ($(#[$attr:meta])* Header($type_name: ident, $header_name: expr ) {
+        $($(#[$variant_attr:meta])* $variant: ident => $value: expr),+ $(,)?
+    }) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum $type_name {
+            $($(#[$variant_attr])* $variant,)+
+        }
+
+        impl $crate::message::header::Header for $type_name {
+            fn name() -> $crate::message::header::HeaderName {
+                $crate::message::header::HeaderName::new_from_ascii_str($header_name)
+            }
+
+            fn parse(
+                s: &str,
+            ) -> ::std::result::Result<
+                Self,
+                ::std::boxed::Box<dyn ::std::error::Error + Send + Sync>,
+            > {
+                let s = s.trim();
+                $(
+                    if s.eq_ignore_ascii_case($value) {
+                        return Ok(Self::$variant);
+                    }
+                )+
+                Err(format!("unknown {} value: {:?}", $header_name, s).into())
+            }
+
+            fn display(&self) -> String {
+                match self {
+                    $(Self::$variant => $value,)+
+                }
+                .to_owned()
+            }
+        }
+    };
+}
+
 text_header!(
     /// `Subject` of the message, defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.5)
     Header(Subject, "Subject")
@@ -46,9 +141,9 @@ text_header!(
     Header(Comments, "Comments")
 );
 text_header!(
-    /// `Keywords` header. Should contain a comma-separated list of one or more
-    /// words or quoted-strings, defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.5)
-    Header(Keywords, "Keywords")
+    /// `Organization` the sender of the message belongs to, registered in
+    /// [RFC4021](https://tools.ietf.org/html/rfc4021)
+    Header(Organization, "Organization")
 );
 text_header!(
     /// `In-Reply-To` header. Contains one or more
@@ -67,6 +162,11 @@ text_header!(
     /// defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.4)
     Header(MessageId, "Message-ID")
 );
+text_header!(
+    /// `Resent-Message-ID` header. Contains a unique identifier of the resent message,
+    /// defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.6)
+    Header(ResentMessageId, "Resent-Message-ID")
+);
 text_header!(
     /// `User-Agent` header. Contains information about the client,
     /// defined in [draft-melnikov-email-user-agent-00](https://tools.ietf.org/html/draft-melnikov-email-user-agent-00#section-3)
@@ -78,14 +178,151 @@ text_header! {
     Header(ContentId, "Content-ID")
 }
 text_header! {
-    /// `Content-Location` header,
-    /// defined in [RFC2110](https://tools.ietf.org/html/rfc2110#section-4.3)
-    Header(ContentLocation, "Content-Location")
+    /// `Content-Description` header, a short description of the content of a part,
+    /// defined in [RFC2045](https://tools.ietf.org/html/rfc2045#section-8)
+    Header(ContentDescription, "Content-Description")
+}
+enum_header! {
+    /// `Sensitivity` header, how private the message is, defined in
+    /// [RFC2156](https://tools.ietf.org/html/rfc2156) and honored by Exchange
+    Header(Sensitivity, "Sensitivity") {
+        /// Personal message
+        Personal => "Personal",
+        /// Private message, which can't be forwarded or changed in Exchange
+        Private => "Private",
+        /// Confidential to the company of the sender
+        CompanyConfidential => "Company-Confidential",
+    }
+}
+enum_header! {
+    /// `Auto-Submitted` header, telling whether the message was sent automatically,
+    /// defined in [RFC3834](https://tools.ietf.org/html/rfc3834#section-5)
+    Header(AutoSubmitted, "Auto-Submitted") {
+        /// Sent by a human
+        No => "no",
+        /// Generated automatically, like a notification or a newsletter
+        AutoGenerated => "auto-generated",
+        /// Automatic reply to another message, like a vacation response
+        AutoReplied => "auto-replied",
+    }
+}
+
+/// `Keywords` header. Contains a comma-separated list of one or more words or phrases,
+/// defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.5)
+///
+/// The keywords containing commas or other special characters are quoted, and the
+/// ones containing non ASCII characters are encoded.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Keywords(Vec<String>);
+
+impl Keywords {
+    /// Creates a `Keywords` header from a list of keywords
+    pub fn new<I, T>(keywords: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        Self(keywords.into_iter().map(Into::into).collect())
+    }
+
+    /// Adds a keyword
+    pub fn push<T: Into<String>>(&mut self, keyword: T) {
+        self.0.push(keyword.into());
+    }
+
+    /// Iterates over the keywords
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+
+    /// Returns the number of keywords
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Returns `true` if there are no keywords
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Splits a comma-separated list of keywords, unquoting them
+    fn from_list(list: &str) -> Self {
+        Self(
+            split_address_list(list)
+                .into_iter()
+                .map(|keyword| unquote(keyword).into_owned())
+                .collect(),
+        )
+    }
+}
+
+impl Header for Keywords {
+    fn name() -> HeaderName {
+        HeaderName::new_from_ascii_str("Keywords")
+    }
+
+    fn parse(s: &str) -> Result<Self, BoxError> {
+        Ok(Self::from_list(s))
+    }
+
+    fn display(&self) -> String {
+        let keywords: Vec<String> = self
+            .0
+            .iter()
+            .map(|keyword| keyword.trim())
+            .filter(|keyword| !keyword.is_empty())
+            .map(quote_phrase)
+            .collect();
+        keywords.join(", ")
+    }
+}
+
+impl<T: Into<String>> FromIterator<T> for Keywords {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        Self::new(iter)
+    }
+}
+
+/// Splits a comma-separated list of keywords, like `"lettre, \"a, b\""`
+impl From<String> for Keywords {
+    #[inline]
+    fn from(list: String) -> Self {
+        Self::from_list(&list)
+    }
+}
+
+impl From<Vec<String>> for Keywords {
+    #[inline]
+    fn from(keywords: Vec<String>) -> Self {
+        Self(keywords)
+    }
+}
+
+/// Quotes a phrase if it contains special characters
+fn quote_phrase(phrase: &str) -> String {
+    const SPECIALS: &[char] = &[
+        '(', ')', '<', '>', '[', ']', ':', ';', '@', '\\', ',', '.', '"',
+    ];
+
+    if !phrase.contains(SPECIALS) {
+        return phrase.to_owned();
+    }
+
+    let mut quoted = String::with_capacity(phrase.len() + 2);
+    quoted.push('"');
+    for c in phrase.chars() {
+        if c == '"' || c == '\\' {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
 }
 
 #[cfg(test)]
 mod test {
-    use super::Subject;
+    use super::{Comments, ContentDescription, Keywords, Organization, Sensitivity, Subject};
     use crate::message::header::{HeaderName, Headers};
 
     #[test]
@@ -120,4 +357,152 @@ mod test {
             Some(Subject("Sample subject".into()))
         );
     }
+
+    #[test]
+    fn format_comments_utf8() {
+        let mut headers = Headers::new();
+        headers.set(Comments("Archivé le lundi".into()));
+
+        assert_eq!(
+            headers.to_string(),
+            "Comments: =?utf-8?b?QXJjaGl2w6k=?= le lundi\r\n"
+        );
+    }
+
+    #[test]
+    fn format_parse_organization() {
+        let mut headers = Headers::new();
+        headers.set(Organization("Société Générale des Lettres".into()));
+        assert_eq!(
+            headers.to_string(),
+            "Organization: =?utf-8?b?U29jacOpdMOpIEfDqW7DqXJhbGU=?= des Lettres\r\n"
+        );
+
+        headers.insert_raw(
+            HeaderName::new_from_ascii_str("Organization"),
+            "Example Corp.".to_string(),
+        );
+        assert_eq!(
+            headers.get::<Organization>(),
+            Some(Organization("Example Corp.".into()))
+        );
+    }
+
+    #[test]
+    fn format_content_description_utf8() {
+        let mut headers = Headers::new();
+        headers.set(ContentDescription("Graphique des résultats".into()));
+
+        assert_eq!(
+            headers.to_string(),
+            "Content-Description: Graphique des =?utf-8?b?csOpc3VsdGF0cw==?=\r\n"
+        );
+    }
+
+    #[test]
+    fn format_keywords() {
+        let mut headers = Headers::new();
+        headers.set(Keywords::new(vec!["lettre", "rust email", "a, b"]));
+
+        assert_eq!(
+            headers.to_string(),
+            "Keywords: lettre, rust email, \"a, b\"\r\n"
+        );
+    }
+
+    #[test]
+    fn format_keywords_utf8() {
+        let mut headers = Headers::new();
+        headers.set(Keywords::new(vec!["café", "thé, sucre", "lettre"]));
+
+        assert_eq!(
+            headers.to_string(),
+            concat!(
+                "Keywords: =?utf-8?b?Y2Fmw6k=?=, =?utf-8?b?dGjDqSwgc3VjcmU=?=, ",
+                "lettre\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn format_keywords_folding() {
+        let mut headers = Headers::new();
+        headers.set(Keywords::new(vec![
+            "archive",
+            "quarterly report",
+            "finance department",
+            "confidential",
+            "annual budget",
+        ]));
+
+        assert_eq!(
+            headers.to_string(),
+            concat!(
+                "Keywords: archive, quarterly report, finance department, confidential,\r\n",
+                " annual budget\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn parse_keywords() {
+        let mut headers = Headers::new();
+        headers.insert_raw(
+            HeaderName::new_from_ascii_str("Keywords"),
+            "lettre, rust email, \"a, b\"".to_string(),
+        );
+
+        assert_eq!(
+            headers.get::<Keywords>(),
+            Some(Keywords::new(vec!["lettre", "rust email", "a, b"]))
+        );
+    }
+
+    #[test]
+    fn keywords_from_string() {
+        assert_eq!(
+            Keywords::from("lettre, rust email, \"a, b\"".to_owned()),
+            Keywords::new(vec!["lettre", "rust email", "a, b"])
+        );
+    }
+
+    enum_header!(
+        /// `X-Env` header
+        Header(XEnv, "X-Env") {
+            Production => "production",
+            Staging => "staging",
+        }
+    );
+
+    #[test]
+    fn format_parse_enum_header() {
+        let mut headers = Headers::new();
+        headers.set(XEnv::Production);
+        assert_eq!(headers.to_string(), "X-Env: production\r\n");
+
+        headers.insert_raw(
+            HeaderName::new_from_ascii_str("X-Env"),
+            " Staging".to_string(),
+        );
+        assert_eq!(headers.get::<XEnv>(), Some(XEnv::Staging));
+
+        headers.insert_raw(
+            HeaderName::new_from_ascii_str("X-Env"),
+            "development".to_string(),
+        );
+        assert_eq!(headers.get::<XEnv>(), None);
+    }
+
+    #[test]
+    fn format_parse_sensitivity() {
+        let mut headers = Headers::new();
+        headers.set(Sensitivity::CompanyConfidential);
+        assert_eq!(headers.to_string(), "Sensitivity: Company-Confidential\r\n");
+
+        headers.insert_raw(
+            HeaderName::new_from_ascii_str("Sensitivity"),
+            "private".to_string(),
+        );
+        assert_eq!(headers.get::<Sensitivity>(), Some(Sensitivity::Private));
+    }
 }
@@ -0,0 +1,364 @@
+use std::fmt::{self, Display, Write};
+
+use super::{header, Mailbox, MessageBuilder, MultiPart, SinglePart};
+use crate::{
+    address::{Address, Envelope},
+    message::header::{ContentType, Headers},
+    Error as EmailError, Message,
+};
+
+/// A builder for message disposition notifications (read receipts)
+///
+/// A message disposition notification (MDN) is sent to the address in the
+/// `Disposition-Notification-To` header of a message, to tell what happened to it, like
+/// being displayed to its recipient. It is a `multipart/report` made of a human-readable
+/// part, a `message/disposition-notification` part and, optionally, the headers of the
+/// original message, as defined in [RFC 8098](https://tools.ietf.org/html/rfc8098).
+///
+/// The notification is sent with an empty envelope sender, so no notification is ever
+/// sent back about it.
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use lettre::message::{Mdn, Message};
+///
+/// let mdn = Mdn::new(
+///     "<original@domain.tld>".to_owned(),
+///     "NoBody <nobody@domain.tld>".parse()?,
+/// )
+/// .reporting_ua("mail.domain.tld; lettre".to_owned())
+/// .build(Message::builder().from("Hei <hei@domain.tld>".parse()?))?;
+/// assert_eq!(mdn.envelope().from(), None);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct Mdn {
+    original_message_id: String,
+    notification_to: Mailbox,
+    disposition: Disposition,
+    reporting_ua: Option<String>,
+    final_recipient: Option<Address>,
+    human_readable: Option<String>,
+    returned_headers: Option<Headers>,
+}
+
+impl Mdn {
+    /// Creates a notification about the message with the `original_message_id`
+    /// `Message-ID`, sent to its `Disposition-Notification-To` mailbox
+    pub fn new(original_message_id: String, disposition_notification_to: Mailbox) -> Self {
+        Self {
+            original_message_id,
+            notification_to: disposition_notification_to,
+            disposition: Disposition::default(),
+            reporting_ua: None,
+            final_recipient: None,
+            human_readable: None,
+            returned_headers: None,
+        }
+    }
+
+    /// Set what happened to the message, defaults to displayed after a manual action
+    pub fn disposition(mut self, disposition: Disposition) -> Self {
+        self.disposition = disposition;
+        self
+    }
+
+    /// Set the `Reporting-UA` field, the name of the host and of the product which
+    /// generated the notification, like `mail.domain.tld; lettre`
+    pub fn reporting_ua(mut self, reporting_ua: String) -> Self {
+        self.reporting_ua = Some(reporting_ua);
+        self
+    }
+
+    /// Set the `Final-Recipient` field, the recipient the message was delivered to
+    ///
+    /// Defaults to the `From` address of the notification.
+    pub fn final_recipient(mut self, address: Address) -> Self {
+        self.final_recipient = Some(address);
+        self
+    }
+
+    /// Set the text of the human-readable part
+    pub fn human_readable(mut self, text: String) -> Self {
+        self.human_readable = Some(text);
+        self
+    }
+
+    /// Return the headers of the original message, in a `text/rfc822-headers` part
+    pub fn returned_headers(mut self, headers: Headers) -> Self {
+        self.returned_headers = Some(headers);
+        self
+    }
+
+    /// Build the notification, with the headers of `builder`
+    ///
+    /// The notification is sent to the `Disposition-Notification-To` mailbox, with a
+    /// `Disposition notification` subject if `builder` has none. Fails if `builder`
+    /// has no `From` mailbox.
+    pub fn build(self, builder: MessageBuilder) -> Result<Message, EmailError> {
+        let final_recipient = match self.final_recipient {
+            Some(final_recipient) => final_recipient,
+            None => {
+                let from: Vec<Mailbox> = match builder.headers.get::<header::From>() {
+                    Some(header::From(from)) => from.into(),
+                    None => return Err(EmailError::MissingFrom),
+                };
+                from.into_iter()
+                    .next()
+                    .ok_or(EmailError::MissingFrom)?
+                    .email
+            }
+        };
+
+        let mut fields = String::new();
+        if let Some(reporting_ua) = &self.reporting_ua {
+            write_field(&mut fields, "Reporting-UA", reporting_ua);
+        }
+        write_field(
+            &mut fields,
+            "Final-Recipient",
+            &format!("rfc822; {}", final_recipient),
+        );
+        write_field(
+            &mut fields,
+            "Original-Message-ID",
+            &self.original_message_id,
+        );
+        write_field(&mut fields, "Disposition", &self.disposition.to_string());
+
+        let (original_message_id, kind) = (&self.original_message_id, self.disposition.kind);
+        let human_readable = self
+            .human_readable
+            .unwrap_or_else(|| format!("The message {} was {}.", original_message_id, kind));
+        let mut report = MultiPart::report("disposition-notification".to_owned())
+            .singlepart(SinglePart::plain(human_readable))
+            .singlepart(
+                SinglePart::builder()
+                    .header(
+                        ContentType::parse("message/disposition-notification")
+                            .expect("valid content type"),
+                    )
+                    .body(fields),
+            );
+        if let Some(headers) = self.returned_headers {
+            report = report.singlepart(
+                SinglePart::builder()
+                    .header(ContentType::parse("text/rfc822-headers").expect("valid content type"))
+                    .body(headers.to_string()),
+            );
+        }
+
+        let envelope = Envelope::new(None, vec![self.notification_to.email.clone()])?;
+        let mut builder = builder.to(self.notification_to).envelope(envelope);
+        if builder.headers.get::<header::Subject>().is_none() {
+            builder = builder.subject("Disposition notification");
+        }
+        builder.multipart(report)
+    }
+}
+
+/// Writes a field of the `message/disposition-notification` part
+fn write_field(fields: &mut String, name: &str, value: &str) {
+    write!(fields, "{}: {}\r\n", name, value).expect("writing to a String can't fail");
+}
+
+/// What happened to a message, in the `Disposition` field of a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Disposition {
+    action: ActionMode,
+    sending: SendingMode,
+    kind: DispositionType,
+}
+
+impl Disposition {
+    /// Creates a disposition
+    pub fn new(action: ActionMode, sending: SendingMode, kind: DispositionType) -> Self {
+        Self {
+            action,
+            sending,
+            kind,
+        }
+    }
+}
+
+impl Default for Disposition {
+    /// Displayed after a manual action, with the notification sent manually
+    fn default() -> Self {
+        Self::new(
+            ActionMode::Manual,
+            SendingMode::Manual,
+            DispositionType::Displayed,
+        )
+    }
+}
+
+impl Display for Disposition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let action = match self.action {
+            ActionMode::Manual => "manual-action",
+            ActionMode::Automatic => "automatic-action",
+        };
+        let sending = match self.sending {
+            SendingMode::Manual => "MDN-sent-manually",
+            SendingMode::Automatic => "MDN-sent-automatically",
+        };
+        write!(f, "{}/{}; {}", action, sending, self.kind)
+    }
+}
+
+/// Whether the disposition was caused by the user or automatically
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionMode {
+    /// The user acted on the message
+    Manual,
+    /// The message was handled without any action of the user
+    Automatic,
+}
+
+/// Whether the user agreed to send the notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SendingMode {
+    /// The user explicitly agreed to send the notification
+    Manual,
+    /// The notification is sent without asking the user
+    Automatic,
+}
+
+/// What happened to the message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DispositionType {
+    /// The message was displayed to the user
+    Displayed,
+    /// The message was deleted without being displayed
+    Deleted,
+    /// The message was printed, faxed or forwarded without being displayed
+    Dispatched,
+    /// The message was processed without being displayed
+    Processed,
+}
+
+impl Display for DispositionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            DispositionType::Displayed => "displayed",
+            DispositionType::Deleted => "deleted",
+            DispositionType::Dispatched => "dispatched",
+            DispositionType::Processed => "processed",
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::SystemTime;
+
+    use super::*;
+
+    fn builder() -> MessageBuilder {
+        Message::builder()
+            .from("Hei <hei@domain.tld>".parse().unwrap())
+            .date(SystemTime::UNIX_EPOCH)
+            .no_user_agent()
+            .message_id(Some("<mdn@domain.tld>".to_owned()))
+    }
+
+    #[test]
+    fn mdn_displayed() {
+        let mdn = Mdn::new(
+            "<original@domain.tld>".to_owned(),
+            "NoBody <nobody@domain.tld>".parse().unwrap(),
+        )
+        .reporting_ua("mail.domain.tld; lettre".to_owned())
+        .build(builder())
+        .unwrap();
+
+        assert_eq!(mdn.envelope().from(), None);
+        assert_eq!(mdn.envelope().to(), ["nobody@domain.tld".parse().unwrap()]);
+
+        let formatted = String::from_utf8(mdn.formatted()).unwrap();
+        let boundary_start = formatted.find("boundary=\"").unwrap() + "boundary=\"".len();
+        let boundary_len = formatted[boundary_start..].find('"').unwrap();
+        let boundary = &formatted[boundary_start..boundary_start + boundary_len];
+        assert_eq!(
+            formatted.replace(boundary, "BOUNDARY"),
+            concat!(
+                "From: Hei <hei@domain.tld>\r\n",
+                "Date: Thu, 01 Jan 1970 00:00:00 -0000\r\n",
+                "Message-ID: <mdn@domain.tld>\r\n",
+                "To: NoBody <nobody@domain.tld>\r\n",
+                "Subject: Disposition notification\r\n",
+                "MIME-Version: 1.0\r\n",
+                "Content-Type: multipart/report; \r\n",
+                " boundary=\"BOUNDARY\"; \r\n",
+                " report-type=\"disposition-notification\"\r\n",
+                "\r\n",
+                "--BOUNDARY\r\n",
+                "Content-Type: text/plain; charset=utf-8\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "\r\n",
+                "The message <original@domain.tld> was displayed.\r\n",
+                "--BOUNDARY\r\n",
+                "Content-Type: message/disposition-notification\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "\r\n",
+                "Reporting-UA: mail.domain.tld; lettre\r\n",
+                "Final-Recipient: rfc822; hei@domain.tld\r\n",
+                "Original-Message-ID: <original@domain.tld>\r\n",
+                "Disposition: manual-action/MDN-sent-manually; displayed\r\n",
+                "\r\n",
+                "--BOUNDARY--\r\n",
+            )
+        );
+    }
+
+    #[test]
+    fn mdn_returned_headers() {
+        let original = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .date(SystemTime::UNIX_EPOCH)
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let mdn = Mdn::new(
+            "<original@domain.tld>".to_owned(),
+            "nobody@domain.tld".parse().unwrap(),
+        )
+        .disposition(Disposition::new(
+            ActionMode::Automatic,
+            SendingMode::Automatic,
+            DispositionType::Deleted,
+        ))
+        .final_recipient("hei@other.tld".parse().unwrap())
+        .returned_headers(original.headers().clone())
+        .build(builder().subject("Deleted: Happy new year"))
+        .unwrap();
+
+        let formatted = String::from_utf8(mdn.formatted()).unwrap();
+        assert!(formatted.contains("Subject: Deleted: Happy new year\r\n"));
+        assert!(formatted.contains("Final-Recipient: rfc822; hei@other.tld\r\n"));
+        assert!(
+            formatted.contains("Disposition: automatic-action/MDN-sent-automatically; deleted\r\n")
+        );
+        assert!(formatted.contains(concat!(
+            "Content-Type: text/rfc822-headers\r\n",
+            "Content-Transfer-Encoding: 7bit\r\n",
+            "\r\n",
+            "From: NoBody <nobody@domain.tld>\r\n",
+            "To: Hei <hei@domain.tld>\r\n",
+        )));
+    }
+
+    #[test]
+    fn mdn_missing_from() {
+        let result = Mdn::new(
+            "<original@domain.tld>".to_owned(),
+            "nobody@domain.tld".parse().unwrap(),
+        )
+        .build(Message::builder());
+        assert!(matches!(result, Err(EmailError::MissingFrom)));
+    }
+}
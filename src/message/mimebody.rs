@@ -1,9 +1,16 @@
 use std::io::Write;
 
 use crate::message::{
-    header::{self, ContentTransferEncoding, ContentType, Header, Headers},
+    body::first_too_long_line,
+    flowed,
+    header::{
+        self, ContentDescription, ContentLocation, ContentTransferEncoding, ContentType, Header,
+        Headers,
+    },
     EmailFormat, IntoBody,
 };
+#[cfg(feature = "charset")]
+use crate::message::{Body, MaybeString};
 use mime::Mime;
 use std::iter::repeat_with;
 
@@ -15,6 +22,97 @@ pub(super) enum Part {
 
     /// Multiple parts of content
     Multi(MultiPart),
+
+    /// Content which must be sent as it is, like signed content
+    Frozen(SignedPart),
+}
+
+impl Part {
+    /// Returns the number, starting at 1, of the first line longer than `max_line_length`
+    /// in the body of one of the parts
+    pub(super) fn first_too_long_line(&self, max_line_length: usize) -> Option<usize> {
+        match self {
+            Part::Single(part) => first_too_long_line(&part.body, max_line_length),
+            Part::Multi(part) => part
+                .parts
+                .iter()
+                .find_map(|part| part.first_too_long_line(max_line_length)),
+            Part::Frozen(part) => first_too_long_line(&part.content, max_line_length),
+        }
+    }
+
+    /// Size in bytes of the formatted part, computed without formatting the bodies
+    pub(super) fn formatted_len(&self) -> usize {
+        match self {
+            Part::Single(part) => part.headers.to_string().len() + part.body.len() + 4,
+            Part::Multi(part) => {
+                // `--boundary\r\n` before each part, `--boundary--\r\n` at the end
+                let boundary_len = part.boundary().len();
+                let parts_len: usize = part
+                    .parts
+                    .iter()
+                    .map(|part| boundary_len + 4 + part.formatted_len())
+                    .sum();
+                part.headers.to_string().len() + 2 + parts_len + boundary_len + 6
+            }
+            Part::Frozen(part) => part.content.len() + 2,
+        }
+    }
+
+    /// Description and body size of the part with the largest body
+    pub(super) fn largest_part(&self) -> (String, usize) {
+        match self {
+            Part::Single(part) => (describe_part(&part.headers), part.body.len()),
+            Part::Multi(part) => part
+                .parts
+                .iter()
+                .map(Part::largest_part)
+                .max_by_key(|(_, size)| *size)
+                .unwrap_or_else(|| (describe_part(&part.headers), 0)),
+            Part::Frozen(part) => ("signed content".to_owned(), part.content.len()),
+        }
+    }
+
+    /// Returns true if the headers of one of the parts contain a CR or LF character
+    pub(super) fn has_line_breaks(&self) -> bool {
+        match self {
+            Part::Single(part) => part.headers.has_line_breaks(),
+            Part::Multi(part) => {
+                part.headers.has_line_breaks() || part.parts.iter().any(Part::has_line_breaks)
+            }
+            Part::Frozen(_) => false,
+        }
+    }
+
+    /// Tells whether an AMP part is the last part of a `multipart/alternative`, where mail
+    /// clients would pick it over the HTML part
+    pub(super) fn has_amp_html_last(&self) -> bool {
+        match self {
+            Part::Multi(part) => {
+                let is_alternative = part
+                    .headers
+                    .get::<ContentType>()
+                    .map_or(false, |content_type| {
+                        content_type.as_ref().subtype() == "alternative"
+                    });
+                (is_alternative && part.parts.last().map_or(false, Part::is_amp_html))
+                    || part.parts.iter().any(Part::has_amp_html_last)
+            }
+            Part::Single(_) | Part::Frozen(_) => false,
+        }
+    }
+
+    fn is_amp_html(&self) -> bool {
+        match self {
+            Part::Single(part) => part
+                .headers
+                .get::<ContentType>()
+                .map_or(false, |content_type| {
+                    content_type.as_ref().essence_str() == AMP_HTML
+                }),
+            Part::Multi(_) | Part::Frozen(_) => false,
+        }
+    }
 }
 
 impl EmailFormat for Part {
@@ -22,6 +120,7 @@ impl EmailFormat for Part {
         match self {
             Part::Single(part) => part.format(out),
             Part::Multi(part) => part.format(out),
+            Part::Frozen(part) => part.format(out),
         }
     }
 }
@@ -52,6 +151,22 @@ impl SinglePartBuilder {
         self
     }
 
+    /// Set the Content-Description header of the singlepart, like `Quarterly results chart`
+    ///
+    /// Non-ASCII descriptions are encoded.
+    pub fn description<T: Into<String>>(mut self, description: T) -> Self {
+        self.headers
+            .set(ContentDescription::from(description.into()));
+        self
+    }
+
+    /// Set the Content-Location header of the singlepart, the URI by which an HTML part
+    /// of the message refers to it
+    pub fn content_location(mut self, content_location: ContentLocation) -> Self {
+        self.headers.set(content_location);
+        self
+    }
+
     /// Build singlepart using body
     pub fn body<T: IntoBody>(mut self, body: T) -> SinglePart {
         let maybe_encoding = self.headers.get::<ContentTransferEncoding>();
@@ -64,6 +179,74 @@ impl SinglePartBuilder {
             body: body.into_vec(),
         }
     }
+
+    /// Build singlepart using `text` encoded in `charset`, instead of UTF-8
+    ///
+    /// The `charset` parameter of the `Content-Type` header is set to the name of the
+    /// charset, `text/plain` being used if no `Content-Type` was set. Any charset known by
+    /// the [encoding_rs](https://docs.rs/encoding_rs) crate can be used, with
+    /// `ISO-8859-1` and `US-ASCII` being encoded as such rather than as `windows-1252`.
+    ///
+    /// Fails if the charset is unknown, or if `text` contains characters which can't be
+    /// represented in it.
+    ///
+    /// ```
+    /// use lettre::message::SinglePart;
+    ///
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// let part = SinglePart::builder().body_with_charset("Café crème", "ISO-8859-1")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[cfg(feature = "charset")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "charset")))]
+    pub fn body_with_charset<T: AsRef<str>>(
+        mut self,
+        text: T,
+        charset: &str,
+    ) -> Result<SinglePart, crate::Error> {
+        let (encoded, charset) = super::charset::encode(text.as_ref(), charset)?;
+
+        let content_type = self
+            .headers
+            .get::<ContentType>()
+            .unwrap_or(ContentType::TEXT_PLAIN);
+        self.headers
+            .set(content_type_with_charset(&content_type, charset));
+
+        let body = match self.headers.get::<ContentTransferEncoding>() {
+            Some(encoding) => MaybeString::Binary(encoded).into_body(Some(encoding)),
+            // Encoded like any other text, as 7bit or quoted-printable
+            None if encoded.is_ascii() => {
+                Body::new(String::from_utf8(encoded).expect("ASCII is valid UTF-8"))
+            }
+            None => Body::new_with_encoding(encoded, ContentTransferEncoding::QuotedPrintable)
+                .expect("quoted-printable is always valid"),
+        };
+        Ok(self.body(body))
+    }
+}
+
+/// Returns `content_type` with its `charset` parameter replaced
+#[cfg(feature = "charset")]
+fn content_type_with_charset(content_type: &ContentType, charset: &str) -> ContentType {
+    use std::fmt::Write as _;
+
+    let mime = content_type.as_ref();
+    let mut value = format!("{}/{}; charset={}", mime.type_(), mime.subtype(), charset);
+    for (name, param) in mime.params().filter(|(name, _)| *name != mime::CHARSET) {
+        let param = param.as_str();
+        if param
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`{|}~".contains(c))
+        {
+            let _ = write!(value, "; {}={}", name, param);
+        } else {
+            let _ = write!(value, "; {}={:?}", name, param);
+        }
+    }
+    ContentType::parse(&value).expect("valid content type")
 }
 
 impl Default for SinglePartBuilder {
@@ -107,6 +290,21 @@ impl SinglePart {
             .body(body)
     }
 
+    /// Directly create a `SinglePart` from an plain UTF-8 content, in `format=flowed`
+    ///
+    /// The text is wrapped with soft line breaks as defined in
+    /// [RFC 3676](https://tools.ietf.org/html/rfc3676), with `delsp=yes`, so
+    /// mail clients can reflow it to the width of the screen. Each line of `text`
+    /// is a paragraph.
+    pub fn plain_flowed<T: AsRef<str>>(text: T) -> Self {
+        let content_type =
+            header::ContentType::parse("text/plain; charset=utf-8; format=flowed; delsp=yes")
+                .expect("valid content type");
+        Self::builder()
+            .header(content_type)
+            .body(flowed::encode(text.as_ref()))
+    }
+
     /// Directly create a `SinglePart` from an UTF-8 HTML content
     pub fn html<T: IntoBody>(body: T) -> Self {
         Self::builder()
@@ -114,6 +312,20 @@ impl SinglePart {
             .body(body)
     }
 
+    /// Directly create a `SinglePart` from an UTF-8 [AMP for Email](https://amp.dev/about/email/)
+    /// content, of type `text/x-amp-html`
+    ///
+    /// The content is encoded with quoted-printable. The part must not be the last one of a
+    /// `multipart/alternative`, see [`MultiPart::alternative_plain_amp_html`].
+    pub fn amp_html<T: IntoBody>(body: T) -> Self {
+        let content_type = header::ContentType::parse("text/x-amp-html; charset=utf-8")
+            .expect("valid content type");
+        Self::builder()
+            .header(content_type)
+            .header(ContentTransferEncoding::QuotedPrintable)
+            .body(body)
+    }
+
     /// Get the headers from singlepart
     #[inline]
     pub fn headers(&self) -> &Headers {
@@ -144,6 +356,116 @@ impl EmailFormat for SinglePart {
     }
 }
 
+/// Content of a `multipart/signed` multipart
+///
+/// The content is formatted once, with canonical CRLF line endings, and its bytes are
+/// then kept as they are. The signature computed over [`SignedPart::as_bytes`] thus
+/// matches the content of the sent message.
+///
+/// The content should use a 7bit `Content-Transfer-Encoding` (`7bit`, `quoted-printable`
+/// or `base64`), as relays are allowed to convert `8bit` and `binary` content.
+///
+/// # Example
+///
+/// ```
+/// use lettre::message::{header, MultiPart, SignedPart, SinglePart};
+///
+/// let content = SignedPart::from(SinglePart::plain(String::from("Signed message")));
+/// // Compute the signature over the bytes which will be sent
+/// let signature = sign(content.as_bytes());
+///
+/// let part = MultiPart::signed(
+///     "application/pgp-signature".to_owned(),
+///     "pgp-sha256".to_owned(),
+/// )
+/// .signed_part(content)
+/// .singlepart(
+///     SinglePart::builder()
+///         .header(header::ContentType::parse("application/pgp-signature").unwrap())
+///         .body(signature),
+/// );
+/// # fn sign(_content: &[u8]) -> String {
+/// #     String::from("-----BEGIN PGP SIGNATURE-----")
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SignedPart {
+    content: Vec<u8>,
+}
+
+impl SignedPart {
+    fn new(mut formatted: Vec<u8>) -> Self {
+        // The line break preceding the next boundary belongs to the boundary,
+        // and isn't part of the signed content
+        if formatted.ends_with(b"\r\n") {
+            formatted.truncate(formatted.len() - 2);
+        }
+
+        Self {
+            content: canonical_line_endings(&formatted),
+        }
+    }
+
+    /// Get the content to sign, as it will be sent
+    #[inline]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.content
+    }
+}
+
+impl From<SinglePart> for SignedPart {
+    fn from(part: SinglePart) -> Self {
+        Self::new(part.formatted())
+    }
+}
+
+impl From<MultiPart> for SignedPart {
+    fn from(part: MultiPart) -> Self {
+        Self::new(part.formatted())
+    }
+}
+
+impl EmailFormat for SignedPart {
+    fn format(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.content);
+        out.extend_from_slice(b"\r\n");
+    }
+}
+
+/// Describes a part by its content type, and its `Content-Disposition` if it has one,
+/// like `text/plain (attachment; filename="debug.log")`
+pub(super) fn describe_part(headers: &Headers) -> String {
+    let content_type = headers.get::<ContentType>().map_or_else(
+        || "text/plain".to_owned(),
+        |content_type| content_type.as_ref().essence_str().to_owned(),
+    );
+    match headers.get_raw("Content-Disposition") {
+        Some(disposition) => format!("{} ({})", content_type, disposition),
+        None => content_type,
+    }
+}
+
+/// Replaces bare `\r` and `\n` characters with `\r\n`
+fn canonical_line_endings(buf: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(buf.len());
+    let mut bytes = buf.iter().copied().peekable();
+
+    while let Some(b) = bytes.next() {
+        match b {
+            b'\r' => {
+                out.extend_from_slice(b"\r\n");
+                if bytes.peek() == Some(&b'\n') {
+                    bytes.next();
+                }
+            }
+            b'\n' => out.extend_from_slice(b"\r\n"),
+            b => out.push(b),
+        }
+    }
+
+    out
+}
+
 /// The kind of multipart
 #[derive(Debug, Clone)]
 pub enum MultiPartKind {
@@ -166,9 +488,23 @@ pub enum MultiPartKind {
     Encrypted { protocol: String },
 
     /// Signed kind for signed messages
+    ///
+    /// `micalg` is the message integrity check algorithm used to compute the signature,
+    /// like `pgp-sha256` for PGP or `sha-256` for S/MIME. It is always emitted in lowercase.
+    /// The signed content should be added with [`MultiPart::signed_part`].
     Signed { protocol: String, micalg: String },
+
+    /// Report kind for delivery status and disposition notifications
+    ///
+    /// `report_type` is the type of the machine-readable part, like
+    /// `disposition-notification`, as defined in
+    /// [RFC 6522](https://tools.ietf.org/html/rfc6522).
+    Report { report_type: String },
 }
 
+/// Media type of [AMP for Email](https://amp.dev/about/email/) parts
+const AMP_HTML: &str = "text/x-amp-html";
+
 /// Create a random MIME boundary.
 /// (Not cryptographically random)
 fn make_boundary() -> String {
@@ -187,12 +523,17 @@ impl MultiPartKind {
                 Self::Related => "related",
                 Self::Encrypted { .. } => "encrypted",
                 Self::Signed { .. } => "signed",
+                Self::Report { .. } => "report",
             },
             boundary,
             match self {
                 Self::Encrypted { protocol } => format!("; protocol=\"{}\"", protocol),
-                Self::Signed { protocol, micalg } =>
-                    format!("; protocol=\"{}\"; micalg=\"{}\"", protocol, micalg),
+                Self::Signed { protocol, micalg } => format!(
+                    "; protocol=\"{}\"; micalg=\"{}\"",
+                    protocol,
+                    micalg.to_ascii_lowercase()
+                ),
+                Self::Report { report_type } => format!("; report-type=\"{}\"", report_type),
                 _ => String::new(),
             }
         )
@@ -214,6 +555,9 @@ impl MultiPartKind {
             "encrypted" => m.get_param("protocol").map(|p| Self::Encrypted {
                 protocol: p.as_str().to_owned(),
             }),
+            "report" => m.get_param("report-type").map(|p| Self::Report {
+                report_type: p.as_str().to_owned(),
+            }),
             _ => None,
         }
     }
@@ -271,6 +615,11 @@ impl MultiPartBuilder {
     pub fn multipart(self, part: MultiPart) -> MultiPart {
         self.build().multipart(part)
     }
+
+    /// Creates multipart using signed content
+    pub fn signed_part(self, part: SignedPart) -> MultiPart {
+        self.build().signed_part(part)
+    }
 }
 
 impl Default for MultiPartBuilder {
@@ -327,6 +676,13 @@ impl MultiPart {
         MultiPart::builder().kind(MultiPartKind::Signed { protocol, micalg })
     }
 
+    /// Creates report multipart builder
+    ///
+    /// Shortcut for `MultiPart::builder().kind(MultiPartKind::Report{ report_type })`
+    pub fn report(report_type: String) -> MultiPartBuilder {
+        MultiPart::builder().kind(MultiPartKind::Report { report_type })
+    }
+
     /// Alias for HTML and plain text versions of an email
     pub fn alternative_plain_html<T: IntoBody, V: IntoBody>(plain: T, html: V) -> Self {
         Self::alternative()
@@ -334,6 +690,27 @@ impl MultiPart {
             .singlepart(SinglePart::html(html))
     }
 
+    /// Alias for plain text, [AMP for Email](https://amp.dev/about/email/) and HTML
+    /// versions of an email
+    ///
+    /// The AMP part comes between the other ones: mail clients pick the last alternative
+    /// they support, those which don't support AMP fall back to the HTML part.
+    pub fn alternative_plain_amp_html<T: IntoBody, U: IntoBody, V: IntoBody>(
+        plain: T,
+        amp: U,
+        html: V,
+    ) -> Self {
+        Self::alternative()
+            .singlepart(SinglePart::plain(plain))
+            .singlepart(SinglePart::amp_html(amp))
+            .singlepart(SinglePart::html(html))
+    }
+
+    /// The parts of the multipart, in order
+    pub(super) fn parts(&self) -> &[Part] {
+        &self.parts
+    }
+
     /// Add single part to multipart
     pub fn singlepart(mut self, part: SinglePart) -> Self {
         self.parts.push(Part::Single(part));
@@ -346,6 +723,20 @@ impl MultiPart {
         self
     }
 
+    /// Add any kind of part to multipart
+    pub(super) fn part(mut self, part: Part) -> Self {
+        self.parts.push(part);
+        self
+    }
+
+    /// Add signed content to multipart
+    ///
+    /// The content is sent exactly as returned by [`SignedPart::as_bytes`].
+    pub fn signed_part(mut self, part: SignedPart) -> Self {
+        self.parts.push(Part::Frozen(part));
+        self
+    }
+
     /// Get the boundary of multipart contents
     pub fn boundary(&self) -> String {
         let content_type = self.headers.get::<ContentType>().unwrap();
@@ -419,6 +810,112 @@ mod test {
         );
     }
 
+    #[test]
+    fn single_part_description_location() {
+        let part = SinglePart::builder()
+            .content_type(header::ContentType::parse("image/png").unwrap())
+            .description("Quarterly results chart")
+            .content_location(ContentLocation::parse("https://example.com/q3/chart.png").unwrap())
+            .header(header::ContentTransferEncoding::Base64)
+            .body(vec![0x89, b'P', b'N', b'G']);
+
+        assert_eq!(
+            String::from_utf8(part.formatted()).unwrap(),
+            concat!(
+                "Content-Type: image/png\r\n",
+                "Content-Description: Quarterly results chart\r\n",
+                "Content-Location: https://example.com/q3/chart.png\r\n",
+                "Content-Transfer-Encoding: base64\r\n",
+                "\r\n",
+                "iVBORw==\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn single_part_description_utf8() {
+        let part = SinglePart::builder()
+            .content_location(ContentLocation::parse("images/chart.png").unwrap())
+            .description("Graphique des résultats")
+            .header(header::ContentType::parse("image/png").unwrap())
+            .body(vec![0x89, b'P', b'N', b'G']);
+
+        assert_eq!(
+            String::from_utf8(part.formatted()).unwrap(),
+            concat!(
+                "Content-Location: images/chart.png\r\n",
+                "Content-Description: Graphique des =?utf-8?b?csOpc3VsdGF0cw==?=\r\n",
+                "Content-Type: image/png\r\n",
+                "Content-Transfer-Encoding: base64\r\n",
+                "\r\n",
+                "iVBORw==\r\n"
+            )
+        );
+    }
+
+    #[test]
+    fn single_part_plain_flowed() {
+        let part = SinglePart::plain_flowed(format!("From me\n{}", "word ".repeat(20)));
+
+        assert_eq!(
+            String::from_utf8(part.formatted()).unwrap(),
+            concat!(
+                "Content-Type: text/plain; charset=utf-8; format=flowed; delsp=yes\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "\r\n",
+                " From me\r\n",
+                "word word word word word word word word word word word word word word word  \r\n",
+                "word word word word word\r\n"
+            )
+        );
+    }
+
+    #[cfg(feature = "charset")]
+    #[test]
+    fn single_part_charset() {
+        let part = SinglePart::builder()
+            .body_with_charset("Café\ncrème", "ISO-8859-1")
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(part.formatted()).unwrap(),
+            concat!(
+                "Content-Type: text/plain; charset=iso-8859-1\r\n",
+                "Content-Transfer-Encoding: quoted-printable\r\n",
+                "\r\n",
+                "Caf=E9\r\n",
+                "cr=E8me\r\n"
+            )
+        );
+
+        let part = SinglePart::builder()
+            .content_type(
+                header::ContentType::parse("text/html; charset=utf-8; name=\"mail page\"").unwrap(),
+            )
+            .body_with_charset("日本", "Shift_JIS")
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(part.formatted()).unwrap(),
+            concat!(
+                "Content-Type: text/html; charset=shift_jis; name=\"mail page\"\r\n",
+                "Content-Transfer-Encoding: quoted-printable\r\n",
+                "\r\n",
+                "=93=FA=96{\r\n"
+            )
+        );
+
+        let part = SinglePart::builder()
+            .body_with_charset("Hello", "ISO-2022-JP")
+            .unwrap();
+        assert!(String::from_utf8(part.formatted()).unwrap().starts_with(
+            "Content-Type: text/plain; charset=iso-2022-jp\r\nContent-Transfer-Encoding: 7bit\r\n"
+        ));
+
+        assert!(matches!(
+            SinglePart::builder().body_with_charset("5 €", "ISO-8859-1"),
+            Err(crate::Error::UnrepresentableChars(_))
+        ));
+    }
+
     #[test]
     fn single_part_quoted_printable() {
         let part = SinglePart::builder()
@@ -608,6 +1105,133 @@ mod test {
         );
     }
 
+    #[test]
+    fn multi_part_alternative_plain_amp_html() {
+        let part = MultiPart::alternative_plain_amp_html(
+            String::from("Hello"),
+            String::from("<!doctype html><html \u{26a1}4email><body>Hello</body></html>"),
+            String::from("<p>Hello</p>"),
+        );
+        let formatted = String::from_utf8(part.formatted())
+            .unwrap()
+            .replace(&part.boundary(), "BOUNDARY");
+
+        assert_eq!(
+            formatted,
+            concat!(
+                "Content-Type: multipart/alternative; \r\n",
+                " boundary=\"BOUNDARY\"\r\n",
+                "\r\n",
+                "--BOUNDARY\r\n",
+                "Content-Type: text/plain; charset=utf-8\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "\r\n",
+                "Hello\r\n",
+                "--BOUNDARY\r\n",
+                "Content-Type: text/x-amp-html; charset=utf-8\r\n",
+                "Content-Transfer-Encoding: quoted-printable\r\n",
+                "\r\n",
+                "<!doctype html><html =E2=9A=A14email><body>Hello</body></html>\r\n",
+                "--BOUNDARY\r\n",
+                "Content-Type: text/html; charset=utf-8\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "\r\n",
+                "<p>Hello</p>\r\n",
+                "--BOUNDARY--\r\n"
+            )
+        );
+        assert!(!Part::Multi(part).has_amp_html_last());
+    }
+
+    #[test]
+    fn amp_html_last() {
+        let amp_last = MultiPart::alternative()
+            .singlepart(SinglePart::plain(String::from("Hello")))
+            .singlepart(SinglePart::amp_html(String::from("<html>Hello</html>")));
+        assert!(Part::Multi(amp_last.clone()).has_amp_html_last());
+
+        let nested = MultiPart::mixed().multipart(amp_last);
+        assert!(Part::Multi(nested).has_amp_html_last());
+
+        // Only the order of alternatives matters
+        let mixed = MultiPart::mixed()
+            .singlepart(SinglePart::plain(String::from("Hello")))
+            .singlepart(SinglePart::amp_html(String::from("<html>Hello</html>")));
+        assert!(!Part::Multi(mixed).has_amp_html_last());
+    }
+
+    #[test]
+    fn signed_part_canonical_line_endings() {
+        let content = SignedPart::from(
+            SinglePart::builder()
+                .header(header::ContentType::TEXT_PLAIN)
+                .header(header::ContentTransferEncoding::SevenBit)
+                .body(b"First line\nSecond line\rThird line\r\n".to_vec()),
+        );
+
+        assert_eq!(
+            content.as_bytes(),
+            concat!(
+                "Content-Type: text/plain; charset=utf-8\r\n",
+                "Content-Transfer-Encoding: 7bit\r\n",
+                "\r\n",
+                "First line\r\n",
+                "Second line\r\n",
+                "Third line\r\n",
+            )
+            .as_bytes()
+        );
+    }
+
+    #[test]
+    fn multi_part_signed_roundtrip() {
+        use crate::message::Message;
+        use ring::signature::{self, Ed25519KeyPair, KeyPair};
+
+        let key = Ed25519KeyPair::from_seed_unchecked(&[42; 32]).unwrap();
+
+        let content = SignedPart::from(
+            MultiPart::alternative()
+                .singlepart(SinglePart::plain(String::from("Signed\ncontent")))
+                .singlepart(SinglePart::html(String::from("<p>Signed\ncontent</p>"))),
+        );
+        let signature = key.sign(content.as_bytes());
+
+        let part = MultiPart::signed(
+            "application/x-ed25519-signature".to_owned(),
+            "SHA-512".to_owned(),
+        )
+        .signed_part(content)
+        .singlepart(
+            SinglePart::builder()
+                .header(ContentType::parse("application/x-ed25519-signature").unwrap())
+                .header(header::ContentTransferEncoding::Base64)
+                .body(signature.as_ref().to_vec()),
+        );
+        let boundary = part.boundary();
+
+        let message = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Signed")
+            .multipart(part)
+            .unwrap()
+            .formatted();
+        let message = String::from_utf8(message).unwrap();
+        assert!(message.contains("micalg=\"sha-512\""));
+
+        // Extract the signed content and the signature as a receiver would
+        let delimiter = format!("\r\n--{}", boundary);
+        let mut parts = message.split(delimiter.as_str()).skip(1);
+        let signed = parts.next().unwrap().strip_prefix("\r\n").unwrap();
+        let signature = parts.next().unwrap().rsplit("\r\n\r\n").next().unwrap();
+        let signature = base64::decode(signature.trim().replace("\r\n", "")).unwrap();
+
+        signature::UnparsedPublicKey::new(&signature::ED25519, key.public_key().as_ref())
+            .verify(signed.as_bytes(), &signature)
+            .unwrap();
+    }
+
     #[test]
     fn multi_part_alternative() {
         let part = MultiPart::alternative()
@@ -650,7 +1274,7 @@ mod test {
                                              .body(String::from("<p>Текст <em>письма</em> в <a href=\"https://ru.wikipedia.org/wiki/Юникод\">уникоде</a><p>")))
                             .singlepart(SinglePart::builder()
                                              .header(header::ContentType::parse("image/png").unwrap())
-                                             .header(header::ContentLocation::from(String::from("/image.png")))
+                                             .header(header::ContentLocation::parse("/image.png").unwrap())
                                              .header(header::ContentTransferEncoding::Base64)
                                              .body(String::from("1234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890123456789012345678901234567890"))))
             .singlepart(SinglePart::builder()
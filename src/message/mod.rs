@@ -202,24 +202,41 @@
 //! ```
 //! </details>
 
-use std::{convert::TryFrom, io::Write, iter, time::SystemTime};
+use std::{cmp, io::Write, iter, time::SystemTime};
 
 pub use attachment::Attachment;
-pub use body::{Body, IntoBody, MaybeString};
+pub use body::{Body, IntoBody, MaybeString, BASE64_LINE_LENGTH};
+pub use deliverability::{deliverability_check, DeliverabilityIssue, DeliverabilityReport};
+pub use delivery_status::{DeliveryAction, DeliveryStatus, RecipientStatus};
+pub use forward::ForwardBuilder;
 pub use mailbox::*;
+pub use mdn::{ActionMode, Disposition, DispositionType, Mdn, SendingMode};
 pub use mimebody::*;
+pub use reply::ReplyBuilder;
+pub use resent::ResentBuilder;
 
 mod attachment;
 mod body;
+#[cfg(feature = "charset")]
+mod charset;
+mod deliverability;
+mod delivery_status;
+mod flowed;
+mod forward;
 pub mod header;
 mod mailbox;
+mod mdn;
 mod mimebody;
+mod original;
+mod reply;
+mod resent;
 
 use crate::{
     address::Envelope,
     message::header::{ContentTransferEncoding, Header, Headers, MailboxesHeader},
     Error as EmailError,
 };
+use original::OriginalHeaders;
 
 const DEFAULT_MESSAGE_ID_DOMAIN: &str = "localhost";
 
@@ -234,14 +251,36 @@ trait EmailFormat {
 pub struct MessageBuilder {
     headers: Headers,
     envelope: Option<Envelope>,
+    max_line_length: Option<usize>,
+    max_size: Option<usize>,
+    undisclosed_recipients: String,
+    keep_duplicate_recipients: bool,
+    default_user_agent: bool,
+    default_sender: Option<Mailbox>,
+    mime_version: bool,
+    attachments: Vec<SinglePart>,
 }
 
+/// Name of the empty group used as `To` header when all the recipients are in `Bcc`
+const UNDISCLOSED_RECIPIENTS: &str = "undisclosed-recipients";
+
+/// `User-Agent` of the messages which have none, with the `default-user-agent` feature
+const DEFAULT_USER_AGENT: &str = concat!("lettre/", env!("CARGO_PKG_VERSION"));
+
 impl MessageBuilder {
     /// Creates a new default message builder
     pub fn new() -> Self {
         Self {
             headers: Headers::new(),
             envelope: None,
+            max_line_length: None,
+            max_size: None,
+            undisclosed_recipients: UNDISCLOSED_RECIPIENTS.to_owned(),
+            keep_duplicate_recipients: false,
+            default_user_agent: cfg!(feature = "default-user-agent"),
+            default_sender: None,
+            mime_version: true,
+            attachments: Vec::new(),
         }
     }
 
@@ -285,17 +324,49 @@ impl MessageBuilder {
         self.header(header::Subject::from(s))
     }
 
+    /// Add a `Comments` header to message
+    ///
+    /// Unlike most headers, `Comments` can appear several times in a message: each call
+    /// adds one, after the ones already present.
+    pub fn comments<S: Into<String>>(mut self, comments: S) -> Self {
+        let comments = header::Comments::from(comments.into());
+        self.headers
+            .push_raw(header::Comments::name(), comments.display());
+        self
+    }
+
+    /// Set `Organization` header to message
+    ///
+    /// Shortcut for `self.header(header::Organization(organization.into()))`.
+    pub fn organization<S: Into<String>>(self, organization: S) -> Self {
+        let s: String = organization.into();
+        self.header(header::Organization::from(s))
+    }
+
+    /// Set `Keywords` header to message, from a list of words or phrases
+    ///
+    /// Shortcut for `self.header(header::Keywords::new(keywords))`.
+    pub fn keywords<I, T>(self, keywords: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.header(header::Keywords::new(keywords))
+    }
+
     /// Set `MIME-Version` header to 1.0
     ///
     /// Shortcut for `self.header(header::MIME_VERSION_1_0)`.
     ///
-    /// Not exposed as it is set by body methods
+    /// Not exposed as it is set when building messages using MIME
     fn mime_1_0(self) -> Self {
         self.header(header::MIME_VERSION_1_0)
     }
 
     /// Set `Sender` header. Should be used when providing several `From` mailboxes.
     ///
+    /// It must differ from the `From` mailbox when there's only one.
+    ///
     /// Defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.2).
     ///
     /// Shortcut for `self.header(header::Sender(mbox))`.
@@ -303,6 +374,16 @@ impl MessageBuilder {
         self.header(header::Sender::from(mbox))
     }
 
+    /// Set the `Sender` header added to messages which have none
+    ///
+    /// Like the mailbox of the authenticated user, sending on behalf of a shared or
+    /// automated mailbox. It isn't added if it's the single `From` mailbox, and allows
+    /// to send messages with several `From` mailboxes.
+    pub fn default_sender(mut self, mbox: Mailbox) -> Self {
+        self.default_sender = Some(mbox);
+        self
+    }
+
     /// Set or add mailbox to `From` header
     ///
     /// Defined in [RFC5322](https://tools.ietf.org/html/rfc5322#section-3.6.2).
@@ -362,23 +443,45 @@ impl MessageBuilder {
     /// If `None` is provided, an id will be generated in the
     /// `<UUID@HOSTNAME>`.
     pub fn message_id(self, id: Option<String>) -> Self {
-        match id {
-            Some(i) => self.header(header::MessageId::from(i)),
-            None => {
-                #[cfg(feature = "hostname")]
-                let hostname = hostname::get()
-                    .map_err(|_| ())
-                    .and_then(|s| s.into_string().map_err(|_| ()))
-                    .unwrap_or_else(|_| DEFAULT_MESSAGE_ID_DOMAIN.to_string());
-                #[cfg(not(feature = "hostname"))]
-                let hostname = DEFAULT_MESSAGE_ID_DOMAIN.to_string();
-
-                self.header(header::MessageId::from(
-                    // https://tools.ietf.org/html/rfc5322#section-3.6.4
-                    format!("<{}@{}>", make_message_id(), hostname),
-                ))
-            }
-        }
+        let id = id.unwrap_or_else(generate_message_id);
+        self.header(header::MessageId::from(id))
+    }
+
+    /// Set the name of the group used as `To` header when all the recipients are in `Bcc`
+    ///
+    /// A message without `To` and `Cc` headers gets an empty group as `To` header, like
+    /// `To: undisclosed-recipients:;`, so the recipients can't see each other and clients
+    /// don't display an empty recipient. The envelope still contains the `Bcc` addresses.
+    /// The name must only contain ASCII characters, building the message fails with
+    /// [`Error::NonAsciiChars`](EmailError::NonAsciiChars) otherwise.
+    ///
+    /// Defaults to `undisclosed-recipients`.
+    pub fn undisclosed_recipients<S: Into<String>>(mut self, group_name: S) -> Self {
+        self.undisclosed_recipients = group_name.into();
+        self
+    }
+
+    /// Mark the message as bulk mail, so automatic responders don't reply to it
+    ///
+    /// Sets the `Precedence: bulk` and `Auto-Submitted: auto-generated` headers.
+    pub fn bulk(self) -> Self {
+        self.header(header::Precedence::Bulk)
+            .header(header::AutoSubmitted::AutoGenerated)
+    }
+
+    /// Set [`Sensitivity`](header::Sensitivity) header to message
+    ///
+    /// Shortcut for `self.header(sensitivity)`.
+    pub fn sensitivity(self, sensitivity: header::Sensitivity) -> Self {
+        self.header(sensitivity)
+    }
+
+    /// Set [`X-Auto-Response-Suppress`](header::AutoResponseSuppress) header to message,
+    /// the automatic replies Exchange shouldn't send, like out of office ones
+    ///
+    /// Shortcut for `self.header(suppress)`.
+    pub fn auto_response_suppress(self, suppress: header::AutoResponseSuppress) -> Self {
+        self.header(suppress)
     }
 
     /// Set [User-Agent
@@ -387,16 +490,111 @@ impl MessageBuilder {
         self.header(header::UserAgent::from(id))
     }
 
+    /// Add a `User-Agent: lettre/<version>` header if the message has no `User-Agent`
+    /// nor `X-Mailer` header, to identify the library when debugging delivery issues
+    ///
+    /// The default with the `default-user-agent` feature.
+    pub fn default_user_agent(mut self) -> Self {
+        self.default_user_agent = true;
+        self
+    }
+
+    /// Don't add the `User-Agent: lettre/<version>` header of the `default-user-agent`
+    /// feature
+    ///
+    /// A `User-Agent` header set with [`MessageBuilder::user_agent`] is kept.
+    pub fn no_user_agent(mut self) -> Self {
+        self.default_user_agent = false;
+        self
+    }
+
+    /// Don't add the `MIME-Version: 1.0` header to messages using MIME
+    ///
+    /// It's added once to messages with parts, or with a `Content-Type` or a
+    /// `Content-Transfer-Encoding` other than the default `text/plain; charset=us-ascii`
+    /// in `7bit`. A `MIME-Version` header set with [`MessageBuilder::header`] is kept.
+    pub fn no_mime_version(mut self) -> Self {
+        self.mime_version = false;
+        self
+    }
+
+    /// Set the [`Feedback-ID`](header::FeedbackId) header used by Gmail
+    ///
+    /// It's validated when built with [`header::FeedbackId::new`].
+    pub fn feedback_id(self, feedback_id: header::FeedbackId) -> Self {
+        self.header(feedback_id)
+    }
+
     /// Force specific envelope (by default it is derived from headers)
     pub fn envelope(mut self, envelope: Envelope) -> Self {
         self.envelope = Some(envelope);
         self
     }
 
-    // TODO: High-level methods for attachments and embedded files
+    /// Keep the recipients found several times in the envelope derived from the headers
+    ///
+    /// By default, an address found in both `To` and `Cc`, or twice in `To`, is only
+    /// sent the message once, comparing the domains case-insensitively. The headers are
+    /// left unchanged either way.
+    pub fn keep_duplicate_recipients(mut self, keep: bool) -> Self {
+        self.keep_duplicate_recipients = keep;
+        self
+    }
+
+    /// Set the maximum length of the lines of the body, excluding the line ending
+    ///
+    /// Bodies passed to [`MessageBuilder::body`] in `7bit` or `8bit` are encoded as
+    /// `quoted-printable` if they have longer lines, unless the encoding was set with a
+    /// [`ContentTransferEncoding`] header. Building the message fails with
+    /// [`Error::LineTooLong`](EmailError::LineTooLong) if the lines of the encoded body,
+    /// or of its parts, are still too long.
+    ///
+    /// `quoted-printable` and `base64` wrap lines at 76 octets, so a lower
+    /// `max_line_length` is raised to 76.
+    ///
+    /// Lines can't be longer than 998 octets according to
+    /// [RFC 5321](https://tools.ietf.org/html/rfc5321#section-4.5.3.1.6), but they are only
+    /// checked when a maximum is set.
+    pub fn max_line_length(mut self, max_line_length: usize) -> Self {
+        self.max_line_length = Some(cmp::max(max_line_length, body::LINE_MAX_LENGTH));
+        self
+    }
+
+    /// Set the size in bytes of the largest message to build
+    ///
+    /// Building a larger message fails with [`Error::TooLarge`](EmailError::TooLarge),
+    /// giving its size and its largest part, to catch runaway attachments before
+    /// sending anything. The size is the one of the formatted message, computed without
+    /// formatting it.
+    ///
+    /// The limit is kept in the [`Message`], and replaces the default limit of the
+    /// transports, like [`SmtpTransportBuilder::max_message_size`].
+    ///
+    /// [`SmtpTransportBuilder::max_message_size`]: crate::transport::smtp::SmtpTransportBuilder::max_message_size
+    pub fn max_size(mut self, max_size: usize) -> Self {
+        self.max_size = Some(max_size);
+        self
+    }
+
+    /// Add an attachment, built with [`Attachment`]
+    ///
+    /// The body of the message and the attachments are sent in a `multipart/mixed`, the
+    /// body first.
+    pub fn attachment(mut self, attachment: SinglePart) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
 
     /// Create message from body
-    fn build(self, body: MessageBody) -> Result<Message, EmailError> {
+    fn build(mut self, body: MessageBody) -> Result<Message, EmailError> {
+        // Insert MIME-Version if missing and needed
+        if self.mime_version
+            && self.headers.get_raw(&header::MimeVersion::name()).is_none()
+            && uses_mime(&self.headers, &body)
+        {
+            self = self.mime_1_0();
+        }
+
         // Check for missing required headers
         // https://tools.ietf.org/html/rfc5322#section-3.6
 
@@ -407,31 +605,98 @@ impl MessageBuilder {
             self
         };
 
+        if res.default_user_agent
+            && res.headers.get_raw(&header::UserAgent::name()).is_none()
+            && res.headers.get_raw(&x_mailer()).is_none()
+        {
+            res.headers
+                .set(header::UserAgent::from(DEFAULT_USER_AGENT.to_owned()));
+        }
+
+        // Reject header values trying to inject additional headers
+        let part_line_breaks = match &body {
+            MessageBody::Mime(part) => part.has_line_breaks(),
+            MessageBody::Raw(_) => false,
+        };
+        if res.headers.has_line_breaks() || part_line_breaks {
+            return Err(EmailError::HeaderLineBreak);
+        }
+
         // Fail is missing correct originator (Sender or From)
-        match res.headers.get::<header::From>() {
-            Some(header::From(f)) => {
-                let from: Vec<Mailbox> = f.into();
-                if from.len() > 1 && res.headers.get::<header::Sender>().is_none() {
-                    return Err(EmailError::TooManyFrom);
+        let from: Vec<Mailbox> = match res.headers.get::<header::From>() {
+            Some(header::From(f)) => f.into(),
+            None => return Err(EmailError::MissingFrom),
+        };
+        let is_single_from = |mbox: &Mailbox| from.len() == 1 && from[0].email == mbox.email;
+        match res.headers.get::<header::Sender>().map(Mailbox::from) {
+            // The Sender must differ from a single author
+            Some(sender) if is_single_from(&sender) => return Err(EmailError::SenderIsFrom),
+            Some(_) => {}
+            None => match res.default_sender.take() {
+                Some(sender) if !is_single_from(&sender) => {
+                    res.headers.set(header::Sender::from(sender));
                 }
-            }
-            None => {
-                return Err(EmailError::MissingFrom);
-            }
+                _ if from.len() > 1 => return Err(EmailError::TooManyFrom),
+                _ => {}
+            },
         }
 
         let envelope = match res.envelope {
             Some(e) => e,
-            None => Envelope::try_from(&res.headers)?,
+            None => Envelope::from_headers(&res.headers, res.keep_duplicate_recipients)?,
         };
 
-        // Remove `Bcc` headers now the envelope is set
-        res.headers.remove::<header::Bcc>();
+        // Remove `Bcc` headers now the envelope is set, and hide the recipients of
+        // messages only sent to `Bcc` addresses
+        if res.headers.remove_raw(&header::Bcc::name()).is_some()
+            && res.headers.get_raw(&header::To::name()).is_none()
+            && res.headers.get_raw(&header::Cc::name()).is_none()
+        {
+            let group = undisclosed_recipients_group(&res.undisclosed_recipients)?;
+            res.headers.insert_raw(header::To::name(), group);
+        }
+
+        if let MessageBody::Mime(part) = &body {
+            if part.has_amp_html_last() {
+                return Err(EmailError::AmpHtmlLast);
+            }
+        }
+
+        if let Some(max_line_length) = res.max_line_length {
+            let too_long_line = match &body {
+                MessageBody::Mime(part) => part.first_too_long_line(max_line_length),
+                MessageBody::Raw(raw) => body::first_too_long_line(raw, max_line_length),
+            };
+            if let Some(line) = too_long_line {
+                return Err(EmailError::LineTooLong(line));
+            }
+        }
+
+        if let Some(max_size) = res.max_size {
+            let (size, (largest_part, largest_part_size)) = match &body {
+                MessageBody::Mime(part) => (part.formatted_len(), part.largest_part()),
+                MessageBody::Raw(raw) => (
+                    raw.len() + 2,
+                    (mimebody::describe_part(&res.headers), raw.len()),
+                ),
+            };
+            let size = size + res.headers.to_string().len();
+            if size > max_size {
+                return Err(EmailError::TooLarge {
+                    size,
+                    max_size,
+                    largest_part,
+                    largest_part_size,
+                });
+            }
+        }
 
         Ok(Message {
             headers: res.headers,
             body,
             envelope,
+            max_size: res.max_size,
+            original_headers: None,
         })
     }
 
@@ -441,8 +706,25 @@ impl MessageBuilder {
     /// `Content-Transfer-Encoding`, based on the most efficient and valid encoding
     /// for `body`.
     pub fn body<T: IntoBody>(mut self, body: T) -> Result<Message, EmailError> {
+        if !self.attachments.is_empty() {
+            // The body becomes the first part of the `multipart/mixed`
+            let content_type = self
+                .headers
+                .remove::<header::ContentType>()
+                .unwrap_or(header::ContentType::TEXT_PLAIN);
+            let mut part = SinglePart::builder().header(content_type);
+            if let Some(encoding) = self.headers.remove::<ContentTransferEncoding>() {
+                part = part.header(encoding);
+            }
+            return self.singlepart(part.body(body));
+        }
+
         let maybe_encoding = self.headers.get::<ContentTransferEncoding>();
-        let body = body.into_body(maybe_encoding);
+        let mut body = body.into_body(maybe_encoding);
+        // Only an encoding which wasn't set explicitly can be changed
+        if let (Some(max_line_length), None) = (self.max_line_length, maybe_encoding) {
+            body = body.rewrap_long_lines(max_line_length);
+        }
 
         self.headers.set(body.encoding());
         self.build(MessageBody::Raw(body.into_vec()))
@@ -450,12 +732,85 @@ impl MessageBuilder {
 
     /// Create message using mime body ([`MultiPart`][self::MultiPart])
     pub fn multipart(self, part: MultiPart) -> Result<Message, EmailError> {
-        self.mime_1_0().build(MessageBody::Mime(Part::Multi(part)))
+        self.mime(Part::Multi(part))
     }
 
     /// Create message using mime body ([`SinglePart`][self::SinglePart])
     pub fn singlepart(self, part: SinglePart) -> Result<Message, EmailError> {
-        self.mime_1_0().build(MessageBody::Mime(Part::Single(part)))
+        self.mime(Part::Single(part))
+    }
+
+    /// Create message with plain text and HTML versions of the body
+    ///
+    /// Shortcut for `multipart(MultiPart::alternative_plain_html(text, html))`, the
+    /// `multipart/alternative` being sent in a `multipart/mixed` with the attachments
+    /// if there are any.
+    pub fn body_alternative<T: IntoBody, V: IntoBody>(
+        self,
+        text: T,
+        html: V,
+    ) -> Result<Message, EmailError> {
+        self.multipart(MultiPart::alternative_plain_html(text, html))
+    }
+
+    /// Create message using mime body, followed by the attachments
+    fn mime(mut self, part: Part) -> Result<Message, EmailError> {
+        let part = if self.attachments.is_empty() {
+            part
+        } else {
+            let mixed = MultiPart::mixed().build().part(part);
+            Part::Multi(
+                self.attachments
+                    .drain(..)
+                    .fold(mixed, |mixed, attachment| mixed.singlepart(attachment)),
+            )
+        };
+        self.build(MessageBody::Mime(part))
+    }
+}
+
+/// Whether a message uses MIME: it has parts, or a content type or an encoding other
+/// than the default `text/plain; charset=us-ascii` in `7bit`
+fn uses_mime(headers: &Headers, body: &MessageBody) -> bool {
+    if let MessageBody::Mime(_) = body {
+        return true;
+    }
+
+    let default_type = headers.get_raw(&header::ContentType::name()).is_none()
+        || headers
+            .get::<header::ContentType>()
+            .map_or(false, |content_type| {
+                content_type.essence() == "text/plain"
+                    && content_type
+                        .get_param("charset")
+                        .map_or(true, |charset| charset.eq_ignore_ascii_case("us-ascii"))
+            });
+    let default_encoding = headers
+        .get::<ContentTransferEncoding>()
+        .map_or(true, |encoding| {
+            encoding == ContentTransferEncoding::SevenBit
+        });
+    !(default_type && default_encoding)
+}
+
+/// Name of the `X-Mailer` header, an older equivalent of `User-Agent`
+fn x_mailer() -> header::HeaderName {
+    header::HeaderName::new_from_ascii_str("X-Mailer")
+}
+
+/// Formats an empty group, quoting the name if needed
+fn undisclosed_recipients_group(name: &str) -> Result<String, EmailError> {
+    let name = name.trim();
+    if !name.bytes().all(|b| b == b' ' || b.is_ascii_graphic()) {
+        return Err(EmailError::NonAsciiChars);
+    }
+
+    let is_atext = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~".contains(c);
+    if !name.is_empty() && name.split(' ').all(|word| word.chars().all(is_atext)) {
+        Ok(format!("{}:;", name))
+    } else {
+        let escaped = name.replace('\\', "\\\\").replace('"', "\\\"");
+        Ok(format!("\"{}\":;", escaped))
     }
 }
 
@@ -466,6 +821,8 @@ pub struct Message {
     headers: Headers,
     body: MessageBody,
     envelope: Envelope,
+    max_size: Option<usize>,
+    original_headers: Option<OriginalHeaders>,
 }
 
 #[derive(Clone, Debug)]
@@ -480,28 +837,183 @@ impl Message {
         MessageBuilder::new()
     }
 
+    /// Parse a message from its source, to relay or resend it
+    ///
+    /// The headers left unchanged are formatted byte for byte like in `raw`, with their
+    /// order, their case and their folding, so that signatures like DKIM ones still
+    /// verify. Headers added to the message, like [`Received`](header::Received) headers
+    /// or the `Resent-*` headers of [`Message::resend`], are formatted above them. The
+    /// body is kept as is, and the envelope is computed from the `Sender`, `From`, `To`,
+    /// `Cc` and `Bcc` headers.
+    ///
+    /// Fails if a line of the header block isn't a header field, or if the envelope
+    /// can't be computed.
+    ///
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use lettre::Message;
+    ///
+    /// let raw = b"From: NoBody <nobody@domain.tld>\r\n\
+    ///     to: Hei <hei@domain.tld>\r\n\
+    ///     Subject: Happy\r\n new year\r\n\
+    ///     \r\n\
+    ///     Be happy!\r\n";
+    /// let email = Message::from_bytes(raw)?;
+    /// assert_eq!(email.envelope().to(), &["hei@domain.tld".parse()?]);
+    /// assert_eq!(email.formatted(), raw);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_bytes(raw: &[u8]) -> Result<Message, EmailError> {
+        let (headers, original_headers, body) = OriginalHeaders::parse(raw)?;
+        let envelope = Envelope::from_headers(&headers, false)?;
+        Ok(Message {
+            headers,
+            body: MessageBody::Raw(body),
+            envelope,
+            max_size: None,
+            original_headers: Some(original_headers),
+        })
+    }
+
     /// Get the headers from the Message
     pub fn headers(&self) -> &Headers {
         &self.headers
     }
 
+    /// Get a mutable reference to the headers of the Message
+    ///
+    /// The envelope isn't updated when the `From`, `Sender`, `To`, `Cc` or `Bcc`
+    /// headers are changed.
+    pub fn headers_mut(&mut self) -> &mut Headers {
+        &mut self.headers
+    }
+
     /// Get `Message` envelope
     pub fn envelope(&self) -> &Envelope {
         &self.envelope
     }
 
+    /// Maximum size in bytes of the message, set with [`MessageBuilder::max_size`]
+    pub fn max_size(&self) -> Option<usize> {
+        self.max_size
+    }
+
     /// Get message content formatted for SMTP
     pub fn formatted(&self) -> Vec<u8> {
         let mut out = Vec::new();
         self.format(&mut out);
         out
     }
+
+    /// Get message content formatted for submission to a SMTP server
+    ///
+    /// Like [`Message::formatted`], without the `Return-Path` headers, which are added
+    /// by the server delivering the message and must not be sent by a submitting client.
+    pub fn formatted_for_submission(&self) -> Vec<u8> {
+        let return_path = header::ReturnPath::name();
+        if self.headers.get_raw(&return_path).is_none() {
+            return self.formatted();
+        }
+
+        let mut headers = self.headers.clone();
+        while headers.remove_raw(&return_path).is_some() {}
+        let mut out = Vec::new();
+        self.format_with_headers(&headers, &mut out);
+        out
+    }
+
+    /// Reply to the message, or to all its recipients
+    ///
+    /// See [`ReplyBuilder`].
+    pub fn reply(&self) -> ReplyBuilder<'_> {
+        ReplyBuilder::new(self)
+    }
+
+    /// Forward the message, attached or inlined
+    ///
+    /// See [`ForwardBuilder`].
+    pub fn forward(&self) -> ForwardBuilder<'_> {
+        ForwardBuilder::new(self)
+    }
+
+    /// Resend the message to other recipients, adding a block of `Resent-*` headers
+    ///
+    /// See [`ResentBuilder`].
+    pub fn resend(&self) -> ResentBuilder {
+        ResentBuilder::new(self.clone())
+    }
+
+    /// Split the message into its envelope and its content, as returned by [`Message::formatted`]
+    ///
+    /// This makes it possible to hand the message to a custom transport or an external queue.
+    /// The `Bcc` header isn't part of the content, its recipients are only in the envelope.
+    ///
+    /// The content keeps the `Return-Path` headers. The SMTP transports leave them out by
+    /// default and send [`Message::formatted_for_submission`] instead, so both only differ
+    /// for a message which has one.
+    pub fn into_parts(self) -> (Envelope, Vec<u8>) {
+        let raw = self.formatted();
+        (self.envelope, raw)
+    }
 }
 
 impl EmailFormat for Message {
     fn format(&self, out: &mut Vec<u8>) {
-        write!(out, "{}", self.headers)
-            .expect("A Write implementation panicked while formatting headers");
+        self.format_with_headers(&self.headers, out);
+    }
+}
+
+impl Message {
+    /// Get the body formatted for SMTP, everything after the blank line ending the headers
+    pub(crate) fn formatted_body(&self) -> Vec<u8> {
+        let mut formatted = self.formatted();
+        let start = formatted
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+            .map_or(formatted.len(), |end| end + 4);
+        formatted.split_off(start)
+    }
+
+    /// Replaces the body with `body`, dropping the MIME parts
+    pub(crate) fn replace_body(&mut self, body: Body) {
+        self.headers.set(body.encoding());
+        self.body = MessageBody::Raw(body.into_vec());
+    }
+
+    /// Replaces the body with a single MIME part
+    pub(crate) fn replace_singlepart(&mut self, part: SinglePart) -> Result<(), EmailError> {
+        self.replace_mime(Part::Single(part))
+    }
+
+    /// Replaces the body with multiple MIME parts
+    pub(crate) fn replace_multipart(&mut self, part: MultiPart) -> Result<(), EmailError> {
+        self.replace_mime(Part::Multi(part))
+    }
+
+    /// Replaces the body with `part`, which holds the headers describing its content
+    fn replace_mime(&mut self, part: Part) -> Result<(), EmailError> {
+        if part.has_line_breaks() {
+            return Err(EmailError::HeaderLineBreak);
+        }
+
+        self.headers.remove::<header::ContentType>();
+        self.headers.remove::<ContentTransferEncoding>();
+        if self.headers.get_raw(&header::MimeVersion::name()).is_none() {
+            self.headers.set(header::MIME_VERSION_1_0);
+        }
+        self.body = MessageBody::Mime(part);
+        Ok(())
+    }
+
+    /// Formats the message with `headers` instead of its own
+    fn format_with_headers(&self, headers: &Headers, out: &mut Vec<u8>) {
+        match &self.original_headers {
+            Some(original_headers) => original_headers.format(headers, out),
+            None => write!(out, "{}", headers)
+                .expect("A Write implementation panicked while formatting headers"),
+        }
 
         match &self.body {
             MessageBody::Mime(p) => p.format(out),
@@ -519,6 +1031,20 @@ impl Default for MessageBuilder {
     }
 }
 
+/// Create a message id in the `<UUID@HOSTNAME>` format
+fn generate_message_id() -> String {
+    #[cfg(feature = "hostname")]
+    let hostname = hostname::get()
+        .map_err(|_| ())
+        .and_then(|s| s.into_string().map_err(|_| ()))
+        .unwrap_or_else(|_| DEFAULT_MESSAGE_ID_DOMAIN.to_string());
+    #[cfg(not(feature = "hostname"))]
+    let hostname = DEFAULT_MESSAGE_ID_DOMAIN.to_string();
+
+    // https://tools.ietf.org/html/rfc5322#section-3.6.4
+    format!("<{}@{}>", make_message_id(), hostname)
+}
+
 /// Create a random message id.
 /// (Not cryptographically random)
 fn make_message_id() -> String {
@@ -529,7 +1055,11 @@ fn make_message_id() -> String {
 mod test {
     use std::time::{Duration, SystemTime};
 
-    use super::{header, mailbox::Mailbox, make_message_id, Message, MultiPart, SinglePart};
+    use super::{
+        header, mailbox::Mailbox, make_message_id, x_mailer, Attachment, Body, EmailError, Message,
+        MultiPart, SinglePart,
+    };
+    use crate::Address;
 
     #[test]
     fn email_missing_originator() {
@@ -538,6 +1068,64 @@ mod test {
             .is_err());
     }
 
+    #[test]
+    fn email_sender() {
+        let builder = || {
+            Message::builder()
+                .from("Team <team@domain.tld>".parse().unwrap())
+                .to("Hei <hei@domain.tld>".parse().unwrap())
+        };
+
+        let email = builder()
+            .default_sender("Bot <bot@domain.tld>".parse().unwrap())
+            .body(String::from("Happy new year!"))
+            .unwrap();
+        assert_eq!(
+            email.headers().get::<header::Sender>(),
+            Some(header::Sender::from(
+                "Bot <bot@domain.tld>".parse::<Mailbox>().unwrap()
+            ))
+        );
+
+        let email = builder()
+            .default_sender("Team <team@domain.tld>".parse().unwrap())
+            .body(String::from("Happy new year!"))
+            .unwrap();
+        assert_eq!(email.headers().get::<header::Sender>(), None);
+
+        assert!(matches!(
+            builder()
+                .sender("team@domain.tld".parse().unwrap())
+                .body(String::from("Happy new year!")),
+            Err(EmailError::SenderIsFrom)
+        ));
+    }
+
+    #[test]
+    fn email_several_from_default_sender() {
+        let builder = || {
+            Message::builder()
+                .from("Alice <alice@domain.tld>".parse().unwrap())
+                .from("Bob <bob@domain.tld>".parse().unwrap())
+                .to("Hei <hei@domain.tld>".parse().unwrap())
+        };
+
+        assert!(matches!(
+            builder().body(String::from("Happy new year!")),
+            Err(EmailError::TooManyFrom)
+        ));
+
+        let email = builder()
+            .default_sender("alice@domain.tld".parse().unwrap())
+            .body(String::from("Happy new year!"))
+            .unwrap();
+        assert_eq!(
+            email.envelope().from(),
+            Some(&"alice@domain.tld".parse().unwrap())
+        );
+        assert_eq!(email.headers().get_raw("Sender"), Some("alice@domain.tld"));
+    }
+
     #[test]
     fn email_miminal_message() {
         assert!(Message::builder()
@@ -556,6 +1144,60 @@ mod test {
             .is_err());
     }
 
+    #[test]
+    fn email_header_injection() {
+        assert!(matches!(
+            Message::builder()
+                .from("NoBody <nobody@domain.tld>".parse().unwrap())
+                .to("NoBody <nobody@domain.tld>".parse().unwrap())
+                .subject("hi\r\nBcc: attacker@evil.com")
+                .body(String::from("Happy new year!")),
+            Err(crate::error::Error::HeaderLineBreak)
+        ));
+
+        assert!(Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("NoBody <nobody@domain.tld>".parse().unwrap())
+            .header(header::UserAgent::from(String::from("lettre\n")))
+            .body(String::from("Happy new year!"))
+            .is_err());
+
+        assert!(Message::builder()
+            .from(Mailbox::new(
+                Some("Attacker\rBcc: attacker@evil.com".into()),
+                "nobody@domain.tld".parse().unwrap()
+            ))
+            .to("NoBody <nobody@domain.tld>".parse().unwrap())
+            .body(String::from("Happy new year!"))
+            .is_err());
+
+        assert!(matches!(
+            Message::builder()
+                .from("NoBody <nobody@domain.tld>".parse().unwrap())
+                .to("NoBody <nobody@domain.tld>".parse().unwrap())
+                .multipart(
+                    MultiPart::mixed().singlepart(
+                        SinglePart::builder()
+                            .header(header::ContentType::TEXT_PLAIN)
+                            .description("Notes\r\nBcc: attacker@evil.com")
+                            .body(String::from("Happy new year!"))
+                    )
+                ),
+            Err(crate::error::Error::HeaderLineBreak)
+        ));
+    }
+
+    #[test]
+    fn email_header_line_break_replaced() {
+        assert!(Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("NoBody <nobody@domain.tld>".parse().unwrap())
+            .subject("hi\r\nBcc: attacker@evil.com")
+            .subject("hi")
+            .body(String::from("Happy new year!"))
+            .is_ok());
+    }
+
     #[test]
     fn email_message() {
         // Tue, 15 Nov 1994 08:12:31 GMT
@@ -563,6 +1205,7 @@ mod test {
 
         let email = Message::builder()
             .date(date)
+            .no_user_agent()
             .bcc("hidden@example.com".parse().unwrap())
             .header(header::From(
                 vec![Mailbox::new(
@@ -592,6 +1235,634 @@ mod test {
         );
     }
 
+    #[test]
+    fn email_into_parts() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .bcc("hidden@example.com".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+        let formatted = email.formatted();
+        let envelope = email.envelope().clone();
+
+        let (parts_envelope, raw) = email.into_parts();
+        assert_eq!(parts_envelope, envelope);
+        assert_eq!(raw, formatted);
+        assert_eq!(
+            parts_envelope
+                .to()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["hei@domain.tld", "hidden@example.com"]
+        );
+        assert!(!String::from_utf8(raw)
+            .unwrap()
+            .contains("hidden@example.com"));
+    }
+
+    #[test]
+    fn email_duplicate_recipients() {
+        let builder = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .to("Yuin <yuin@domain.tld>".parse().unwrap())
+            .cc("Hei <hei@DOMAIN.TLD>".parse().unwrap())
+            .bcc("yuin@domain.tld".parse().unwrap())
+            .subject("Happy new year");
+
+        let email = builder.clone().body(String::from("Be happy!")).unwrap();
+        assert_eq!(
+            email.envelope().to(),
+            &[
+                "hei@domain.tld".parse::<Address>().unwrap(),
+                "yuin@domain.tld".parse().unwrap()
+            ]
+        );
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.contains("Cc: Hei <hei@DOMAIN.TLD>\r\n"));
+
+        let email = builder
+            .keep_duplicate_recipients(true)
+            .body(String::from("Be happy!"))
+            .unwrap();
+        assert_eq!(email.envelope().to().len(), 4);
+    }
+
+    #[test]
+    fn email_bcc_only() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .bcc("hidden@example.com".parse().unwrap())
+            .bcc("Secret <secret@example.com>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        assert_eq!(
+            email
+                .envelope()
+                .to()
+                .iter()
+                .map(ToString::to_string)
+                .collect::<Vec<_>>(),
+            vec!["hidden@example.com", "secret@example.com"]
+        );
+        assert_eq!(
+            email.headers().get_raw("To"),
+            Some("undisclosed-recipients:;")
+        );
+        assert!(email.headers().get_raw("Bcc").is_none());
+
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.contains("To: undisclosed-recipients:;\r\n"));
+        assert!(!formatted.contains("hidden@example.com"));
+        assert!(!formatted.contains("secret@example.com"));
+    }
+
+    #[test]
+    fn email_bcc_only_custom_group() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .bcc("hidden@example.com".parse().unwrap())
+            .undisclosed_recipients("Newsletter subscribers")
+            .body(String::from("Be happy!"))
+            .unwrap();
+        assert_eq!(
+            email.headers().get_raw("To"),
+            Some("Newsletter subscribers:;")
+        );
+
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .bcc("hidden@example.com".parse().unwrap())
+            .undisclosed_recipients("Friends, \"family\"")
+            .body(String::from("Be happy!"))
+            .unwrap();
+        assert_eq!(
+            email.headers().get_raw("To"),
+            Some("\"Friends, \\\"family\\\"\":;")
+        );
+
+        assert!(matches!(
+            Message::builder()
+                .from("NoBody <nobody@domain.tld>".parse().unwrap())
+                .bcc("hidden@example.com".parse().unwrap())
+                .undisclosed_recipients("Каи")
+                .body(String::from("Be happy!")),
+            Err(EmailError::NonAsciiChars)
+        ));
+    }
+
+    #[test]
+    fn email_max_line_length() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .max_line_length(100)
+            .body("a".repeat(2000))
+            .unwrap();
+        assert_eq!(
+            email.headers().get::<header::ContentTransferEncoding>(),
+            Some(header::ContentTransferEncoding::QuotedPrintable)
+        );
+
+        let result = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .max_line_length(998)
+            .singlepart(SinglePart::plain(Body::dangerous_pre_encoded(
+                format!("Hello\r\n{}", "a".repeat(2000)).into_bytes(),
+                header::ContentTransferEncoding::SevenBit,
+            )));
+        assert!(matches!(result, Err(EmailError::LineTooLong(line)) if line == 2));
+    }
+
+    #[test]
+    fn email_max_line_length_raised() {
+        let builder = || {
+            Message::builder()
+                .from("NoBody <nobody@domain.tld>".parse().unwrap())
+                .to("Hei <hei@domain.tld>".parse().unwrap())
+                .max_line_length(10)
+        };
+
+        let email = builder().body("a".repeat(50)).unwrap();
+        assert_eq!(
+            email.headers().get::<header::ContentTransferEncoding>(),
+            Some(header::ContentTransferEncoding::SevenBit)
+        );
+
+        let email = builder().body("a".repeat(200)).unwrap();
+        assert_eq!(
+            email.headers().get::<header::ContentTransferEncoding>(),
+            Some(header::ContentTransferEncoding::QuotedPrintable)
+        );
+    }
+
+    #[test]
+    fn email_max_line_length_encoded_body() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .max_line_length(100)
+            .body(
+                Body::new_with_encoding("a".repeat(200), header::ContentTransferEncoding::SevenBit)
+                    .unwrap(),
+            )
+            .unwrap();
+        assert_eq!(
+            email.headers().get::<header::ContentTransferEncoding>(),
+            Some(header::ContentTransferEncoding::QuotedPrintable)
+        );
+
+        let result = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .header(header::ContentTransferEncoding::SevenBit)
+            .max_line_length(100)
+            .body("a".repeat(200));
+        assert!(matches!(result, Err(EmailError::LineTooLong(1))));
+    }
+
+    #[test]
+    fn email_default_user_agent() {
+        let builder = || {
+            Message::builder()
+                .from("NoBody <nobody@domain.tld>".parse().unwrap())
+                .to("NoBody <nobody@domain.tld>".parse().unwrap())
+                .default_user_agent()
+        };
+
+        let email = builder().body(String::from("Happy new year!")).unwrap();
+        let user_agent = email.headers().get::<header::UserAgent>().unwrap();
+        assert_eq!(
+            user_agent.as_ref(),
+            format!("lettre/{}", env!("CARGO_PKG_VERSION"))
+        );
+        assert!(!user_agent
+            .as_ref()
+            .contains(|c: char| c.is_whitespace() || c.is_control()));
+
+        let email = builder()
+            .user_agent("Custom/1.0".to_owned())
+            .body(String::from("Happy new year!"))
+            .unwrap();
+        assert_eq!(
+            email.headers().get::<header::UserAgent>().unwrap().as_ref(),
+            "Custom/1.0"
+        );
+
+        let mut builder = builder();
+        builder
+            .headers
+            .insert_raw(x_mailer(), "Custom Mailer 2.0".to_owned());
+        let email = builder.body(String::from("Happy new year!")).unwrap();
+        assert_eq!(email.headers().get::<header::UserAgent>(), None);
+        assert_eq!(
+            email.headers().get_raw(&x_mailer()),
+            Some("Custom Mailer 2.0")
+        );
+    }
+
+    #[test]
+    fn email_no_user_agent() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("NoBody <nobody@domain.tld>".parse().unwrap())
+            .default_user_agent()
+            .no_user_agent()
+            .body(String::from("Happy new year!"))
+            .unwrap();
+
+        assert_eq!(email.headers().get::<header::UserAgent>(), None);
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(!formatted.contains("User-Agent"));
+        assert!(!formatted.contains("lettre/"));
+    }
+
+    #[test]
+    fn email_prepend_received() {
+        let date = SystemTime::UNIX_EPOCH + Duration::from_secs(784887151);
+        let mut email = Message::builder()
+            .date(date)
+            .no_user_agent()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .body(String::from("Happy new year!"))
+            .unwrap();
+
+        email
+            .headers_mut()
+            .prepend(header::Received::new(date).by("mx.domain.tld"));
+        email.headers_mut().prepend(
+            header::Received::new(date + Duration::from_secs(1))
+                .by("relay.domain.tld")
+                .with(header::ReceivedProtocol::Esmtps),
+        );
+
+        assert_eq!(
+            email.headers().get_all_raw("Received").collect::<Vec<_>>(),
+            vec![
+                "by relay.domain.tld with ESMTPS; Tue, 15 Nov 1994 08:12:32 -0000",
+                "by mx.domain.tld; Tue, 15 Nov 1994 08:12:31 -0000",
+            ]
+        );
+        assert!(String::from_utf8(email.formatted())
+            .unwrap()
+            .starts_with(concat!(
+                "Received: by relay.domain.tld with ESMTPS; Tue, 15 Nov 1994 08:12:32 -0000\r\n",
+                "Received: by mx.domain.tld; Tue, 15 Nov 1994 08:12:31 -0000\r\n",
+                "Date: Tue, 15 Nov 1994 08:12:31 -0000\r\n",
+            )));
+    }
+
+    #[test]
+    fn email_formatted_for_submission() {
+        let date = SystemTime::UNIX_EPOCH + Duration::from_secs(784887151);
+        let mut email = Message::builder()
+            .date(date)
+            .no_user_agent()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .body(String::from("Happy new year!"))
+            .unwrap();
+        let submitted = email.formatted();
+
+        email.headers_mut().prepend(header::ReturnPath::new(
+            "bounces@domain.tld".parse().unwrap(),
+        ));
+        assert!(String::from_utf8(email.formatted())
+            .unwrap()
+            .starts_with("Return-Path: <bounces@domain.tld>\r\nDate: "));
+        assert_eq!(email.formatted_for_submission(), submitted);
+    }
+
+    #[test]
+    fn email_into_parts_return_path() {
+        let mut email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .body(String::from("Happy new year!"))
+            .unwrap();
+        email.headers_mut().prepend(header::ReturnPath::new(
+            "bounces@domain.tld".parse().unwrap(),
+        ));
+        let formatted = email.formatted();
+        let submitted = email.formatted_for_submission();
+
+        let (_envelope, raw) = email.into_parts();
+        assert_eq!(raw, formatted);
+        assert_ne!(raw, submitted);
+        assert!(raw.starts_with(b"Return-Path: <bounces@domain.tld>\r\n"));
+    }
+
+    #[test]
+    fn email_max_size() {
+        let builder = || {
+            Message::builder()
+                .from("NoBody <nobody@domain.tld>".parse().unwrap())
+                .to("Hei <hei@domain.tld>".parse().unwrap())
+                .attachment(Attachment::new("debug.log".to_owned()).body(
+                    "a".repeat(2000),
+                    header::ContentType::parse("application/octet-stream").unwrap(),
+                ))
+        };
+
+        // The size is computed without formatting the message
+        let email = builder().body(String::from("Be happy!")).unwrap();
+        let size = email.formatted().len();
+        let email = builder()
+            .max_size(size)
+            .body(String::from("Be happy!"))
+            .unwrap();
+        assert_eq!(email.max_size(), Some(size));
+
+        let result = builder().max_size(size - 1).body(String::from("Be happy!"));
+        match result {
+            Err(EmailError::TooLarge {
+                size: too_large,
+                max_size,
+                largest_part,
+                largest_part_size,
+            }) => {
+                assert_eq!((too_large, max_size), (size, size - 1));
+                assert_eq!(
+                    largest_part,
+                    "application/octet-stream (attachment; filename=\"debug.log\")"
+                );
+                assert!(largest_part_size > 2000);
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .body(String::from("Be happy!"))
+            .unwrap();
+        let size = email.formatted().len();
+        let result = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .date(SystemTime::now())
+            .max_size(size - 1)
+            .body(String::from("Be happy!"));
+        assert!(
+            matches!(result, Err(EmailError::TooLarge { largest_part, .. }) if largest_part == "text/plain")
+        );
+    }
+
+    #[test]
+    fn email_feedback_id() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .feedback_id(header::FeedbackId::new(Some("newyear"), None, None, "lettre1").unwrap())
+            .body(String::from("Be happy!"))
+            .unwrap();
+        assert_eq!(
+            email
+                .headers()
+                .get::<header::FeedbackId>()
+                .unwrap()
+                .sender_id(),
+            "lettre1"
+        );
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.contains("Feedback-ID: newyear:::lettre1\r\n"));
+    }
+
+    #[test]
+    fn email_with_amp_html_last() {
+        let result = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .multipart(
+                MultiPart::alternative()
+                    .singlepart(SinglePart::html(String::from("<p>Hello</p>")))
+                    .singlepart(SinglePart::amp_html(String::from("<html>Hello</html>"))),
+            );
+        assert!(matches!(result, Err(EmailError::AmpHtmlLast)));
+
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .multipart(MultiPart::alternative_plain_amp_html(
+                String::from("Hello"),
+                String::from("<html>Hello</html>"),
+                String::from("<p>Hello</p>"),
+            ))
+            .unwrap();
+    }
+
+    #[test]
+    fn email_comments_organization() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .comments("First comment")
+            .comments("Second comment")
+            .organization("Example Corp.")
+            .keywords(vec!["lettre", "Doe, John"])
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let comments = email
+            .headers()
+            .get_all::<header::Comments>()
+            .collect::<Vec<_>>();
+        assert_eq!(
+            comments,
+            [
+                header::Comments::from("First comment".to_owned()),
+                header::Comments::from("Second comment".to_owned())
+            ]
+        );
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.contains(concat!(
+            "Comments: First comment\r\n",
+            "Comments: Second comment\r\n",
+            "Organization: Example Corp.\r\n",
+            "Keywords: lettre, \"Doe, John\"\r\n"
+        )));
+
+        // Line breaks can't inject headers
+        let err = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .comments("Comment\r\nBcc: eve@domain.tld")
+            .body(String::from("Be happy!"))
+            .unwrap_err();
+        assert!(matches!(err, EmailError::HeaderLineBreak));
+    }
+
+    #[test]
+    fn email_bulk() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .bulk()
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        assert_eq!(
+            email.headers().get::<header::Precedence>(),
+            Some(header::Precedence::Bulk)
+        );
+        assert_eq!(
+            email.headers().get::<header::AutoSubmitted>(),
+            Some(header::AutoSubmitted::AutoGenerated)
+        );
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.contains("Precedence: bulk\r\nAuto-Submitted: auto-generated\r\n"));
+    }
+
+    #[test]
+    fn email_sensitivity_auto_response_suppress() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .sensitivity(header::Sensitivity::CompanyConfidential)
+            .auto_response_suppress(
+                header::AutoResponseSuppress::OOF | header::AutoResponseSuppress::AUTO_REPLY,
+            )
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.contains(concat!(
+            "Sensitivity: Company-Confidential\r\n",
+            "X-Auto-Response-Suppress: OOF, AutoReply\r\n"
+        )));
+    }
+
+    #[test]
+    fn email_mime_version() {
+        let mime_versions = |email: Message| {
+            String::from_utf8(email.formatted())
+                .unwrap()
+                .matches("MIME-Version: ")
+                .count()
+        };
+        let builder = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap());
+
+        // Plain text in 7bit isn't MIME
+        let email = builder.clone().body(String::from("Be happy!")).unwrap();
+        assert_eq!(mime_versions(email), 0);
+        let email = builder.clone().body(String::from("Être heureux")).unwrap();
+        assert_eq!(mime_versions(email), 1);
+        let email = builder
+            .clone()
+            .header(header::ContentType::TEXT_PLAIN)
+            .body(String::from("Be happy!"))
+            .unwrap();
+        assert_eq!(mime_versions(email), 1);
+
+        let email = builder
+            .clone()
+            .body_alternative(String::from("Hello"), String::from("<p>Hello</p>"))
+            .unwrap();
+        assert_eq!(mime_versions(email), 1);
+
+        // Never duplicated, nor overridden
+        let email = builder
+            .clone()
+            .header(header::MimeVersion::new(1, 1))
+            .body_alternative(String::from("Hello"), String::from("<p>Hello</p>"))
+            .unwrap();
+        assert_eq!(
+            email.headers().get::<header::MimeVersion>(),
+            Some(header::MimeVersion::new(1, 1))
+        );
+        assert_eq!(mime_versions(email), 1);
+
+        let email = builder
+            .clone()
+            .no_mime_version()
+            .body_alternative(String::from("Hello"), String::from("<p>Hello</p>"))
+            .unwrap();
+        assert_eq!(mime_versions(email), 0);
+        let email = builder
+            .no_mime_version()
+            .header(header::MIME_VERSION_1_0)
+            .body(String::from("Être heureux"))
+            .unwrap();
+        assert_eq!(mime_versions(email), 1);
+    }
+
+    #[test]
+    fn email_body_alternative() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .body_alternative(String::from("Hello"), String::from("<p>Hello</p>"))
+            .unwrap();
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.contains("MIME-Version: 1.0\r\n"));
+        assert!(formatted.contains("Content-Type: multipart/alternative;"));
+        let plain = formatted
+            .find("Content-Type: text/plain; charset=utf-8")
+            .unwrap();
+        let html = formatted
+            .find("Content-Type: text/html; charset=utf-8")
+            .unwrap();
+        assert!(plain < html);
+        assert!(!formatted.contains("multipart/mixed"));
+    }
+
+    #[test]
+    fn email_body_alternative_with_attachment() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .attachment(
+                Attachment::new("notes.txt".to_owned())
+                    .body(String::from("Notes"), header::ContentType::TEXT_PLAIN),
+            )
+            .body_alternative(String::from("Hello"), String::from("<p>Hello</p>"))
+            .unwrap();
+
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        let mixed = formatted.find("Content-Type: multipart/mixed;").unwrap();
+        let alternative = formatted
+            .find("Content-Type: multipart/alternative;")
+            .unwrap();
+        assert!(mixed < alternative);
+        let html = formatted.find("<p>Hello</p>").unwrap();
+        let attachment = formatted
+            .find("Content-Disposition: attachment; filename=\"notes.txt\"")
+            .unwrap();
+        assert!(alternative < html && html < attachment);
+    }
+
+    #[test]
+    fn email_body_with_attachment() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .header(header::ContentType::TEXT_HTML)
+            .attachment(
+                Attachment::new("notes.txt".to_owned())
+                    .body(String::from("Notes"), header::ContentType::TEXT_PLAIN),
+            )
+            .body(String::from("<p>Hello</p>"))
+            .unwrap();
+
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted.contains("Content-Type: multipart/mixed;"));
+        assert_eq!(formatted.matches("text/html").count(), 1);
+        let html = formatted
+            .find("Content-Type: text/html; charset=utf-8\r\nContent-Transfer-Encoding: 7bit\r\n\r\n<p>Hello</p>")
+            .unwrap();
+        let attachment = formatted.find("filename=\"notes.txt\"").unwrap();
+        assert!(html < attachment);
+    }
+
     #[test]
     fn email_with_png() {
         // Tue, 15 Nov 1994 08:12:31 GMT
@@ -599,6 +1870,7 @@ mod test {
         let img = std::fs::read("./docs/lettre.png").unwrap();
         let m = Message::builder()
             .date(date)
+            .no_user_agent()
             .from("NoBody <nobody@domain.tld>".parse().unwrap())
             .reply_to("Yuin <yuin@domain.tld>".parse().unwrap())
             .to("Hei <hei@domain.tld>".parse().unwrap())
@@ -0,0 +1,197 @@
+use std::io::Write;
+
+use super::header::{HeaderName, Headers};
+use crate::Error as EmailError;
+
+/// The header fields of a parsed message, as they were in its source
+///
+/// Used to format the headers left unchanged since parsing byte for byte, instead of
+/// encoding and folding them again.
+#[derive(Debug, Clone)]
+pub(super) struct OriginalHeaders {
+    /// The fields with their name, their unfolded value, and their source with line breaks
+    fields: Vec<(HeaderName, String, Vec<u8>)>,
+}
+
+impl OriginalHeaders {
+    /// Splits `raw` into its headers and its body
+    pub(super) fn parse(raw: &[u8]) -> Result<(Headers, Self, Vec<u8>), EmailError> {
+        let mut sources: Vec<(HeaderName, Vec<u8>)> = Vec::new();
+        let mut body = Vec::new();
+        let mut pos = 0;
+        let mut line_number = 1;
+        while pos < raw.len() {
+            let end = raw[pos..]
+                .iter()
+                .position(|&b| b == b'\n')
+                .map_or(raw.len(), |i| pos + i + 1);
+            let line = &raw[pos..end];
+            pos = end;
+
+            if line == b"\r\n" || line == b"\n" {
+                body = raw[pos..].to_vec();
+                break;
+            }
+
+            if line[0] == b' ' || line[0] == b'\t' {
+                // Folded field
+                match sources.last_mut() {
+                    Some((_name, source)) => source.extend_from_slice(line),
+                    None => return Err(EmailError::MalformedHeader(line_number)),
+                }
+            } else {
+                let name = line
+                    .iter()
+                    .position(|&b| b == b':')
+                    .and_then(|colon| std::str::from_utf8(&line[..colon]).ok())
+                    .and_then(|name| HeaderName::new_from_ascii(name.trim_end().to_owned()).ok())
+                    .ok_or(EmailError::MalformedHeader(line_number))?;
+                sources.push((name, line.to_vec()));
+            }
+            line_number += 1;
+        }
+
+        let mut headers = Headers::with_capacity(sources.len());
+        let fields = sources
+            .into_iter()
+            .map(|(name, source)| {
+                let source_ = String::from_utf8_lossy(&source);
+                let (_name, value) =
+                    source_.split_at(source_.find(':').expect("field has a colon"));
+                let value = value[1..]
+                    .chars()
+                    .filter(|&c| c != '\r' && c != '\n')
+                    .collect::<String>()
+                    .trim()
+                    .to_owned();
+                headers.push_raw(name.clone(), value.clone());
+                (name, value, source)
+            })
+            .collect();
+        Ok((headers, Self { fields }, body))
+    }
+
+    /// Formats `headers`, writing the fields unchanged since parsing as in the source
+    ///
+    /// The headers are matched with the source fields from the bottom, as headers are
+    /// added at the top of a message. A header which was changed, or which isn't in the
+    /// source, is formatted like in any other message.
+    pub(super) fn format(&self, headers: &Headers, out: &mut Vec<u8>) {
+        let entries = headers.iter().collect::<Vec<_>>();
+        let mut sources = vec![None; entries.len()];
+        let (mut i, mut j) = (entries.len(), self.fields.len());
+        while i > 0 && j > 0 {
+            let (name, value) = entries[i - 1];
+            let is_field = |(name_, value_, _source): &(HeaderName, String, Vec<u8>)| {
+                **name == **name_ && value == value_
+            };
+            if is_field(&self.fields[j - 1]) {
+                sources[i - 1] = Some(&self.fields[j - 1].2);
+                i -= 1;
+                j -= 1;
+            } else if self.fields[..j].iter().any(is_field) {
+                // The field was removed or changed
+                j -= 1;
+            } else {
+                i -= 1;
+            }
+        }
+
+        for ((name, value), source) in entries.into_iter().zip(sources) {
+            match source {
+                Some(source) => out.extend_from_slice(source),
+                None => {
+                    let mut header = Headers::new();
+                    header.push_raw(name.clone(), value.to_owned());
+                    write!(out, "{}", header)
+                        .expect("A Write implementation panicked while formatting headers");
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use crate::{message::header, Error as EmailError, Message};
+
+    const RAW: &[u8] = b"Received: from mx.domain.tld by mail.domain.tld;\r\n\
+        \tThu, 01 Jan 1970 00:00:00 +0000\r\n\
+        DKIM-Signature: v=1; a=rsa-sha256; d=domain.tld; s=default;\r\n \
+        h=from:to:subject; bh=AAAA; b=BBBB\r\n\
+        from: NoBody <nobody@domain.tld>\r\n\
+        To:   Hei <hei@domain.tld>\r\n\
+        Subject :  Happy\r\n   new year\r\n\
+        \r\n\
+        Be happy!\r\n";
+
+    #[test]
+    fn parse_message() {
+        let message = Message::from_bytes(RAW).unwrap();
+        assert_eq!(message.formatted(), RAW);
+        assert_eq!(
+            message.headers().get::<header::Subject>(),
+            Some(header::Subject::from("Happy   new year".to_owned()))
+        );
+        assert_eq!(
+            message.envelope().to(),
+            &["hei@domain.tld".parse().unwrap()]
+        );
+        assert_eq!(message.headers().get_all_raw("Received").count(), 1);
+    }
+
+    #[test]
+    fn parse_message_added_headers() {
+        let mut message = Message::from_bytes(RAW).unwrap();
+        message.headers_mut().prepend(header::Received::new(
+            SystemTime::UNIX_EPOCH + Duration::from_secs(60),
+        ));
+        let formatted = message.formatted();
+        assert!(formatted.starts_with(b"Received: ; Thu, 01 Jan 1970 00:01:00 -0000\r\n"));
+        assert!(formatted.ends_with(RAW));
+
+        let resent = message
+            .resend()
+            .from("Hei <hei@domain.tld>".parse().unwrap())
+            .to("Yuin <yuin@domain.tld>".parse().unwrap())
+            .build()
+            .unwrap();
+        assert!(resent.formatted().starts_with(b"Resent-Date: "));
+        assert!(resent.formatted().ends_with(RAW));
+    }
+
+    #[test]
+    fn parse_message_changed_headers() {
+        let mut message = Message::from_bytes(RAW).unwrap();
+        message
+            .headers_mut()
+            .set(header::Subject::from("Happy new year".to_owned()));
+        let formatted = String::from_utf8(message.formatted()).unwrap();
+        assert!(formatted.contains("To:   Hei <hei@domain.tld>\r\nSubject: Happy new year\r\n\r\n"));
+
+        let mut message = Message::from_bytes(RAW).unwrap();
+        message.headers_mut().remove::<header::To>();
+        let formatted = String::from_utf8(message.formatted()).unwrap();
+        assert!(formatted.contains("from: NoBody <nobody@domain.tld>\r\nSubject :  Happy"));
+
+        let raw = [b"Return-Path: <nobody@domain.tld>\r\n", RAW].concat();
+        let message = Message::from_bytes(&raw).unwrap();
+        assert_eq!(message.formatted(), raw);
+        assert_eq!(message.formatted_for_submission(), RAW);
+        assert!(message.headers().get::<header::ReturnPath>().is_some());
+    }
+
+    #[test]
+    fn parse_message_malformed() {
+        assert!(matches!(
+            Message::from_bytes(b" folded\r\nTo: hei@domain.tld\r\n"),
+            Err(EmailError::MalformedHeader(1))
+        ));
+        assert!(matches!(
+            Message::from_bytes(b"From: nobody@domain.tld\r\nnot a header\r\n\r\nbody"),
+            Err(EmailError::MalformedHeader(2))
+        ));
+    }
+}
@@ -0,0 +1,403 @@
+use super::{
+    header::{self, ContentTransferEncoding, ContentType, Header, Headers},
+    mimebody::Part,
+    Mailbox, Mailboxes, Message, MessageBody, MessageBuilder,
+};
+use crate::Address;
+
+/// Subject prefixes recognized by default as marking a reply, in several languages
+const REPLY_PREFIXES: &[&str] = &[
+    "Re", "Aw", "Antw", "Sv", "Vs", "Rif", "Ref", "Réf", "Odp", "Ynt", "回复", "答复",
+];
+
+/// A builder for replying to a [`Message`]
+///
+/// Fills a [`MessageBuilder`] with the headers of the reply:
+///
+/// * `To`: the `Reply-To` mailboxes of the message, or its `From` mailboxes
+/// * `Cc`: when replying to all, the `To` and `Cc` mailboxes of the message, without
+///   your own addresses
+/// * `In-Reply-To`: the `Message-ID` of the message
+/// * `References`: the `References` of the message, followed by its `Message-ID`
+/// * `Subject`: the subject of the message, prefixed with `Re: ` unless it already
+///   starts with a reply prefix
+///
+/// Created with [`Message::reply`].
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use lettre::Message;
+///
+/// let email = Message::builder()
+///     .from("NoBody <nobody@domain.tld>".parse()?)
+///     .to("Hei <hei@domain.tld>".parse()?)
+///     .message_id(Some("<first@domain.tld>".to_owned()))
+///     .subject("Happy new year")
+///     .body(String::from("Be happy!"))?;
+///
+/// let reply = email.reply();
+/// let quote = reply.quoted_text().unwrap_or_default();
+/// let answer = reply
+///     .builder()
+///     .from("Hei <hei@domain.tld>".parse()?)
+///     .body(format!("You too!\n\n{}", quote))?;
+/// assert_eq!(answer.headers().get_raw("Subject"), Some("Re: Happy new year"));
+/// assert_eq!(answer.headers().get_raw("In-Reply-To"), Some("<first@domain.tld>"));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReplyBuilder<'a> {
+    message: &'a Message,
+    reply_all: bool,
+    own_addresses: Vec<Address>,
+    subject_prefixes: Vec<String>,
+}
+
+impl<'a> ReplyBuilder<'a> {
+    pub(super) fn new(message: &'a Message) -> Self {
+        Self {
+            message,
+            reply_all: false,
+            own_addresses: Vec::new(),
+            subject_prefixes: REPLY_PREFIXES.iter().map(|&p| p.to_owned()).collect(),
+        }
+    }
+
+    /// Reply to all the recipients of the message, keeping them as `Cc`
+    pub fn reply_all(mut self) -> Self {
+        self.reply_all = true;
+        self
+    }
+
+    /// Add one of your own addresses, left out of the recipients of the reply
+    pub fn own_address(mut self, address: Address) -> Self {
+        self.own_addresses.push(address);
+        self
+    }
+
+    /// Set the subject prefixes marking a reply, compared case-insensitively
+    ///
+    /// Defaults to `Re` and its translations in a few languages, like `Aw` or `Sv`.
+    /// A prefix is followed by a colon, and optionally by a counter, like `Re[2]:`.
+    pub fn subject_prefixes<I, T>(mut self, prefixes: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.subject_prefixes = prefixes.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// The plain text of the message, quoted with `> ` on each line
+    ///
+    /// `None` if the message has no plain text part, or if its charset isn't UTF-8.
+    pub fn quoted_text(&self) -> Option<String> {
+        let text = match &self.message.body {
+            MessageBody::Mime(part) => part_text(part)?,
+            MessageBody::Raw(body) => decode_text(&self.message.headers, body)?,
+        };
+
+        let quoted = text
+            .lines()
+            .map(|line| {
+                if line.is_empty() {
+                    ">".to_owned()
+                } else {
+                    format!("> {}", line)
+                }
+            })
+            .collect::<Vec<_>>();
+        Some(quoted.join("\n"))
+    }
+
+    /// Create a [`MessageBuilder`] with the headers of the reply
+    ///
+    /// The author, like the body, still has to be set.
+    pub fn builder(self) -> MessageBuilder {
+        let headers = &self.message.headers;
+        let mut builder = MessageBuilder::new();
+
+        let to: Vec<Mailbox> = match headers.get::<header::ReplyTo>() {
+            Some(header::ReplyTo(reply_to)) => reply_to.into(),
+            None => headers
+                .get::<header::From>()
+                .map(|header::From(from)| from.into())
+                .unwrap_or_default(),
+        };
+        if self.reply_all {
+            let mut cc = Mailboxes::new();
+            let recipients = vec![
+                headers.get::<header::To>().map(|h| h.0),
+                headers.get::<header::Cc>().map(|h| h.0),
+            ];
+            for mbox in recipients.into_iter().flatten().flatten() {
+                let known = to
+                    .iter()
+                    .chain(cc.iter())
+                    .any(|known| known.email == mbox.email);
+                if !known && !self.own_addresses.contains(&mbox.email) {
+                    cc.push(mbox);
+                }
+            }
+            if cc.iter().next().is_some() {
+                builder = builder.header(header::Cc(cc));
+            }
+        }
+        if !to.is_empty() {
+            builder = builder.header(header::To(to.into()));
+        }
+
+        if let Some(message_id) = headers.get_raw(&header::MessageId::name()) {
+            builder = builder.in_reply_to(message_id.trim().to_owned());
+        }
+        if let Some(references) = references(headers) {
+            builder = builder.references(references);
+        }
+
+        let subject = headers
+            .get::<header::Subject>()
+            .map(|subject| subject.as_ref().to_owned())
+            .unwrap_or_default();
+        if has_subject_prefix(&subject, &self.subject_prefixes) {
+            builder.subject(subject)
+        } else {
+            builder.subject(format!("Re: {}", subject))
+        }
+    }
+}
+
+/// The `References` of a response to a message: the `References` of the message,
+/// followed by its `Message-ID`
+///
+/// `None` if the message has no `Message-ID`.
+pub(super) fn references(headers: &Headers) -> Option<String> {
+    let message_id = headers.get_raw(&header::MessageId::name())?.trim();
+    let references = headers
+        .get_raw(&header::References::name())
+        .or_else(|| headers.get_raw(&header::InReplyTo::name()))
+        .map(str::trim)
+        .filter(|references| !references.is_empty());
+    Some(match references {
+        Some(references) => format!("{} {}", references, message_id),
+        None => message_id.to_owned(),
+    })
+}
+
+/// Checks whether `subject` starts with one of `prefixes`, compared case-insensitively
+pub(super) fn has_subject_prefix(subject: &str, prefixes: &[String]) -> bool {
+    let subject = subject.trim_start();
+    prefixes.iter().any(|prefix| {
+        let rest = match subject.get(..prefix.len()) {
+            Some(start) if start.eq_ignore_ascii_case(prefix) => &subject[prefix.len()..],
+            _ => return false,
+        };
+        // Skip a counter like `Re[2]:` or `Re(2):`
+        let rest = match rest.chars().next() {
+            Some(open @ '[') | Some(open @ '(') => {
+                let close = if open == '[' { ']' } else { ')' };
+                match rest[1..].find(close) {
+                    Some(end) if rest[1..1 + end].chars().all(|c| c.is_ascii_digit()) => {
+                        &rest[end + 2..]
+                    }
+                    _ => return false,
+                }
+            }
+            _ => rest,
+        };
+        let rest = rest.trim_start();
+        rest.starts_with(':') || rest.starts_with('：')
+    })
+}
+
+/// The plain text of the first `text/plain` part which isn't an attachment
+pub(super) fn part_text(part: &Part) -> Option<String> {
+    match part {
+        Part::Single(part) if is_inline(part.headers()) => {
+            decode_text(part.headers(), part.raw_body())
+        }
+        Part::Single(_) | Part::Frozen(_) => None,
+        Part::Multi(part) => part.parts().iter().find_map(part_text),
+    }
+}
+
+/// Checks that the part isn't an attachment
+pub(super) fn is_inline(headers: &Headers) -> bool {
+    headers
+        .get_raw(&header::ContentDisposition::name())
+        .map_or(true, |disposition| {
+            !disposition
+                .trim_start()
+                .get(..10)
+                .map_or(false, |kind| kind.eq_ignore_ascii_case("attachment"))
+        })
+}
+
+/// Decodes a `text/plain` body in UTF-8, or in ASCII
+pub(super) fn decode_text(headers: &Headers, body: &[u8]) -> Option<String> {
+    // Bodies without `Content-Type` are ASCII plain text
+    if let Some(content_type) = headers.get::<ContentType>() {
+        let charset = content_type.get_param("charset").unwrap_or("us-ascii");
+        if content_type.essence() != "text/plain"
+            || !(charset.eq_ignore_ascii_case("utf-8") || charset.eq_ignore_ascii_case("us-ascii"))
+        {
+            return None;
+        }
+    }
+
+    let decoded = match headers.get::<ContentTransferEncoding>() {
+        Some(ContentTransferEncoding::QuotedPrintable) => {
+            quoted_printable::decode(body, quoted_printable::ParseMode::Robust).ok()?
+        }
+        Some(ContentTransferEncoding::Base64) => {
+            let encoded: Vec<u8> = body
+                .iter()
+                .copied()
+                .filter(|b| !b.is_ascii_whitespace())
+                .collect();
+            base64::decode(encoded).ok()?
+        }
+        _ => body.to_vec(),
+    };
+    String::from_utf8(decoded).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::ReplyBuilder;
+    use crate::message::{header, Message, MultiPart, SinglePart};
+
+    fn message() -> Message {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .to("Yuin <yuin@domain.tld>".parse().unwrap())
+            .cc("Alice <alice@domain.tld>".parse().unwrap())
+            .message_id(Some("<second@domain.tld>".to_owned()))
+            .references("<first@domain.tld>".to_owned())
+            .subject("Happy new year")
+            .multipart(MultiPart::alternative_plain_html(
+                String::from("Be happy!\n\nNoBody"),
+                String::from("<p>Be happy!</p>"),
+            ))
+            .unwrap()
+    }
+
+    #[test]
+    fn reply_headers() {
+        let reply = message()
+            .reply()
+            .builder()
+            .from("Hei <hei@domain.tld>".parse().unwrap())
+            .body(String::from("You too!"))
+            .unwrap();
+        let headers = reply.headers();
+
+        assert_eq!(headers.get_raw("To"), Some("NoBody <nobody@domain.tld>"));
+        assert_eq!(headers.get_raw("Cc"), None);
+        assert_eq!(headers.get_raw("In-Reply-To"), Some("<second@domain.tld>"));
+        assert_eq!(
+            headers.get_raw("References"),
+            Some("<first@domain.tld> <second@domain.tld>")
+        );
+        assert_eq!(headers.get_raw("Subject"), Some("Re: Happy new year"));
+    }
+
+    #[test]
+    fn reply_all() {
+        let mut message = message();
+        message
+            .headers_mut()
+            .set(header::ReplyTo("List <list@domain.tld>".parse().unwrap()));
+
+        let builder = message
+            .reply()
+            .reply_all()
+            .own_address("hei@domain.tld".parse().unwrap())
+            .builder();
+        let reply = builder
+            .from("Hei <hei@domain.tld>".parse().unwrap())
+            .body(String::from("You too!"))
+            .unwrap();
+
+        assert_eq!(
+            reply.headers().get_raw("To"),
+            Some("List <list@domain.tld>")
+        );
+        assert_eq!(
+            reply.headers().get_raw("Cc"),
+            Some("Yuin <yuin@domain.tld>, Alice <alice@domain.tld>")
+        );
+    }
+
+    #[test]
+    fn reply_subject_prefix() {
+        for (subject, expected) in &[
+            ("Re: Happy new year", "Re: Happy new year"),
+            ("RE : Happy new year", "RE : Happy new year"),
+            ("Re[2]: Happy new year", "Re[2]: Happy new year"),
+            ("AW: Frohes neues Jahr", "AW: Frohes neues Jahr"),
+            ("Réf : Bonne année", "Réf : Bonne année"),
+            ("Regards", "Re: Regards"),
+            ("Re(a): Happy new year", "Re: Re(a): Happy new year"),
+        ] {
+            let mut message = message();
+            message
+                .headers_mut()
+                .set(header::Subject::from(subject.to_string()));
+            let reply = message.reply().builder();
+            let reply = reply
+                .from("Hei <hei@domain.tld>".parse().unwrap())
+                .body(String::new())
+                .unwrap();
+            assert_eq!(reply.headers().get_raw("Subject"), Some(*expected));
+        }
+
+        let mut message = message();
+        message.headers_mut().set(header::Subject::from(
+            "Antwort: Frohes neues Jahr".to_owned(),
+        ));
+        let subject = |reply: ReplyBuilder<'_>| {
+            let reply = reply
+                .builder()
+                .from("Hei <hei@domain.tld>".parse().unwrap())
+                .body(String::new())
+                .unwrap();
+            reply.headers().get_raw("Subject").map(str::to_owned)
+        };
+        assert_eq!(
+            subject(message.reply()).as_deref(),
+            Some("Re: Antwort: Frohes neues Jahr")
+        );
+        assert_eq!(
+            subject(message.reply().subject_prefixes(vec!["Antwort"])).as_deref(),
+            Some("Antwort: Frohes neues Jahr")
+        );
+    }
+
+    #[test]
+    fn reply_quoted_text() {
+        assert_eq!(
+            message().reply().quoted_text().as_deref(),
+            Some("> Be happy!\n>\n> NoBody")
+        );
+
+        let message = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .singlepart(SinglePart::html(String::from("<p>Be happy!</p>")))
+            .unwrap();
+        assert_eq!(message.reply().quoted_text(), None);
+
+        let message = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .body(String::from("Hyvää uutta vuotta"))
+            .unwrap();
+        assert_eq!(
+            message.reply().quoted_text().as_deref(),
+            Some("> Hyvää uutta vuotta")
+        );
+    }
+}
@@ -0,0 +1,271 @@
+use std::time::SystemTime;
+
+use super::{generate_message_id, header, Mailbox, Message};
+use crate::{
+    address::{dedup_recipients, Envelope},
+    message::header::{Header, Headers, MailboxesHeader},
+    Error as EmailError,
+};
+
+/// A builder for resending a [`Message`]
+///
+/// Resending a message keeps its headers unchanged, and adds a block of
+/// `Resent-*` headers at the top, as defined in
+/// [RFC 5322](https://tools.ietf.org/html/rfc5322#section-3.6.6). The envelope is
+/// computed from the `Resent-*` addresses: the message is sent to the `Resent-To`,
+/// `Resent-Cc` and `Resent-Bcc` addresses, and not to its original recipients.
+///
+/// A message can be resent several times, each block is added above the previous ones.
+/// Created with [`Message::resend`].
+///
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// use lettre::Message;
+///
+/// let email = Message::builder()
+///     .from("NoBody <nobody@domain.tld>".parse()?)
+///     .to("Hei <hei@domain.tld>".parse()?)
+///     .subject("Happy new year")
+///     .body(String::from("Be happy!"))?;
+///
+/// let resent = email
+///     .resend()
+///     .from("Hei <hei@domain.tld>".parse()?)
+///     .to("Yuin <yuin@domain.tld>".parse()?)
+///     .message_id(None)
+///     .build()?;
+/// assert_eq!(resent.envelope().to(), &["yuin@domain.tld".parse()?]);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct ResentBuilder {
+    message: Message,
+    headers: Headers,
+}
+
+impl ResentBuilder {
+    pub(super) fn new(message: Message) -> Self {
+        Self {
+            message,
+            headers: Headers::new(),
+        }
+    }
+
+    fn header<H: Header>(mut self, header: H) -> Self {
+        self.headers.set(header);
+        self
+    }
+
+    fn mailbox<H: Header + MailboxesHeader>(self, header: H) -> Self {
+        match self.headers.get::<H>() {
+            Some(mut header_) => {
+                header_.join_mailboxes(header);
+                self.header(header_)
+            }
+            None => self.header(header),
+        }
+    }
+
+    /// Set `Resent-Date` header, defaults to the current date/time
+    pub fn date(self, st: SystemTime) -> Self {
+        self.header(header::ResentDate::new(st))
+    }
+
+    /// Set `Resent-Sender` header. Should be used when providing several
+    /// `Resent-From` mailboxes.
+    pub fn sender(self, mbox: Mailbox) -> Self {
+        self.header(header::ResentSender::from(mbox))
+    }
+
+    /// Add `Resent-From` mailbox, the author of the resending
+    pub fn from(self, mbox: Mailbox) -> Self {
+        self.mailbox(header::ResentFrom(mbox.into()))
+    }
+
+    /// Add `Resent-To` mailbox
+    pub fn to(self, mbox: Mailbox) -> Self {
+        self.mailbox(header::ResentTo(mbox.into()))
+    }
+
+    /// Add `Resent-Cc` mailbox
+    pub fn cc(self, mbox: Mailbox) -> Self {
+        self.mailbox(header::ResentCc(mbox.into()))
+    }
+
+    /// Add `Resent-Bcc` mailbox, only kept in the envelope
+    pub fn bcc(self, mbox: Mailbox) -> Self {
+        self.mailbox(header::ResentBcc(mbox.into()))
+    }
+
+    /// Set `Resent-Message-ID` header
+    ///
+    /// If `None` is provided, an id will be generated in the
+    /// `<UUID@HOSTNAME>` format.
+    pub fn message_id(self, id: Option<String>) -> Self {
+        let id = id.unwrap_or_else(generate_message_id);
+        self.header(header::ResentMessageId::from(id))
+    }
+
+    /// Add the `Resent-*` headers to the message, and compute its new envelope
+    ///
+    /// Fails if there is no `Resent-From` mailbox, if there are several of them without
+    /// a `Resent-Sender`, or if there is no recipient.
+    pub fn build(self) -> Result<Message, EmailError> {
+        let mut headers = self.headers;
+        if headers.has_line_breaks() {
+            return Err(EmailError::HeaderLineBreak);
+        }
+
+        let envelope = resent_envelope(&headers)?;
+        headers.remove_raw(&header::ResentBcc::name());
+
+        // The date comes first, as the trace of the resending
+        let date = headers
+            .remove::<header::ResentDate>()
+            .unwrap_or_else(header::ResentDate::now);
+        let mut block = Headers::new();
+        block.set(date);
+        headers.prepend_block(block);
+
+        let mut message = self.message;
+        message.headers.prepend_block(headers);
+        message.envelope = envelope;
+        Ok(message)
+    }
+}
+
+/// Envelope of a message sent to the `Resent-*` addresses
+fn resent_envelope(headers: &Headers) -> Result<Envelope, EmailError> {
+    let from: Vec<Mailbox> = match headers.get::<header::ResentFrom>() {
+        Some(header::ResentFrom(from)) => from.into(),
+        None => return Err(EmailError::MissingFrom),
+    };
+    let sender = match headers.get::<header::ResentSender>() {
+        Some(sender) => Mailbox::from(sender),
+        None if from.len() > 1 => return Err(EmailError::TooManyFrom),
+        None => from.into_iter().next().ok_or(EmailError::MissingFrom)?,
+    };
+
+    let mut to = Vec::new();
+    let recipients = vec![
+        headers.get::<header::ResentTo>().map(|h| h.0),
+        headers.get::<header::ResentCc>().map(|h| h.0),
+        headers.get::<header::ResentBcc>().map(|h| h.0),
+    ];
+    for mailboxes in recipients.into_iter().flatten() {
+        to.extend(mailboxes.into_iter().map(|mb| mb.email));
+    }
+    dedup_recipients(&mut to);
+
+    Envelope::new(Some(sender.email), to)
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, SystemTime};
+
+    use crate::{message::Message, Address, Error as EmailError};
+
+    fn email() -> Message {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .date(SystemTime::UNIX_EPOCH)
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap()
+    }
+
+    #[test]
+    fn resent_block() {
+        let email = email();
+        let resent = email
+            .resend()
+            .from("Hei <hei@domain.tld>".parse().unwrap())
+            .to("Yuin <yuin@domain.tld>".parse().unwrap())
+            .bcc("hidden@domain.tld".parse().unwrap())
+            .date(SystemTime::UNIX_EPOCH + Duration::from_secs(784887151))
+            .message_id(Some("<resent@domain.tld>".to_owned()))
+            .build()
+            .unwrap();
+
+        let to: Vec<Address> = vec![
+            "yuin@domain.tld".parse().unwrap(),
+            "hidden@domain.tld".parse().unwrap(),
+        ];
+        assert_eq!(resent.envelope().to(), &to[..]);
+        assert_eq!(
+            resent.envelope().from(),
+            Some(&"hei@domain.tld".parse().unwrap())
+        );
+
+        let formatted = String::from_utf8(resent.formatted()).unwrap();
+        let original = String::from_utf8(email.formatted()).unwrap();
+        assert_eq!(
+            formatted,
+            format!(
+                concat!(
+                    "Resent-Date: Tue, 15 Nov 1994 08:12:31 -0000\r\n",
+                    "Resent-From: Hei <hei@domain.tld>\r\n",
+                    "Resent-To: Yuin <yuin@domain.tld>\r\n",
+                    "Resent-Message-ID: <resent@domain.tld>\r\n",
+                    "{}"
+                ),
+                original
+            )
+        );
+    }
+
+    #[test]
+    fn resent_twice() {
+        let resent = email()
+            .resend()
+            .from("hei@domain.tld".parse().unwrap())
+            .to("yuin@domain.tld".parse().unwrap())
+            .build()
+            .unwrap()
+            .resend()
+            .from("yuin@domain.tld".parse().unwrap())
+            .to("kai@domain.tld".parse().unwrap())
+            .build()
+            .unwrap();
+
+        let formatted = String::from_utf8(resent.formatted()).unwrap();
+        let first = formatted.find("Resent-To: kai@domain.tld").unwrap();
+        let second = formatted.find("Resent-To: yuin@domain.tld").unwrap();
+        assert!(first < second);
+        assert_eq!(
+            resent.envelope().to(),
+            &["kai@domain.tld".parse::<Address>().unwrap()]
+        );
+    }
+
+    #[test]
+    fn resent_invalid() {
+        assert!(matches!(
+            email()
+                .resend()
+                .to("yuin@domain.tld".parse().unwrap())
+                .build(),
+            Err(EmailError::MissingFrom)
+        ));
+        assert!(matches!(
+            email()
+                .resend()
+                .from("hei@domain.tld".parse().unwrap())
+                .from("yuin@domain.tld".parse().unwrap())
+                .to("kai@domain.tld".parse().unwrap())
+                .build(),
+            Err(EmailError::TooManyFrom)
+        ));
+        assert!(matches!(
+            email()
+                .resend()
+                .from("hei@domain.tld".parse().unwrap())
+                .build(),
+            Err(EmailError::MissingTo)
+        ));
+    }
+}
@@ -0,0 +1,946 @@
+//! Mock SMTP server for integration tests
+//!
+//! [`MockSmtpServer`] listens on an ephemeral local port and speaks enough SMTP to accept
+//! emails from the SMTP transports: `EHLO`, `STARTTLS`, `AUTH PLAIN` and `AUTH LOGIN`,
+//! `MAIL`, `RCPT`, `DATA`, `RSET`, `NOOP` and `QUIT`. It records the received messages and
+//! the full command transcript, so tests can make assertions on them.
+//!
+//! The replies can be scripted, to return a specific code or drop the connection at the _n_-th
+//! occurrence of a step, counted over all connections.
+//!
+//! `STARTTLS` is only available with the `native-tls` feature, once a certificate and its
+//! private key are given with [`MockSmtpServerBuilder::tls_identity`]. Where `native-tls`
+//! uses OpenSSL, [`MockSmtpServerBuilder::self_signed_tls`] generates a self-signed
+//! certificate instead. With a self-signed certificate, clients have to accept invalid
+//! certificates.
+//!
+//! #### Example
+//!
+//! ```rust
+//! # #[cfg(all(feature = "builder", feature = "smtp-transport"))]
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use lettre::{
+//!     test_server::{Action, MockSmtpServer, Step},
+//!     Message, SmtpTransport, Transport,
+//! };
+//!
+//! let server = MockSmtpServer::builder()
+//!     .on(Step::Rcpt, 2, Action::Reply(451, "4.3.0 Try again later".to_owned()))
+//!     .start()?;
+//!
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .to("Yuin <yuin@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+//!     .port(server.port())
+//!     .build();
+//! assert!(sender.send(&email).is_err());
+//! assert!(server.messages().is_empty());
+//! # Ok(())
+//! # }
+//! # #[cfg(not(all(feature = "builder", feature = "smtp-transport")))]
+//! # fn main() {}
+//! ```
+
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread,
+    time::Duration,
+};
+
+/// Step of a SMTP session, to which a scripted [`Action`] applies
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Step {
+    /// Greeting sent when the client connects
+    Greeting,
+    /// `EHLO` or `HELO` command
+    Ehlo,
+    /// `STARTTLS` command
+    StartTls,
+    /// `AUTH` command
+    Auth,
+    /// `XCLIENT` command
+    XClient,
+    /// `MAIL FROM` command
+    Mail,
+    /// `RCPT TO` command
+    Rcpt,
+    /// `DATA` command
+    Data,
+    /// End of the message content
+    Message,
+    /// `RSET` command
+    Rset,
+    /// `NOOP` command
+    Noop,
+    /// `QUIT` command
+    Quit,
+}
+
+/// What the server does at a scripted step, instead of the default reply
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Action {
+    /// Reply with the given code and message
+    Reply(u16, String),
+    /// Close the connection without replying
+    Disconnect,
+    /// Wait before sending the default reply
+    Delay(Duration),
+    /// Send the default reply one byte at a time, waiting between the bytes
+    Trickle(Duration),
+}
+
+/// Line of the transcript of a SMTP session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TranscriptLine {
+    /// Line received from the client, without the line ending
+    Client(String),
+    /// Line sent by the server, without the line ending
+    Server(String),
+}
+
+/// Message accepted by the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReceivedMessage {
+    from: Option<String>,
+    to: Vec<String>,
+    data: Vec<u8>,
+}
+
+impl ReceivedMessage {
+    /// Reverse-path given in `MAIL FROM`, `None` if it was empty
+    pub fn from(&self) -> Option<&str> {
+        self.from.as_deref()
+    }
+
+    /// Forward-paths accepted in `RCPT TO`
+    pub fn to(&self) -> &[String] {
+        &self.to
+    }
+
+    /// Content of the message, after removing the dot-stuffing
+    pub fn data(&self) -> &[u8] {
+        &self.data
+    }
+}
+
+#[derive(Debug)]
+struct Config {
+    name: String,
+    keywords: Vec<String>,
+    credentials: Option<(String, String)>,
+    login_prompts: Vec<String>,
+    script: Vec<(Step, usize, Action)>,
+    /// PEM encoded certificate chain and PKCS #8 private key used after `STARTTLS`
+    #[cfg(feature = "native-tls")]
+    identity: Option<(String, String)>,
+    /// Generate a self-signed identity when starting the server
+    #[cfg(all(
+        feature = "native-tls",
+        not(any(target_os = "windows", target_vendor = "apple"))
+    ))]
+    self_signed: bool,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    connections: usize,
+    messages: Vec<ReceivedMessage>,
+    transcript: Vec<TranscriptLine>,
+    steps: HashMap<Step, usize>,
+}
+
+#[derive(Debug)]
+struct Shared {
+    config: Config,
+    state: Mutex<State>,
+}
+
+impl Shared {
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("mock SMTP server state poisoned")
+    }
+
+    /// Counts an occurrence of `step`, and returns the scripted action for it
+    fn action(&self, step: Step) -> Option<Action> {
+        let mut state = self.state();
+        let count = state.steps.entry(step).or_insert(0);
+        *count += 1;
+        let count = *count;
+
+        self.config
+            .script
+            .iter()
+            .find(|(s, n, _)| *s == step && *n == count)
+            .map(|(_, _, action)| action.clone())
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    addr: SocketAddr,
+    shared: Arc<Shared>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl Drop for Inner {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake up the listener thread
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+/// Mock SMTP server listening on an ephemeral local port
+///
+/// The server stops accepting connections when the last clone is dropped.
+#[derive(Debug, Clone)]
+pub struct MockSmtpServer {
+    inner: Arc<Inner>,
+}
+
+impl MockSmtpServer {
+    /// Starts a server with the default configuration
+    pub fn start() -> io::Result<MockSmtpServer> {
+        Self::builder().start()
+    }
+
+    /// Creates a builder to configure the server
+    pub fn builder() -> MockSmtpServerBuilder {
+        MockSmtpServerBuilder::new()
+    }
+
+    /// Address the server listens on
+    pub fn addr(&self) -> SocketAddr {
+        self.inner.addr
+    }
+
+    /// Port the server listens on
+    pub fn port(&self) -> u16 {
+        self.inner.addr.port()
+    }
+
+    /// Number of connections accepted so far
+    pub fn connections(&self) -> usize {
+        self.inner.shared.state().connections
+    }
+
+    /// Messages accepted so far, in the order they were received
+    pub fn messages(&self) -> Vec<ReceivedMessage> {
+        self.inner.shared.state().messages.clone()
+    }
+
+    /// Lines exchanged so far, over all connections
+    ///
+    /// The content of the messages isn't included.
+    pub fn transcript(&self) -> Vec<TranscriptLine> {
+        self.inner.shared.state().transcript.clone()
+    }
+
+    /// Lines received from the clients so far, over all connections
+    pub fn commands(&self) -> Vec<String> {
+        self.transcript()
+            .into_iter()
+            .filter_map(|line| match line {
+                TranscriptLine::Client(line) => Some(line),
+                TranscriptLine::Server(_) => None,
+            })
+            .collect()
+    }
+
+    /// Handles a SMTP session over a stream accepted by the caller, like a Unix domain socket
+    ///
+    /// Returns when the session ends.
+    pub fn serve<S: Read + Write>(&self, stream: S) -> io::Result<()> {
+        Session::run(&self.inner.shared, stream)
+    }
+}
+
+/// Builder for the [`MockSmtpServer`]
+#[derive(Debug)]
+pub struct MockSmtpServerBuilder {
+    config: Config,
+}
+
+impl MockSmtpServerBuilder {
+    fn new() -> Self {
+        Self {
+            config: Config {
+                name: "localhost".to_owned(),
+                keywords: vec![
+                    "8BITMIME".to_owned(),
+                    "SMTPUTF8".to_owned(),
+                    "AUTH PLAIN LOGIN".to_owned(),
+                ],
+                credentials: None,
+                login_prompts: vec!["VXNlcm5hbWU6".to_owned(), "UGFzc3dvcmQ6".to_owned()],
+                script: Vec::new(),
+                #[cfg(feature = "native-tls")]
+                identity: None,
+                #[cfg(all(
+                    feature = "native-tls",
+                    not(any(target_os = "windows", target_vendor = "apple"))
+                ))]
+                self_signed: false,
+            },
+        }
+    }
+
+    /// Set the name sent in the greeting and in the `EHLO` reply
+    pub fn name<T: Into<String>>(mut self, name: T) -> Self {
+        self.config.name = name.into();
+        self
+    }
+
+    /// Set the keywords advertised in the `EHLO` reply
+    ///
+    /// Defaults to `8BITMIME`, `SMTPUTF8`, `AUTH PLAIN LOGIN`, and `STARTTLS` once a TLS
+    /// identity is set. `STARTTLS` is no longer advertised once the connection is
+    /// encrypted. `XCLIENT` is only accepted when advertised.
+    pub fn ehlo_keywords<I, T>(mut self, keywords: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.config.keywords = keywords.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Only accept these credentials
+    ///
+    /// Any credentials are accepted by default.
+    pub fn credentials<U: Into<String>, P: Into<String>>(
+        mut self,
+        username: U,
+        password: P,
+    ) -> Self {
+        self.config.credentials = Some((username.into(), password.into()));
+        self
+    }
+
+    /// Set the challenges sent for `AUTH LOGIN`, as sent on the wire
+    ///
+    /// Defaults to `Username:` and `Password:`, base64 encoded. The password is read from
+    /// the answer to the challenge decoding to a text starting with `pass`, or to the
+    /// last one, and the username from the first other answer.
+    pub fn login_prompts<I, T>(mut self, prompts: I) -> Self
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<String>,
+    {
+        self.config.login_prompts = prompts.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Use this certificate chain and private key after `STARTTLS`, and advertise it
+    ///
+    /// The certificates are PEM encoded, starting with the one of the server, and the
+    /// key is a PEM encoded PKCS #8 key.
+    #[cfg(feature = "native-tls")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "native-tls")))]
+    pub fn tls_identity<C: Into<String>, K: Into<String>>(
+        mut self,
+        certificates: C,
+        key: K,
+    ) -> Self {
+        self.config.identity = Some((certificates.into(), key.into()));
+        self.advertise_starttls();
+        self
+    }
+
+    /// Use a self-signed certificate for `localhost` and `127.0.0.1` after `STARTTLS`,
+    /// and advertise it
+    ///
+    /// The certificate and its key are generated when starting the server.
+    #[cfg(all(
+        feature = "native-tls",
+        not(any(target_os = "windows", target_vendor = "apple"))
+    ))]
+    #[cfg_attr(docsrs, doc(cfg(feature = "native-tls")))]
+    pub fn self_signed_tls(mut self) -> Self {
+        self.config.self_signed = true;
+        self.advertise_starttls();
+        self
+    }
+
+    #[cfg(feature = "native-tls")]
+    fn advertise_starttls(&mut self) {
+        if !self
+            .config
+            .keywords
+            .iter()
+            .any(|keyword| keyword == "STARTTLS")
+        {
+            self.config.keywords.push("STARTTLS".to_owned());
+        }
+    }
+
+    /// Do `action` at the `nth` occurrence of `step`, counting from 1 over all connections
+    pub fn on(mut self, step: Step, nth: usize, action: Action) -> Self {
+        self.config.script.push((step, nth, action));
+        self
+    }
+
+    /// Binds an ephemeral local port and starts accepting connections
+    pub fn start(self) -> io::Result<MockSmtpServer> {
+        #[allow(unused_mut)]
+        let mut config = self.config;
+        #[cfg(all(
+            feature = "native-tls",
+            not(any(target_os = "windows", target_vendor = "apple"))
+        ))]
+        if config.self_signed {
+            config.identity = Some(self_signed_identity().map_err(other)?);
+        }
+
+        let listener = TcpListener::bind(("127.0.0.1", 0))?;
+        let addr = listener.local_addr()?;
+        let shared = Arc::new(Shared {
+            config,
+            state: Mutex::new(State::default()),
+        });
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let listener_shared = Arc::clone(&shared);
+        let listener_shutdown = Arc::clone(&shutdown);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if listener_shutdown.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let shared = Arc::clone(&listener_shared);
+                    thread::spawn(move || Session::run(&shared, stream));
+                }
+            }
+        });
+
+        Ok(MockSmtpServer {
+            inner: Arc::new(Inner {
+                addr,
+                shared,
+                shutdown,
+            }),
+        })
+    }
+}
+
+/// Stream of a session, encrypted after `STARTTLS`
+enum Stream<S: Read + Write> {
+    Plain(S),
+    #[cfg(feature = "native-tls")]
+    Tls(Box<native_tls::TlsStream<S>>),
+}
+
+impl<S: Read + Write> Stream<S> {
+    #[cfg(feature = "native-tls")]
+    fn upgrade(self, config: &Config) -> io::Result<Self> {
+        let stream = match self {
+            Stream::Plain(stream) => stream,
+            Stream::Tls(_) => return Err(other("the stream is already encrypted")),
+        };
+        let (certificates, key) = config
+            .identity
+            .as_ref()
+            .ok_or_else(|| other("no TLS identity was set"))?;
+        let identity = native_tls::Identity::from_pkcs8(certificates.as_bytes(), key.as_bytes())
+            .map_err(other)?;
+        let acceptor = native_tls::TlsAcceptor::new(identity).map_err(other)?;
+        let stream = acceptor.accept(stream).map_err(|err| match err {
+            native_tls::HandshakeError::Failure(err) => other(err),
+            native_tls::HandshakeError::WouldBlock(_) => other("TLS handshake interrupted"),
+        })?;
+        Ok(Stream::Tls(Box::new(stream)))
+    }
+
+    #[cfg(not(feature = "native-tls"))]
+    fn upgrade(self, _config: &Config) -> io::Result<Self> {
+        Err(other("TLS isn't available"))
+    }
+
+    fn is_encrypted(&self) -> bool {
+        match self {
+            Stream::Plain(_) => false,
+            #[cfg(feature = "native-tls")]
+            Stream::Tls(_) => true,
+        }
+    }
+}
+
+impl<S: Read + Write> Read for Stream<S> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.read(buf),
+            #[cfg(feature = "native-tls")]
+            Stream::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+impl<S: Read + Write> Write for Stream<S> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Stream::Plain(stream) => stream.write(buf),
+            #[cfg(feature = "native-tls")]
+            Stream::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Stream::Plain(stream) => stream.flush(),
+            #[cfg(feature = "native-tls")]
+            Stream::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+/// A SMTP session with a client
+struct Session<'a, S: Read + Write> {
+    shared: &'a Shared,
+    reader: BufReader<Stream<S>>,
+    from: Option<Option<String>>,
+    to: Vec<String>,
+}
+
+impl<'a, S: Read + Write> Session<'a, S> {
+    fn run(shared: &'a Shared, stream: S) -> io::Result<()> {
+        shared.state().connections += 1;
+        let mut session = Session {
+            shared,
+            reader: BufReader::new(Stream::Plain(stream)),
+            from: None,
+            to: Vec::new(),
+        };
+
+        let greeting = format!("{} ESMTP", shared.config.name);
+        if session.respond(Step::Greeting, 220, &[greeting])?.is_none() {
+            return Ok(());
+        }
+
+        while let Some(line) = session.read_command()? {
+            let (verb, argument) = match line.find(' ') {
+                Some(idx) => (&line[..idx], line[idx + 1..].trim()),
+                None => (line.as_str(), ""),
+            };
+            let verb = verb.to_ascii_uppercase();
+
+            let replied = match verb.as_str() {
+                // PROXY protocol header, which doesn't get a reply
+                "PROXY" => Some(0),
+                "EHLO" | "HELO" => session.ehlo(verb == "EHLO")?,
+                "STARTTLS" => {
+                    let code = session.starttls()?;
+                    if code == Some(220) {
+                        session = session.upgrade()?;
+                    }
+                    code
+                }
+                "AUTH" => session.auth(argument)?,
+                "XCLIENT" if session.advertises("XCLIENT") => {
+                    session.reset();
+                    let greeting = format!("{} ESMTP", shared.config.name);
+                    session.respond(Step::XClient, 220, &[greeting])?
+                }
+                "MAIL" => session.mail(argument)?,
+                "RCPT" => session.rcpt(argument)?,
+                "DATA" => session.data()?,
+                "RSET" => {
+                    session.reset();
+                    session.respond(Step::Rset, 250, &["2.0.0 OK".to_owned()])?
+                }
+                "NOOP" => session.respond(Step::Noop, 250, &["2.0.0 OK".to_owned()])?,
+                "QUIT" => {
+                    session.respond(Step::Quit, 221, &["2.0.0 Bye".to_owned()])?;
+                    None
+                }
+                _ => session.reply(502, &["5.5.2 Command not recognized".to_owned()])?,
+            };
+            if replied.is_none() {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn record(&self, line: TranscriptLine) {
+        self.shared.state().transcript.push(line);
+    }
+
+    fn reset(&mut self) {
+        self.from = None;
+        self.to.clear();
+    }
+
+    fn advertises(&self, keyword: &str) -> bool {
+        self.shared
+            .config
+            .keywords
+            .iter()
+            .any(|k| k.split_whitespace().next() == Some(keyword))
+    }
+
+    /// Reads a line, without the line ending
+    fn read_line(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut line = Vec::new();
+        if self.reader.read_until(b'\n', &mut line)? == 0 {
+            return Ok(None);
+        }
+        while line.last() == Some(&b'\n') || line.last() == Some(&b'\r') {
+            line.pop();
+        }
+        Ok(Some(line))
+    }
+
+    /// Reads a line from the client, and adds it to the transcript
+    fn read_command(&mut self) -> io::Result<Option<String>> {
+        let line = match self.read_line()? {
+            Some(line) => String::from_utf8_lossy(&line).into_owned(),
+            None => return Ok(None),
+        };
+        self.record(TranscriptLine::Client(line.clone()));
+        Ok(Some(line))
+    }
+
+    /// Sends a reply, and returns its code
+    fn reply(&mut self, code: u16, lines: &[String]) -> io::Result<Option<u16>> {
+        let reply = self.format_reply(code, lines);
+        self.reader.get_mut().write_all(reply.as_bytes())?;
+        Ok(Some(code))
+    }
+
+    /// Sends a reply one byte at a time, waiting `delay` between the bytes
+    fn trickle(&mut self, code: u16, lines: &[String], delay: Duration) -> io::Result<Option<u16>> {
+        let reply = self.format_reply(code, lines);
+        for byte in reply.as_bytes() {
+            thread::sleep(delay);
+            let stream = self.reader.get_mut();
+            stream.write_all(&[*byte])?;
+            stream.flush()?;
+        }
+        Ok(Some(code))
+    }
+
+    /// Formats a reply, and records its lines
+    fn format_reply(&mut self, code: u16, lines: &[String]) -> String {
+        let mut reply = String::new();
+        for (idx, line) in lines.iter().enumerate() {
+            let separator = if idx + 1 == lines.len() { ' ' } else { '-' };
+            let line = format!("{}{}{}", code, separator, line);
+            reply.push_str(&line);
+            reply.push_str("\r\n");
+            self.record(TranscriptLine::Server(line));
+        }
+        reply
+    }
+
+    /// Sends the default reply for the step, unless the script says otherwise
+    ///
+    /// Returns the code of the reply, or `None` if the connection must be closed.
+    fn respond(&mut self, step: Step, code: u16, lines: &[String]) -> io::Result<Option<u16>> {
+        match self.shared.action(step) {
+            Some(Action::Reply(code, message)) => self.reply(code, &[message]),
+            Some(Action::Disconnect) => Ok(None),
+            Some(Action::Delay(delay)) => {
+                thread::sleep(delay);
+                self.reply(code, lines)
+            }
+            Some(Action::Trickle(delay)) => self.trickle(code, lines, delay),
+            None => self.reply(code, lines),
+        }
+    }
+
+    fn ehlo(&mut self, extended: bool) -> io::Result<Option<u16>> {
+        self.reset();
+        let mut lines = vec![self.shared.config.name.clone()];
+        if extended {
+            let encrypted = self.reader.get_ref().is_encrypted();
+            lines.extend(
+                self.shared
+                    .config
+                    .keywords
+                    .iter()
+                    .filter(|keyword| !(encrypted && keyword.as_str() == "STARTTLS"))
+                    .cloned(),
+            );
+        }
+        self.respond(Step::Ehlo, 250, &lines)
+    }
+
+    fn starttls(&mut self) -> io::Result<Option<u16>> {
+        if !cfg!(feature = "native-tls")
+            || !self.advertises("STARTTLS")
+            || self.reader.get_ref().is_encrypted()
+        {
+            return self.reply(454, &["4.7.0 TLS not available".to_owned()]);
+        }
+        self.respond(
+            Step::StartTls,
+            220,
+            &["2.0.0 Ready to start TLS".to_owned()],
+        )
+    }
+
+    /// Encrypts the stream, after a successful `STARTTLS`
+    fn upgrade(self) -> io::Result<Self> {
+        let stream = self.reader.into_inner().upgrade(&self.shared.config)?;
+        Ok(Session {
+            shared: self.shared,
+            reader: BufReader::new(stream),
+            from: None,
+            to: Vec::new(),
+        })
+    }
+
+    fn auth(&mut self, argument: &str) -> io::Result<Option<u16>> {
+        let mut split = argument.split_whitespace();
+        let mechanism = split.next().unwrap_or("").to_ascii_uppercase();
+        let initial_response = split.next();
+
+        if let Some(Action::Reply(code, message)) = self.shared.action(Step::Auth) {
+            return self.reply(code, &[message]);
+        }
+
+        let credentials = match mechanism.as_str() {
+            "PLAIN" => {
+                let response = match initial_response {
+                    Some(response) => response.to_owned(),
+                    None => match self.challenge("")? {
+                        Some(response) => response,
+                        None => return Ok(None),
+                    },
+                };
+                decode(&response).and_then(|response| {
+                    let mut parts = response.split('\0').skip(1);
+                    match (parts.next(), parts.next()) {
+                        (Some(username), Some(password)) => {
+                            Some((username.to_owned(), password.to_owned()))
+                        }
+                        _ => None,
+                    }
+                })
+            }
+            "LOGIN" => {
+                let prompts = self.shared.config.login_prompts.clone();
+                let mut answers = Vec::with_capacity(prompts.len());
+                for prompt in &prompts {
+                    match self.challenge(prompt)? {
+                        Some(answer) if answer == "*" => {
+                            return self.reply(501, &["5.0.0 Authentication cancelled".to_owned()])
+                        }
+                        Some(answer) => answers.push(answer),
+                        None => return Ok(None),
+                    }
+                }
+
+                let password = prompts
+                    .iter()
+                    .position(|prompt| {
+                        decode(prompt).map_or(false, |prompt| {
+                            prompt.to_ascii_lowercase().starts_with("pass")
+                        })
+                    })
+                    .unwrap_or_else(|| prompts.len().saturating_sub(1));
+                let username = (0..answers.len()).find(|&i| i != password);
+                match (username, answers.get(password)) {
+                    (Some(username), Some(password)) => decode(&answers[username])
+                        .and_then(|username| decode(password).map(|password| (username, password))),
+                    _ => None,
+                }
+            }
+            _ => return self.reply(504, &["5.5.4 Unrecognized authentication type".to_owned()]),
+        };
+
+        match (credentials, &self.shared.config.credentials) {
+            (None, _) => self.reply(501, &["5.5.2 Cannot decode response".to_owned()]),
+            (Some(credentials), Some(expected)) if credentials != *expected => self.reply(
+                535,
+                &["5.7.8 Authentication credentials invalid".to_owned()],
+            ),
+            (Some(_), _) => self.reply(235, &["2.7.0 Authentication successful".to_owned()]),
+        }
+    }
+
+    /// Sends a 334 challenge, and returns the response of the client
+    fn challenge(&mut self, challenge: &str) -> io::Result<Option<String>> {
+        self.reply(334, &[challenge.to_owned()])?;
+        self.read_command()
+    }
+
+    fn mail(&mut self, argument: &str) -> io::Result<Option<u16>> {
+        let path = match path(argument, "FROM:") {
+            Some(path) => path,
+            None => return self.reply(501, &["5.5.4 Syntax error".to_owned()]),
+        };
+        if self.from.is_some() {
+            return self.reply(503, &["5.5.1 Sender already specified".to_owned()]);
+        }
+
+        let code = self.respond(Step::Mail, 250, &["2.1.0 OK".to_owned()])?;
+        if is_positive(code) {
+            self.from = Some(Some(path).filter(|path| !path.is_empty()));
+        }
+        Ok(code)
+    }
+
+    fn rcpt(&mut self, argument: &str) -> io::Result<Option<u16>> {
+        let path = match path(argument, "TO:") {
+            Some(path) => path,
+            None => return self.reply(501, &["5.5.4 Syntax error".to_owned()]),
+        };
+        if self.from.is_none() {
+            return self.reply(503, &["5.5.1 Need MAIL command".to_owned()]);
+        }
+
+        let code = self.respond(Step::Rcpt, 250, &["2.1.5 OK".to_owned()])?;
+        if is_positive(code) {
+            self.to.push(path);
+        }
+        Ok(code)
+    }
+
+    fn data(&mut self) -> io::Result<Option<u16>> {
+        if self.from.is_none() || self.to.is_empty() {
+            return self.reply(503, &["5.5.1 No valid recipients".to_owned()]);
+        }
+
+        let code = self.respond(
+            Step::Data,
+            354,
+            &["Start mail input; end with <CRLF>.<CRLF>".to_owned()],
+        )?;
+        if code != Some(354) {
+            return Ok(code);
+        }
+
+        let mut data = Vec::new();
+        loop {
+            let line = match self.read_line()? {
+                Some(line) => line,
+                None => return Ok(None),
+            };
+            if line == b"." {
+                break;
+            }
+            let line = if line.first() == Some(&b'.') {
+                &line[1..]
+            } else {
+                &line[..]
+            };
+            data.extend_from_slice(line);
+            data.extend_from_slice(b"\r\n");
+        }
+
+        let message = ReceivedMessage {
+            from: self.from.take().unwrap_or_default(),
+            to: std::mem::take(&mut self.to),
+            data,
+        };
+        match self.shared.action(Step::Message) {
+            Some(Action::Reply(code, message_text)) => {
+                if code < 400 {
+                    self.shared.state().messages.push(message);
+                }
+                self.reply(code, &[message_text])
+            }
+            Some(Action::Disconnect) => Ok(None),
+            action => {
+                self.shared.state().messages.push(message);
+                let lines = ["2.0.0 OK: queued".to_owned()];
+                match action {
+                    Some(Action::Delay(delay)) => {
+                        thread::sleep(delay);
+                        self.reply(250, &lines)
+                    }
+                    Some(Action::Trickle(delay)) => self.trickle(250, &lines, delay),
+                    _ => self.reply(250, &lines),
+                }
+            }
+        }
+    }
+}
+
+/// Generates a PEM encoded self-signed certificate for `localhost` and `127.0.0.1`,
+/// valid for a day, and its PKCS #8 private key
+#[cfg(all(
+    feature = "native-tls",
+    not(any(target_os = "windows", target_vendor = "apple"))
+))]
+fn self_signed_identity() -> Result<(String, String), openssl::error::ErrorStack> {
+    use openssl::{
+        asn1::Asn1Time,
+        bn::BigNum,
+        hash::MessageDigest,
+        nid::Nid,
+        pkey::PKey,
+        rsa::Rsa,
+        x509::{extension::SubjectAlternativeName, X509Name, X509},
+    };
+
+    let key = PKey::from_rsa(Rsa::generate(2048)?)?;
+    let mut name = X509Name::builder()?;
+    name.append_entry_by_nid(Nid::COMMONNAME, "localhost")?;
+    let name = name.build();
+
+    let mut certificate = X509::builder()?;
+    certificate.set_version(2)?;
+    certificate.set_serial_number(&*BigNum::from_u32(1)?.to_asn1_integer()?)?;
+    certificate.set_subject_name(&name)?;
+    certificate.set_issuer_name(&name)?;
+    certificate.set_pubkey(&key)?;
+    certificate.set_not_before(&*Asn1Time::days_from_now(0)?)?;
+    certificate.set_not_after(&*Asn1Time::days_from_now(1)?)?;
+    let alt_names = SubjectAlternativeName::new()
+        .dns("localhost")
+        .ip("127.0.0.1")
+        .build(&certificate.x509v3_context(None, None))?;
+    certificate.append_extension(alt_names)?;
+    certificate.sign(&key, MessageDigest::sha256())?;
+
+    let certificate = certificate.build().to_pem()?;
+    let key = key.private_key_to_pem_pkcs8()?;
+    Ok((
+        String::from_utf8_lossy(&certificate).into_owned(),
+        String::from_utf8_lossy(&key).into_owned(),
+    ))
+}
+
+fn other<E: Display>(err: E) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, err.to_string())
+}
+
+fn is_positive(code: Option<u16>) -> bool {
+    matches!(code, Some(code) if code < 400)
+}
+
+/// Extracts the path of a `MAIL FROM` or `RCPT TO` argument
+fn path(argument: &str, prefix: &str) -> Option<String> {
+    if argument.len() < prefix.len() || !argument[..prefix.len()].eq_ignore_ascii_case(prefix) {
+        return None;
+    }
+    let argument = argument[prefix.len()..].trim_start();
+    let end = argument.find('>')?;
+    if !argument.starts_with('<') {
+        return None;
+    }
+    Some(argument[1..end].to_owned())
+}
+
+fn decode(response: &str) -> Option<String> {
+    base64::decode(response)
+        .ok()
+        .and_then(|decoded| String::from_utf8(decoded).ok())
+}
@@ -0,0 +1,241 @@
+//! Boxed transports erase the type of a transport, so it can be chosen at runtime.
+//!
+//! The associated `Ok` and `Error` types of [`Transport`] and [`AsyncTransport`] make it
+//! impossible to store different transports in the same field. [`BoxedTransport`] and
+//! [`BoxedAsyncTransport`] wrap any transport whose error implements [`FailoverError`],
+//! discarding the response and boxing the error into a [`BoxedError`]. The original error
+//! can still be recovered with `downcast_ref`, and boxed transports can be used in the
+//! [`failover`](crate::transport::failover) transport.
+//!
+//! Boxed transports are created with [`Transport::boxed`] and [`AsyncTransport::boxed`].
+//!
+//! #### Boxed Transport
+//!
+//! ```rust
+//! # #[cfg(all(feature = "builder", feature = "file-transport"))]
+//! # {
+//! use lettre::{
+//!     transport::{
+//!         boxed::BoxedTransport,
+//!         stub::{self, StubTransport},
+//!     },
+//!     FileTransport, Message, Transport,
+//! };
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! # let use_file = false;
+//! let sender: BoxedTransport = if use_file {
+//!     FileTransport::new(std::env::temp_dir()).boxed()
+//! } else {
+//!     StubTransport::new(Err(stub::Error)).boxed()
+//! };
+//!
+//! let err = sender.send(&email).unwrap_err();
+//! assert!(err.downcast_ref::<stub::Error>().is_some());
+//! # Ok(())
+//! # }
+//! # }
+//! ```
+//!
+//! [`AsyncTransport`]: crate::AsyncTransport
+//! [`AsyncTransport::boxed`]: crate::AsyncTransport::boxed
+//! [`FailoverError`]: crate::transport::failover::FailoverError
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use crate::AsyncTransport;
+#[cfg(feature = "builder")]
+use crate::Message;
+use crate::{address::Envelope, transport::failover::FailoverError, Transport};
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use async_trait::async_trait;
+use std::{error::Error as StdError, fmt};
+
+/// Error returned by boxed transports
+///
+/// Use [`downcast_ref`](BoxedError::downcast_ref) to get the error of the inner transport.
+pub struct BoxedError {
+    inner: Box<dyn FailoverError>,
+}
+
+impl BoxedError {
+    fn new<E: FailoverError>(err: E) -> Self {
+        Self {
+            inner: Box::new(err),
+        }
+    }
+
+    /// Returns a reference to the error of the inner transport if it is of type `E`
+    pub fn downcast_ref<E: StdError + 'static>(&self) -> Option<&E> {
+        let inner: &(dyn StdError + 'static) = &*self.inner;
+        inner.downcast_ref()
+    }
+}
+
+impl fmt::Debug for BoxedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.inner, f)
+    }
+}
+
+impl fmt::Display for BoxedError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+impl StdError for BoxedError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.inner.source()
+    }
+}
+
+impl FailoverError for BoxedError {
+    fn should_try_next(&self) -> bool {
+        self.inner.should_try_next()
+    }
+}
+
+/// Adapts a transport to the types used by boxed transports
+struct Erased<T>(T);
+
+impl<T> Transport for Erased<T>
+where
+    T: Transport,
+    T::Error: FailoverError,
+{
+    type Ok = ();
+    type Error = BoxedError;
+
+    #[cfg(feature = "builder")]
+    fn send(&self, message: &Message) -> Result<Self::Ok, Self::Error> {
+        self.0.send(message).map(|_| ()).map_err(BoxedError::new)
+    }
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.0
+            .send_raw(envelope, email)
+            .map(|_| ())
+            .map_err(BoxedError::new)
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl<T> AsyncTransport for Erased<T>
+where
+    T: AsyncTransport + Sync,
+    T::Error: FailoverError,
+{
+    type Ok = ();
+    type Error = BoxedError;
+
+    #[cfg(feature = "builder")]
+    async fn send(&self, message: Message) -> Result<Self::Ok, Self::Error> {
+        self.0
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(BoxedError::new)
+    }
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.0
+            .send_raw(envelope, email)
+            .await
+            .map(|_| ())
+            .map_err(BoxedError::new)
+    }
+}
+
+/// A transport of any type
+pub struct BoxedTransport {
+    inner: Box<dyn Transport<Ok = (), Error = BoxedError> + Send + Sync>,
+}
+
+impl BoxedTransport {
+    /// Boxes `transport`
+    ///
+    /// Equivalent to [`Transport::boxed`].
+    pub fn new<T>(transport: T) -> Self
+    where
+        T: Transport + Send + Sync + 'static,
+        T::Error: FailoverError,
+    {
+        Self {
+            inner: Box::new(Erased(transport)),
+        }
+    }
+}
+
+impl fmt::Debug for BoxedTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BoxedTransport")
+    }
+}
+
+impl Transport for BoxedTransport {
+    type Ok = ();
+    type Error = BoxedError;
+
+    #[cfg(feature = "builder")]
+    fn send(&self, message: &Message) -> Result<Self::Ok, Self::Error> {
+        self.inner.send(message)
+    }
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.inner.send_raw(envelope, email)
+    }
+}
+
+/// An async transport of any type
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "tokio1", feature = "async-std1"))))]
+pub struct BoxedAsyncTransport {
+    inner: Box<dyn AsyncTransport<Ok = (), Error = BoxedError> + Send + Sync>,
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+impl BoxedAsyncTransport {
+    /// Boxes `transport`
+    ///
+    /// Equivalent to [`AsyncTransport::boxed`].
+    pub fn new<T>(transport: T) -> Self
+    where
+        T: AsyncTransport + Send + Sync + 'static,
+        T::Error: FailoverError,
+    {
+        Self {
+            inner: Box::new(Erased(transport)),
+        }
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+impl fmt::Debug for BoxedAsyncTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BoxedAsyncTransport")
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl AsyncTransport for BoxedAsyncTransport {
+    type Ok = ();
+    type Error = BoxedError;
+
+    #[cfg(feature = "builder")]
+    async fn send(&self, message: Message) -> Result<Self::Ok, Self::Error> {
+        self.inner.send(message).await
+    }
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.inner.send_raw(envelope, email).await
+    }
+}
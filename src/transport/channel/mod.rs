@@ -0,0 +1,428 @@
+//! The channel transport sends the envelope and content of every message into an in-memory
+//! channel. It can be useful for testing purposes, when the code sending the messages runs in
+//! another thread or task than the assertions.
+//!
+//! #### Channel Transport
+//!
+//! [`ChannelTransport::new`] returns a transport and the [`Receiver`] end of its channel.
+//! The transport can be cloned, and the channel is closed once all the clones have been dropped.
+//!
+//! ```rust
+//! # #[cfg(feature = "builder")]
+//! # {
+//! use std::{thread, time::Duration};
+//!
+//! use lettre::{transport::channel::ChannelTransport, Message, Transport};
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let (sender, receiver) = ChannelTransport::new();
+//!
+//! let expected = email.clone();
+//! thread::spawn(move || sender.send(&email));
+//!
+//! let (envelope, content) = receiver
+//!     .recv_timeout(Duration::from_secs(1))
+//!     .expect("no message received");
+//! assert_eq!(&envelope, expected.envelope());
+//! assert_eq!(content, expected.formatted());
+//! # Ok(())
+//! # }
+//! # }
+//! ```
+//!
+//! With the async API, messages can be awaited with [`Receiver::recv_async`], which can be
+//! wrapped into the timeout utility of your runtime, like `tokio::time::timeout`.
+//!
+//! A channel can be given a maximum capacity with [`ChannelTransport::bounded`]. Sends either
+//! wait or fail when the channel is full, depending on the chosen [`Backpressure`].
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use crate::AsyncTransport;
+use crate::{address::Envelope, transport::failover::FailoverError, Transport};
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use async_trait::async_trait;
+use std::{
+    collections::VecDeque,
+    error::Error as StdError,
+    fmt,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use std::{
+    future::Future,
+    task::{Context, Poll, Waker},
+};
+
+/// Envelope and content of a message sent through a `ChannelTransport`
+type Item = (Envelope, Vec<u8>);
+
+/// Error returned by the channel transport
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The channel is full, and the transport was configured to reject messages
+    /// in this case
+    Full,
+    /// The receiving end of the channel has been dropped
+    Disconnected,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Full => f.write_str("channel is full"),
+            Error::Disconnected => f.write_str("channel receiver has been dropped"),
+        }
+    }
+}
+
+impl StdError for Error {}
+
+impl FailoverError for Error {
+    fn should_try_next(&self) -> bool {
+        true
+    }
+}
+
+/// Behavior of a bounded `ChannelTransport` when its channel is full
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Backpressure {
+    /// Wait for the receiver to make room in the channel
+    Block,
+    /// Return [`Error::Full`]
+    Reject,
+}
+
+struct State {
+    queue: VecDeque<Item>,
+    capacity: Option<usize>,
+    senders: usize,
+    receiver_alive: bool,
+    #[cfg(any(feature = "tokio1", feature = "async-std1"))]
+    wakers: Vec<Waker>,
+}
+
+impl State {
+    fn is_full(&self) -> bool {
+        self.capacity
+            .map_or(false, |capacity| self.queue.len() >= capacity)
+    }
+
+    /// Registers the task to wake up when the channel changes, once even if it's
+    /// polled again before
+    #[cfg(any(feature = "tokio1", feature = "async-std1"))]
+    fn register(&mut self, waker: &Waker) {
+        if !self
+            .wakers
+            .iter()
+            .any(|registered| registered.will_wake(waker))
+        {
+            self.wakers.push(waker.clone());
+        }
+    }
+}
+
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("Couldn't acquire lock on channel")
+    }
+
+    /// Wakes up every sender and receiver waiting for the channel to change
+    #[allow(unused_variables)]
+    fn notify(&self, state: &mut State) {
+        self.changed.notify_all();
+        #[cfg(any(feature = "tokio1", feature = "async-std1"))]
+        for waker in state.wakers.drain(..) {
+            waker.wake();
+        }
+    }
+}
+
+/// This transport sends the message envelope and content into a channel
+pub struct ChannelTransport {
+    shared: Arc<Shared>,
+    backpressure: Backpressure,
+}
+
+impl ChannelTransport {
+    /// Creates a new transport with an unbounded channel, and the receiving end of the channel
+    pub fn new() -> (ChannelTransport, Receiver) {
+        ChannelTransport::new_impl(None, Backpressure::Block)
+    }
+
+    /// Creates a new transport with a channel holding at most `capacity` messages,
+    /// and the receiving end of the channel
+    ///
+    /// `backpressure` selects what happens when sending a message while the channel is full.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `capacity` is 0.
+    pub fn bounded(capacity: usize, backpressure: Backpressure) -> (ChannelTransport, Receiver) {
+        assert!(capacity > 0, "the channel capacity must be at least 1");
+        ChannelTransport::new_impl(Some(capacity), backpressure)
+    }
+
+    fn new_impl(
+        capacity: Option<usize>,
+        backpressure: Backpressure,
+    ) -> (ChannelTransport, Receiver) {
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                queue: VecDeque::new(),
+                capacity,
+                senders: 1,
+                receiver_alive: true,
+                #[cfg(any(feature = "tokio1", feature = "async-std1"))]
+                wakers: Vec::new(),
+            }),
+            changed: Condvar::new(),
+        });
+
+        (
+            ChannelTransport {
+                shared: Arc::clone(&shared),
+                backpressure,
+            },
+            Receiver { shared },
+        )
+    }
+
+    /// Pushes the message if there is room for it
+    ///
+    /// Gives the message back if it should be retried later.
+    fn try_push(&self, state: &mut State, item: Item) -> Result<Option<Item>, Error> {
+        if !state.receiver_alive {
+            return Err(Error::Disconnected);
+        }
+
+        if state.is_full() {
+            return match self.backpressure {
+                Backpressure::Block => Ok(Some(item)),
+                Backpressure::Reject => Err(Error::Full),
+            };
+        }
+
+        state.queue.push_back(item);
+        self.shared.notify(state);
+        Ok(None)
+    }
+}
+
+impl Clone for ChannelTransport {
+    fn clone(&self) -> Self {
+        self.shared.lock().senders += 1;
+
+        ChannelTransport {
+            shared: Arc::clone(&self.shared),
+            backpressure: self.backpressure,
+        }
+    }
+}
+
+impl Drop for ChannelTransport {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.senders -= 1;
+            if state.senders == 0 {
+                self.shared.notify(&mut state);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for ChannelTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChannelTransport")
+            .field("backpressure", &self.backpressure)
+            .finish()
+    }
+}
+
+impl Transport for ChannelTransport {
+    type Ok = ();
+    type Error = Error;
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let mut item = (envelope.clone(), email.to_vec());
+        let mut state = self.shared.lock();
+
+        while let Some(rejected) = self.try_push(&mut state, item)? {
+            item = rejected;
+            state = self
+                .shared
+                .changed
+                .wait(state)
+                .expect("Couldn't acquire lock on channel");
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl AsyncTransport for ChannelTransport {
+    type Ok = ();
+    type Error = Error;
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let mut item = Some((envelope.clone(), email.to_vec()));
+
+        futures_util::future::poll_fn(|cx: &mut Context<'_>| {
+            let mut state = self.shared.lock();
+            let pending = item.take().expect("polled after completion");
+
+            match self.try_push(&mut state, pending) {
+                Ok(Some(rejected)) => {
+                    item = Some(rejected);
+                    state.register(cx.waker());
+                    Poll::Pending
+                }
+                Ok(None) => Poll::Ready(Ok(())),
+                Err(err) => Poll::Ready(Err(err)),
+            }
+        })
+        .await
+    }
+}
+
+/// The receiving end of a `ChannelTransport`
+///
+/// Receiving methods return `None` once the channel is empty and all the
+/// transports have been dropped.
+pub struct Receiver {
+    shared: Arc<Shared>,
+}
+
+impl Receiver {
+    /// Returns the next message if one is available, without waiting
+    pub fn try_recv(&self) -> Option<(Envelope, Vec<u8>)> {
+        let mut state = self.shared.lock();
+        self.pop(&mut state)
+    }
+
+    /// Waits for the next message
+    pub fn recv(&self) -> Option<(Envelope, Vec<u8>)> {
+        let mut state = self.shared.lock();
+
+        loop {
+            if let Some(item) = self.pop(&mut state) {
+                return Some(item);
+            }
+            if state.senders == 0 {
+                return None;
+            }
+
+            state = self
+                .shared
+                .changed
+                .wait(state)
+                .expect("Couldn't acquire lock on channel");
+        }
+    }
+
+    /// Waits for the next message for at most `timeout`
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<(Envelope, Vec<u8>)> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.lock();
+
+        loop {
+            if let Some(item) = self.pop(&mut state) {
+                return Some(item);
+            }
+            if state.senders == 0 {
+                return None;
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_secs(0) {
+                return None;
+            }
+
+            state = self
+                .shared
+                .changed
+                .wait_timeout(state, remaining)
+                .expect("Couldn't acquire lock on channel")
+                .0;
+        }
+    }
+
+    /// Waits asynchronously for the next message
+    #[cfg(any(feature = "tokio1", feature = "async-std1"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "tokio1", feature = "async-std1"))))]
+    pub fn recv_async(&self) -> impl Future<Output = Option<(Envelope, Vec<u8>)>> + '_ {
+        futures_util::future::poll_fn(move |cx: &mut Context<'_>| {
+            let mut state = self.shared.lock();
+
+            if let Some(item) = self.pop(&mut state) {
+                Poll::Ready(Some(item))
+            } else if state.senders == 0 {
+                Poll::Ready(None)
+            } else {
+                state.register(cx.waker());
+                Poll::Pending
+            }
+        })
+    }
+
+    fn pop(&self, state: &mut State) -> Option<Item> {
+        let item = state.queue.pop_front();
+        if item.is_some() {
+            self.shared.notify(state);
+        }
+        item
+    }
+}
+
+impl Drop for Receiver {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.receiver_alive = false;
+            state.queue.clear();
+            self.shared.notify(&mut state);
+        }
+    }
+}
+
+impl fmt::Debug for Receiver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Receiver").finish()
+    }
+}
+
+#[cfg(test)]
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+mod test {
+    use super::ChannelTransport;
+    use futures_util::{
+        future::Future,
+        task::{noop_waker_ref, Context},
+    };
+
+    #[test]
+    fn register_waker_once() {
+        let (_transport, receiver) = ChannelTransport::new();
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        let recv = receiver.recv_async();
+        futures_util::pin_mut!(recv);
+        for _ in 0..3 {
+            assert!(recv.as_mut().poll(&mut cx).is_pending());
+        }
+        assert_eq!(receiver.shared.lock().wakers.len(), 1);
+    }
+}
@@ -0,0 +1,282 @@
+//! The circuit breaker transport wraps another transport, and stops using it for a while
+//! after several consecutive failures.
+//!
+//! After `failure_threshold` consecutive failures which could be avoided by using another
+//! transport, like connection errors or transient SMTP errors, the circuit opens: sends fail
+//! immediately with [`Error::CircuitOpen`], without using the inner transport. Once the
+//! `cooldown` is over, the circuit is half-open, and the next send is a trial: the circuit
+//! closes again if it succeeds, and opens for another cooldown if it fails. Other sends
+//! keep failing fast while the trial is in progress.
+//!
+//! Permanent errors, like a 5xx SMTP rejection, show the inner transport is working, and
+//! reset the count of failures. The state of the circuit is shared between the clones of a
+//! transport. [`Error`] implements [`FailoverError`], so the circuit breaker can be used as
+//! a transport of a [`FailoverTransport`] to fall back to another relay, or wrap a
+//! failover transport to stop using all of its relays.
+//!
+//! #### Circuit Breaker Transport
+//!
+//! ```rust
+//! # #[cfg(feature = "builder")]
+//! # {
+//! use std::time::Duration;
+//!
+//! use lettre::{
+//!     transport::{
+//!         circuit_breaker::{CircuitBreakerTransport, CircuitState, Error},
+//!         stub::StubTransport,
+//!     },
+//!     Message, Transport,
+//! };
+//!
+//! # use std::error::Error as StdError;
+//! # fn main() -> Result<(), Box<dyn StdError>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let sender = CircuitBreakerTransport::new(
+//!     StubTransport::new_transient_error(),
+//!     2,
+//!     Duration::from_secs(30),
+//! );
+//!
+//! assert!(matches!(sender.send(&email), Err(Error::Transport(_))));
+//! assert!(matches!(sender.send(&email), Err(Error::Transport(_))));
+//! assert_eq!(sender.state(), CircuitState::Open);
+//! assert!(matches!(sender.send(&email), Err(Error::CircuitOpen)));
+//! # Ok(())
+//! # }
+//! # }
+//! ```
+//!
+//! [`FailoverTransport`]: crate::transport::failover::FailoverTransport
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use crate::AsyncTransport;
+use crate::{address::Envelope, transport::failover::FailoverError, Transport};
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use async_trait::async_trait;
+use std::{
+    error::Error as StdError,
+    fmt,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+/// State of the circuit of a `CircuitBreakerTransport`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Sends use the inner transport
+    Closed,
+    /// Sends fail immediately until the end of the cooldown
+    Open,
+    /// The cooldown is over, and the next send is a trial
+    HalfOpen,
+}
+
+#[derive(Debug, Default)]
+struct Circuit {
+    /// Consecutive failures of the inner transport
+    failures: u32,
+    opened_at: Option<Instant>,
+    /// A trial send is in progress
+    trial: bool,
+}
+
+#[derive(Debug)]
+struct Breaker {
+    failure_threshold: u32,
+    cooldown: Duration,
+    circuit: Mutex<Circuit>,
+}
+
+impl Breaker {
+    fn circuit(&self) -> MutexGuard<'_, Circuit> {
+        self.circuit
+            .lock()
+            .expect("Couldn't acquire lock on circuit")
+    }
+
+    fn state(&self) -> CircuitState {
+        match self.circuit().opened_at {
+            None => CircuitState::Closed,
+            Some(opened_at) if opened_at.elapsed() < self.cooldown => CircuitState::Open,
+            Some(_) => CircuitState::HalfOpen,
+        }
+    }
+
+    /// Allows a send if the circuit is closed, or if it is the trial of a half-open circuit
+    fn acquire(&self) -> Option<Permit<'_>> {
+        let mut circuit = self.circuit();
+        let trial = match circuit.opened_at {
+            None => false,
+            Some(opened_at) if opened_at.elapsed() < self.cooldown || circuit.trial => return None,
+            Some(_) => true,
+        };
+        circuit.trial = trial;
+
+        Some(Permit {
+            breaker: self,
+            trial,
+        })
+    }
+}
+
+/// Permission to send with the inner transport
+///
+/// The trial is abandoned if the permit is dropped without recording the result,
+/// for example when an async send is cancelled.
+struct Permit<'a> {
+    breaker: &'a Breaker,
+    trial: bool,
+}
+
+impl Permit<'_> {
+    fn record<T, E: FailoverError>(mut self, result: &Result<T, E>) {
+        let mut circuit = self.breaker.circuit();
+        let failed = matches!(result, Err(err) if err.should_try_next());
+
+        if !failed {
+            *circuit = Circuit::default();
+        } else if self.trial {
+            circuit.failures += 1;
+            circuit.opened_at = Some(Instant::now());
+            circuit.trial = false;
+        } else {
+            circuit.failures += 1;
+            if circuit.failures >= self.breaker.failure_threshold {
+                circuit.opened_at = Some(Instant::now());
+            }
+        }
+        self.trial = false;
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        if self.trial {
+            self.breaker.circuit().trial = false;
+        }
+    }
+}
+
+/// Error returned by the circuit breaker transport
+#[derive(Debug)]
+pub enum Error<E> {
+    /// The circuit is open, the inner transport wasn't used
+    CircuitOpen,
+    /// The inner transport failed to send the message
+    Transport(E),
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::CircuitOpen => f.write_str("the circuit is open"),
+            Error::Transport(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl<E: StdError + 'static> StdError for Error<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::CircuitOpen => None,
+            Error::Transport(err) => Some(err),
+        }
+    }
+}
+
+impl<E: FailoverError> FailoverError for Error<E> {
+    fn should_try_next(&self) -> bool {
+        match self {
+            Error::CircuitOpen => true,
+            Error::Transport(err) => err.should_try_next(),
+        }
+    }
+}
+
+/// Stops using the inner transport for a while after consecutive failures
+#[derive(Debug, Clone)]
+pub struct CircuitBreakerTransport<T> {
+    inner: T,
+    breaker: Arc<Breaker>,
+}
+
+impl<T> CircuitBreakerTransport<T> {
+    /// Wraps `inner`, opening the circuit for `cooldown` after `failure_threshold`
+    /// consecutive failures
+    ///
+    /// # Panics
+    ///
+    /// Panics if `failure_threshold` is 0.
+    pub fn new(inner: T, failure_threshold: u32, cooldown: Duration) -> Self {
+        assert!(
+            failure_threshold > 0,
+            "a circuit breaker must allow at least one failure"
+        );
+
+        Self {
+            inner,
+            breaker: Arc::new(Breaker {
+                failure_threshold,
+                cooldown,
+                circuit: Mutex::new(Circuit::default()),
+            }),
+        }
+    }
+
+    /// Get a reference to the inner transport
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Current state of the circuit
+    pub fn state(&self) -> CircuitState {
+        self.breaker.state()
+    }
+
+    /// Number of consecutive failures of the inner transport
+    pub fn consecutive_failures(&self) -> u32 {
+        self.breaker.circuit().failures
+    }
+}
+
+impl<T> Transport for CircuitBreakerTransport<T>
+where
+    T: Transport,
+    T::Error: FailoverError,
+{
+    type Ok = T::Ok;
+    type Error = Error<T::Error>;
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let permit = self.breaker.acquire().ok_or(Error::CircuitOpen)?;
+
+        let result = self.inner.send_raw(envelope, email);
+        permit.record(&result);
+        result.map_err(Error::Transport)
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl<T> AsyncTransport for CircuitBreakerTransport<T>
+where
+    T: AsyncTransport + Sync,
+    T::Error: FailoverError,
+{
+    type Ok = T::Ok;
+    type Error = Error<T::Error>;
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let permit = self.breaker.acquire().ok_or(Error::CircuitOpen)?;
+
+        let result = self.inner.send_raw(envelope, email).await;
+        permit.record(&result);
+        result.map_err(Error::Transport)
+    }
+}
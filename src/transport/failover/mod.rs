@@ -0,0 +1,347 @@
+//! The failover transport sends messages using a primary transport, and falls back
+//! to other transports when the primary one fails with an error which could be
+//! avoided by using another one, like a connection error or a transient SMTP error.
+//!
+//! Transports are tried in order. Permanent errors, like a 5xx SMTP rejection,
+//! are returned immediately without trying the next transports.
+//!
+//! #### Failover Transport
+//!
+//! ```rust
+//! # #[cfg(feature = "builder")]
+//! # {
+//! use lettre::{
+//!     transport::{
+//!         failover::FailoverTransport,
+//!         stub::StubTransport,
+//!     },
+//!     Message, Transport,
+//! };
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let sender = FailoverTransport::new(StubTransport::new_transient_error())
+//!     .fallback(StubTransport::new_ok());
+//!
+//! let delivery = sender.send(&email)?;
+//! assert_eq!(delivery.index(), 1);
+//! assert_eq!(delivery.skipped_errors().len(), 1);
+//! # Ok(())
+//! # }
+//! # }
+//! ```
+//!
+//! Inner transports can be of different types. Their errors need to implement
+//! [`FailoverError`], which decides whether the next transport should be tried. It's
+//! implemented by the errors of all the transports of lettre, including the failover
+//! transport itself, so failover transports can be nested.
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use crate::AsyncTransport;
+use crate::{address::Envelope, BoxError, Transport};
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use async_trait::async_trait;
+use std::{any::Any, error::Error as StdError, fmt, sync::Arc};
+
+/// Errors of transports which can be used in a `FailoverTransport`
+pub trait FailoverError: StdError + Send + Sync + 'static {
+    /// Returns true if sending the message with the next transport could succeed
+    ///
+    /// Defaults to `true`, an error of an unknown cause may not happen with another
+    /// transport.
+    fn should_try_next(&self) -> bool {
+        true
+    }
+}
+
+type ErasedResult = Result<Box<dyn Any + Send>, (BoxError, bool)>;
+
+fn erase<O, E>(result: Result<O, E>) -> ErasedResult
+where
+    O: Send + 'static,
+    E: FailoverError,
+{
+    match result {
+        Ok(ok) => {
+            let response: Box<dyn Any + Send> = Box::new(ok);
+            Ok(response)
+        }
+        Err(err) => {
+            let try_next = err.should_try_next();
+            let err: BoxError = Box::new(err);
+            Err((err, try_next))
+        }
+    }
+}
+
+/// Object-safe version of `Transport`, erasing the response and error types
+trait ErasedTransport: Send + Sync {
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> ErasedResult;
+}
+
+impl<T> ErasedTransport for T
+where
+    T: Transport + Send + Sync,
+    T::Ok: Send + 'static,
+    T::Error: FailoverError,
+{
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> ErasedResult {
+        erase(Transport::send_raw(self, envelope, email))
+    }
+}
+
+/// Successful result of a `FailoverTransport` or an `AsyncFailoverTransport`
+pub struct Delivery {
+    index: usize,
+    response: Box<dyn Any + Send>,
+    skipped: Vec<BoxError>,
+}
+
+impl Delivery {
+    /// Position of the transport which sent the message, `0` being the primary transport
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Response of the transport which sent the message
+    ///
+    /// Returns `None` if `R` isn't the response type of that transport.
+    pub fn response<R: Any>(&self) -> Option<&R> {
+        self.response.downcast_ref()
+    }
+
+    /// Errors returned by the transports tried before the one which sent the message
+    pub fn skipped_errors(&self) -> &[BoxError] {
+        &self.skipped
+    }
+}
+
+impl fmt::Debug for Delivery {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Delivery")
+            .field("index", &self.index)
+            .field("skipped", &self.skipped)
+            .finish()
+    }
+}
+
+/// Error returned by the failover transport
+///
+/// Contains the errors of all the transports which were tried, in order.
+#[derive(Debug)]
+pub struct Error {
+    errors: Vec<BoxError>,
+    retryable: bool,
+}
+
+impl Error {
+    /// Errors returned by the transports which were tried, in order
+    ///
+    /// The last one is the error which caused the send to fail.
+    pub fn errors(&self) -> &[BoxError] {
+        &self.errors
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.errors.last() {
+            Some(err) => write!(
+                f,
+                "failover error after trying {} transport(s): {}",
+                self.errors.len(),
+                err
+            ),
+            None => f.write_str("failover error: no transport available"),
+        }
+    }
+}
+
+impl FailoverError for Error {
+    /// The last tried transport failed with an error which could be avoided by
+    /// using another transport, so nested failover transports try the next one
+    fn should_try_next(&self) -> bool {
+        self.retryable
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        self.errors.last().map(|e| {
+            let r: &(dyn StdError + 'static) = &**e;
+            r
+        })
+    }
+}
+
+/// Sends emails with the first transport which succeeds
+#[derive(Clone)]
+pub struct FailoverTransport {
+    transports: Vec<Arc<dyn ErasedTransport>>,
+}
+
+impl FailoverTransport {
+    /// Creates a new failover transport using `primary` first
+    pub fn new<T>(primary: T) -> FailoverTransport
+    where
+        T: Transport + Send + Sync + 'static,
+        T::Ok: Send + 'static,
+        T::Error: FailoverError,
+    {
+        FailoverTransport {
+            transports: vec![Arc::new(primary)],
+        }
+    }
+
+    /// Adds a transport to use when the previous ones failed
+    pub fn fallback<T>(mut self, transport: T) -> FailoverTransport
+    where
+        T: Transport + Send + Sync + 'static,
+        T::Ok: Send + 'static,
+        T::Error: FailoverError,
+    {
+        self.transports.push(Arc::new(transport));
+        self
+    }
+}
+
+impl fmt::Debug for FailoverTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FailoverTransport")
+            .field("transports", &self.transports.len())
+            .finish()
+    }
+}
+
+impl Transport for FailoverTransport {
+    type Ok = Delivery;
+    type Error = Error;
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let mut errors = Vec::new();
+        let mut retryable = false;
+
+        for (index, transport) in self.transports.iter().enumerate() {
+            match transport.send_raw(envelope, email) {
+                Ok(response) => {
+                    return Ok(Delivery {
+                        index,
+                        response,
+                        skipped: errors,
+                    })
+                }
+                Err((err, try_next)) => {
+                    errors.push(err);
+                    retryable = try_next;
+                    if !try_next {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Err(Error { errors, retryable })
+    }
+}
+
+/// Object-safe version of `AsyncTransport`, erasing the response and error types
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+trait AsyncErasedTransport: Send + Sync {
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> ErasedResult;
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl<T> AsyncErasedTransport for T
+where
+    T: AsyncTransport + Send + Sync,
+    T::Ok: Send + 'static,
+    T::Error: FailoverError,
+{
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> ErasedResult {
+        erase(AsyncTransport::send_raw(self, envelope, email).await)
+    }
+}
+
+/// Asynchronously sends emails with the first transport which succeeds
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "tokio1", feature = "async-std1"))))]
+#[derive(Clone)]
+pub struct AsyncFailoverTransport {
+    transports: Vec<Arc<dyn AsyncErasedTransport>>,
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+impl AsyncFailoverTransport {
+    /// Creates a new failover transport using `primary` first
+    pub fn new<T>(primary: T) -> AsyncFailoverTransport
+    where
+        T: AsyncTransport + Send + Sync + 'static,
+        T::Ok: Send + 'static,
+        T::Error: FailoverError,
+    {
+        AsyncFailoverTransport {
+            transports: vec![Arc::new(primary)],
+        }
+    }
+
+    /// Adds a transport to use when the previous ones failed
+    pub fn fallback<T>(mut self, transport: T) -> AsyncFailoverTransport
+    where
+        T: AsyncTransport + Send + Sync + 'static,
+        T::Ok: Send + 'static,
+        T::Error: FailoverError,
+    {
+        self.transports.push(Arc::new(transport));
+        self
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+impl fmt::Debug for AsyncFailoverTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncFailoverTransport")
+            .field("transports", &self.transports.len())
+            .finish()
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl AsyncTransport for AsyncFailoverTransport {
+    type Ok = Delivery;
+    type Error = Error;
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let mut errors = Vec::new();
+        let mut retryable = false;
+
+        for (index, transport) in self.transports.iter().enumerate() {
+            match transport.send_raw(envelope, email).await {
+                Ok(response) => {
+                    return Ok(Delivery {
+                        index,
+                        response,
+                        skipped: errors,
+                    })
+                }
+                Err((err, try_next)) => {
+                    errors.push(err);
+                    retryable = try_next;
+                    if !try_next {
+                        break;
+                    }
+                }
+            }
+        }
+
+        Err(Error { errors, retryable })
+    }
+}
@@ -1,6 +1,6 @@
 //! Error and result type for file transport
 
-use crate::BoxError;
+use crate::{transport::failover::FailoverError, BoxError};
 use std::{error::Error as StdError, fmt};
 
 /// The Errors that may occur when sending an email over SMTP
@@ -86,6 +86,14 @@ impl StdError for Error {
     }
 }
 
+impl FailoverError for Error {
+    /// Writing the file can fail because of the local file system, like a full
+    /// disk, which another transport doesn't depend on
+    fn should_try_next(&self) -> bool {
+        true
+    }
+}
+
 pub(crate) fn io<E: Into<BoxError>>(e: E) -> Error {
     Error::new(Kind::Io, Some(e))
 }
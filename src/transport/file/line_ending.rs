@@ -0,0 +1,310 @@
+use std::io::{self, Write};
+
+/// Line ending of the files written by the file transport
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LineEnding {
+    /// `\r\n`, like the emails sent over the network
+    Crlf,
+    /// `\n`, like the text files of Unix systems
+    Lf,
+}
+
+impl Default for LineEnding {
+    fn default() -> Self {
+        LineEnding::Crlf
+    }
+}
+
+impl LineEnding {
+    fn as_bytes(self) -> &'static [u8] {
+        match self {
+            LineEnding::Crlf => b"\r\n",
+            LineEnding::Lf => b"\n",
+        }
+    }
+}
+
+/// Converts the line breaks of `data` to `line_ending`
+#[cfg(any(
+    test,
+    feature = "file-transport-envelope",
+    feature = "async-std1",
+    feature = "tokio1"
+))]
+pub(super) fn convert(data: &[u8], line_ending: LineEnding) -> Vec<u8> {
+    let mut writer = LineEndingWriter::new(Vec::with_capacity(data.len()), line_ending);
+    writer
+        .write_all(data)
+        .and_then(|()| writer.finish())
+        .expect("writing to a Vec can't fail");
+    writer.inner
+}
+
+/// Writes an email to the inner writer, converting its line breaks to `line_ending`
+///
+/// The conversion is done line by line, while the email is written. Line breaks are
+/// converted in the headers and in the bodies of the parts, except for the parts with
+/// the `binary` transfer encoding, which are written unchanged: their line breaks can
+/// be part of the data. Converting an email written as `LF` back to `CRLF` gives the
+/// original bytes, as long as all its other line breaks were `CRLF`.
+pub(super) struct LineEndingWriter<W> {
+    inner: W,
+    line_ending: LineEnding,
+    line: Vec<u8>,
+    mime: MimeState,
+}
+
+impl<W: Write> LineEndingWriter<W> {
+    pub(super) fn new(inner: W, line_ending: LineEnding) -> Self {
+        Self {
+            inner,
+            line_ending,
+            line: Vec::new(),
+            mime: MimeState::default(),
+        }
+    }
+
+    /// Writes the last line, if it has no line break, and flushes the inner writer
+    pub(super) fn finish(&mut self) -> io::Result<()> {
+        self.inner.write_all(&self.line)?;
+        self.line.clear();
+        self.inner.flush()
+    }
+
+    fn write_line(&mut self) -> io::Result<()> {
+        let content = content(&self.line);
+        if self.mime.line(content) {
+            self.inner.write_all(&self.line)?;
+        } else {
+            self.inner.write_all(content)?;
+            self.inner.write_all(self.line_ending.as_bytes())?;
+        }
+        self.line.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for LineEndingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut rest = buf;
+        while let Some(end) = rest.iter().position(|&b| b == b'\n') {
+            self.line.extend_from_slice(&rest[..=end]);
+            self.write_line()?;
+            rest = &rest[end + 1..];
+        }
+        self.line.extend_from_slice(rest);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Returns a line without its line break
+fn content(line: &[u8]) -> &[u8] {
+    let line = match line.split_last() {
+        Some((b'\n', line)) => line,
+        _ => return line,
+    };
+    match line.split_last() {
+        Some((b'\r', line)) => line,
+        _ => line,
+    }
+}
+
+/// Position in the MIME structure of the email
+#[derive(Debug, Default)]
+struct MimeState {
+    /// Boundaries of the enclosing multiparts, from the outermost
+    boundaries: Vec<Vec<u8>>,
+    /// Whether the lines are part of a body
+    in_body: bool,
+    /// Whether the current body has the `binary` transfer encoding
+    binary: bool,
+    /// Current header, unfolded
+    header: Vec<u8>,
+    /// Boundary of the multipart whose headers are read
+    boundary: Option<Vec<u8>>,
+    /// Whether the part whose headers are read has the `binary` transfer encoding
+    binary_part: bool,
+}
+
+impl MimeState {
+    /// Reads the next line, returns whether its line break must be kept unchanged
+    fn line(&mut self, content: &[u8]) -> bool {
+        if !self.in_body {
+            self.header_line(content);
+            return false;
+        }
+
+        match self.delimiter(content) {
+            Some((index, close)) => {
+                if close {
+                    // The epilogue of the multipart is in the body of the enclosing part
+                    self.boundaries.truncate(index);
+                    self.binary = false;
+                } else {
+                    self.boundaries.truncate(index + 1);
+                    self.in_body = false;
+                }
+                false
+            }
+            None => self.binary,
+        }
+    }
+
+    fn header_line(&mut self, content: &[u8]) {
+        if content.first().map_or(false, |&b| b == b' ' || b == b'\t') {
+            self.header.extend_from_slice(content);
+            return;
+        }
+
+        self.end_header();
+        if content.is_empty() {
+            self.in_body = true;
+            self.binary = self.binary_part;
+            self.binary_part = false;
+            if let Some(boundary) = self.boundary.take() {
+                self.boundaries.push(boundary);
+            }
+        } else {
+            self.header.extend_from_slice(content);
+        }
+    }
+
+    fn end_header(&mut self) {
+        let header = String::from_utf8_lossy(&self.header).into_owned();
+        self.header.clear();
+
+        let (name, value) = match header.find(':') {
+            Some(colon) => (header[..colon].trim(), header[colon + 1..].trim()),
+            None => return,
+        };
+        if name.eq_ignore_ascii_case("Content-Type") {
+            if value.to_ascii_lowercase().starts_with("multipart/") {
+                self.boundary = boundary(value).map(|boundary| boundary.as_bytes().to_vec());
+            }
+        } else if name.eq_ignore_ascii_case("Content-Transfer-Encoding") {
+            self.binary_part = value.eq_ignore_ascii_case("binary");
+        }
+    }
+
+    /// Finds the multipart delimited by `content`, and whether it is the closing delimiter
+    fn delimiter(&self, content: &[u8]) -> Option<(usize, bool)> {
+        if !content.starts_with(b"--") {
+            return None;
+        }
+        let content = &content[2..];
+        self.boundaries
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, boundary)| content.starts_with(boundary))
+            .map(|(index, boundary)| (index, content[boundary.len()..].starts_with(b"--")))
+    }
+}
+
+/// Returns the `boundary` parameter of a `Content-Type` header
+fn boundary(value: &str) -> Option<&str> {
+    value.split(';').skip(1).find_map(|param| {
+        let (name, value) = param.split_at(param.find('=')?);
+        if !name.trim().eq_ignore_ascii_case("boundary") {
+            return None;
+        }
+        Some(value[1..].trim().trim_matches('"'))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const MULTIPART: &[u8] = concat!(
+        "From: NoBody <nobody@domain.tld>\r\n",
+        "Content-Type: multipart/mixed;\r\n",
+        " boundary=\"outer\"\r\n",
+        "\r\n",
+        "--outer\r\n",
+        "Content-Type: text/plain\r\n",
+        "\r\n",
+        "Be happy!\r\n",
+        "--outer\r\n",
+        "Content-Type: application/octet-stream\r\n",
+        "Content-Transfer-Encoding: binary\r\n",
+        "\r\n",
+        "\x00\x01\r\n\x02\n\x03\r\n",
+        "--outer\r\n",
+        "Content-Type: application/pdf\r\n",
+        "Content-Transfer-Encoding: base64\r\n",
+        "\r\n",
+        "SGFwcHkgbmV3IHllYXIh\r\n",
+        "SGFwcHkgbmV3IHllYXIh\r\n",
+        "--outer--\r\n",
+        "Epilogue\r\n"
+    )
+    .as_bytes();
+
+    #[test]
+    fn convert_lf() {
+        let lf = convert(MULTIPART, LineEnding::Lf);
+        assert_eq!(
+            String::from_utf8(lf).unwrap(),
+            concat!(
+                "From: NoBody <nobody@domain.tld>\n",
+                "Content-Type: multipart/mixed;\n",
+                " boundary=\"outer\"\n",
+                "\n",
+                "--outer\n",
+                "Content-Type: text/plain\n",
+                "\n",
+                "Be happy!\n",
+                "--outer\n",
+                "Content-Type: application/octet-stream\n",
+                "Content-Transfer-Encoding: binary\n",
+                "\n",
+                "\x00\x01\r\n\x02\n\x03\r\n",
+                "--outer\n",
+                "Content-Type: application/pdf\n",
+                "Content-Transfer-Encoding: base64\n",
+                "\n",
+                "SGFwcHkgbmV3IHllYXIh\n",
+                "SGFwcHkgbmV3IHllYXIh\n",
+                "--outer--\n",
+                "Epilogue\n"
+            )
+        );
+    }
+
+    #[test]
+    fn convert_round_trip() {
+        let lf = convert(MULTIPART, LineEnding::Lf);
+        assert_eq!(convert(&lf, LineEnding::Crlf), MULTIPART);
+
+        let simple = b"Subject: Hi\r\n\r\nHello\r\nWorld";
+        let lf = convert(simple, LineEnding::Lf);
+        assert_eq!(lf, b"Subject: Hi\n\nHello\nWorld");
+        assert_eq!(convert(&lf, LineEnding::Crlf), &simple[..]);
+    }
+
+    #[test]
+    fn convert_streaming() {
+        let mut writer = LineEndingWriter::new(Vec::new(), LineEnding::Lf);
+        for chunk in MULTIPART.chunks(3) {
+            writer.write_all(chunk).unwrap();
+        }
+        writer.finish().unwrap();
+        assert_eq!(writer.inner, convert(MULTIPART, LineEnding::Lf));
+    }
+
+    #[test]
+    fn boundary_param() {
+        assert_eq!(boundary("multipart/mixed; boundary=\"abc\""), Some("abc"));
+        assert_eq!(
+            boundary("multipart/mixed;charset=x; Boundary=abc"),
+            Some("abc")
+        );
+        assert_eq!(boundary("multipart/mixed"), None);
+    }
+}
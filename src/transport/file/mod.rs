@@ -132,7 +132,14 @@
 //! {"forward_path":["hei@domain.tld"],"reverse_path":"nobody@domain.tld"}
 //! ```
 
-pub use self::error::Error;
+#[cfg(any(
+    feature = "file-transport-envelope",
+    feature = "async-std1",
+    feature = "tokio1"
+))]
+use self::line_ending::convert;
+use self::line_ending::LineEndingWriter;
+pub use self::{error::Error, line_ending::LineEnding};
 use crate::{address::Envelope, Transport};
 #[cfg(any(feature = "async-std1", feature = "tokio1"))]
 use crate::{AsyncTransport, Executor};
@@ -147,6 +154,7 @@ use std::{
 use uuid::Uuid;
 
 mod error;
+mod line_ending;
 
 type Id = String;
 
@@ -158,6 +166,8 @@ pub struct FileTransport {
     path: PathBuf,
     #[cfg(feature = "file-transport-envelope")]
     save_envelope: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    line_ending: LineEnding,
 }
 
 /// Asynchronously writes the content and the envelope information to a file
@@ -179,6 +189,7 @@ impl FileTransport {
             path: PathBuf::from(path.as_ref()),
             #[cfg(feature = "file-transport-envelope")]
             save_envelope: false,
+            line_ending: LineEnding::Crlf,
         }
     }
 
@@ -192,9 +203,21 @@ impl FileTransport {
             path: PathBuf::from(path.as_ref()),
             #[cfg(feature = "file-transport-envelope")]
             save_envelope: true,
+            line_ending: LineEnding::Crlf,
         }
     }
 
+    /// Set the line ending of the written emails, `CRLF` by default
+    ///
+    /// With [`LineEnding::Lf`], the line breaks of the headers and of the bodies are
+    /// written as `\n`, except in the parts with the `binary` transfer encoding, which
+    /// are written unchanged. The emails read back with `read` have their `CRLF` line
+    /// endings again.
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
     /// Read a message that was written using the file transport.
     ///
     /// Reads the envelope and the raw message content.
@@ -203,7 +226,7 @@ impl FileTransport {
         use std::fs;
 
         let eml_file = self.path.join(format!("{}.eml", email_id));
-        let eml = fs::read(eml_file).map_err(error::io)?;
+        let eml = self.restore_line_endings(fs::read(eml_file).map_err(error::io)?);
 
         let json_file = self.path.join(format!("{}.json", email_id));
         let json = fs::read(&json_file).map_err(error::io)?;
@@ -215,6 +238,15 @@ impl FileTransport {
     fn path(&self, email_id: &Uuid, extension: &str) -> PathBuf {
         self.path.join(format!("{}.{}", email_id, extension))
     }
+
+    /// Converts the line endings of an email read from a file back to `CRLF`
+    #[cfg(feature = "file-transport-envelope")]
+    fn restore_line_endings(&self, eml: Vec<u8>) -> Vec<u8> {
+        match self.line_ending {
+            LineEnding::Crlf => eml,
+            LineEnding::Lf => convert(&eml, LineEnding::Crlf),
+        }
+    }
 }
 
 #[cfg(any(feature = "async-std1", feature = "tokio1"))]
@@ -244,13 +276,25 @@ where
         }
     }
 
+    /// Set the line ending of the written emails, `CRLF` by default
+    ///
+    /// See [`FileTransport::line_ending`]. Unlike [`FileTransport`], which converts the
+    /// line endings while writing the file, the converted email is built in memory before
+    /// being written, as the executors only write whole files.
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.inner = self.inner.line_ending(line_ending);
+        self
+    }
+
     /// Read a message that was written using the file transport.
     ///
     /// Reads the envelope and the raw message content.
     #[cfg(feature = "file-transport-envelope")]
     pub async fn read(&self, email_id: &str) -> Result<(Envelope, Vec<u8>), Error> {
         let eml_file = self.inner.path.join(format!("{}.eml", email_id));
-        let eml = E::fs_read(&eml_file).await.map_err(error::io)?;
+        let eml = self
+            .inner
+            .restore_line_endings(E::fs_read(&eml_file).await.map_err(error::io)?);
 
         let json_file = self.inner.path.join(format!("{}.json", email_id));
         let json = E::fs_read(&json_file).await.map_err(error::io)?;
@@ -265,12 +309,25 @@ impl Transport for FileTransport {
     type Error = Error;
 
     fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
-        use std::fs;
+        use std::{
+            fs,
+            io::{BufWriter, Write},
+        };
 
         let email_id = Uuid::new_v4();
 
         let file = self.path(&email_id, "eml");
-        fs::write(file, email).map_err(error::io)?;
+        match self.line_ending {
+            LineEnding::Crlf => fs::write(file, email).map_err(error::io)?,
+            line_ending => {
+                let file = BufWriter::new(fs::File::create(file).map_err(error::io)?);
+                let mut writer = LineEndingWriter::new(file, line_ending);
+                writer
+                    .write_all(email)
+                    .and_then(|()| writer.finish())
+                    .map_err(error::io)?;
+            }
+        }
 
         #[cfg(feature = "file-transport-envelope")]
         {
@@ -300,7 +357,11 @@ where
         let email_id = Uuid::new_v4();
 
         let file = self.inner.path(&email_id, "eml");
-        E::fs_write(&file, email).await.map_err(error::io)?;
+        match self.inner.line_ending {
+            LineEnding::Crlf => E::fs_write(&file, email).await,
+            line_ending => E::fs_write(&file, &convert(email, line_ending)).await,
+        }
+        .map_err(error::io)?;
 
         #[cfg(feature = "file-transport-envelope")]
         {
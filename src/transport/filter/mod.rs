@@ -0,0 +1,178 @@
+//! The filter transport wraps another transport, and removes from the envelope the
+//! recipients rejected by a predicate.
+//!
+//! The predicate can implement an allow-list, so that staging environments only send
+//! emails to known domains, or a deny-list, to honor a suppression list. The headers
+//! of the email are left unchanged, only the envelope is filtered.
+//!
+//! When all the recipients are removed, the inner transport isn't used, and
+//! [`Filtered::Skipped`] is returned.
+//!
+//! #### Filter Transport
+//!
+//! ```rust
+//! # #[cfg(feature = "builder")]
+//! # {
+//! use lettre::{
+//!     transport::{
+//!         filter::{FilterTransport, Filtered},
+//!         stub::StubTransport,
+//!     },
+//!     Address, Message, Transport,
+//! };
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .cc("Staging <staging@example.com>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let sender = FilterTransport::new(StubTransport::new_ok(), |recipient: &Address| {
+//!     recipient.domain() == "example.com"
+//! });
+//!
+//! match sender.send(&email)? {
+//!     Filtered::Sent { removed, .. } => println!("{} recipients removed", removed.len()),
+//!     Filtered::Skipped { .. } => println!("no recipient allowed"),
+//! }
+//! # Ok(())
+//! # }
+//! # }
+//! ```
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use crate::AsyncTransport;
+use crate::{address::Envelope, Address, Transport};
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use async_trait::async_trait;
+use std::{fmt, sync::Arc};
+
+/// Result of a send through the filter transport
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filtered<R> {
+    /// The email was sent to the remaining recipients
+    Sent {
+        /// Response of the inner transport
+        response: R,
+        /// Recipients removed from the envelope
+        removed: Vec<Address>,
+    },
+    /// All the recipients were removed, the email wasn't sent
+    Skipped {
+        /// Recipients removed from the envelope
+        removed: Vec<Address>,
+    },
+}
+
+impl<R> Filtered<R> {
+    /// Tells whether the email wasn't sent because all the recipients were removed
+    pub fn is_skipped(&self) -> bool {
+        matches!(self, Filtered::Skipped { .. })
+    }
+
+    /// Recipients removed from the envelope
+    pub fn removed(&self) -> &[Address] {
+        match self {
+            Filtered::Sent { removed, .. } | Filtered::Skipped { removed } => removed,
+        }
+    }
+
+    /// Response of the inner transport, if the email was sent
+    pub fn response(&self) -> Option<&R> {
+        match self {
+            Filtered::Sent { response, .. } => Some(response),
+            Filtered::Skipped { .. } => None,
+        }
+    }
+}
+
+/// Sends the emails with the inner transport, only to the recipients accepted by a predicate
+#[derive(Clone)]
+pub struct FilterTransport<T> {
+    inner: T,
+    filter: Arc<dyn Fn(&Address) -> bool + Send + Sync>,
+}
+
+impl<T> FilterTransport<T> {
+    /// Wraps `inner`, only sending emails to the recipients for which `filter` returns `true`
+    pub fn new<F>(inner: T, filter: F) -> Self
+    where
+        F: Fn(&Address) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            inner,
+            filter: Arc::new(filter),
+        }
+    }
+
+    /// Get a reference to the inner transport
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get the inner transport
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    /// Splits the recipients, returning `None` as the envelope when none is accepted
+    fn filter(&self, envelope: &Envelope) -> (Option<Envelope>, Vec<Address>) {
+        let (accepted, removed): (Vec<Address>, Vec<Address>) = envelope
+            .to()
+            .iter()
+            .cloned()
+            .partition(|recipient| (self.filter)(recipient));
+
+        if removed.is_empty() {
+            return (Some(envelope.clone()), removed);
+        }
+        let filtered = Envelope::new(envelope.from().cloned(), accepted).ok();
+        (filtered, removed)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for FilterTransport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FilterTransport")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<T: Transport> Transport for FilterTransport<T> {
+    type Ok = Filtered<T::Ok>;
+    type Error = T::Error;
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        match self.filter(envelope) {
+            (Some(envelope), removed) => {
+                let response = self.inner.send_raw(&envelope, email)?;
+                Ok(Filtered::Sent { response, removed })
+            }
+            (None, removed) => Ok(Filtered::Skipped { removed }),
+        }
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl<T> AsyncTransport for FilterTransport<T>
+where
+    T: AsyncTransport + Sync,
+{
+    type Ok = Filtered<T::Ok>;
+    type Error = T::Error;
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        match self.filter(envelope) {
+            (Some(envelope), removed) => {
+                let response = self.inner.send_raw(&envelope, email).await?;
+                Ok(Filtered::Sent { response, removed })
+            }
+            (None, removed) => Ok(Filtered::Skipped { removed }),
+        }
+    }
+}
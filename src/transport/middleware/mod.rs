@@ -0,0 +1,298 @@
+//! The middleware transport wraps another transport, and lets [`MessageMiddleware`]s
+//! modify every message right before it is serialized.
+//!
+//! This centralizes cross-cutting concerns like adding tracking headers or sanitizing
+//! messages, instead of repeating them at every call site. Middlewares run in the order
+//! they were added, on a copy of the message passed to `send`.
+//!
+//! Middlewares can add, replace or remove headers through [`MiddlewareMessage`], but
+//! not the ones the envelope was built from, so they can't change the recipients of
+//! the message. They can also read the body, to sign it, or replace it. Only [`Message`]s go through the middlewares: emails sent with
+//! `send_raw` are passed to the inner transport unchanged.
+//!
+//! #### Middleware Transport
+//!
+//! ```rust
+//! # #[cfg(feature = "builder")]
+//! # {
+//! use lettre::{
+//!     message::header::{HeaderName, UserAgent},
+//!     transport::{
+//!         middleware::{MiddlewareMessage, MiddlewareTransport},
+//!         stub::StubTransport,
+//!     },
+//!     Message, Transport,
+//! };
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let sender = MiddlewareTransport::new(StubTransport::new_ok())
+//!     .before_send(|message: &mut MiddlewareMessage<'_>| {
+//!         message
+//!             .set(UserAgent::from("My App".to_owned()))
+//!             .expect("User-Agent isn't an envelope header")
+//!     })
+//!     .before_send(|message: &mut MiddlewareMessage<'_>| {
+//!         message
+//!             .insert_raw(
+//!                 HeaderName::new_from_ascii_str("X-Campaign"),
+//!                 "newsletter".to_owned(),
+//!             )
+//!             .expect("X-Campaign isn't an envelope header")
+//!     });
+//!
+//! sender.send(&email)?;
+//! # Ok(())
+//! # }
+//! # }
+//! ```
+//!
+//! [`Message`]: crate::Message
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use crate::AsyncTransport;
+use crate::{
+    address::Envelope,
+    error::Error,
+    message::{
+        header::{Header, HeaderName, Headers},
+        IntoBody, MultiPart, SinglePart,
+    },
+    Message, Transport,
+};
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use async_trait::async_trait;
+use std::{fmt, sync::Arc};
+
+/// Headers the envelope of a message is built from, which middlewares can't change
+const ENVELOPE_HEADERS: [&str; 10] = [
+    "From",
+    "Sender",
+    "To",
+    "Cc",
+    "Bcc",
+    "Resent-From",
+    "Resent-Sender",
+    "Resent-To",
+    "Resent-Cc",
+    "Resent-Bcc",
+];
+
+/// Modifies messages before they are sent
+///
+/// Implemented for functions taking a `&mut MiddlewareMessage<'_>`.
+pub trait MessageMiddleware: Send + Sync {
+    /// Called with every message right before it is serialized
+    fn before_send(&self, message: &mut MiddlewareMessage<'_>);
+}
+
+impl<F> MessageMiddleware for F
+where
+    F: Fn(&mut MiddlewareMessage<'_>) + Send + Sync,
+{
+    fn before_send(&self, message: &mut MiddlewareMessage<'_>) {
+        self(message)
+    }
+}
+
+/// A message being modified by middlewares
+///
+/// The headers the envelope was built from, `From`, `Sender`, `To`, `Cc`, `Bcc` and
+/// their `Resent-*` counterparts, can't be changed, and values containing a CR or LF
+/// character are rejected.
+#[derive(Debug)]
+pub struct MiddlewareMessage<'a> {
+    message: &'a mut Message,
+}
+
+impl MiddlewareMessage<'_> {
+    /// Get the headers of the message
+    pub fn headers(&self) -> &Headers {
+        self.message.headers()
+    }
+
+    /// Get the envelope of the message
+    pub fn envelope(&self) -> &Envelope {
+        self.message.envelope()
+    }
+
+    /// Sets `header`, overriding the present one, see [`Headers::set`]
+    pub fn set<H: Header>(&mut self, header: H) -> Result<(), Error> {
+        self.insert_raw(H::name(), header.display())
+    }
+
+    /// Inserts a raw header, overriding the present one, see [`Headers::insert_raw`]
+    pub fn insert_raw(&mut self, name: HeaderName, value: String) -> Result<(), Error> {
+        check_header(&name, &value)?;
+        self.message.headers_mut().insert_raw(name, value);
+        Ok(())
+    }
+
+    /// Inserts `header` before all the headers, see [`Headers::prepend`]
+    pub fn prepend<H: Header>(&mut self, header: H) -> Result<(), Error> {
+        let value = header.display();
+        check_header(&H::name(), &value)?;
+        self.message.headers_mut().prepend(header);
+        Ok(())
+    }
+
+    /// Remove a raw header, returning it, see [`Headers::remove_raw`]
+    pub fn remove_raw(&mut self, name: &str) -> Result<Option<(HeaderName, String)>, Error> {
+        check_name(name)?;
+        Ok(self.message.headers_mut().remove_raw(name))
+    }
+
+    /// Get the body of the message, as it will be sent
+    ///
+    /// This is everything after the blank line ending the headers, including the headers
+    /// of the MIME parts, like the body a DKIM signature is computed over.
+    pub fn formatted_body(&self) -> Vec<u8> {
+        self.message.formatted_body()
+    }
+
+    /// Replace the body of the message, see [`MessageBuilder::body`]
+    ///
+    /// The MIME parts of the message are dropped, and the `Content-Transfer-Encoding` is
+    /// chosen for `body`. Without a `Content-Type` header, the body is sent as plain text.
+    ///
+    /// [`MessageBuilder::body`]: crate::message::MessageBuilder::body
+    pub fn set_body<T: IntoBody>(&mut self, body: T) {
+        self.message.replace_body(body.into_body(None));
+    }
+
+    /// Replace the body of the message with a single MIME part, see
+    /// [`MessageBuilder::singlepart`]
+    ///
+    /// The `Content-Type` and `Content-Transfer-Encoding` headers of the message are
+    /// removed, as `part` has its own.
+    ///
+    /// [`MessageBuilder::singlepart`]: crate::message::MessageBuilder::singlepart
+    pub fn set_singlepart(&mut self, part: SinglePart) -> Result<(), Error> {
+        self.message.replace_singlepart(part)
+    }
+
+    /// Replace the body of the message with multiple MIME parts, see
+    /// [`MessageBuilder::multipart`]
+    ///
+    /// The `Content-Type` and `Content-Transfer-Encoding` headers of the message are
+    /// removed, as `part` has its own.
+    ///
+    /// [`MessageBuilder::multipart`]: crate::message::MessageBuilder::multipart
+    pub fn set_multipart(&mut self, part: MultiPart) -> Result<(), Error> {
+        self.message.replace_multipart(part)
+    }
+}
+
+fn check_name(name: &str) -> Result<(), Error> {
+    match ENVELOPE_HEADERS
+        .iter()
+        .find(|envelope_header| envelope_header.eq_ignore_ascii_case(name))
+    {
+        Some(envelope_header) => Err(Error::EnvelopeHeader((*envelope_header).to_owned())),
+        None => Ok(()),
+    }
+}
+
+fn check_header(name: &str, value: &str) -> Result<(), Error> {
+    check_name(name)?;
+    if value.contains(&['\r', '\n'][..]) {
+        return Err(Error::HeaderLineBreak);
+    }
+    Ok(())
+}
+
+/// Runs middlewares on every message before sending it with the inner transport
+#[derive(Clone)]
+pub struct MiddlewareTransport<T> {
+    inner: T,
+    middlewares: Vec<Arc<dyn MessageMiddleware>>,
+}
+
+impl<T> MiddlewareTransport<T> {
+    /// Wraps `inner`, without any middleware
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            middlewares: Vec::new(),
+        }
+    }
+
+    /// Add a middleware, run after the previously added ones
+    pub fn before_send<M>(mut self, middleware: M) -> Self
+    where
+        M: MessageMiddleware + 'static,
+    {
+        self.middlewares.push(Arc::new(middleware));
+        self
+    }
+
+    /// Get a reference to the inner transport
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get the inner transport
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn apply(&self, message: &mut Message) {
+        let mut message = MiddlewareMessage { message };
+        for middleware in &self.middlewares {
+            middleware.before_send(&mut message);
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for MiddlewareTransport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MiddlewareTransport")
+            .field("inner", &self.inner)
+            .field("middlewares", &self.middlewares.len())
+            .finish()
+    }
+}
+
+impl<T: Transport> Transport for MiddlewareTransport<T> {
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    fn send(&self, message: &Message) -> Result<Self::Ok, Self::Error> {
+        if self.middlewares.is_empty() {
+            return self.inner.send(message);
+        }
+
+        let mut message = message.clone();
+        self.apply(&mut message);
+        self.inner.send(&message)
+    }
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.inner.send_raw(envelope, email)
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl<T> AsyncTransport for MiddlewareTransport<T>
+where
+    T: AsyncTransport + Sync,
+{
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    async fn send(&self, mut message: Message) -> Result<Self::Ok, Self::Error> {
+        self.apply(&mut message);
+        self.inner.send(message).await
+    }
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        self.inner.send_raw(envelope, email).await
+    }
+}
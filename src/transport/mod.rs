@@ -12,7 +12,7 @@
 //!
 //! * a service from your Cloud or hosting provider
 //! * an email server ([MTA] for Mail Transfer Agent, like Postfix or Exchange), running either
-//! locally on your servers or accessible over the network
+//!   locally on your servers or accessible over the network
 //! * a dedicated external service, like Mailchimp, Mailgun, etc.
 //!
 //! In most cases, the best option is to:
@@ -27,12 +27,25 @@
 //!
 //! The following transports are available:
 //!
-//! | Module       | Protocol | Sync API              | Async API                  | Description                                             |
-//! | ------------ | -------- | --------------------- | -------------------------- | ------------------------------------------------------- |
-//! | [`smtp`]     | SMTP     | [`SmtpTransport`]     | [`AsyncSmtpTransport`]     | Uses the SMTP protocol to send emails to a relay server |
-//! | [`sendmail`] | Sendmail | [`SendmailTransport`] | [`AsyncSendmailTransport`] | Uses the `sendmail` command to send emails              |
-//! | [`file`]     | File     | [`FileTransport`]     | [`AsyncFileTransport`]     | Saves the email as an `.eml` file                       |
-//! | [`stub`]     | Debug    | [`StubTransport`]     | [`StubTransport`]          | Drops the email - Useful for debugging                  |
+//! | Module              | Protocol | Sync API                    | Async API                     | Description                                             |
+//! | ------------------- | -------- | --------------------------- | ----------------------------- | ------------------------------------------------------- |
+//! | [`smtp`]            | SMTP     | [`SmtpTransport`]           | [`AsyncSmtpTransport`]        | Uses the SMTP protocol to send emails to a relay server |
+//! | [`lmtp`]            | LMTP     | [`LmtpTransport`]           | -                             | Delivers the email to a local mail store                |
+//! | [`direct`]          | SMTP     | [`DirectTransport`]         | -                             | Delivers the email to the MX hosts of a domain          |
+//! | [`sendmail`]        | Sendmail | [`SendmailTransport`]       | [`AsyncSendmailTransport`]    | Uses the `sendmail` command to send emails              |
+//! | [`file`]            | File     | [`FileTransport`]           | [`AsyncFileTransport`]        | Saves the email as an `.eml` file                       |
+//! | [`stub`]            | Debug    | [`StubTransport`]           | [`StubTransport`]             | Records the email - Useful for debugging                |
+//! | [`channel`]         | Debug    | [`ChannelTransport`]        | [`ChannelTransport`]          | Sends the email into a channel - Useful for testing     |
+//! | [`boxed`]           | Any      | [`BoxedTransport`]          | [`BoxedAsyncTransport`]       | Erases the type of a transport chosen at runtime        |
+//! | [`failover`]        | Any      | [`FailoverTransport`]       | [`AsyncFailoverTransport`]    | Tries several transports in order until one succeeds    |
+//! | [`circuit_breaker`] | Any      | [`CircuitBreakerTransport`] | [`CircuitBreakerTransport`]   | Fails fast for a while after consecutive failures       |
+//! | [`filter`]          | Any      | [`FilterTransport`]         | [`FilterTransport`]           | Only sends the email to the allowed recipients          |
+//! | [`middleware`]      | Any      | [`MiddlewareTransport`]     | [`MiddlewareTransport`]       | Modifies the email before sending it                    |
+//! | [`queue`]           | Any      | [`QueueTransport`]          | -                             | Stores the email and sends it in the background         |
+//! | [`rate_limited`]    | Any      | [`RateLimitedTransport`]    | [`AsyncRateLimitedTransport`] | Limits the number of emails sent per time window        |
+//! | [`redirect`]        | Any      | [`RedirectTransport`]       | [`RedirectTransport`]         | Sends all the emails to a single address                |
+//! | [`traced`]          | Any      | [`TracedTransport`]         | [`TracedTransport`]           | Emits `tracing` events for every sent email             |
+//! | [`writer`]          | Debug    | [`WriterTransport`]         | -                             | Writes the email to a `Write` sink                      |
 //!
 //! ## Building an email
 //!
@@ -93,22 +106,56 @@
 //! [`file`]: self::file
 //! [`SmtpTransport`]: crate::SmtpTransport
 //! [`AsyncSmtpTransport`]: crate::AsyncSmtpTransport
+//! [`lmtp`]: crate::transport::smtp::lmtp
+//! [`LmtpTransport`]: crate::transport::smtp::lmtp::LmtpTransport
+//! [`direct`]: crate::transport::smtp::direct
+//! [`DirectTransport`]: crate::transport::smtp::direct::DirectTransport
 //! [`SendmailTransport`]: crate::SendmailTransport
 //! [`AsyncSendmailTransport`]: crate::AsyncSendmailTransport
 //! [`FileTransport`]: crate::FileTransport
 //! [`AsyncFileTransport`]: crate::AsyncFileTransport
 //! [`StubTransport`]: crate::transport::stub::StubTransport
+//! [`ChannelTransport`]: crate::transport::channel::ChannelTransport
+//! [`BoxedTransport`]: crate::transport::boxed::BoxedTransport
+//! [`BoxedAsyncTransport`]: crate::transport::boxed::BoxedAsyncTransport
+//! [`FailoverTransport`]: crate::transport::failover::FailoverTransport
+//! [`AsyncFailoverTransport`]: crate::transport::failover::AsyncFailoverTransport
+//! [`CircuitBreakerTransport`]: crate::transport::circuit_breaker::CircuitBreakerTransport
+//! [`FilterTransport`]: crate::transport::filter::FilterTransport
+//! [`MiddlewareTransport`]: crate::transport::middleware::MiddlewareTransport
+//! [`QueueTransport`]: crate::transport::queue::QueueTransport
+//! [`RateLimitedTransport`]: crate::transport::rate_limited::RateLimitedTransport
+//! [`AsyncRateLimitedTransport`]: crate::transport::rate_limited::AsyncRateLimitedTransport
+//! [`RedirectTransport`]: crate::transport::redirect::RedirectTransport
+//! [`TracedTransport`]: crate::transport::traced::TracedTransport
+//! [`WriterTransport`]: crate::transport::writer::WriterTransport
 
 #[cfg(any(feature = "async-std1", feature = "tokio1"))]
 use async_trait::async_trait;
 
+#[cfg(any(feature = "async-std1", feature = "tokio1"))]
+use self::boxed::BoxedAsyncTransport;
+use self::{boxed::BoxedTransport, failover::FailoverError};
 use crate::Envelope;
 #[cfg(feature = "builder")]
 use crate::Message;
 
+pub mod boxed;
+pub mod channel;
+pub mod circuit_breaker;
+pub mod failover;
 #[cfg(feature = "file-transport")]
 #[cfg_attr(docsrs, doc(cfg(feature = "file-transport")))]
 pub mod file;
+pub mod filter;
+#[cfg(feature = "builder")]
+#[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+pub mod middleware;
+#[cfg(feature = "file-transport-envelope")]
+#[cfg_attr(docsrs, doc(cfg(feature = "file-transport-envelope")))]
+pub mod queue;
+pub mod rate_limited;
+pub mod redirect;
 #[cfg(feature = "sendmail-transport")]
 #[cfg_attr(docsrs, doc(cfg(feature = "sendmail-transport")))]
 pub mod sendmail;
@@ -116,6 +163,10 @@ pub mod sendmail;
 #[cfg_attr(docsrs, doc(cfg(feature = "smtp-transport")))]
 pub mod smtp;
 pub mod stub;
+#[cfg(feature = "tracing")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tracing")))]
+pub mod traced;
+pub mod writer;
 
 /// Blocking Transport method for emails
 pub trait Transport {
@@ -133,6 +184,15 @@ pub trait Transport {
     }
 
     fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error>;
+
+    /// Erases the type of the transport, to choose between transports at runtime
+    fn boxed(self) -> BoxedTransport
+    where
+        Self: Sized + Send + Sync + 'static,
+        Self::Error: FailoverError,
+    {
+        BoxedTransport::new(self)
+    }
 }
 
 /// Async Transport method for emails
@@ -150,10 +210,18 @@ pub trait AsyncTransport {
     #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
     // TODO take &Message
     async fn send(&self, message: Message) -> Result<Self::Ok, Self::Error> {
-        let raw = message.formatted();
-        let envelope = message.envelope();
-        self.send_raw(envelope, &raw).await
+        let (envelope, raw) = message.into_parts();
+        self.send_raw(&envelope, &raw).await
     }
 
     async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error>;
+
+    /// Erases the type of the transport, to choose between transports at runtime
+    fn boxed(self) -> BoxedAsyncTransport
+    where
+        Self: Sized + Send + Sync + 'static,
+        Self::Error: FailoverError,
+    {
+        BoxedAsyncTransport::new(self)
+    }
 }
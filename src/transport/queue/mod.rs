@@ -0,0 +1,538 @@
+//! The queue transport persists messages to a directory and returns immediately, while
+//! a background thread delivers them through an inner transport.
+//!
+//! Messages are stored in the format of the [`file`] transport with its envelope, as an
+//! `.eml` file and a `.json` file named after the message id, which is returned by
+//! `send`. Messages which are still in the directory when a queue is built, for example
+//! after a crash, are delivered again.
+//!
+//! Sending failures classified as retryable by [`FailoverError`] are retried with an
+//! exponential backoff. Messages which failed with a permanent error, or too many times,
+//! are moved to the `dead` subdirectory of the queue directory.
+//!
+//! Shutting down the queue, or dropping it, tries to deliver every queued message once
+//! more, without waiting for their retry delay, and waits for the background thread to
+//! stop. Messages which still failed with a retryable error are kept in the queue
+//! directory for the next start.
+//!
+//! #### Queue Transport
+//!
+//! ```rust,no_run
+//! # #[cfg(all(feature = "builder", feature = "smtp-transport"))]
+//! # {
+//! use lettre::{
+//!     transport::queue::{Outcome, QueueTransport},
+//!     Message, SmtpTransport, Transport,
+//! };
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let sender = QueueTransport::builder("/var/spool/app-mail", SmtpTransport::relay("smtp.domain.tld")?.build())
+//!     .max_queued(10_000)
+//!     .on_outcome(|id, outcome| {
+//!         if let Outcome::DeadLettered { error } = outcome {
+//!             eprintln!("couldn't send email {}: {}", id, error);
+//!         }
+//!     })
+//!     .build()?;
+//!
+//! // Returns as soon as the message has been written to the queue directory
+//! let id = sender.send(&email)?;
+//!
+//! // Waits for the queued messages to be sent
+//! sender.shutdown();
+//! # Ok(())
+//! # }
+//! # }
+//! ```
+//!
+//! [`file`]: crate::transport::file
+//! [`FailoverError`]: crate::transport::failover::FailoverError
+
+use crate::{
+    address::Envelope,
+    transport::{
+        failover::FailoverError,
+        file::{self, FileTransport},
+    },
+    BoxError, Transport,
+};
+use std::{
+    error::Error as StdError,
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+/// Name of the subdirectory holding the messages which couldn't be sent
+const DEAD_LETTER_DIR: &str = "dead";
+
+type Hook = Arc<dyn Fn(&str, &Outcome<'_>) + Send + Sync>;
+
+/// Result of a delivery attempt of a queued message
+#[derive(Debug)]
+pub enum Outcome<'a> {
+    /// The message was sent, and removed from the queue
+    Delivered,
+    /// Sending the message failed with a retryable error, and will be retried later
+    Retrying {
+        /// Number of failed attempts so far
+        attempt: u32,
+        /// Error returned by the inner transport
+        error: &'a (dyn StdError + 'static),
+    },
+    /// Sending the message failed with a retryable error while the queue was shutting
+    /// down; the message stays in the queue directory
+    Postponed {
+        /// Error returned by the inner transport
+        error: &'a (dyn StdError + 'static),
+    },
+    /// The message couldn't be sent, and was moved to the dead letter directory
+    DeadLettered {
+        /// Error which caused the message to be given up
+        error: &'a (dyn StdError + 'static),
+    },
+}
+
+/// Error returned by the queue transport
+#[derive(Debug)]
+pub enum Error {
+    /// The queue already holds the maximum number of messages
+    Full,
+    /// The message couldn't be written to the queue directory
+    Storage(file::Error),
+    /// The queue directory or the background thread couldn't be set up
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Full => f.write_str("queue is full"),
+            Error::Storage(err) => write!(f, "couldn't store message: {}", err),
+            Error::Io(err) => write!(f, "couldn't set up queue: {}", err),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Full => None,
+            Error::Storage(err) => Some(err),
+            Error::Io(err) => Some(err),
+        }
+    }
+}
+
+impl FailoverError for Error {
+    fn should_try_next(&self) -> bool {
+        true
+    }
+}
+
+/// A queued message
+#[derive(Debug)]
+struct Pending {
+    id: String,
+    attempts: u32,
+    due: Instant,
+}
+
+#[derive(Debug)]
+struct State {
+    pending: Vec<Pending>,
+    in_flight: usize,
+    shutdown: bool,
+}
+
+impl State {
+    fn len(&self) -> usize {
+        self.pending.len() + self.in_flight
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    state: Mutex<State>,
+    changed: Condvar,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().expect("Couldn't acquire lock on queue")
+    }
+}
+
+/// Builder for `QueueTransport`
+pub struct QueueTransportBuilder<T> {
+    path: PathBuf,
+    inner: T,
+    max_queued: Option<usize>,
+    max_attempts: u32,
+    retry_delay: Duration,
+    max_retry_delay: Duration,
+    hook: Option<Hook>,
+}
+
+impl<T> QueueTransportBuilder<T> {
+    /// Set the maximum number of messages in the queue
+    ///
+    /// Sending a message while the queue is full returns [`Error::Full`]. Defaults to no limit.
+    pub fn max_queued(mut self, max_queued: usize) -> Self {
+        self.max_queued = Some(max_queued);
+        self
+    }
+
+    /// Set the maximum number of delivery attempts of a message before moving it
+    /// to the dead letter directory
+    ///
+    /// Defaults to 5.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Set the delay before the first retry, and the maximum delay between retries
+    ///
+    /// The delay is doubled after every failed attempt. Defaults to 1 second and 5 minutes.
+    pub fn retry_delay(mut self, initial: Duration, max: Duration) -> Self {
+        self.retry_delay = initial;
+        self.max_retry_delay = max;
+        self
+    }
+
+    /// Set a function called with the id of a message and the outcome of every
+    /// delivery attempt
+    ///
+    /// The function is called from the background thread.
+    pub fn on_outcome<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str, &Outcome<'_>) + Send + Sync + 'static,
+    {
+        self.hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Creates the queue directories, and starts delivering the queued messages
+    pub fn build(self) -> Result<QueueTransport, Error>
+    where
+        T: Transport + Send + 'static,
+        T::Error: FailoverError,
+    {
+        let dead_letter_dir = self.path.join(DEAD_LETTER_DIR);
+        fs::create_dir_all(&dead_letter_dir).map_err(Error::Io)?;
+
+        let shared = Arc::new(Shared {
+            state: Mutex::new(State {
+                pending: recover(&self.path).map_err(Error::Io)?,
+                in_flight: 0,
+                shutdown: false,
+            }),
+            changed: Condvar::new(),
+        });
+
+        let worker = Worker {
+            inner: self.inner,
+            storage: FileTransport::with_envelope(&self.path),
+            path: self.path.clone(),
+            dead_letter_dir,
+            shared: Arc::clone(&shared),
+            max_attempts: self.max_attempts,
+            retry_delay: self.retry_delay,
+            max_retry_delay: self.max_retry_delay,
+            hook: self.hook,
+        };
+        let worker = thread::Builder::new()
+            .name("lettre-queue".to_owned())
+            .spawn(move || worker.run())
+            .map_err(Error::Io)?;
+
+        Ok(QueueTransport {
+            storage: FileTransport::with_envelope(&self.path),
+            shared,
+            max_queued: self.max_queued,
+            worker: Some(worker),
+        })
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for QueueTransportBuilder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueueTransportBuilder")
+            .field("path", &self.path)
+            .field("inner", &self.inner)
+            .field("max_queued", &self.max_queued)
+            .field("max_attempts", &self.max_attempts)
+            .field("retry_delay", &self.retry_delay)
+            .field("max_retry_delay", &self.max_retry_delay)
+            .finish()
+    }
+}
+
+/// Lists the messages left in the queue directory, oldest first
+fn recover(path: &Path) -> io::Result<Vec<Pending>> {
+    let mut messages = Vec::new();
+
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let file = entry.path();
+
+        let is_envelope = file.extension().map_or(false, |ext| ext == "json");
+        if !is_envelope || !file.with_extension("eml").is_file() {
+            continue;
+        }
+
+        if let Some(id) = file.file_stem().and_then(|id| id.to_str()) {
+            messages.push((entry.metadata()?.modified()?, id.to_owned()));
+        }
+    }
+    messages.sort();
+
+    let now = Instant::now();
+    Ok(messages
+        .into_iter()
+        .map(|(_, id)| Pending {
+            id,
+            attempts: 0,
+            due: now,
+        })
+        .collect())
+}
+
+/// Background delivery of the queued messages
+struct Worker<T> {
+    inner: T,
+    storage: FileTransport,
+    path: PathBuf,
+    dead_letter_dir: PathBuf,
+    shared: Arc<Shared>,
+    max_attempts: u32,
+    retry_delay: Duration,
+    max_retry_delay: Duration,
+    hook: Option<Hook>,
+}
+
+impl<T> Worker<T>
+where
+    T: Transport,
+    T::Error: FailoverError,
+{
+    fn run(self) {
+        while let Some((message, shutdown)) = self.next() {
+            self.deliver(message, shutdown);
+        }
+    }
+
+    /// Waits for the next message to deliver
+    ///
+    /// Returns `None` once the queue is shut down and every message was tried.
+    fn next(&self) -> Option<(Pending, bool)> {
+        let mut state = self.shared.lock();
+
+        loop {
+            let now = Instant::now();
+            let shutdown = state.shutdown;
+
+            let ready = state
+                .pending
+                .iter()
+                .enumerate()
+                .filter(|(_, message)| shutdown || message.due <= now)
+                .min_by_key(|(_, message)| message.due)
+                .map(|(i, _)| i);
+            if let Some(i) = ready {
+                state.in_flight += 1;
+                return Some((state.pending.remove(i), shutdown));
+            }
+
+            if shutdown {
+                return None;
+            }
+
+            state = match state.pending.iter().map(|message| message.due).min() {
+                Some(due) => {
+                    self.shared
+                        .changed
+                        .wait_timeout(state, due.saturating_duration_since(now))
+                        .expect("Couldn't acquire lock on queue")
+                        .0
+                }
+                None => self
+                    .shared
+                    .changed
+                    .wait(state)
+                    .expect("Couldn't acquire lock on queue"),
+            };
+        }
+    }
+
+    fn deliver(&self, mut message: Pending, shutdown: bool) {
+        let result = match self.storage.read(&message.id) {
+            Ok((envelope, email)) => self.inner.send_raw(&envelope, &email).map_err(|err| {
+                let retryable = err.should_try_next();
+                (BoxError::from(Box::new(err)), retryable)
+            }),
+            Err(err) => Err((BoxError::from(Box::new(err)), false)),
+        };
+
+        let requeue = match result {
+            Ok(_) => {
+                // A message which couldn't be removed will be sent again on the next start
+                let _ = fs::remove_file(self.file(&message.id, "eml"));
+                let _ = fs::remove_file(self.file(&message.id, "json"));
+                self.notify(&message.id, &Outcome::Delivered);
+                None
+            }
+            Err((error, retryable)) => {
+                message.attempts += 1;
+
+                if !retryable || message.attempts >= self.max_attempts {
+                    self.dead_letter(&message.id);
+                    self.notify(&message.id, &Outcome::DeadLettered { error: &*error });
+                    None
+                } else if shutdown {
+                    self.notify(&message.id, &Outcome::Postponed { error: &*error });
+                    None
+                } else {
+                    message.due = Instant::now() + self.delay(message.attempts);
+                    self.notify(
+                        &message.id,
+                        &Outcome::Retrying {
+                            attempt: message.attempts,
+                            error: &*error,
+                        },
+                    );
+                    Some(message)
+                }
+            }
+        };
+
+        let mut state = self.shared.lock();
+        state.in_flight -= 1;
+        state.pending.extend(requeue);
+    }
+
+    /// Delay before the next attempt, after `attempts` failed attempts
+    fn delay(&self, attempts: u32) -> Duration {
+        let factor = 1_u32 << (attempts - 1).min(31);
+        self.retry_delay
+            .checked_mul(factor)
+            .map_or(self.max_retry_delay, |delay| {
+                delay.min(self.max_retry_delay)
+            })
+    }
+
+    fn dead_letter(&self, id: &str) {
+        for extension in &["eml", "json"] {
+            let file = format!("{}.{}", id, extension);
+            let _ = fs::rename(self.path.join(&file), self.dead_letter_dir.join(&file));
+        }
+    }
+
+    fn file(&self, id: &str, extension: &str) -> PathBuf {
+        self.path.join(format!("{}.{}", id, extension))
+    }
+
+    fn notify(&self, id: &str, outcome: &Outcome<'_>) {
+        if let Some(hook) = &self.hook {
+            hook(id, outcome);
+        }
+    }
+}
+
+/// Persists messages and delivers them in the background through an inner transport
+pub struct QueueTransport {
+    storage: FileTransport,
+    shared: Arc<Shared>,
+    max_queued: Option<usize>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl QueueTransport {
+    /// Creates a new queue storing messages in `path` and delivering them with `inner`
+    pub fn builder<P: AsRef<Path>, T>(path: P, inner: T) -> QueueTransportBuilder<T> {
+        QueueTransportBuilder {
+            path: path.as_ref().to_path_buf(),
+            inner,
+            max_queued: None,
+            max_attempts: 5,
+            retry_delay: Duration::from_secs(1),
+            max_retry_delay: Duration::from_secs(5 * 60),
+            hook: None,
+        }
+    }
+
+    /// Number of messages in the queue, including the ones being sent
+    pub fn len(&self) -> usize {
+        self.shared.lock().len()
+    }
+
+    /// Returns true if all the queued messages were delivered or given up
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Tries to deliver every queued message once more, and stops the background thread
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        self.shared.lock().shutdown = true;
+        self.shared.changed.notify_all();
+
+        if let Some(worker) = self.worker.take() {
+            // The messages of a panicked worker are still in the queue directory
+            let _ = worker.join();
+        }
+    }
+}
+
+impl Drop for QueueTransport {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+impl fmt::Debug for QueueTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("QueueTransport")
+            .field("storage", &self.storage)
+            .field("max_queued", &self.max_queued)
+            .finish()
+    }
+}
+
+impl Transport for QueueTransport {
+    type Ok = String;
+    type Error = Error;
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let mut state = self.shared.lock();
+        if self.max_queued.map_or(false, |max| state.len() >= max) {
+            return Err(Error::Full);
+        }
+
+        let id = self
+            .storage
+            .send_raw(envelope, email)
+            .map_err(Error::Storage)?;
+        state.pending.push(Pending {
+            id: id.clone(),
+            attempts: 0,
+            due: Instant::now(),
+        });
+        self.shared.changed.notify_all();
+
+        Ok(id)
+    }
+}
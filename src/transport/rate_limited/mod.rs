@@ -0,0 +1,350 @@
+//! The rate limited transport wraps another transport, and limits the number of messages
+//! it sends per time window.
+//!
+//! The limit is a token bucket: up to `burst` messages can be sent at once, and the bucket
+//! is then refilled at the configured rate. Sending a message while the limit is reached
+//! waits for the next available permit, blocking the current thread with the sync API,
+//! and awaiting with the async API. `try_send` returns [`Error::WouldExceedRateLimit`]
+//! instead of waiting.
+//!
+//! The limit is shared between the clones of a transport.
+//!
+//! #### Rate Limited Transport
+//!
+//! ```rust
+//! # #[cfg(feature = "builder")]
+//! # {
+//! use std::time::Duration;
+//!
+//! use lettre::{
+//!     transport::{
+//!         rate_limited::{Error, RateLimit, RateLimitedTransport},
+//!         stub::StubTransport,
+//!     },
+//!     Message, Transport,
+//! };
+//!
+//! # use std::error::Error as StdError;
+//! # fn main() -> Result<(), Box<dyn StdError>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! // Send at most 10 messages per minute
+//! let sender = RateLimitedTransport::new(
+//!     StubTransport::new_ok(),
+//!     RateLimit::new(10, Duration::from_secs(60)).burst(1),
+//! );
+//!
+//! sender.send(&email)?;
+//! assert!(matches!(
+//!     sender.try_send(&email),
+//!     Err(Error::WouldExceedRateLimit)
+//! ));
+//! # Ok(())
+//! # }
+//! # }
+//! ```
+
+#[cfg(feature = "builder")]
+use crate::Message;
+use crate::{address::Envelope, transport::failover::FailoverError, Transport};
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use crate::{AsyncTransport, Executor};
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use async_trait::async_trait;
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use std::marker::PhantomData;
+use std::{
+    error::Error as StdError,
+    fmt,
+    sync::{Arc, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Maximum rate of a `RateLimitedTransport`
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RateLimit {
+    messages: u32,
+    window: Duration,
+    burst: u32,
+}
+
+impl RateLimit {
+    /// Allows sending `messages` per `window`
+    ///
+    /// Up to `messages` can be sent at once, see [`RateLimit::burst`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `messages` is 0.
+    pub fn new(messages: u32, window: Duration) -> Self {
+        assert!(messages > 0, "a rate limit must allow at least one message");
+
+        Self {
+            messages,
+            window,
+            burst: messages,
+        }
+    }
+
+    /// Set the maximum number of messages which can be sent at once
+    ///
+    /// A burst of 1 evenly spreads the messages over the window.
+    /// Values lower than 1 are treated as 1.
+    pub fn burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+}
+
+/// Token bucket, tracking the time at which the bucket will be full again
+#[derive(Debug)]
+struct Limiter {
+    /// Time needed to get one more permit
+    interval: Duration,
+    /// How far in the future permits can be taken
+    tolerance: Duration,
+    full_at: Mutex<Option<Instant>>,
+}
+
+impl Limiter {
+    fn new(limit: RateLimit) -> Self {
+        let interval = limit.window / limit.messages;
+
+        Self {
+            interval,
+            tolerance: interval * (limit.burst - 1),
+            full_at: Mutex::new(None),
+        }
+    }
+
+    /// Takes the next permit, and returns how long to wait before using it
+    fn reserve(&self) -> Duration {
+        self.take(true).unwrap_or_else(|wait| wait)
+    }
+
+    /// Takes a permit if one is available now
+    fn try_acquire(&self) -> bool {
+        self.take(false).is_ok()
+    }
+
+    fn take(&self, reserve: bool) -> Result<Duration, Duration> {
+        let now = Instant::now();
+        let mut full_at = self
+            .full_at
+            .lock()
+            .expect("Couldn't acquire lock on rate limit");
+
+        let start = full_at.map_or(now, |full_at| full_at.max(now));
+        let wait = start
+            .saturating_duration_since(now)
+            .checked_sub(self.tolerance)
+            .unwrap_or_else(|| Duration::from_secs(0));
+
+        if wait == Duration::from_secs(0) || reserve {
+            *full_at = Some(start + self.interval);
+            Ok(wait)
+        } else {
+            Err(wait)
+        }
+    }
+}
+
+/// Error returned by `try_send`
+#[derive(Debug)]
+pub enum Error<E> {
+    /// Sending the message now would exceed the rate limit
+    WouldExceedRateLimit,
+    /// The inner transport failed to send the message
+    Transport(E),
+}
+
+impl<E: fmt::Display> fmt::Display for Error<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::WouldExceedRateLimit => f.write_str("sending would exceed the rate limit"),
+            Error::Transport(err) => fmt::Display::fmt(err, f),
+        }
+    }
+}
+
+impl<E: StdError + 'static> StdError for Error<E> {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::WouldExceedRateLimit => None,
+            Error::Transport(err) => Some(err),
+        }
+    }
+}
+
+impl<E: FailoverError> FailoverError for Error<E> {
+    fn should_try_next(&self) -> bool {
+        match self {
+            Error::WouldExceedRateLimit => true,
+            Error::Transport(err) => err.should_try_next(),
+        }
+    }
+}
+
+/// Limits the number of messages sent with the inner transport
+#[derive(Debug, Clone)]
+pub struct RateLimitedTransport<T> {
+    inner: T,
+    limiter: Arc<Limiter>,
+}
+
+impl<T> RateLimitedTransport<T> {
+    /// Wraps `inner`, limiting it to `limit`
+    pub fn new(inner: T, limit: RateLimit) -> Self {
+        Self {
+            inner,
+            limiter: Arc::new(Limiter::new(limit)),
+        }
+    }
+
+    /// Get a reference to the inner transport
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+}
+
+impl<T: Transport> RateLimitedTransport<T> {
+    /// Sends the email if it doesn't exceed the rate limit
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    pub fn try_send(&self, message: &Message) -> Result<T::Ok, Error<T::Error>> {
+        let raw = message.formatted();
+        self.try_send_raw(message.envelope(), &raw)
+    }
+
+    /// Sends the email if it doesn't exceed the rate limit
+    pub fn try_send_raw(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+    ) -> Result<T::Ok, Error<T::Error>> {
+        if !self.limiter.try_acquire() {
+            return Err(Error::WouldExceedRateLimit);
+        }
+
+        self.inner
+            .send_raw(envelope, email)
+            .map_err(Error::Transport)
+    }
+}
+
+impl<T: Transport> Transport for RateLimitedTransport<T> {
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let wait = self.limiter.reserve();
+        if wait > Duration::from_secs(0) {
+            thread::sleep(wait);
+        }
+
+        self.inner.send_raw(envelope, email)
+    }
+}
+
+/// Asynchronously limits the number of messages sent with the inner transport
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "tokio1", feature = "async-std1"))))]
+pub struct AsyncRateLimitedTransport<T, E: Executor> {
+    inner: T,
+    limiter: Arc<Limiter>,
+    marker_: PhantomData<E>,
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+impl<T, E: Executor> AsyncRateLimitedTransport<T, E> {
+    /// Wraps `inner`, limiting it to `limit`
+    pub fn new(inner: T, limit: RateLimit) -> Self {
+        Self {
+            inner,
+            limiter: Arc::new(Limiter::new(limit)),
+            marker_: PhantomData,
+        }
+    }
+
+    /// Get a reference to the inner transport
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+impl<T, E> AsyncRateLimitedTransport<T, E>
+where
+    T: AsyncTransport + Sync,
+    E: Executor,
+{
+    /// Sends the email if it doesn't exceed the rate limit
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    pub async fn try_send(&self, message: Message) -> Result<T::Ok, Error<T::Error>> {
+        let raw = message.formatted();
+        self.try_send_raw(message.envelope(), &raw).await
+    }
+
+    /// Sends the email if it doesn't exceed the rate limit
+    pub async fn try_send_raw(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+    ) -> Result<T::Ok, Error<T::Error>> {
+        if !self.limiter.try_acquire() {
+            return Err(Error::WouldExceedRateLimit);
+        }
+
+        self.inner
+            .send_raw(envelope, email)
+            .await
+            .map_err(Error::Transport)
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+impl<T: Clone, E: Executor> Clone for AsyncRateLimitedTransport<T, E> {
+    fn clone(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            limiter: Arc::clone(&self.limiter),
+            marker_: PhantomData,
+        }
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+impl<T: fmt::Debug, E: Executor> fmt::Debug for AsyncRateLimitedTransport<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncRateLimitedTransport")
+            .field("inner", &self.inner)
+            .field("limiter", &self.limiter)
+            .finish()
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl<T, E> AsyncTransport for AsyncRateLimitedTransport<T, E>
+where
+    T: AsyncTransport + Sync,
+    E: Executor,
+{
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let wait = self.limiter.reserve();
+        if wait > Duration::from_secs(0) {
+            E::sleep(wait).await;
+        }
+
+        self.inner.send_raw(envelope, email).await
+    }
+}
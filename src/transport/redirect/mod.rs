@@ -0,0 +1,163 @@
+//! The redirect transport wraps another transport, and sends all the emails to a single
+//! address instead of their recipients.
+//!
+//! This is a safety net for staging environments, which can send all their emails to
+//! a development inbox without any risk of emailing real users. The recipients of the
+//! envelope are replaced with the redirect address, and the original ones are kept in an
+//! `X-Original-To` header added at the top of the email. The sender and the headers of
+//! the email, including `To` and `Cc`, are left unchanged.
+//!
+//! Both the emails sent with `send` and `send_raw` are redirected.
+//!
+//! #### Redirect Transport
+//!
+//! ```rust
+//! # #[cfg(feature = "builder")]
+//! # {
+//! use lettre::{
+//!     transport::{redirect::RedirectTransport, stub::StubTransport},
+//!     Message, Transport,
+//! };
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let sender = RedirectTransport::new(StubTransport::new_ok(), "dev@domain.tld".parse()?);
+//! sender.send(&email)?;
+//! # Ok(())
+//! # }
+//! # }
+//! ```
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use crate::AsyncTransport;
+use crate::{address::Envelope, Address, Transport};
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use async_trait::async_trait;
+
+/// Name of the header keeping the original recipients
+const ORIGINAL_TO: &str = "X-Original-To";
+
+/// Maximum length of a header line, before folding it
+const MAX_LINE_LEN: usize = 78;
+
+/// Sends all the emails to a single address with the inner transport
+#[derive(Debug, Clone)]
+pub struct RedirectTransport<T> {
+    inner: T,
+    to: Address,
+}
+
+impl<T> RedirectTransport<T> {
+    /// Wraps `inner`, sending all the emails to `to`
+    pub fn new(inner: T, to: Address) -> Self {
+        Self { inner, to }
+    }
+
+    /// Address all the emails are sent to
+    pub fn redirect_to(&self) -> &Address {
+        &self.to
+    }
+
+    /// Get a reference to the inner transport
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get the inner transport
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn redirect(&self, envelope: &Envelope, email: &[u8]) -> (Envelope, Vec<u8>) {
+        let redirected = Envelope::new(envelope.from().cloned(), vec![self.to.clone()])
+            .expect("the envelope has a recipient");
+
+        let header = original_to_header(envelope.to());
+        let mut redirected_email = Vec::with_capacity(header.len() + email.len());
+        redirected_email.extend_from_slice(header.as_bytes());
+        redirected_email.extend_from_slice(email);
+
+        (redirected, redirected_email)
+    }
+}
+
+/// Formats the `X-Original-To` header, folded after the commas
+fn original_to_header(recipients: &[Address]) -> String {
+    let mut header = format!("{}:", ORIGINAL_TO);
+    let mut line_len = header.len();
+    for (i, recipient) in recipients.iter().enumerate() {
+        let recipient: &str = recipient.as_ref();
+        if i > 0 {
+            header.push(',');
+            line_len += 1;
+        }
+        if i > 0 && line_len + 1 + recipient.len() > MAX_LINE_LEN {
+            header.push_str("\r\n");
+            line_len = 0;
+        }
+        header.push(' ');
+        header.push_str(recipient);
+        line_len += 1 + recipient.len();
+    }
+    header.push_str("\r\n");
+    header
+}
+
+impl<T: Transport> Transport for RedirectTransport<T> {
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let (envelope, email) = self.redirect(envelope, email);
+        self.inner.send_raw(&envelope, &email)
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl<T> AsyncTransport for RedirectTransport<T>
+where
+    T: AsyncTransport + Sync,
+{
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let (envelope, email) = self.redirect(envelope, email);
+        self.inner.send_raw(&envelope, &email).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn original_to_header_folded() {
+        let recipients = (0..4)
+            .map(|i| {
+                format!("recipient{}@some-long-domain.tld", i)
+                    .parse()
+                    .unwrap()
+            })
+            .collect::<Vec<Address>>();
+        assert_eq!(
+            original_to_header(&recipients[..1]),
+            "X-Original-To: recipient0@some-long-domain.tld\r\n"
+        );
+        assert_eq!(
+            original_to_header(&recipients),
+            concat!(
+                "X-Original-To: recipient0@some-long-domain.tld,\r\n",
+                " recipient1@some-long-domain.tld, recipient2@some-long-domain.tld,\r\n",
+                " recipient3@some-long-domain.tld\r\n"
+            )
+        );
+    }
+}
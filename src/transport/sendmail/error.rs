@@ -1,6 +1,6 @@
 //! Error and result type for sendmail transport
 
-use crate::BoxError;
+use crate::{transport::failover::FailoverError, BoxError};
 use std::{error::Error as StdError, fmt};
 
 /// The Errors that may occur when sending an email over sendmail
@@ -83,6 +83,14 @@ impl StdError for Error {
     }
 }
 
+impl FailoverError for Error {
+    /// The sendmail command can fail because of the local mail system, like a
+    /// missing binary or a full queue, which another transport doesn't depend on
+    fn should_try_next(&self) -> bool {
+        true
+    }
+}
+
 pub(crate) fn response<E: Into<BoxError>>(e: E) -> Error {
     Error::new(Kind::Response, Some(e))
 }
@@ -1,25 +1,47 @@
+#[cfg(feature = "socket2")]
+use std::ops::RangeInclusive;
+#[cfg(unix)]
+use std::path::Path;
 use std::{
     fmt::{self, Debug},
+    io,
     marker::PhantomData,
+    net::SocketAddr,
     sync::Arc,
-    time::Duration,
+    time::{Duration, Instant},
 };
 
 use async_trait::async_trait;
+use futures_io::AsyncRead;
 
+#[cfg(not(feature = "pool"))]
+use super::observer::CloseReason;
 #[cfg(feature = "pool")]
 use super::pool::async_impl::Pool;
 #[cfg(feature = "pool")]
 use super::PoolConfig;
 use super::{
-    client::AsyncSmtpConnection, ClientId, Credentials, Error, Mechanism, Response, SmtpInfo,
+    check_message_size,
+    client::{AsyncSmtpConnection, ProxyProtocol, ProxyProtocolVersion},
+    commands::XClient,
+    dns_cache::DnsCache,
+    error,
+    observer::{notify, notify_phases, PhaseTimings, TransportObserver},
+    relay::{RelayHealth, RelaySelection, Relays},
+    response::{EnvelopeVerification, SplitDelivery},
+    tls_info,
+    transcript::{finish_send, TranscriptConfig},
+    ClientId, ConnectionTarget, Credentials, Error, Mechanism, Response, SmtpInfo, TcpTarget, Tls,
+    CONNECT_RETRY_DELAY, MAX_SPLIT_TRANSACTIONS, SMTP_PORT,
 };
 #[cfg(feature = "async-std1")]
 use crate::AsyncStd1Executor;
 #[cfg(any(feature = "tokio1", feature = "async-std1"))]
 use crate::AsyncTransport;
+#[cfg(feature = "builder")]
+use crate::Message;
 #[cfg(feature = "tokio1")]
-use crate::Tokio1Executor;
+use crate::{executor::Tokio1Read, Tokio1Executor};
 use crate::{Envelope, Executor};
 
 /// Asynchronously sends emails using the SMTP protocol
@@ -29,6 +51,13 @@ pub struct AsyncSmtpTransport<E: Executor> {
     inner: Arc<Pool<E>>,
     #[cfg(not(feature = "pool"))]
     inner: AsyncSmtpClient<E>,
+    observer: Option<Arc<dyn TransportObserver>>,
+    relays: Arc<Relays>,
+    max_message_size: Option<usize>,
+    max_recipients_per_transaction: Option<usize>,
+    split_on_too_many_recipients: bool,
+    #[cfg(feature = "builder")]
+    strip_return_path: bool,
 }
 
 #[cfg(feature = "tokio1")]
@@ -37,16 +66,19 @@ impl AsyncTransport for AsyncSmtpTransport<Tokio1Executor> {
     type Ok = Response;
     type Error = Error;
 
+    /// Sends an email, limited to its own maximum size if it has one
+    #[cfg(feature = "builder")]
+    async fn send(&self, message: Message) -> Result<Self::Ok, Self::Error> {
+        let max_message_size = message.max_size().or(self.max_message_size);
+        let raw = self.formatted(&message);
+        let envelope = message.envelope();
+        self.send_email(envelope, &raw, max_message_size).await
+    }
+
     /// Sends an email
     async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
-        let mut conn = self.inner.connection().await?;
-
-        let result = conn.send(envelope, email).await?;
-
-        #[cfg(not(feature = "pool"))]
-        conn.quit().await?;
-
-        Ok(result)
+        self.send_email(envelope, email, self.max_message_size)
+            .await
     }
 }
 
@@ -56,15 +88,19 @@ impl AsyncTransport for AsyncSmtpTransport<AsyncStd1Executor> {
     type Ok = Response;
     type Error = Error;
 
+    /// Sends an email, limited to its own maximum size if it has one
+    #[cfg(feature = "builder")]
+    async fn send(&self, message: Message) -> Result<Self::Ok, Self::Error> {
+        let max_message_size = message.max_size().or(self.max_message_size);
+        let raw = self.formatted(&message);
+        let envelope = message.envelope();
+        self.send_email(envelope, &raw, max_message_size).await
+    }
+
     /// Sends an email
     async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
-        let mut conn = self.inner.connection().await?;
-
-        let result = conn.send(envelope, email).await?;
-
-        conn.quit().await?;
-
-        Ok(result)
+        self.send_email(envelope, email, self.max_message_size)
+            .await
     }
 }
 
@@ -158,15 +194,252 @@ where
     /// if possible.
     pub fn builder_dangerous<T: Into<String>>(server: T) -> AsyncSmtpTransportBuilder {
         let info = SmtpInfo {
-            server: server.into(),
+            target: ConnectionTarget::Tcp(server.into(), SMTP_PORT),
             ..Default::default()
         };
         AsyncSmtpTransportBuilder {
             info,
+            resolver: None,
             #[cfg(feature = "pool")]
             pool_config: PoolConfig::default(),
         }
     }
+
+    /// Creates a new SMTP client connecting to the Unix domain socket at `path`
+    ///
+    /// Local mail servers like Postfix can accept emails on a Unix domain socket.
+    /// The same defaults as [`AsyncSmtpTransport::builder_dangerous`](#method.builder_dangerous)
+    /// apply, and TLS can't be used.
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    pub fn unix<P: AsRef<Path>>(path: P) -> AsyncSmtpTransportBuilder {
+        let info = SmtpInfo {
+            target: ConnectionTarget::Unix(path.as_ref().to_path_buf()),
+            ..Default::default()
+        };
+        AsyncSmtpTransportBuilder {
+            info,
+            resolver: None,
+            #[cfg(feature = "pool")]
+            pool_config: PoolConfig::default(),
+        }
+    }
+
+    /// Health of the relay hosts, the main one first
+    ///
+    /// See the [`relay`](super::relay) module.
+    pub fn relay_health(&self) -> Vec<RelayHealth> {
+        self.relays.health()
+    }
+
+    /// Checks that emails can be sent to the server, without sending any
+    ///
+    /// Takes an idle connection from the pool, or opens a new one, authenticating
+    /// if credentials are set, and sends `NOOP`. Returns whether the server accepted it.
+    /// The connection is given back to the pool, or closed without the `pool` feature.
+    /// The errors opening the connection are returned, and tell with the methods of
+    /// [`Error`] whether the authentication failed or the server couldn't be reached.
+    pub async fn test_connection(&self) -> Result<bool, Error> {
+        let mut conn = self.inner.connection().await?;
+
+        let is_connected = conn.test_connected().await;
+
+        #[cfg(not(feature = "pool"))]
+        self.close(conn).await?;
+        Ok(is_connected)
+    }
+
+    /// Checks that the server accepts the sender and each recipient of `envelope`,
+    /// without sending any message
+    ///
+    /// Uses a connection like sending an email, but never sends the `DATA` command.
+    /// See [`AsyncSmtpConnection::verify_envelope`].
+    ///
+    /// The result is only a hint: many relays accept every recipient, and send a bounce
+    /// later for the ones which don't exist.
+    pub async fn verify_recipients(
+        &self,
+        envelope: &Envelope,
+    ) -> Result<EnvelopeVerification, Error> {
+        let mut conn = self.inner.connection().await?;
+
+        let result = conn.verify_envelope(envelope).await;
+        notify_phases(self.observer.as_ref(), conn.take_phase_timings());
+
+        #[cfg(not(feature = "pool"))]
+        let result = {
+            let closed = self.close(conn).await;
+            result.and_then(|verification| closed.map(|()| verification))
+        };
+        result
+    }
+
+    /// Sends an email in several transactions, and returns the result of each one
+    ///
+    /// The recipients are split following
+    /// [`AsyncSmtpTransportBuilder::max_recipients_per_transaction`], and the `452` replies
+    /// of the server. See [`AsyncSmtpConnection::send_split`].
+    pub async fn send_split(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+    ) -> Result<SplitDelivery, Error> {
+        check_message_size(self.max_message_size, email)?;
+        let mut conn = self.inner.connection().await?;
+
+        let max_recipients = self.max_recipients_per_transaction.unwrap_or(usize::MAX);
+        let result = conn.send_split(envelope, email, max_recipients).await;
+        notify_phases(self.observer.as_ref(), conn.take_phase_timings());
+
+        #[cfg(not(feature = "pool"))]
+        let result = {
+            let closed = self.close(conn).await;
+            result.and_then(|delivery| closed.map(|()| delivery))
+        };
+        result
+    }
+
+    /// Sends an email, streaming its content from `body`
+    ///
+    /// Uses a connection like [`AsyncTransport::send_raw`], but the content is read and
+    /// written in bounded chunks instead of being kept whole in memory, which suits large
+    /// emails. See [`AsyncSmtpConnection::send_stream`].
+    ///
+    /// The size of the content is only known once it's sent, so the maximum message size
+    /// of the transport isn't checked: the server rejects the emails it finds too large.
+    /// The email can't be sent in several transactions either, so it's rejected if it has
+    /// more recipients than
+    /// [`AsyncSmtpTransportBuilder::max_recipients_per_transaction`].
+    pub async fn send_stream<R>(&self, envelope: &Envelope, body: R) -> Result<Response, Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let start = Instant::now();
+        let mut len = 0;
+        let connection = match self.max_recipients_per_transaction {
+            Some(max_recipients) if envelope.to().len() > max_recipients => {
+                Err(error::client(format!(
+                    "streamed email with {} recipients can't be sent in transactions of {}",
+                    envelope.to().len(),
+                    max_recipients
+                )))
+            }
+            _ => self.inner.connection().await,
+        };
+        let (result, transcript, timings) = match connection {
+            Ok(mut conn) => {
+                let result = conn.send_stream_counted(envelope, body, &mut len).await;
+                let transcript = conn.take_transcript();
+                let timings = conn.take_phase_timings();
+
+                #[cfg(not(feature = "pool"))]
+                let result = {
+                    let closed = self.close(conn).await;
+                    result.and_then(|response| closed.map(|()| response))
+                };
+                (result, transcript, timings)
+            }
+            Err(err) => (Err(err), None, PhaseTimings::default()),
+        };
+
+        notify(self.observer.as_ref(), |observer| {
+            observer.on_send(result.as_ref(), start.elapsed(), len)
+        });
+        notify_phases(self.observer.as_ref(), timings);
+        finish_send(self.observer.as_ref(), result, transcript)
+    }
+
+    /// Sends an email, streaming its content from a tokio reader
+    ///
+    /// See [`AsyncSmtpTransport::send_stream`].
+    #[cfg(feature = "tokio1")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "tokio1")))]
+    pub async fn send_tokio1_stream<R>(
+        &self,
+        envelope: &Envelope,
+        body: R,
+    ) -> Result<Response, Error>
+    where
+        R: tokio1_crate::io::AsyncRead + Unpin,
+    {
+        self.send_stream(envelope, Tokio1Read(body)).await
+    }
+
+    /// Formats `message` to be sent, without its `Return-Path` headers unless disabled
+    #[cfg(feature = "builder")]
+    fn formatted(&self, message: &Message) -> Vec<u8> {
+        if self.strip_return_path {
+            message.formatted_for_submission()
+        } else {
+            message.formatted()
+        }
+    }
+
+    /// Sends an email over a connection from the pool, or a new one, rejecting it if
+    /// it's larger than `max_message_size`
+    async fn send_email(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+        max_message_size: Option<usize>,
+    ) -> Result<Response, Error> {
+        let start = Instant::now();
+        let connection = match check_message_size(max_message_size, email) {
+            Ok(()) => self.inner.connection().await,
+            Err(err) => Err(err),
+        };
+        let (result, transcript, timings) = match connection {
+            Ok(mut conn) => {
+                let result = match self.max_recipients_per_transaction {
+                    Some(max_recipients) => conn
+                        .send_split(envelope, email, max_recipients)
+                        .await
+                        .and_then(SplitDelivery::into_result),
+                    None if self.split_on_too_many_recipients => conn
+                        .send_split_capped(envelope, email, usize::MAX, MAX_SPLIT_TRANSACTIONS)
+                        .await
+                        .and_then(SplitDelivery::into_result),
+                    None => conn.send(envelope, email).await,
+                };
+                let transcript = conn.take_transcript();
+                let timings = conn.take_phase_timings();
+
+                #[cfg(not(feature = "pool"))]
+                let result = {
+                    let closed = self.close(conn).await;
+                    result.and_then(|response| closed.map(|()| response))
+                };
+                (result, transcript, timings)
+            }
+            Err(err) => (Err(err), None, PhaseTimings::default()),
+        };
+
+        notify(self.observer.as_ref(), |observer| {
+            observer.on_send(result.as_ref(), start.elapsed(), email.len())
+        });
+        notify_phases(self.observer.as_ref(), timings);
+        finish_send(self.observer.as_ref(), result, transcript)
+    }
+
+    /// Closes a connection which isn't needed anymore
+    #[cfg(not(feature = "pool"))]
+    async fn close(&self, mut conn: AsyncSmtpConnection) -> Result<(), Error> {
+        let result = if conn.has_broken() {
+            Ok(())
+        } else {
+            conn.quit().await.map(|_| ())
+        };
+
+        let reason = if conn.has_broken() {
+            CloseReason::Broken
+        } else {
+            CloseReason::Quit
+        };
+        notify(self.observer.as_ref(), |observer| {
+            observer.on_connection_closed(reason)
+        });
+        result
+    }
 }
 
 impl<E: Executor> Debug for AsyncSmtpTransport<E> {
@@ -184,16 +457,37 @@ where
     fn clone(&self) -> Self {
         Self {
             inner: self.inner.clone(),
+            observer: self.observer.clone(),
+            relays: Arc::clone(&self.relays),
+            max_message_size: self.max_message_size,
+            max_recipients_per_transaction: self.max_recipients_per_transaction,
+            split_on_too_many_recipients: self.split_on_too_many_recipients,
+            #[cfg(feature = "builder")]
+            strip_return_path: self.strip_return_path,
         }
     }
 }
 
+/// Resolves the server hostname into the addresses to connect to
+///
+/// By default, the async transports use the non-blocking resolution provided
+/// by their executor. A custom resolver can be set using
+/// [`AsyncSmtpTransportBuilder::resolver`], for example to use a different
+/// DNS client.
+#[cfg_attr(docsrs, doc(cfg(any(feature = "tokio1", feature = "async-std1"))))]
+#[async_trait]
+pub trait AsyncResolver: Debug + Send + Sync + 'static {
+    /// Returns the addresses to try, in order, to connect to `hostname` on `port`
+    async fn resolve(&self, hostname: &str, port: u16) -> io::Result<Vec<SocketAddr>>;
+}
+
 /// Contains client configuration.
 /// Instances of this struct can be created using functions of [`AsyncSmtpTransport`].
 #[derive(Debug, Clone)]
 #[cfg_attr(docsrs, doc(cfg(any(feature = "tokio1", feature = "async-std1"))))]
 pub struct AsyncSmtpTransportBuilder {
     info: SmtpInfo,
+    resolver: Option<Arc<dyn AsyncResolver>>,
     #[cfg(feature = "pool")]
     pool_config: PoolConfig,
 }
@@ -201,7 +495,22 @@ pub struct AsyncSmtpTransportBuilder {
 /// Builder for the SMTP `AsyncSmtpTransport`
 impl AsyncSmtpTransportBuilder {
     /// Set the name used during EHLO
+    ///
+    /// Defaults to the hostname of the machine if it's a fully-qualified domain name,
+    /// and to an address literal of the local address of the connection otherwise.
+    /// See [`ClientId::auto`] and [`ClientId::from_local_addr`].
     pub fn hello_name(mut self, name: ClientId) -> Self {
+        self.info.hello_name = Some(name);
+        self
+    }
+
+    /// Set the name used during EHLO if `name` is set, and the automatic one otherwise
+    ///
+    /// Tries in order `name`, the hostname of the machine if it's a fully-qualified domain
+    /// name, and an address literal of the local address of the connection, which is only
+    /// known once connected. See [`ClientId::auto`]. Convenient when the name comes from
+    /// an optional setting.
+    pub fn hello_name_or_auto(mut self, name: Option<ClientId>) -> Self {
         self.info.hello_name = name;
         self
     }
@@ -219,8 +528,10 @@ impl AsyncSmtpTransportBuilder {
     }
 
     /// Set the port to use
+    ///
+    /// Ignored when connecting to a Unix domain socket.
     pub fn port(mut self, port: u16) -> Self {
-        self.info.port = port;
+        self.info.target.set_port(port);
         self
     }
 
@@ -230,6 +541,175 @@ impl AsyncSmtpTransportBuilder {
         self
     }
 
+    /// Set the number of recipients sent in a single transaction
+    ///
+    /// Emails with more recipients are sent in several transactions over the same
+    /// connection, each one to a part of the recipients, like required by servers which
+    /// only take a limited number of `RCPT` commands. The transactions are also split when
+    /// the server replies `452` (too many recipients) to a `RCPT` command. The send
+    /// succeeds if every transaction succeeds, otherwise the error of the first failed
+    /// transaction is returned, even if some recipients received the email: use
+    /// [`AsyncSmtpTransport::send_split`] to get the result of each transaction.
+    /// Not set by default, all the recipients are sent in one transaction.
+    pub fn max_recipients_per_transaction(mut self, max_recipients: Option<usize>) -> Self {
+        self.info.max_recipients_per_transaction = max_recipients;
+        self
+    }
+
+    /// Set whether emails are sent in several transactions when the server replies `452`
+    /// (too many recipients)
+    ///
+    /// Some servers take a limited number of `RCPT` commands without advertising it. The
+    /// message is then sent to the recipients the server already accepted, and the
+    /// remaining ones go to a new transaction over the same connection, in at most 20
+    /// transactions. The result is the same as with
+    /// [`AsyncSmtpTransportBuilder::max_recipients_per_transaction`], which always splits the
+    /// transactions like this. Disabled by default, the send fails when the server
+    /// replies `452`.
+    /// Doesn't apply to streamed emails.
+    pub fn split_on_too_many_recipients(mut self, split: bool) -> Self {
+        self.info.split_on_too_many_recipients = split;
+        self
+    }
+
+    /// Set whether the `Return-Path` headers of the messages are left out when sending them
+    ///
+    /// `Return-Path` is added by the server delivering the message, and must not be sent
+    /// by a client submitting it, like when re-sending an archived message. Enabled by
+    /// default, only applies to [`Message`](crate::Message)s, not to raw emails.
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    pub fn strip_return_path(mut self, strip: bool) -> Self {
+        self.info.strip_return_path = strip;
+        self
+    }
+
+    /// Set the size in bytes of the largest message to send
+    ///
+    /// Larger messages are rejected with a client error before connecting to the server,
+    /// whatever the `SIZE` advertised by the server. Protects from sending huge messages by
+    /// mistake, like ones with runaway attachments. No limit by default.
+    ///
+    /// Messages built with a limit of their own, set with
+    /// [`MessageBuilder::max_size`](crate::message::MessageBuilder::max_size), are
+    /// checked against it instead.
+    pub fn max_message_size(mut self, max_message_size: Option<usize>) -> Self {
+        self.info.max_message_size = max_message_size;
+        self
+    }
+
+    /// Set the timeout for receiving the greeting of the server, defaults to the timeout
+    ///
+    /// It bounds the whole greeting, to detect servers accepting connections but never
+    /// sending their greeting, or trickling it. The error is a connection error, which
+    /// can be retried with another server.
+    pub fn greeting_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.info.greeting_timeout = timeout;
+        self
+    }
+
+    /// Set the number of times to retry connecting to the server
+    ///
+    /// Only connection errors, like a refused connection or a failed name
+    /// resolution, are retried, after a short delay. Defaults to 0.
+    pub fn connect_retries(mut self, retries: u32) -> Self {
+        self.info.connect_retries = retries;
+        self
+    }
+
+    /// Set the time during which the addresses the servers are resolved to are reused
+    ///
+    /// Without it, the hostname of the server is resolved again for each connection,
+    /// which is wasteful when sending many emails to the same server. The addresses are
+    /// cached per host and port, in the order given by the resolver, and forgotten when a
+    /// connection to them fails. Disabled by default.
+    pub fn dns_cache_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.info.dns_cache_ttl = ttl;
+        self
+    }
+
+    /// Set whether closing a connection waits for the reply to `QUIT`
+    ///
+    /// When `true`, the default, the `221` reply is read and checked, for at most a
+    /// few seconds. When `false`, `QUIT` is sent and the connection shut down
+    /// immediately, saving a round-trip when closing connections.
+    pub fn quit_wait(mut self, quit_wait: bool) -> Self {
+        self.info.quit_wait = quit_wait;
+        self
+    }
+
+    /// Set the servers to connect to when a connection can't be opened to the main one,
+    /// in order
+    ///
+    /// The retries set with [`connect_retries`](Self::connect_retries) apply to each
+    /// server, and the TLS certificate of each one is validated for its own name.
+    /// See the [`relay`](super::relay) module.
+    pub fn fallback_relays(mut self, targets: Vec<ConnectionTarget>) -> Self {
+        self.info.fallback_targets = targets;
+        self
+    }
+
+    /// Set the order in which the main and the fallback servers are tried,
+    /// defaults to [`RelaySelection::Ordered`]
+    pub fn relay_selection(mut self, selection: RelaySelection) -> Self {
+        self.info.relay_selection = selection;
+        self
+    }
+
+    /// Connect from a local port of `ports`, like the range a NAT gateway or a firewall
+    /// lets through
+    ///
+    /// The ports are tried in order until one is available, for each address of the
+    /// server. Not used when connecting to a Unix domain socket.
+    #[cfg(feature = "socket2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "socket2")))]
+    pub fn source_port_range(mut self, ports: RangeInclusive<u16>) -> Self {
+        self.info.source_port_range = Some(ports);
+        self
+    }
+
+    /// Send a PROXY protocol header declaring `source` as the address of the client,
+    /// for servers behind a proxy like HAProxy
+    ///
+    /// The header is sent right after connecting, before the server greeting and the TLS
+    /// handshake. `source` defaults to the local address of the connection.
+    /// Not used when connecting to a Unix domain socket.
+    pub fn proxy_protocol(
+        mut self,
+        version: ProxyProtocolVersion,
+        source: Option<SocketAddr>,
+    ) -> Self {
+        self.info.proxy_protocol = Some(ProxyProtocol::new(version, source));
+        self
+    }
+
+    /// Forward the attributes of the original client with `XCLIENT`
+    ///
+    /// Sent on every new connection, after the TLS negotiation and before authentication,
+    /// when the server advertises the `XCLIENT` extension. The attributes the server
+    /// doesn't support are omitted.
+    pub fn xclient(mut self, xclient: XClient) -> Self {
+        self.info.xclient = Some(xclient);
+        self
+    }
+
+    /// Record the dialogue with the server, to debug failed sends
+    ///
+    /// The transcript of each send is attached to its error, and given to the
+    /// [`observer`](Self::observer). See the [`transcript`](super::transcript) module.
+    pub fn capture_transcript(mut self, config: TranscriptConfig) -> Self {
+        self.info.transcript = Some(config);
+        self
+    }
+
+    /// Notify `observer` of the connections and emails sent, for example to collect metrics
+    ///
+    /// See [`TransportObserver`].
+    pub fn observer<O: TransportObserver>(mut self, observer: O) -> Self {
+        self.info.observer = Some(Arc::new(observer));
+        self
+    }
+
     /// Set the TLS settings to use
     #[cfg(any(
         feature = "tokio1-native-tls",
@@ -250,6 +730,12 @@ impl AsyncSmtpTransportBuilder {
         self
     }
 
+    /// Use a custom resolver for the server hostname
+    pub fn resolver<R: AsyncResolver>(mut self, resolver: R) -> Self {
+        self.resolver = Some(Arc::new(resolver));
+        self
+    }
+
     /// Use a custom configuration for the connection pool
     ///
     /// Defaults can be found at [`PoolConfig`]
@@ -265,21 +751,43 @@ impl AsyncSmtpTransportBuilder {
     where
         E: Executor,
     {
+        let observer = self.info.observer.clone();
+        let max_message_size = self.info.max_message_size;
+        let max_recipients_per_transaction = self.info.max_recipients_per_transaction;
+        let split_on_too_many_recipients = self.info.split_on_too_many_recipients;
+        #[cfg(feature = "builder")]
+        let strip_return_path = self.info.strip_return_path;
+        let relays = self.info.relays();
         let client = AsyncSmtpClient {
+            dns_cache: self.info.dns_cache(),
             info: self.info,
+            relays: Arc::clone(&relays),
+            resolver: self.resolver,
             marker_: PhantomData,
         };
 
         #[cfg(feature = "pool")]
         let client = Pool::new(self.pool_config, client);
 
-        AsyncSmtpTransport { inner: client }
+        AsyncSmtpTransport {
+            inner: client,
+            observer,
+            relays,
+            max_message_size,
+            max_recipients_per_transaction,
+            split_on_too_many_recipients,
+            #[cfg(feature = "builder")]
+            strip_return_path,
+        }
     }
 }
 
 /// Build client
 pub struct AsyncSmtpClient<E> {
     info: SmtpInfo,
+    relays: Arc<Relays>,
+    dns_cache: Option<Arc<DnsCache>>,
+    resolver: Option<Arc<dyn AsyncResolver>>,
     marker_: PhantomData<E>,
 }
 
@@ -291,26 +799,172 @@ where
     ///
     /// Handles encryption and authentication
     pub async fn connection(&self) -> Result<AsyncSmtpConnection, Error> {
-        let mut conn = E::connect(
-            &self.info.server,
-            self.info.port,
-            self.info.timeout,
-            &self.info.hello_name,
-            &self.info.tls,
-        )
-        .await?;
+        let start = Instant::now();
+        let mut result = self.open_connection().await;
+
+        let observer = self.info.observer.as_ref();
+        match &mut result {
+            Ok(conn) => {
+                let tls = self.relays.tls(conn.relay(), &self.info.tls);
+                if let Some(tls_info) = tls_info(&tls, conn.is_encrypted()) {
+                    notify(observer, |observer| observer.on_tls_established(&tls_info));
+                }
+                notify(observer, |observer| {
+                    observer.on_connect(start.elapsed(), conn.is_encrypted());
+                    observer.on_connected(&conn.connection_info());
+                });
+                notify_phases(observer, conn.take_phase_timings());
+            }
+            Err(err) => notify(observer, |observer| observer.on_connection_error(err)),
+        }
+        result
+    }
+
+    /// Connects to the server, then handles authentication
+    async fn open_connection(&self) -> Result<AsyncSmtpConnection, Error> {
+        let mut relays = self.relays.order().into_iter().peekable();
+        let mut conn = loop {
+            let relay = relays.next().expect("there's at least one relay host");
+            let tls = self.relays.tls(relay, &self.info.tls);
+            match self.connect(self.relays.target(relay), &tls).await {
+                Ok(mut conn) => {
+                    self.relays.succeeded(relay);
+                    conn.set_relay(relay);
+                    conn.set_quit_wait(self.info.quit_wait);
+                    break conn;
+                }
+                Err(err) if err.is_connection() && relays.peek().is_some() => {
+                    self.relays.failed(relay);
+                }
+                Err(err) => {
+                    if err.is_connection() {
+                        self.relays.failed(relay);
+                    }
+                    return Err(err);
+                }
+            }
+        };
+
+        if let Some(xclient) = &self.info.xclient {
+            conn.xclient(xclient)
+                .await
+                .map_err(|err| conn.attach_transcript(err))?;
+        }
 
         if let Some(credentials) = &self.info.credentials {
-            conn.auth(&self.info.authentication, credentials).await?;
+            let start = Instant::now();
+            let result = conn.auth(&self.info.authentication, credentials).await;
+            notify(self.info.observer.as_ref(), |observer| {
+                observer.on_auth(result.as_ref().map(|_| ()), start.elapsed())
+            });
+            result.map_err(|err| conn.attach_transcript(err))?;
         }
+
+        #[cfg(feature = "tracing")]
+        conn.record_handshake_time();
         Ok(conn)
     }
+
+    /// Notified of the connections and emails sent
+    #[cfg(feature = "pool")]
+    pub(crate) fn observer(&self) -> Option<&Arc<dyn TransportObserver>> {
+        self.info.observer.as_ref()
+    }
+
+    /// Relay hosts to connect to, with their health
+    #[cfg(feature = "pool")]
+    pub(crate) fn relays(&self) -> &Arc<Relays> {
+        &self.relays
+    }
+
+    /// Connects to `target`, retrying after connection errors
+    async fn connect(
+        &self,
+        target: &ConnectionTarget,
+        tls: &Tls,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        let mut retries = self.info.connect_retries;
+        loop {
+            match self.connect_once(target, tls).await {
+                Ok(conn) => return Ok(conn),
+                Err(err) if retries > 0 && err.is_connection() => {
+                    retries -= 1;
+                    E::sleep(CONNECT_RETRY_DELAY).await;
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Resolves the address of `target` and connects to it
+    async fn connect_once(
+        &self,
+        target: &ConnectionTarget,
+        tls: &Tls,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        let target = match target {
+            ConnectionTarget::Tcp(server, port) => TcpTarget::new(server, *port),
+            #[cfg(unix)]
+            ConnectionTarget::Unix(path) => {
+                #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+                if let Tls::Required(_) | Tls::Wrapper(_) = tls {
+                    return Err(error::client("TLS isn't supported on Unix sockets"));
+                }
+                return E::connect_unix(
+                    path,
+                    self.info.timeout,
+                    self.info.greeting_timeout,
+                    self.info.hello_name.as_ref(),
+                    self.info.transcript.as_ref(),
+                )
+                .await;
+            }
+        };
+        let cached = self
+            .dns_cache
+            .as_ref()
+            .and_then(|cache| cache.get(target.host(), target.port));
+        let addrs = match (target.scoped_addr(), cached) {
+            (Some(addr), _) => vec![addr],
+            (None, Some(addrs)) => addrs,
+            (None, None) => {
+                let addrs = match &self.resolver {
+                    Some(resolver) => resolver.resolve(target.host(), target.port).await,
+                    None => E::resolve(target.host(), target.port).await,
+                }
+                .map_err(error::connection)?;
+                if let Some(cache) = &self.dns_cache {
+                    cache.insert(target.host(), target.port, &addrs);
+                }
+                addrs
+            }
+        };
+
+        let result = E::connect(
+            &addrs,
+            self.info.timeout,
+            self.info.greeting_timeout,
+            self.info.hello_name.as_ref(),
+            self.info.source_port_range.as_ref(),
+            self.info.proxy_protocol.as_ref(),
+            tls,
+            self.info.transcript.as_ref(),
+        )
+        .await;
+        if let (Err(err), Some(cache)) = (&result, &self.dns_cache) {
+            if err.is_connection() {
+                cache.invalidate(target.host(), target.port);
+            }
+        }
+        result
+    }
 }
 
 impl<E> Debug for AsyncSmtpClient<E> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         let mut builder = f.debug_struct("AsyncSmtpClient");
         builder.field("info", &self.info);
+        builder.field("resolver", &self.resolver);
         builder.finish()
     }
 }
@@ -324,6 +978,9 @@ where
     fn clone(&self) -> Self {
         Self {
             info: self.info.clone(),
+            relays: Arc::clone(&self.relays),
+            dns_cache: self.dns_cache.clone(),
+            resolver: self.resolver.clone(),
             marker_: PhantomData,
         }
     }
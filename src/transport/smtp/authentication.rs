@@ -43,7 +43,7 @@ impl Debug for Credentials {
 }
 
 /// Represents authentication mechanisms
-#[derive(PartialEq, Eq, Copy, Clone, Hash, Debug)]
+#[derive(PartialEq, Eq, Clone, Hash, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Mechanism {
     /// PLAIN authentication mechanism, defined in
@@ -57,31 +57,36 @@ pub enum Mechanism {
     /// Non-standard XOAUTH2 mechanism, defined in
     /// [xoauth2-protocol](https://developers.google.com/gmail/imap/xoauth2-protocol)
     Xoauth2,
+    /// Mechanism advertised by the server but not implemented by lettre, with its name
+    ///
+    /// It can't be used to authenticate.
+    Other(String),
 }
 
 impl Display for Mechanism {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        f.write_str(match *self {
+        f.write_str(match self {
             Mechanism::Plain => "PLAIN",
             Mechanism::Login => "LOGIN",
             Mechanism::Xoauth2 => "XOAUTH2",
+            Mechanism::Other(name) => name,
         })
     }
 }
 
 impl Mechanism {
     /// Does the mechanism supports initial response
-    pub fn supports_initial_response(self) -> bool {
+    pub fn supports_initial_response(&self) -> bool {
         match self {
             Mechanism::Plain | Mechanism::Xoauth2 => true,
-            Mechanism::Login => false,
+            Mechanism::Login | Mechanism::Other(_) => false,
         }
     }
 
     /// Returns the string to send to the server, using the provided username, password and
     /// challenge in some cases
     pub fn response(
-        self,
+        &self,
         credentials: &Credentials,
         challenge: Option<&str>,
     ) -> Result<String, Error> {
@@ -97,15 +102,11 @@ impl Mechanism {
                 let decoded_challenge = challenge
                     .ok_or_else(|| error::client("This mechanism does expect a challenge"))?;
 
-                if vec!["User Name", "Username:", "Username"].contains(&decoded_challenge) {
-                    return Ok(credentials.authentication_identity.to_string());
-                }
-
-                if vec!["Password", "Password:"].contains(&decoded_challenge) {
-                    return Ok(credentials.secret.to_string());
+                match LoginPrompt::recognize(decoded_challenge) {
+                    Some(LoginPrompt::Username) => Ok(credentials.authentication_identity.clone()),
+                    Some(LoginPrompt::Password) => Ok(credentials.secret.clone()),
+                    None => Err(error::client("Unrecognized challenge")),
                 }
-
-                Err(error::client("Unrecognized challenge"))
             }
             Mechanism::Xoauth2 => match challenge {
                 Some(_) => Err(error::client("This mechanism does not expect a challenge")),
@@ -114,13 +115,79 @@ impl Mechanism {
                     credentials.authentication_identity, credentials.secret
                 )),
             },
+            Mechanism::Other(_) => Err(error::client("Unsupported authentication mechanism")),
+        }
+    }
+}
+
+/// Credential asked for by a prompt of the `LOGIN` mechanism
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum LoginPrompt {
+    Username,
+    Password,
+}
+
+impl LoginPrompt {
+    /// Recognizes the usual prompts, like `Username:`, `User Name` or `password`
+    fn recognize(prompt: &str) -> Option<LoginPrompt> {
+        let prompt = prompt.trim().to_ascii_lowercase();
+        if prompt.starts_with("user") || prompt.starts_with("login") {
+            Some(LoginPrompt::Username)
+        } else if prompt.starts_with("pass") {
+            Some(LoginPrompt::Password)
+        } else {
+            None
+        }
+    }
+}
+
+/// Progress of a `LOGIN` exchange
+///
+/// Servers don't agree on the prompts of the `LOGIN` mechanism, nor always on their
+/// order. Each challenge is answered with the credential recognized in its prompt, or
+/// otherwise with the next credential not sent yet, starting with the username.
+#[derive(Debug, Default)]
+pub(crate) struct LoginExchange {
+    username_sent: bool,
+    password_sent: bool,
+}
+
+impl LoginExchange {
+    /// Returns the credential answering a challenge, given its decoded prompt if readable
+    pub(crate) fn respond(
+        &mut self,
+        credentials: &Credentials,
+        prompt: Option<&str>,
+    ) -> Result<String, Error> {
+        if self.username_sent && self.password_sent {
+            return Err(error::response(
+                "Unexpected challenge after sending the LOGIN username and password",
+            ));
+        }
+
+        let prompt = prompt
+            .and_then(LoginPrompt::recognize)
+            .unwrap_or(if self.username_sent {
+                LoginPrompt::Password
+            } else {
+                LoginPrompt::Username
+            });
+        match prompt {
+            LoginPrompt::Username => {
+                self.username_sent = true;
+                Ok(credentials.authentication_identity.clone())
+            }
+            LoginPrompt::Password => {
+                self.password_sent = true;
+                Ok(credentials.secret.clone())
+            }
         }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{Credentials, Mechanism};
+    use super::{Credentials, LoginExchange, Mechanism};
 
     #[test]
     fn test_plain() {
@@ -149,7 +216,43 @@ mod test {
             mechanism.response(&credentials, Some("Password")).unwrap(),
             "wonderland"
         );
+        assert_eq!(
+            mechanism.response(&credentials, Some("User Name")).unwrap(),
+            "alice"
+        );
+        assert_eq!(
+            mechanism.response(&credentials, Some("password:")).unwrap(),
+            "wonderland"
+        );
         assert!(mechanism.response(&credentials, None).is_err());
+        assert!(mechanism
+            .response(&credentials, Some("Who are you?"))
+            .is_err());
+    }
+
+    #[test]
+    fn test_login_exchange() {
+        let credentials = Credentials::new("alice".to_string(), "wonderland".to_string());
+
+        // Unknown prompts are answered in order
+        let mut exchange = LoginExchange::default();
+        assert_eq!(
+            exchange
+                .respond(&credentials, Some("Who are you?"))
+                .unwrap(),
+            "alice"
+        );
+        assert_eq!(exchange.respond(&credentials, None).unwrap(), "wonderland");
+        assert!(exchange.respond(&credentials, Some("Username:")).is_err());
+
+        // Known prompts are answered in any order
+        let mut exchange = LoginExchange::default();
+        assert_eq!(
+            exchange.respond(&credentials, Some("Password:")).unwrap(),
+            "wonderland"
+        );
+        assert_eq!(exchange.respond(&credentials, None).unwrap(), "alice");
+        assert!(exchange.respond(&credentials, None).is_err());
     }
 
     #[test]
@@ -168,6 +271,16 @@ mod test {
         assert!(mechanism.response(&credentials, Some("test")).is_err());
     }
 
+    #[test]
+    fn test_other() {
+        let mechanism = Mechanism::Other("CRAM-MD5".to_string());
+        let credentials = Credentials::new("alice".to_string(), "wonderland".to_string());
+
+        assert_eq!(mechanism.to_string(), "CRAM-MD5");
+        assert!(!mechanism.supports_initial_response());
+        assert!(mechanism.response(&credentials, None).is_err());
+    }
+
     #[test]
     fn test_from_user_pass_for_credentials() {
         assert_eq!(
@@ -1,20 +1,37 @@
-use super::{AsyncNetworkStream, ClientCodec, TlsParameters};
+use super::{
+    body_mail_options, check_argument, check_greeting, closed_after_quit, default_hello_name,
+    eight_bit_unsupported, greeting_timed_out, mail_options, placeholder_greeting,
+    redacted_command, too_many_recipients, AsyncNetworkStream, ClientCodec, ProxyProtocol,
+    TlsParameters, QUIT_TIMEOUT,
+};
+#[cfg(feature = "tokio1")]
+use crate::executor::Tokio1Read;
 use crate::{
     transport::smtp::{
-        authentication::{Credentials, Mechanism},
+        authentication::{Credentials, LoginExchange, Mechanism},
         commands::*,
         error,
-        error::Error,
-        extension::{ClientId, Extension, MailBodyParameter, MailParameter, ServerInfo},
-        response::{parse_response, Response},
+        error::{Error, Phase},
+        extension::{ClientId, Extension, ServerInfo},
+        observer::{ConnectionInfo, PhaseTimings},
+        response::{parse_response, EnvelopeVerification, Response, SplitDelivery, Verification},
+        transcript::{Direction, Transcript, TranscriptConfig},
     },
-    Envelope,
+    Address, Envelope,
+};
+use futures_io::AsyncRead;
+use futures_util::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+#[cfg(unix)]
+use std::path::Path;
+use std::{
+    fmt::Display,
+    io, mem,
+    ops::RangeInclusive,
+    time::{Duration, Instant},
 };
-use futures_util::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use std::{fmt::Display, time::Duration};
 
 #[cfg(feature = "tracing")]
-use super::escape_crlf;
+use super::{connection_span, escape_crlf, message_span};
 
 macro_rules! try_smtp (
     ($err: expr, $client: ident) => ({
@@ -28,6 +45,9 @@ macro_rules! try_smtp (
     })
 );
 
+/// Size of the chunks of content read and written by `send_stream`
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
 /// Structure that implements the SMTP client
 pub struct AsyncSmtpConnection {
     /// TCP stream between client and server
@@ -37,6 +57,24 @@ pub struct AsyncSmtpConnection {
     panic: bool,
     /// Information about the server
     server_info: ServerInfo,
+    /// Name sent in EHLO
+    hello_name: ClientId,
+    /// Greeting sent by the server
+    greeting: Response,
+    /// Lines sent and received, when capturing transcripts
+    transcript: Option<Transcript>,
+    /// Index of the relay host of the transport the connection was opened to
+    relay: usize,
+    /// Whether `QUIT` waits for the reply of the server before closing the connection
+    quit_wait: bool,
+    /// Time spent in each phase of the dialogue, until taken by the transport
+    timings: PhaseTimings,
+    /// When the connection was established, before the greeting
+    #[cfg(feature = "tracing")]
+    connected_at: Instant,
+    /// Span of the connection, or of the message being sent
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
 }
 
 impl AsyncSmtpConnection {
@@ -44,6 +82,43 @@ impl AsyncSmtpConnection {
         &self.server_info
     }
 
+    /// Name the client sent when greeting the server
+    pub fn hello_name(&self) -> &ClientId {
+        &self.hello_name
+    }
+
+    /// Greeting sent by the server when the client connected
+    pub fn greeting(&self) -> &Response {
+        &self.greeting
+    }
+
+    /// Index of the relay host of the transport the connection was opened to
+    pub(crate) fn relay(&self) -> usize {
+        self.relay
+    }
+
+    /// Sets whether `QUIT` waits for the reply of the server, `true` by default
+    ///
+    /// When `false`, [`quit`](#method.quit) and `abort` send `QUIT` and shut the connection
+    /// down immediately, saving a round-trip.
+    pub fn set_quit_wait(&mut self, quit_wait: bool) {
+        self.quit_wait = quit_wait;
+    }
+
+    pub(crate) fn set_relay(&mut self, relay: usize) {
+        self.relay = relay;
+    }
+
+    /// Describes the connection for the observer of the transport
+    pub(crate) fn connection_info(&self) -> ConnectionInfo {
+        ConnectionInfo::new(
+            self.stream.get_ref().peer_addr(),
+            &self.greeting,
+            &self.server_info,
+            self.is_encrypted(),
+        )
+    }
+
     /// Connects to the configured server
     ///
     /// Sends EHLO and parses server information
@@ -54,8 +129,68 @@ impl AsyncSmtpConnection {
         hello_name: &ClientId,
         tls_parameters: Option<TlsParameters>,
     ) -> Result<AsyncSmtpConnection, Error> {
-        let stream = AsyncNetworkStream::connect_tokio1(server, timeout, tls_parameters).await?;
-        Self::connect_impl(stream, hello_name).await
+        Self::connect_tokio1_with(
+            server,
+            timeout,
+            None,
+            Some(hello_name),
+            None,
+            None,
+            tls_parameters,
+            None,
+        )
+        .await
+    }
+
+    /// Connects to the configured server
+    ///
+    /// Connects from a local port of `source_ports` if set. Sends the PROXY protocol
+    /// header if `proxy_protocol` is set, then EHLO with `hello_name`, or with a name
+    /// derived from the local address of the connection if it's `None`, and parses
+    /// server information. The greeting must be received within `greeting_timeout`,
+    /// defaulting to `timeout`. Records a transcript from the greeting if `transcript`
+    /// is set.
+    #[allow(clippy::too_many_arguments)]
+    #[cfg(feature = "tokio1")]
+    pub(crate) async fn connect_tokio1_with<T: tokio1_crate::net::ToSocketAddrs>(
+        server: T,
+        timeout: Option<Duration>,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        source_ports: Option<&RangeInclusive<u16>>,
+        proxy_protocol: Option<&ProxyProtocol>,
+        tls_parameters: Option<TlsParameters>,
+        transcript: Option<&TranscriptConfig>,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        let start = Instant::now();
+        let stream = AsyncNetworkStream::connect_tokio1_with(
+            server,
+            timeout,
+            source_ports,
+            proxy_protocol,
+            tls_parameters,
+        )
+        .await
+        .map_err(|err| err.with_phase(Phase::Connect))?;
+        let connect_time = start.elapsed();
+
+        let mut conn = Self::new(stream, hello_name, transcript);
+        conn.timings.record(Phase::Connect, connect_time);
+        let start = Instant::now();
+        let greeting = match greeting_timeout.or(timeout) {
+            Some(greeting_timeout) => {
+                tokio1_crate::time::timeout(greeting_timeout, conn.read_response())
+                    .await
+                    .unwrap_or_else(|_| Err(greeting_timed_out()))
+            }
+            None => conn.read_response().await,
+        };
+        conn.timings.record(Phase::Greeting, start.elapsed());
+        let conn = conn.greet(greeting).await?;
+        #[cfg(feature = "tracing")]
+        conn.span
+            .record("connect_time", tracing::field::debug(connect_time));
+        Ok(conn)
     }
 
     /// Connects to the configured server
@@ -68,61 +203,273 @@ impl AsyncSmtpConnection {
         hello_name: &ClientId,
         tls_parameters: Option<TlsParameters>,
     ) -> Result<AsyncSmtpConnection, Error> {
-        let stream = AsyncNetworkStream::connect_asyncstd1(server, timeout, tls_parameters).await?;
-        Self::connect_impl(stream, hello_name).await
+        Self::connect_asyncstd1_with(
+            server,
+            timeout,
+            None,
+            Some(hello_name),
+            None,
+            None,
+            tls_parameters,
+            None,
+        )
+        .await
     }
 
-    async fn connect_impl(
+    /// Connects to the configured server
+    ///
+    /// Connects from a local port of `source_ports` if set. Sends the PROXY protocol
+    /// header if `proxy_protocol` is set, then EHLO with `hello_name`, or with a name
+    /// derived from the local address of the connection if it's `None`, and parses
+    /// server information. The greeting must be received within `greeting_timeout`,
+    /// defaulting to `timeout`. Records a transcript from the greeting if `transcript`
+    /// is set.
+    #[allow(clippy::too_many_arguments)]
+    #[cfg(feature = "async-std1")]
+    pub(crate) async fn connect_asyncstd1_with<T: async_std::net::ToSocketAddrs>(
+        server: T,
+        timeout: Option<Duration>,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        source_ports: Option<&RangeInclusive<u16>>,
+        proxy_protocol: Option<&ProxyProtocol>,
+        tls_parameters: Option<TlsParameters>,
+        transcript: Option<&TranscriptConfig>,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        let start = Instant::now();
+        let stream = AsyncNetworkStream::connect_asyncstd1_with(
+            server,
+            timeout,
+            source_ports,
+            proxy_protocol,
+            tls_parameters,
+        )
+        .await
+        .map_err(|err| err.with_phase(Phase::Connect))?;
+        let connect_time = start.elapsed();
+
+        let mut conn = Self::new(stream, hello_name, transcript);
+        conn.timings.record(Phase::Connect, connect_time);
+        let start = Instant::now();
+        let greeting = match greeting_timeout.or(timeout) {
+            Some(greeting_timeout) => {
+                async_std::future::timeout(greeting_timeout, conn.read_response())
+                    .await
+                    .unwrap_or_else(|_| Err(greeting_timed_out()))
+            }
+            None => conn.read_response().await,
+        };
+        conn.timings.record(Phase::Greeting, start.elapsed());
+        let conn = conn.greet(greeting).await?;
+        #[cfg(feature = "tracing")]
+        conn.span
+            .record("connect_time", tracing::field::debug(connect_time));
+        Ok(conn)
+    }
+
+    /// Connects to a SMTP server listening on a Unix domain socket with tokio
+    ///
+    /// Sends EHLO and parses server information.
+    #[cfg(all(feature = "tokio1", unix))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "tokio1", unix))))]
+    pub async fn connect_tokio1_unix<P: AsRef<Path>>(
+        path: P,
+        timeout: Option<Duration>,
+        hello_name: &ClientId,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        Self::connect_tokio1_unix_with(path, timeout, None, Some(hello_name), None).await
+    }
+
+    /// Connects to a SMTP server listening on a Unix domain socket with tokio
+    ///
+    /// Sends EHLO with `hello_name`, or with the default [`ClientId`] if it's `None`.
+    /// The greeting must be received within `greeting_timeout`, defaulting to `timeout`.
+    /// Records a transcript from the greeting if `transcript` is set.
+    #[cfg(all(feature = "tokio1", unix))]
+    pub(crate) async fn connect_tokio1_unix_with<P: AsRef<Path>>(
+        path: P,
+        timeout: Option<Duration>,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        transcript: Option<&TranscriptConfig>,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        let start = Instant::now();
+        let stream = AsyncNetworkStream::connect_tokio1_unix(path)
+            .await
+            .map_err(|err| err.with_phase(Phase::Connect))?;
+        Self::connect_unix_impl(
+            stream,
+            start.elapsed(),
+            greeting_timeout.or(timeout),
+            hello_name,
+            transcript,
+        )
+        .await
+    }
+
+    /// Connects to a SMTP server listening on a Unix domain socket with async-std
+    ///
+    /// Sends EHLO and parses server information.
+    #[cfg(all(feature = "async-std1", unix))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "async-std1", unix))))]
+    pub async fn connect_asyncstd1_unix<P: AsRef<Path>>(
+        path: P,
+        timeout: Option<Duration>,
+        hello_name: &ClientId,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        Self::connect_asyncstd1_unix_with(path, timeout, None, Some(hello_name), None).await
+    }
+
+    /// Connects to a SMTP server listening on a Unix domain socket with async-std
+    ///
+    /// Sends EHLO with `hello_name`, or with the default [`ClientId`] if it's `None`.
+    /// The greeting must be received within `greeting_timeout`, defaulting to `timeout`.
+    /// Records a transcript from the greeting if `transcript` is set.
+    #[cfg(all(feature = "async-std1", unix))]
+    pub(crate) async fn connect_asyncstd1_unix_with<P: AsRef<Path>>(
+        path: P,
+        timeout: Option<Duration>,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        transcript: Option<&TranscriptConfig>,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        let start = Instant::now();
+        let stream = AsyncNetworkStream::connect_asyncstd1_unix(path)
+            .await
+            .map_err(|err| err.with_phase(Phase::Connect))?;
+        Self::connect_unix_impl(
+            stream,
+            start.elapsed(),
+            greeting_timeout.or(timeout),
+            hello_name,
+            transcript,
+        )
+        .await
+    }
+
+    /// Reads the greeting from a connected Unix domain socket, within `greeting_timeout`,
+    /// then sends EHLO
+    #[cfg(unix)]
+    async fn connect_unix_impl(
         stream: AsyncNetworkStream,
+        connect_time: Duration,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        transcript: Option<&TranscriptConfig>,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        let runtime = stream.runtime();
+        let mut conn = Self::new(stream, hello_name, transcript);
+        conn.timings.record(Phase::Connect, connect_time);
+        let start = Instant::now();
+        let greeting = match greeting_timeout {
+            Some(greeting_timeout) => runtime
+                .timeout(greeting_timeout, conn.read_response())
+                .await
+                .unwrap_or_else(|| Err(greeting_timed_out())),
+            None => conn.read_response().await,
+        };
+        conn.timings.record(Phase::Greeting, start.elapsed());
+        let conn = conn.greet(greeting).await?;
+        #[cfg(feature = "tracing")]
+        conn.span
+            .record("connect_time", tracing::field::debug(connect_time));
+        Ok(conn)
+    }
+
+    /// Uses an already connected stream, like a tunnel obtained from a custom dialer
+    ///
+    /// Sends EHLO and parses server information. Tokio and async-std TCP streams can still
+    /// be upgraded with [`AsyncSmtpConnection::starttls`]; see
+    /// [`AsyncNetworkStream::from_stream`] for other streams.
+    pub async fn from_stream<S: Into<AsyncNetworkStream>>(
+        stream: S,
         hello_name: &ClientId,
     ) -> Result<AsyncSmtpConnection, Error> {
+        Self::connect_impl(stream.into(), Some(hello_name), None).await
+    }
+
+    async fn connect_impl(
+        stream: AsyncNetworkStream,
+        hello_name: Option<&ClientId>,
+        transcript: Option<&TranscriptConfig>,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        let mut conn = Self::new(stream, hello_name, transcript);
+        let start = Instant::now();
+        let greeting = conn.read_response().await;
+        conn.timings.record(Phase::Greeting, start.elapsed());
+        conn.greet(greeting).await
+    }
+
+    /// Wraps a connected stream, before reading the server greeting
+    fn new(
+        stream: AsyncNetworkStream,
+        hello_name: Option<&ClientId>,
+        transcript: Option<&TranscriptConfig>,
+    ) -> AsyncSmtpConnection {
+        let hello_name = match hello_name {
+            Some(hello_name) => hello_name.clone(),
+            None => default_hello_name(stream.local_addr()),
+        };
+        #[cfg(feature = "tracing")]
+        let span = connection_span(stream.peer_addr(), stream.is_encrypted(), &hello_name);
         let stream = BufReader::new(stream);
-        let mut conn = AsyncSmtpConnection {
+        AsyncSmtpConnection {
             stream,
             panic: false,
             server_info: ServerInfo::default(),
-        };
-        // TODO log
-        let _response = conn.read_response().await?;
+            hello_name,
+            greeting: placeholder_greeting(),
+            transcript: transcript.map(|config| Transcript::new(*config)),
+            relay: 0,
+            quit_wait: true,
+            timings: PhaseTimings::default(),
+            #[cfg(feature = "tracing")]
+            connected_at: Instant::now(),
+            #[cfg(feature = "tracing")]
+            span,
+        }
+    }
 
-        conn.ehlo(hello_name).await?;
+    /// Checks the greeting read from the server, then sends EHLO and parses server information
+    async fn greet(
+        mut self,
+        greeting: Result<Response, Error>,
+    ) -> Result<AsyncSmtpConnection, Error> {
+        self.greeting = greeting
+            .and_then(check_greeting)
+            .map_err(|err| self.attach_transcript(err.with_phase(Phase::Greeting)))?;
+
+        let hello_name = self.hello_name.clone();
+        self.ehlo(&hello_name)
+            .await
+            .map_err(|err| self.attach_transcript(err))?;
 
         // Print server information
         #[cfg(feature = "tracing")]
-        tracing::debug!("server {}", conn.server_info);
-        Ok(conn)
+        tracing::debug!(parent: &self.span, "server {}", self.server_info);
+        Ok(self)
     }
 
     pub async fn send(&mut self, envelope: &Envelope, email: &[u8]) -> Result<Response, Error> {
-        // Mail
-        let mut mail_options = vec![];
-
-        // Internationalization handling
-        //
-        // * 8BITMIME: https://tools.ietf.org/html/rfc6152
-        // * SMTPUTF8: https://tools.ietf.org/html/rfc653
-
-        // Check for non-ascii addresses and use the SMTPUTF8 option if any.
-        if envelope.has_non_ascii_addresses() {
-            if !self.server_info().supports_feature(Extension::SmtpUtfEight) {
-                // don't try to send non-ascii addresses (per RFC)
-                return Err(error::client(
-                    "Envelope contains non-ascii chars but server does not support SMTPUTF8",
-                ));
-            }
-            mail_options.push(MailParameter::SmtpUtfEight);
-        }
+        #[cfg(feature = "tracing")]
+        let connection_span = {
+            let span = message_span(&self.span, envelope, email);
+            mem::replace(&mut self.span, span)
+        };
 
-        // Check for non-ascii content in message
-        if !email.is_ascii() {
-            if !self.server_info().supports_feature(Extension::EightBitMime) {
-                return Err(error::client(
-                    "Message contains non-ascii chars but server does not support 8BITMIME",
-                ));
-            }
-            mail_options.push(MailParameter::Body(MailBodyParameter::EightBitMime));
+        let result = self.send_message(envelope, email).await;
+
+        #[cfg(feature = "tracing")]
+        {
+            self.span = connection_span;
         }
+        result
+    }
 
+    /// Sends the transaction of `send`
+    async fn send_message(&mut self, envelope: &Envelope, email: &[u8]) -> Result<Response, Error> {
+        // Mail
+        let mail_options = mail_options(&self.server_info, envelope, email)?;
         try_smtp!(
             self.command(Mail::new(envelope.from().cloned(), mail_options))
                 .await,
@@ -138,17 +485,288 @@ impl AsyncSmtpConnection {
         }
 
         // Data
+        #[cfg(feature = "tracing")]
+        let start = Instant::now();
         try_smtp!(self.command(Data).await, self);
 
         // Message content
         let result = try_smtp!(self.message(email).await, self);
+        #[cfg(feature = "tracing")]
+        self.span
+            .record("data_time", tracing::field::debug(start.elapsed()));
         Ok(result)
     }
 
+    /// Sends an email, streaming its content from `body`
+    ///
+    /// The content is read, dot-stuffed and written in chunks of 64 KiB, each chunk being
+    /// written to the server before the next one is read, so only a chunk is kept in
+    /// memory whatever the size of the email. `body` must give a formatted email, with
+    /// CRLF line endings.
+    ///
+    /// As the content isn't known when sending `MAIL`, `BODY=8BITMIME` is declared if the
+    /// server supports it. With servers which don't, 8-bit content fails the sending.
+    /// A failure while the content is sent breaks the connection, as the server can't be
+    /// told to discard what it received.
+    pub async fn send_stream<R>(&mut self, envelope: &Envelope, body: R) -> Result<Response, Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        self.send_stream_counted(envelope, body, &mut 0).await
+    }
+
+    /// Sends an email, streaming its content from a tokio reader
+    ///
+    /// See [`AsyncSmtpConnection::send_stream`].
+    #[cfg(feature = "tokio1")]
+    pub async fn send_tokio1_stream<R>(
+        &mut self,
+        envelope: &Envelope,
+        body: R,
+    ) -> Result<Response, Error>
+    where
+        R: tokio1_crate::io::AsyncRead + Unpin,
+    {
+        self.send_stream(envelope, Tokio1Read(body)).await
+    }
+
+    /// Sends the transaction of `send_stream`, adding the number of bytes read from
+    /// `body` to `len`
+    pub(crate) async fn send_stream_counted<R>(
+        &mut self,
+        envelope: &Envelope,
+        mut body: R,
+        len: &mut usize,
+    ) -> Result<Response, Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        // Mail
+        let eight_bit = self.server_info.supports_feature(Extension::EightBitMime);
+        let mail_options = body_mail_options(&self.server_info, envelope, eight_bit)?;
+        try_smtp!(
+            self.command(Mail::new(envelope.from().cloned(), mail_options))
+                .await,
+            self
+        );
+
+        // Recipient
+        for to_address in envelope.to() {
+            try_smtp!(
+                self.command(Rcpt::new(to_address.clone(), vec![])).await,
+                self
+            );
+        }
+
+        // Data
+        try_smtp!(self.command(Data).await, self);
+
+        // Message content
+        Ok(try_smtp!(self.message_stream(&mut body, len).await, self))
+    }
+
+    /// Sends an email in several transactions, with at most `max_recipients` recipients
+    /// in each
+    ///
+    /// See [`SmtpConnection::send_split`](super::SmtpConnection::send_split).
+    pub async fn send_split(
+        &mut self,
+        envelope: &Envelope,
+        email: &[u8],
+        max_recipients: usize,
+    ) -> Result<SplitDelivery, Error> {
+        self.send_split_capped(envelope, email, max_recipients, usize::MAX)
+            .await
+    }
+
+    /// Sends an email like `send_split`, in at most `max_transactions` transactions
+    ///
+    /// The recipients left once the last transaction is sent are reported as failed.
+    pub(crate) async fn send_split_capped(
+        &mut self,
+        envelope: &Envelope,
+        email: &[u8],
+        max_recipients: usize,
+        max_transactions: usize,
+    ) -> Result<SplitDelivery, Error> {
+        #[cfg(feature = "tracing")]
+        let connection_span = {
+            let span = message_span(&self.span, envelope, email);
+            mem::replace(&mut self.span, span)
+        };
+
+        let result = self
+            .send_transactions(envelope, email, max_recipients.max(1), max_transactions)
+            .await;
+
+        #[cfg(feature = "tracing")]
+        {
+            self.span = connection_span;
+        }
+        result
+    }
+
+    /// Sends the transactions of `send_split`
+    async fn send_transactions(
+        &mut self,
+        envelope: &Envelope,
+        email: &[u8],
+        max_recipients: usize,
+        max_transactions: usize,
+    ) -> Result<SplitDelivery, Error> {
+        let mail_options = mail_options(&self.server_info, envelope, email)?;
+
+        let mut transactions = Vec::new();
+        let mut remaining = envelope.to();
+        while !remaining.is_empty() {
+            if transactions.len() == max_transactions {
+                let err = error::client(format!(
+                    "the recipients can't be sent in more than {} transactions",
+                    max_transactions
+                ));
+                transactions.push((remaining.to_vec(), Err(err)));
+                break;
+            }
+
+            let mail = self
+                .command(Mail::new(envelope.from().cloned(), mail_options.clone()))
+                .await;
+            if let Err(err) = mail {
+                self.end_transaction(&err).await;
+                transactions.push((remaining.to_vec(), Err(err)));
+                break;
+            }
+
+            let chunk = &remaining[..remaining.len().min(max_recipients)];
+            let (sent, result) = self.send_chunk(chunk, email).await;
+            let reusable = match &result {
+                Ok(_) => true,
+                Err(err) => self.end_transaction(err).await,
+            };
+            // The remaining recipients can't be sent the message on a broken connection
+            let sent = if reusable { sent } else { remaining.len() };
+            transactions.push((remaining[..sent].to_vec(), result));
+            remaining = &remaining[sent..];
+        }
+
+        Ok(SplitDelivery { transactions })
+    }
+
+    /// Sends the recipients and the message of a transaction of `send_split`
+    ///
+    /// Returns the number of recipients the transaction covered, which can be less than
+    /// the size of the chunk.
+    async fn send_chunk(
+        &mut self,
+        chunk: &[Address],
+        email: &[u8],
+    ) -> (usize, Result<Response, Error>) {
+        let mut accepted = 0;
+        for to_address in chunk {
+            match self.command(Rcpt::new(to_address.clone(), vec![])).await {
+                Ok(_) => accepted += 1,
+                // Too many recipients, the next transaction takes the remaining ones
+                Err(err) if accepted > 0 && too_many_recipients(&err) => break,
+                Err(err) => return (accepted + 1, Err(err)),
+            }
+        }
+
+        let result = match self.command(Data).await {
+            Ok(_) => self.message(email).await,
+            Err(err) => Err(err),
+        };
+        (accepted, result)
+    }
+
+    /// Resets the transaction after `err`, returns false if the connection was aborted
+    async fn end_transaction(&mut self, err: &Error) -> bool {
+        if (err.is_transient() || err.is_permanent()) && self.command(Rset).await.is_ok() {
+            return true;
+        }
+        self.abort().await;
+        false
+    }
+
+    /// Checks that the server accepts the sender and each recipient of `envelope`,
+    /// without sending any message
+    ///
+    /// Sends the `MAIL` and `RCPT` commands, then resets the transaction with `RSET`.
+    /// `DATA` is never sent, whatever the replies of the server. The recipients are
+    /// checked even if some of them are rejected, and an error is returned if the
+    /// sender is rejected.
+    pub async fn verify_envelope(
+        &mut self,
+        envelope: &Envelope,
+    ) -> Result<EnvelopeVerification, Error> {
+        let mail_options = mail_options(&self.server_info, envelope, &[])?;
+        let sender = match self
+            .command(Mail::new(envelope.from().cloned(), mail_options))
+            .await
+        {
+            Ok(response) => response,
+            Err(err) if err.is_transient() || err.is_permanent() => {
+                try_smtp!(self.command(Rset).await, self);
+                return Err(err);
+            }
+            Err(err) => {
+                self.abort().await;
+                return Err(err);
+            }
+        };
+
+        let mut recipients = Vec::with_capacity(envelope.to().len());
+        for to_address in envelope.to() {
+            let result = match self.command(Rcpt::new(to_address.clone(), vec![])).await {
+                Err(err) if !(err.is_transient() || err.is_permanent()) => {
+                    self.abort().await;
+                    return Err(err);
+                }
+                result => result,
+            };
+            recipients.push((to_address.clone(), result));
+        }
+
+        try_smtp!(self.command(Rset).await, self);
+
+        Ok(EnvelopeVerification { sender, recipients })
+    }
+
     pub fn has_broken(&self) -> bool {
         self.panic
     }
 
+    /// Records the time spent on the handshake in the span of the connection
+    #[cfg(feature = "tracing")]
+    pub(crate) fn record_handshake_time(&self) {
+        self.span.record(
+            "handshake_time",
+            tracing::field::debug(self.connected_at.elapsed()),
+        );
+    }
+
+    /// Starts recording the lines sent and received on this connection
+    ///
+    /// The lines recorded before are dropped. The arguments of `AUTH` commands are
+    /// redacted, as for the transcripts of the transport.
+    pub fn capture_transcript(&mut self, config: TranscriptConfig) {
+        self.transcript = Some(Transcript::new(config));
+    }
+
+    /// Returns the lines recorded since the last call, if capturing transcripts
+    pub fn take_transcript(&mut self) -> Option<Transcript> {
+        self.transcript.as_mut().map(Transcript::take)
+    }
+
+    /// Takes the time spent in each phase of the dialogue since the last call
+    pub(crate) fn take_phase_timings(&mut self) -> PhaseTimings {
+        mem::take(&mut self.timings)
+    }
+
+    /// Attaches the lines recorded since the last call to `err`
+    pub(crate) fn attach_transcript(&mut self, err: Error) -> Error {
+        err.with_transcript(self.take_transcript())
+    }
+
     pub fn can_starttls(&self) -> bool {
         !self.is_encrypted() && self.server_info.supports_feature(Extension::StartTls)
     }
@@ -161,12 +779,19 @@ impl AsyncSmtpConnection {
     ) -> Result<(), Error> {
         if self.server_info.supports_feature(Extension::StartTls) {
             try_smtp!(self.command(Starttls).await, self);
-            try_smtp!(
-                self.stream.get_mut().upgrade_tls(tls_parameters).await,
-                self
-            );
+            let start = Instant::now();
+            let upgraded = self.stream.get_mut().upgrade_tls(tls_parameters).await;
+            self.timings.record(Phase::TlsHandshake, start.elapsed());
+            if let Err(err) = upgraded {
+                // The stream is lost when the handshake fails, don't try to send QUIT
+                self.panic = true;
+                return Err(err);
+            }
             #[cfg(feature = "tracing")]
-            tracing::debug!("connection encrypted");
+            {
+                self.span.record("tls", true);
+                tracing::debug!(parent: &self.span, "connection encrypted");
+            }
             // Send EHLO again
             try_smtp!(self.ehlo(hello_name).await, self);
             Ok(())
@@ -175,22 +800,87 @@ impl AsyncSmtpConnection {
         }
     }
 
+    /// Forwards the attributes of the original client with `XCLIENT`
+    ///
+    /// The attributes the server doesn't advertise are omitted, and nothing is sent if
+    /// none is left. As required by the extension, EHLO is sent again afterwards
+    /// to refresh the server info. Must not be called during a mail transaction.
+    pub async fn xclient(&mut self, xclient: &XClient) -> Result<(), Error> {
+        let xclient = xclient.supported_by(&self.server_info);
+        if xclient.is_empty() {
+            return Ok(());
+        }
+
+        try_smtp!(self.command(xclient).await, self);
+        let hello_name = self.hello_name.clone();
+        self.ehlo(&hello_name).await
+    }
+
     /// Send EHLO and update server info
     async fn ehlo(&mut self, hello_name: &ClientId) -> Result<(), Error> {
         let ehlo_response = try_smtp!(self.command(Ehlo::new(hello_name.clone())).await, self);
         self.server_info = try_smtp!(ServerInfo::from_response(&ehlo_response), self);
+        self.hello_name = hello_name.clone();
         Ok(())
     }
 
+    /// Sends `QUIT` and closes the connection gracefully
+    ///
+    /// A server closing the connection without replying is considered to have accepted
+    /// the command. If the connection doesn't [wait](#method.set_quit_wait) for the
+    /// reply, it's closed right after sending the command, and the reply is assumed.
     pub async fn quit(&mut self) -> Result<Response, Error> {
-        Ok(try_smtp!(self.command(Quit).await, self))
+        let command = Quit.to_string();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(parent: &self.span, ">> {}", command);
+        if let Some(transcript) = &mut self.transcript {
+            transcript.record_command(&command, &command);
+        }
+
+        if !self.quit_wait {
+            let result = self
+                .write(command.as_bytes())
+                .await
+                .map(|()| closed_after_quit())
+                .map_err(|err| err.with_command(&command).with_phase(Phase::Command));
+            if result.is_err() {
+                self.panic = true;
+            }
+
+            let _ = self.stream.get_mut().close().await;
+            return result;
+        }
+
+        let runtime = self.stream.get_ref().runtime();
+        let reply = async {
+            self.write(command.as_bytes()).await?;
+            self.read_response_or_eof().await
+        };
+        let result = match runtime.timeout(QUIT_TIMEOUT, reply).await {
+            Some(reply) => reply.map(|response| response.unwrap_or_else(closed_after_quit)),
+            None => Err(error::network(io::Error::new(
+                io::ErrorKind::TimedOut,
+                "timed out waiting for the reply to QUIT",
+            ))),
+        };
+        let result = result.map_err(|err| err.with_command(&command).with_phase(Phase::Command));
+        if result.is_err() {
+            self.panic = true;
+        }
+
+        let _ = self.stream.get_mut().close_gracefully(QUIT_TIMEOUT).await;
+        result
     }
 
     pub async fn abort(&mut self) {
         // Only try to quit if we are not already broken
         if !self.panic {
             self.panic = true;
-            let _ = self.command(Quit).await;
+            if self.quit_wait {
+                let _ = self.command(Quit).await;
+            } else {
+                let _ = self.write(Quit.to_string().as_bytes()).await;
+            }
         }
     }
 
@@ -209,6 +899,18 @@ impl AsyncSmtpConnection {
         self.command(Noop).await.is_ok()
     }
 
+    /// Asks the server to verify a user name or mailbox using the VRFY SMTP command
+    pub async fn vrfy(&mut self, address: &str) -> Result<Verification, Error> {
+        check_argument(address)?;
+        Verification::from_result(self.command(Vrfy::new(address.to_string())).await)
+    }
+
+    /// Asks the server to expand a mailing list using the EXPN SMTP command
+    pub async fn expn(&mut self, list: &str) -> Result<Verification, Error> {
+        check_argument(list)?;
+        Verification::from_result(self.command(Expn::new(list.to_string())).await)
+    }
+
     /// Sends an AUTH command with the given mechanism, and handles challenge if needed
     pub async fn auth(
         &mut self,
@@ -223,18 +925,22 @@ impl AsyncSmtpConnection {
         // Limit challenges to avoid blocking
         let mut challenges: u8 = 10;
         let mut response = self
-            .command(Auth::new(mechanism, credentials.clone(), None)?)
+            .command(Auth::new(mechanism.clone(), credentials.clone(), None)?)
             .await?;
 
+        let mut login = LoginExchange::default();
         while challenges > 0 && response.has_code(334) {
             challenges -= 1;
+            let auth = match mechanism {
+                Mechanism::Login => {
+                    Auth::new_from_login_response(credentials.clone(), &response, &mut login)
+                }
+                _ => Auth::new_from_response(mechanism.clone(), credentials.clone(), &response),
+            };
+            let auth_response = try_smtp!(auth, self).to_string();
             response = try_smtp!(
-                self.command(Auth::new_from_response(
-                    mechanism,
-                    credentials.clone(),
-                    &response,
-                )?)
-                .await,
+                self.send_command(&auth_response, &format!("AUTH {}", mechanism))
+                    .await,
                 self
             );
         }
@@ -247,19 +953,121 @@ impl AsyncSmtpConnection {
     }
 
     /// Sends the message content
+    ///
+    /// Errors are recorded as happening during the `DATA` command.
     pub async fn message(&mut self, message: &[u8]) -> Result<Response, Error> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(parent: &self.span, ">> message data ({} bytes)", message.len());
+        if let Some(transcript) = &mut self.transcript {
+            transcript.record_data(message);
+        }
         let mut out_buf: Vec<u8> = vec![];
         let mut codec = ClientCodec::new();
         codec.encode(message, &mut out_buf);
-        self.write(out_buf.as_slice()).await?;
-        self.write(b"\r\n.\r\n").await?;
-        self.read_response().await
+        let start = Instant::now();
+        let written = match self.write(out_buf.as_slice()).await {
+            Ok(()) => self.write(b"\r\n.\r\n").await,
+            Err(err) => Err(err),
+        };
+        self.timings.record(Phase::Message, start.elapsed());
+        let result = match written {
+            Ok(()) => self.read_message_reply().await,
+            Err(err) => Err(err.with_phase(Phase::Message)),
+        };
+        result.map_err(|err| err.with_command("DATA"))
+    }
+
+    /// Sends the message content read from `body`, in bounded chunks
+    ///
+    /// The connection is broken if the content can't be read or written entirely.
+    async fn message_stream<R>(&mut self, body: &mut R, len: &mut usize) -> Result<Response, Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let start = Instant::now();
+        let written = self.write_stream(body, len).await;
+        self.timings.record(Phase::Message, start.elapsed());
+        let result = match written {
+            Ok(()) => self.read_message_reply().await,
+            Err(err) => {
+                self.panic = true;
+                Err(err.with_phase(Phase::Message))
+            }
+        };
+        result.map_err(|err| err.with_command("DATA"))
+    }
+
+    /// Reads the reply to the whole message data
+    async fn read_message_reply(&mut self) -> Result<Response, Error> {
+        let start = Instant::now();
+        let response = self.read_response().await;
+        self.timings.record(Phase::MessageReply, start.elapsed());
+        response.map_err(|err| err.with_phase(Phase::MessageReply))
+    }
+
+    /// Writes the message content read from `body`, followed by the end of data
+    async fn write_stream<R>(&mut self, body: &mut R, len: &mut usize) -> Result<(), Error>
+    where
+        R: AsyncRead + Unpin,
+    {
+        let seven_bit = !self.server_info.supports_feature(Extension::EightBitMime);
+        let data_prefix = self.transcript.as_ref().map_or(0, Transcript::data_prefix);
+        let mut start = Vec::with_capacity(data_prefix);
+
+        let mut codec = ClientCodec::new();
+        let mut chunk = vec![0; STREAM_CHUNK_SIZE];
+        let mut out_buf = Vec::with_capacity(STREAM_CHUNK_SIZE);
+        loop {
+            let read = body.read(&mut chunk).await.map_err(error::client)?;
+            if read == 0 {
+                break;
+            }
+            let chunk = &chunk[..read];
+            if seven_bit && !chunk.is_ascii() {
+                return Err(eight_bit_unsupported());
+            }
+            if start.len() < data_prefix {
+                let missing = data_prefix - start.len();
+                start.extend_from_slice(&chunk[..chunk.len().min(missing)]);
+            }
+            *len += read;
+
+            out_buf.clear();
+            codec.encode(chunk, &mut out_buf);
+            self.write(&out_buf).await?;
+        }
+
+        #[cfg(feature = "tracing")]
+        tracing::debug!(parent: &self.span, ">> message data ({} bytes, streamed)", len);
+        if let Some(transcript) = &mut self.transcript {
+            transcript.record_streamed_data(&start, *len);
+        }
+        self.write(b"\r\n.\r\n").await
     }
 
     /// Sends an SMTP command
+    ///
+    /// Errors record the command, with the arguments of `AUTH` commands redacted.
     pub async fn command<C: Display>(&mut self, command: C) -> Result<Response, Error> {
-        self.write(command.to_string().as_bytes()).await?;
-        self.read_response().await
+        let command = command.to_string();
+        self.send_command(&command, redacted_command(&command))
+            .await
+    }
+
+    /// Sends a raw SMTP command, logged and recorded in errors as `redacted`
+    async fn send_command(&mut self, command: &str, redacted: &str) -> Result<Response, Error> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(parent: &self.span, ">> {}", redacted);
+        if let Some(transcript) = &mut self.transcript {
+            transcript.record_command(command, redacted);
+        }
+        let start = Instant::now();
+        let result = match self.write(command.as_bytes()).await {
+            Ok(()) => self.read_response().await,
+            Err(err) => Err(err),
+        };
+        self.timings.record(Phase::Command, start.elapsed());
+        result.map_err(|err| err.with_command(redacted).with_phase(Phase::Command))
     }
 
     /// Writes a string to the server
@@ -274,14 +1082,19 @@ impl AsyncSmtpConnection {
             .flush()
             .await
             .map_err(error::network)?;
-
-        #[cfg(feature = "tracing")]
-        tracing::debug!("Wrote: {}", escape_crlf(&String::from_utf8_lossy(string)));
         Ok(())
     }
 
     /// Gets the SMTP response
     pub async fn read_response(&mut self) -> Result<Response, Error> {
+        self.read_response_or_eof()
+            .await?
+            .ok_or_else(|| error::response("incomplete response"))
+    }
+
+    /// Gets the SMTP response, `None` if the server closed the connection before sending
+    /// anything
+    async fn read_response_or_eof(&mut self) -> Result<Option<Response>, Error> {
         let mut buffer = String::with_capacity(100);
 
         while self
@@ -291,15 +1104,21 @@ impl AsyncSmtpConnection {
             .map_err(error::network)?
             > 0
         {
-            #[cfg(feature = "tracing")]
-            tracing::debug!("<< {}", escape_crlf(&buffer));
-            match parse_response(&buffer) {
+            let parsed = parse_response(&buffer);
+            if !matches!(parsed, Err(nom::Err::Incomplete(_))) {
+                if let Some(transcript) = &mut self.transcript {
+                    transcript.record(Direction::Server, &buffer);
+                }
+            }
+            match parsed {
                 Ok((_remaining, response)) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(parent: &self.span, "<< {}", escape_crlf(&buffer));
                     return if response.is_positive() {
-                        Ok(response)
+                        Ok(Some(response))
                     } else {
-                        Err(error::code(response.code()))
-                    }
+                        Err(error::reply(response))
+                    };
                 }
                 Err(nom::Err::Failure(e)) => {
                     return Err(error::response(e.to_string()));
@@ -311,6 +1130,10 @@ impl AsyncSmtpConnection {
             }
         }
 
-        Err(error::response("incomplete response"))
+        if buffer.is_empty() {
+            Ok(None)
+        } else {
+            Err(error::response("incomplete response"))
+        }
     }
 }
@@ -1,6 +1,8 @@
 use std::{
+    future::Future,
     io, mem,
     net::SocketAddr,
+    ops::RangeInclusive,
     pin::Pin,
     task::{Context, Poll},
     time::Duration,
@@ -15,8 +17,17 @@ use tokio1_crate::io::{AsyncRead as _, AsyncWrite as _, ReadBuf as Tokio1ReadBuf
 
 #[cfg(feature = "async-std1")]
 use async_std::net::{TcpStream as AsyncStd1TcpStream, ToSocketAddrs as AsyncStd1ToSocketAddrs};
+#[cfg(all(feature = "async-std1", unix))]
+use async_std::os::unix::net::UnixStream as AsyncStd1UnixStream;
+#[cfg(unix)]
+use std::path::Path;
+#[cfg(all(feature = "tokio1", unix))]
+use tokio1_crate::net::UnixStream as Tokio1UnixStream;
 #[cfg(feature = "tokio1")]
-use tokio1_crate::net::{TcpStream as Tokio1TcpStream, ToSocketAddrs as Tokio1ToSocketAddrs};
+use tokio1_crate::net::{
+    TcpSocket as Tokio1TcpSocket, TcpStream as Tokio1TcpStream,
+    ToSocketAddrs as Tokio1ToSocketAddrs,
+};
 
 #[cfg(feature = "async-std1-native-tls")]
 use async_native_tls::TlsStream as AsyncStd1TlsStream;
@@ -28,6 +39,10 @@ use async_rustls::client::TlsStream as AsyncStd1RustlsTlsStream;
 #[cfg(feature = "tokio1-rustls-tls")]
 use tokio1_rustls::client::TlsStream as Tokio1RustlsTlsStream;
 
+#[cfg(all(feature = "async-std1", feature = "socket2"))]
+use super::net::connect_from;
+#[cfg(feature = "tokio1")]
+use super::net::{is_port_unavailable, source_addr};
 #[cfg(any(
     feature = "tokio1-native-tls",
     feature = "tokio1-rustls-tls",
@@ -35,7 +50,7 @@ use tokio1_rustls::client::TlsStream as Tokio1RustlsTlsStream;
     feature = "async-std1-rustls-tls"
 ))]
 use super::InnerTlsParameters;
-use super::TlsParameters;
+use super::{ProxyProtocol, TlsParameters};
 use crate::transport::smtp::{error, Error};
 
 /// A network stream
@@ -67,10 +82,53 @@ enum InnerAsyncNetworkStream {
     /// Encrypted Tokio 1.x TCP stream
     #[cfg(feature = "async-std1-rustls-tls")]
     AsyncStd1RustlsTls(AsyncStd1RustlsTlsStream<AsyncStd1TcpStream>),
+    /// Tokio 1.x Unix domain socket stream
+    #[cfg(all(feature = "tokio1", unix))]
+    Tokio1Unix(Tokio1UnixStream),
+    /// async-std 1.x Unix domain socket stream
+    #[cfg(all(feature = "async-std1", unix))]
+    AsyncStd1Unix(AsyncStd1UnixStream),
+    /// Stream provided by the caller
+    Custom(Box<dyn AsyncReadWrite>),
     /// Can't be built
     None,
 }
 
+/// Runtime driving a stream, providing its timers
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Runtime {
+    #[cfg(feature = "tokio1")]
+    Tokio1,
+    #[cfg(feature = "async-std1")]
+    AsyncStd1,
+    /// Stream provided by the caller, no timer is available
+    Unknown,
+}
+
+impl Runtime {
+    /// Runs `future` for at most `duration`, `None` if it didn't complete in time
+    ///
+    /// Futures of streams provided by the caller always run to completion.
+    pub(crate) async fn timeout<F: Future>(
+        self,
+        duration: Duration,
+        future: F,
+    ) -> Option<F::Output> {
+        match self {
+            #[cfg(feature = "tokio1")]
+            Runtime::Tokio1 => tokio1_crate::time::timeout(duration, future).await.ok(),
+            #[cfg(feature = "async-std1")]
+            Runtime::AsyncStd1 => async_std::future::timeout(duration, future).await.ok(),
+            Runtime::Unknown => Some(future.await),
+        }
+    }
+}
+
+/// Streams which can be wrapped by an [`AsyncNetworkStream`]
+trait AsyncReadWrite: FuturesAsyncRead + FuturesAsyncWrite + Send + Unpin {}
+
+impl<S: FuturesAsyncRead + FuturesAsyncWrite + Send + Unpin> AsyncReadWrite for S {}
+
 impl AsyncNetworkStream {
     fn new(inner: InnerAsyncNetworkStream) -> Self {
         if let InnerAsyncNetworkStream::None = inner {
@@ -80,6 +138,17 @@ impl AsyncNetworkStream {
         AsyncNetworkStream { inner }
     }
 
+    /// Wraps an already connected stream, like a tunnel obtained from a custom dialer
+    ///
+    /// The stream can't be upgraded to TLS. Tokio and async-std TCP streams should be
+    /// converted with `From` instead, to keep STARTTLS support.
+    pub fn from_stream<S>(stream: S) -> Self
+    where
+        S: FuturesAsyncRead + FuturesAsyncWrite + Send + Unpin + 'static,
+    {
+        AsyncNetworkStream::new(InnerAsyncNetworkStream::Custom(Box::new(stream)))
+    }
+
     /// Returns peer's address
     pub fn peer_addr(&self) -> IoResult<SocketAddr> {
         match self.inner {
@@ -97,6 +166,61 @@ impl AsyncNetworkStream {
             InnerAsyncNetworkStream::AsyncStd1NativeTls(ref s) => s.get_ref().peer_addr(),
             #[cfg(feature = "async-std1-rustls-tls")]
             InnerAsyncNetworkStream::AsyncStd1RustlsTls(ref s) => s.get_ref().0.peer_addr(),
+            #[cfg(all(feature = "tokio1", unix))]
+            InnerAsyncNetworkStream::Tokio1Unix(_) => Err(IoError::new(
+                ErrorKind::Other,
+                "Unix sockets don't have an IP address",
+            )),
+            #[cfg(all(feature = "async-std1", unix))]
+            InnerAsyncNetworkStream::AsyncStd1Unix(_) => Err(IoError::new(
+                ErrorKind::Other,
+                "Unix sockets don't have an IP address",
+            )),
+            InnerAsyncNetworkStream::Custom(_) => Err(IoError::new(
+                ErrorKind::Other,
+                "the address of a caller-provided stream is unknown",
+            )),
+            InnerAsyncNetworkStream::None => {
+                debug_assert!(false, "InnerAsyncNetworkStream::None must never be built");
+                Err(IoError::new(
+                    ErrorKind::Other,
+                    "InnerAsyncNetworkStream::None must never be built",
+                ))
+            }
+        }
+    }
+
+    /// Returns the local address of the connection
+    pub fn local_addr(&self) -> IoResult<SocketAddr> {
+        match self.inner {
+            #[cfg(feature = "tokio1")]
+            InnerAsyncNetworkStream::Tokio1Tcp(ref s) => s.local_addr(),
+            #[cfg(feature = "tokio1-native-tls")]
+            InnerAsyncNetworkStream::Tokio1NativeTls(ref s) => {
+                s.get_ref().get_ref().get_ref().local_addr()
+            }
+            #[cfg(feature = "tokio1-rustls-tls")]
+            InnerAsyncNetworkStream::Tokio1RustlsTls(ref s) => s.get_ref().0.local_addr(),
+            #[cfg(feature = "async-std1")]
+            InnerAsyncNetworkStream::AsyncStd1Tcp(ref s) => s.local_addr(),
+            #[cfg(feature = "async-std1-native-tls")]
+            InnerAsyncNetworkStream::AsyncStd1NativeTls(ref s) => s.get_ref().local_addr(),
+            #[cfg(feature = "async-std1-rustls-tls")]
+            InnerAsyncNetworkStream::AsyncStd1RustlsTls(ref s) => s.get_ref().0.local_addr(),
+            #[cfg(all(feature = "tokio1", unix))]
+            InnerAsyncNetworkStream::Tokio1Unix(_) => Err(IoError::new(
+                ErrorKind::Other,
+                "Unix sockets don't have an IP address",
+            )),
+            #[cfg(all(feature = "async-std1", unix))]
+            InnerAsyncNetworkStream::AsyncStd1Unix(_) => Err(IoError::new(
+                ErrorKind::Other,
+                "Unix sockets don't have an IP address",
+            )),
+            InnerAsyncNetworkStream::Custom(_) => Err(IoError::new(
+                ErrorKind::Other,
+                "the address of a caller-provided stream is unknown",
+            )),
             InnerAsyncNetworkStream::None => {
                 debug_assert!(false, "InnerAsyncNetworkStream::None must never be built");
                 Err(IoError::new(
@@ -112,6 +236,20 @@ impl AsyncNetworkStream {
         server: T,
         timeout: Option<Duration>,
         tls_parameters: Option<TlsParameters>,
+    ) -> Result<AsyncNetworkStream, Error> {
+        Self::connect_tokio1_with(server, timeout, None, None, tls_parameters).await
+    }
+
+    /// Connects to `server` from a local port of `source_ports` if set, sending the
+    /// PROXY protocol header first if `proxy_protocol` is set, then upgrades the
+    /// connection to TLS if `tls_parameters` are set
+    #[cfg(feature = "tokio1")]
+    pub(crate) async fn connect_tokio1_with<T: Tokio1ToSocketAddrs>(
+        server: T,
+        timeout: Option<Duration>,
+        source_ports: Option<&RangeInclusive<u16>>,
+        proxy_protocol: Option<&ProxyProtocol>,
+        tls_parameters: Option<TlsParameters>,
     ) -> Result<AsyncNetworkStream, Error> {
         async fn try_connect_timeout<T: Tokio1ToSocketAddrs>(
             server: T,
@@ -143,14 +281,83 @@ impl AsyncNetworkStream {
             })
         }
 
-        let tcp_stream = match timeout {
-            Some(t) => try_connect_timeout(server, t).await?,
-            None => Tokio1TcpStream::connect(server)
+        async fn try_connect_from<T: Tokio1ToSocketAddrs>(
+            server: T,
+            ports: &RangeInclusive<u16>,
+            timeout: Option<Duration>,
+        ) -> Result<Tokio1TcpStream, Error> {
+            let addrs = tokio1_crate::net::lookup_host(server)
+                .await
+                .map_err(error::connection)?;
+
+            let mut last_err = None;
+
+            for addr in addrs {
+                for port in ports.clone() {
+                    let socket = match addr {
+                        SocketAddr::V4(_) => Tokio1TcpSocket::new_v4(),
+                        SocketAddr::V6(_) => Tokio1TcpSocket::new_v6(),
+                    }
+                    .map_err(error::connection)?;
+                    if let Err(err) = socket.bind(source_addr(&addr, port)) {
+                        if !is_port_unavailable(&err) {
+                            return Err(error::connection(err));
+                        }
+                        last_err = Some(err);
+                        continue;
+                    }
+
+                    let connect_future = socket.connect(addr);
+                    let result = match timeout {
+                        Some(timeout) => tokio1_crate::time::timeout(timeout, connect_future)
+                            .await
+                            .unwrap_or_else(|_| {
+                                Err(io::Error::new(
+                                    io::ErrorKind::TimedOut,
+                                    "connection timed out",
+                                ))
+                            }),
+                        None => connect_future.await,
+                    };
+                    match result {
+                        Ok(stream) => return Ok(stream),
+                        Err(err) if is_port_unavailable(&err) => last_err = Some(err),
+                        Err(err) => {
+                            last_err = Some(err);
+                            break;
+                        }
+                    }
+                }
+            }
+
+            Err(match last_err {
+                Some(last_err) => error::connection(last_err),
+                None => error::connection("could not resolve to any address"),
+            })
+        }
+
+        let mut tcp_stream = match (source_ports, timeout) {
+            (Some(ports), _) => try_connect_from(server, ports, timeout).await?,
+            (None, Some(t)) => try_connect_timeout(server, t).await?,
+            (None, None) => Tokio1TcpStream::connect(server)
                 .await
                 .map_err(error::connection)?,
         };
 
-        let mut stream = AsyncNetworkStream::new(InnerAsyncNetworkStream::Tokio1Tcp(tcp_stream));
+        if let Some(proxy_protocol) = proxy_protocol {
+            use tokio1_crate::io::AsyncWriteExt;
+
+            let header = proxy_protocol.header(
+                tcp_stream.local_addr().map_err(error::network)?,
+                tcp_stream.peer_addr().map_err(error::network)?,
+            );
+            tcp_stream
+                .write_all(&header)
+                .await
+                .map_err(error::network)?;
+        }
+
+        let mut stream = AsyncNetworkStream::from(tcp_stream);
         if let Some(tls_parameters) = tls_parameters {
             stream.upgrade_tls(tls_parameters).await?;
         }
@@ -162,6 +369,20 @@ impl AsyncNetworkStream {
         server: T,
         timeout: Option<Duration>,
         tls_parameters: Option<TlsParameters>,
+    ) -> Result<AsyncNetworkStream, Error> {
+        Self::connect_asyncstd1_with(server, timeout, None, None, tls_parameters).await
+    }
+
+    /// Connects to `server` from a local port of `source_ports` if set, sending the
+    /// PROXY protocol header first if `proxy_protocol` is set, then upgrades the
+    /// connection to TLS if `tls_parameters` are set
+    #[cfg(feature = "async-std1")]
+    pub(crate) async fn connect_asyncstd1_with<T: AsyncStd1ToSocketAddrs>(
+        server: T,
+        timeout: Option<Duration>,
+        source_ports: Option<&RangeInclusive<u16>>,
+        proxy_protocol: Option<&ProxyProtocol>,
+        tls_parameters: Option<TlsParameters>,
     ) -> Result<AsyncNetworkStream, Error> {
         async fn try_connect_timeout<T: AsyncStd1ToSocketAddrs>(
             server: T,
@@ -191,20 +412,83 @@ impl AsyncNetworkStream {
             })
         }
 
-        let tcp_stream = match timeout {
-            Some(t) => try_connect_timeout(server, t).await?,
-            None => AsyncStd1TcpStream::connect(server)
+        #[cfg(feature = "socket2")]
+        async fn try_connect_from<T: AsyncStd1ToSocketAddrs>(
+            server: T,
+            ports: &RangeInclusive<u16>,
+            timeout: Option<Duration>,
+        ) -> Result<AsyncStd1TcpStream, Error> {
+            let addrs = server.to_socket_addrs().await.map_err(error::connection)?;
+
+            let mut last_err = None;
+
+            for addr in addrs {
+                let ports = ports.clone();
+                let connect_future =
+                    async_std::task::spawn_blocking(move || connect_from(&addr, &ports, timeout));
+                match connect_future.await {
+                    Ok(stream) => return Ok(AsyncStd1TcpStream::from(stream)),
+                    Err(err) => last_err = Some(err),
+                }
+            }
+
+            Err(match last_err {
+                Some(last_err) => error::connection(last_err),
+                None => error::connection("could not resolve to any address"),
+            })
+        }
+
+        let mut tcp_stream = match (source_ports, timeout) {
+            #[cfg(feature = "socket2")]
+            (Some(ports), _) => try_connect_from(server, ports, timeout).await?,
+            (_, Some(t)) => try_connect_timeout(server, t).await?,
+            (_, None) => AsyncStd1TcpStream::connect(server)
                 .await
                 .map_err(error::connection)?,
         };
 
-        let mut stream = AsyncNetworkStream::new(InnerAsyncNetworkStream::AsyncStd1Tcp(tcp_stream));
+        if let Some(proxy_protocol) = proxy_protocol {
+            use futures_util::io::AsyncWriteExt;
+
+            let header = proxy_protocol.header(
+                tcp_stream.local_addr().map_err(error::network)?,
+                tcp_stream.peer_addr().map_err(error::network)?,
+            );
+            tcp_stream
+                .write_all(&header)
+                .await
+                .map_err(error::network)?;
+        }
+
+        let mut stream = AsyncNetworkStream::from(tcp_stream);
         if let Some(tls_parameters) = tls_parameters {
             stream.upgrade_tls(tls_parameters).await?;
         }
         Ok(stream)
     }
 
+    /// Connects to a Unix domain socket with tokio
+    #[cfg(all(feature = "tokio1", unix))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "tokio1", unix))))]
+    pub async fn connect_tokio1_unix<P: AsRef<Path>>(path: P) -> Result<AsyncNetworkStream, Error> {
+        let stream = Tokio1UnixStream::connect(path)
+            .await
+            .map_err(error::connection)?;
+        Ok(AsyncNetworkStream::from(stream))
+    }
+
+    /// Connects to a Unix domain socket with async-std
+    #[cfg(all(feature = "async-std1", unix))]
+    #[cfg_attr(docsrs, doc(cfg(all(feature = "async-std1", unix))))]
+    pub async fn connect_asyncstd1_unix<P: AsRef<Path>>(
+        path: P,
+    ) -> Result<AsyncNetworkStream, Error> {
+        let stream = AsyncStd1UnixStream::connect(path.as_ref())
+            .await
+            .map_err(error::connection)?;
+        Ok(AsyncNetworkStream::from(stream))
+    }
+
     pub async fn upgrade_tls(&mut self, tls_parameters: TlsParameters) -> Result<(), Error> {
         match &self.inner {
             #[cfg(all(
@@ -225,9 +509,7 @@ impl AsyncNetworkStream {
                     _ => unreachable!(),
                 };
 
-                self.inner = Self::upgrade_tokio1_tls(tcp_stream, tls_parameters)
-                    .await
-                    .map_err(error::connection)?;
+                self.inner = Self::upgrade_tokio1_tls(tcp_stream, tls_parameters).await?;
                 Ok(())
             }
             #[cfg(all(
@@ -248,11 +530,20 @@ impl AsyncNetworkStream {
                     _ => unreachable!(),
                 };
 
-                self.inner = Self::upgrade_asyncstd1_tls(tcp_stream, tls_parameters)
-                    .await
-                    .map_err(error::connection)?;
+                self.inner = Self::upgrade_asyncstd1_tls(tcp_stream, tls_parameters).await?;
                 Ok(())
             }
+            #[cfg(all(feature = "tokio1", unix))]
+            InnerAsyncNetworkStream::Tokio1Unix(_) => {
+                Err(error::client("TLS isn't supported on Unix sockets"))
+            }
+            #[cfg(all(feature = "async-std1", unix))]
+            InnerAsyncNetworkStream::AsyncStd1Unix(_) => {
+                Err(error::client("TLS isn't supported on Unix sockets"))
+            }
+            InnerAsyncNetworkStream::Custom(_) => Err(error::client(
+                "TLS isn't supported on caller-provided streams",
+            )),
             _ => Ok(()),
         }
     }
@@ -279,7 +570,7 @@ impl AsyncNetworkStream {
                     let stream = connector
                         .connect(&domain, tcp_stream)
                         .await
-                        .map_err(error::connection)?;
+                        .map_err(error::tls_handshake)?;
                     Ok(InnerAsyncNetworkStream::Tokio1NativeTls(stream))
                 };
             }
@@ -299,7 +590,7 @@ impl AsyncNetworkStream {
                     let stream = connector
                         .connect(domain, tcp_stream)
                         .await
-                        .map_err(error::connection)?;
+                        .map_err(error::tls_handshake)?;
                     Ok(InnerAsyncNetworkStream::Tokio1RustlsTls(stream))
                 };
             }
@@ -351,13 +642,72 @@ impl AsyncNetworkStream {
                     let stream = connector
                         .connect(domain, tcp_stream)
                         .await
-                        .map_err(error::connection)?;
+                        .map_err(error::tls_handshake)?;
                     Ok(InnerAsyncNetworkStream::AsyncStd1RustlsTls(stream))
                 };
             }
         }
     }
 
+    /// Runtime the stream was opened with
+    pub(crate) fn runtime(&self) -> Runtime {
+        match self.inner {
+            #[cfg(feature = "tokio1")]
+            InnerAsyncNetworkStream::Tokio1Tcp(_) => Runtime::Tokio1,
+            #[cfg(feature = "tokio1-native-tls")]
+            InnerAsyncNetworkStream::Tokio1NativeTls(_) => Runtime::Tokio1,
+            #[cfg(feature = "tokio1-rustls-tls")]
+            InnerAsyncNetworkStream::Tokio1RustlsTls(_) => Runtime::Tokio1,
+            #[cfg(feature = "async-std1")]
+            InnerAsyncNetworkStream::AsyncStd1Tcp(_) => Runtime::AsyncStd1,
+            #[cfg(feature = "async-std1-native-tls")]
+            InnerAsyncNetworkStream::AsyncStd1NativeTls(_) => Runtime::AsyncStd1,
+            #[cfg(feature = "async-std1-rustls-tls")]
+            InnerAsyncNetworkStream::AsyncStd1RustlsTls(_) => Runtime::AsyncStd1,
+            #[cfg(all(feature = "tokio1", unix))]
+            InnerAsyncNetworkStream::Tokio1Unix(_) => Runtime::Tokio1,
+            #[cfg(all(feature = "async-std1", unix))]
+            InnerAsyncNetworkStream::AsyncStd1Unix(_) => Runtime::AsyncStd1,
+            InnerAsyncNetworkStream::Custom(_) | InnerAsyncNetworkStream::None => Runtime::Unknown,
+        }
+    }
+
+    /// Closes the connection gracefully, once `QUIT` was answered
+    ///
+    /// Ends the TLS session with a `close_notify` alert, shuts down the writing half of
+    /// the connection, then reads what the server still sends until it closes the
+    /// connection, for at most `timeout`. Streams provided by the caller are only closed.
+    pub async fn close_gracefully(&mut self, timeout: Duration) -> IoResult<()> {
+        use futures_util::io::{AsyncReadExt, AsyncWriteExt};
+
+        self.close().await?;
+        // native-tls only ends the TLS session
+        match self.inner {
+            #[cfg(feature = "tokio1-native-tls")]
+            InnerAsyncNetworkStream::Tokio1NativeTls(ref mut s) => {
+                use tokio1_crate::io::AsyncWriteExt as _;
+
+                s.get_mut().get_mut().get_mut().shutdown().await?;
+            }
+            #[cfg(feature = "async-std1-native-tls")]
+            InnerAsyncNetworkStream::AsyncStd1NativeTls(ref mut s) => {
+                s.get_ref().shutdown(std::net::Shutdown::Write)?;
+            }
+            _ => {}
+        }
+
+        let runtime = match self.runtime() {
+            Runtime::Unknown => return Ok(()),
+            runtime => runtime,
+        };
+        let drain = async {
+            let mut buf = [0; 1024];
+            while self.read(&mut buf).await? > 0 {}
+            Ok(())
+        };
+        runtime.timeout(timeout, drain).await.unwrap_or(Ok(()))
+    }
+
     pub fn is_encrypted(&self) -> bool {
         match self.inner {
             #[cfg(feature = "tokio1")]
@@ -372,11 +722,52 @@ impl AsyncNetworkStream {
             InnerAsyncNetworkStream::AsyncStd1NativeTls(_) => true,
             #[cfg(feature = "async-std1-rustls-tls")]
             InnerAsyncNetworkStream::AsyncStd1RustlsTls(_) => true,
+            #[cfg(all(feature = "tokio1", unix))]
+            InnerAsyncNetworkStream::Tokio1Unix(_) => false,
+            #[cfg(all(feature = "async-std1", unix))]
+            InnerAsyncNetworkStream::AsyncStd1Unix(_) => false,
+            InnerAsyncNetworkStream::Custom(_) => false,
             InnerAsyncNetworkStream::None => false,
         }
     }
 }
 
+#[cfg(feature = "tokio1")]
+#[cfg_attr(docsrs, doc(cfg(feature = "tokio1")))]
+impl From<Tokio1TcpStream> for AsyncNetworkStream {
+    /// Wraps a connected TCP stream, which can later be upgraded to TLS
+    fn from(stream: Tokio1TcpStream) -> Self {
+        AsyncNetworkStream::new(InnerAsyncNetworkStream::Tokio1Tcp(stream))
+    }
+}
+
+#[cfg(feature = "async-std1")]
+#[cfg_attr(docsrs, doc(cfg(feature = "async-std1")))]
+impl From<AsyncStd1TcpStream> for AsyncNetworkStream {
+    /// Wraps a connected TCP stream, which can later be upgraded to TLS
+    fn from(stream: AsyncStd1TcpStream) -> Self {
+        AsyncNetworkStream::new(InnerAsyncNetworkStream::AsyncStd1Tcp(stream))
+    }
+}
+
+#[cfg(all(feature = "tokio1", unix))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "tokio1", unix))))]
+impl From<Tokio1UnixStream> for AsyncNetworkStream {
+    /// Wraps a connected Unix domain socket stream
+    fn from(stream: Tokio1UnixStream) -> Self {
+        AsyncNetworkStream::new(InnerAsyncNetworkStream::Tokio1Unix(stream))
+    }
+}
+
+#[cfg(all(feature = "async-std1", unix))]
+#[cfg_attr(docsrs, doc(cfg(all(feature = "async-std1", unix))))]
+impl From<AsyncStd1UnixStream> for AsyncNetworkStream {
+    /// Wraps a connected Unix domain socket stream
+    fn from(stream: AsyncStd1UnixStream) -> Self {
+        AsyncNetworkStream::new(InnerAsyncNetworkStream::AsyncStd1Unix(stream))
+    }
+}
+
 impl FuturesAsyncRead for AsyncNetworkStream {
     fn poll_read(
         mut self: Pin<&mut Self>,
@@ -421,6 +812,18 @@ impl FuturesAsyncRead for AsyncNetworkStream {
             InnerAsyncNetworkStream::AsyncStd1RustlsTls(ref mut s) => {
                 Pin::new(s).poll_read(cx, buf)
             }
+            #[cfg(all(feature = "tokio1", unix))]
+            InnerAsyncNetworkStream::Tokio1Unix(ref mut s) => {
+                let mut b = Tokio1ReadBuf::new(buf);
+                match Pin::new(s).poll_read(cx, &mut b) {
+                    Poll::Ready(Ok(())) => Poll::Ready(Ok(b.filled().len())),
+                    Poll::Ready(Err(err)) => Poll::Ready(Err(err)),
+                    Poll::Pending => Poll::Pending,
+                }
+            }
+            #[cfg(all(feature = "async-std1", unix))]
+            InnerAsyncNetworkStream::AsyncStd1Unix(ref mut s) => Pin::new(s).poll_read(cx, buf),
+            InnerAsyncNetworkStream::Custom(ref mut s) => Pin::new(s).poll_read(cx, buf),
             InnerAsyncNetworkStream::None => {
                 debug_assert!(false, "InnerAsyncNetworkStream::None must never be built");
                 Poll::Ready(Ok(0))
@@ -452,6 +855,11 @@ impl FuturesAsyncWrite for AsyncNetworkStream {
             InnerAsyncNetworkStream::AsyncStd1RustlsTls(ref mut s) => {
                 Pin::new(s).poll_write(cx, buf)
             }
+            #[cfg(all(feature = "tokio1", unix))]
+            InnerAsyncNetworkStream::Tokio1Unix(ref mut s) => Pin::new(s).poll_write(cx, buf),
+            #[cfg(all(feature = "async-std1", unix))]
+            InnerAsyncNetworkStream::AsyncStd1Unix(ref mut s) => Pin::new(s).poll_write(cx, buf),
+            InnerAsyncNetworkStream::Custom(ref mut s) => Pin::new(s).poll_write(cx, buf),
             InnerAsyncNetworkStream::None => {
                 debug_assert!(false, "InnerAsyncNetworkStream::None must never be built");
                 Poll::Ready(Ok(0))
@@ -473,6 +881,11 @@ impl FuturesAsyncWrite for AsyncNetworkStream {
             InnerAsyncNetworkStream::AsyncStd1NativeTls(ref mut s) => Pin::new(s).poll_flush(cx),
             #[cfg(feature = "async-std1-rustls-tls")]
             InnerAsyncNetworkStream::AsyncStd1RustlsTls(ref mut s) => Pin::new(s).poll_flush(cx),
+            #[cfg(all(feature = "tokio1", unix))]
+            InnerAsyncNetworkStream::Tokio1Unix(ref mut s) => Pin::new(s).poll_flush(cx),
+            #[cfg(all(feature = "async-std1", unix))]
+            InnerAsyncNetworkStream::AsyncStd1Unix(ref mut s) => Pin::new(s).poll_flush(cx),
+            InnerAsyncNetworkStream::Custom(ref mut s) => Pin::new(s).poll_flush(cx),
             InnerAsyncNetworkStream::None => {
                 debug_assert!(false, "InnerAsyncNetworkStream::None must never be built");
                 Poll::Ready(Ok(()))
@@ -494,6 +907,11 @@ impl FuturesAsyncWrite for AsyncNetworkStream {
             InnerAsyncNetworkStream::AsyncStd1NativeTls(ref mut s) => Pin::new(s).poll_close(cx),
             #[cfg(feature = "async-std1-rustls-tls")]
             InnerAsyncNetworkStream::AsyncStd1RustlsTls(ref mut s) => Pin::new(s).poll_close(cx),
+            #[cfg(all(feature = "tokio1", unix))]
+            InnerAsyncNetworkStream::Tokio1Unix(ref mut s) => Pin::new(s).poll_shutdown(cx),
+            #[cfg(all(feature = "async-std1", unix))]
+            InnerAsyncNetworkStream::AsyncStd1Unix(ref mut s) => Pin::new(s).poll_close(cx),
+            InnerAsyncNetworkStream::Custom(ref mut s) => Pin::new(s).poll_close(cx),
             InnerAsyncNetworkStream::None => {
                 debug_assert!(false, "InnerAsyncNetworkStream::None must never be built");
                 Poll::Ready(Ok(()))
@@ -1,25 +1,39 @@
+#[cfg(unix)]
+use std::path::Path;
 use std::{
     fmt::Display,
     io::{self, BufRead, BufReader, Write},
-    net::ToSocketAddrs,
-    time::Duration,
+    mem,
+    net::{Shutdown, ToSocketAddrs},
+    ops::RangeInclusive,
+    time::{Duration, Instant},
 };
 
-use super::{ClientCodec, NetworkStream, TlsParameters};
+#[cfg(feature = "socket2")]
+use socket2::Socket;
+
+use super::{
+    check_argument, check_greeting, closed_after_quit, default_hello_name, greeting_timed_out,
+    mail_options, placeholder_greeting, redacted_command, too_many_recipients, ClientCodec,
+    NetworkStream, ProxyProtocol, TlsParameters, QUIT_TIMEOUT,
+};
 use crate::{
-    address::Envelope,
+    address::{Address, Envelope},
     transport::smtp::{
-        authentication::{Credentials, Mechanism},
+        authentication::{Credentials, LoginExchange, Mechanism},
         commands::*,
         error,
-        error::Error,
-        extension::{ClientId, Extension, MailBodyParameter, MailParameter, ServerInfo},
-        response::{parse_response, Response},
+        error::{Error, Phase},
+        extension::{ClientId, Extension, ServerInfo},
+        lmtp::LmtpResponse,
+        observer::{ConnectionInfo, PhaseTimings},
+        response::{parse_response, EnvelopeVerification, Response, SplitDelivery, Verification},
+        transcript::{Direction, Transcript, TranscriptConfig},
     },
 };
 
 #[cfg(feature = "tracing")]
-use super::escape_crlf;
+use super::{connection_span, escape_crlf, message_span};
 
 macro_rules! try_smtp (
     ($err: expr, $client: ident) => ({
@@ -42,6 +56,26 @@ pub struct SmtpConnection {
     panic: bool,
     /// Information about the server
     server_info: ServerInfo,
+    /// Name sent in EHLO
+    hello_name: ClientId,
+    /// Greeting sent by the server
+    greeting: Response,
+    /// When the last reply of the server was received
+    last_reply: Instant,
+    /// Lines sent and received, when capturing transcripts
+    transcript: Option<Transcript>,
+    /// Index of the relay host of the transport the connection was opened to
+    relay: usize,
+    /// Whether `QUIT` waits for the reply of the server before closing the connection
+    quit_wait: bool,
+    /// Time spent in each phase of the dialogue, until taken by the transport
+    timings: PhaseTimings,
+    /// When the connection was established, before the greeting
+    #[cfg(feature = "tracing")]
+    connected_at: Instant,
+    /// Span of the connection, or of the message being sent
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
 }
 
 impl SmtpConnection {
@@ -49,6 +83,43 @@ impl SmtpConnection {
         &self.server_info
     }
 
+    /// Name the client sent when greeting the server
+    pub fn hello_name(&self) -> &ClientId {
+        &self.hello_name
+    }
+
+    /// Greeting sent by the server when the client connected
+    pub fn greeting(&self) -> &Response {
+        &self.greeting
+    }
+
+    /// Index of the relay host of the transport the connection was opened to
+    pub(crate) fn relay(&self) -> usize {
+        self.relay
+    }
+
+    /// Sets whether `QUIT` waits for the reply of the server, `true` by default
+    ///
+    /// When `false`, [`quit`](#method.quit) and `abort` send `QUIT` and shut the connection
+    /// down immediately, saving a round-trip.
+    pub fn set_quit_wait(&mut self, quit_wait: bool) {
+        self.quit_wait = quit_wait;
+    }
+
+    pub(crate) fn set_relay(&mut self, relay: usize) {
+        self.relay = relay;
+    }
+
+    /// Describes the connection for the observer of the transport
+    pub(crate) fn connection_info(&self) -> ConnectionInfo {
+        ConnectionInfo::new(
+            self.stream.get_ref().peer_addr(),
+            &self.greeting,
+            &self.server_info,
+            self.is_encrypted(),
+        )
+    }
+
     // FIXME add simple connect and rename this one
 
     /// Connects to the configured server
@@ -60,55 +131,257 @@ impl SmtpConnection {
         hello_name: &ClientId,
         tls_parameters: Option<&TlsParameters>,
     ) -> Result<SmtpConnection, Error> {
-        let stream = NetworkStream::connect(server, timeout, tls_parameters)?;
-        let stream = BufReader::new(stream);
-        let mut conn = SmtpConnection {
-            stream,
-            panic: false,
-            server_info: ServerInfo::default(),
-        };
-        conn.set_timeout(timeout).map_err(error::network)?;
-        // TODO log
-        let _response = conn.read_response()?;
+        Self::connect_with(
+            server,
+            timeout,
+            None,
+            Some(hello_name),
+            None,
+            None,
+            tls_parameters,
+            None,
+        )
+    }
 
-        conn.ehlo(hello_name)?;
+    /// Connects to the configured server
+    ///
+    /// Connects from a local port of `source_ports` if set. Sends the PROXY protocol
+    /// header if `proxy_protocol` is set, then EHLO with `hello_name`, or with a name
+    /// derived from the local address of the connection if it's `None`, and parses
+    /// server information. The greeting must be received within `greeting_timeout`,
+    /// defaulting to `timeout`. Records a transcript from the greeting if `transcript`
+    /// is set.
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn connect_with<A: ToSocketAddrs>(
+        server: A,
+        timeout: Option<Duration>,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        source_ports: Option<&RangeInclusive<u16>>,
+        proxy_protocol: Option<&ProxyProtocol>,
+        tls_parameters: Option<&TlsParameters>,
+        transcript: Option<&TranscriptConfig>,
+    ) -> Result<SmtpConnection, Error> {
+        let start = Instant::now();
+        let stream = NetworkStream::connect_with(
+            server,
+            timeout,
+            source_ports,
+            proxy_protocol,
+            tls_parameters,
+        )
+        .map_err(|err| err.with_phase(Phase::Connect))?;
+        let connect_time = start.elapsed();
 
-        // Print server information
+        let mut conn = Self::greet(stream, timeout, greeting_timeout, hello_name, transcript)?;
+        conn.timings.record(Phase::Connect, connect_time);
         #[cfg(feature = "tracing")]
-        tracing::debug!("server {}", conn.server_info);
+        conn.span
+            .record("connect_time", tracing::field::debug(connect_time));
         Ok(conn)
     }
 
-    pub fn send(&mut self, envelope: &Envelope, email: &[u8]) -> Result<Response, Error> {
-        // Mail
-        let mut mail_options = vec![];
-
-        // Internationalization handling
-        //
-        // * 8BITMIME: https://tools.ietf.org/html/rfc6152
-        // * SMTPUTF8: https://tools.ietf.org/html/rfc653
-
-        // Check for non-ascii addresses and use the SMTPUTF8 option if any.
-        if envelope.has_non_ascii_addresses() {
-            if !self.server_info().supports_feature(Extension::SmtpUtfEight) {
-                // don't try to send non-ascii addresses (per RFC)
-                return Err(error::client(
-                    "Envelope contains non-ascii chars but server does not support SMTPUTF8",
-                ));
-            }
-            mail_options.push(MailParameter::SmtpUtfEight);
+    /// Uses an already connected stream, like a [`TcpStream`](std::net::TcpStream)
+    /// obtained from a custom dialer or tunnel
+    ///
+    /// Sends EHLO and parses server information. TCP streams can still be upgraded
+    /// with [`SmtpConnection::starttls`].
+    pub fn from_stream<S: Into<NetworkStream>>(
+        stream: S,
+        timeout: Option<Duration>,
+        hello_name: &ClientId,
+    ) -> Result<SmtpConnection, Error> {
+        Self::greet(stream.into(), timeout, None, Some(hello_name), None)
+    }
+
+    /// Connects to a SMTP server listening on a Unix domain socket
+    ///
+    /// Sends EHLO and parses server information.
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    pub fn connect_unix<P: AsRef<Path>>(
+        path: P,
+        timeout: Option<Duration>,
+        hello_name: &ClientId,
+    ) -> Result<SmtpConnection, Error> {
+        Self::connect_unix_with(path, timeout, None, Some(hello_name), None)
+    }
+
+    /// Connects to a SMTP server listening on a Unix domain socket
+    ///
+    /// Sends EHLO with `hello_name`, or with the default [`ClientId`] if it's `None`.
+    /// The greeting must be received within `greeting_timeout`, defaulting to `timeout`.
+    /// Records a transcript from the greeting if `transcript` is set.
+    #[cfg(unix)]
+    pub(crate) fn connect_unix_with<P: AsRef<Path>>(
+        path: P,
+        timeout: Option<Duration>,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        transcript: Option<&TranscriptConfig>,
+    ) -> Result<SmtpConnection, Error> {
+        let start = Instant::now();
+        let stream =
+            NetworkStream::connect_unix(path).map_err(|err| err.with_phase(Phase::Connect))?;
+        let connect_time = start.elapsed();
+
+        let mut conn = Self::greet(stream, timeout, greeting_timeout, hello_name, transcript)?;
+        conn.timings.record(Phase::Connect, connect_time);
+        Ok(conn)
+    }
+
+    /// Uses a socket connected by the caller, upgrading it to TLS if `tls_parameters`
+    /// are set
+    ///
+    /// Sends EHLO with `hello_name`, or with the default [`ClientId`] if it's `None`.
+    /// The greeting must be received within `greeting_timeout`, defaulting to `timeout`.
+    /// Records a transcript from the greeting if `transcript` is set.
+    #[cfg(feature = "socket2")]
+    pub(crate) fn from_socket_with(
+        socket: Socket,
+        timeout: Option<Duration>,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        tls_parameters: Option<&TlsParameters>,
+        transcript: Option<&TranscriptConfig>,
+    ) -> Result<SmtpConnection, Error> {
+        let start = Instant::now();
+        let mut stream =
+            NetworkStream::from_socket(socket).map_err(|err| err.with_phase(Phase::Connect))?;
+        if let Some(tls_parameters) = tls_parameters {
+            stream
+                .upgrade_tls(tls_parameters)
+                .map_err(|err| err.with_phase(Phase::Connect))?;
         }
+        let connect_time = start.elapsed();
 
-        // Check for non-ascii content in message
-        if !email.is_ascii() {
-            if !self.server_info().supports_feature(Extension::EightBitMime) {
-                return Err(error::client(
-                    "Message contains non-ascii chars but server does not support 8BITMIME",
-                ));
+        let mut conn = Self::greet(stream, timeout, greeting_timeout, hello_name, transcript)?;
+        conn.timings.record(Phase::Connect, connect_time);
+        Ok(conn)
+    }
+
+    /// Connects to a LMTP server ([RFC 2033](https://tools.ietf.org/html/rfc2033))
+    ///
+    /// Sends LHLO and parses server information. Use [`SmtpConnection::send_lmtp`]
+    /// to send emails over the connection.
+    pub fn connect_lmtp<A: ToSocketAddrs>(
+        server: A,
+        timeout: Option<Duration>,
+        hello_name: &ClientId,
+    ) -> Result<SmtpConnection, Error> {
+        let stream = NetworkStream::connect(server, timeout, None)
+            .map_err(|err| err.with_phase(Phase::Connect))?;
+        let mut conn = SmtpConnection::new(stream, timeout, None, Some(hello_name), None)?;
+        conn.lhlo(hello_name)?;
+        Ok(conn)
+    }
+
+    /// Connects to a LMTP server listening on a Unix domain socket
+    ///
+    /// Sends LHLO and parses server information.
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    pub fn connect_lmtp_unix<P: AsRef<Path>>(
+        path: P,
+        timeout: Option<Duration>,
+        hello_name: &ClientId,
+    ) -> Result<SmtpConnection, Error> {
+        let stream =
+            NetworkStream::connect_unix(path).map_err(|err| err.with_phase(Phase::Connect))?;
+        let mut conn = SmtpConnection::new(stream, timeout, None, Some(hello_name), None)?;
+        conn.lhlo(hello_name)?;
+        Ok(conn)
+    }
+
+    /// Reads the server greeting, then sends EHLO and parses server information
+    fn greet(
+        stream: NetworkStream,
+        timeout: Option<Duration>,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        transcript: Option<&TranscriptConfig>,
+    ) -> Result<SmtpConnection, Error> {
+        let mut conn =
+            SmtpConnection::new(stream, timeout, greeting_timeout, hello_name, transcript)?;
+
+        let hello_name = conn.hello_name.clone();
+        conn.ehlo(&hello_name)
+            .map_err(|err| conn.attach_transcript(err))?;
+
+        // Print server information
+        #[cfg(feature = "tracing")]
+        tracing::debug!(parent: &conn.span, "server {}", conn.server_info);
+        Ok(conn)
+    }
+
+    /// Wraps a connected stream, and reads the server greeting
+    ///
+    /// The whole greeting must be received within `greeting_timeout`, or `timeout` if
+    /// it's `None`, so that servers trickling it are detected too.
+    fn new(
+        stream: NetworkStream,
+        timeout: Option<Duration>,
+        greeting_timeout: Option<Duration>,
+        hello_name: Option<&ClientId>,
+        transcript: Option<&TranscriptConfig>,
+    ) -> Result<SmtpConnection, Error> {
+        let hello_name = match hello_name {
+            Some(hello_name) => hello_name.clone(),
+            None => default_hello_name(stream.local_addr()),
+        };
+        #[cfg(feature = "tracing")]
+        let span = connection_span(stream.peer_addr(), stream.is_encrypted(), &hello_name);
+        let mut conn = SmtpConnection {
+            stream: BufReader::new(stream),
+            panic: false,
+            server_info: ServerInfo::default(),
+            hello_name,
+            greeting: placeholder_greeting(),
+            last_reply: Instant::now(),
+            transcript: transcript.map(|config| Transcript::new(*config)),
+            relay: 0,
+            quit_wait: true,
+            timings: PhaseTimings::default(),
+            #[cfg(feature = "tracing")]
+            connected_at: Instant::now(),
+            #[cfg(feature = "tracing")]
+            span,
+        };
+        let start = Instant::now();
+        let greeting = match greeting_timeout.or(timeout) {
+            Some(greeting_timeout) => {
+                let greeting = conn.read_greeting(Instant::now() + greeting_timeout);
+                conn.set_timeout(timeout).map_err(error::network)?;
+                greeting
             }
-            mail_options.push(MailParameter::Body(MailBodyParameter::EightBitMime));
+            None => conn.read_response(),
+        };
+        conn.timings.record(Phase::Greeting, start.elapsed());
+        conn.greeting = greeting
+            .and_then(check_greeting)
+            .map_err(|err| conn.attach_transcript(err.with_phase(Phase::Greeting)))?;
+        Ok(conn)
+    }
+
+    pub fn send(&mut self, envelope: &Envelope, email: &[u8]) -> Result<Response, Error> {
+        #[cfg(feature = "tracing")]
+        let connection_span = {
+            let span = message_span(&self.span, envelope, email);
+            mem::replace(&mut self.span, span)
+        };
+
+        let result = self.send_message(envelope, email);
+
+        #[cfg(feature = "tracing")]
+        {
+            self.span = connection_span;
         }
+        result
+    }
 
+    /// Sends the transaction of `send`
+    fn send_message(&mut self, envelope: &Envelope, email: &[u8]) -> Result<Response, Error> {
+        let mail_options = mail_options(&self.server_info, envelope, email)?;
         try_smtp!(
             self.command(Mail::new(envelope.from().cloned(), mail_options)),
             self
@@ -120,17 +393,276 @@ impl SmtpConnection {
         }
 
         // Data
+        #[cfg(feature = "tracing")]
+        let start = Instant::now();
         try_smtp!(self.command(Data), self);
 
         // Message content
         let result = try_smtp!(self.message(email), self);
+        #[cfg(feature = "tracing")]
+        self.span
+            .record("data_time", tracing::field::debug(start.elapsed()));
         Ok(result)
     }
 
+    /// Sends an email in several transactions, with at most `max_recipients` recipients
+    /// in each
+    ///
+    /// The envelope recipients are split in order, and the same `email` is sent in each
+    /// transaction. When the server replies `452` (too many recipients) to a `RCPT`
+    /// command, the message is sent to the recipients it already accepted, and the
+    /// remaining ones go to the next transaction. A rejected recipient ends its
+    /// transaction, which is reset with `RSET`, and the next transaction starts after it.
+    ///
+    /// If the sender is rejected or the connection breaks, the remaining recipients are
+    /// reported as failed with that error. An error is only returned if no transaction
+    /// could be started.
+    pub fn send_split(
+        &mut self,
+        envelope: &Envelope,
+        email: &[u8],
+        max_recipients: usize,
+    ) -> Result<SplitDelivery, Error> {
+        self.send_split_capped(envelope, email, max_recipients, usize::MAX)
+    }
+
+    /// Sends an email like `send_split`, in at most `max_transactions` transactions
+    ///
+    /// The recipients left once the last transaction is sent are reported as failed.
+    pub(crate) fn send_split_capped(
+        &mut self,
+        envelope: &Envelope,
+        email: &[u8],
+        max_recipients: usize,
+        max_transactions: usize,
+    ) -> Result<SplitDelivery, Error> {
+        #[cfg(feature = "tracing")]
+        let connection_span = {
+            let span = message_span(&self.span, envelope, email);
+            mem::replace(&mut self.span, span)
+        };
+
+        let result =
+            self.send_transactions(envelope, email, max_recipients.max(1), max_transactions);
+
+        #[cfg(feature = "tracing")]
+        {
+            self.span = connection_span;
+        }
+        result
+    }
+
+    /// Sends the transactions of `send_split`
+    fn send_transactions(
+        &mut self,
+        envelope: &Envelope,
+        email: &[u8],
+        max_recipients: usize,
+        max_transactions: usize,
+    ) -> Result<SplitDelivery, Error> {
+        let mail_options = mail_options(&self.server_info, envelope, email)?;
+
+        let mut transactions = Vec::new();
+        let mut remaining = envelope.to();
+        while !remaining.is_empty() {
+            if transactions.len() == max_transactions {
+                let err = error::client(format!(
+                    "the recipients can't be sent in more than {} transactions",
+                    max_transactions
+                ));
+                transactions.push((remaining.to_vec(), Err(err)));
+                break;
+            }
+
+            let mail = self.command(Mail::new(envelope.from().cloned(), mail_options.clone()));
+            if let Err(err) = mail {
+                self.end_transaction(&err);
+                transactions.push((remaining.to_vec(), Err(err)));
+                break;
+            }
+
+            let chunk = &remaining[..remaining.len().min(max_recipients)];
+            let (sent, result) = self.send_chunk(chunk, email);
+            let reusable = match &result {
+                Ok(_) => true,
+                Err(err) => self.end_transaction(err),
+            };
+            // The remaining recipients can't be sent the message on a broken connection
+            let sent = if reusable { sent } else { remaining.len() };
+            transactions.push((remaining[..sent].to_vec(), result));
+            remaining = &remaining[sent..];
+        }
+
+        Ok(SplitDelivery { transactions })
+    }
+
+    /// Sends the recipients and the message of a transaction of `send_split`
+    ///
+    /// Returns the number of recipients the transaction covered, which can be less than
+    /// the size of the chunk.
+    fn send_chunk(&mut self, chunk: &[Address], email: &[u8]) -> (usize, Result<Response, Error>) {
+        let mut accepted = 0;
+        for to_address in chunk {
+            match self.command(Rcpt::new(to_address.clone(), vec![])) {
+                Ok(_) => accepted += 1,
+                // Too many recipients, the next transaction takes the remaining ones
+                Err(err) if accepted > 0 && too_many_recipients(&err) => break,
+                Err(err) => return (accepted + 1, Err(err)),
+            }
+        }
+
+        let result = self.command(Data).and_then(|_| self.message(email));
+        (accepted, result)
+    }
+
+    /// Resets the transaction after `err`, returns false if the connection was aborted
+    fn end_transaction(&mut self, err: &Error) -> bool {
+        if (err.is_transient() || err.is_permanent()) && self.command(Rset).is_ok() {
+            return true;
+        }
+        self.abort();
+        false
+    }
+
+    /// Checks that the server accepts the sender and each recipient of `envelope`,
+    /// without sending any message
+    ///
+    /// Sends the `MAIL` and `RCPT` commands, then resets the transaction with `RSET`.
+    /// `DATA` is never sent, whatever the replies of the server. The recipients are
+    /// checked even if some of them are rejected, and an error is returned if the
+    /// sender is rejected.
+    pub fn verify_envelope(&mut self, envelope: &Envelope) -> Result<EnvelopeVerification, Error> {
+        let mail_options = mail_options(&self.server_info, envelope, &[])?;
+        let sender = match self.command(Mail::new(envelope.from().cloned(), mail_options)) {
+            Ok(response) => response,
+            Err(err) if err.is_transient() || err.is_permanent() => {
+                try_smtp!(self.command(Rset), self);
+                return Err(err);
+            }
+            Err(err) => {
+                self.abort();
+                return Err(err);
+            }
+        };
+
+        let mut recipients = Vec::with_capacity(envelope.to().len());
+        for to_address in envelope.to() {
+            let result = match self.command(Rcpt::new(to_address.clone(), vec![])) {
+                Err(err) if !(err.is_transient() || err.is_permanent()) => {
+                    self.abort();
+                    return Err(err);
+                }
+                result => result,
+            };
+            recipients.push((to_address.clone(), result));
+        }
+
+        try_smtp!(self.command(Rset), self);
+
+        Ok(EnvelopeVerification { sender, recipients })
+    }
+
+    /// Sends an email over a LMTP connection
+    ///
+    /// Unlike SMTP, the server accepts or rejects the message for each recipient: the
+    /// returned response contains the result for every envelope recipient.
+    /// Recipients rejected at `RCPT` time don't receive the message.
+    pub fn send_lmtp(&mut self, envelope: &Envelope, email: &[u8]) -> Result<LmtpResponse, Error> {
+        let mail_options = mail_options(&self.server_info, envelope, email)?;
+        try_smtp!(
+            self.command(Mail::new(envelope.from().cloned(), mail_options)),
+            self
+        );
+
+        let mut results = Vec::with_capacity(envelope.to().len());
+        for to_address in envelope.to() {
+            let result = match self.command(Rcpt::new(to_address.clone(), vec![])) {
+                Err(err) if !(err.is_transient() || err.is_permanent()) => {
+                    self.abort();
+                    return Err(err);
+                }
+                result => result,
+            };
+            results.push((to_address.clone(), result));
+        }
+
+        if results.iter().all(|(_, result)| result.is_err()) {
+            try_smtp!(self.command(Rset), self);
+            return Ok(LmtpResponse {
+                recipients: results,
+            });
+        }
+
+        try_smtp!(self.command(Data), self);
+
+        try_smtp!(
+            self.write_message(email)
+                .map_err(|err| err.with_phase(Phase::Message)),
+            self
+        );
+
+        // One reply per accepted recipient, in the order of the RCPT commands
+        for (_, result) in results.iter_mut().filter(|(_, result)| result.is_ok()) {
+            let response = self
+                .read_response()
+                .map_err(|err| err.with_phase(Phase::MessageReply));
+            *result = match response {
+                Err(err) if !(err.is_transient() || err.is_permanent()) => {
+                    self.abort();
+                    return Err(err);
+                }
+                result => result,
+            };
+        }
+
+        Ok(LmtpResponse {
+            recipients: results,
+        })
+    }
+
+    /// Parameters of the `MAIL` command needed to send the email
     pub fn has_broken(&self) -> bool {
         self.panic
     }
 
+    /// Records the time spent on the handshake in the span of the connection
+    #[cfg(feature = "tracing")]
+    pub(crate) fn record_handshake_time(&self) {
+        self.span.record(
+            "handshake_time",
+            tracing::field::debug(self.connected_at.elapsed()),
+        );
+    }
+
+    /// Starts recording the lines sent and received on this connection
+    ///
+    /// The lines recorded before are dropped. The arguments of `AUTH` commands are
+    /// redacted, as for the transcripts of the transport.
+    pub fn capture_transcript(&mut self, config: TranscriptConfig) {
+        self.transcript = Some(Transcript::new(config));
+    }
+
+    /// Returns the lines recorded since the last call, if capturing transcripts
+    pub fn take_transcript(&mut self) -> Option<Transcript> {
+        self.transcript.as_mut().map(Transcript::take)
+    }
+
+    /// Takes the time spent in each phase of the dialogue since the last call
+    pub(crate) fn take_phase_timings(&mut self) -> PhaseTimings {
+        mem::take(&mut self.timings)
+    }
+
+    /// Attaches the lines recorded since the last call to `err`
+    pub(crate) fn attach_transcript(&mut self, err: Error) -> Error {
+        err.with_transcript(self.take_transcript())
+    }
+
+    /// Time elapsed since the last reply of the server
+    #[cfg(feature = "r2d2")]
+    pub(crate) fn idle_duration(&self) -> Duration {
+        self.last_reply.elapsed()
+    }
+
     pub fn can_starttls(&self) -> bool {
         !self.is_encrypted() && self.server_info.supports_feature(Extension::StartTls)
     }
@@ -145,9 +677,19 @@ impl SmtpConnection {
             #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
             {
                 try_smtp!(self.command(Starttls), self);
-                try_smtp!(self.stream.get_mut().upgrade_tls(tls_parameters), self);
+                let start = Instant::now();
+                let upgraded = self.stream.get_mut().upgrade_tls(tls_parameters);
+                self.timings.record(Phase::TlsHandshake, start.elapsed());
+                if let Err(err) = upgraded {
+                    // The stream is lost when the handshake fails, don't try to send QUIT
+                    self.panic = true;
+                    return Err(err);
+                }
                 #[cfg(feature = "tracing")]
-                tracing::debug!("connection encrypted");
+                {
+                    self.span.record("tls", true);
+                    tracing::debug!(parent: &self.span, "connection encrypted");
+                }
                 // Send EHLO again
                 try_smtp!(self.ehlo(hello_name), self);
                 Ok(())
@@ -161,22 +703,87 @@ impl SmtpConnection {
         }
     }
 
+    /// Forwards the attributes of the original client with `XCLIENT`
+    ///
+    /// The attributes the server doesn't advertise are omitted, and nothing is sent if
+    /// none is left. As required by the extension, EHLO is sent again afterwards
+    /// to refresh the server info. Must not be called during a mail transaction.
+    pub fn xclient(&mut self, xclient: &XClient) -> Result<(), Error> {
+        let xclient = xclient.supported_by(&self.server_info);
+        if xclient.is_empty() {
+            return Ok(());
+        }
+
+        try_smtp!(self.command(xclient), self);
+        let hello_name = self.hello_name.clone();
+        self.ehlo(&hello_name)
+    }
+
     /// Send EHLO and update server info
     fn ehlo(&mut self, hello_name: &ClientId) -> Result<(), Error> {
         let ehlo_response = try_smtp!(self.command(Ehlo::new(hello_name.clone())), self);
         self.server_info = try_smtp!(ServerInfo::from_response(&ehlo_response), self);
+        self.hello_name = hello_name.clone();
+        Ok(())
+    }
+
+    /// Send LHLO and update server info
+    fn lhlo(&mut self, hello_name: &ClientId) -> Result<(), Error> {
+        let lhlo_response = try_smtp!(self.command(Lhlo::new(hello_name.clone())), self);
+        self.server_info = try_smtp!(ServerInfo::from_response(&lhlo_response), self);
+        self.hello_name = hello_name.clone();
         Ok(())
     }
 
+    /// Sends `QUIT` and closes the connection gracefully
+    ///
+    /// A server closing the connection without replying is considered to have accepted
+    /// the command. If the connection doesn't [wait](#method.set_quit_wait) for the
+    /// reply, it's shut down right after sending the command, and the reply is assumed.
     pub fn quit(&mut self) -> Result<Response, Error> {
-        Ok(try_smtp!(self.command(Quit), self))
+        let command = Quit.to_string();
+        #[cfg(feature = "tracing")]
+        tracing::debug!(parent: &self.span, ">> {}", command);
+        if let Some(transcript) = &mut self.transcript {
+            transcript.record_command(&command, &command);
+        }
+
+        if !self.quit_wait {
+            let result = self
+                .write(command.as_bytes())
+                .map(|()| closed_after_quit())
+                .map_err(|err| err.with_command(&command).with_phase(Phase::Command));
+            if result.is_err() {
+                self.panic = true;
+            }
+
+            let _ = self.stream.get_mut().shutdown(Shutdown::Both);
+            return result;
+        }
+
+        let _ = self.stream.get_mut().set_read_timeout(Some(QUIT_TIMEOUT));
+        let result = self
+            .write(command.as_bytes())
+            .and_then(|()| self.read_response_or_eof())
+            .map(|response| response.unwrap_or_else(closed_after_quit))
+            .map_err(|err| err.with_command(&command).with_phase(Phase::Command));
+        if result.is_err() {
+            self.panic = true;
+        }
+
+        let _ = self.stream.get_mut().close(QUIT_TIMEOUT);
+        result
     }
 
     pub fn abort(&mut self) {
         // Only try to quit if we are not already broken
         if !self.panic {
             self.panic = true;
-            let _ = self.command(Quit);
+            if self.quit_wait {
+                let _ = self.command(Quit);
+            } else {
+                let _ = self.write(Quit.to_string().as_bytes());
+            }
         }
     }
 
@@ -196,11 +803,31 @@ impl SmtpConnection {
         self.stream.get_mut().set_write_timeout(duration)
     }
 
+    /// Checks if the connection is still usable, without a round-trip to the server
+    ///
+    /// Cheaper than [`SmtpConnection::test_connected`], but only detects the connections
+    /// which are broken or were already closed by the server.
+    pub fn is_idle_healthy(&self) -> bool {
+        !self.panic && !self.stream.get_ref().is_closed_by_peer().unwrap_or(true)
+    }
+
     /// Checks if the server is connected using the NOOP SMTP command
     pub fn test_connected(&mut self) -> bool {
         self.command(Noop).is_ok()
     }
 
+    /// Asks the server to verify a user name or mailbox using the VRFY SMTP command
+    pub fn vrfy(&mut self, address: &str) -> Result<Verification, Error> {
+        check_argument(address)?;
+        Verification::from_result(self.command(Vrfy::new(address.to_string())))
+    }
+
+    /// Asks the server to expand a mailing list using the EXPN SMTP command
+    pub fn expn(&mut self, list: &str) -> Result<Verification, Error> {
+        check_argument(list)?;
+        Verification::from_result(self.command(Expn::new(list.to_string())))
+    }
+
     /// Sends an AUTH command with the given mechanism, and handles challenge if needed
     pub fn auth(
         &mut self,
@@ -214,16 +841,21 @@ impl SmtpConnection {
 
         // Limit challenges to avoid blocking
         let mut challenges = 10;
-        let mut response = self.command(Auth::new(mechanism, credentials.clone(), None)?)?;
+        let mut response =
+            self.command(Auth::new(mechanism.clone(), credentials.clone(), None)?)?;
 
+        let mut login = LoginExchange::default();
         while challenges > 0 && response.has_code(334) {
             challenges -= 1;
+            let auth = match mechanism {
+                Mechanism::Login => {
+                    Auth::new_from_login_response(credentials.clone(), &response, &mut login)
+                }
+                _ => Auth::new_from_response(mechanism.clone(), credentials.clone(), &response),
+            };
+            let auth_response = try_smtp!(auth, self).to_string();
             response = try_smtp!(
-                self.command(Auth::new_from_response(
-                    mechanism,
-                    credentials.clone(),
-                    &response,
-                )?),
+                self.send_command(&auth_response, &format!("AUTH {}", mechanism)),
                 self
             );
         }
@@ -236,19 +868,57 @@ impl SmtpConnection {
     }
 
     /// Sends the message content
+    ///
+    /// Errors are recorded as happening during the `DATA` command.
     pub fn message(&mut self, message: &[u8]) -> Result<Response, Error> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(parent: &self.span, ">> message data ({} bytes)", message.len());
+        if let Some(transcript) = &mut self.transcript {
+            transcript.record_data(message);
+        }
+        let start = Instant::now();
+        self.write_message(message)
+            .map_err(|err| err.with_phase(Phase::Message))
+            .and_then(|()| {
+                self.timings.record(Phase::Message, start.elapsed());
+                let start = Instant::now();
+                let response = self.read_response();
+                self.timings.record(Phase::MessageReply, start.elapsed());
+                response.map_err(|err| err.with_phase(Phase::MessageReply))
+            })
+            .map_err(|err| err.with_command("DATA"))
+    }
+
+    /// Writes the message content, followed by the end of data marker
+    fn write_message(&mut self, message: &[u8]) -> Result<(), Error> {
         let mut out_buf: Vec<u8> = vec![];
         let mut codec = ClientCodec::new();
         codec.encode(message, &mut out_buf);
         self.write(out_buf.as_slice())?;
-        self.write(b"\r\n.\r\n")?;
-        self.read_response()
+        self.write(b"\r\n.\r\n")
     }
 
     /// Sends an SMTP command
+    ///
+    /// Errors record the command, with the arguments of `AUTH` commands redacted.
     pub fn command<C: Display>(&mut self, command: C) -> Result<Response, Error> {
-        self.write(command.to_string().as_bytes())?;
-        self.read_response()
+        let command = command.to_string();
+        self.send_command(&command, redacted_command(&command))
+    }
+
+    /// Sends a raw SMTP command, logged and recorded in errors as `redacted`
+    fn send_command(&mut self, command: &str, redacted: &str) -> Result<Response, Error> {
+        #[cfg(feature = "tracing")]
+        tracing::debug!(parent: &self.span, ">> {}", redacted);
+        if let Some(transcript) = &mut self.transcript {
+            transcript.record_command(command, redacted);
+        }
+        let start = Instant::now();
+        let result = self
+            .write(command.as_bytes())
+            .and_then(|()| self.read_response());
+        self.timings.record(Phase::Command, start.elapsed());
+        result.map_err(|err| err.with_command(redacted).with_phase(Phase::Command))
     }
 
     /// Writes a string to the server
@@ -258,37 +928,118 @@ impl SmtpConnection {
             .write_all(string)
             .map_err(error::network)?;
         self.stream.get_mut().flush().map_err(error::network)?;
-
-        #[cfg(feature = "tracing")]
-        tracing::debug!("Wrote: {}", escape_crlf(&String::from_utf8_lossy(string)));
         Ok(())
     }
 
     /// Gets the SMTP response
     pub fn read_response(&mut self) -> Result<Response, Error> {
+        self.read_response_or_eof()?
+            .ok_or_else(|| error::response("incomplete response"))
+    }
+
+    /// Gets the SMTP response, `None` if the server closed the connection before sending
+    /// anything
+    fn read_response_or_eof(&mut self) -> Result<Option<Response>, Error> {
         let mut buffer = String::with_capacity(100);
 
         while self.stream.read_line(&mut buffer).map_err(error::network)? > 0 {
-            #[cfg(feature = "tracing")]
-            tracing::debug!("<< {}", escape_crlf(&buffer));
-            match parse_response(&buffer) {
-                Ok((_remaining, response)) => {
-                    return if response.is_positive() {
-                        Ok(response)
-                    } else {
-                        Err(error::code(response.code()))
-                    };
-                }
-                Err(nom::Err::Failure(e)) => {
-                    return Err(error::response(e.to_string()));
-                }
-                Err(nom::Err::Incomplete(_)) => { /* read more */ }
-                Err(nom::Err::Error(e)) => {
-                    return Err(error::response(e.to_string()));
-                }
+            if let Some(response) = self.parse_response(&buffer) {
+                return response.map(Some);
+            }
+        }
+
+        if buffer.is_empty() {
+            Ok(None)
+        } else {
+            Err(error::response("incomplete response"))
+        }
+    }
+
+    /// Gets the whole greeting of the server before `deadline`, or a timeout error
+    fn read_greeting(&mut self, deadline: Instant) -> Result<Response, Error> {
+        let mut buffer = String::with_capacity(100);
+
+        while self.read_greeting_line(&mut buffer, deadline)? > 0 {
+            if let Some(response) = self.parse_response(&buffer) {
+                return response;
             }
         }
 
         Err(error::response("incomplete response"))
     }
+
+    /// Reads a line, adjusting the read timeout to give up at `deadline` even when the
+    /// server keeps sending bytes
+    fn read_greeting_line(
+        &mut self,
+        buffer: &mut String,
+        deadline: Instant,
+    ) -> Result<usize, Error> {
+        let mut line = Vec::new();
+        loop {
+            let remaining = deadline
+                .checked_duration_since(Instant::now())
+                .filter(|remaining| *remaining > Duration::from_secs(0))
+                .ok_or_else(greeting_timed_out)?;
+            self.stream
+                .get_mut()
+                .set_read_timeout(Some(remaining))
+                .map_err(error::network)?;
+
+            let available = match self.stream.fill_buf() {
+                Ok(available) => available,
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock
+                    ) =>
+                {
+                    return Err(greeting_timed_out())
+                }
+                Err(err) => return Err(error::network(err)),
+            };
+            if available.is_empty() {
+                break;
+            }
+            let (used, done) = match available.iter().position(|&b| b == b'\n') {
+                Some(idx) => (idx + 1, true),
+                None => (available.len(), false),
+            };
+            line.extend_from_slice(&available[..used]);
+            self.stream.consume(used);
+            if done {
+                break;
+            }
+        }
+
+        let line = String::from_utf8(line).map_err(error::network)?;
+        buffer.push_str(&line);
+        Ok(line.len())
+    }
+
+    /// Parses the lines read so far, returning `None` if the response is incomplete
+    fn parse_response(&mut self, buffer: &str) -> Option<Result<Response, Error>> {
+        let parsed = parse_response(buffer);
+        if !matches!(parsed, Err(nom::Err::Incomplete(_))) {
+            if let Some(transcript) = &mut self.transcript {
+                transcript.record(Direction::Server, buffer);
+            }
+        }
+        match parsed {
+            Ok((_remaining, response)) => {
+                #[cfg(feature = "tracing")]
+                tracing::debug!(parent: &self.span, "<< {}", escape_crlf(buffer));
+                self.last_reply = Instant::now();
+                Some(if response.is_positive() {
+                    Ok(response)
+                } else {
+                    Err(error::reply(response))
+                })
+            }
+            Err(nom::Err::Failure(e)) | Err(nom::Err::Error(e)) => {
+                Some(Err(error::response(e.to_string())))
+            }
+            Err(nom::Err::Incomplete(_)) => None,
+        }
+    }
 }
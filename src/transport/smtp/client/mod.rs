@@ -26,18 +26,31 @@
 
 #[cfg(feature = "serde")]
 use std::fmt::Debug;
+use std::{io, net::SocketAddr, time::Duration};
 
-#[cfg(any(feature = "tokio1", feature = "async-std1"))]
-pub(crate) use self::async_connection::AsyncSmtpConnection;
-#[cfg(any(feature = "tokio1", feature = "async-std1"))]
-pub(crate) use self::async_net::AsyncNetworkStream;
-use self::net::NetworkStream;
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+pub(super) use self::tls::tls_error_kind;
 #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
 pub(super) use self::tls::InnerTlsParameters;
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "native-tls", feature = "rustls-tls"))))]
+pub use self::tls::TlsErrorKind;
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "tokio1", feature = "async-std1"))))]
+pub use self::{async_connection::AsyncSmtpConnection, async_net::AsyncNetworkStream};
 pub use self::{
     connection::SmtpConnection,
+    net::NetworkStream,
+    proxy::{ProxyProtocol, ProxyProtocolVersion},
     tls::{Certificate, Tls, TlsParameters, TlsParametersBuilder},
 };
+use super::{
+    error,
+    extension::{ClientId, Extension, MailBodyParameter, MailParameter, ServerInfo},
+    response::{Category, Code, Detail, Response, Severity},
+    Error,
+};
+use crate::address::Envelope;
 
 #[cfg(any(feature = "tokio1", feature = "async-std1"))]
 mod async_connection;
@@ -45,8 +58,12 @@ mod async_connection;
 mod async_net;
 mod connection;
 mod net;
+mod proxy;
 mod tls;
 
+/// Time to wait for the reply to `QUIT`, then for the server to close the connection
+const QUIT_TIMEOUT: Duration = Duration::from_secs(5);
+
 /// The codec used for transparency
 #[derive(Default, Clone, Copy, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -102,6 +119,207 @@ impl ClientCodec {
     }
 }
 
+/// Name sent in `EHLO` when none is configured, derived from the local address of the connection
+fn default_hello_name(local_addr: io::Result<SocketAddr>) -> ClientId {
+    local_addr.map_or_else(
+        |_| ClientId::default(),
+        |addr| ClientId::from_local_addr(addr.ip()),
+    )
+}
+
+/// Greeting of a connection until the one of the server is read
+fn placeholder_greeting() -> Response {
+    Response::new(
+        Code::new(
+            Severity::PositiveCompletion,
+            Category::Connections,
+            Detail::Zero,
+        ),
+        Vec::new(),
+    )
+}
+
+/// Reply to `QUIT` assumed when the server closes the connection without sending one
+fn closed_after_quit() -> Response {
+    Response::new(
+        Code::new(
+            Severity::PositiveCompletion,
+            Category::Connections,
+            Detail::One,
+        ),
+        Vec::new(),
+    )
+}
+
+/// Error of a server not sending its whole greeting in time
+fn greeting_timed_out() -> Error {
+    error::connection(io::Error::new(
+        io::ErrorKind::TimedOut,
+        "timed out waiting for server greeting",
+    ))
+}
+
+/// Checks that the greeting of the server is a `220` reply
+///
+/// Negative replies are already errors, other positive replies are unexpected.
+fn check_greeting(greeting: Response) -> Result<Response, Error> {
+    if greeting.has_code(220) {
+        Ok(greeting)
+    } else {
+        let text = greeting.message().collect::<Vec<&str>>().join(" ");
+        Err(error::response(format!(
+            "unexpected server greeting: {} {}",
+            greeting.code(),
+            text
+        )))
+    }
+}
+
+/// Tells whether `err` is the `452` reply of a server refusing more recipients in the
+/// transaction
+fn too_many_recipients(err: &Error) -> bool {
+    err.response()
+        .map_or(false, |response| response.has_code(452))
+}
+
+/// Parameters of the `MAIL FROM` command for sending `email` to a server
+///
+/// * SMTPUTF8 ([RFC 6531](https://tools.ietf.org/html/rfc6531)) is required for
+///   non-ascii addresses
+/// * `BODY=8BITMIME` ([RFC 6152](https://tools.ietf.org/html/rfc6152)) is used for
+///   messages with 8-bit content, and `BODY=7BIT` for the other ones when the server
+///   advertises 8BITMIME. Servers without it can only receive 7-bit content.
+fn mail_options(
+    server_info: &ServerInfo,
+    envelope: &Envelope,
+    email: &[u8],
+) -> Result<Vec<MailParameter>, Error> {
+    body_mail_options(server_info, envelope, !email.is_ascii())
+}
+
+/// Parameters of the `MAIL FROM` command for sending content which is 8-bit if `eight_bit`
+///
+/// See [`mail_options`].
+fn body_mail_options(
+    server_info: &ServerInfo,
+    envelope: &Envelope,
+    eight_bit: bool,
+) -> Result<Vec<MailParameter>, Error> {
+    let mut mail_options = vec![];
+
+    // Check for non-ascii addresses and use the SMTPUTF8 option if any.
+    if envelope.has_non_ascii_addresses() {
+        if !server_info.supports_feature(Extension::SmtpUtfEight) {
+            // don't try to send non-ascii addresses (per RFC)
+            return Err(error::client(
+                "Envelope contains non-ascii chars but server does not support SMTPUTF8",
+            ));
+        }
+        mail_options.push(MailParameter::SmtpUtfEight);
+    }
+
+    // Check for non-ascii content in message
+    if server_info.supports_feature(Extension::EightBitMime) {
+        mail_options.push(MailParameter::Body(if eight_bit {
+            MailBodyParameter::EightBitMime
+        } else {
+            MailBodyParameter::SevenBit
+        }));
+    } else if eight_bit {
+        return Err(eight_bit_unsupported());
+    }
+
+    Ok(mail_options)
+}
+
+/// Error for 8-bit content sent to a server which doesn't support 8BITMIME
+fn eight_bit_unsupported() -> Error {
+    error::client(
+        "Message contains non-ascii chars but server does not support 8BITMIME, \
+         use a 7-bit Content-Transfer-Encoding like quoted-printable or base64",
+    )
+}
+
+/// Checks that a command argument can't be used to inject other commands
+fn check_argument(argument: &str) -> Result<(), Error> {
+    if argument.contains(&['\r', '\n'][..]) {
+        Err(error::client("Command argument contains line breaks"))
+    } else {
+        Ok(())
+    }
+}
+
+/// Returns the command to record in errors, without the line ending
+///
+/// Only the mechanism of `AUTH` commands is kept, the rest may contain credentials.
+fn redacted_command(command: &str) -> &str {
+    let command = command.trim_end_matches("\r\n");
+    match command
+        .strip_prefix("AUTH ")
+        .and_then(|args| args.find(' '))
+    {
+        Some(idx) => &command[.."AUTH ".len() + idx],
+        None => command,
+    }
+}
+
+/// Span covering the life of a connection
+///
+/// The timings are recorded once known: `connect_time` for establishing the
+/// connection, and `handshake_time` for the greeting, `EHLO`, `STARTTLS` and `AUTH`.
+#[cfg(feature = "tracing")]
+fn connection_span(
+    peer_addr: io::Result<SocketAddr>,
+    encrypted: bool,
+    hello_name: &ClientId,
+) -> tracing::Span {
+    tracing::debug_span!(
+        "smtp_connection",
+        peer_addr = %peer_addr.map_or_else(|_| "unknown".to_string(), |addr| addr.to_string()),
+        tls = encrypted,
+        client_id = %hello_name,
+        connect_time = tracing::field::Empty,
+        handshake_time = tracing::field::Empty,
+    )
+}
+
+/// Span covering the sending of a message, child of the span of the connection
+///
+/// `data_time` is recorded once the server replied to the message content.
+#[cfg(feature = "tracing")]
+fn message_span(connection: &tracing::Span, envelope: &Envelope, email: &[u8]) -> tracing::Span {
+    let span = tracing::debug_span!(
+        parent: connection,
+        "smtp_message",
+        message_id = tracing::field::Empty,
+        recipients = envelope.to().len(),
+        size = email.len(),
+        data_time = tracing::field::Empty,
+    );
+    if let Some(message_id) = message_id(email) {
+        span.record("message_id", message_id);
+    }
+    span
+}
+
+/// Finds the value of the `Message-ID` header of a formatted email
+#[cfg(feature = "tracing")]
+fn message_id(email: &[u8]) -> Option<&str> {
+    email
+        .split(|&b| b == b'\n')
+        .map(|line| std::str::from_utf8(line).map(|line| line.trim_end_matches('\r')))
+        .take_while(|line| line.map_or(true, |line| !line.is_empty()))
+        .filter_map(Result::ok)
+        .find_map(|line| {
+            let (name, value) = line.split_at(line.find(':')?);
+            if name.eq_ignore_ascii_case("Message-ID") {
+                Some(value[1..].trim())
+            } else {
+                None
+            }
+        })
+}
+
 /// Returns the string replacing all the CRLF with "\<CRLF\>"
 /// Used for debug displays
 #[cfg(feature = "tracing")]
@@ -134,6 +352,30 @@ mod test {
         );
     }
 
+    #[test]
+    fn test_redacted_command() {
+        assert_eq!(
+            redacted_command("RCPT TO:<hei@domain.tld>\r\n"),
+            "RCPT TO:<hei@domain.tld>"
+        );
+        assert_eq!(redacted_command("AUTH LOGIN\r\n"), "AUTH LOGIN");
+        assert_eq!(
+            redacted_command("AUTH PLAIN AHVzZXIAcGFzcw==\r\n"),
+            "AUTH PLAIN"
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "tracing")]
+    fn test_message_id() {
+        assert_eq!(
+            message_id(b"From: a@b.c\r\nmessage-id: <1234@b.c> \r\n\r\nMessage-ID: <body>"),
+            Some("<1234@b.c>")
+        );
+        assert_eq!(message_id(b"From: a@b.c\r\n\r\nMessage-ID: <body>"), None);
+        assert_eq!(message_id(b"Message-IDs: <1234@b.c>\r\n\r\n"), None);
+    }
+
     #[test]
     #[cfg(feature = "log")]
     fn test_escape_crlf() {
@@ -144,4 +386,42 @@ mod test {
             "EHLO my_name<CRLF>SIZE 42<CRLF>"
         );
     }
+
+    fn server_info(ehlo_response: &str) -> ServerInfo {
+        ServerInfo::from_response(&ehlo_response.parse().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_mail_options_body() {
+        let envelope = Envelope::new(
+            Some("nobody@domain.tld".parse().unwrap()),
+            vec!["hei@domain.tld".parse().unwrap()],
+        )
+        .unwrap();
+        let eight_bit_mime = server_info("250-server\r\n250 8BITMIME\r\n");
+        let seven_bit = server_info("250 server\r\n");
+
+        assert_eq!(
+            mail_options(&eight_bit_mime, &envelope, b"Be happy!").unwrap(),
+            vec![MailParameter::Body(MailBodyParameter::SevenBit)]
+        );
+        assert_eq!(
+            mail_options(
+                &eight_bit_mime,
+                &envelope,
+                "Happy new year, Каи!".as_bytes()
+            )
+            .unwrap(),
+            vec![MailParameter::Body(MailBodyParameter::EightBitMime)]
+        );
+        assert_eq!(
+            mail_options(&seven_bit, &envelope, b"Be happy!").unwrap(),
+            vec![]
+        );
+        assert!(
+            mail_options(&seven_bit, &envelope, "Happy new year, Каи!".as_bytes())
+                .unwrap_err()
+                .is_client()
+        );
+    }
 }
@@ -1,19 +1,26 @@
+#[cfg(any(feature = "socket2", feature = "tokio1"))]
+use std::net::Ipv6Addr;
 use std::{
     io::{self, Read, Write},
     mem,
     net::{Ipv4Addr, Shutdown, SocketAddr, SocketAddrV4, TcpStream, ToSocketAddrs},
-    time::Duration,
+    ops::RangeInclusive,
+    time::{Duration, Instant},
 };
+#[cfg(unix)]
+use std::{os::unix::net::UnixStream, path::Path};
 
 #[cfg(feature = "native-tls")]
 use native_tls::TlsStream;
 
 #[cfg(feature = "rustls-tls")]
-use rustls::{ClientSession, StreamOwned};
+use rustls::{ClientSession, Session, StreamOwned};
+#[cfg(feature = "socket2")]
+use socket2::{Domain, Protocol, Socket, Type};
 
 #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
 use super::InnerTlsParameters;
-use super::TlsParameters;
+use super::{ProxyProtocol, TlsParameters};
 use crate::transport::smtp::{error, Error};
 
 /// A network stream
@@ -34,6 +41,9 @@ enum InnerNetworkStream {
     /// Encrypted TCP stream
     #[cfg(feature = "rustls-tls")]
     RustlsTls(StreamOwned<ClientSession, TcpStream>),
+    /// Unix domain socket
+    #[cfg(unix)]
+    Unix(UnixStream),
     /// Can't be built
     None,
 }
@@ -55,6 +65,34 @@ impl NetworkStream {
             InnerNetworkStream::NativeTls(ref s) => s.get_ref().peer_addr(),
             #[cfg(feature = "rustls-tls")]
             InnerNetworkStream::RustlsTls(ref s) => s.get_ref().peer_addr(),
+            #[cfg(unix)]
+            InnerNetworkStream::Unix(_) => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Unix sockets don't have an IP address",
+            )),
+            InnerNetworkStream::None => {
+                debug_assert!(false, "InnerNetworkStream::None must never be built");
+                Ok(SocketAddr::V4(SocketAddrV4::new(
+                    Ipv4Addr::new(127, 0, 0, 1),
+                    80,
+                )))
+            }
+        }
+    }
+
+    /// Returns the local address of the connection
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        match self.inner {
+            InnerNetworkStream::Tcp(ref s) => s.local_addr(),
+            #[cfg(feature = "native-tls")]
+            InnerNetworkStream::NativeTls(ref s) => s.get_ref().local_addr(),
+            #[cfg(feature = "rustls-tls")]
+            InnerNetworkStream::RustlsTls(ref s) => s.get_ref().local_addr(),
+            #[cfg(unix)]
+            InnerNetworkStream::Unix(_) => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "Unix sockets don't have an IP address",
+            )),
             InnerNetworkStream::None => {
                 debug_assert!(false, "InnerNetworkStream::None must never be built");
                 Ok(SocketAddr::V4(SocketAddrV4::new(
@@ -73,6 +111,8 @@ impl NetworkStream {
             InnerNetworkStream::NativeTls(ref s) => s.get_ref().shutdown(how),
             #[cfg(feature = "rustls-tls")]
             InnerNetworkStream::RustlsTls(ref s) => s.get_ref().shutdown(how),
+            #[cfg(unix)]
+            InnerNetworkStream::Unix(ref s) => s.shutdown(how),
             InnerNetworkStream::None => {
                 debug_assert!(false, "InnerNetworkStream::None must never be built");
                 Ok(())
@@ -80,21 +120,69 @@ impl NetworkStream {
         }
     }
 
+    /// Closes the connection gracefully, once `QUIT` was answered
+    ///
+    /// Ends the TLS session with a `close_notify` alert, shuts down the writing half of
+    /// the connection, then reads what the server still sends until it closes the
+    /// connection, for at most `timeout`.
+    pub fn close(&mut self, timeout: Duration) -> io::Result<()> {
+        match self.inner {
+            #[cfg(feature = "native-tls")]
+            InnerNetworkStream::NativeTls(ref mut s) => s.shutdown()?,
+            #[cfg(feature = "rustls-tls")]
+            InnerNetworkStream::RustlsTls(ref mut s) => {
+                s.sess.send_close_notify();
+                s.flush()?;
+            }
+            _ => {}
+        }
+        self.shutdown(Shutdown::Write)?;
+
+        let deadline = Instant::now() + timeout;
+        let mut buf = [0; 1024];
+        while let Some(remaining) = deadline.checked_duration_since(Instant::now()) {
+            if remaining == Duration::from_secs(0) {
+                break;
+            }
+            self.set_read_timeout(Some(remaining))?;
+            match self.read(&mut buf) {
+                Ok(0) => break,
+                Ok(_) => {}
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(())
+    }
+
     pub fn connect<T: ToSocketAddrs>(
         server: T,
         timeout: Option<Duration>,
         tls_parameters: Option<&TlsParameters>,
     ) -> Result<NetworkStream, Error> {
-        fn try_connect_timeout<T: ToSocketAddrs>(
+        Self::connect_with(server, timeout, None, None, tls_parameters)
+    }
+
+    /// Connects to `server` from a local port of `source_ports` if set, sending the
+    /// PROXY protocol header first if `proxy_protocol` is set, then upgrades the
+    /// connection to TLS if `tls_parameters` are set
+    pub(crate) fn connect_with<T: ToSocketAddrs>(
+        server: T,
+        timeout: Option<Duration>,
+        source_ports: Option<&RangeInclusive<u16>>,
+        proxy_protocol: Option<&ProxyProtocol>,
+        tls_parameters: Option<&TlsParameters>,
+    ) -> Result<NetworkStream, Error> {
+        fn try_connect<T: ToSocketAddrs>(
             server: T,
-            timeout: Duration,
+            connect: impl Fn(&SocketAddr) -> io::Result<TcpStream>,
         ) -> Result<TcpStream, Error> {
             let addrs = server.to_socket_addrs().map_err(error::connection)?;
 
             let mut last_err = None;
 
             for addr in addrs {
-                match TcpStream::connect_timeout(&addr, timeout) {
+                match connect(&addr) {
                     Ok(stream) => return Ok(stream),
                     Err(err) => last_err = Some(err),
                 }
@@ -106,18 +194,55 @@ impl NetworkStream {
             })
         }
 
-        let tcp_stream = match timeout {
-            Some(t) => try_connect_timeout(server, t)?,
-            None => TcpStream::connect(server).map_err(error::connection)?,
+        let mut tcp_stream = match (source_ports, timeout) {
+            #[cfg(feature = "socket2")]
+            (Some(ports), _) => try_connect(server, |addr| connect_from(addr, ports, timeout))?,
+            (_, Some(t)) => try_connect(server, |addr| TcpStream::connect_timeout(addr, t))?,
+            (_, None) => TcpStream::connect(server).map_err(error::connection)?,
         };
 
-        let mut stream = NetworkStream::new(InnerNetworkStream::Tcp(tcp_stream));
+        if let Some(proxy_protocol) = proxy_protocol {
+            let header = proxy_protocol.header(
+                tcp_stream.local_addr().map_err(error::network)?,
+                tcp_stream.peer_addr().map_err(error::network)?,
+            );
+            tcp_stream.write_all(&header).map_err(error::network)?;
+        }
+
+        let mut stream = NetworkStream::from(tcp_stream);
         if let Some(tls_parameters) = tls_parameters {
             stream.upgrade_tls(tls_parameters)?;
         }
         Ok(stream)
     }
 
+    /// Connects to a Unix domain socket
+    #[cfg(unix)]
+    pub fn connect_unix<P: AsRef<Path>>(path: P) -> Result<NetworkStream, Error> {
+        let stream = UnixStream::connect(path).map_err(error::connection)?;
+        Ok(NetworkStream::from(stream))
+    }
+
+    /// Uses a socket connected by the caller, like one received through socket activation
+    ///
+    /// The socket must be a connected TCP stream, or a Unix domain socket stream on Unix.
+    /// It's switched to blocking mode. TCP streams can still be upgraded to TLS.
+    #[cfg(feature = "socket2")]
+    pub fn from_socket(socket: Socket) -> Result<NetworkStream, Error> {
+        if socket.r#type().map_err(error::connection)? != Type::STREAM {
+            return Err(error::connection("the socket isn't a stream socket"));
+        }
+        let domain = socket.local_addr().map_err(error::connection)?.domain();
+        socket.set_nonblocking(false).map_err(error::connection)?;
+
+        match domain {
+            Domain::IPV4 | Domain::IPV6 => Ok(NetworkStream::from(TcpStream::from(socket))),
+            #[cfg(unix)]
+            Domain::UNIX => Ok(NetworkStream::from(UnixStream::from(socket))),
+            _ => Err(error::connection("unsupported socket domain")),
+        }
+    }
+
     pub fn upgrade_tls(&mut self, tls_parameters: &TlsParameters) -> Result<(), Error> {
         match &self.inner {
             #[cfg(not(any(feature = "native-tls", feature = "rustls-tls")))]
@@ -138,6 +263,11 @@ impl NetworkStream {
                 self.inner = Self::upgrade_tls_impl(tcp_stream, tls_parameters)?;
                 Ok(())
             }
+            #[cfg(unix)]
+            InnerNetworkStream::Unix(_) => {
+                let _ = tls_parameters;
+                Err(error::client("TLS isn't supported on Unix sockets"))
+            }
             _ => Ok(()),
         }
     }
@@ -152,7 +282,7 @@ impl NetworkStream {
             InnerTlsParameters::NativeTls(connector) => {
                 let stream = connector
                     .connect(tls_parameters.domain(), tcp_stream)
-                    .map_err(error::connection)?;
+                    .map_err(error::tls_handshake)?;
                 InnerNetworkStream::NativeTls(stream)
             }
             #[cfg(feature = "rustls-tls")]
@@ -161,7 +291,15 @@ impl NetworkStream {
 
                 let domain = DNSNameRef::try_from_ascii_str(tls_parameters.domain())
                     .map_err(error::connection)?;
-                let stream = StreamOwned::new(ClientSession::new(connector, domain), tcp_stream);
+                let mut stream =
+                    StreamOwned::new(ClientSession::new(connector, domain), tcp_stream);
+                // Complete the handshake now, rather than on the first read, to report its errors
+                while stream.sess.is_handshaking() {
+                    stream
+                        .sess
+                        .complete_io(&mut stream.sock)
+                        .map_err(error::tls_handshake)?;
+                }
 
                 InnerNetworkStream::RustlsTls(stream)
             }
@@ -175,6 +313,8 @@ impl NetworkStream {
             InnerNetworkStream::NativeTls(_) => true,
             #[cfg(feature = "rustls-tls")]
             InnerNetworkStream::RustlsTls(_) => true,
+            #[cfg(unix)]
+            InnerNetworkStream::Unix(_) => false,
             InnerNetworkStream::None => {
                 debug_assert!(false, "InnerNetworkStream::None must never be built");
                 false
@@ -182,6 +322,40 @@ impl NetworkStream {
         }
     }
 
+    /// Checks whether the server closed the connection, without a round-trip
+    ///
+    /// Peeks at the socket without blocking: the connection is closed if the end of the
+    /// stream was reached or if it was reset. Data waiting to be read doesn't count as a
+    /// closed connection. Always `false` for Unix domain sockets.
+    pub fn is_closed_by_peer(&self) -> io::Result<bool> {
+        let stream = match self.inner {
+            InnerNetworkStream::Tcp(ref s) => s,
+            #[cfg(feature = "native-tls")]
+            InnerNetworkStream::NativeTls(ref s) => s.get_ref(),
+            #[cfg(feature = "rustls-tls")]
+            InnerNetworkStream::RustlsTls(ref s) => s.get_ref(),
+            #[cfg(unix)]
+            InnerNetworkStream::Unix(_) => return Ok(false),
+            InnerNetworkStream::None => {
+                debug_assert!(false, "InnerNetworkStream::None must never be built");
+                return Ok(false);
+            }
+        };
+
+        stream.set_nonblocking(true)?;
+        let result = stream.peek(&mut [0; 1]);
+        stream.set_nonblocking(false)?;
+
+        match result {
+            Ok(len) => Ok(len == 0),
+            Err(err) => match err.kind() {
+                io::ErrorKind::WouldBlock => Ok(false),
+                io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted => Ok(true),
+                _ => Err(err),
+            },
+        }
+    }
+
     pub fn set_read_timeout(&mut self, duration: Option<Duration>) -> io::Result<()> {
         match self.inner {
             InnerNetworkStream::Tcp(ref mut stream) => stream.set_read_timeout(duration),
@@ -193,6 +367,8 @@ impl NetworkStream {
             InnerNetworkStream::RustlsTls(ref mut stream) => {
                 stream.get_ref().set_read_timeout(duration)
             }
+            #[cfg(unix)]
+            InnerNetworkStream::Unix(ref mut stream) => stream.set_read_timeout(duration),
             InnerNetworkStream::None => {
                 debug_assert!(false, "InnerNetworkStream::None must never be built");
                 Ok(())
@@ -213,6 +389,8 @@ impl NetworkStream {
             InnerNetworkStream::RustlsTls(ref mut stream) => {
                 stream.get_ref().set_write_timeout(duration)
             }
+            #[cfg(unix)]
+            InnerNetworkStream::Unix(ref mut stream) => stream.set_write_timeout(duration),
 
             InnerNetworkStream::None => {
                 debug_assert!(false, "InnerNetworkStream::None must never be built");
@@ -222,6 +400,73 @@ impl NetworkStream {
     }
 }
 
+/// Connects to `addr` from a local port of `ports`, trying the ports in turn until one
+/// is available
+#[cfg(feature = "socket2")]
+pub(super) fn connect_from(
+    addr: &SocketAddr,
+    ports: &RangeInclusive<u16>,
+    timeout: Option<Duration>,
+) -> io::Result<TcpStream> {
+    let mut last_err = None;
+
+    for port in ports.clone() {
+        let socket = Socket::new(
+            Domain::for_address(*addr),
+            Type::STREAM,
+            Some(Protocol::TCP),
+        )?;
+        let result = socket
+            .bind(&source_addr(addr, port).into())
+            .and_then(|()| match timeout {
+                Some(timeout) => socket.connect_timeout(&(*addr).into(), timeout),
+                None => socket.connect(&(*addr).into()),
+            });
+        match result {
+            Ok(()) => return Ok(socket.into()),
+            Err(err) if is_port_unavailable(&err) => last_err = Some(err),
+            Err(err) => return Err(err),
+        }
+    }
+
+    Err(last_err
+        .unwrap_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "empty source port range")))
+}
+
+/// The local address to bind to connect to `addr` from `port`
+#[cfg(any(feature = "socket2", feature = "tokio1"))]
+pub(super) fn source_addr(addr: &SocketAddr, port: u16) -> SocketAddr {
+    match addr {
+        SocketAddr::V4(_) => SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), port),
+        SocketAddr::V6(_) => SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), port),
+    }
+}
+
+/// Tells whether connecting from another source port may succeed
+#[cfg(any(feature = "socket2", feature = "tokio1"))]
+pub(super) fn is_port_unavailable(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::AddrInUse | io::ErrorKind::AddrNotAvailable
+    )
+}
+
+impl From<TcpStream> for NetworkStream {
+    /// Wraps a connected TCP stream, which can later be upgraded to TLS
+    fn from(stream: TcpStream) -> Self {
+        NetworkStream::new(InnerNetworkStream::Tcp(stream))
+    }
+}
+
+#[cfg(unix)]
+#[cfg_attr(docsrs, doc(cfg(unix)))]
+impl From<UnixStream> for NetworkStream {
+    /// Wraps a connected Unix domain socket
+    fn from(stream: UnixStream) -> Self {
+        NetworkStream::new(InnerNetworkStream::Unix(stream))
+    }
+}
+
 impl Read for NetworkStream {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         match self.inner {
@@ -230,6 +475,8 @@ impl Read for NetworkStream {
             InnerNetworkStream::NativeTls(ref mut s) => s.read(buf),
             #[cfg(feature = "rustls-tls")]
             InnerNetworkStream::RustlsTls(ref mut s) => s.read(buf),
+            #[cfg(unix)]
+            InnerNetworkStream::Unix(ref mut s) => s.read(buf),
             InnerNetworkStream::None => {
                 debug_assert!(false, "InnerNetworkStream::None must never be built");
                 Ok(0)
@@ -246,6 +493,8 @@ impl Write for NetworkStream {
             InnerNetworkStream::NativeTls(ref mut s) => s.write(buf),
             #[cfg(feature = "rustls-tls")]
             InnerNetworkStream::RustlsTls(ref mut s) => s.write(buf),
+            #[cfg(unix)]
+            InnerNetworkStream::Unix(ref mut s) => s.write(buf),
             InnerNetworkStream::None => {
                 debug_assert!(false, "InnerNetworkStream::None must never be built");
                 Ok(0)
@@ -260,6 +509,8 @@ impl Write for NetworkStream {
             InnerNetworkStream::NativeTls(ref mut s) => s.flush(),
             #[cfg(feature = "rustls-tls")]
             InnerNetworkStream::RustlsTls(ref mut s) => s.flush(),
+            #[cfg(unix)]
+            InnerNetworkStream::Unix(ref mut s) => s.flush(),
             InnerNetworkStream::None => {
                 debug_assert!(false, "InnerNetworkStream::None must never be built");
                 Ok(())
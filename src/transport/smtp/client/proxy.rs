@@ -0,0 +1,181 @@
+//! PROXY protocol header, declaring the original source address of a connection
+//! to a server behind a proxy like HAProxy
+//!
+//! The [specification](https://www.haproxy.org/download/2.4/doc/proxy-protocol.txt)
+//! defines a text format (version 1) and a binary format (version 2).
+
+use std::net::{IpAddr, SocketAddr};
+
+/// Signature starting a version 2 header
+const V2_SIGNATURE: &[u8; 12] = b"\r\n\r\n\0\r\nQUIT\n";
+
+/// Version of the PROXY protocol
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProxyProtocolVersion {
+    /// Human-readable header
+    V1,
+    /// Binary header
+    V2,
+}
+
+/// PROXY protocol header sent right after connecting, before the server greeting
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProxyProtocol {
+    version: ProxyProtocolVersion,
+    source: Option<SocketAddr>,
+}
+
+impl ProxyProtocol {
+    /// Creates a header declaring `source` as the address of the client
+    ///
+    /// Defaults to the local address of the connection if `source` is `None`.
+    pub fn new(version: ProxyProtocolVersion, source: Option<SocketAddr>) -> Self {
+        Self { version, source }
+    }
+
+    /// Returns the header for a connection from `local_addr` to `peer_addr`
+    pub fn header(&self, local_addr: SocketAddr, peer_addr: SocketAddr) -> Vec<u8> {
+        let source = self.source.unwrap_or(local_addr);
+        // Both addresses must be of the same family
+        let (source_ip, destination_ip) = match (source.ip(), peer_addr.ip()) {
+            (IpAddr::V4(source), IpAddr::V6(destination)) => {
+                (IpAddr::V6(source.to_ipv6_mapped()), IpAddr::V6(destination))
+            }
+            (IpAddr::V6(source), IpAddr::V4(destination)) => {
+                (IpAddr::V6(source), IpAddr::V6(destination.to_ipv6_mapped()))
+            }
+            addresses => addresses,
+        };
+
+        match self.version {
+            ProxyProtocolVersion::V1 => {
+                let protocol = if source_ip.is_ipv4() { "TCP4" } else { "TCP6" };
+                format!(
+                    "PROXY {} {} {} {} {}\r\n",
+                    protocol,
+                    source_ip,
+                    destination_ip,
+                    source.port(),
+                    peer_addr.port()
+                )
+                .into_bytes()
+            }
+            ProxyProtocolVersion::V2 => {
+                let mut header = V2_SIGNATURE.to_vec();
+                // Version 2, PROXY command
+                header.push(0x21);
+
+                let mut addresses = Vec::with_capacity(36);
+                match (source_ip, destination_ip) {
+                    (IpAddr::V4(source_ip), IpAddr::V4(destination_ip)) => {
+                        // TCP over IPv4
+                        header.push(0x11);
+                        addresses.extend_from_slice(&source_ip.octets());
+                        addresses.extend_from_slice(&destination_ip.octets());
+                    }
+                    (IpAddr::V6(source_ip), IpAddr::V6(destination_ip)) => {
+                        // TCP over IPv6
+                        header.push(0x21);
+                        addresses.extend_from_slice(&source_ip.octets());
+                        addresses.extend_from_slice(&destination_ip.octets());
+                    }
+                    _ => unreachable!("addresses are of the same family"),
+                }
+                addresses.extend_from_slice(&source.port().to_be_bytes());
+                addresses.extend_from_slice(&peer_addr.port().to_be_bytes());
+
+                header.extend_from_slice(&(addresses.len() as u16).to_be_bytes());
+                header.extend_from_slice(&addresses);
+                header
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_v1_ipv4() {
+        let proxy = ProxyProtocol::new(ProxyProtocolVersion::V1, None);
+        assert_eq!(
+            proxy.header(
+                "192.0.2.1:56324".parse().unwrap(),
+                "198.51.100.7:25".parse().unwrap()
+            ),
+            b"PROXY TCP4 192.0.2.1 198.51.100.7 56324 25\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_v1_ipv6() {
+        let proxy = ProxyProtocol::new(
+            ProxyProtocolVersion::V1,
+            Some("[2001:db8::1]:56324".parse().unwrap()),
+        );
+        assert_eq!(
+            proxy.header(
+                "[::1]:40000".parse().unwrap(),
+                "[2001:db8::25]:587".parse().unwrap()
+            ),
+            b"PROXY TCP6 2001:db8::1 2001:db8::25 56324 587\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_v1_mixed() {
+        let proxy = ProxyProtocol::new(
+            ProxyProtocolVersion::V1,
+            Some("192.0.2.1:56324".parse().unwrap()),
+        );
+        assert_eq!(
+            proxy.header(
+                "[::1]:40000".parse().unwrap(),
+                "[2001:db8::25]:25".parse().unwrap()
+            ),
+            b"PROXY TCP6 ::ffff:192.0.2.1 2001:db8::25 56324 25\r\n".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_v2_ipv4() {
+        let proxy = ProxyProtocol::new(ProxyProtocolVersion::V2, None);
+        assert_eq!(
+            proxy.header(
+                "192.0.2.1:56324".parse().unwrap(),
+                "198.51.100.7:25".parse().unwrap()
+            ),
+            vec![
+                0x0d, 0x0a, 0x0d, 0x0a, 0x00, 0x0d, 0x0a, 0x51, 0x55, 0x49, 0x54,
+                0x0a, // signature
+                0x21, 0x11, 0x00, 0x0c, // version, command, family, length
+                192, 0, 2, 1, // source
+                198, 51, 100, 7, // destination
+                0xdc, 0x04, 0x00, 0x19, // ports
+            ]
+        );
+    }
+
+    #[test]
+    fn test_v2_ipv6() {
+        let proxy = ProxyProtocol::new(
+            ProxyProtocolVersion::V2,
+            Some("[2001:db8::1]:56324".parse().unwrap()),
+        );
+        assert_eq!(
+            proxy.header(
+                "[::1]:40000".parse().unwrap(),
+                "[2001:db8::25]:587".parse().unwrap()
+            ),
+            vec![
+                0x0d, 0x0a, 0x0d, 0x0a, 0x00, 0x0d, 0x0a, 0x51, 0x55, 0x49, 0x54,
+                0x0a, // signature
+                0x21, 0x21, 0x00, 0x24, // version, command, family, length
+                0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x01, // source
+                0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x25, // destination
+                0xdc, 0x04, 0x02, 0x4b, // ports
+            ]
+        );
+    }
+}
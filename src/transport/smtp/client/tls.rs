@@ -7,6 +7,8 @@ use rustls::{ClientConfig, RootCertStore, ServerCertVerified, ServerCertVerifier
 use std::fmt::{self, Debug};
 #[cfg(feature = "rustls-tls")]
 use std::sync::Arc;
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+use std::{error::Error as StdError, io};
 #[cfg(feature = "rustls-tls")]
 use webpki::DNSNameRef;
 
@@ -50,6 +52,125 @@ impl Debug for Tls {
     }
 }
 
+impl Tls {
+    /// The same settings, validating the certificate of the server for `domain`
+    pub(crate) fn for_domain(&self, domain: &str) -> Tls {
+        #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+        let with_domain = |tls_parameters: &TlsParameters| TlsParameters {
+            connector: tls_parameters.connector.clone(),
+            domain: domain.to_owned(),
+        };
+        #[cfg(not(any(feature = "native-tls", feature = "rustls-tls")))]
+        let _ = domain;
+
+        match self {
+            Self::None => Self::None,
+            #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+            Self::Opportunistic(tls_parameters) => Self::Opportunistic(with_domain(tls_parameters)),
+            #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+            Self::Required(tls_parameters) => Self::Required(with_domain(tls_parameters)),
+            #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+            Self::Wrapper(tls_parameters) => Self::Wrapper(with_domain(tls_parameters)),
+        }
+    }
+}
+
+/// Cause of a failed TLS handshake, see [`Error::tls_kind`]
+///
+/// rustls errors are mapped exactly. native-tls doesn't expose the cause of its errors,
+/// so it is derived from the message of the certificate verification error of OpenSSL,
+/// and other errors are reported as [`TlsErrorKind::HandshakeFailure`].
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+#[cfg_attr(docsrs, doc(cfg(any(feature = "native-tls", feature = "rustls-tls"))))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum TlsErrorKind {
+    /// The certificate of the server isn't signed by a trusted authority,
+    /// like a self-signed certificate
+    UntrustedCert,
+    /// The certificate of the server isn't valid for the domain of the `TlsParameters`
+    HostnameMismatch,
+    /// The certificate of the server is expired, or not valid yet
+    Expired,
+    /// The handshake failed, like when the server closed the connection or doesn't speak TLS
+    HandshakeFailure,
+    /// Any other TLS error
+    Other,
+}
+
+/// Classifies a source of a TLS handshake error, `None` if it doesn't come from TLS
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+pub(crate) fn tls_error_kind(err: &(dyn StdError + 'static)) -> Option<TlsErrorKind> {
+    #[cfg(feature = "native-tls")]
+    {
+        if let Some(err) = err.downcast_ref::<native_tls::Error>() {
+            return Some(native_tls_error_kind(err));
+        }
+    }
+    #[cfg(feature = "rustls-tls")]
+    {
+        if let Some(err) = err.downcast_ref::<TLSError>() {
+            return Some(rustls_error_kind(err));
+        }
+    }
+    if let Some(err) = err.downcast_ref::<io::Error>() {
+        // rustls reports its errors wrapped in I/O errors
+        if let Some(kind) = err.get_ref().and_then(|inner| tls_error_kind(inner)) {
+            return Some(kind);
+        }
+        if matches!(
+            err.kind(),
+            io::ErrorKind::UnexpectedEof
+                | io::ErrorKind::ConnectionReset
+                | io::ErrorKind::ConnectionAborted
+        ) {
+            return Some(TlsErrorKind::HandshakeFailure);
+        }
+    }
+    None
+}
+
+#[cfg(feature = "native-tls")]
+fn native_tls_error_kind(err: &native_tls::Error) -> TlsErrorKind {
+    let message = err.to_string().to_ascii_lowercase();
+    if message.contains("hostname mismatch") || message.contains("ip address mismatch") {
+        TlsErrorKind::HostnameMismatch
+    } else if message.contains("certificate has expired")
+        || message.contains("certificate is not yet valid")
+    {
+        TlsErrorKind::Expired
+    } else if message.contains("self-signed certificate")
+        || message.contains("self signed certificate")
+        || message.contains("unable to get local issuer certificate")
+        || message.contains("unable to get issuer certificate")
+        || message.contains("certificate verify failed")
+    {
+        TlsErrorKind::UntrustedCert
+    } else {
+        TlsErrorKind::HandshakeFailure
+    }
+}
+
+#[cfg(feature = "rustls-tls")]
+fn rustls_error_kind(err: &TLSError) -> TlsErrorKind {
+    match err {
+        TLSError::WebPKIError(webpki::Error::CertExpired)
+        | TLSError::WebPKIError(webpki::Error::CertNotValidYet) => TlsErrorKind::Expired,
+        TLSError::WebPKIError(webpki::Error::CertNotValidForName) => TlsErrorKind::HostnameMismatch,
+        TLSError::WebPKIError(_) | TLSError::NoCertificatesPresented => TlsErrorKind::UntrustedCert,
+        TLSError::InappropriateMessage { .. }
+        | TLSError::InappropriateHandshakeMessage { .. }
+        | TLSError::CorruptMessage
+        | TLSError::CorruptMessagePayload(_)
+        | TLSError::DecryptError
+        | TLSError::PeerIncompatibleError(_)
+        | TLSError::PeerMisbehavedError(_)
+        | TLSError::AlertReceived(_)
+        | TLSError::HandshakeNotComplete => TlsErrorKind::HandshakeFailure,
+        _ => TlsErrorKind::Other,
+    }
+}
+
 /// Parameters to use for secure clients
 #[derive(Clone)]
 pub struct TlsParameters {
@@ -65,6 +186,7 @@ pub struct TlsParametersBuilder {
     root_certs: Vec<Certificate>,
     accept_invalid_hostnames: bool,
     accept_invalid_certs: bool,
+    disable_renegotiation: bool,
 }
 
 impl TlsParametersBuilder {
@@ -75,6 +197,7 @@ impl TlsParametersBuilder {
             root_certs: Vec::new(),
             accept_invalid_hostnames: false,
             accept_invalid_certs: false,
+            disable_renegotiation: false,
         }
     }
 
@@ -127,6 +250,20 @@ impl TlsParametersBuilder {
         self
     }
 
+    /// Requires the TLS renegotiations requested by the server to be refused
+    ///
+    /// Defaults to `false`, keeping the behavior of the TLS backend.
+    ///
+    /// Renegotiation has been the source of several attacks, and isn't needed to send
+    /// emails. rustls never supports it, so this is always the case with the `rustls-tls`
+    /// backend. native-tls doesn't allow configuring it, and the behavior depends on the
+    /// system TLS library, so building `TlsParameters` with native-tls fails when this is
+    /// enabled, instead of silently allowing renegotiation.
+    pub fn disable_renegotiation(mut self, disable_renegotiation: bool) -> Self {
+        self.disable_renegotiation = disable_renegotiation;
+        self
+    }
+
     /// Creates a new `TlsParameters` using native-tls or rustls
     /// depending on which one is available
     #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
@@ -143,6 +280,12 @@ impl TlsParametersBuilder {
     #[cfg(feature = "native-tls")]
     #[cfg_attr(docsrs, doc(cfg(feature = "native-tls")))]
     pub fn build_native(self) -> Result<TlsParameters, Error> {
+        if self.disable_renegotiation {
+            return Err(error::tls(
+                "renegotiation can't be disabled with the native-tls backend",
+            ));
+        }
+
         let mut tls_builder = TlsConnector::builder();
 
         for cert in self.root_certs {
@@ -165,6 +308,7 @@ impl TlsParametersBuilder {
     pub fn build_rustls(self) -> Result<TlsParameters, Error> {
         use webpki_roots::TLS_SERVER_ROOTS;
 
+        // rustls never renegotiates, `disable_renegotiation` always holds
         let mut tls = ClientConfig::new();
 
         for cert in self.root_certs {
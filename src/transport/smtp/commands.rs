@@ -3,13 +3,19 @@
 use crate::{
     address::Address,
     transport::smtp::{
-        authentication::{Credentials, Mechanism},
+        authentication::{Credentials, LoginExchange, Mechanism},
         error::{self, Error},
-        extension::{ClientId, MailParameter, RcptParameter},
+        extension::{
+            ClientId, Extension, MailParameter, RcptParameter, ServerInfo, XClientAttribute,
+        },
         response::Response,
+        util::XText,
     },
 };
-use std::fmt::{self, Display, Formatter};
+use std::{
+    fmt::{self, Display, Formatter},
+    net::IpAddr,
+};
 
 /// EHLO command
 #[derive(PartialEq, Clone, Debug)]
@@ -31,6 +37,26 @@ impl Ehlo {
     }
 }
 
+/// LHLO command, replacing EHLO in LMTP ([RFC 2033](https://tools.ietf.org/html/rfc2033))
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lhlo {
+    client_id: ClientId,
+}
+
+impl Display for Lhlo {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "LHLO {}\r\n", self.client_id)
+    }
+}
+
+impl Lhlo {
+    /// Creates a LHLO command
+    pub fn new(client_id: ClientId) -> Lhlo {
+        Lhlo { client_id }
+    }
+}
+
 /// STARTTLS command
 #[derive(PartialEq, Clone, Debug, Copy)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -270,7 +296,7 @@ impl Auth {
         #[cfg(feature = "tracing")]
         tracing::debug!("auth encoded challenge: {}", encoded_challenge);
 
-        let decoded_base64 = base64::decode(&encoded_challenge).map_err(error::response)?;
+        let decoded_base64 = base64::decode(encoded_challenge).map_err(error::response)?;
         let decoded_challenge = String::from_utf8(decoded_base64).map_err(error::response)?;
         #[cfg(feature = "tracing")]
         tracing::debug!("auth decoded challenge: {}", decoded_challenge);
@@ -284,6 +310,138 @@ impl Auth {
             response,
         })
     }
+
+    /// Creates the answer to a challenge of the `LOGIN` mechanism
+    ///
+    /// Unlike with [`Auth::new_from_response`], the prompt doesn't need to be recognized,
+    /// nor to be valid base64.
+    pub(crate) fn new_from_login_response(
+        credentials: Credentials,
+        response: &Response,
+        exchange: &mut LoginExchange,
+    ) -> Result<Auth, Error> {
+        if !response.has_code(334) {
+            return Err(error::response("Expecting a challenge"));
+        }
+
+        let decoded_challenge = response
+            .first_word()
+            .and_then(|challenge| base64::decode(challenge).ok())
+            .and_then(|challenge| String::from_utf8(challenge).ok());
+        #[cfg(feature = "tracing")]
+        tracing::debug!("auth decoded challenge: {:?}", decoded_challenge);
+
+        let response = exchange.respond(&credentials, decoded_challenge.as_deref())?;
+        Ok(Auth {
+            mechanism: Mechanism::Login,
+            credentials,
+            challenge: decoded_challenge,
+            response: Some(response),
+        })
+    }
+}
+
+/// XCLIENT command, forwarding the attributes of the original client to a trusted server
+///
+/// Described in the [Postfix documentation](http://www.postfix.org/XCLIENT_README.html)
+#[derive(PartialEq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct XClient {
+    attributes: Vec<(XClientAttribute, String)>,
+}
+
+impl Display for XClient {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str("XCLIENT")?;
+        for (attribute, value) in &self.attributes {
+            write!(f, " {}={}", attribute, XText(value))?;
+        }
+        f.write_str("\r\n")
+    }
+}
+
+impl XClient {
+    /// Creates a XCLIENT command
+    ///
+    /// `[UNAVAILABLE]` and `[TEMPUNAVAIL]` are accepted for every attribute. Otherwise,
+    /// `ADDR` must be an IP address, `PORT` a port number, `PROTO` either `SMTP` or `ESMTP`,
+    /// and the other values non-empty ASCII strings.
+    pub fn new<V: Into<String>>(
+        attributes: impl IntoIterator<Item = (XClientAttribute, V)>,
+    ) -> Result<XClient, Error> {
+        let attributes = attributes
+            .into_iter()
+            .map(|(attribute, value)| {
+                let value = Self::validate(attribute, value.into())?;
+                Ok((attribute, value))
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(XClient { attributes })
+    }
+
+    fn validate(attribute: XClientAttribute, value: String) -> Result<String, Error> {
+        if value == "[UNAVAILABLE]" || value == "[TEMPUNAVAIL]" {
+            return Ok(value);
+        }
+
+        let invalid = || {
+            error::client(format!(
+                "invalid value for the XCLIENT {} attribute: {:?}",
+                attribute, value
+            ))
+        };
+        match attribute {
+            XClientAttribute::Addr => {
+                let addr = if value.len() > 5 && value[..5].eq_ignore_ascii_case("IPV6:") {
+                    &value[5..]
+                } else {
+                    value.as_str()
+                };
+                match addr.parse::<IpAddr>().map_err(|_| invalid())? {
+                    IpAddr::V4(addr) => Ok(addr.to_string()),
+                    IpAddr::V6(addr) => Ok(format!("IPV6:{}", addr)),
+                }
+            }
+            XClientAttribute::Port => {
+                let port = value.parse::<u16>().map_err(|_| invalid())?;
+                Ok(port.to_string())
+            }
+            XClientAttribute::Proto => {
+                if value.eq_ignore_ascii_case("SMTP") || value.eq_ignore_ascii_case("ESMTP") {
+                    Ok(value.to_ascii_uppercase())
+                } else {
+                    Err(invalid())
+                }
+            }
+            _ => {
+                if !value.is_empty() && value.chars().all(|c| c.is_ascii() && !c.is_ascii_control())
+                {
+                    Ok(value)
+                } else {
+                    Err(invalid())
+                }
+            }
+        }
+    }
+
+    /// Returns the command without the attributes the server doesn't support
+    pub fn supported_by(&self, server_info: &ServerInfo) -> XClient {
+        XClient {
+            attributes: self
+                .attributes
+                .iter()
+                .filter(|(attribute, _)| {
+                    server_info.supports_feature(Extension::XClient(*attribute))
+                })
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Returns true if the command doesn't have any attribute
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
 }
 
 #[cfg(test)]
@@ -304,7 +462,8 @@ mod test {
             keyword: "TEST".to_string(),
             value: Some("value".to_string()),
         };
-        assert_eq!(format!("{}", Ehlo::new(id)), "EHLO localhost\r\n");
+        assert_eq!(format!("{}", Ehlo::new(id.clone())), "EHLO localhost\r\n");
+        assert_eq!(format!("{}", Lhlo::new(id)), "LHLO localhost\r\n");
         assert_eq!(
             format!("{}", Mail::new(Some(email.clone()), vec![])),
             "MAIL FROM:<test@example.com>\r\n"
@@ -372,4 +531,49 @@ mod test {
             "AUTH LOGIN\r\n"
         );
     }
+
+    #[test]
+    fn test_xclient() {
+        let xclient = XClient::new(vec![
+            (XClientAttribute::Name, "client.example.com"),
+            (XClientAttribute::Addr, "2001:db8::1"),
+            (XClientAttribute::Port, "4242"),
+            (XClientAttribute::Proto, "esmtp"),
+            (XClientAttribute::Helo, "[UNAVAILABLE]"),
+            (XClientAttribute::Login, "user+tag=1 name"),
+        ])
+        .unwrap();
+        assert_eq!(
+            format!("{}", xclient),
+            "XCLIENT NAME=client.example.com ADDR=IPV6:2001:db8::1 PORT=4242 PROTO=ESMTP \
+             HELO=[UNAVAILABLE] LOGIN=user+2Btag+3D1+20name\r\n"
+        );
+
+        let response: Response = "250-me\r\n250 XCLIENT NAME ADDR\r\n".parse().unwrap();
+        let server_info = ServerInfo::from_response(&response).unwrap();
+        assert_eq!(
+            format!("{}", xclient.supported_by(&server_info)),
+            "XCLIENT NAME=client.example.com ADDR=IPV6:2001:db8::1\r\n"
+        );
+        assert!(XClient::new(vec![(XClientAttribute::Login, "user")])
+            .unwrap()
+            .supported_by(&server_info)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_xclient_invalid() {
+        for (attribute, value) in [
+            (XClientAttribute::Addr, "client.example.com"),
+            (XClientAttribute::Port, "65536"),
+            (XClientAttribute::Proto, "LMTP"),
+            (XClientAttribute::Helo, ""),
+            (XClientAttribute::Login, "user\r\nRSET"),
+            (XClientAttribute::Name, "bjørn.example.com"),
+        ]
+        .iter()
+        {
+            assert!(XClient::new(vec![(*attribute, *value)]).is_err());
+        }
+    }
 }
@@ -0,0 +1,200 @@
+//! The direct transport delivers emails to the MX hosts of the domain of the recipients,
+//! without going through a relay.
+//!
+//! The MX records of the domain are resolved by the caller, and given with their priority.
+//! The hosts are tried from the lowest priority value, the hosts of the same priority in a
+//! random order, as defined in [RFC 5321](https://tools.ietf.org/html/rfc5321#section-5.1).
+//! A host which can't be connected to, or which replies with a transient error, is skipped
+//! for the next one, while a permanent error fails the delivery. The connections are
+//! encrypted with STARTTLS when the host supports it, validating its certificate for its
+//! name. Sending an email returns a [`DirectResponse`] telling which host accepted it.
+//!
+//! All the recipients of an envelope must be in the domain the MX hosts are for.
+//!
+//! #### Direct Transport
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "builder")]
+//! # fn test() -> Result<(), Box<dyn std::error::Error>> {
+//! use lettre::{transport::smtp::direct::DirectTransport, Message, Transport};
+//!
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@example.com>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! // The MX records of example.com
+//! let sender = DirectTransport::builder(vec![(10, "mx1.example.com"), (20, "mx2.example.com")])
+//!     .build();
+//! let response = sender.send(&email)?;
+//! println!("email accepted by {}", response.host());
+//! # Ok(())
+//! # }
+//! ```
+
+use std::time::Duration;
+
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+use super::client::TlsParameters;
+use super::{
+    client::SmtpConnection, error, extension::ClientId, response::Response, Error, TcpTarget,
+    DEFAULT_TIMEOUT, SMTP_PORT,
+};
+#[cfg(feature = "builder")]
+use crate::Message;
+use crate::{address::Envelope, Transport};
+
+/// Reply of the MX host which accepted an email
+#[derive(Debug)]
+pub struct DirectResponse {
+    host: String,
+    response: Response,
+}
+
+impl DirectResponse {
+    /// The MX host which accepted the email
+    pub fn host(&self) -> &str {
+        &self.host
+    }
+
+    /// The reply of the host to the content of the email
+    pub fn response(&self) -> &Response {
+        &self.response
+    }
+}
+
+/// Delivers emails directly to the MX hosts of a domain
+#[derive(Debug, Clone)]
+pub struct DirectTransport {
+    /// The MX hosts with their priority
+    mx: Vec<(u16, String)>,
+    port: u16,
+    hello_name: Option<ClientId>,
+    timeout: Option<Duration>,
+}
+
+impl DirectTransport {
+    /// Creates a new direct transport to the MX hosts `mx`, given with their priority
+    pub fn builder<I, T>(mx: I) -> DirectTransportBuilder
+    where
+        I: IntoIterator<Item = (u16, T)>,
+        T: Into<String>,
+    {
+        DirectTransportBuilder {
+            transport: DirectTransport {
+                mx: mx
+                    .into_iter()
+                    .map(|(priority, host)| (priority, host.into()))
+                    .collect(),
+                port: SMTP_PORT,
+                hello_name: None,
+                timeout: Some(DEFAULT_TIMEOUT),
+            },
+        }
+    }
+
+    /// The MX hosts in the order they're tried
+    fn hosts(&self) -> Vec<&str> {
+        let mut mx = self.mx.iter().collect::<Vec<_>>();
+        // The sort is stable, which keeps the hosts of the same priority shuffled
+        fastrand::shuffle(&mut mx);
+        mx.sort_by_key(|(priority, _host)| *priority);
+        mx.into_iter()
+            .map(|(_priority, host)| host.as_str())
+            .collect()
+    }
+
+    /// Delivers the email to the MX host `host`
+    fn deliver(&self, host: &str, envelope: &Envelope, email: &[u8]) -> Result<Response, Error> {
+        let mut conn = SmtpConnection::connect_with(
+            TcpTarget::new(host, self.port),
+            self.timeout,
+            None,
+            self.hello_name.as_ref(),
+            None,
+            None,
+            None,
+            None,
+        )?;
+
+        #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+        if conn.can_starttls() {
+            let tls_parameters = TlsParameters::new(host.trim_end_matches('.').to_owned())?;
+            let hello_name = conn.hello_name().clone();
+            conn.starttls(&tls_parameters, &hello_name)?;
+        }
+
+        let response = conn.send(envelope, email)?;
+        // The email was accepted, failing to close the connection doesn't change it
+        let _ = conn.quit();
+        Ok(response)
+    }
+}
+
+/// Builder for the [`DirectTransport`]
+#[derive(Debug, Clone)]
+pub struct DirectTransportBuilder {
+    transport: DirectTransport,
+}
+
+impl DirectTransportBuilder {
+    /// Set the name used during EHLO
+    ///
+    /// Defaults to the automatic one, see [`ClientId::auto`]. MX hosts often check that
+    /// it resolves to the address of the client.
+    pub fn hello_name(mut self, name: ClientId) -> Self {
+        self.transport.hello_name = Some(name);
+        self
+    }
+
+    /// Set the timeout duration
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.transport.timeout = timeout;
+        self
+    }
+
+    /// Set the port to connect to on the MX hosts, defaults to [`SMTP_PORT`]
+    pub fn port(mut self, port: u16) -> Self {
+        self.transport.port = port;
+        self
+    }
+
+    /// Build the transport
+    pub fn build(self) -> DirectTransport {
+        self.transport
+    }
+}
+
+impl Transport for DirectTransport {
+    type Ok = DirectResponse;
+    type Error = Error;
+
+    /// Delivers an email, leaving out its `Return-Path` headers
+    #[cfg(feature = "builder")]
+    fn send(&self, message: &Message) -> Result<Self::Ok, Self::Error> {
+        self.send_raw(message.envelope(), &message.formatted_for_submission())
+    }
+
+    /// Delivers an email to the first MX host accepting it
+    ///
+    /// Fails with the error of the last host tried if no host accepted the email, or
+    /// with the first permanent error.
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let mut last_err = None;
+        for host in self.hosts() {
+            match self.deliver(host, envelope, email) {
+                Ok(response) => {
+                    return Ok(DirectResponse {
+                        host: host.to_owned(),
+                        response,
+                    })
+                }
+                Err(err) if err.is_permanent() => return Err(err),
+                Err(err) => last_err = Some(err),
+            }
+        }
+
+        Err(last_err.unwrap_or_else(|| error::client("there is no MX host to deliver to")))
+    }
+}
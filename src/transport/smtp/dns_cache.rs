@@ -0,0 +1,92 @@
+//! Cache of the addresses the relay hosts are resolved to
+
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Mutex, MutexGuard, PoisonError},
+    time::{Duration, Instant},
+};
+
+type Entries = HashMap<(String, u16), (Vec<SocketAddr>, Instant)>;
+
+/// Addresses resolved for each host and port, reused until they're older than `ttl`
+#[derive(Debug)]
+pub(crate) struct DnsCache {
+    ttl: Duration,
+    entries: Mutex<Entries>,
+}
+
+impl DnsCache {
+    pub(crate) fn new(ttl: Duration) -> Self {
+        Self {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Addresses of `host` resolved less than `ttl` ago
+    pub(crate) fn get(&self, host: &str, port: u16) -> Option<Vec<SocketAddr>> {
+        let mut entries = self.lock();
+        let key = (host.to_owned(), port);
+        match entries.get(&key) {
+            Some((addrs, resolved)) if resolved.elapsed() < self.ttl => Some(addrs.clone()),
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Stores the addresses `host` was just resolved to
+    ///
+    /// IP addresses aren't stored, as they don't need to be resolved.
+    pub(crate) fn insert(&self, host: &str, port: u16, addrs: &[SocketAddr]) {
+        if host.parse::<IpAddr>().is_err() && !addrs.is_empty() {
+            self.lock()
+                .insert((host.to_owned(), port), (addrs.to_vec(), Instant::now()));
+        }
+    }
+
+    /// Forgets the addresses of `host`, which couldn't be connected to
+    pub(crate) fn invalidate(&self, host: &str, port: u16) {
+        self.lock().remove(&(host.to_owned(), port));
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{net::SocketAddr, thread, time::Duration};
+
+    use super::DnsCache;
+
+    #[test]
+    fn dns_cache() {
+        let addrs: Vec<SocketAddr> = vec!["192.0.2.1:25".parse().unwrap()];
+        let cache = DnsCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get("smtp.domain.tld", 25), None);
+
+        cache.insert("smtp.domain.tld", 25, &addrs);
+        assert_eq!(cache.get("smtp.domain.tld", 25), Some(addrs.clone()));
+        assert_eq!(cache.get("smtp.domain.tld", 587), None);
+
+        cache.invalidate("smtp.domain.tld", 25);
+        assert_eq!(cache.get("smtp.domain.tld", 25), None);
+
+        cache.insert("192.0.2.1", 25, &addrs);
+        assert_eq!(cache.get("192.0.2.1", 25), None);
+    }
+
+    #[test]
+    fn dns_cache_expired() {
+        let addrs: Vec<SocketAddr> = vec!["192.0.2.1:25".parse().unwrap()];
+        let cache = DnsCache::new(Duration::from_millis(10));
+        cache.insert("smtp.domain.tld", 25, &addrs);
+        thread::sleep(Duration::from_millis(20));
+        assert_eq!(cache.get("smtp.domain.tld", 25), None);
+    }
+}
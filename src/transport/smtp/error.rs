@@ -1,7 +1,39 @@
 //! Error and result type for SMTP clients
+//!
+//! Errors are classified by what caused them, to help deciding whether sending again
+//! could succeed:
+//!
+//! | Method                   | Cause                                                 | Retry                    |
+//! | ------------------------ | ----------------------------------------------------- | ------------------------ |
+//! | [`Error::is_transient`]  | 4xx reply from the server, like 421 or 450            | Later                    |
+//! | [`Error::is_permanent`]  | 5xx reply from the server, like 550                   | No                       |
+//! | [`Error::is_connection`] | Connecting to the server failed                       | Later, or another server |
+//! | [`Error::is_network`]    | I/O error on an established connection, like a reset  | Later, or another server |
+//! | [`Error::is_response`]   | The reply of the server couldn't be parsed            | No                       |
+//! | [`Error::is_client`]     | Invalid input or unsupported operation in lettre      | No                       |
+//! | `Error::is_tls`          | The TLS handshake failed, with a TLS feature enabled  | Later, or another server |
+//!
+//! [`Error::is_timeout`] tells if a connection or network error was caused by a timeout,
+//! and [`Error::status`] returns the reply code of transient and permanent errors.
+//!
+//! [`Error::phase`] tells at which step of the SMTP dialogue the error happened. When the
+//! whole message was sent but no reply was read, like after a timeout waiting for the
+//! reply to the message data, [`Error::data_was_sent`] returns `true`: the server may
+//! have accepted the message, and sending it again may deliver it twice.
+//!
+//! [`Error::category`] tells what a transient or permanent error is about, from its
+//! enhanced status code, and `Error::tls_kind` tells why a TLS handshake failed.
 
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+use crate::transport::smtp::client::{tls_error_kind, TlsErrorKind};
 use crate::{
-    transport::smtp::response::{Code, Severity},
+    transport::{
+        failover::FailoverError,
+        smtp::{
+            response::{Code, EnhancedCode, Response, Severity, StatusCategory},
+            transcript::Transcript,
+        },
+    },
     BoxError,
 };
 use std::{error::Error as StdError, fmt};
@@ -16,6 +48,32 @@ pub struct Error {
 struct Inner {
     kind: Kind,
     source: Option<BoxError>,
+    /// Command in flight when the error happened
+    command: Option<String>,
+    /// Negative reply of the server
+    response: Option<Response>,
+    /// Dialogue with the server, when captured
+    transcript: Option<Transcript>,
+    /// Step of the dialogue when the error happened
+    phase: Option<Phase>,
+}
+
+/// Step of the SMTP dialogue at which an error happened
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Phase {
+    /// Connecting to the server, including the TLS handshake of wrapped TLS connections
+    Connect,
+    /// Negotiating TLS, on a wrapped TLS connection or after `STARTTLS`
+    TlsHandshake,
+    /// Waiting for the greeting of the server
+    Greeting,
+    /// Sending a command or reading its reply, see [`Error::command`]
+    Command,
+    /// Sending the message data, the server can't have accepted the message yet
+    Message,
+    /// Waiting for the reply after the whole message data was sent
+    MessageReply,
 }
 
 impl Error {
@@ -27,37 +85,89 @@ impl Error {
             inner: Box::new(Inner {
                 kind,
                 source: source.map(Into::into),
+                command: None,
+                response: None,
+                transcript: None,
+                phase: None,
             }),
         }
     }
 
-    /// Returns true if the error is from response
+    /// Records the command that was in flight
+    pub(crate) fn with_command<C: Into<String>>(mut self, command: C) -> Error {
+        self.inner.command = Some(command.into());
+        self
+    }
+
+    /// Records the step of the dialogue, unless one is already recorded
+    pub(crate) fn with_phase(mut self, phase: Phase) -> Error {
+        if self.inner.phase.is_none() {
+            self.inner.phase = Some(phase);
+        }
+        self
+    }
+
+    /// Attaches the dialogue with the server, unless one is already attached
+    pub(crate) fn with_transcript(mut self, transcript: Option<Transcript>) -> Error {
+        if self.inner.transcript.is_none() {
+            self.inner.transcript = transcript;
+        }
+        self
+    }
+
+    /// Dialogue with the server until the error, when
+    /// [`capture_transcript`](super::SmtpTransportBuilder::capture_transcript) is set
+    pub fn transcript(&self) -> Option<&Transcript> {
+        self.inner.transcript.as_ref()
+    }
+
+    /// Returns true if the reply of the server couldn't be parsed, or was unexpected
     pub fn is_response(&self) -> bool {
         matches!(self.inner.kind, Kind::Response)
     }
 
-    /// Returns true if the error is from client
+    /// Returns true if the error comes from the client, like an invalid argument or an
+    /// operation the server doesn't support
     pub fn is_client(&self) -> bool {
         matches!(self.inner.kind, Kind::Client)
     }
 
-    /// Returns true if the error is a transient SMTP error
+    /// Returns true if the error happened while establishing the connection, like a
+    /// refused connection or a failed name resolution
+    pub fn is_connection(&self) -> bool {
+        matches!(self.inner.kind, Kind::Connection)
+    }
+
+    /// Returns true if the error is an I/O error on an established connection,
+    /// like a connection reset by the server
+    pub fn is_network(&self) -> bool {
+        matches!(self.inner.kind, Kind::Network)
+    }
+
+    /// Returns true if the error is a transient SMTP error, with a 4xx reply code
     pub fn is_transient(&self) -> bool {
         matches!(self.inner.kind, Kind::Transient(_))
     }
 
-    /// Returns true if the error is a permanent SMTP error
+    /// Returns true if the error is a permanent SMTP error, with a 5xx reply code
     pub fn is_permanent(&self) -> bool {
         matches!(self.inner.kind, Kind::Permanent(_))
     }
 
     /// Returns true if the error is caused by a timeout
+    ///
+    /// Looks for an I/O error of kind `TimedOut` in the sources of the error, or
+    /// `WouldBlock`, returned by some platforms when a read or write timeout of a socket
+    /// expires. Use [`Error::phase`] to know what timed out.
     pub fn is_timeout(&self) -> bool {
         let mut source = self.source();
 
         while let Some(err) = source {
             if let Some(io_err) = err.downcast_ref::<std::io::Error>() {
-                return io_err.kind() == std::io::ErrorKind::TimedOut;
+                return matches!(
+                    io_err.kind(),
+                    std::io::ErrorKind::TimedOut | std::io::ErrorKind::WouldBlock
+                );
             }
 
             source = err.source();
@@ -73,13 +183,103 @@ impl Error {
         matches!(self.inner.kind, Kind::Tls)
     }
 
-    /// Returns the status code, if the error was generated from a response.
+    /// Returns the cause of a failed TLS handshake
+    ///
+    /// The original error of the TLS backend is kept as the source of the error.
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    #[cfg_attr(docsrs, doc(cfg(any(feature = "native-tls", feature = "rustls-tls"))))]
+    pub fn tls_kind(&self) -> Option<TlsErrorKind> {
+        if !self.is_tls() || self.inner.phase != Some(Phase::TlsHandshake) {
+            return None;
+        }
+
+        let mut source = self.source();
+        while let Some(err) = source {
+            if let Some(kind) = tls_error_kind(err) {
+                return Some(kind);
+            }
+            source = err.source();
+        }
+        Some(TlsErrorKind::Other)
+    }
+
+    /// Returns the reply code of transient and permanent errors
     pub fn status(&self) -> Option<Code> {
         match self.inner.kind {
             Kind::Transient(code) | Kind::Permanent(code) => Some(code),
             _ => None,
         }
     }
+
+    /// Returns the enhanced status code sent by the server with a transient or permanent error
+    pub fn enhanced_code(&self) -> Option<EnhancedCode> {
+        self.inner
+            .response
+            .as_ref()
+            .and_then(Response::enhanced_code)
+    }
+
+    /// Returns the category of a transient or permanent error
+    ///
+    /// The category comes from the subject of the [enhanced status code](Error::enhanced_code)
+    /// when the server sent one, like `5.1.1`. Otherwise, it is guessed from the reply code:
+    ///
+    /// | Reply code              | Category                            | Usual meaning                      |
+    /// | ----------------------- | ----------------------------------- | ---------------------------------- |
+    /// | 421                     | [`StatusCategory::NetworkRouting`]  | Service unavailable, closing       |
+    /// | 450, 550                | [`StatusCategory::Mailbox`]         | Mailbox unavailable or unknown     |
+    /// | 451, 452                | [`StatusCategory::MailSystem`]      | Local error, insufficient storage  |
+    /// | 551, 553                | [`StatusCategory::Address`]         | User not local, invalid address    |
+    /// | 552                     | [`StatusCategory::Mailbox`]         | Mailbox storage exceeded           |
+    /// | 554                     | [`StatusCategory::Security`]        | Transaction failed, often a policy |
+    /// | 530, 534, 535, 538      | [`StatusCategory::Security`]        | Authentication required or failed  |
+    /// | 500–504, 555            | [`StatusCategory::Delivery`]        | Invalid command or parameters      |
+    /// | Other 4xx and 5xx codes | [`StatusCategory::Other`]           |                                    |
+    pub fn category(&self) -> Option<StatusCategory> {
+        let code = self.status()?;
+        if let Some(enhanced) = self.enhanced_code() {
+            return Some(enhanced.category());
+        }
+
+        let category = match code.to_string().as_str() {
+            "421" => StatusCategory::NetworkRouting,
+            "450" | "550" | "552" => StatusCategory::Mailbox,
+            "451" | "452" => StatusCategory::MailSystem,
+            "551" | "553" => StatusCategory::Address,
+            "554" | "530" | "534" | "535" | "538" => StatusCategory::Security,
+            "500" | "501" | "502" | "503" | "504" | "555" => StatusCategory::Delivery,
+            _ => StatusCategory::Other,
+        };
+        Some(category)
+    }
+
+    /// Returns the full negative reply of the server, with all its lines
+    pub fn response(&self) -> Option<&Response> {
+        self.inner.response.as_ref()
+    }
+
+    /// Returns the step of the dialogue at which the error happened, if it happened
+    /// while talking to a server
+    pub fn phase(&self) -> Option<Phase> {
+        self.inner.phase
+    }
+
+    /// Returns true if the whole message was sent, but its reply couldn't be read
+    ///
+    /// The server may have accepted the message before the timeout or the connection
+    /// failure, so sending it again could deliver it twice. Negative replies of the
+    /// server to the message data are not concerned: the message was refused.
+    pub fn data_was_sent(&self) -> bool {
+        self.inner.phase == Some(Phase::MessageReply)
+            && !matches!(self.inner.kind, Kind::Transient(_) | Kind::Permanent(_))
+    }
+
+    /// Returns the command that was sent when the error happened, without its line ending
+    ///
+    /// The arguments of `AUTH` commands are redacted, to avoid leaking credentials.
+    pub fn command(&self) -> Option<&str> {
+        self.inner.command.as_deref()
+    }
 }
 
 #[derive(Debug)]
@@ -112,9 +312,21 @@ impl fmt::Debug for Error {
 
         builder.field("kind", &self.inner.kind);
 
+        if let Some(ref command) = self.inner.command {
+            builder.field("command", command);
+        }
+        if let Some(ref response) = self.inner.response {
+            builder.field("response", response);
+        }
         if let Some(ref source) = self.inner.source {
             builder.field("source", source);
         }
+        if let Some(ref phase) = self.inner.phase {
+            builder.field("phase", phase);
+        }
+        if let Some(ref transcript) = self.inner.transcript {
+            builder.field("transcript", transcript);
+        }
 
         builder.finish()
     }
@@ -137,6 +349,18 @@ impl fmt::Display for Error {
             }
         };
 
+        if let Some(ref command) = self.inner.command {
+            write!(f, " for {:?}", command)?;
+        }
+        if let Some(ref response) = self.inner.response {
+            let mut lines = response.message();
+            if let Some(line) = lines.next() {
+                write!(f, ": {}", line)?;
+                for line in lines {
+                    write!(f, " {}", line)?;
+                }
+            }
+        }
         if let Some(ref e) = self.inner.source {
             write!(f, ": {}", e)?;
         }
@@ -154,6 +378,25 @@ impl StdError for Error {
     }
 }
 
+impl FailoverError for Error {
+    /// Connection, network and TLS errors, and transient negative responses
+    /// may not happen with another server
+    ///
+    /// Errors after the whole message was sent are not retried, as the message could
+    /// have been accepted.
+    fn should_try_next(&self) -> bool {
+        if self.data_was_sent() {
+            return false;
+        }
+        match self.inner.kind {
+            Kind::Network | Kind::Connection | Kind::Transient(_) => true,
+            #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+            Kind::Tls => true,
+            _ => false,
+        }
+    }
+}
+
 pub(crate) fn code(c: Code) -> Error {
     match c.severity {
         Severity::TransientNegativeCompletion => Error::new::<Error>(Kind::Transient(c), None),
@@ -162,6 +405,15 @@ pub(crate) fn code(c: Code) -> Error {
     }
 }
 
+/// Builds the error of a negative reply of the server
+pub(crate) fn reply(response: Response) -> Error {
+    let mut err = code(response.code());
+    if err.is_transient() || err.is_permanent() {
+        err.inner.response = Some(response);
+    }
+    err
+}
+
 pub(crate) fn response<E: Into<BoxError>>(e: E) -> Error {
     Error::new(Kind::Response, Some(e))
 }
@@ -182,3 +434,185 @@ pub(crate) fn connection<E: Into<BoxError>>(e: E) -> Error {
 pub(crate) fn tls<E: Into<BoxError>>(e: E) -> Error {
     Error::new(Kind::Tls, Some(e))
 }
+
+/// Failed TLS handshake, classified by [`Error::tls_kind`]
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+pub(crate) fn tls_handshake<E: Into<BoxError>>(e: E) -> Error {
+    tls(e).with_phase(Phase::TlsHandshake)
+}
+
+#[cfg(test)]
+mod test {
+    use std::io;
+
+    use super::*;
+    use crate::transport::smtp::response::{Category, Detail};
+
+    #[test]
+    fn code_transient() {
+        let code = Code::new(
+            Severity::TransientNegativeCompletion,
+            Category::Connections,
+            Detail::One,
+        );
+        let err = super::code(code);
+        assert!(err.is_transient());
+        assert!(!err.is_permanent());
+        assert_eq!(err.status(), Some(code));
+        assert!(err.should_try_next());
+    }
+
+    #[test]
+    fn code_permanent() {
+        let code = Code::new(
+            Severity::PermanentNegativeCompletion,
+            Category::MailSystem,
+            Detail::Zero,
+        );
+        let err = super::code(code);
+        assert!(err.is_permanent());
+        assert!(!err.is_transient());
+        assert_eq!(err.status(), Some(code));
+        assert!(!err.should_try_next());
+    }
+
+    #[test]
+    fn code_positive() {
+        let err = super::code(Code::new(
+            Severity::PositiveCompletion,
+            Category::MailSystem,
+            Detail::Zero,
+        ));
+        assert!(err.is_client());
+        assert_eq!(err.status(), None);
+    }
+
+    #[test]
+    fn reply() {
+        let response: Response = "550-5.1.1 No such user\r\n550 5.1.1 Try again\r\n"
+            .parse()
+            .unwrap();
+        let err = super::reply(response.clone());
+        assert!(err.is_permanent());
+        assert_eq!(err.status(), Some(response.code()));
+        assert_eq!(err.response(), Some(&response));
+        assert_eq!(err.command(), None);
+        assert_eq!(
+            err.to_string(),
+            "permanent error (550): 5.1.1 No such user 5.1.1 Try again"
+        );
+
+        let err = err.with_command("RCPT TO:<hei@domain.tld>");
+        assert_eq!(err.command(), Some("RCPT TO:<hei@domain.tld>"));
+        assert_eq!(
+            err.to_string(),
+            "permanent error (550) for \"RCPT TO:<hei@domain.tld>\": 5.1.1 No such user 5.1.1 Try again"
+        );
+    }
+
+    #[test]
+    fn category() {
+        let err = super::reply("550 5.7.1 Relaying denied\r\n".parse().unwrap());
+        assert_eq!(err.category(), Some(StatusCategory::Security));
+        assert_eq!(err.enhanced_code().map(EnhancedCode::detail), Some(1));
+
+        // Guessed from the reply code
+        let err = super::reply("550 No such user\r\n".parse().unwrap());
+        assert_eq!(err.enhanced_code(), None);
+        assert_eq!(err.category(), Some(StatusCategory::Mailbox));
+        let err = super::reply("421 Closing\r\n".parse().unwrap());
+        assert_eq!(err.category(), Some(StatusCategory::NetworkRouting));
+        let err = super::code(Code::new(
+            Severity::PermanentNegativeCompletion,
+            Category::MailSystem,
+            Detail::Three,
+        ));
+        assert_eq!(err.category(), Some(StatusCategory::Address));
+        let err = super::code(Code::new(
+            Severity::PermanentNegativeCompletion,
+            Category::Unspecified4,
+            Detail::Seven,
+        ));
+        assert_eq!(err.category(), Some(StatusCategory::Other));
+
+        assert_eq!(super::client("invalid").category(), None);
+    }
+
+    #[test]
+    fn response() {
+        let err = super::response("invalid reply");
+        assert!(err.is_response());
+        assert!(!err.is_transient() && !err.is_permanent());
+        assert_eq!(err.status(), None);
+        assert!(!err.should_try_next());
+    }
+
+    #[test]
+    fn client() {
+        let err = super::client("invalid argument");
+        assert!(err.is_client());
+        assert!(!err.is_connection() && !err.is_network());
+        assert_eq!(err.status(), None);
+        assert!(!err.should_try_next());
+    }
+
+    #[test]
+    fn network() {
+        let err = super::network(io::Error::from(io::ErrorKind::ConnectionReset));
+        assert!(err.is_network());
+        assert!(!err.is_connection());
+        assert!(!err.is_timeout());
+        assert_eq!(err.status(), None);
+        assert!(err.should_try_next());
+
+        let err = super::network(io::Error::from(io::ErrorKind::TimedOut));
+        assert!(err.is_network());
+        assert!(err.is_timeout());
+    }
+
+    #[test]
+    fn phase() {
+        let err = super::network(io::Error::from(io::ErrorKind::WouldBlock))
+            .with_phase(Phase::MessageReply)
+            .with_phase(Phase::Command);
+        assert!(err.is_timeout());
+        assert_eq!(err.phase(), Some(Phase::MessageReply));
+        assert!(err.data_was_sent());
+        assert!(!err.should_try_next());
+
+        let err =
+            super::network(io::Error::from(io::ErrorKind::TimedOut)).with_phase(Phase::Message);
+        assert!(!err.data_was_sent());
+        assert!(err.should_try_next());
+
+        let err = super::reply("451 4.3.0 Try again\r\n".parse().unwrap())
+            .with_phase(Phase::MessageReply);
+        assert!(!err.data_was_sent());
+        assert!(err.should_try_next());
+
+        assert_eq!(super::client("invalid").phase(), None);
+    }
+
+    #[test]
+    fn connection() {
+        let err = super::connection(io::Error::from(io::ErrorKind::ConnectionRefused));
+        assert!(err.is_connection());
+        assert!(!err.is_network());
+        assert!(!err.is_timeout());
+        assert_eq!(err.status(), None);
+        assert!(err.should_try_next());
+
+        let err = super::connection(io::Error::from(io::ErrorKind::TimedOut));
+        assert!(err.is_timeout());
+    }
+
+    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+    #[test]
+    fn tls() {
+        let err = super::tls("handshake failed");
+        assert!(err.is_tls());
+        assert!(!err.is_client());
+        assert_eq!(err.status(), None);
+        assert!(err.should_try_next());
+    }
+}
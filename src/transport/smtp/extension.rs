@@ -9,7 +9,7 @@ use crate::transport::smtp::{
 use std::{
     collections::HashSet,
     fmt::{self, Display, Formatter},
-    net::{Ipv4Addr, Ipv6Addr},
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
     result::Result,
 };
 
@@ -38,15 +38,9 @@ impl Default for ClientId {
         // client's address is dynamically assigned and the client does not have
         // an obvious name), an address literal SHOULD be substituted for the
         // domain name.
-        #[cfg(feature = "hostname")]
-        {
-            hostname::get()
-                .ok()
-                .and_then(|s| s.into_string().map(Self::Domain).ok())
-                .unwrap_or(LOCALHOST_CLIENT)
-        }
-        #[cfg(not(feature = "hostname"))]
-        LOCALHOST_CLIENT
+        local_hostname()
+            .map(Self::Domain)
+            .unwrap_or(LOCALHOST_CLIENT)
     }
 }
 
@@ -61,6 +55,58 @@ impl Display for ClientId {
 }
 
 impl ClientId {
+    /// The best client identifier known before connecting, the hostname of the machine if
+    /// it's a fully-qualified domain name
+    ///
+    /// The identifier used when no `hello_name` is configured is, in order:
+    ///
+    /// * the hostname of the machine, if it's a fully-qualified domain name, returned here
+    /// * an address literal of the local address of the connection, like `[192.0.2.1]`,
+    ///   which can only be known once connected, with
+    ///   [`local_addr`](std::net::TcpStream::local_addr), see [`ClientId::from_local_addr`]
+    ///
+    /// Returns `None` when the hostname isn't fully qualified, like in most containers,
+    /// where it's a random string, or without the `hostname` feature.
+    pub fn auto() -> Option<Self> {
+        Self::from_fqdn(local_hostname())
+    }
+
+    /// The client identifier of `hostname`, if it's a fully-qualified domain name
+    fn from_fqdn(hostname: Option<String>) -> Option<Self> {
+        hostname
+            .filter(|hostname| is_fqdn(hostname))
+            .map(Self::Domain)
+    }
+
+    /// Derives the client identifier from the local address of the connection to the server
+    ///
+    /// This is the identifier used when no `hello_name` is configured. The hostname of the
+    /// machine is used if it's a fully-qualified domain name, see [`ClientId::auto`].
+    /// Otherwise an address literal of `local_addr` is used, like `[192.0.2.1]`.
+    pub fn from_local_addr(local_addr: IpAddr) -> Self {
+        Self::from_hostname_or_addr(local_hostname(), local_addr)
+    }
+
+    /// `hostname` if it's a fully-qualified domain name, or else an address literal of
+    /// `local_addr`
+    fn from_hostname_or_addr(hostname: Option<String>, local_addr: IpAddr) -> Self {
+        if let Some(client_id) = Self::from_fqdn(hostname) {
+            return client_id;
+        }
+
+        match local_addr {
+            IpAddr::V4(addr) => Self::Ipv4(addr),
+            // IPv4 clients of dual-stack sockets
+            IpAddr::V6(addr) if addr.segments()[..6] == [0, 0, 0, 0, 0, 0xffff] => {
+                let octets = addr.octets();
+                Self::Ipv4(Ipv4Addr::new(
+                    octets[12], octets[13], octets[14], octets[15],
+                ))
+            }
+            IpAddr::V6(addr) => Self::Ipv6(addr),
+        }
+    }
+
     #[doc(hidden)]
     #[deprecated(since = "0.10.0", note = "Please use ClientId::Domain(domain) instead")]
     /// Creates a new `ClientId` from a fully qualified domain name
@@ -69,8 +115,34 @@ impl ClientId {
     }
 }
 
+/// The hostname of the machine, without the `hostname` feature `None`
+fn local_hostname() -> Option<String> {
+    #[cfg(feature = "hostname")]
+    {
+        hostname::get().ok().and_then(|s| s.into_string().ok())
+    }
+    #[cfg(not(feature = "hostname"))]
+    None
+}
+
+/// Returns true if `name` looks like a fully-qualified domain name
+fn is_fqdn(name: &str) -> bool {
+    let labels: Vec<&str> = name.trim_end_matches('.').split('.').collect();
+
+    labels.len() > 1
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+        && !labels[labels.len() - 1].chars().all(|c| c.is_ascii_digit())
+        && !name.eq_ignore_ascii_case("localhost.localdomain")
+}
+
 /// Supported ESMTP keywords
-#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+#[derive(PartialEq, Eq, Hash, Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[non_exhaustive]
 pub enum Extension {
@@ -86,8 +158,17 @@ pub enum Extension {
     ///
     /// Defined in [RFC 2487](https://tools.ietf.org/html/rfc2487)
     StartTls,
+    /// SIZE keyword
+    ///
+    /// Defined in [RFC 1870](https://tools.ietf.org/html/rfc1870), the advertised limit
+    /// is given by [`ServerInfo::max_message_size`]
+    Size,
     /// AUTH mechanism
     Authentication(Mechanism),
+    /// XCLIENT attribute
+    ///
+    /// Described in the [Postfix documentation](http://www.postfix.org/XCLIENT_README.html)
+    XClient(XClientAttribute),
 }
 
 impl Display for Extension {
@@ -96,11 +177,59 @@ impl Display for Extension {
             Extension::EightBitMime => f.write_str("8BITMIME"),
             Extension::SmtpUtfEight => f.write_str("SMTPUTF8"),
             Extension::StartTls => f.write_str("STARTTLS"),
+            Extension::Size => f.write_str("SIZE"),
             Extension::Authentication(ref mechanism) => write!(f, "AUTH {}", mechanism),
+            Extension::XClient(ref attribute) => write!(f, "XCLIENT {}", attribute),
+        }
+    }
+}
+
+/// Attribute of the original client, forwarded with `XCLIENT`
+#[derive(PartialEq, Eq, Hash, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum XClientAttribute {
+    /// Host name of the client, as found by reverse DNS lookup
+    Name,
+    /// IP address of the client
+    Addr,
+    /// Port of the client
+    Port,
+    /// Protocol used by the client, `SMTP` or `ESMTP`
+    Proto,
+    /// Name sent by the client in `HELO` or `EHLO`
+    Helo,
+    /// Name used by the client to authenticate
+    Login,
+}
+
+impl XClientAttribute {
+    fn from_keyword(keyword: &str) -> Option<XClientAttribute> {
+        match keyword {
+            "NAME" => Some(XClientAttribute::Name),
+            "ADDR" => Some(XClientAttribute::Addr),
+            "PORT" => Some(XClientAttribute::Port),
+            "PROTO" => Some(XClientAttribute::Proto),
+            "HELO" => Some(XClientAttribute::Helo),
+            "LOGIN" => Some(XClientAttribute::Login),
+            _ => None,
         }
     }
 }
 
+impl Display for XClientAttribute {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(match *self {
+            XClientAttribute::Name => "NAME",
+            XClientAttribute::Addr => "ADDR",
+            XClientAttribute::Port => "PORT",
+            XClientAttribute::Proto => "PROTO",
+            XClientAttribute::Helo => "HELO",
+            XClientAttribute::Login => "LOGIN",
+        })
+    }
+}
+
 /// Contains information about an SMTP server
 #[derive(Clone, Debug, Eq, PartialEq, Default)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
@@ -113,6 +242,12 @@ pub struct ServerInfo {
     ///
     /// It contains the features supported by the server and known by the `Extension` module.
     features: HashSet<Extension>,
+    /// Keywords not known by the `Extension` module, with their parameters
+    raw_extensions: Vec<(String, Vec<String>)>,
+    /// Authentication mechanisms offered by the server, in the order of the EHLO response
+    auth_mechanisms: Vec<Mechanism>,
+    /// Size in bytes of the largest message accepted by the server, from `SIZE`
+    max_message_size: Option<u64>,
 }
 
 impl Display for ServerInfo {
@@ -135,8 +270,12 @@ impl ServerInfo {
         };
 
         let mut features: HashSet<Extension> = HashSet::new();
+        let mut raw_extensions = Vec::new();
+        let mut auth_mechanisms = Vec::new();
+        let mut max_message_size = None;
 
-        for line in response.message() {
+        // The first line holds the server name and the greeting
+        for line in response.message().skip(1) {
             if line.is_empty() {
                 continue;
             }
@@ -152,29 +291,46 @@ impl ServerInfo {
                 "STARTTLS" => {
                     features.insert(Extension::StartTls);
                 }
+                "SIZE" => {
+                    features.insert(Extension::Size);
+                    // A limit of 0 means that the server has no fixed limit
+                    max_message_size = split
+                        .next()
+                        .and_then(|size| size.parse().ok())
+                        .filter(|&size| size > 0);
+                }
                 "AUTH" => {
                     for mechanism in split {
-                        match mechanism {
-                            "PLAIN" => {
-                                features.insert(Extension::Authentication(Mechanism::Plain));
-                            }
-                            "LOGIN" => {
-                                features.insert(Extension::Authentication(Mechanism::Login));
-                            }
-                            "XOAUTH2" => {
-                                features.insert(Extension::Authentication(Mechanism::Xoauth2));
-                            }
-                            _ => (),
+                        let mechanism = match mechanism {
+                            "PLAIN" => Mechanism::Plain,
+                            "LOGIN" => Mechanism::Login,
+                            "XOAUTH2" => Mechanism::Xoauth2,
+                            other => Mechanism::Other(other.to_string()),
+                        };
+                        if features.insert(Extension::Authentication(mechanism.clone())) {
+                            auth_mechanisms.push(mechanism);
                         }
                     }
                 }
-                _ => (),
+                "XCLIENT" => {
+                    features.extend(
+                        split
+                            .filter_map(XClientAttribute::from_keyword)
+                            .map(Extension::XClient),
+                    );
+                }
+                keyword => {
+                    raw_extensions.push((keyword.to_string(), split.map(String::from).collect()));
+                }
             };
         }
 
         Ok(ServerInfo {
             name: name.to_string(),
             features,
+            raw_extensions,
+            auth_mechanisms,
+            max_message_size,
         })
     }
 
@@ -192,8 +348,8 @@ impl ServerInfo {
     /// Gets a compatible mechanism from list
     pub fn get_auth_mechanism(&self, mechanisms: &[Mechanism]) -> Option<Mechanism> {
         for mechanism in mechanisms {
-            if self.supports_auth_mechanism(*mechanism) {
-                return Some(*mechanism);
+            if self.supports_auth_mechanism(mechanism.clone()) {
+                return Some(mechanism.clone());
             }
         }
         None
@@ -203,6 +359,28 @@ impl ServerInfo {
     pub fn name(&self) -> &str {
         self.name.as_ref()
     }
+
+    /// Keywords advertised by the server but not known by the `Extension` module
+    ///
+    /// Each keyword is given as sent by the server, in the order of the EHLO response,
+    /// with its parameters, which are empty for keywords sent alone.
+    pub fn raw_extensions(&self) -> &[(String, Vec<String>)] {
+        &self.raw_extensions
+    }
+
+    /// Authentication mechanisms offered by the server, in the order of the EHLO response
+    ///
+    /// The mechanisms not implemented by lettre are given as [`Mechanism::Other`].
+    pub fn auth_mechanisms(&self) -> &[Mechanism] {
+        &self.auth_mechanisms
+    }
+
+    /// Size in bytes of the largest message accepted by the server
+    ///
+    /// `None` when the server doesn't advertise `SIZE`, or advertises it without a limit.
+    pub fn max_message_size(&self) -> Option<u64> {
+        self.max_message_size
+    }
 }
 
 /// A `MAIL FROM` extension parameter
@@ -308,6 +486,61 @@ mod test {
         assert_eq!(format!("{}", LOCALHOST_CLIENT), "[127.0.0.1]".to_string());
     }
 
+    #[test]
+    fn test_is_fqdn() {
+        assert!(is_fqdn("mail.example.com"));
+        assert!(is_fqdn("mail.example.com."));
+        assert!(is_fqdn("a-b.example"));
+        assert!(!is_fqdn("3f4a9c1b2d7e"));
+        assert!(!is_fqdn("localhost"));
+        assert!(!is_fqdn("localhost.localdomain"));
+        assert!(!is_fqdn("192.0.2.1"));
+        assert!(!is_fqdn("-bad.example.com"));
+        assert!(!is_fqdn("under_score.example.com"));
+        assert!(!is_fqdn("two..dots"));
+    }
+
+    #[test]
+    fn test_clientid_auto() {
+        match ClientId::auto() {
+            Some(ClientId::Domain(hostname)) => assert!(is_fqdn(&hostname)),
+            Some(client_id) => panic!("unexpected client id {:?}", client_id),
+            None => {}
+        }
+        if let Some(client_id) = ClientId::auto() {
+            assert_eq!(
+                ClientId::from_local_addr("192.0.2.1".parse().unwrap()),
+                client_id
+            );
+        }
+    }
+
+    #[test]
+    fn test_clientid_from_hostname_or_addr() {
+        let v4 = Ipv4Addr::new(192, 0, 2, 1);
+        let from_addr = |hostname: Option<&str>, local_addr: IpAddr| {
+            ClientId::from_hostname_or_addr(hostname.map(str::to_owned), local_addr).to_string()
+        };
+
+        assert_eq!(
+            from_addr(Some("mail.example.com"), IpAddr::V4(v4)),
+            "mail.example.com"
+        );
+        assert_eq!(
+            from_addr(Some("3f4a9c1b2d7e"), IpAddr::V4(v4)),
+            "[192.0.2.1]"
+        );
+        assert_eq!(from_addr(None, IpAddr::V4(v4)), "[192.0.2.1]");
+        assert_eq!(
+            from_addr(Some("localhost"), IpAddr::V6(v4.to_ipv6_mapped())),
+            "[192.0.2.1]"
+        );
+        assert_eq!(
+            from_addr(None, "2001:db8::1".parse().unwrap()),
+            "[IPv6:2001:db8::1]"
+        );
+    }
+
     #[test]
     fn test_extension_fmt() {
         assert_eq!(
@@ -331,6 +564,9 @@ mod test {
                 ServerInfo {
                     name: "name".to_string(),
                     features: eightbitmime,
+                    raw_extensions: Vec::new(),
+                    auth_mechanisms: Vec::new(),
+                    max_message_size: None,
                 }
             ),
             "name with {EightBitMime}".to_string()
@@ -344,6 +580,9 @@ mod test {
                 ServerInfo {
                     name: "name".to_string(),
                     features: empty,
+                    raw_extensions: Vec::new(),
+                    auth_mechanisms: Vec::new(),
+                    max_message_size: None,
                 }
             ),
             "name with no supported features".to_string()
@@ -358,6 +597,9 @@ mod test {
                 ServerInfo {
                     name: "name".to_string(),
                     features: plain,
+                    raw_extensions: Vec::new(),
+                    auth_mechanisms: Vec::new(),
+                    max_message_size: None,
                 }
             ),
             "name with {Authentication(Plain)}".to_string()
@@ -381,16 +623,22 @@ mod test {
 
         let mut features = HashSet::new();
         assert!(features.insert(Extension::EightBitMime));
+        assert!(features.insert(Extension::Size));
 
         let server_info = ServerInfo {
             name: "me".to_string(),
             features,
+            raw_extensions: Vec::new(),
+            auth_mechanisms: Vec::new(),
+            max_message_size: Some(42),
         };
 
         assert_eq!(ServerInfo::from_response(&response).unwrap(), server_info);
 
         assert!(server_info.supports_feature(Extension::EightBitMime));
+        assert!(server_info.supports_feature(Extension::Size));
         assert!(!server_info.supports_feature(Extension::StartTls));
+        assert_eq!(server_info.max_message_size(), Some(42));
 
         let response2 = Response::new(
             Code::new(
@@ -408,18 +656,108 @@ mod test {
 
         let mut features2 = HashSet::new();
         assert!(features2.insert(Extension::EightBitMime));
-        assert!(features2.insert(Extension::Authentication(Mechanism::Plain),));
-        assert!(features2.insert(Extension::Authentication(Mechanism::Xoauth2),));
+        assert!(features2.insert(Extension::Size));
+        let auth_mechanisms = vec![
+            Mechanism::Plain,
+            Mechanism::Other("CRAM-MD5".to_string()),
+            Mechanism::Xoauth2,
+            Mechanism::Other("OTHER".to_string()),
+        ];
+        for mechanism in &auth_mechanisms {
+            assert!(features2.insert(Extension::Authentication(mechanism.clone())));
+        }
 
         let server_info2 = ServerInfo {
             name: "me".to_string(),
             features: features2,
+            raw_extensions: Vec::new(),
+            auth_mechanisms: auth_mechanisms.clone(),
+            max_message_size: Some(42),
         };
 
         assert_eq!(ServerInfo::from_response(&response2).unwrap(), server_info2);
 
         assert!(server_info2.supports_feature(Extension::EightBitMime));
         assert!(server_info2.supports_auth_mechanism(Mechanism::Plain));
+        assert!(!server_info2.supports_auth_mechanism(Mechanism::Login));
+        assert_eq!(server_info2.auth_mechanisms(), &auth_mechanisms[..]);
+        assert_eq!(
+            server_info2.get_auth_mechanism(&[Mechanism::Login, Mechanism::Xoauth2]),
+            Some(Mechanism::Xoauth2)
+        );
         assert!(!server_info2.supports_feature(Extension::StartTls));
     }
+
+    #[test]
+    fn test_serverinfo_size() {
+        let response: Response = "250-me\r\n250 SIZE 35882577\r\n".parse().unwrap();
+        let server_info = ServerInfo::from_response(&response).unwrap();
+        assert!(server_info.supports_feature(Extension::Size));
+        assert_eq!(server_info.max_message_size(), Some(35_882_577));
+
+        for size in &["250-me\r\n250 SIZE\r\n", "250-me\r\n250 SIZE 0\r\n"] {
+            let server_info = ServerInfo::from_response(&size.parse().unwrap()).unwrap();
+            assert!(server_info.supports_feature(Extension::Size));
+            assert_eq!(server_info.max_message_size(), None);
+        }
+
+        let response: Response = "250-me\r\n250 8BITMIME\r\n".parse().unwrap();
+        let server_info = ServerInfo::from_response(&response).unwrap();
+        assert!(!server_info.supports_feature(Extension::Size));
+        assert_eq!(server_info.max_message_size(), None);
+    }
+
+    #[test]
+    fn test_serverinfo_xclient() {
+        let response: Response = "250-me\r\n250 XCLIENT NAME ADDR PROTO DESTADDR\r\n"
+            .parse()
+            .unwrap();
+        let server_info = ServerInfo::from_response(&response).unwrap();
+
+        assert!(server_info.supports_feature(Extension::XClient(XClientAttribute::Name)));
+        assert!(server_info.supports_feature(Extension::XClient(XClientAttribute::Addr)));
+        assert!(server_info.supports_feature(Extension::XClient(XClientAttribute::Proto)));
+        assert!(!server_info.supports_feature(Extension::XClient(XClientAttribute::Login)));
+        assert_eq!(
+            Extension::XClient(XClientAttribute::Helo).to_string(),
+            "XCLIENT HELO"
+        );
+    }
+
+    #[test]
+    fn test_serverinfo_raw_extensions() {
+        let response: Response = concat!(
+            "250-me\r\n",
+            "250-XFORWARD NAME ADDR PROTO HELO\r\n",
+            "250-X-EXPS GSSAPI NTLM\r\n",
+            "250-8BITMIME\r\n",
+            "250-X-LINK2STATE\r\n",
+            "250 PIPELINING\r\n"
+        )
+        .parse()
+        .unwrap();
+        let server_info = ServerInfo::from_response(&response).unwrap();
+
+        assert!(server_info.supports_feature(Extension::EightBitMime));
+        assert_eq!(
+            server_info.raw_extensions(),
+            &[
+                (
+                    "XFORWARD".to_string(),
+                    vec![
+                        "NAME".to_string(),
+                        "ADDR".to_string(),
+                        "PROTO".to_string(),
+                        "HELO".to_string()
+                    ]
+                ),
+                (
+                    "X-EXPS".to_string(),
+                    vec!["GSSAPI".to_string(), "NTLM".to_string()]
+                ),
+                ("X-LINK2STATE".to_string(), vec![]),
+                ("PIPELINING".to_string(), vec![]),
+            ]
+        );
+    }
 }
@@ -0,0 +1,183 @@
+//! The LMTP transport delivers emails to a local mail store using the LMTP protocol.
+//!
+//! LMTP ([RFC 2033](https://tools.ietf.org/html/rfc2033)) is a variant of SMTP used to
+//! hand messages over to a mail store like Dovecot or Cyrus. The client greets the server
+//! with `LHLO` instead of `EHLO`, and after the message content the server replies once
+//! for every accepted recipient, as the message can be delivered to some mailboxes and not
+//! to others. Sending an email returns an [`LmtpResponse`] holding the result for each
+//! envelope recipient.
+//!
+//! The server can be reached over TCP or, on Unix systems, over a Unix domain socket.
+//! LMTP servers are expected to be local, so this transport doesn't support TLS or
+//! authentication.
+//!
+//! #### LMTP Transport
+//!
+//! ```rust,no_run
+//! # #[cfg(all(feature = "builder", unix))]
+//! # fn test() -> Result<(), Box<dyn std::error::Error>> {
+//! use lettre::{transport::smtp::lmtp::LmtpTransport, Message, Transport};
+//!
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .to("Yuin <yuin@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let sender = LmtpTransport::unix("/var/run/dovecot/lmtp").build();
+//! let response = sender.send(&email)?;
+//!
+//! for (recipient, error) in response.rejected() {
+//!     eprintln!("couldn't deliver email to {}: {}", recipient, error);
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+#[cfg(unix)]
+use std::path::Path;
+use std::time::Duration;
+
+use super::{
+    client::SmtpConnection, extension::ClientId, response::Response, ConnectionTarget, Error,
+    TcpTarget, DEFAULT_TIMEOUT,
+};
+use crate::{
+    address::{Address, Envelope},
+    Transport,
+};
+
+/// Default LMTP port
+///
+/// LMTP doesn't have a registered port; 24 is the one commonly used for private mail systems.
+pub const LMTP_PORT: u16 = 24;
+
+/// Result of a LMTP delivery, for each envelope recipient
+#[derive(Debug)]
+pub struct LmtpResponse {
+    pub(crate) recipients: Vec<(Address, Result<Response, Error>)>,
+}
+
+impl LmtpResponse {
+    /// Returns the recipients with the reply of the server, in the order of the envelope
+    pub fn iter(&self) -> impl Iterator<Item = (&Address, Result<&Response, &Error>)> {
+        self.recipients
+            .iter()
+            .map(|(address, result)| (address, result.as_ref()))
+    }
+
+    /// Returns the recipients the message was delivered to
+    pub fn delivered(&self) -> impl Iterator<Item = &Address> {
+        self.iter()
+            .filter(|(_, result)| result.is_ok())
+            .map(|(address, _)| address)
+    }
+
+    /// Returns the recipients the message wasn't delivered to, with the error
+    pub fn rejected(&self) -> impl Iterator<Item = (&Address, &Error)> {
+        self.iter()
+            .filter_map(|(address, result)| result.err().map(|err| (address, err)))
+    }
+
+    /// Returns true if the message was delivered to every recipient
+    pub fn is_delivered(&self) -> bool {
+        self.rejected().next().is_none()
+    }
+}
+
+/// Delivers emails using the LMTP protocol
+#[derive(Debug, Clone)]
+pub struct LmtpTransport {
+    target: ConnectionTarget,
+    hello_name: ClientId,
+    timeout: Option<Duration>,
+}
+
+impl LmtpTransport {
+    /// Creates a new LMTP transport connecting to `server` over TCP, on port [`LMTP_PORT`]
+    pub fn builder<T: Into<String>>(server: T) -> LmtpTransportBuilder {
+        LmtpTransportBuilder::new(ConnectionTarget::Tcp(server.into(), LMTP_PORT))
+    }
+
+    /// Creates a new LMTP transport connecting to the Unix domain socket at `path`
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    pub fn unix<P: AsRef<Path>>(path: P) -> LmtpTransportBuilder {
+        LmtpTransportBuilder::new(ConnectionTarget::Unix(path.as_ref().to_path_buf()))
+    }
+
+    fn connection(&self) -> Result<SmtpConnection, Error> {
+        match &self.target {
+            ConnectionTarget::Tcp(server, port) => SmtpConnection::connect_lmtp(
+                TcpTarget::new(server, *port),
+                self.timeout,
+                &self.hello_name,
+            ),
+            #[cfg(unix)]
+            ConnectionTarget::Unix(path) => {
+                SmtpConnection::connect_lmtp_unix(path, self.timeout, &self.hello_name)
+            }
+        }
+    }
+}
+
+/// Builder for the [`LmtpTransport`]
+#[derive(Debug, Clone)]
+pub struct LmtpTransportBuilder {
+    transport: LmtpTransport,
+}
+
+impl LmtpTransportBuilder {
+    fn new(target: ConnectionTarget) -> Self {
+        Self {
+            transport: LmtpTransport {
+                target,
+                hello_name: ClientId::default(),
+                timeout: Some(DEFAULT_TIMEOUT),
+            },
+        }
+    }
+
+    /// Set the name used during LHLO
+    pub fn hello_name(mut self, name: ClientId) -> Self {
+        self.transport.hello_name = name;
+        self
+    }
+
+    /// Set the timeout duration
+    pub fn timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.transport.timeout = timeout;
+        self
+    }
+
+    /// Set the port to use
+    ///
+    /// Ignored when connecting to a Unix domain socket.
+    pub fn port(mut self, port: u16) -> Self {
+        self.transport.target.set_port(port);
+        self
+    }
+
+    /// Build the transport
+    pub fn build(self) -> LmtpTransport {
+        self.transport
+    }
+}
+
+impl Transport for LmtpTransport {
+    type Ok = LmtpResponse;
+    type Error = Error;
+
+    /// Delivers an email
+    ///
+    /// An error is returned if the whole transaction failed. Otherwise, the returned
+    /// [`LmtpResponse`] tells to which recipients the email was delivered.
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let mut conn = self.connection()?;
+        let response = conn.send_lmtp(envelope, email)?;
+        conn.quit()?;
+
+        Ok(response)
+    }
+}
@@ -11,6 +11,9 @@
 //! * AUTH ([RFC 4954](https://tools.ietf.org/html/rfc4954)) with PLAIN, LOGIN and XOAUTH2 mechanisms
 //! * STARTTLS ([RFC 2487](https://tools.ietf.org/html/rfc2487))
 //!
+//! Local delivery with LMTP ([RFC 2033](https://tools.ietf.org/html/rfc2033)) is available
+//! in the [`lmtp`] module, and delivery to the MX hosts of a domain in the [`direct`] module.
+//!
 //! #### SMTP Transport
 //!
 //! This transport uses the SMTP protocol to send emails over the network (locally or remotely).
@@ -117,13 +120,13 @@
 //! ```
 
 #[cfg(any(feature = "tokio1", feature = "async-std1"))]
-pub use self::async_transport::{AsyncSmtpTransport, AsyncSmtpTransportBuilder};
+pub use self::async_transport::{AsyncResolver, AsyncSmtpTransport, AsyncSmtpTransportBuilder};
 #[cfg(any(feature = "r2d2", feature = "pool"))]
 pub use self::pool::PoolConfig;
 #[cfg(feature = "r2d2")]
 pub(crate) use self::transport::SmtpClient;
 pub use self::{
-    error::Error,
+    error::{Error, Phase},
     transport::{SmtpTransport, SmtpTransportBuilder},
 };
 #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
@@ -131,22 +134,47 @@ use crate::transport::smtp::client::TlsParameters;
 use crate::transport::smtp::{
     authentication::{Credentials, Mechanism, DEFAULT_MECHANISMS},
     client::SmtpConnection,
+    commands::XClient,
+    dns_cache::DnsCache,
     extension::ClientId,
+    observer::{TlsInfo, TransportObserver},
+    relay::{RelaySelection, Relays},
     response::Response,
+    transcript::TranscriptConfig,
+};
+use client::{ProxyProtocol, Tls};
+#[cfg(unix)]
+use std::path::PathBuf;
+#[cfg(feature = "socket2")]
+use std::sync::Mutex;
+use std::{
+    io,
+    net::{Ipv6Addr, SocketAddr, SocketAddrV6, ToSocketAddrs},
+    ops::RangeInclusive,
+    sync::Arc,
+    time::Duration,
+    vec,
 };
-use client::Tls;
-use std::time::Duration;
+
+#[cfg(feature = "socket2")]
+use socket2::Socket;
 
 #[cfg(any(feature = "tokio1", feature = "async-std1"))]
 mod async_transport;
 pub mod authentication;
 pub mod client;
 pub mod commands;
+pub mod direct;
+mod dns_cache;
 mod error;
 pub mod extension;
+pub mod lmtp;
+pub mod observer;
 #[cfg(any(feature = "r2d2", feature = "pool"))]
 mod pool;
+pub mod relay;
 pub mod response;
+pub mod transcript;
 mod transport;
 pub(super) mod util;
 
@@ -166,14 +194,135 @@ pub const SUBMISSIONS_PORT: u16 = 465;
 /// Default timeout
 const DEFAULT_TIMEOUT: Duration = Duration::from_secs(60);
 
+/// Delay between two connection attempts
+const CONNECT_RETRY_DELAY: Duration = Duration::from_millis(200);
+
+/// Maximum number of transactions an email is split in when the server replies `452`
+/// (too many recipients), if enabled
+const MAX_SPLIT_TRANSACTIONS: usize = 20;
+
+/// Rejects the messages larger than `max_message_size`, before any network I/O
+fn check_message_size(max_message_size: Option<usize>, email: &[u8]) -> Result<(), Error> {
+    match max_message_size {
+        Some(max_message_size) if email.len() > max_message_size => Err(error::client(format!(
+            "message of {} bytes exceeds the maximum message size of {} bytes",
+            email.len(),
+            max_message_size
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Where to connect to reach the server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectionTarget {
+    /// Host name or IP address, and port of the server
+    ///
+    /// IPv6 addresses can carry a zone ID to reach link-local hosts, like `fe80::1%eth0`.
+    Tcp(String, u16),
+    /// Path of a Unix domain socket the server listens on
+    ///
+    /// TLS isn't supported on Unix domain sockets.
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    Unix(PathBuf),
+}
+
+impl ConnectionTarget {
+    /// Changes the port of a TCP target, does nothing for a Unix domain socket
+    pub(crate) fn set_port(&mut self, port: u16) {
+        #[allow(irrefutable_let_patterns)]
+        if let ConnectionTarget::Tcp(_, ref mut current) = self {
+            *current = port;
+        }
+    }
+}
+
+/// Host and port of a TCP target, resolved with support for IPv6 zone IDs
+///
+/// IPv6 addresses can be enclosed in brackets, and carry a zone ID to reach link-local
+/// addresses, like `fe80::1%eth0` or `[fe80::1%2]`. Numeric zone IDs are used as the
+/// scope ID of the address, interface names are resolved by the system.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct TcpTarget<'a> {
+    host: &'a str,
+    port: u16,
+    cache: Option<&'a DnsCache>,
+}
+
+impl<'a> TcpTarget<'a> {
+    pub(crate) fn new(host: &'a str, port: u16) -> Self {
+        Self {
+            host,
+            port,
+            cache: None,
+        }
+    }
+
+    /// Reuses the addresses of the host found in `cache`, and stores them there once
+    /// resolved
+    pub(crate) fn cached(mut self, cache: Option<&'a DnsCache>) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Host to give to a resolver, without the brackets around IPv6 addresses
+    pub(crate) fn host(&self) -> &'a str {
+        self.host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(self.host)
+    }
+
+    /// Address of an IPv6 host with a numeric zone ID, which doesn't need to be resolved
+    pub(crate) fn scoped_addr(&self) -> Option<SocketAddr> {
+        let host = self.host();
+        let zone_start = host.find('%')?;
+        let addr = host[..zone_start].parse::<Ipv6Addr>().ok()?;
+        let scope_id = host[zone_start + 1..].parse::<u32>().ok()?;
+        Some(SocketAddr::V6(SocketAddrV6::new(
+            addr, self.port, 0, scope_id,
+        )))
+    }
+}
+
+impl ToSocketAddrs for TcpTarget<'_> {
+    type Iter = vec::IntoIter<SocketAddr>;
+
+    fn to_socket_addrs(&self) -> io::Result<Self::Iter> {
+        if let Some(addr) = self.scoped_addr() {
+            return Ok(vec![addr].into_iter());
+        }
+
+        match self.cache {
+            Some(cache) => match cache.get(self.host(), self.port) {
+                Some(addrs) => Ok(addrs.into_iter()),
+                None => {
+                    let addrs = (self.host(), self.port)
+                        .to_socket_addrs()?
+                        .collect::<Vec<_>>();
+                    cache.insert(self.host(), self.port, &addrs);
+                    Ok(addrs.into_iter())
+                }
+            },
+            None => (self.host(), self.port).to_socket_addrs(),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 struct SmtpInfo {
-    /// Name sent during EHLO
-    hello_name: ClientId,
+    /// Name sent during EHLO, derived from the local address of the connection if `None`
+    hello_name: Option<ClientId>,
     /// Server we are connecting to
-    server: String,
-    /// Port to connect to
-    port: u16,
+    target: ConnectionTarget,
+    /// Socket connected by the caller, used by the first connection instead of `target`
+    #[cfg(feature = "socket2")]
+    socket: Option<Arc<Mutex<Option<Socket>>>>,
+    /// Servers tried in turn when a connection can't be opened to `target`
+    fallback_targets: Vec<ConnectionTarget>,
+    /// Order in which `target` and the fallback servers are tried
+    relay_selection: RelaySelection,
     /// TLS security configuration
     tls: Tls,
     /// Optional enforced authentication mechanism
@@ -183,18 +332,132 @@ struct SmtpInfo {
     /// Define network timeout
     /// It can be changed later for specific needs (like a different timeout for each SMTP command)
     timeout: Option<Duration>,
+    /// Timeout for receiving the whole greeting of the server, defaults to `timeout`
+    greeting_timeout: Option<Duration>,
+    /// Number of times to retry connecting to the server after a connection error
+    connect_retries: u32,
+    /// Time during which the addresses the servers are resolved to are reused, if set
+    dns_cache_ttl: Option<Duration>,
+    /// Whether `QUIT` waits for the reply of the server before closing the connection
+    quit_wait: bool,
+    /// Local ports to connect from, tried in turn until one is available
+    source_port_range: Option<RangeInclusive<u16>>,
+    /// PROXY protocol header to send before the greeting of the server
+    proxy_protocol: Option<ProxyProtocol>,
+    /// Attributes of the original client to forward with `XCLIENT`
+    xclient: Option<XClient>,
+    /// Notified of the connections and emails sent
+    observer: Option<Arc<dyn TransportObserver>>,
+    /// Limits of the transcripts recorded by the connections, if enabled
+    transcript: Option<TranscriptConfig>,
+    /// Size in bytes of the largest message sent, larger ones are rejected without connecting
+    max_message_size: Option<usize>,
+    /// Number of recipients sent in a transaction, larger envelopes are split
+    max_recipients_per_transaction: Option<usize>,
+    /// Whether the transactions are split when the server replies `452` (too many recipients)
+    split_on_too_many_recipients: bool,
+    /// Whether the `Return-Path` headers of the messages are left out when sending them
+    #[cfg(feature = "builder")]
+    strip_return_path: bool,
+}
+
+impl SmtpInfo {
+    /// Relay hosts to connect to, with their health
+    fn relays(&self) -> Arc<Relays> {
+        Arc::new(Relays::new(
+            self.target.clone(),
+            self.fallback_targets.clone(),
+            self.relay_selection,
+            self.observer.clone(),
+        ))
+    }
+
+    /// Cache of the addresses the servers are resolved to, if enabled
+    fn dns_cache(&self) -> Option<Arc<DnsCache>> {
+        self.dns_cache_ttl.map(|ttl| Arc::new(DnsCache::new(ttl)))
+    }
+}
+
+/// Describes the encryption with `tls` of a connection ready to send emails
+#[allow(unused_variables)]
+fn tls_info(tls: &Tls, encrypted: bool) -> Option<TlsInfo> {
+    match tls {
+        #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+        Tls::Wrapper(tls_parameters) => Some(TlsInfo {
+            domain: tls_parameters.domain().to_owned(),
+            starttls: false,
+        }),
+        #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+        Tls::Opportunistic(tls_parameters) | Tls::Required(tls_parameters) if encrypted => {
+            Some(TlsInfo {
+                domain: tls_parameters.domain().to_owned(),
+                starttls: true,
+            })
+        }
+        _ => None,
+    }
 }
 
 impl Default for SmtpInfo {
     fn default() -> Self {
         Self {
-            server: "localhost".to_string(),
-            port: SMTP_PORT,
-            hello_name: ClientId::default(),
+            target: ConnectionTarget::Tcp("localhost".to_string(), SMTP_PORT),
+            #[cfg(feature = "socket2")]
+            socket: None,
+            fallback_targets: Vec::new(),
+            relay_selection: RelaySelection::Ordered,
+            hello_name: None,
             credentials: None,
             authentication: DEFAULT_MECHANISMS.into(),
             timeout: Some(DEFAULT_TIMEOUT),
+            greeting_timeout: None,
+            connect_retries: 0,
+            dns_cache_ttl: None,
+            quit_wait: true,
+            source_port_range: None,
+            proxy_protocol: None,
+            xclient: None,
+            observer: None,
+            transcript: None,
+            max_message_size: None,
+            max_recipients_per_transaction: None,
+            split_on_too_many_recipients: false,
+            #[cfg(feature = "builder")]
+            strip_return_path: true,
             tls: Tls::None,
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn tcp_target_host() {
+        assert_eq!(
+            TcpTarget::new("smtp.example.com", 25).host(),
+            "smtp.example.com"
+        );
+        assert_eq!(TcpTarget::new("[::1]", 25).host(), "::1");
+        assert_eq!(TcpTarget::new("[fe80::1%eth0]", 25).host(), "fe80::1%eth0");
+    }
+
+    #[test]
+    fn tcp_target_zone_id() {
+        let expected = SocketAddr::V6(SocketAddrV6::new("fe80::1".parse().unwrap(), 25, 0, 2));
+        for host in &["fe80::1%2", "[fe80::1%2]"] {
+            let target = TcpTarget::new(host, 25);
+            assert_eq!(target.scoped_addr(), Some(expected));
+            assert_eq!(
+                target.to_socket_addrs().unwrap().collect::<Vec<_>>(),
+                [expected]
+            );
+        }
+
+        // Interface names are resolved by the system
+        assert_eq!(TcpTarget::new("fe80::1%eth0", 25).scoped_addr(), None);
+        assert_eq!(TcpTarget::new("fe80::1", 25).scoped_addr(), None);
+        assert_eq!(TcpTarget::new("127.0.0.1", 25).scoped_addr(), None);
+    }
+}
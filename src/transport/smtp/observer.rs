@@ -0,0 +1,272 @@
+//! Observation of the SMTP transports, to collect metrics
+//!
+//! A [`TransportObserver`] set on the transport builder is notified when connections
+//! are opened, authenticated and closed, and when messages are sent, with the time
+//! spent in each [`Phase`] of the SMTP dialogue. It can feed counters and histograms
+//! to any metrics system. The transports don't do anything more when no observer
+//! is set.
+//!
+//! The connections opened by the pools are observed as well, and the panics of the
+//! observer are caught so that they can't break the pool.
+//!
+//! ```rust
+//! # #[cfg(feature = "builder")]
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use std::{
+//!     sync::atomic::{AtomicU64, Ordering},
+//!     time::Duration,
+//! };
+//!
+//! use lettre::transport::smtp::{observer::TransportObserver, response::Response, Error};
+//! use lettre::SmtpTransport;
+//!
+//! #[derive(Debug, Default)]
+//! struct Counters {
+//!     sent: AtomicU64,
+//!     failed: AtomicU64,
+//!     bytes: AtomicU64,
+//! }
+//!
+//! impl TransportObserver for Counters {
+//!     fn on_send(&self, result: Result<&Response, &Error>, _duration: Duration, size: usize) {
+//!         match result {
+//!             Ok(_) => {
+//!                 self.sent.fetch_add(1, Ordering::Relaxed);
+//!                 self.bytes.fetch_add(size as u64, Ordering::Relaxed);
+//!             }
+//!             Err(_) => {
+//!                 self.failed.fetch_add(1, Ordering::Relaxed);
+//!             }
+//!         }
+//!     }
+//! }
+//!
+//! let mailer = SmtpTransport::builder_dangerous("localhost")
+//!     .observer(Counters::default())
+//!     .build();
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "builder"))]
+//! # fn main() {}
+//! ```
+
+use std::{
+    fmt::Debug,
+    io,
+    net::SocketAddr,
+    panic::{self, AssertUnwindSafe},
+    sync::Arc,
+    time::Duration,
+};
+
+use super::{
+    error::Phase, extension::ServerInfo, relay::RelayHealth, response::Response,
+    transcript::Transcript, Error,
+};
+
+/// Receives the events of a SMTP transport
+///
+/// All the methods do nothing by default. They are called synchronously, on the thread
+/// or the task using the transport, or opening connections for the pool, so they should
+/// be quick. Their panics are caught and ignored.
+pub trait TransportObserver: Debug + Send + Sync + 'static {
+    /// A connection is ready to send emails, after `duration`
+    ///
+    /// The duration includes the retries, the greeting of the server, `EHLO`,
+    /// `STARTTLS` and `AUTH`. `tls` tells whether the connection is encrypted.
+    fn on_connect(&self, duration: Duration, tls: bool) {
+        let _ = (duration, tls);
+    }
+
+    /// A connection is ready to send emails
+    ///
+    /// Called after [`TransportObserver::on_connect`], with the greeting and the
+    /// capabilities of the server.
+    fn on_connected(&self, info: &ConnectionInfo) {
+        let _ = info;
+    }
+
+    /// The connection was encrypted, before being ready to send emails
+    ///
+    /// Not called for unencrypted connections, including when the server doesn't
+    /// advertise `STARTTLS` with [`Tls::Opportunistic`](super::client::Tls::Opportunistic).
+    fn on_tls_established(&self, info: &TlsInfo) {
+        let _ = info;
+    }
+
+    /// The connection was authenticated, or the server rejected the credentials,
+    /// after `duration`
+    ///
+    /// Only called when credentials are set, before [`TransportObserver::on_connect`]
+    /// or [`TransportObserver::on_connection_error`].
+    fn on_auth(&self, result: Result<(), &Error>, duration: Duration) {
+        let _ = (result, duration);
+    }
+
+    /// `duration` was spent in `phase` of the SMTP dialogue
+    ///
+    /// Called after a connection is ready to send emails, for the phases of opening it,
+    /// and after each email is sent, successfully or not, for the phases of sending it.
+    /// The durations of all the commands are summed in [`Phase::Command`].
+    fn on_phase(&self, phase: Phase, duration: Duration) {
+        let _ = (phase, duration);
+    }
+
+    /// A connection couldn't be opened
+    ///
+    /// Called once all the retries failed, including when `STARTTLS` or `AUTH` failed.
+    fn on_connection_error(&self, error: &Error) {
+        let _ = error;
+    }
+
+    /// An email of `size` bytes was sent, or failed to be sent, in `duration`
+    ///
+    /// The duration includes getting a connection. The errors can be classified
+    /// with the methods of [`Error`].
+    fn on_send(&self, result: Result<&Response, &Error>, duration: Duration, size: usize) {
+        let _ = (result, duration, size);
+    }
+
+    /// A connection was closed
+    fn on_connection_closed(&self, reason: CloseReason) {
+        let _ = reason;
+    }
+
+    /// The dialogue with the server for sending an email, successfully or not
+    ///
+    /// Only called when [`capture_transcript`](super::SmtpTransportBuilder::capture_transcript)
+    /// is set, after [`TransportObserver::on_send`].
+    fn on_transcript(&self, transcript: &Transcript) {
+        let _ = transcript;
+    }
+
+    /// A relay host went down, because a connection couldn't be opened to it,
+    /// or is up again
+    ///
+    /// See the [`relay`](super::relay) module.
+    fn on_relay_health(&self, health: &RelayHealth) {
+        let _ = health;
+    }
+}
+
+/// Information about a connection ready to send emails
+#[derive(Debug, Clone)]
+pub struct ConnectionInfo {
+    peer_addr: Option<SocketAddr>,
+    greeting: Response,
+    server_info: ServerInfo,
+    encrypted: bool,
+}
+
+impl ConnectionInfo {
+    pub(crate) fn new(
+        peer_addr: io::Result<SocketAddr>,
+        greeting: &Response,
+        server_info: &ServerInfo,
+        encrypted: bool,
+    ) -> Self {
+        Self {
+            peer_addr: peer_addr.ok(),
+            greeting: greeting.clone(),
+            server_info: server_info.clone(),
+            encrypted,
+        }
+    }
+
+    /// Address of the server, `None` for Unix domain sockets
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// Greeting sent by the server when the client connected
+    pub fn greeting(&self) -> &Response {
+        &self.greeting
+    }
+
+    /// Name and capabilities advertised by the server in reply to `EHLO`
+    pub fn server_info(&self) -> &ServerInfo {
+        &self.server_info
+    }
+
+    /// Tells whether the connection is encrypted
+    pub fn is_encrypted(&self) -> bool {
+        self.encrypted
+    }
+}
+
+/// Information about the encryption of a connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TlsInfo {
+    pub(crate) domain: String,
+    pub(crate) starttls: bool,
+}
+
+impl TlsInfo {
+    /// Domain name validated with the certificate of the server
+    pub fn domain(&self) -> &str {
+        &self.domain
+    }
+
+    /// Tells whether the connection was upgraded with `STARTTLS`, rather than being
+    /// encrypted from the start
+    pub fn is_starttls(&self) -> bool {
+        self.starttls
+    }
+}
+
+/// Why a connection was closed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum CloseReason {
+    /// The connection isn't needed anymore, and was closed with `QUIT`
+    Quit,
+    /// The connection was dropped after an error, or closed by the server
+    Broken,
+    /// The connection was dropped by the pool after being idle for too long
+    Idle,
+}
+
+/// Phases of the SMTP dialogue, in order
+const PHASES: [Phase; 6] = [
+    Phase::Connect,
+    Phase::TlsHandshake,
+    Phase::Greeting,
+    Phase::Command,
+    Phase::Message,
+    Phase::MessageReply,
+];
+
+/// Time spent in each phase of the SMTP dialogue over a connection, since they
+/// were last taken
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PhaseTimings([Option<Duration>; PHASES.len()]);
+
+impl PhaseTimings {
+    /// Adds `duration` to the time spent in `phase`
+    pub(crate) fn record(&mut self, phase: Phase, duration: Duration) {
+        if let Some(index) = PHASES.iter().position(|known| *known == phase) {
+            *self.0[index].get_or_insert(Duration::from_secs(0)) += duration;
+        }
+    }
+}
+
+/// Notifies the observer of the time spent in each phase, in the order of the dialogue
+pub(crate) fn notify_phases(observer: Option<&Arc<dyn TransportObserver>>, timings: PhaseTimings) {
+    notify(observer, |observer| {
+        for (phase, duration) in PHASES.iter().zip(timings.0.iter()) {
+            if let Some(duration) = duration {
+                observer.on_phase(*phase, *duration);
+            }
+        }
+    });
+}
+
+/// Calls `f` with the observer if there's one, ignoring its panics
+pub(crate) fn notify<F>(observer: Option<&Arc<dyn TransportObserver>>, f: F)
+where
+    F: FnOnce(&dyn TransportObserver),
+{
+    if let Some(observer) = observer {
+        let _ = panic::catch_unwind(AssertUnwindSafe(|| f(observer.as_ref())));
+    }
+}
@@ -13,13 +13,15 @@ use crate::transport::smtp::async_transport::AsyncSmtpClient;
 use crate::Executor;
 
 use super::super::client::AsyncSmtpConnection;
+use super::super::observer::{notify, CloseReason};
 use super::super::Error;
-use super::PoolConfig;
+use super::{PoolConfig, ReconnectJitter};
 
 pub struct Pool<E: Executor> {
     config: PoolConfig,
     connections: Mutex<Vec<ParkedConnection>>,
     client: AsyncSmtpClient<E>,
+    reconnect_jitter: ReconnectJitter,
     handle: OnceCell<E::Handle>,
 }
 
@@ -36,6 +38,7 @@ pub struct PooledConnection<E: Executor> {
 impl<E: Executor> Pool<E> {
     pub fn new(config: PoolConfig, client: AsyncSmtpClient<E>) -> Arc<Self> {
         let pool = Arc::new(Self {
+            reconnect_jitter: ReconnectJitter::new(config.reconnect_jitter),
             config,
             connections: Mutex::new(Vec::new()),
             client,
@@ -78,7 +81,7 @@ impl<E: Executor> Pool<E> {
                             #[cfg(feature = "tracing")]
                             let mut created = 0;
                             for _ in count..=(min_idle as usize) {
-                                let conn = match pool.client.connection().await {
+                                let conn = match pool.connect().await {
                                     Ok(conn) => conn,
                                     Err(err) => {
                                         #[cfg(feature = "tracing")]
@@ -108,8 +111,12 @@ impl<E: Executor> Pool<E> {
                                 #[cfg(feature = "tracing")]
                                 tracing::debug!("dropped {} idle connections", dropped.len());
 
+                                let count = dropped.len();
                                 abort_concurrent(dropped.into_iter().map(|conn| conn.unpark()))
                                     .await;
+                                for _ in 0..count {
+                                    pool.closed(CloseReason::Idle);
+                                }
                             }
                         }
                         None => {
@@ -142,14 +149,40 @@ impl<E: Executor> Pool<E> {
 
             match conn {
                 Some(conn) => {
+                    // The server may be closing the connection
+                    let idle_too_long = match self.config.server_idle_timeout {
+                        Some(timeout) => conn.idle_duration() >= timeout,
+                        None => false,
+                    };
+                    if idle_too_long {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("dropping a connection idle for too long");
+
+                        conn.unpark().abort().await;
+                        self.closed(CloseReason::Idle);
+                        continue;
+                    }
+
                     let mut conn = conn.unpark();
 
+                    // The host may be down, prefer connecting to the next one
+                    if self.client.relays().is_down(conn.relay()) {
+                        #[cfg(feature = "tracing")]
+                        tracing::debug!("dropping a connection to a relay host which is down");
+
+                        conn.abort().await;
+                        self.closed(CloseReason::Broken);
+                        continue;
+                    }
+
                     // TODO: handle the client try another connection if this one isn't good
                     if !conn.test_connected().await {
                         #[cfg(feature = "tracing")]
                         tracing::debug!("dropping a broken connection");
 
                         conn.abort().await;
+                        self.reconnect_jitter.failed();
+                        self.closed(CloseReason::Broken);
                         continue;
                     }
 
@@ -162,13 +195,27 @@ impl<E: Executor> Pool<E> {
                     #[cfg(feature = "tracing")]
                     tracing::debug!("creating a new connection");
 
-                    let conn = self.client.connection().await?;
+                    let conn = self.connect().await?;
                     return Ok(PooledConnection::wrap(conn, self.clone()));
                 }
             }
         }
     }
 
+    /// Opens a new connection, after a random delay if the last one failed
+    async fn connect(&self) -> Result<AsyncSmtpConnection, Error> {
+        if let Some(delay) = self.reconnect_jitter.delay() {
+            E::sleep(delay).await;
+        }
+
+        let result = self.client.connection().await;
+        match result {
+            Ok(_) => self.reconnect_jitter.connected(),
+            Err(_) => self.reconnect_jitter.failed(),
+        }
+        result
+    }
+
     async fn recycle(&self, mut conn: AsyncSmtpConnection) {
         if conn.has_broken() {
             #[cfg(feature = "tracing")]
@@ -176,6 +223,7 @@ impl<E: Executor> Pool<E> {
 
             conn.abort().await;
             drop(conn);
+            self.closed(CloseReason::Broken);
         } else {
             #[cfg(feature = "tracing")]
             tracing::debug!("recycling connection");
@@ -184,12 +232,20 @@ impl<E: Executor> Pool<E> {
             if connections.len() >= self.config.max_size as usize {
                 drop(connections);
                 conn.abort().await;
+                self.closed(CloseReason::Quit);
             } else {
                 let conn = ParkedConnection::park(conn);
                 connections.push(conn);
             }
         }
     }
+
+    /// Notifies the observer that a connection was closed
+    fn closed(&self, reason: CloseReason) {
+        notify(self.client.observer(), |observer| {
+            observer.on_connection_closed(reason)
+        });
+    }
 }
 
 impl<E: Executor> Debug for Pool<E> {
@@ -223,12 +279,19 @@ impl<E: Executor> Drop for Pool<E> {
 
         let connections = mem::take(self.connections.get_mut());
         let handle = self.handle.take();
+        let observer = self.client.observer().cloned();
         E::spawn(async move {
             if let Some(handle) = handle {
                 handle.shutdown().await;
             }
 
+            let count = connections.len();
             abort_concurrent(connections.into_iter().map(|conn| conn.unpark())).await;
+            for _ in 0..count {
+                notify(observer.as_ref(), |observer| {
+                    observer.on_connection_closed(CloseReason::Quit)
+                });
+            }
         });
     }
 }
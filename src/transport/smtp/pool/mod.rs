@@ -1,4 +1,10 @@
-use std::time::Duration;
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 #[cfg(all(feature = "pool", any(feature = "tokio1", feature = "async-std1")))]
 pub mod async_impl;
@@ -14,6 +20,8 @@ pub struct PoolConfig {
     max_size: u32,
     connection_timeout: Duration,
     idle_timeout: Duration,
+    server_idle_timeout: Option<Duration>,
+    reconnect_jitter: Option<Duration>,
 }
 
 impl PoolConfig {
@@ -55,6 +63,32 @@ impl PoolConfig {
         self.idle_timeout = idle_timeout;
         self
     }
+
+    /// Idle timeout of the server
+    ///
+    /// Connections idle for longer are never reused: they are closed and a new connection
+    /// is opened instead, as the server may be closing them at the same time. Unlike
+    /// [`PoolConfig::idle_timeout`], it is checked when taking a connection out of the pool.
+    /// Use a value slightly shorter than the timeout of the server.
+    ///
+    /// Defaults to no timeout
+    pub fn server_idle_timeout(mut self, server_idle_timeout: Duration) -> Self {
+        self.server_idle_timeout = Some(server_idle_timeout);
+        self
+    }
+
+    /// Maximum random delay before opening a connection after a failure
+    ///
+    /// When the server closed the pooled connections, or a connection couldn't be
+    /// opened, new connections are opened after a random delay shorter than
+    /// `reconnect_jitter`, until one is opened successfully. This spreads the
+    /// reconnections of many clients to a restarting server over time.
+    ///
+    /// Defaults to no delay
+    pub fn reconnect_jitter(mut self, reconnect_jitter: Duration) -> Self {
+        self.reconnect_jitter = Some(reconnect_jitter);
+        self
+    }
 }
 
 impl Default for PoolConfig {
@@ -64,6 +98,67 @@ impl Default for PoolConfig {
             max_size: 10,
             connection_timeout: Duration::from_secs(30),
             idle_timeout: Duration::from_secs(60),
+            server_idle_timeout: None,
+            reconnect_jitter: None,
         }
     }
 }
+
+/// Delays the connections opened after a failure, see [`PoolConfig::reconnect_jitter`]
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ReconnectJitter {
+    jitter: Option<Duration>,
+    /// Whether the last connection broke or couldn't be opened
+    failed: Arc<AtomicBool>,
+}
+
+impl ReconnectJitter {
+    pub(crate) fn new(jitter: Option<Duration>) -> Self {
+        Self {
+            jitter,
+            failed: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Random delay to wait before opening a connection, if needed
+    pub(crate) fn delay(&self) -> Option<Duration> {
+        match self.jitter {
+            Some(jitter) if self.failed.load(Ordering::Relaxed) => {
+                Some(jitter.mul_f64(fastrand::f64()))
+            }
+            _ => None,
+        }
+    }
+
+    /// A pooled connection was closed by the server, or a connection couldn't be opened
+    pub(crate) fn failed(&self) {
+        self.failed.store(true, Ordering::Relaxed);
+    }
+
+    /// A connection was opened
+    pub(crate) fn connected(&self) {
+        self.failed.store(false, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn reconnect_jitter() {
+        let jitter = ReconnectJitter::new(Some(Duration::from_millis(100)));
+        assert_eq!(jitter.delay(), None);
+
+        jitter.failed();
+        let delay = jitter.delay().unwrap();
+        assert!(delay < Duration::from_millis(100));
+
+        jitter.connected();
+        assert_eq!(jitter.delay(), None);
+
+        let disabled = ReconnectJitter::new(None);
+        disabled.failed();
+        assert_eq!(disabled.delay(), None);
+    }
+}
@@ -1,19 +1,29 @@
+use std::{sync::Arc, thread};
+
 use r2d2::{CustomizeConnection, ManageConnection, Pool};
 
-use super::PoolConfig;
-use crate::transport::smtp::{client::SmtpConnection, error, error::Error, SmtpClient};
+use super::{PoolConfig, ReconnectJitter};
+use crate::transport::smtp::{
+    client::SmtpConnection,
+    error,
+    error::Error,
+    observer::{notify, CloseReason, TransportObserver},
+    SmtpClient,
+};
 
 impl PoolConfig {
-    pub(crate) fn build<C: ManageConnection<Connection = SmtpConnection, Error = Error>>(
-        &self,
-        client: C,
-    ) -> Pool<C> {
+    pub(crate) fn build(&self, mut client: SmtpClient) -> Pool<SmtpClient> {
+        client.server_idle_timeout = self.server_idle_timeout;
+        client.reconnect_jitter = ReconnectJitter::new(self.reconnect_jitter);
+        let quitter = SmtpConnectionQuitter {
+            observer: client.observer().cloned(),
+        };
         Pool::builder()
             .min_idle(Some(self.min_idle))
             .max_size(self.max_size)
             .connection_timeout(self.connection_timeout)
             .idle_timeout(Some(self.idle_timeout))
-            .connection_customizer(Box::new(SmtpConnectionQuitter))
+            .connection_customizer(Box::new(quitter))
             .build_unchecked(client)
     }
 }
@@ -23,13 +33,36 @@ impl ManageConnection for SmtpClient {
     type Error = Error;
 
     fn connect(&self) -> Result<Self::Connection, Error> {
-        self.connection()
+        if let Some(delay) = self.reconnect_jitter.delay() {
+            thread::sleep(delay);
+        }
+
+        let result = self.connection();
+        match result {
+            Ok(_) => self.reconnect_jitter.connected(),
+            Err(_) => self.reconnect_jitter.failed(),
+        }
+        result
     }
 
     fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Error> {
+        if self.relays().is_down(conn.relay()) {
+            return Err(error::network("is connected to a relay host which is down"));
+        }
+        if let Some(timeout) = self.server_idle_timeout {
+            if conn.idle_duration() >= timeout {
+                return Err(error::network("has been idle for too long"));
+            }
+        }
+        // Cheap check before sending NOOP
+        if !conn.is_idle_healthy() {
+            self.reconnect_jitter.failed();
+            return Err(error::network("has been closed by the server"));
+        }
         if conn.test_connected() {
             return Ok(());
         }
+        self.reconnect_jitter.failed();
         Err(error::network("is not connected anymore"))
     }
 
@@ -38,8 +71,10 @@ impl ManageConnection for SmtpClient {
     }
 }
 
-#[derive(Copy, Clone, Debug)]
-struct SmtpConnectionQuitter;
+#[derive(Clone, Debug)]
+struct SmtpConnectionQuitter {
+    observer: Option<Arc<dyn TransportObserver>>,
+}
 
 impl CustomizeConnection<SmtpConnection, Error> for SmtpConnectionQuitter {
     fn on_release(&self, conn: SmtpConnection) {
@@ -47,5 +82,14 @@ impl CustomizeConnection<SmtpConnection, Error> for SmtpConnectionQuitter {
         if !conn.has_broken() {
             let _quit = conn.quit();
         }
+
+        let reason = if conn.has_broken() {
+            CloseReason::Broken
+        } else {
+            CloseReason::Quit
+        };
+        notify(self.observer.as_ref(), |observer| {
+            observer.on_connection_closed(reason)
+        });
     }
 }
@@ -0,0 +1,289 @@
+//! Fallback relay hosts of the SMTP transports
+//!
+//! Providers often give several hosts to submit emails to, expecting the clients to fail
+//! over between them. The hosts set with
+//! [`SmtpTransportBuilder::fallback_relays`](super::SmtpTransportBuilder::fallback_relays)
+//! are tried in turn after the main one, when a connection can't be opened because of a
+//! connection error, like a refused connection or a failed name resolution. The TLS
+//! certificate of each fallback host is validated for its own name.
+//!
+//! A host which couldn't be connected to is considered down for 30 seconds: the other
+//! hosts are tried first, and the pooled connections to it are replaced by connections
+//! to the next host. The health of the hosts is given by `relay_health` on the transports,
+//! and to [`TransportObserver::on_relay_health`] when it changes.
+//!
+//! ```rust,no_run
+//! # #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+//! # fn test() -> Result<(), Box<dyn std::error::Error>> {
+//! use lettre::{
+//!     transport::smtp::{relay::RelaySelection, ConnectionTarget, SUBMISSIONS_PORT},
+//!     SmtpTransport,
+//! };
+//!
+//! let sender = SmtpTransport::relay("smtp1.example.com")?
+//!     .fallback_relays(vec![ConnectionTarget::Tcp(
+//!         "smtp2.example.com".to_string(),
+//!         SUBMISSIONS_PORT,
+//!     )])
+//!     .relay_selection(RelaySelection::RoundRobin)
+//!     .build();
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    fmt::{self, Debug},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, MutexGuard, PoisonError,
+    },
+    time::{Duration, Instant},
+};
+
+use super::{
+    client::Tls,
+    observer::{notify, TransportObserver},
+    ConnectionTarget,
+};
+
+/// Time during which a host which couldn't be connected to is tried last
+const RELAY_DOWN_DELAY: Duration = Duration::from_secs(30);
+
+/// Order in which the relay hosts are tried
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelaySelection {
+    /// The main host first, then the fallback hosts in order
+    Ordered,
+    /// Each new connection starts with the host following the one the previous
+    /// connection started with, spreading the connections over all the hosts
+    RoundRobin,
+}
+
+impl Default for RelaySelection {
+    fn default() -> Self {
+        RelaySelection::Ordered
+    }
+}
+
+/// Health of a relay host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelayHealth {
+    target: ConnectionTarget,
+    consecutive_failures: u32,
+    healthy: bool,
+}
+
+impl RelayHealth {
+    /// Where the host is reached
+    pub fn target(&self) -> &ConnectionTarget {
+        &self.target
+    }
+
+    /// Number of connections which couldn't be opened to the host since the last
+    /// successful one
+    pub fn consecutive_failures(&self) -> u32 {
+        self.consecutive_failures
+    }
+
+    /// Tells whether the host is up, `false` when it couldn't be connected to recently
+    pub fn is_healthy(&self) -> bool {
+        self.healthy
+    }
+}
+
+/// Relay hosts of a transport, with their health
+pub(crate) struct Relays {
+    targets: Vec<ConnectionTarget>,
+    selection: RelaySelection,
+    /// Host the next connection starts with, with round-robin selection
+    next: AtomicUsize,
+    health: Mutex<Vec<HostHealth>>,
+    observer: Option<Arc<dyn TransportObserver>>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct HostHealth {
+    consecutive_failures: u32,
+    last_failure: Option<Instant>,
+}
+
+impl HostHealth {
+    fn is_down(&self) -> bool {
+        match self.last_failure {
+            Some(last_failure) => last_failure.elapsed() < RELAY_DOWN_DELAY,
+            None => false,
+        }
+    }
+}
+
+impl Relays {
+    pub(crate) fn new(
+        main: ConnectionTarget,
+        fallbacks: Vec<ConnectionTarget>,
+        selection: RelaySelection,
+        observer: Option<Arc<dyn TransportObserver>>,
+    ) -> Self {
+        let mut targets = Vec::with_capacity(fallbacks.len() + 1);
+        targets.push(main);
+        targets.extend(fallbacks);
+
+        Self {
+            health: Mutex::new(vec![HostHealth::default(); targets.len()]),
+            targets,
+            selection,
+            next: AtomicUsize::new(0),
+            observer,
+        }
+    }
+
+    /// Where to reach the host `relay`
+    pub(crate) fn target(&self, relay: usize) -> &ConnectionTarget {
+        &self.targets[relay]
+    }
+
+    /// TLS settings to use with the host `relay`
+    ///
+    /// The certificates of the fallback hosts are validated for their own name.
+    pub(crate) fn tls(&self, relay: usize, tls: &Tls) -> Tls {
+        match &self.targets[relay] {
+            ConnectionTarget::Tcp(host, _) if relay > 0 => tls.for_domain(host),
+            _ => tls.clone(),
+        }
+    }
+
+    /// Hosts to try to open a new connection, in order
+    ///
+    /// The hosts which are down come last.
+    pub(crate) fn order(&self) -> Vec<usize> {
+        let len = self.targets.len();
+        let start = match self.selection {
+            RelaySelection::Ordered => 0,
+            RelaySelection::RoundRobin => self.next.fetch_add(1, Ordering::Relaxed) % len,
+        };
+
+        let health = self.lock();
+        let (up, down): (Vec<usize>, Vec<usize>) = (0..len)
+            .map(|i| (start + i) % len)
+            .partition(|&relay| !health[relay].is_down());
+        up.into_iter().chain(down).collect()
+    }
+
+    /// Tells whether the connections to the host `relay` should be replaced
+    #[cfg(any(feature = "r2d2", feature = "pool", test))]
+    pub(crate) fn is_down(&self, relay: usize) -> bool {
+        self.lock()[relay].is_down()
+    }
+
+    /// A connection was opened to the host `relay`
+    pub(crate) fn succeeded(&self, relay: usize) {
+        let recovered = {
+            let mut health = self.lock();
+            let recovered = health[relay].consecutive_failures > 0;
+            health[relay] = HostHealth::default();
+            recovered
+        };
+        if recovered {
+            self.notify(relay);
+        }
+    }
+
+    /// A connection couldn't be opened to the host `relay`
+    pub(crate) fn failed(&self, relay: usize) {
+        let went_down = {
+            let mut health = self.lock();
+            let went_down = !health[relay].is_down();
+            health[relay].consecutive_failures += 1;
+            health[relay].last_failure = Some(Instant::now());
+            went_down
+        };
+        if went_down {
+            self.notify(relay);
+        }
+    }
+
+    /// Health of each host, the main one first
+    pub(crate) fn health(&self) -> Vec<RelayHealth> {
+        let health = self.lock();
+        self.targets
+            .iter()
+            .zip(health.iter())
+            .map(|(target, health)| RelayHealth {
+                target: target.clone(),
+                consecutive_failures: health.consecutive_failures,
+                healthy: !health.is_down(),
+            })
+            .collect()
+    }
+
+    fn notify(&self, relay: usize) {
+        let health = self.health().swap_remove(relay);
+        notify(self.observer.as_ref(), |observer| {
+            observer.on_relay_health(&health)
+        });
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<HostHealth>> {
+        self.health.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Debug for Relays {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Relays")
+            .field("targets", &self.targets)
+            .field("selection", &self.selection)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn relays(selection: RelaySelection) -> Relays {
+        Relays::new(
+            ConnectionTarget::Tcp("smtp1.example.com".to_string(), 465),
+            vec![
+                ConnectionTarget::Tcp("smtp2.example.com".to_string(), 465),
+                ConnectionTarget::Tcp("smtp3.example.com".to_string(), 587),
+            ],
+            selection,
+            None,
+        )
+    }
+
+    #[test]
+    fn ordered() {
+        let relays = relays(RelaySelection::Ordered);
+        assert_eq!(relays.order(), vec![0, 1, 2]);
+        assert_eq!(relays.order(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn round_robin() {
+        let relays = relays(RelaySelection::RoundRobin);
+        assert_eq!(relays.order(), vec![0, 1, 2]);
+        assert_eq!(relays.order(), vec![1, 2, 0]);
+        assert_eq!(relays.order(), vec![2, 0, 1]);
+        assert_eq!(relays.order(), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn down_relays_last() {
+        let relays = relays(RelaySelection::Ordered);
+        relays.failed(0);
+        relays.failed(0);
+        assert!(relays.is_down(0));
+        assert_eq!(relays.order(), vec![1, 2, 0]);
+
+        let health = relays.health();
+        assert!(!health[0].is_healthy());
+        assert_eq!(health[0].consecutive_failures(), 2);
+        assert!(health[1].is_healthy());
+
+        relays.succeeded(0);
+        assert!(!relays.is_down(0));
+        assert_eq!(relays.order(), vec![0, 1, 2]);
+        assert_eq!(relays.health()[0].consecutive_failures(), 0);
+    }
+}
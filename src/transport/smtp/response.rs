@@ -1,7 +1,10 @@
 //! SMTP response, containing a mandatory return code and an optional text
 //! message
 
-use crate::transport::smtp::{error, Error};
+use crate::{
+    address::Address,
+    transport::smtp::{error, Error},
+};
 use nom::{
     branch::alt,
     bytes::streaming::{tag, take_until},
@@ -130,6 +133,114 @@ impl Code {
     }
 }
 
+/// Subject of an enhanced status code (second number)
+///
+/// [RFC 3463, section 3](https://tools.ietf.org/html/rfc3463#section-3)
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[non_exhaustive]
+pub enum StatusCategory {
+    /// X.0.XXX, other or undefined status, and unknown subjects
+    Other,
+    /// X.1.XXX, addressing status, like a bad destination address
+    Address,
+    /// X.2.XXX, mailbox status, like a disabled or full mailbox
+    Mailbox,
+    /// X.3.XXX, mail system status, like a full disk on the server
+    MailSystem,
+    /// X.4.XXX, network and routing status, like an unreachable host
+    NetworkRouting,
+    /// X.5.XXX, mail delivery protocol status, like an invalid command
+    Delivery,
+    /// X.6.XXX, message content or media status, like an unsupported encoding
+    Content,
+    /// X.7.XXX, security or policy status, like a rejected sender or failed authentication
+    Security,
+}
+
+impl StatusCategory {
+    /// Category of an enhanced status code subject
+    pub fn from_subject(subject: u16) -> StatusCategory {
+        match subject {
+            1 => StatusCategory::Address,
+            2 => StatusCategory::Mailbox,
+            3 => StatusCategory::MailSystem,
+            4 => StatusCategory::NetworkRouting,
+            5 => StatusCategory::Delivery,
+            6 => StatusCategory::Content,
+            7 => StatusCategory::Security,
+            _ => StatusCategory::Other,
+        }
+    }
+}
+
+/// An enhanced status code, like `5.1.1`, sent at the start of a reply text
+///
+/// [RFC 3463](https://tools.ietf.org/html/rfc3463)
+#[derive(PartialEq, Eq, Copy, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnhancedCode {
+    class: u8,
+    subject: u16,
+    detail: u16,
+}
+
+impl EnhancedCode {
+    /// Class of the status code, `2` for success, `4` for transient and `5` for
+    /// permanent failures
+    pub fn class(self) -> u8 {
+        self.class
+    }
+
+    /// Subject of the status code, see [`EnhancedCode::category`]
+    pub fn subject(self) -> u16 {
+        self.subject
+    }
+
+    /// Detail of the status code, its meaning depends on the subject
+    pub fn detail(self) -> u16 {
+        self.detail
+    }
+
+    /// Typed subject of the status code
+    pub fn category(self) -> StatusCategory {
+        StatusCategory::from_subject(self.subject)
+    }
+}
+
+impl Display for EnhancedCode {
+    fn fmt(&self, f: &mut Formatter<'_>) -> Result {
+        write!(f, "{}.{}.{}", self.class, self.subject, self.detail)
+    }
+}
+
+impl FromStr for EnhancedCode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> result::Result<EnhancedCode, Error> {
+        fn number<T: FromStr>(part: Option<&str>) -> Option<T> {
+            part.filter(|p| (1..=3).contains(&p.len()) && p.bytes().all(|b| b.is_ascii_digit()))
+                .and_then(|p| p.parse().ok())
+        }
+
+        let mut parts = s.split('.');
+        let class = number::<u8>(parts.next()).filter(|c| matches!(c, 2 | 4 | 5));
+        let subject = number(parts.next());
+        let detail = number(parts.next());
+        match (class, subject, detail, parts.next()) {
+            (Some(class), Some(subject), Some(detail), None) => Ok(EnhancedCode {
+                class,
+                subject,
+                detail,
+            }),
+            _ => Err(error::response(format!(
+                "invalid enhanced status code: {}",
+                s
+            ))),
+        }
+    }
+}
+
 /// Contains an SMTP reply, with separated code and message
 ///
 /// The text message is optional, only the code is mandatory
@@ -181,6 +292,15 @@ impl Response {
         self.message.first().map(String::as_str)
     }
 
+    /// Enhanced status code at the start of the message, if the server sent one
+    ///
+    /// The code is ignored if its class doesn't match the first digit of the reply code.
+    pub fn enhanced_code(&self) -> Option<EnhancedCode> {
+        self.first_word()
+            .and_then(|word| word.parse::<EnhancedCode>().ok())
+            .filter(|enhanced| enhanced.class == self.code.severity as u8)
+    }
+
     /// Response code
     pub fn code(&self) -> Code {
         self.code
@@ -192,6 +312,208 @@ impl Response {
     }
 }
 
+/// A mailbox returned by the server in reply to a `VRFY` or `EXPN` command
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VerifiedMailbox {
+    /// The full name of the user, if returned by the server
+    pub name: Option<String>,
+    /// The mailbox address
+    pub email: Address,
+}
+
+impl FromStr for VerifiedMailbox {
+    type Err = Error;
+
+    fn from_str(line: &str) -> result::Result<VerifiedMailbox, Error> {
+        let line = line.trim();
+
+        let (name, email) = match (line.rfind('<'), line.strip_suffix('>')) {
+            (Some(start), Some(without_end)) => {
+                let name = line[..start].trim().trim_matches('"').trim();
+                let name = if name.is_empty() {
+                    None
+                } else {
+                    Some(name.to_string())
+                };
+                (name, &without_end[start + 1..])
+            }
+            _ => (None, line),
+        };
+
+        let email = email.parse().map_err(error::response)?;
+        Ok(VerifiedMailbox { name, email })
+    }
+}
+
+/// Result of a `VRFY` or `EXPN` command
+///
+/// [RFC 5321, section 3.5](https://tools.ietf.org/html/rfc5321#section-3.5)
+#[derive(PartialEq, Eq, Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Verification {
+    /// The server returned the mailbox (`VRFY`) or the members of the mailing
+    /// list (`EXPN`)
+    Mailboxes(Vec<VerifiedMailbox>),
+    /// The server could not verify the argument but would accept a message for it
+    /// (252 reply code)
+    Unverified,
+    /// The server does not implement the command, or has disabled it
+    /// (500, 502 or 504 reply code)
+    NotSupported,
+}
+
+impl Verification {
+    /// Parses a positive `VRFY` or `EXPN` response
+    ///
+    /// The enhanced status code starting each line, if any, is skipped.
+    pub fn from_response(response: &Response) -> result::Result<Verification, Error> {
+        if response.has_code(252) {
+            return Ok(Verification::Unverified);
+        }
+
+        if !(response.has_code(250) || response.has_code(251)) {
+            return Err(error::response("Unexpected reply code"));
+        }
+
+        let enhanced = response.enhanced_code().is_some();
+        response
+            .message()
+            .map(|line| {
+                let line = line.trim_start();
+                match line.split_whitespace().next() {
+                    Some(word) if enhanced && word.parse::<EnhancedCode>().is_ok() => {
+                        &line[word.len()..]
+                    }
+                    _ => line,
+                }
+            })
+            .map(str::parse)
+            .collect::<result::Result<Vec<VerifiedMailbox>, Error>>()
+            .map(Verification::Mailboxes)
+    }
+
+    /// Builds the result of a `VRFY` or `EXPN` command, turning "command not
+    /// supported" errors into `Verification::NotSupported`
+    pub(crate) fn from_result(
+        result: result::Result<Response, Error>,
+    ) -> result::Result<Verification, Error> {
+        match result {
+            Ok(response) => Verification::from_response(&response),
+            Err(err) => match err.status() {
+                // 500, 502 and 504
+                Some(Code {
+                    severity: Severity::PermanentNegativeCompletion,
+                    category: Category::Syntax,
+                    detail,
+                }) if matches!(detail, Detail::Zero | Detail::Two | Detail::Four) => {
+                    Ok(Verification::NotSupported)
+                }
+                _ => Err(err),
+            },
+        }
+    }
+}
+
+/// Result of a dry run of an envelope, without sending any message
+///
+/// Holds the reply to the `MAIL` command, and the reply to the `RCPT` command of
+/// each recipient.
+#[derive(Debug)]
+pub struct EnvelopeVerification {
+    pub(crate) sender: Response,
+    pub(crate) recipients: Vec<(Address, result::Result<Response, Error>)>,
+}
+
+impl EnvelopeVerification {
+    /// Returns the reply of the server to the `MAIL` command
+    pub fn sender(&self) -> &Response {
+        &self.sender
+    }
+
+    /// Returns the recipients with the reply of the server, in the order of the envelope
+    pub fn iter(&self) -> impl Iterator<Item = (&Address, result::Result<&Response, &Error>)> {
+        self.recipients
+            .iter()
+            .map(|(address, result)| (address, result.as_ref()))
+    }
+
+    /// Returns the recipients accepted by the server
+    pub fn accepted(&self) -> impl Iterator<Item = &Address> {
+        self.iter()
+            .filter(|(_, result)| result.is_ok())
+            .map(|(address, _)| address)
+    }
+
+    /// Returns the recipients rejected by the server, with the error
+    pub fn rejected(&self) -> impl Iterator<Item = (&Address, &Error)> {
+        self.iter()
+            .filter_map(|(address, result)| result.err().map(|err| (address, err)))
+    }
+
+    /// Returns true if the server accepted every recipient
+    pub fn is_accepted(&self) -> bool {
+        self.rejected().next().is_none()
+    }
+
+    /// Returns the recipients with the reply of the server, or the error, in the order
+    /// of the envelope
+    pub fn into_recipients(self) -> Vec<(Address, result::Result<Response, Error>)> {
+        self.recipients
+    }
+}
+
+/// Result of an email sent in several transactions, to stay under the recipient limit
+/// of the server
+///
+/// Holds the recipients of each transaction, with the reply to the message data or the
+/// error which ended the transaction.
+#[derive(Debug)]
+pub struct SplitDelivery {
+    pub(crate) transactions: Vec<(Vec<Address>, result::Result<Response, Error>)>,
+}
+
+impl SplitDelivery {
+    /// Returns the recipients of each transaction with its result, in the order they were sent
+    pub fn iter(&self) -> impl Iterator<Item = (&[Address], result::Result<&Response, &Error>)> {
+        self.transactions
+            .iter()
+            .map(|(recipients, result)| (&recipients[..], result.as_ref()))
+    }
+
+    /// Returns the recipients the message was delivered to
+    pub fn delivered(&self) -> impl Iterator<Item = &Address> {
+        self.iter()
+            .filter(|(_, result)| result.is_ok())
+            .flat_map(|(recipients, _)| recipients)
+    }
+
+    /// Returns the recipients the message wasn't delivered to, with the error
+    pub fn failed(&self) -> impl Iterator<Item = (&Address, &Error)> {
+        self.iter().flat_map(|(recipients, result)| {
+            let err = result.err();
+            recipients
+                .iter()
+                .filter_map(move |recipient| err.map(|err| (recipient, err)))
+        })
+    }
+
+    /// Returns true if the message was delivered to every recipient
+    pub fn is_delivered(&self) -> bool {
+        self.failed().next().is_none()
+    }
+
+    /// Returns the reply to the last transaction if every transaction succeeded, or the
+    /// error of the first failed one
+    pub(crate) fn into_result(self) -> result::Result<Response, Error> {
+        let mut last = Err(error::client("no recipient in the envelope"));
+        for (_, result) in self.transactions {
+            last = Ok(result?);
+        }
+        last
+    }
+}
+
 // Parsers (originally from tokio-smtp)
 
 fn parse_code(i: &str) -> IResult<&str, Code> {
@@ -278,6 +600,111 @@ pub(crate) fn parse_response(i: &str) -> IResult<&str, Response> {
 mod test {
     use super::*;
 
+    #[test]
+    fn test_verification_from_response() {
+        let response = "250 Fred Smith <Fred@USC-ISIF.ARPA>\r\n"
+            .parse::<Response>()
+            .unwrap();
+        assert_eq!(
+            Verification::from_response(&response).unwrap(),
+            Verification::Mailboxes(vec![VerifiedMailbox {
+                name: Some("Fred Smith".to_string()),
+                email: "Fred@USC-ISIF.ARPA".parse().unwrap(),
+            }])
+        );
+
+        let response =
+            "250-Jon Postel <Postel@isi.edu>\r\n250-<Quark@isi.edu>\r\n250 sam@isi.edu\r\n"
+                .parse::<Response>()
+                .unwrap();
+        assert_eq!(
+            Verification::from_response(&response).unwrap(),
+            Verification::Mailboxes(vec![
+                VerifiedMailbox {
+                    name: Some("Jon Postel".to_string()),
+                    email: "Postel@isi.edu".parse().unwrap(),
+                },
+                VerifiedMailbox {
+                    name: None,
+                    email: "Quark@isi.edu".parse().unwrap(),
+                },
+                VerifiedMailbox {
+                    name: None,
+                    email: "sam@isi.edu".parse().unwrap(),
+                },
+            ])
+        );
+
+        let response = "252 Cannot VRFY user, but will accept message\r\n"
+            .parse::<Response>()
+            .unwrap();
+        assert_eq!(
+            Verification::from_response(&response).unwrap(),
+            Verification::Unverified
+        );
+
+        let response = "250 not an address\r\n".parse::<Response>().unwrap();
+        assert!(Verification::from_response(&response).is_err());
+    }
+
+    #[test]
+    fn test_verification_from_response_enhanced_code() {
+        let response = "250 2.1.5 Fred Smith <Fred@USC-ISIF.ARPA>\r\n"
+            .parse::<Response>()
+            .unwrap();
+        assert_eq!(
+            Verification::from_response(&response).unwrap(),
+            Verification::Mailboxes(vec![VerifiedMailbox {
+                name: Some("Fred Smith".to_string()),
+                email: "Fred@USC-ISIF.ARPA".parse().unwrap(),
+            }])
+        );
+
+        let response = "250-2.1.5 <Postel@isi.edu>\r\n250 2.1.5 sam@isi.edu\r\n"
+            .parse::<Response>()
+            .unwrap();
+        assert_eq!(
+            Verification::from_response(&response).unwrap(),
+            Verification::Mailboxes(vec![
+                VerifiedMailbox {
+                    name: None,
+                    email: "Postel@isi.edu".parse().unwrap(),
+                },
+                VerifiedMailbox {
+                    name: None,
+                    email: "sam@isi.edu".parse().unwrap(),
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_verification_from_result() {
+        let not_implemented = Code::new(
+            Severity::PermanentNegativeCompletion,
+            Category::Syntax,
+            Detail::Two,
+        );
+        assert_eq!(
+            Verification::from_result(Err(error::code(not_implemented))).unwrap(),
+            Verification::NotSupported
+        );
+
+        let no_such_user = Code::new(
+            Severity::PermanentNegativeCompletion,
+            Category::MailSystem,
+            Detail::Zero,
+        );
+        assert!(Verification::from_result(Err(error::code(no_such_user))).is_err());
+
+        let invalid_argument = Code::new(
+            Severity::PermanentNegativeCompletion,
+            Category::Syntax,
+            Detail::One,
+        );
+        assert!(Verification::from_result(Err(error::code(invalid_argument))).is_err());
+    }
+
     #[test]
     fn test_severity_fmt() {
         assert_eq!(format!("{}", Severity::PositiveCompletion), "2");
@@ -579,4 +1006,46 @@ mod test {
             Some("")
         );
     }
+
+    #[test]
+    fn test_enhanced_code() {
+        let response: Response = "550 5.1.1 No such user\r\n".parse().unwrap();
+        let enhanced = response.enhanced_code().unwrap();
+        assert_eq!(
+            (enhanced.class(), enhanced.subject(), enhanced.detail()),
+            (5, 1, 1)
+        );
+        assert_eq!(enhanced.category(), StatusCategory::Address);
+        assert_eq!(enhanced.to_string(), "5.1.1");
+
+        let response: Response = "452 4.3.1 Insufficient storage\r\n".parse().unwrap();
+        assert_eq!(
+            response.enhanced_code().map(EnhancedCode::category),
+            Some(StatusCategory::MailSystem)
+        );
+        let response: Response = "554 5.7.123 Rejected\r\n".parse().unwrap();
+        assert_eq!(response.enhanced_code().unwrap().detail(), 123);
+
+        // Missing, invalid, or with a class not matching the reply code
+        for text in &[
+            "550 No such user\r\n",
+            "550 5.1 No such user\r\n",
+            "550 5.1.1.1 No such user\r\n",
+            "550 5.1.1000 No such user\r\n",
+            "550 3.1.1 No such user\r\n",
+            "550 4.1.1 No such user\r\n",
+            "250 OK\r\n",
+        ] {
+            let response: Response = text.parse().unwrap();
+            assert_eq!(response.enhanced_code(), None, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_status_category_from_subject() {
+        assert_eq!(StatusCategory::from_subject(0), StatusCategory::Other);
+        assert_eq!(StatusCategory::from_subject(2), StatusCategory::Mailbox);
+        assert_eq!(StatusCategory::from_subject(7), StatusCategory::Security);
+        assert_eq!(StatusCategory::from_subject(8), StatusCategory::Other);
+    }
 }
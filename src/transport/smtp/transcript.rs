@@ -0,0 +1,372 @@
+//! Transcript of the SMTP dialogue, to debug failed sends
+//!
+//! When [`capture_transcript`] is set on the transport builder, the connections record
+//! every line sent and received, with the time and the direction. The arguments of
+//! `AUTH` commands and the answers to the challenges of the server are redacted, and
+//! only the beginning of the message data is kept.
+//!
+//! The transcript of a send covers the opening of the connection when a new one was
+//! needed, and the transaction. It is attached to the errors, see [`Error::transcript`],
+//! and given to the [`TransportObserver::on_transcript`] of the transport after every
+//! send, successful or not. When using an [`SmtpConnection`] directly, the transcript is
+//! started with [`SmtpConnection::capture_transcript`] and read with
+//! [`SmtpConnection::take_transcript`].
+//!
+//! The memory used by a transcript is bounded: once [`TranscriptConfig::max_lines`] lines
+//! are recorded, the oldest ones are dropped, and lines are truncated to 1000 bytes.
+//!
+//! ```rust,no_run
+//! # #[cfg(feature = "builder")]
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! use lettre::{
+//!     transport::smtp::transcript::TranscriptConfig, Message, SmtpTransport, Transport,
+//! };
+//!
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let mailer = SmtpTransport::builder_dangerous("localhost")
+//!     .capture_transcript(TranscriptConfig::new().data_prefix(128))
+//!     .build();
+//!
+//! if let Err(err) = mailer.send(&email) {
+//!     if let Some(transcript) = err.transcript() {
+//!         eprintln!("{}", transcript);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! # #[cfg(not(feature = "builder"))]
+//! # fn main() {}
+//! ```
+//!
+//! [`capture_transcript`]: super::SmtpTransportBuilder::capture_transcript
+//! [`Error::transcript`]: super::Error::transcript
+//! [`TransportObserver::on_transcript`]: super::observer::TransportObserver::on_transcript
+//! [`SmtpConnection`]: super::client::SmtpConnection
+//! [`SmtpConnection::capture_transcript`]: super::client::SmtpConnection::capture_transcript
+//! [`SmtpConnection::take_transcript`]: super::client::SmtpConnection::take_transcript
+
+use std::{
+    collections::{vec_deque, VecDeque},
+    fmt::{self, Display},
+    sync::Arc,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use super::{
+    observer::{notify, TransportObserver},
+    Error,
+};
+
+/// Maximum length of a recorded line, longer lines are truncated
+const MAX_LINE_LEN: usize = 1000;
+
+/// Limits of the transcripts recorded by a transport
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TranscriptConfig {
+    max_lines: usize,
+    data_prefix: usize,
+}
+
+impl TranscriptConfig {
+    /// Keeps the last 100 lines, and the first 512 bytes of the message data
+    pub fn new() -> Self {
+        Self {
+            max_lines: 100,
+            data_prefix: 512,
+        }
+    }
+
+    /// Maximum number of lines kept, the oldest ones are dropped first
+    pub fn max_lines(mut self, max_lines: usize) -> Self {
+        self.max_lines = max_lines;
+        self
+    }
+
+    /// Number of bytes of the message data kept, `0` to only record its size
+    pub fn data_prefix(mut self, data_prefix: usize) -> Self {
+        self.data_prefix = data_prefix;
+        self
+    }
+}
+
+impl Default for TranscriptConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Who sent a line of the transcript
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Sent by lettre
+    Client,
+    /// Sent by the server
+    Server,
+}
+
+/// A line of the transcript
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TranscriptLine {
+    time: SystemTime,
+    direction: Direction,
+    line: String,
+}
+
+impl TranscriptLine {
+    /// When the line was sent or received
+    pub fn time(&self) -> SystemTime {
+        self.time
+    }
+
+    /// Who sent the line
+    pub fn direction(&self) -> Direction {
+        self.direction
+    }
+
+    /// The line, without its line ending
+    pub fn line(&self) -> &str {
+        &self.line
+    }
+}
+
+/// Lines sent and received on a connection
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transcript {
+    config: TranscriptConfig,
+    lines: VecDeque<TranscriptLine>,
+    dropped: usize,
+}
+
+impl Transcript {
+    pub(crate) fn new(config: TranscriptConfig) -> Self {
+        Self {
+            config,
+            lines: VecDeque::new(),
+            dropped: 0,
+        }
+    }
+
+    /// Recorded lines, from the oldest
+    pub fn lines(&self) -> vec_deque::Iter<'_, TranscriptLine> {
+        self.lines.iter()
+    }
+
+    /// Number of lines dropped to stay under [`TranscriptConfig::max_lines`]
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    /// Tells whether no line was recorded
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty() && self.dropped == 0
+    }
+
+    /// Records a command or the lines of a reply
+    pub(crate) fn record(&mut self, direction: Direction, text: &str) {
+        let time = SystemTime::now();
+        for line in text.trim_end_matches(&['\r', '\n'][..]).split("\r\n") {
+            self.push(TranscriptLine {
+                time,
+                direction,
+                line: truncate(line, MAX_LINE_LEN).to_owned(),
+            });
+        }
+    }
+
+    /// Records a command, replacing it with `redacted` followed by a mark if they differ
+    pub(crate) fn record_command(&mut self, command: &str, redacted: &str) {
+        if command.trim_end_matches("\r\n") == redacted {
+            self.record(Direction::Client, redacted);
+        } else {
+            self.record(Direction::Client, &format!("{} [redacted]", redacted));
+        }
+    }
+
+    /// Records the beginning of the message data
+    pub(crate) fn record_data(&mut self, data: &[u8]) {
+        self.record_streamed_data(data, data.len());
+    }
+
+    /// Records the beginning of message data of `len` bytes, starting with `start`
+    pub(crate) fn record_streamed_data(&mut self, start: &[u8], len: usize) {
+        let prefix = &start[..start.len().min(self.config.data_prefix)];
+        if !prefix.is_empty() {
+            self.record(Direction::Client, &String::from_utf8_lossy(prefix));
+        }
+        if prefix.len() < len {
+            let omitted = format!("[{} more bytes of data]", len - prefix.len());
+            self.record(Direction::Client, &omitted);
+        }
+    }
+
+    /// Number of bytes recorded from the beginning of the message data
+    #[cfg(any(feature = "tokio1", feature = "async-std1"))]
+    pub(crate) fn data_prefix(&self) -> usize {
+        self.config.data_prefix
+    }
+
+    /// Returns the recorded lines, and starts a new transcript
+    pub(crate) fn take(&mut self) -> Transcript {
+        let config = self.config;
+        std::mem::replace(self, Transcript::new(config))
+    }
+
+    fn push(&mut self, line: TranscriptLine) {
+        if self.config.max_lines == 0 {
+            self.dropped += 1;
+            return;
+        }
+        if self.lines.len() >= self.config.max_lines {
+            self.lines.pop_front();
+            self.dropped += 1;
+        }
+        self.lines.push_back(line);
+    }
+}
+
+impl Display for Transcript {
+    /// Formats one line per recorded line, prefixed with its time, and with `C:` or `S:`
+    /// for the lines sent by the client and the server
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.dropped > 0 {
+            writeln!(f, "[{} lines dropped]", self.dropped)?;
+        }
+        for line in &self.lines {
+            let time = line
+                .time
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or(Duration::from_secs(0));
+            let prefix = match line.direction {
+                Direction::Client => "C",
+                Direction::Server => "S",
+            };
+            writeln!(
+                f,
+                "{}.{:03} {}: {}",
+                time.as_secs(),
+                time.subsec_millis(),
+                prefix,
+                line.line
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Attaches the transcript of a send to its error, and gives it to the observer
+pub(crate) fn finish_send<T>(
+    observer: Option<&Arc<dyn TransportObserver>>,
+    result: Result<T, Error>,
+    transcript: Option<Transcript>,
+) -> Result<T, Error> {
+    match result {
+        Ok(value) => {
+            if let Some(transcript) = &transcript {
+                notify(observer, |observer| observer.on_transcript(transcript));
+            }
+            Ok(value)
+        }
+        Err(err) => {
+            let err = err.with_transcript(transcript);
+            if let Some(transcript) = err.transcript() {
+                notify(observer, |observer| observer.on_transcript(transcript));
+            }
+            Err(err)
+        }
+    }
+}
+
+/// Truncates `line` to at most `max_len` bytes, on a char boundary
+fn truncate(line: &str, max_len: usize) -> &str {
+    if line.len() <= max_len {
+        return line;
+    }
+    let mut end = max_len;
+    while !line.is_char_boundary(end) {
+        end -= 1;
+    }
+    &line[..end]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lines(transcript: &Transcript) -> Vec<(Direction, &str)> {
+        transcript
+            .lines()
+            .map(|line| (line.direction(), line.line()))
+            .collect()
+    }
+
+    #[test]
+    fn record_reply_lines() {
+        let mut transcript = Transcript::new(TranscriptConfig::new());
+        transcript.record(Direction::Client, "EHLO localhost\r\n");
+        transcript.record(Direction::Server, "250-localhost\r\n250 SIZE 100\r\n");
+        assert_eq!(
+            lines(&transcript),
+            vec![
+                (Direction::Client, "EHLO localhost"),
+                (Direction::Server, "250-localhost"),
+                (Direction::Server, "250 SIZE 100"),
+            ]
+        );
+    }
+
+    #[test]
+    fn record_redacted_command() {
+        let mut transcript = Transcript::new(TranscriptConfig::new());
+        transcript.record_command("NOOP\r\n", "NOOP");
+        transcript.record_command("AUTH PLAIN AHVzZXIAcGFzcw==\r\n", "AUTH PLAIN");
+        assert_eq!(
+            lines(&transcript),
+            vec![
+                (Direction::Client, "NOOP"),
+                (Direction::Client, "AUTH PLAIN [redacted]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn bounded_lines() {
+        let mut transcript = Transcript::new(TranscriptConfig::new().max_lines(2));
+        transcript.record(Direction::Client, "NOOP\r\n");
+        transcript.record(Direction::Server, "250 OK\r\n");
+        transcript.record(Direction::Client, "QUIT\r\n");
+        assert_eq!(transcript.dropped(), 1);
+        assert_eq!(
+            lines(&transcript),
+            vec![(Direction::Server, "250 OK"), (Direction::Client, "QUIT")]
+        );
+        assert!(transcript.to_string().starts_with("[1 lines dropped]\n"));
+
+        let taken = transcript.take();
+        assert_eq!(taken.dropped(), 1);
+        assert!(transcript.is_empty());
+    }
+
+    #[test]
+    fn data_prefix() {
+        let mut transcript = Transcript::new(TranscriptConfig::new().data_prefix(12));
+        transcript.record_data(b"Subject: Hi\r\n\r\nHello");
+        assert_eq!(
+            lines(&transcript),
+            vec![
+                (Direction::Client, "Subject: Hi"),
+                (Direction::Client, "[8 more bytes of data]"),
+            ]
+        );
+    }
+
+    #[test]
+    fn truncate_char_boundary() {
+        assert_eq!(truncate("abc", 5), "abc");
+        assert_eq!(truncate("aéb", 2), "a");
+    }
+}
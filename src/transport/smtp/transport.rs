@@ -1,13 +1,49 @@
-use std::time::Duration;
+#[cfg(unix)]
+use std::path::Path;
+use std::{
+    net::SocketAddr,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+#[cfg(feature = "socket2")]
+use std::{ops::RangeInclusive, sync::Mutex};
 
 #[cfg(feature = "r2d2")]
-use r2d2::Pool;
+use r2d2::{Pool, PooledConnection};
+#[cfg(feature = "socket2")]
+use socket2::{SockAddr, Socket};
 
+#[cfg(feature = "builder")]
+use super::commands::Rset;
+#[cfg(any(
+    feature = "socket2",
+    feature = "r2d2",
+    feature = "native-tls",
+    feature = "rustls-tls"
+))]
+use super::error;
+#[cfg(feature = "socket2")]
+use super::Phase;
+use super::{
+    check_message_size,
+    client::{ProxyProtocol, ProxyProtocolVersion},
+    commands::XClient,
+    dns_cache::DnsCache,
+    observer::{notify, notify_phases, CloseReason, PhaseTimings, TransportObserver},
+    relay::{RelayHealth, RelaySelection, Relays},
+    response::{EnvelopeVerification, SplitDelivery},
+    tls_info,
+    transcript::{finish_send, TranscriptConfig},
+    ClientId, ConnectionTarget, Credentials, Error, Mechanism, Response, SmtpConnection, SmtpInfo,
+    TcpTarget, Tls, CONNECT_RETRY_DELAY, MAX_SPLIT_TRANSACTIONS, SMTP_PORT,
+};
 #[cfg(feature = "r2d2")]
-use super::PoolConfig;
+use super::{pool::ReconnectJitter, PoolConfig};
 #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
-use super::{error, Tls, TlsParameters, SUBMISSIONS_PORT, SUBMISSION_PORT};
-use super::{ClientId, Credentials, Error, Mechanism, Response, SmtpConnection, SmtpInfo};
+use super::{TlsParameters, SUBMISSIONS_PORT, SUBMISSION_PORT};
+#[cfg(feature = "builder")]
+use crate::Message;
 use crate::{address::Envelope, Transport};
 
 /// Sends emails using the SMTP protocol
@@ -18,25 +54,33 @@ pub struct SmtpTransport {
     inner: Pool<SmtpClient>,
     #[cfg(not(feature = "r2d2"))]
     inner: SmtpClient,
+    /// Opens the connections testing the server when the pool has no idle connection
+    #[cfg(feature = "r2d2")]
+    client: SmtpClient,
+    observer: Option<Arc<dyn TransportObserver>>,
+    relays: Arc<Relays>,
+    max_message_size: Option<usize>,
+    max_recipients_per_transaction: Option<usize>,
+    split_on_too_many_recipients: bool,
+    #[cfg(feature = "builder")]
+    strip_return_path: bool,
 }
 
 impl Transport for SmtpTransport {
     type Ok = Response;
     type Error = Error;
 
+    /// Sends an email, limited to its own maximum size if it has one
+    #[cfg(feature = "builder")]
+    fn send(&self, message: &Message) -> Result<Self::Ok, Self::Error> {
+        let raw = self.formatted(message);
+        let max_message_size = message.max_size().or(self.max_message_size);
+        self.send_email(message.envelope(), &raw, max_message_size)
+    }
+
     /// Sends an email
     fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
-        #[cfg(feature = "r2d2")]
-        let mut conn = self.inner.get().map_err(error::client)?;
-        #[cfg(not(feature = "r2d2"))]
-        let mut conn = self.inner.connection()?;
-
-        let result = conn.send(envelope, email)?;
-
-        #[cfg(not(feature = "r2d2"))]
-        conn.quit()?;
-
-        Ok(result)
+        self.send_email(envelope, email, self.max_message_size)
     }
 }
 
@@ -98,8 +142,42 @@ impl SmtpTransport {
     /// [`SmtpTransport::starttls_relay`](#method.starttls_relay) instead,
     /// if possible.
     pub fn builder_dangerous<T: Into<String>>(server: T) -> SmtpTransportBuilder {
+        Self::builder_target(ConnectionTarget::Tcp(server.into(), SMTP_PORT))
+    }
+
+    /// Creates a new SMTP client connecting to the Unix domain socket at `path`
+    ///
+    /// Local mail servers like Postfix can accept emails on a Unix domain socket.
+    /// The same defaults as [`SmtpTransport::builder_dangerous`](#method.builder_dangerous)
+    /// apply, and TLS can't be used.
+    #[cfg(unix)]
+    #[cfg_attr(docsrs, doc(cfg(unix)))]
+    pub fn unix<P: AsRef<Path>>(path: P) -> SmtpTransportBuilder {
+        Self::builder_target(ConnectionTarget::Unix(path.as_ref().to_path_buf()))
+    }
+
+    /// Creates a new SMTP client sending emails over `socket`, connected by the caller
+    ///
+    /// Useful in sandboxes which don't allow connecting, where a connected TCP or Unix
+    /// domain socket is received instead, like with socket activation. The SMTP dialog,
+    /// and the TLS handshake if TLS is set, run over the socket. It's used by the first
+    /// connection only: once it's closed, opening another connection fails.
+    /// The same defaults as [`SmtpTransport::builder_dangerous`](#method.builder_dangerous)
+    /// apply.
+    #[cfg(feature = "socket2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "socket2")))]
+    pub fn from_socket(socket: Socket) -> SmtpTransportBuilder {
+        let mut builder = Self::builder_target(socket_target(&socket));
+        builder.info.socket = Some(Arc::new(Mutex::new(Some(socket))));
+        builder
+    }
+
+    /// Creates a new SMTP client connecting to `target`
+    ///
+    /// See [`SmtpTransport::builder_dangerous`](#method.builder_dangerous) for the defaults.
+    pub fn builder_target(target: ConnectionTarget) -> SmtpTransportBuilder {
         let new = SmtpInfo {
-            server: server.into(),
+            target,
             ..Default::default()
         };
 
@@ -109,8 +187,237 @@ impl SmtpTransport {
             pool_config: PoolConfig::default(),
         }
     }
+
+    /// Checks that emails can be sent to the server, without sending any
+    ///
+    /// Takes an idle connection from the pool, or opens a new one, authenticating
+    /// if credentials are set, and sends `NOOP`. Returns whether the server accepted it.
+    /// The connection is given back to the pool, or closed when it was opened for the
+    /// check. The errors opening the connection are returned, and tell with the methods of
+    /// [`Error`] whether the authentication failed or the server couldn't be reached.
+    pub fn test_connection(&self) -> Result<bool, Error> {
+        #[cfg(feature = "r2d2")]
+        if let Some(mut conn) = self.inner.try_get() {
+            return Ok(conn.test_connected());
+        }
+
+        #[cfg(feature = "r2d2")]
+        let client = &self.client;
+        #[cfg(not(feature = "r2d2"))]
+        let client = &self.inner;
+
+        let mut conn = client.connection()?;
+        let is_connected = conn.test_connected();
+        self.close(conn)?;
+        Ok(is_connected)
+    }
+
+    /// Checks that the server accepts the sender and each recipient of `envelope`,
+    /// without sending any message
+    ///
+    /// Uses a connection like sending an email, but never sends the `DATA` command.
+    /// See [`SmtpConnection::verify_envelope`].
+    ///
+    /// The result is only a hint: many relays accept every recipient, and send a bounce
+    /// later for the ones which don't exist.
+    pub fn verify_recipients(&self, envelope: &Envelope) -> Result<EnvelopeVerification, Error> {
+        let mut conn = self.connection()?;
+
+        let result = conn.verify_envelope(envelope);
+        notify_phases(self.observer.as_ref(), conn.take_phase_timings());
+        let released = self.release(conn);
+        result.and_then(|verification| released.map(|()| verification))
+    }
+
+    /// Sends an email in several transactions, and returns the result of each one
+    ///
+    /// The recipients are split following
+    /// [`SmtpTransportBuilder::max_recipients_per_transaction`], and the `452` replies of
+    /// the server. See [`SmtpConnection::send_split`].
+    pub fn send_split(&self, envelope: &Envelope, email: &[u8]) -> Result<SplitDelivery, Error> {
+        check_message_size(self.max_message_size, email)?;
+        let mut conn = self.connection()?;
+
+        let max_recipients = self.max_recipients_per_transaction.unwrap_or(usize::MAX);
+        let result = conn.send_split(envelope, email, max_recipients);
+        notify_phases(self.observer.as_ref(), conn.take_phase_timings());
+        let released = self.release(conn);
+        result.and_then(|delivery| released.map(|()| delivery))
+    }
+
+    /// Sends all the `messages` over the same connection
+    ///
+    /// The transaction is reset with `RSET` between messages. A new connection is opened
+    /// if the current one breaks, for example because a message is rejected or the server
+    /// closes it: the following messages are still sent. Returns the result of each
+    /// message, in order.
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    pub fn send_batch(&self, messages: &[Message]) -> Vec<Result<Response, Error>> {
+        let mut results = Vec::with_capacity(messages.len());
+        let mut conn: Option<Connection> = None;
+
+        for message in messages {
+            let email = self.formatted(message);
+            let max_message_size = message.max_size().or(self.max_message_size);
+            if let Err(err) = check_message_size(max_message_size, &email) {
+                results.push(finish_send(self.observer.as_ref(), Err(err), None));
+                continue;
+            }
+
+            // Reuse the connection if the server accepts to start a new transaction
+            let reusable = match conn.as_mut() {
+                Some(conn) if !conn.has_broken() => conn.command(Rset).is_ok(),
+                _ => false,
+            };
+            if !reusable {
+                if let Some(conn) = conn.take() {
+                    let _ = self.release(conn);
+                }
+                conn = match self.connection() {
+                    Ok(conn) => Some(conn),
+                    Err(err) => {
+                        results.push(finish_send(self.observer.as_ref(), Err(err), None));
+                        continue;
+                    }
+                };
+            }
+
+            let conn = conn.as_mut().expect("a connection was just opened");
+            let start = Instant::now();
+            let result = self.send_with(conn, message.envelope(), &email);
+            notify(self.observer.as_ref(), |observer| {
+                observer.on_send(result.as_ref(), start.elapsed(), email.len())
+            });
+            notify_phases(self.observer.as_ref(), conn.take_phase_timings());
+            let transcript = conn.take_transcript();
+            results.push(finish_send(self.observer.as_ref(), result, transcript));
+        }
+
+        if let Some(conn) = conn {
+            let _ = self.release(conn);
+        }
+
+        results
+    }
+
+    /// Health of the relay hosts, the main one first
+    ///
+    /// See the [`relay`](super::relay) module.
+    pub fn relay_health(&self) -> Vec<RelayHealth> {
+        self.relays.health()
+    }
+
+    /// Formats `message` to be sent, without its `Return-Path` headers unless disabled
+    #[cfg(feature = "builder")]
+    fn formatted(&self, message: &Message) -> Vec<u8> {
+        if self.strip_return_path {
+            message.formatted_for_submission()
+        } else {
+            message.formatted()
+        }
+    }
+
+    /// Sends an email over a connection from the pool, or a new one, rejecting it if
+    /// it's larger than `max_message_size`
+    fn send_email(
+        &self,
+        envelope: &Envelope,
+        email: &[u8],
+        max_message_size: Option<usize>,
+    ) -> Result<Response, Error> {
+        let start = Instant::now();
+        let connection =
+            check_message_size(max_message_size, email).and_then(|()| self.connection());
+        let (result, transcript, timings) = match connection {
+            Ok(mut conn) => {
+                let result = self.send_with(&mut conn, envelope, email);
+                let transcript = conn.take_transcript();
+                let timings = conn.take_phase_timings();
+                let released = self.release(conn);
+                (
+                    result.and_then(|response| released.map(|()| response)),
+                    transcript,
+                    timings,
+                )
+            }
+            Err(err) => (Err(err), None, PhaseTimings::default()),
+        };
+
+        notify(self.observer.as_ref(), |observer| {
+            observer.on_send(result.as_ref(), start.elapsed(), email.len())
+        });
+        notify_phases(self.observer.as_ref(), timings);
+        finish_send(self.observer.as_ref(), result, transcript)
+    }
+
+    /// Sends an email over `conn`, in several transactions if it has too many recipients
+    fn send_with(
+        &self,
+        conn: &mut SmtpConnection,
+        envelope: &Envelope,
+        email: &[u8],
+    ) -> Result<Response, Error> {
+        match self.max_recipients_per_transaction {
+            Some(max_recipients) => conn
+                .send_split(envelope, email, max_recipients)
+                .and_then(SplitDelivery::into_result),
+            None if self.split_on_too_many_recipients => conn
+                .send_split_capped(envelope, email, usize::MAX, MAX_SPLIT_TRANSACTIONS)
+                .and_then(SplitDelivery::into_result),
+            None => conn.send(envelope, email),
+        }
+    }
+
+    /// Gets a connection from the pool, or opens a new one
+    fn connection(&self) -> Result<Connection, Error> {
+        #[cfg(feature = "r2d2")]
+        let conn = self.inner.get().map_err(error::client)?;
+        #[cfg(not(feature = "r2d2"))]
+        let conn = self.inner.connection()?;
+
+        Ok(conn)
+    }
+
+    /// Returns a connection to the pool, or closes it
+    #[cfg(feature = "r2d2")]
+    #[allow(clippy::unnecessary_wraps)]
+    fn release(&self, conn: Connection) -> Result<(), Error> {
+        drop(conn);
+        Ok(())
+    }
+
+    /// Returns a connection to the pool, or closes it
+    #[cfg(not(feature = "r2d2"))]
+    fn release(&self, conn: Connection) -> Result<(), Error> {
+        self.close(conn)
+    }
+
+    /// Closes a connection which isn't needed anymore
+    fn close(&self, mut conn: SmtpConnection) -> Result<(), Error> {
+        let result = if conn.has_broken() {
+            Ok(())
+        } else {
+            conn.quit().map(|_| ())
+        };
+
+        let reason = if conn.has_broken() {
+            CloseReason::Broken
+        } else {
+            CloseReason::Quit
+        };
+        notify(self.observer.as_ref(), |observer| {
+            observer.on_connection_closed(reason)
+        });
+        result
+    }
 }
 
+#[cfg(feature = "r2d2")]
+type Connection = PooledConnection<SmtpClient>;
+#[cfg(not(feature = "r2d2"))]
+type Connection = SmtpConnection;
+
 /// Contains client configuration.
 /// Instances of this struct can be created using functions of [`SmtpTransport`].
 #[derive(Debug, Clone)]
@@ -123,7 +430,22 @@ pub struct SmtpTransportBuilder {
 /// Builder for the SMTP `SmtpTransport`
 impl SmtpTransportBuilder {
     /// Set the name used during EHLO
+    ///
+    /// Defaults to the hostname of the machine if it's a fully-qualified domain name,
+    /// and to an address literal of the local address of the connection otherwise.
+    /// See [`ClientId::auto`] and [`ClientId::from_local_addr`].
     pub fn hello_name(mut self, name: ClientId) -> Self {
+        self.info.hello_name = Some(name);
+        self
+    }
+
+    /// Set the name used during EHLO if `name` is set, and the automatic one otherwise
+    ///
+    /// Tries in order `name`, the hostname of the machine if it's a fully-qualified domain
+    /// name, and an address literal of the local address of the connection, which is only
+    /// known once connected. See [`ClientId::auto`]. Convenient when the name comes from
+    /// an optional setting.
+    pub fn hello_name_or_auto(mut self, name: Option<ClientId>) -> Self {
         self.info.hello_name = name;
         self
     }
@@ -146,9 +468,185 @@ impl SmtpTransportBuilder {
         self
     }
 
+    /// Set the number of recipients sent in a single transaction
+    ///
+    /// Emails with more recipients are sent in several transactions over the same
+    /// connection, each one to a part of the recipients, like required by servers which
+    /// only take a limited number of `RCPT` commands. The transactions are also split when
+    /// the server replies `452` (too many recipients) to a `RCPT` command. The send
+    /// succeeds if every transaction succeeds, otherwise the error of the first failed
+    /// transaction is returned, even if some recipients received the email: use
+    /// [`SmtpTransport::send_split`] to get the result of each transaction.
+    /// Not set by default, all the recipients are sent in one transaction.
+    pub fn max_recipients_per_transaction(mut self, max_recipients: Option<usize>) -> Self {
+        self.info.max_recipients_per_transaction = max_recipients;
+        self
+    }
+
+    /// Set whether emails are sent in several transactions when the server replies `452`
+    /// (too many recipients)
+    ///
+    /// Some servers take a limited number of `RCPT` commands without advertising it. The
+    /// message is then sent to the recipients the server already accepted, and the
+    /// remaining ones go to a new transaction over the same connection, in at most 20
+    /// transactions. The result is the same as with
+    /// [`SmtpTransportBuilder::max_recipients_per_transaction`], which always splits the
+    /// transactions like this. Disabled by default, the send fails when the server
+    /// replies `452`.
+    pub fn split_on_too_many_recipients(mut self, split: bool) -> Self {
+        self.info.split_on_too_many_recipients = split;
+        self
+    }
+
+    /// Set whether the `Return-Path` headers of the messages are left out when sending them
+    ///
+    /// `Return-Path` is added by the server delivering the message, and must not be sent
+    /// by a client submitting it, like when re-sending an archived message. Enabled by
+    /// default, only applies to [`Message`](crate::Message)s, not to raw emails.
+    #[cfg(feature = "builder")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "builder")))]
+    pub fn strip_return_path(mut self, strip: bool) -> Self {
+        self.info.strip_return_path = strip;
+        self
+    }
+
+    /// Set the size in bytes of the largest message to send
+    ///
+    /// Larger messages are rejected with a client error before connecting to the server,
+    /// whatever the `SIZE` advertised by the server. Protects from sending huge messages by
+    /// mistake, like ones with runaway attachments. No limit by default.
+    ///
+    /// Messages built with a limit of their own, set with
+    /// [`MessageBuilder::max_size`](crate::message::MessageBuilder::max_size), are
+    /// checked against it instead.
+    pub fn max_message_size(mut self, max_message_size: Option<usize>) -> Self {
+        self.info.max_message_size = max_message_size;
+        self
+    }
+
+    /// Set the timeout for receiving the greeting of the server, defaults to the timeout
+    ///
+    /// It bounds the whole greeting, to detect servers accepting connections but never
+    /// sending their greeting, or trickling it. The error is a connection error, which
+    /// can be retried with another server.
+    pub fn greeting_timeout(mut self, timeout: Option<Duration>) -> Self {
+        self.info.greeting_timeout = timeout;
+        self
+    }
+
     /// Set the port to use
+    ///
+    /// Ignored when connecting to a Unix domain socket.
     pub fn port(mut self, port: u16) -> Self {
-        self.info.port = port;
+        self.info.target.set_port(port);
+        self
+    }
+
+    /// Set where to connect to the server
+    pub fn target(mut self, target: ConnectionTarget) -> Self {
+        self.info.target = target;
+        self
+    }
+
+    /// Set the number of times to retry connecting to the server
+    ///
+    /// Only connection errors, like a refused connection or a failed name
+    /// resolution, are retried, after a short delay. Defaults to 0.
+    pub fn connect_retries(mut self, retries: u32) -> Self {
+        self.info.connect_retries = retries;
+        self
+    }
+
+    /// Set the time during which the addresses the servers are resolved to are reused
+    ///
+    /// Without it, the hostname of the server is resolved again for each connection,
+    /// which is wasteful when sending many emails to the same server. The addresses are
+    /// cached per host and port, in the order given by the resolver, and forgotten when a
+    /// connection to them fails. Disabled by default.
+    pub fn dns_cache_ttl(mut self, ttl: Option<Duration>) -> Self {
+        self.info.dns_cache_ttl = ttl;
+        self
+    }
+
+    /// Set whether closing a connection waits for the reply to `QUIT`
+    ///
+    /// When `true`, the default, the `221` reply is read and checked, for at most a
+    /// few seconds. When `false`, `QUIT` is sent and the connection shut down
+    /// immediately, saving a round-trip when closing connections.
+    pub fn quit_wait(mut self, quit_wait: bool) -> Self {
+        self.info.quit_wait = quit_wait;
+        self
+    }
+
+    /// Set the servers to connect to when a connection can't be opened to the main one,
+    /// in order
+    ///
+    /// The retries set with [`connect_retries`](Self::connect_retries) apply to each
+    /// server, and the TLS certificate of each one is validated for its own name.
+    /// See the [`relay`](super::relay) module.
+    pub fn fallback_relays(mut self, targets: Vec<ConnectionTarget>) -> Self {
+        self.info.fallback_targets = targets;
+        self
+    }
+
+    /// Set the order in which the main and the fallback servers are tried,
+    /// defaults to [`RelaySelection::Ordered`]
+    pub fn relay_selection(mut self, selection: RelaySelection) -> Self {
+        self.info.relay_selection = selection;
+        self
+    }
+
+    /// Connect from a local port of `ports`, like the range a NAT gateway or a firewall
+    /// lets through
+    ///
+    /// The ports are tried in order until one is available, for each address of the
+    /// server. Not used when connecting to a Unix domain socket.
+    #[cfg(feature = "socket2")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "socket2")))]
+    pub fn source_port_range(mut self, ports: RangeInclusive<u16>) -> Self {
+        self.info.source_port_range = Some(ports);
+        self
+    }
+
+    /// Send a PROXY protocol header declaring `source` as the address of the client,
+    /// for servers behind a proxy like HAProxy
+    ///
+    /// The header is sent right after connecting, before the server greeting and the TLS
+    /// handshake. `source` defaults to the local address of the connection.
+    /// Not used when connecting to a Unix domain socket.
+    pub fn proxy_protocol(
+        mut self,
+        version: ProxyProtocolVersion,
+        source: Option<SocketAddr>,
+    ) -> Self {
+        self.info.proxy_protocol = Some(ProxyProtocol::new(version, source));
+        self
+    }
+
+    /// Forward the attributes of the original client with `XCLIENT`
+    ///
+    /// Sent on every new connection, after the TLS negotiation and before authentication,
+    /// when the server advertises the `XCLIENT` extension. The attributes the server
+    /// doesn't support are omitted.
+    pub fn xclient(mut self, xclient: XClient) -> Self {
+        self.info.xclient = Some(xclient);
+        self
+    }
+
+    /// Record the dialogue with the server, to debug failed sends
+    ///
+    /// The transcript of each send is attached to its error, and given to the
+    /// [`observer`](Self::observer). See the [`transcript`](super::transcript) module.
+    pub fn capture_transcript(mut self, config: TranscriptConfig) -> Self {
+        self.info.transcript = Some(config);
+        self
+    }
+
+    /// Notify `observer` of the connections and emails sent, for example to collect metrics
+    ///
+    /// See [`TransportObserver`].
+    pub fn observer<O: TransportObserver>(mut self, observer: O) -> Self {
+        self.info.observer = Some(Arc::new(observer));
         self
     }
 
@@ -175,12 +673,28 @@ impl SmtpTransportBuilder {
     /// If the `r2d2` feature is enabled an `Arc` wrapped pool is be created.
     /// Defaults can be found at [`PoolConfig`]
     pub fn build(self) -> SmtpTransport {
-        let client = SmtpClient { info: self.info };
+        let observer = self.info.observer.clone();
+        let max_message_size = self.info.max_message_size;
+        let max_recipients_per_transaction = self.info.max_recipients_per_transaction;
+        let split_on_too_many_recipients = self.info.split_on_too_many_recipients;
+        #[cfg(feature = "builder")]
+        let strip_return_path = self.info.strip_return_path;
+        let client = SmtpClient::new(self.info);
+        let relays = Arc::clone(&client.relays);
         SmtpTransport {
             #[cfg(feature = "r2d2")]
-            inner: self.pool_config.build(client),
+            inner: self.pool_config.build(client.clone()),
+            #[cfg(feature = "r2d2")]
+            client,
             #[cfg(not(feature = "r2d2"))]
             inner: client,
+            observer,
+            relays,
+            max_message_size,
+            max_recipients_per_transaction,
+            split_on_too_many_recipients,
+            #[cfg(feature = "builder")]
+            strip_return_path,
         }
     }
 }
@@ -189,44 +703,265 @@ impl SmtpTransportBuilder {
 #[derive(Debug, Clone)]
 pub struct SmtpClient {
     info: SmtpInfo,
+    relays: Arc<Relays>,
+    dns_cache: Option<Arc<DnsCache>>,
+    /// Connections idle for longer can't be taken from the pool
+    #[cfg(feature = "r2d2")]
+    pub(crate) server_idle_timeout: Option<Duration>,
+    /// Delays the connections opened by the pool after a failure
+    #[cfg(feature = "r2d2")]
+    pub(crate) reconnect_jitter: ReconnectJitter,
 }
 
 impl SmtpClient {
+    fn new(info: SmtpInfo) -> Self {
+        Self {
+            relays: info.relays(),
+            dns_cache: info.dns_cache(),
+            info,
+            #[cfg(feature = "r2d2")]
+            server_idle_timeout: None,
+            #[cfg(feature = "r2d2")]
+            reconnect_jitter: ReconnectJitter::default(),
+        }
+    }
+
     /// Creates a new connection directly usable to send emails
     ///
     /// Handles encryption and authentication
     pub fn connection(&self) -> Result<SmtpConnection, Error> {
-        #[allow(clippy::match_single_binding)]
-        let tls_parameters = match self.info.tls {
-            #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
-            Tls::Wrapper(ref tls_parameters) => Some(tls_parameters),
-            _ => None,
-        };
+        let start = Instant::now();
+        let mut result = self.open_connection();
 
-        #[allow(unused_mut)]
-        let mut conn = SmtpConnection::connect::<(&str, u16)>(
-            (self.info.server.as_ref(), self.info.port),
-            self.info.timeout,
-            &self.info.hello_name,
-            tls_parameters,
-        )?;
+        let observer = self.info.observer.as_ref();
+        match &mut result {
+            Ok(conn) => {
+                let tls = self.relays.tls(conn.relay(), &self.info.tls);
+                if let Some(tls_info) = tls_info(&tls, conn.is_encrypted()) {
+                    notify(observer, |observer| observer.on_tls_established(&tls_info));
+                }
+                notify(observer, |observer| {
+                    observer.on_connect(start.elapsed(), conn.is_encrypted());
+                    observer.on_connected(&conn.connection_info());
+                });
+                notify_phases(observer, conn.take_phase_timings());
+            }
+            Err(err) => notify(observer, |observer| observer.on_connection_error(err)),
+        }
+        result
+    }
 
-        #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
-        match self.info.tls {
-            Tls::Opportunistic(ref tls_parameters) => {
-                if conn.can_starttls() {
-                    conn.starttls(tls_parameters, &self.info.hello_name)?;
+    /// Connects to the server, then handles encryption and authentication
+    fn open_connection(&self) -> Result<SmtpConnection, Error> {
+        let mut relays = self.relays.order().into_iter().peekable();
+        #[allow(unused_variables, unused_mut)]
+        let (target, tls, mut conn) = loop {
+            let relay = relays.next().expect("there's at least one relay host");
+            let target = self.relays.target(relay);
+            let tls = self.relays.tls(relay, &self.info.tls);
+            match self.connect(target, &tls) {
+                Ok(mut conn) => {
+                    self.relays.succeeded(relay);
+                    conn.set_relay(relay);
+                    conn.set_quit_wait(self.info.quit_wait);
+                    break (target, tls, conn);
                 }
+                Err(err) if err.is_connection() && relays.peek().is_some() => {
+                    self.relays.failed(relay);
+                }
+                Err(err) => {
+                    if err.is_connection() {
+                        self.relays.failed(relay);
+                    }
+                    return Err(err);
+                }
+            }
+        };
+
+        #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+        match tls {
+            Tls::Opportunistic(ref tls_parameters) if conn.can_starttls() && !is_unix(target) => {
+                let hello_name = conn.hello_name().clone();
+                conn.starttls(tls_parameters, &hello_name)
+                    .map_err(|err| conn.attach_transcript(err))?;
             }
             Tls::Required(ref tls_parameters) => {
-                conn.starttls(tls_parameters, &self.info.hello_name)?;
+                let hello_name = conn.hello_name().clone();
+                conn.starttls(tls_parameters, &hello_name)
+                    .map_err(|err| conn.attach_transcript(err))?;
             }
             _ => (),
         }
 
+        if let Some(xclient) = &self.info.xclient {
+            conn.xclient(xclient)
+                .map_err(|err| conn.attach_transcript(err))?;
+        }
+
         if let Some(credentials) = &self.info.credentials {
-            conn.auth(&self.info.authentication, credentials)?;
+            let start = Instant::now();
+            let result = conn.auth(&self.info.authentication, credentials);
+            notify(self.info.observer.as_ref(), |observer| {
+                observer.on_auth(result.as_ref().map(|_| ()), start.elapsed())
+            });
+            result.map_err(|err| conn.attach_transcript(err))?;
         }
+
+        #[cfg(feature = "tracing")]
+        conn.record_handshake_time();
         Ok(conn)
     }
+
+    /// Connects to `target`, retrying after connection errors
+    fn connect(&self, target: &ConnectionTarget, tls: &Tls) -> Result<SmtpConnection, Error> {
+        #[allow(clippy::match_single_binding)]
+        let tls_parameters = match tls {
+            #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+            Tls::Wrapper(ref tls_parameters) => Some(tls_parameters),
+            _ => None,
+        };
+
+        #[cfg(feature = "socket2")]
+        if let Some(socket) = &self.info.socket {
+            let socket = socket
+                .lock()
+                .expect("socket lock poisoned")
+                .take()
+                .ok_or_else(|| error::connection("the provided socket was already used"))
+                .map_err(|err| err.with_phase(Phase::Connect))?;
+            return SmtpConnection::from_socket_with(
+                socket,
+                self.info.timeout,
+                self.info.greeting_timeout,
+                self.info.hello_name.as_ref(),
+                tls_parameters,
+                self.info.transcript.as_ref(),
+            );
+        }
+
+        let mut retries = self.info.connect_retries;
+        loop {
+            let result = match target {
+                ConnectionTarget::Tcp(server, port) => {
+                    let target = TcpTarget::new(server, *port).cached(self.dns_cache.as_deref());
+                    let result = SmtpConnection::connect_with(
+                        target,
+                        self.info.timeout,
+                        self.info.greeting_timeout,
+                        self.info.hello_name.as_ref(),
+                        self.info.source_port_range.as_ref(),
+                        self.info.proxy_protocol.as_ref(),
+                        tls_parameters,
+                        self.info.transcript.as_ref(),
+                    );
+                    if let (Err(err), Some(cache)) = (&result, &self.dns_cache) {
+                        if err.is_connection() {
+                            cache.invalidate(target.host(), *port);
+                        }
+                    }
+                    result
+                }
+                #[cfg(unix)]
+                ConnectionTarget::Unix(path) => {
+                    #[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+                    if tls_parameters.is_some() {
+                        return Err(error::client("TLS isn't supported on Unix sockets"));
+                    }
+                    SmtpConnection::connect_unix_with(
+                        path,
+                        self.info.timeout,
+                        self.info.greeting_timeout,
+                        self.info.hello_name.as_ref(),
+                        self.info.transcript.as_ref(),
+                    )
+                }
+            };
+            match result {
+                Ok(conn) => return Ok(conn),
+                Err(err) if retries > 0 && err.is_connection() => {
+                    retries -= 1;
+                    thread::sleep(CONNECT_RETRY_DELAY);
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Notified of the connections and emails sent
+    #[cfg(feature = "r2d2")]
+    pub(crate) fn observer(&self) -> Option<&Arc<dyn TransportObserver>> {
+        self.info.observer.as_ref()
+    }
+
+    /// Relay hosts to connect to, with their health
+    #[cfg(feature = "r2d2")]
+    pub(crate) fn relays(&self) -> &Arc<Relays> {
+        &self.relays
+    }
+}
+
+/// The server `socket` is connected to, or the default target if it's unknown
+#[cfg(feature = "socket2")]
+fn socket_target(socket: &Socket) -> ConnectionTarget {
+    let peer = socket.peer_addr().ok();
+    match peer.as_ref().and_then(SockAddr::as_socket) {
+        Some(addr) => ConnectionTarget::Tcp(addr.ip().to_string(), addr.port()),
+        #[cfg(unix)]
+        None if peer.as_ref().map_or(false, SockAddr::is_unix) => ConnectionTarget::Unix(
+            peer.as_ref()
+                .and_then(SockAddr::as_pathname)
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+        ),
+        None => SmtpInfo::default().target,
+    }
+}
+
+#[cfg(any(feature = "native-tls", feature = "rustls-tls"))]
+fn is_unix(target: &ConnectionTarget) -> bool {
+    match target {
+        ConnectionTarget::Tcp(..) => false,
+        #[cfg(unix)]
+        ConnectionTarget::Unix(_) => true,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn connect_retries() {
+        let client = SmtpClient::new(SmtpInfo {
+            target: ConnectionTarget::Tcp("127.0.0.1".to_string(), 1),
+            connect_retries: 2,
+            ..Default::default()
+        });
+
+        let start = Instant::now();
+        let err = match client.connection() {
+            Ok(_) => panic!("connection to a closed port succeeded"),
+            Err(err) => err,
+        };
+        assert!(err.is_connection());
+        assert!(start.elapsed() >= CONNECT_RETRY_DELAY * 2);
+    }
+
+    #[cfg(all(unix, any(feature = "native-tls", feature = "rustls-tls")))]
+    #[test]
+    fn unix_rejects_tls_wrapper() {
+        let client = SmtpClient::new(SmtpInfo {
+            target: ConnectionTarget::Unix("/nonexistent/lettre.sock".into()),
+            tls: Tls::Wrapper(TlsParameters::new("domain.tld".into()).unwrap()),
+            ..Default::default()
+        });
+
+        let err = match client.connection() {
+            Ok(_) => panic!("TLS connection over a Unix socket succeeded"),
+            Err(err) => err,
+        };
+        assert!(err.is_client());
+    }
 }
@@ -1,10 +1,11 @@
-//! The stub transport only logs message envelope and drops the content. It can be useful for
-//! testing purposes.
+//! The stub transport records the messages it is asked to send, and returns the provided result.
+//! It can be useful for testing purposes.
 //!
 //! #### Stub Transport
 //!
-//! The stub transport returns provided result and drops the content. It can be useful for
-//! testing purposes.
+//! The stub transport returns provided result and keeps a copy of every envelope and
+//! message sent through it, which can be retrieved with [`StubTransport::messages`].
+//! Clones of a `StubTransport` share the same recorded messages.
 //!
 //! ```rust
 //! # #[cfg(feature = "builder")]
@@ -23,19 +24,92 @@
 //! let mut sender = StubTransport::new_ok();
 //! let result = sender.send(&email);
 //! assert!(result.is_ok());
+//!
+//! let messages = sender.messages();
+//! assert_eq!(messages.len(), 1);
+//! assert_eq!(messages[0].0.to(), email.envelope().to());
+//! # Ok(())
+//! # }
+//! # }
+//! ```
+//!
+//! The outcome of each send can also be decided programmatically, for example to test
+//! retry logic:
+//!
+//! ```rust
+//! # #[cfg(feature = "builder")]
+//! # {
+//! use lettre::{
+//!     transport::stub::{StubTransport, TransientError},
+//!     Message, Transport,
+//! };
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! // Fail the first two sends with a transient error, then succeed
+//! let sender = StubTransport::new_sequence(vec![Err(TransientError), Err(TransientError)]);
+//! assert_eq!(sender.send(&email), Err(TransientError));
+//! assert_eq!(sender.send(&email), Err(TransientError));
+//! assert_eq!(sender.send(&email), Ok(()));
 //! # Ok(())
 //! # }
 //! # }
 //! ```
+//!
+//! #### Async Stub Transport
+//!
+//! With the `tokio1` or `async-std1` features, [`StubTransport`] also implements
+//! `AsyncTransport`, so the same type and the same results are used to test async code.
+//! Messages sent through a clone from sync code and from async code are recorded together.
+//!
+//! ```rust,no_run
+//! # use std::error::Error;
+//! #
+//! # #[cfg(all(feature = "tokio1", feature = "builder"))]
+//! # async fn run() -> Result<(), Box<dyn Error>> {
+//! use lettre::{
+//!     transport::stub::{StubTransport, TransientError},
+//!     AsyncTransport, Message,
+//! };
+//!
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let sender = StubTransport::new_sequence(vec![Err(TransientError)]);
+//! assert!(sender.send(email.clone()).await.is_err());
+//! assert!(sender.send(email).await.is_ok());
+//! assert_eq!(sender.messages().len(), 2);
+//! # Ok(())
+//! # }
+//! ```
 
 #[cfg(any(feature = "tokio1", feature = "async-std1"))]
 use crate::AsyncTransport;
-use crate::{address::Envelope, Transport};
+use crate::{address::Envelope, transport::failover::FailoverError, Transport};
 #[cfg(any(feature = "tokio1", feature = "async-std1"))]
 use async_trait::async_trait;
-use std::{error::Error as StdError, fmt};
+use std::{
+    error::Error as StdError,
+    fmt,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
 
-#[derive(Debug, Copy, Clone)]
+/// Error returned by the stub transport
+///
+/// It is permanent: [`FailoverTransport`](crate::transport::failover::FailoverTransport)
+/// doesn't try the next transport after it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Error;
 
 impl fmt::Display for Error {
@@ -46,47 +120,200 @@ impl fmt::Display for Error {
 
 impl StdError for Error {}
 
-/// This transport logs the message envelope and returns the given response
-#[derive(Debug, Clone, Copy)]
-pub struct StubTransport {
-    response: Result<(), Error>,
+impl FailoverError for Error {
+    fn should_try_next(&self) -> bool {
+        false
+    }
+}
+
+/// Transient error returned by the stub transport, to simulate a temporary failure
+///
+/// [`FailoverTransport`](crate::transport::failover::FailoverTransport) tries the next
+/// transport after it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TransientError;
+
+impl fmt::Display for TransientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("transient stub error")
+    }
+}
+
+impl StdError for TransientError {}
+
+impl FailoverError for TransientError {
+    fn should_try_next(&self) -> bool {
+        true
+    }
+}
+
+type MessageLog = Arc<Mutex<Vec<(Envelope, Vec<u8>)>>>;
+type ResponseFn<E> = Arc<Mutex<dyn FnMut(&Envelope, &[u8]) -> Result<(), E> + Send>>;
+
+/// This transport records the message envelope and content, and returns the given response
+///
+/// Clones share the recorded messages and the function deciding the responses, which is
+/// why the transport isn't `Copy`.
+///
+/// The error is a [`stub::Error`](Error) by default, other error types, like
+/// [`TransientError`] or one of the application, can be returned by the transports
+/// created with [`StubTransport::new_sequence`] and [`StubTransport::new_with_fn`].
+pub struct StubTransport<E = Error> {
+    response: ResponseFn<E>,
+    delay: Option<Duration>,
+    message_log: MessageLog,
 }
 
 impl StubTransport {
     /// Creates a new transport that always returns the given Result
     pub fn new(response: Result<(), Error>) -> StubTransport {
-        StubTransport { response }
+        StubTransport::new_with_fn(move |_envelope, _email| response)
     }
 
     /// Creates a new transport that always returns a success response
     pub fn new_ok() -> StubTransport {
-        StubTransport { response: Ok(()) }
+        StubTransport::new(Ok(()))
     }
 
     /// Creates a new transport that always returns an error
     pub fn new_error() -> StubTransport {
+        StubTransport::new(Err(Error))
+    }
+}
+
+impl StubTransport<TransientError> {
+    /// Creates a new transport that always returns a transient error
+    pub fn new_transient_error() -> StubTransport<TransientError> {
+        StubTransport::new_with_fn(|_envelope, _email| Err(TransientError))
+    }
+}
+
+impl<E> StubTransport<E> {
+    /// Creates a new transport returning the given results in order, one per send
+    ///
+    /// Sends succeed once all the results have been returned.
+    pub fn new_sequence<I>(responses: I) -> StubTransport<E>
+    where
+        I: IntoIterator<Item = Result<(), E>>,
+        I::IntoIter: Send + 'static,
+    {
+        let mut responses = responses.into_iter();
+        StubTransport::new_with_fn(move |_envelope, _email| responses.next().unwrap_or(Ok(())))
+    }
+
+    /// Creates a new transport calling the given function to decide the result of each send
+    pub fn new_with_fn<F>(response: F) -> StubTransport<E>
+    where
+        F: FnMut(&Envelope, &[u8]) -> Result<(), E> + Send + 'static,
+    {
         StubTransport {
-            response: Err(Error),
+            response: Arc::new(Mutex::new(response)),
+            delay: None,
+            message_log: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    /// Waits for the given duration before returning the result of each send
+    ///
+    /// Async sends wait with the timer of the runtime, tokio 1.x when called from
+    /// one of its tasks, and async-std 1.x otherwise.
+    pub fn with_delay(mut self, delay: Duration) -> StubTransport<E> {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Returns a copy of the envelopes and messages sent through this transport
+    ///
+    /// This includes sends for which an error was returned.
+    pub fn messages(&self) -> Vec<(Envelope, Vec<u8>)> {
+        self.message_log
+            .lock()
+            .expect("Couldn't acquire lock to read message log")
+            .clone()
+    }
+
+    /// Forgets all the messages recorded so far
+    pub fn clear(&self) {
+        self.message_log
+            .lock()
+            .expect("Couldn't acquire lock to clear message log")
+            .clear();
+    }
+
+    fn record(&self, envelope: &Envelope, email: &[u8]) -> Result<(), E> {
+        self.message_log
+            .lock()
+            .expect("Couldn't acquire lock to write message log")
+            .push((envelope.clone(), email.to_vec()));
+
+        let mut response = self
+            .response
+            .lock()
+            .expect("Couldn't acquire lock to compute response");
+        (*response)(envelope, email)
+    }
+}
+
+impl<E> Clone for StubTransport<E> {
+    fn clone(&self) -> Self {
+        Self {
+            response: Arc::clone(&self.response),
+            delay: self.delay,
+            message_log: Arc::clone(&self.message_log),
         }
     }
 }
 
-impl Transport for StubTransport {
+impl<E> fmt::Debug for StubTransport<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StubTransport")
+            .field("delay", &self.delay)
+            .field("message_log", &self.message_log)
+            .finish()
+    }
+}
+
+impl<E> Transport for StubTransport<E> {
     type Ok = ();
-    type Error = Error;
+    type Error = E;
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        if let Some(delay) = self.delay {
+            thread::sleep(delay);
+        }
 
-    fn send_raw(&self, _envelope: &Envelope, _email: &[u8]) -> Result<Self::Ok, Self::Error> {
-        self.response
+        self.record(envelope, email)
     }
 }
 
 #[cfg(any(feature = "tokio1", feature = "async-std1"))]
 #[async_trait]
-impl AsyncTransport for StubTransport {
+impl<E: Send> AsyncTransport for StubTransport<E> {
     type Ok = ();
-    type Error = Error;
+    type Error = E;
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        if let Some(delay) = self.delay {
+            sleep(delay).await;
+        }
 
-    async fn send_raw(&self, _envelope: &Envelope, _email: &[u8]) -> Result<Self::Ok, Self::Error> {
-        self.response
+        self.record(envelope, email)
+    }
+}
+
+/// Sleeps with the timer of the runtime running the task
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+async fn sleep(duration: Duration) {
+    #[cfg(all(feature = "tokio1", feature = "async-std1"))]
+    {
+        if tokio1_crate::runtime::Handle::try_current().is_ok() {
+            tokio1_crate::time::sleep(duration).await;
+        } else {
+            async_std::task::sleep(duration).await;
+        }
     }
+    #[cfg(all(feature = "tokio1", not(feature = "async-std1")))]
+    tokio1_crate::time::sleep(duration).await;
+    #[cfg(all(feature = "async-std1", not(feature = "tokio1")))]
+    async_std::task::sleep(duration).await;
 }
@@ -0,0 +1,295 @@
+//! The traced transport wraps another transport, and emits [`tracing`] spans and events
+//! for every sent message.
+//!
+//! Each send happens inside a `send_email` span with the following fields:
+//!
+//! * `message_id`: the `Message-ID` header of the message, if any
+//! * `from` and `to`: the envelope addresses, redacted according to the chosen [`Redaction`]
+//! * `size`: the size of the message in bytes
+//!
+//! Once the inner transport returns, an event is emitted with the duration of the send in
+//! milliseconds. Failures are recorded at the `WARN` level with the error, and with whether
+//! another transport could succeed (see [`FailoverError`]) if enabled with
+//! [`TracedTransport::classify_errors`].
+//!
+//! #### Traced Transport
+//!
+//! ```rust
+//! # #[cfg(feature = "builder")]
+//! # {
+//! use lettre::{
+//!     transport::{
+//!         stub::StubTransport,
+//!         traced::{Redaction, TracedTransport},
+//!     },
+//!     Message, Transport,
+//! };
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let sender = TracedTransport::new(StubTransport::new_ok()).redaction(Redaction::Domain);
+//! sender.send(&email)?;
+//! # Ok(())
+//! # }
+//! # }
+//! ```
+//!
+//! The traced transport can wrap any transport, including the [`failover`] transport, and
+//! traced transports can be used as the transports of the failover transport.
+//!
+//! [`tracing`]: https://docs.rs/tracing
+//! [`FailoverError`]: crate::transport::failover::FailoverError
+//! [`failover`]: crate::transport::failover
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use crate::AsyncTransport;
+use crate::{
+    address::{Address, Envelope},
+    transport::failover::FailoverError,
+    Transport,
+};
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use async_trait::async_trait;
+use std::{
+    any::Any,
+    fmt,
+    time::{Duration, Instant},
+};
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+use tracing::Instrument;
+
+/// How envelope addresses are written in spans
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Redaction {
+    /// Addresses are written as they are
+    None,
+    /// Only the domain of addresses is written, like `*@domain.tld`
+    Domain,
+    /// Addresses are replaced by a hash, which allows correlating the sends to
+    /// a same recipient without revealing it
+    ///
+    /// The hash is the 64 bits FNV-1a hash of the address, which stays the same
+    /// across builds. It isn't cryptographically secure, and known addresses can be
+    /// matched against it.
+    Hash,
+}
+
+impl Redaction {
+    fn apply(self, address: &Address) -> String {
+        match self {
+            Redaction::None => address.to_string(),
+            Redaction::Domain => format!("*@{}", address.domain()),
+            Redaction::Hash => format!("{:016x}", fnv1a(AsRef::<str>::as_ref(address).as_bytes())),
+        }
+    }
+}
+
+/// 64 bits FNV-1a hash
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Redaction::None
+    }
+}
+
+/// Returns whether another transport could succeed, if the error is of the classified type
+type Classify = fn(&dyn Any) -> Option<bool>;
+
+/// Emits tracing spans and events for every message sent with the inner transport
+#[derive(Clone)]
+pub struct TracedTransport<T> {
+    inner: T,
+    redaction: Redaction,
+    classify: Option<Classify>,
+}
+
+impl<T> TracedTransport<T> {
+    /// Wraps `inner`, without redacting addresses
+    pub fn new(inner: T) -> Self {
+        Self {
+            inner,
+            redaction: Redaction::default(),
+            classify: None,
+        }
+    }
+
+    /// Set how envelope addresses are written in spans
+    pub fn redaction(mut self, redaction: Redaction) -> Self {
+        self.redaction = redaction;
+        self
+    }
+
+    /// Record whether another transport could succeed when sending fails, for errors of
+    /// type `E`, the error type of the inner transport
+    ///
+    /// The `retryable` field is added to the failure events, see
+    /// [`FailoverError::should_try_next`].
+    pub fn classify_errors<E: FailoverError>(mut self) -> Self {
+        self.classify = Some(|err| err.downcast_ref::<E>().map(E::should_try_next));
+        self
+    }
+
+    /// Get a reference to the inner transport
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Get the inner transport
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn span(&self, envelope: &Envelope, email: &[u8]) -> tracing::Span {
+        let from = envelope
+            .from()
+            .map(|from| self.redaction.apply(from))
+            .unwrap_or_default();
+        let to = envelope
+            .to()
+            .iter()
+            .map(|to| self.redaction.apply(to))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        tracing::info_span!(
+            "send_email",
+            message_id = message_id(email).unwrap_or_default(),
+            from = from.as_str(),
+            to = to.as_str(),
+            size = email.len()
+        )
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for TracedTransport<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TracedTransport")
+            .field("inner", &self.inner)
+            .field("redaction", &self.redaction)
+            .field("classify_errors", &self.classify.is_some())
+            .finish()
+    }
+}
+
+fn record<O, E>(result: &Result<O, E>, duration: Duration, classify: Option<Classify>)
+where
+    E: fmt::Display + 'static,
+{
+    let duration_ms = duration.as_millis() as u64;
+
+    match result {
+        Ok(_) => tracing::info!(duration_ms, "email sent"),
+        Err(err) => match classify.and_then(|classify| classify(err)) {
+            Some(retryable) => tracing::warn!(
+                duration_ms,
+                error = %err,
+                retryable,
+                "email not sent"
+            ),
+            None => tracing::warn!(duration_ms, error = %err, "email not sent"),
+        },
+    }
+}
+
+/// Finds the value of the `Message-ID` header of a formatted message
+fn message_id(email: &[u8]) -> Option<&str> {
+    email
+        .split(|&b| b == b'\n')
+        .map(|line| match line.split_last() {
+            Some((b'\r', line)) => line,
+            _ => line,
+        })
+        .take_while(|line| !line.is_empty())
+        .find_map(|line| {
+            let colon = line.iter().position(|&b| b == b':')?;
+            let (name, value) = line.split_at(colon);
+            if name.eq_ignore_ascii_case(b"Message-ID") {
+                std::str::from_utf8(&value[1..]).ok().map(str::trim)
+            } else {
+                None
+            }
+        })
+}
+
+impl<T> Transport for TracedTransport<T>
+where
+    T: Transport,
+    T::Error: fmt::Display + 'static,
+{
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let span = self.span(envelope, email);
+        let _enter = span.enter();
+
+        let start = Instant::now();
+        let result = self.inner.send_raw(envelope, email);
+        record(&result, start.elapsed(), self.classify);
+        result
+    }
+}
+
+#[cfg(any(feature = "tokio1", feature = "async-std1"))]
+#[async_trait]
+impl<T> AsyncTransport for TracedTransport<T>
+where
+    T: AsyncTransport + Sync,
+    T::Error: fmt::Display + 'static,
+{
+    type Ok = T::Ok;
+    type Error = T::Error;
+
+    async fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let span = self.span(envelope, email);
+
+        let start = Instant::now();
+        let result = self
+            .inner
+            .send_raw(envelope, email)
+            .instrument(span.clone())
+            .await;
+        span.in_scope(|| record(&result, start.elapsed(), self.classify));
+        result
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn message_id_header() {
+        let email = concat!(
+            "From: NoBody <nobody@domain.tld>\r\n",
+            "message-id: <123@domain.tld>\r\n",
+            "\r\n",
+            "Message-ID: <456@domain.tld>\r\n"
+        );
+        assert_eq!(message_id(email.as_bytes()), Some("<123@domain.tld>"));
+
+        let email = "From: NoBody <nobody@domain.tld>\r\n\r\nMessage-ID: <456@domain.tld>\r\n";
+        assert_eq!(message_id(email.as_bytes()), None);
+    }
+
+    #[test]
+    fn redaction() {
+        let address: Address = "hei@domain.tld".parse().unwrap();
+
+        assert_eq!(Redaction::None.apply(&address), "hei@domain.tld");
+        assert_eq!(Redaction::Domain.apply(&address), "*@domain.tld");
+
+        assert_eq!(Redaction::Hash.apply(&address), "e24e1781684b4729");
+    }
+}
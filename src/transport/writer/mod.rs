@@ -0,0 +1,155 @@
+//! The writer transport writes the emails to any [`Write`] sink, like a buffer, the
+//! standard output or the standard input of another process.
+//!
+//! Unlike the [`file`](super::file) transport, it doesn't touch the filesystem: each email
+//! is written as is to the sink, followed by a delimiter, `\r\n` by default. The envelope
+//! can also be written before each email, as `X-Envelope-From` and `X-Envelope-To`
+//! headers, so that it can be read back with the message.
+//!
+//! #### Writer Transport
+//!
+//! ```rust
+//! # #[cfg(feature = "builder")]
+//! # {
+//! use lettre::{transport::writer::WriterTransport, Message, Transport};
+//!
+//! # use std::error::Error;
+//! # fn main() -> Result<(), Box<dyn Error>> {
+//! let email = Message::builder()
+//!     .from("NoBody <nobody@domain.tld>".parse()?)
+//!     .to("Hei <hei@domain.tld>".parse()?)
+//!     .subject("Happy new year")
+//!     .body(String::from("Be happy!"))?;
+//!
+//! let sender = WriterTransport::new(Vec::new())
+//!     .delimiter("\r\n.\r\n")
+//!     .with_envelope(true);
+//! sender.send(&email)?;
+//!
+//! let written = String::from_utf8(sender.into_inner())?;
+//! assert!(written.starts_with("X-Envelope-From: nobody@domain.tld\r\n"));
+//! # Ok(())
+//! # }
+//! # }
+//! ```
+
+use crate::{address::Envelope, Transport};
+use std::{
+    fmt,
+    io::{self, Write},
+    sync::{Mutex, MutexGuard},
+};
+
+/// Writes the emails to the inner sink, one after the other
+pub struct WriterTransport<W> {
+    writer: Mutex<W>,
+    delimiter: Vec<u8>,
+    envelope: bool,
+}
+
+impl<W: Write> WriterTransport<W> {
+    /// Writes the emails to `writer`, followed by `\r\n`, without the envelope
+    pub fn new(writer: W) -> Self {
+        Self {
+            writer: Mutex::new(writer),
+            delimiter: b"\r\n".to_vec(),
+            envelope: false,
+        }
+    }
+
+    /// Set the bytes written after each email, nothing is written when empty
+    pub fn delimiter<D: Into<Vec<u8>>>(mut self, delimiter: D) -> Self {
+        self.delimiter = delimiter.into();
+        self
+    }
+
+    /// Set whether the envelope is written as a header block before each email
+    ///
+    /// The sender is written in a `X-Envelope-From` header, `<>` if there is none, and
+    /// the recipients in a `X-Envelope-To` header, separated by commas.
+    pub fn with_envelope(mut self, envelope: bool) -> Self {
+        self.envelope = envelope;
+        self
+    }
+
+    /// Locks the inner sink, for example to read a buffer between two sends
+    pub fn writer(&self) -> MutexGuard<'_, W> {
+        self.writer
+            .lock()
+            .expect("Couldn't acquire lock to read the writer")
+    }
+
+    /// Get the inner sink
+    pub fn into_inner(self) -> W {
+        self.writer
+            .into_inner()
+            .expect("Couldn't acquire lock to take the writer")
+    }
+}
+
+impl<W> fmt::Debug for WriterTransport<W> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WriterTransport")
+            .field("delimiter", &String::from_utf8_lossy(&self.delimiter))
+            .field("envelope", &self.envelope)
+            .finish()
+    }
+}
+
+/// Formats the envelope as a block of headers
+fn envelope_headers(envelope: &Envelope) -> String {
+    let from = envelope.from().map(|from| from.as_ref()).unwrap_or("<>");
+    let to = envelope
+        .to()
+        .iter()
+        .map(|to| to.as_ref())
+        .collect::<Vec<&str>>()
+        .join(", ");
+    format!("X-Envelope-From: {}\r\nX-Envelope-To: {}\r\n", from, to)
+}
+
+impl<W: Write> Transport for WriterTransport<W> {
+    type Ok = ();
+    type Error = io::Error;
+
+    fn send_raw(&self, envelope: &Envelope, email: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let mut writer = self
+            .writer
+            .lock()
+            .expect("Couldn't acquire lock to write the email");
+
+        if self.envelope {
+            writer.write_all(envelope_headers(envelope).as_bytes())?;
+        }
+        writer.write_all(email)?;
+        writer.write_all(&self.delimiter)?;
+        writer.flush()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn envelope_headers_format() {
+        let envelope = Envelope::new(
+            Some("nobody@domain.tld".parse().unwrap()),
+            vec![
+                "hei@domain.tld".parse().unwrap(),
+                "yuin@domain.tld".parse().unwrap(),
+            ],
+        )
+        .unwrap();
+        assert_eq!(
+            envelope_headers(&envelope),
+            "X-Envelope-From: nobody@domain.tld\r\nX-Envelope-To: hei@domain.tld, yuin@domain.tld\r\n"
+        );
+
+        let envelope = Envelope::new(None, vec!["hei@domain.tld".parse().unwrap()]).unwrap();
+        assert_eq!(
+            envelope_headers(&envelope),
+            "X-Envelope-From: <>\r\nX-Envelope-To: hei@domain.tld\r\n"
+        );
+    }
+}
@@ -0,0 +1,78 @@
+#[cfg(test)]
+#[cfg(feature = "builder")]
+mod sync {
+    use lettre::{
+        address::Envelope,
+        transport::{
+            boxed::BoxedTransport,
+            stub::{self, StubTransport},
+        },
+        Message, Transport,
+    };
+
+    fn email() -> Message {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap()
+    }
+
+    #[test]
+    fn boxed_transport() {
+        let stub = StubTransport::new_ok();
+        let senders: Vec<BoxedTransport> = vec![
+            stub.clone().boxed(),
+            BoxedTransport::new(StubTransport::new(Err(stub::Error))),
+        ];
+
+        senders[0].send(&email()).unwrap();
+        senders[0]
+            .send_raw(
+                &Envelope::new(None, vec!["hei@domain.tld".parse().unwrap()]).unwrap(),
+                b"Be happy!",
+            )
+            .unwrap();
+        assert_eq!(stub.messages().len(), 2);
+
+        let err = senders[1].send(&email()).unwrap_err();
+        assert!(err.downcast_ref::<stub::Error>().is_some());
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "builder", feature = "tokio1"))]
+mod tokio_1 {
+    use lettre::{
+        transport::{
+            boxed::BoxedAsyncTransport,
+            stub::{self, StubTransport},
+        },
+        AsyncTransport, Message,
+    };
+
+    use tokio1_crate as tokio;
+
+    #[tokio::test]
+    async fn boxed_transport_tokio1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let stub = StubTransport::new_ok();
+        let senders: Vec<BoxedAsyncTransport> = vec![
+            stub.clone().boxed(),
+            StubTransport::new_transient_error().boxed(),
+        ];
+
+        senders[0].send(email.clone()).await.unwrap();
+        assert_eq!(stub.messages().len(), 1);
+
+        let err = senders[1].send(email).await.unwrap_err();
+        assert!(err.downcast_ref::<stub::TransientError>().is_some());
+    }
+}
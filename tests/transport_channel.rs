@@ -0,0 +1,168 @@
+#[cfg(test)]
+#[cfg(feature = "builder")]
+mod sync {
+    use std::{thread, time::Duration};
+
+    use lettre::{
+        transport::channel::{Backpressure, ChannelTransport, Error},
+        Message, Transport,
+    };
+
+    fn email() -> Message {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap()
+    }
+
+    #[test]
+    fn channel_transport() {
+        let email = email();
+        let (sender, receiver) = ChannelTransport::new();
+
+        let handle = {
+            let email = email.clone();
+            thread::spawn(move || {
+                sender.send(&email).unwrap();
+                sender.send(&email).unwrap();
+            })
+        };
+
+        for _ in 0..2 {
+            let (envelope, content) = receiver.recv_timeout(Duration::from_secs(5)).unwrap();
+            assert_eq!(&envelope, email.envelope());
+            assert_eq!(content, email.formatted());
+        }
+
+        handle.join().unwrap();
+        assert!(receiver.recv().is_none());
+    }
+
+    #[test]
+    fn channel_transport_timeout() {
+        let (_sender, receiver) = ChannelTransport::new();
+        assert!(receiver.recv_timeout(Duration::from_millis(10)).is_none());
+    }
+
+    #[test]
+    fn channel_transport_backpressure() {
+        let email = email();
+
+        let (sender, receiver) = ChannelTransport::bounded(1, Backpressure::Reject);
+        sender.send(&email).unwrap();
+        assert_eq!(sender.send(&email), Err(Error::Full));
+        assert!(receiver.try_recv().is_some());
+        sender.send(&email).unwrap();
+
+        let (sender, receiver) = ChannelTransport::bounded(1, Backpressure::Block);
+        sender.send(&email).unwrap();
+        let handle = {
+            let email = email.clone();
+            thread::spawn(move || sender.send(&email))
+        };
+        assert!(receiver.recv().is_some());
+        handle.join().unwrap().unwrap();
+        assert!(receiver.recv().is_some());
+        assert!(receiver.recv().is_none());
+
+        let (sender, receiver) = ChannelTransport::new();
+        drop(receiver);
+        assert_eq!(sender.send(&email), Err(Error::Disconnected));
+    }
+
+    #[test]
+    #[should_panic(expected = "the channel capacity must be at least 1")]
+    fn channel_transport_zero_capacity() {
+        ChannelTransport::bounded(0, Backpressure::Block);
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "builder", feature = "tokio1"))]
+mod tokio_1 {
+    use std::time::Duration;
+
+    use lettre::{
+        transport::channel::{Backpressure, ChannelTransport},
+        AsyncTransport, Message,
+    };
+
+    use tokio1_crate as tokio;
+
+    #[tokio::test]
+    async fn channel_transport_tokio1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let (sender, receiver) = ChannelTransport::bounded(1, Backpressure::Block);
+
+        let task = {
+            let email = email.clone();
+            tokio::spawn(async move {
+                sender.send(email.clone()).await.unwrap();
+                sender.send(email).await.unwrap();
+            })
+        };
+
+        for _ in 0..2 {
+            let (envelope, _content) =
+                tokio::time::timeout(Duration::from_secs(5), receiver.recv_async())
+                    .await
+                    .unwrap()
+                    .unwrap();
+            assert_eq!(&envelope, email.envelope());
+        }
+
+        task.await.unwrap();
+        assert!(receiver.recv_async().await.is_none());
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "builder", feature = "async-std1"))]
+mod asyncstd_1 {
+    use std::time::Duration;
+
+    use lettre::{
+        transport::channel::{Backpressure, ChannelTransport},
+        AsyncTransport, Message,
+    };
+
+    #[async_std::test]
+    async fn channel_transport_asyncstd1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let (sender, receiver) = ChannelTransport::bounded(1, Backpressure::Block);
+
+        let task = {
+            let email = email.clone();
+            async_std::task::spawn(async move {
+                sender.send(email.clone()).await.unwrap();
+                sender.send(email).await.unwrap();
+            })
+        };
+
+        for _ in 0..2 {
+            let (envelope, _content) =
+                async_std::future::timeout(Duration::from_secs(5), receiver.recv_async())
+                    .await
+                    .unwrap()
+                    .unwrap();
+            assert_eq!(&envelope, email.envelope());
+        }
+
+        task.await;
+        assert!(receiver.recv_async().await.is_none());
+    }
+}
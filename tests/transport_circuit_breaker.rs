@@ -0,0 +1,185 @@
+#[cfg(test)]
+#[cfg(feature = "builder")]
+mod sync {
+    use std::{fmt, thread, time::Duration};
+
+    use lettre::{
+        transport::{
+            circuit_breaker::{CircuitBreakerTransport, CircuitState, Error},
+            failover::{FailoverError, FailoverTransport},
+            stub::{self, StubTransport},
+        },
+        Message, Transport,
+    };
+
+    fn email() -> Message {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap()
+    }
+
+    #[test]
+    fn circuit_breaker_opens() {
+        let stub = StubTransport::new_transient_error();
+        let sender = CircuitBreakerTransport::new(stub.clone(), 3, Duration::from_secs(60));
+
+        for _ in 0..3 {
+            assert_eq!(sender.state(), CircuitState::Closed);
+            assert!(matches!(sender.send(&email()), Err(Error::Transport(_))));
+        }
+        assert_eq!(sender.state(), CircuitState::Open);
+        assert_eq!(sender.consecutive_failures(), 3);
+
+        assert!(matches!(sender.send(&email()), Err(Error::CircuitOpen)));
+        assert!(matches!(
+            sender.clone().send(&email()),
+            Err(Error::CircuitOpen)
+        ));
+        assert_eq!(stub.messages().len(), 3);
+    }
+
+    #[test]
+    fn circuit_breaker_half_open() {
+        let stub =
+            StubTransport::new_sequence(vec![Err(stub::TransientError), Err(stub::TransientError)]);
+        let sender = CircuitBreakerTransport::new(stub.clone(), 1, Duration::from_millis(50));
+
+        assert!(sender.send(&email()).is_err());
+        assert_eq!(sender.state(), CircuitState::Open);
+
+        // The trial fails, and the circuit opens again
+        thread::sleep(Duration::from_millis(50));
+        assert_eq!(sender.state(), CircuitState::HalfOpen);
+        assert!(matches!(sender.send(&email()), Err(Error::Transport(_))));
+        assert_eq!(sender.state(), CircuitState::Open);
+        assert!(matches!(sender.send(&email()), Err(Error::CircuitOpen)));
+
+        // The trial succeeds, and the circuit closes
+        thread::sleep(Duration::from_millis(50));
+        sender.send(&email()).unwrap();
+        assert_eq!(sender.state(), CircuitState::Closed);
+        assert_eq!(sender.consecutive_failures(), 0);
+        assert_eq!(stub.messages().len(), 3);
+    }
+
+    #[derive(Debug)]
+    enum StubError {
+        Transient,
+        Permanent,
+    }
+
+    impl fmt::Display for StubError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt::Debug::fmt(self, f)
+        }
+    }
+
+    impl std::error::Error for StubError {}
+
+    impl FailoverError for StubError {
+        fn should_try_next(&self) -> bool {
+            matches!(self, StubError::Transient)
+        }
+    }
+
+    #[test]
+    fn circuit_breaker_permanent_errors() {
+        let stub = StubTransport::new_sequence(vec![
+            Err(StubError::Transient),
+            Err(StubError::Permanent),
+            Err(StubError::Transient),
+        ]);
+        let sender = CircuitBreakerTransport::new(stub, 2, Duration::from_secs(60));
+
+        assert!(sender.send(&email()).is_err());
+        assert!(sender.send(&email()).is_err());
+        assert_eq!(sender.consecutive_failures(), 0);
+        assert!(sender.send(&email()).is_err());
+        assert_eq!(sender.state(), CircuitState::Closed);
+    }
+
+    #[test]
+    fn circuit_breaker_failover() {
+        let primary = StubTransport::new_transient_error();
+        let fallback = StubTransport::new_ok();
+        let sender = FailoverTransport::new(CircuitBreakerTransport::new(
+            primary.clone(),
+            1,
+            Duration::from_secs(60),
+        ))
+        .fallback(fallback.clone());
+
+        assert_eq!(sender.send(&email()).unwrap().index(), 1);
+        assert_eq!(sender.send(&email()).unwrap().index(), 1);
+        assert_eq!(primary.messages().len(), 1);
+        assert_eq!(fallback.messages().len(), 2);
+    }
+
+    #[test]
+    fn circuit_breaker_around_failover() {
+        let relays = FailoverTransport::new(StubTransport::new_transient_error())
+            .fallback(StubTransport::new_transient_error().boxed());
+        let sender = CircuitBreakerTransport::new(relays, 2, Duration::from_secs(60));
+
+        assert!(matches!(sender.send(&email()), Err(Error::Transport(_))));
+        assert!(matches!(sender.send(&email()), Err(Error::Transport(_))));
+        assert_eq!(sender.state(), CircuitState::Open);
+        assert!(matches!(sender.send(&email()), Err(Error::CircuitOpen)));
+
+        let sender = CircuitBreakerTransport::new(
+            StubTransport::new_transient_error().boxed(),
+            1,
+            Duration::from_secs(60),
+        );
+        assert!(sender.send(&email()).is_err());
+        assert_eq!(sender.state(), CircuitState::Open);
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "builder", feature = "tokio1"))]
+mod tokio_1 {
+    use std::time::Duration;
+
+    use lettre::{
+        transport::{
+            circuit_breaker::{CircuitBreakerTransport, CircuitState, Error},
+            stub::{self, StubTransport},
+        },
+        AsyncTransport, Message,
+    };
+
+    use tokio1_crate as tokio;
+
+    #[tokio::test]
+    async fn circuit_breaker_tokio1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender = CircuitBreakerTransport::new(
+            StubTransport::new_sequence(vec![Err(stub::TransientError)]),
+            1,
+            Duration::from_millis(50),
+        );
+
+        assert!(matches!(
+            sender.send(email.clone()).await,
+            Err(Error::Transport(_))
+        ));
+        assert!(matches!(
+            sender.send(email.clone()).await,
+            Err(Error::CircuitOpen)
+        ));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        sender.send(email).await.unwrap();
+        assert_eq!(sender.state(), CircuitState::Closed);
+    }
+}
@@ -0,0 +1,97 @@
+#[cfg(test)]
+#[cfg(all(
+    feature = "smtp-transport",
+    feature = "builder",
+    feature = "test-server"
+))]
+mod sync {
+    use lettre::{
+        test_server::{Action, MockSmtpServer, Step},
+        transport::smtp::direct::DirectTransport,
+        Message, Transport,
+    };
+
+    fn email() -> Message {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap()
+    }
+
+    fn server() -> lettre::test_server::MockSmtpServerBuilder {
+        MockSmtpServer::builder().ehlo_keywords(vec!["8BITMIME"])
+    }
+
+    #[test]
+    fn direct_transport_priority() {
+        let server = server()
+            .on(
+                Step::Greeting,
+                1,
+                Action::Reply(421, "4.3.2 Too busy".to_owned()),
+            )
+            .start()
+            .unwrap();
+
+        // The host with the lowest priority value is tried first
+        let sender = DirectTransport::builder(vec![(20, "127.0.0.1"), (10, "localhost")])
+            .port(server.port())
+            .build();
+        let response = sender.send(&email()).unwrap();
+        assert_eq!(response.host(), "127.0.0.1");
+        assert!(response.response().is_positive());
+        assert_eq!(server.connections(), 2);
+        assert_eq!(server.messages().len(), 1);
+    }
+
+    #[test]
+    fn direct_transport_permanent_error() {
+        let server = server()
+            .on(
+                Step::Rcpt,
+                1,
+                Action::Reply(550, "5.1.1 No such user".to_owned()),
+            )
+            .start()
+            .unwrap();
+
+        let sender = DirectTransport::builder(vec![(10, "127.0.0.1"), (20, "localhost")])
+            .port(server.port())
+            .build();
+        let err = sender.send(&email()).unwrap_err();
+        assert!(err.is_permanent());
+        assert_eq!(server.connections(), 1);
+    }
+
+    #[test]
+    fn direct_transport_no_host() {
+        let server = server()
+            .on(
+                Step::Greeting,
+                1,
+                Action::Reply(421, "4.3.2 Too busy".to_owned()),
+            )
+            .on(
+                Step::Greeting,
+                2,
+                Action::Reply(421, "4.3.2 Shutting down".to_owned()),
+            )
+            .start()
+            .unwrap();
+
+        let sender = DirectTransport::builder(vec![(10, "127.0.0.1"), (20, "localhost")])
+            .port(server.port())
+            .build();
+        let err = sender.send(&email()).unwrap_err();
+        assert!(err.is_transient());
+        assert_eq!(
+            err.to_string(),
+            "transient error (421): 4.3.2 Shutting down"
+        );
+
+        let sender = DirectTransport::builder(Vec::<(u16, String)>::new()).build();
+        assert!(sender.send(&email()).is_err());
+    }
+}
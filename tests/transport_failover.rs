@@ -0,0 +1,168 @@
+#[cfg(test)]
+#[cfg(feature = "builder")]
+mod sync {
+    use lettre::{
+        transport::{
+            channel::ChannelTransport,
+            failover::FailoverTransport,
+            stub::{Error, StubTransport},
+        },
+        Message, Transport,
+    };
+
+    fn email() -> Message {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap()
+    }
+
+    #[test]
+    fn failover_transport_primary() {
+        let primary = StubTransport::new_ok();
+        let fallback = StubTransport::new_ok();
+        let sender = FailoverTransport::new(primary.clone()).fallback(fallback.clone());
+
+        let delivery = sender.send(&email()).unwrap();
+        assert_eq!(delivery.index(), 0);
+        assert!(delivery.skipped_errors().is_empty());
+        assert_eq!(delivery.response::<()>(), Some(&()));
+        assert_eq!(primary.messages().len(), 1);
+        assert!(fallback.messages().is_empty());
+    }
+
+    #[test]
+    fn failover_transport_transient() {
+        let (channel, receiver) = ChannelTransport::new();
+        drop(receiver);
+        let fallback = StubTransport::new_ok();
+        let sender = FailoverTransport::new(StubTransport::new_transient_error())
+            .fallback(channel)
+            .fallback(fallback.clone());
+
+        let delivery = sender.send(&email()).unwrap();
+        assert_eq!(delivery.index(), 2);
+        assert_eq!(delivery.skipped_errors().len(), 2);
+        assert_eq!(fallback.messages().len(), 1);
+    }
+
+    #[test]
+    fn failover_transport_permanent() {
+        let fallback = StubTransport::new_ok();
+        let sender = FailoverTransport::new(StubTransport::new_transient_error())
+            .fallback(StubTransport::new_error())
+            .fallback(fallback.clone());
+
+        let err = sender.send(&email()).unwrap_err();
+        assert_eq!(err.errors().len(), 2);
+        assert!(err.errors()[1].downcast_ref::<Error>().is_some());
+        assert!(fallback.messages().is_empty());
+    }
+
+    #[test]
+    fn failover_transport_nested() {
+        let fallback = StubTransport::new_ok();
+        let primary = FailoverTransport::new(StubTransport::new_transient_error())
+            .fallback(StubTransport::new_transient_error().boxed());
+        let sender = FailoverTransport::new(primary).fallback(fallback.clone().boxed());
+
+        let delivery = sender.send(&email()).unwrap();
+        assert_eq!(delivery.index(), 1);
+        assert_eq!(fallback.messages().len(), 1);
+
+        let primary = FailoverTransport::new(StubTransport::new_error());
+        let sender = FailoverTransport::new(primary).fallback(fallback.clone());
+        assert!(sender.send(&email()).is_err());
+        assert_eq!(fallback.messages().len(), 1);
+    }
+
+    #[cfg(all(feature = "sendmail-transport", feature = "file-transport"))]
+    #[test]
+    fn failover_transport_sendmail_file() {
+        use lettre::{FileTransport, SendmailTransport};
+
+        let dir = std::env::temp_dir().join("lettre-failover");
+        std::fs::create_dir_all(&dir).unwrap();
+        let sender =
+            FailoverTransport::new(SendmailTransport::new_with_command("/nonexistent/sendmail"))
+                .fallback(FileTransport::new(&dir));
+
+        let delivery = sender.send(&email()).unwrap();
+        assert_eq!(delivery.index(), 1);
+        let id = delivery.response::<String>().unwrap();
+        std::fs::remove_file(dir.join(format!("{}.eml", id))).unwrap();
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "builder", feature = "tokio1"))]
+mod tokio_1 {
+    use lettre::{
+        transport::{
+            failover::AsyncFailoverTransport,
+            stub::{StubTransport, TransientError},
+        },
+        AsyncTransport, Message,
+    };
+
+    use tokio1_crate as tokio;
+
+    #[tokio::test]
+    async fn failover_transport_tokio1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender =
+            AsyncFailoverTransport::new(StubTransport::new_sequence(vec![Err(TransientError)]))
+                .fallback(StubTransport::new_ok());
+        let delivery = sender.send(email.clone()).await.unwrap();
+        assert_eq!(delivery.index(), 1);
+        assert_eq!(delivery.skipped_errors().len(), 1);
+
+        let delivery = sender.send(email.clone()).await.unwrap();
+        assert_eq!(delivery.index(), 0);
+
+        let sender = AsyncFailoverTransport::new(StubTransport::new_error())
+            .fallback(StubTransport::new_ok());
+        assert_eq!(sender.send(email).await.unwrap_err().errors().len(), 1);
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "builder", feature = "async-std1"))]
+mod asyncstd_1 {
+    use lettre::{
+        transport::{
+            failover::AsyncFailoverTransport,
+            stub::{StubTransport, TransientError},
+        },
+        AsyncTransport, Message,
+    };
+
+    #[async_std::test]
+    async fn failover_transport_asyncstd1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender =
+            AsyncFailoverTransport::new(StubTransport::new_sequence(vec![Err(TransientError)]))
+                .fallback(StubTransport::new_ok());
+        let delivery = sender.send(email.clone()).await.unwrap();
+        assert_eq!(delivery.index(), 1);
+        assert_eq!(delivery.skipped_errors().len(), 1);
+
+        let sender = AsyncFailoverTransport::new(StubTransport::new_error())
+            .fallback(StubTransport::new_ok());
+        assert_eq!(sender.send(email).await.unwrap_err().errors().len(), 1);
+    }
+}
@@ -25,6 +25,7 @@ mod sync {
             .to("Hei <hei@domain.tld>".parse().unwrap())
             .subject("Happy new year")
             .date(default_date())
+            .no_user_agent()
             .body(String::from("Be happy!"))
             .unwrap();
 
@@ -60,6 +61,7 @@ mod sync {
             .to("Hei <hei@domain.tld>".parse().unwrap())
             .subject("Happy new year")
             .date(default_date())
+            .no_user_agent()
             .body(String::from("Be happy!"))
             .unwrap();
 
@@ -99,6 +101,49 @@ mod sync {
         remove_file(eml_file).unwrap();
         remove_file(json_file).unwrap();
     }
+
+    #[test]
+    #[cfg(feature = "file-transport-envelope")]
+    fn file_transport_lf() {
+        let sender = FileTransport::with_envelope(temp_dir())
+            .line_ending(lettre::transport::file::LineEnding::Lf);
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .date(default_date())
+            .no_user_agent()
+            .body(String::from("Be happy!\r\nReally!"))
+            .unwrap();
+
+        let id = sender.send(&email).unwrap();
+
+        let eml_file = temp_dir().join(format!("{}.eml", id));
+        let eml = read_to_string(&eml_file).unwrap();
+        let json_file = temp_dir().join(format!("{}.json", id));
+
+        assert_eq!(
+            eml,
+            concat!(
+                "From: NoBody <nobody@domain.tld>\n",
+                "To: Hei <hei@domain.tld>\n",
+                "Subject: Happy new year\n",
+                "Date: Tue, 15 Nov 1994 08:12:31 -0000\n",
+                "Content-Transfer-Encoding: 7bit\n",
+                "\n",
+                "Be happy!\n",
+                "Really!"
+            )
+        );
+
+        let (e, m) = sender.read(&id).unwrap();
+
+        assert_eq!(&e, email.envelope());
+        assert_eq!(m, email.formatted());
+
+        remove_file(eml_file).unwrap();
+        remove_file(json_file).unwrap();
+    }
 }
 
 #[cfg(test)]
@@ -122,6 +167,7 @@ mod tokio_1 {
             .to("Hei <hei@domain.tld>".parse().unwrap())
             .subject("Happy new year")
             .date(default_date())
+            .no_user_agent()
             .body(String::from("Be happy!"))
             .unwrap();
 
@@ -171,6 +217,7 @@ mod asyncstd_1 {
             .to("Hei <hei@domain.tld>".parse().unwrap())
             .subject("Happy new year")
             .date(default_date())
+            .no_user_agent()
             .body(String::from("Be happy!"))
             .unwrap();
 
@@ -0,0 +1,78 @@
+#[cfg(test)]
+#[cfg(feature = "builder")]
+mod sync {
+    use lettre::{
+        transport::{
+            filter::{FilterTransport, Filtered},
+            stub::StubTransport,
+        },
+        Address, Message, Transport,
+    };
+
+    #[test]
+    fn filter_transport() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .cc("Staging <staging@example.com>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let stub = StubTransport::new_ok();
+        let allowed = FilterTransport::new(stub.clone(), |recipient: &Address| {
+            recipient.domain() == "example.com"
+        });
+        let result = allowed.send(&email).unwrap();
+        assert_eq!(
+            result,
+            Filtered::Sent {
+                response: (),
+                removed: vec!["hei@domain.tld".parse().unwrap()],
+            }
+        );
+
+        let denied = FilterTransport::new(stub.clone(), |_: &Address| false);
+        let result = denied.send(&email).unwrap();
+        assert!(result.is_skipped());
+        assert_eq!(result.removed().len(), 2);
+        assert_eq!(result.response(), None);
+
+        let messages = stub.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(
+            messages[0].0.to(),
+            &["staging@example.com".parse::<Address>().unwrap()]
+        );
+        assert_eq!(messages[0].1, email.formatted());
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "builder", feature = "tokio1"))]
+mod tokio_1 {
+    use lettre::{
+        transport::{filter::FilterTransport, stub::StubTransport},
+        Address, AsyncTransport, Message,
+    };
+
+    use tokio1_crate as tokio;
+
+    #[tokio::test]
+    async fn filter_transport_tokio1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let stub = StubTransport::new_ok();
+        let sender = FilterTransport::new(stub.clone(), |recipient: &Address| {
+            recipient.domain() != "domain.tld"
+        });
+        let result = sender.send(email).await.unwrap();
+        assert!(result.is_skipped());
+        assert!(stub.messages().is_empty());
+    }
+}
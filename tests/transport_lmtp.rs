@@ -0,0 +1,156 @@
+#[cfg(test)]
+#[cfg(all(feature = "builder", feature = "smtp-transport"))]
+mod sync {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        thread::{self, JoinHandle},
+    };
+
+    use lettre::{
+        transport::smtp::{extension::ClientId, lmtp::LmtpTransport},
+        Message, Transport,
+    };
+
+    /// Replies to the lines starting with the commands of the script, in order,
+    /// and returns the lines received from the client
+    fn serve<S: Read + Write>(mut stream: S, script: &[(&str, &str)]) -> Vec<String> {
+        stream
+            .write_all(b"220 lmtp.domain.tld LMTP ready\r\n")
+            .unwrap();
+
+        let mut reader = BufReader::new(stream);
+        let mut script = script.iter();
+        let mut next = script.next();
+        let mut received = Vec::new();
+
+        while let Some((command, reply)) = next {
+            let mut line = String::new();
+            if reader.read_line(&mut line).unwrap() == 0 {
+                break;
+            }
+
+            if line.starts_with(command) {
+                reader.get_mut().write_all(reply.as_bytes()).unwrap();
+                next = script.next();
+            }
+            received.push(line);
+        }
+
+        received
+    }
+
+    fn email() -> Message {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .to("Unknown <unknown@domain.tld>".parse().unwrap())
+            .to("Full <full@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap()
+    }
+
+    const SCRIPT: &[(&str, &str)] = &[
+        (
+            "LHLO",
+            "250-lmtp.domain.tld\r\n250-PIPELINING\r\n250 ENHANCEDSTATUSCODES\r\n",
+        ),
+        ("MAIL FROM:<nobody@domain.tld>", "250 2.1.0 OK\r\n"),
+        ("RCPT TO:<hei@domain.tld>", "250 2.1.5 OK\r\n"),
+        (
+            "RCPT TO:<unknown@domain.tld>",
+            "550 5.1.1 User doesn't exist\r\n",
+        ),
+        ("RCPT TO:<full@domain.tld>", "250 2.1.5 OK\r\n"),
+        ("DATA", "354 OK\r\n"),
+        (
+            ".\r\n",
+            "250 2.0.0 <hei@domain.tld> Saved\r\n452 4.2.2 <full@domain.tld> Quota exceeded\r\n",
+        ),
+        ("QUIT", "221 2.0.0 Bye\r\n"),
+    ];
+
+    fn check(server: JoinHandle<Vec<String>>, sender: LmtpTransport) {
+        let response = sender.send(&email()).unwrap();
+        let received = server.join().unwrap();
+
+        assert_eq!(received[0], "LHLO localhost\r\n");
+        assert!(received.contains(&"Be happy!\r\n".to_owned()));
+        assert_eq!(received.last().unwrap(), "QUIT\r\n");
+
+        let delivered: Vec<String> = response.delivered().map(ToString::to_string).collect();
+        assert_eq!(delivered, vec!["hei@domain.tld"]);
+
+        let rejected: Vec<(String, bool)> = response
+            .rejected()
+            .map(|(address, err)| (address.to_string(), err.is_permanent()))
+            .collect();
+        assert_eq!(
+            rejected,
+            vec![
+                ("unknown@domain.tld".to_owned(), true),
+                ("full@domain.tld".to_owned(), false)
+            ]
+        );
+        assert!(!response.is_delivered());
+    }
+
+    #[test]
+    fn lmtp_transport_tcp() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || serve(listener.accept().unwrap().0, SCRIPT));
+
+        let sender = LmtpTransport::builder("127.0.0.1")
+            .port(port)
+            .hello_name(ClientId::Domain("localhost".to_owned()))
+            .build();
+        check(server, sender);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn lmtp_transport_unix() {
+        use std::os::unix::net::UnixListener;
+
+        let path = std::env::temp_dir().join(format!("lettre-lmtp-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let server = thread::spawn(move || serve(listener.accept().unwrap().0, SCRIPT));
+
+        let sender = LmtpTransport::unix(&path)
+            .hello_name(ClientId::Domain("localhost".to_owned()))
+            .build();
+        check(server, sender);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn lmtp_transport_all_rejected() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = thread::spawn(move || {
+            serve(
+                listener.accept().unwrap().0,
+                &[
+                    ("LHLO", "250 lmtp.domain.tld\r\n"),
+                    ("MAIL", "250 OK\r\n"),
+                    ("RCPT", "550 No such user\r\n"),
+                    ("RCPT", "550 No such user\r\n"),
+                    ("RCPT", "550 No such user\r\n"),
+                    ("RSET", "250 OK\r\n"),
+                    ("QUIT", "221 Bye\r\n"),
+                ],
+            )
+        });
+
+        let sender = LmtpTransport::builder("127.0.0.1").port(port).build();
+        let response = sender.send(&email()).unwrap();
+        let received = server.join().unwrap();
+
+        assert_eq!(response.delivered().count(), 0);
+        assert_eq!(response.rejected().count(), 3);
+        assert!(!received.iter().any(|line| line.starts_with("DATA")));
+    }
+}
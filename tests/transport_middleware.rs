@@ -0,0 +1,214 @@
+#[cfg(test)]
+#[cfg(feature = "builder")]
+mod sync {
+    use lettre::{
+        error::Error,
+        message::{
+            header::{Bcc, ContentType, HeaderName, Subject, UserAgent},
+            Mailboxes, SinglePart,
+        },
+        transport::{
+            middleware::{MiddlewareMessage, MiddlewareTransport},
+            stub::StubTransport,
+        },
+        Message, Transport,
+    };
+
+    #[test]
+    fn middleware_transport() {
+        let email = Message::builder()
+            .no_user_agent()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let stub = StubTransport::new_ok();
+        let sender = MiddlewareTransport::new(stub.clone())
+            .before_send(|message: &mut MiddlewareMessage<'_>| {
+                message.set(UserAgent::from("lettre".to_owned())).unwrap()
+            })
+            .before_send(|message: &mut MiddlewareMessage<'_>| {
+                let agent = message.headers().get::<UserAgent>().unwrap();
+                message
+                    .insert_raw(
+                        HeaderName::new_from_ascii_str("X-Agent"),
+                        format!("{} via middleware", agent.as_ref()),
+                    )
+                    .unwrap()
+            })
+            .before_send(|message: &mut MiddlewareMessage<'_>| {
+                let bcc: Mailboxes = "Eve <eve@domain.tld>".parse().unwrap();
+                assert!(matches!(
+                    message.set(Bcc::from(bcc)),
+                    Err(Error::EnvelopeHeader(name)) if name == "Bcc"
+                ));
+                assert!(matches!(
+                    message.remove_raw("to"),
+                    Err(Error::EnvelopeHeader(name)) if name == "To"
+                ));
+                assert!(matches!(
+                    message.set(Subject::from("Hi\r\nBcc: eve@domain.tld".to_owned())),
+                    Err(Error::HeaderLineBreak)
+                ));
+            });
+        sender.send(&email).unwrap();
+        sender.send_raw(email.envelope(), b"raw").unwrap();
+
+        let messages = stub.messages();
+        let sent = String::from_utf8(messages[0].1.clone()).unwrap();
+        assert!(sent.contains("User-Agent: lettre\r\n"));
+        assert!(sent.contains("X-Agent: lettre via middleware\r\n"));
+        assert!(sent.contains("To: Hei <hei@domain.tld>\r\nSubject: Happy new year\r\n"));
+        assert_eq!(messages[0].0, *email.envelope());
+        assert!(email.headers().get::<UserAgent>().is_none());
+        assert_eq!(messages[1].1, b"raw");
+    }
+
+    fn body_hash(body: &[u8]) -> String {
+        ring::digest::digest(&ring::digest::SHA256, body)
+            .as_ref()
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    #[test]
+    fn middleware_transport_body() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy! <script>alert(1)</script>"))
+            .unwrap();
+
+        let stub = StubTransport::new_ok();
+        let sender = MiddlewareTransport::new(stub.clone())
+            .before_send(|message: &mut MiddlewareMessage<'_>| {
+                let body = String::from_utf8(message.formatted_body()).unwrap();
+                message.set_body(body.replace("<script>alert(1)</script>", ""));
+            })
+            .before_send(|message: &mut MiddlewareMessage<'_>| {
+                let hash = body_hash(&message.formatted_body());
+                message
+                    .insert_raw(HeaderName::new_from_ascii_str("X-Hash"), hash)
+                    .unwrap()
+            });
+        sender.send(&email).unwrap();
+
+        let sent = String::from_utf8(stub.messages()[0].1.clone()).unwrap();
+        let (headers, body) = sent.split_at(sent.find("\r\n\r\n").unwrap() + 4);
+        assert_eq!(body, "Be happy! ");
+        assert!(headers.contains(&format!("X-Hash: {}\r\n", body_hash(body.as_bytes()))));
+    }
+
+    #[test]
+    fn middleware_transport_replace_part() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .header(ContentType::TEXT_HTML)
+            .body(String::from("<p>Be happy!</p>"))
+            .unwrap();
+
+        let stub = StubTransport::new_ok();
+        let sender = MiddlewareTransport::new(stub.clone()).before_send(
+            |message: &mut MiddlewareMessage<'_>| {
+                message
+                    .set_singlepart(SinglePart::plain(String::from("Be happy!")))
+                    .unwrap();
+                assert!(matches!(
+                    message.set_singlepart(
+                        SinglePart::builder()
+                            .header(ContentType::TEXT_PLAIN)
+                            .description("Hi\r\nBcc: eve@domain.tld")
+                            .body(String::from("Be happy!"))
+                    ),
+                    Err(Error::HeaderLineBreak)
+                ));
+            },
+        );
+        sender.send(&email).unwrap();
+
+        let sent = String::from_utf8(stub.messages()[0].1.clone()).unwrap();
+        assert!(!sent.contains("text/html"));
+        assert!(sent.contains("MIME-Version: 1.0\r\n"));
+        assert!(sent.ends_with(concat!(
+            "Content-Type: text/plain; charset=utf-8\r\n",
+            "Content-Transfer-Encoding: 7bit\r\n",
+            "\r\n",
+            "Be happy!\r\n"
+        )));
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "builder", feature = "tokio1"))]
+mod tokio_1 {
+    use lettre::{
+        message::header::UserAgent,
+        transport::{
+            middleware::{MiddlewareMessage, MiddlewareTransport},
+            stub::StubTransport,
+        },
+        AsyncTransport, Message,
+    };
+
+    use tokio1_crate as tokio;
+
+    #[tokio::test]
+    async fn middleware_transport_tokio1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let stub = StubTransport::new_ok();
+        let sender = MiddlewareTransport::new(stub.clone()).before_send(
+            |message: &mut MiddlewareMessage<'_>| {
+                message.set(UserAgent::from("lettre".to_owned())).unwrap()
+            },
+        );
+        sender.send(email).await.unwrap();
+
+        let sent = String::from_utf8(stub.messages()[0].1.clone()).unwrap();
+        assert!(sent.contains("User-Agent: lettre\r\n"));
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "builder", feature = "async-std1"))]
+mod asyncstd_1 {
+    use lettre::{
+        message::header::UserAgent,
+        transport::{
+            middleware::{MiddlewareMessage, MiddlewareTransport},
+            stub::StubTransport,
+        },
+        AsyncTransport, Message,
+    };
+
+    #[async_std::test]
+    async fn middleware_transport_asyncstd1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let stub = StubTransport::new_ok();
+        let sender = MiddlewareTransport::new(stub.clone()).before_send(
+            |message: &mut MiddlewareMessage<'_>| {
+                message.set(UserAgent::from("lettre".to_owned())).unwrap()
+            },
+        );
+        sender.send(email).await.unwrap();
+
+        let sent = String::from_utf8(stub.messages()[0].1.clone()).unwrap();
+        assert!(sent.contains("User-Agent: lettre\r\n"));
+    }
+}
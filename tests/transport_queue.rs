@@ -0,0 +1,199 @@
+#[cfg(test)]
+#[cfg(all(feature = "file-transport-envelope", feature = "builder"))]
+mod sync {
+    use std::{
+        env::temp_dir,
+        fs,
+        path::PathBuf,
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use lettre::{
+        transport::{
+            queue::{Error, Outcome, QueueTransport},
+            stub::{self, StubTransport},
+        },
+        FileTransport, Message, Transport,
+    };
+
+    fn email() -> Message {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap()
+    }
+
+    fn queue_dir(name: &str) -> PathBuf {
+        let path = temp_dir().join(format!("lettre-queue-{}", name));
+        let _ = fs::remove_dir_all(&path);
+        path
+    }
+
+    fn count_files(path: &PathBuf) -> usize {
+        fs::read_dir(path)
+            .unwrap()
+            .filter(|entry| entry.as_ref().unwrap().path().is_file())
+            .count()
+    }
+
+    type Outcomes = Arc<Mutex<Vec<(String, String)>>>;
+
+    fn record(outcomes: &Outcomes) -> impl Fn(&str, &Outcome<'_>) + Send + Sync + 'static {
+        let outcomes = Arc::clone(outcomes);
+        move |id, outcome| {
+            let outcome = match outcome {
+                Outcome::Delivered => "delivered".to_owned(),
+                Outcome::Retrying { attempt, .. } => format!("retrying {}", attempt),
+                Outcome::Postponed { .. } => "postponed".to_owned(),
+                Outcome::DeadLettered { .. } => "dead".to_owned(),
+            };
+            outcomes.lock().unwrap().push((id.to_owned(), outcome));
+        }
+    }
+
+    #[test]
+    fn queue_transport() {
+        let path = queue_dir("deliver");
+        let stub = StubTransport::new_ok();
+        let outcomes = Outcomes::default();
+
+        let sender = QueueTransport::builder(&path, stub.clone())
+            .on_outcome(record(&outcomes))
+            .build()
+            .unwrap();
+        let first = sender.send(&email()).unwrap();
+        let second = sender.send(&email()).unwrap();
+        sender.shutdown();
+
+        assert_eq!(stub.messages().len(), 2);
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            vec![
+                (first, "delivered".to_owned()),
+                (second, "delivered".to_owned())
+            ]
+        );
+        assert_eq!(count_files(&path), 0);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn queue_transport_retry() {
+        let path = queue_dir("retry");
+        let stub = StubTransport::new_sequence(vec![Err(stub::TransientError), Ok(())]);
+        let outcomes = Outcomes::default();
+
+        let sender = QueueTransport::builder(&path, stub.clone())
+            .retry_delay(Duration::from_millis(10), Duration::from_millis(10))
+            .on_outcome(record(&outcomes))
+            .build()
+            .unwrap();
+        let id = sender.send(&email()).unwrap();
+        while !sender.is_empty() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        sender.shutdown();
+
+        assert_eq!(stub.messages().len(), 2);
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            vec![
+                (id.clone(), "retrying 1".to_owned()),
+                (id, "delivered".to_owned())
+            ]
+        );
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn queue_transport_dead_letter() {
+        let path = queue_dir("dead");
+        let outcomes = Outcomes::default();
+
+        let sender = QueueTransport::builder(&path, StubTransport::new_error())
+            .on_outcome(record(&outcomes))
+            .build()
+            .unwrap();
+        let id = sender.send(&email()).unwrap();
+        sender.shutdown();
+
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            vec![(id.clone(), "dead".to_owned())]
+        );
+        assert_eq!(count_files(&path), 0);
+        assert!(path.join("dead").join(format!("{}.eml", id)).is_file());
+        assert!(path.join("dead").join(format!("{}.json", id)).is_file());
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn queue_transport_postponed_on_shutdown() {
+        let path = queue_dir("postponed");
+        let stub = StubTransport::new_transient_error();
+        let outcomes = Outcomes::default();
+
+        let sender = QueueTransport::builder(&path, stub)
+            .retry_delay(Duration::from_secs(60), Duration::from_secs(60))
+            .on_outcome(record(&outcomes))
+            .build()
+            .unwrap();
+        let id = sender.send(&email()).unwrap();
+        while outcomes.lock().unwrap().is_empty() {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+        sender.shutdown();
+
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            vec![
+                (id.clone(), "retrying 1".to_owned()),
+                (id, "postponed".to_owned())
+            ]
+        );
+        assert_eq!(count_files(&path), 2);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn queue_transport_full() {
+        let path = queue_dir("full");
+        let stub = StubTransport::new_ok().with_delay(Duration::from_millis(100));
+
+        let sender = QueueTransport::builder(&path, stub.clone())
+            .max_queued(1)
+            .build()
+            .unwrap();
+        sender.send(&email()).unwrap();
+        assert!(matches!(sender.send(&email()), Err(Error::Full)));
+        sender.shutdown();
+
+        assert_eq!(stub.messages().len(), 1);
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn queue_transport_recover() {
+        let path = queue_dir("recover");
+        fs::create_dir_all(&path).unwrap();
+        let id = FileTransport::with_envelope(&path).send(&email()).unwrap();
+
+        let stub = StubTransport::new_ok();
+        let outcomes = Outcomes::default();
+        QueueTransport::builder(&path, stub.clone())
+            .on_outcome(record(&outcomes))
+            .build()
+            .unwrap()
+            .shutdown();
+
+        assert_eq!(stub.messages().len(), 1);
+        assert_eq!(
+            *outcomes.lock().unwrap(),
+            vec![(id, "delivered".to_owned())]
+        );
+        fs::remove_dir_all(&path).unwrap();
+    }
+}
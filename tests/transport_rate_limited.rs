@@ -0,0 +1,148 @@
+#[cfg(test)]
+#[cfg(feature = "builder")]
+mod sync {
+    use std::time::{Duration, Instant};
+
+    use lettre::{
+        transport::{
+            rate_limited::{Error, RateLimit, RateLimitedTransport},
+            stub::{self, StubTransport},
+        },
+        Message, Transport,
+    };
+
+    fn email() -> Message {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap()
+    }
+
+    #[test]
+    fn rate_limited_transport() {
+        let stub = StubTransport::new_ok();
+        let sender =
+            RateLimitedTransport::new(stub.clone(), RateLimit::new(2, Duration::from_millis(200)));
+
+        let start = Instant::now();
+        sender.send(&email()).unwrap();
+        sender.send(&email()).unwrap();
+        assert!(start.elapsed() < Duration::from_millis(100));
+
+        sender.send(&email()).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+        assert_eq!(stub.messages().len(), 3);
+    }
+
+    #[test]
+    fn rate_limited_transport_try_send() {
+        let stub = StubTransport::new_ok();
+        let sender =
+            RateLimitedTransport::new(stub.clone(), RateLimit::new(1, Duration::from_millis(100)));
+        let clone = sender.clone();
+
+        sender.try_send(&email()).unwrap();
+        assert!(matches!(
+            clone.try_send(&email()),
+            Err(Error::WouldExceedRateLimit)
+        ));
+        assert_eq!(stub.messages().len(), 1);
+
+        std::thread::sleep(Duration::from_millis(100));
+        clone.try_send(&email()).unwrap();
+        assert_eq!(stub.messages().len(), 2);
+    }
+
+    #[test]
+    fn rate_limited_transport_inner_error() {
+        let sender = RateLimitedTransport::new(
+            StubTransport::new_error(),
+            RateLimit::new(1, Duration::from_secs(1)),
+        );
+
+        assert!(matches!(
+            sender.try_send(&email()),
+            Err(Error::Transport(stub::Error))
+        ));
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "builder", feature = "tokio1"))]
+mod tokio_1 {
+    use std::time::{Duration, Instant};
+
+    use lettre::{
+        transport::{
+            rate_limited::{AsyncRateLimitedTransport, Error, RateLimit},
+            stub::StubTransport,
+        },
+        AsyncTransport, Message, Tokio1Executor,
+    };
+
+    use tokio1_crate as tokio;
+
+    #[tokio::test]
+    async fn rate_limited_transport_tokio1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender = AsyncRateLimitedTransport::<_, Tokio1Executor>::new(
+            StubTransport::new_ok(),
+            RateLimit::new(10, Duration::from_secs(1)).burst(1),
+        );
+
+        let start = Instant::now();
+        sender.send(email.clone()).await.unwrap();
+        assert!(matches!(
+            sender.clone().try_send(email.clone()).await,
+            Err(Error::WouldExceedRateLimit)
+        ));
+        sender.send(email).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "builder", feature = "async-std1"))]
+mod asyncstd_1 {
+    use std::time::{Duration, Instant};
+
+    use lettre::{
+        transport::{
+            rate_limited::{AsyncRateLimitedTransport, Error, RateLimit},
+            stub::StubTransport,
+        },
+        AsyncStd1Executor, AsyncTransport, Message,
+    };
+
+    #[async_std::test]
+    async fn rate_limited_transport_asyncstd1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender = AsyncRateLimitedTransport::<_, AsyncStd1Executor>::new(
+            StubTransport::new_ok(),
+            RateLimit::new(10, Duration::from_secs(1)).burst(1),
+        );
+
+        let start = Instant::now();
+        sender.send(email.clone()).await.unwrap();
+        assert!(matches!(
+            sender.clone().try_send(email.clone()).await,
+            Err(Error::WouldExceedRateLimit)
+        ));
+        sender.send(email).await.unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,71 @@
+#[cfg(test)]
+#[cfg(feature = "builder")]
+mod sync {
+    use lettre::{
+        transport::{redirect::RedirectTransport, stub::StubTransport},
+        Address, Message, Transport,
+    };
+
+    #[test]
+    fn redirect_transport() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .cc("Cc <cc@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let stub = StubTransport::new_ok();
+        let dev: Address = "dev@domain.tld".parse().unwrap();
+        let sender = RedirectTransport::new(stub.clone(), dev.clone());
+        sender.send(&email).unwrap();
+        sender.send_raw(email.envelope(), b"raw").unwrap();
+
+        let messages = stub.messages();
+        assert_eq!(messages.len(), 2);
+        for (envelope, _) in &messages {
+            assert_eq!(envelope.to(), std::slice::from_ref(&dev));
+            assert_eq!(envelope.from(), email.envelope().from());
+        }
+
+        let sent = String::from_utf8(messages[0].1.clone()).unwrap();
+        assert!(sent.starts_with("X-Original-To: hei@domain.tld, cc@domain.tld\r\n"));
+        assert!(sent.contains("\r\nTo: Hei <hei@domain.tld>\r\n"));
+        assert_eq!(
+            messages[1].1,
+            b"X-Original-To: hei@domain.tld, cc@domain.tld\r\nraw"
+        );
+    }
+}
+
+#[cfg(test)]
+#[cfg(all(feature = "builder", feature = "tokio1"))]
+mod tokio_1 {
+    use lettre::{
+        transport::{redirect::RedirectTransport, stub::StubTransport},
+        Address, AsyncTransport, Message,
+    };
+
+    use tokio1_crate as tokio;
+
+    #[tokio::test]
+    async fn redirect_transport_tokio1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let stub = StubTransport::new_ok();
+        let dev: Address = "dev@domain.tld".parse().unwrap();
+        let sender = RedirectTransport::new(stub.clone(), dev.clone());
+        sender.send(email).await.unwrap();
+
+        let messages = stub.messages();
+        assert_eq!(messages[0].0.to(), &[dev]);
+        let sent = String::from_utf8(messages[0].1.clone()).unwrap();
+        assert!(sent.starts_with("X-Original-To: hei@domain.tld\r\n"));
+    }
+}
@@ -1,10 +1,51 @@
 #[cfg(test)]
-#[cfg(all(feature = "smtp-transport", feature = "builder"))]
+#[cfg(all(
+    feature = "smtp-transport",
+    feature = "builder",
+    feature = "test-server"
+))]
 mod sync {
-    use lettre::{Message, SmtpTransport, Transport};
+    #[cfg(feature = "socket2")]
+    use std::net::TcpListener;
+    use std::{
+        net::TcpStream,
+        sync::{Arc, Mutex},
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use lettre::{
+        address::Envelope,
+        message::header::ReturnPath,
+        test_server::{Action, MockSmtpServer, Step, TranscriptLine},
+        transport::smtp::{
+            authentication::Mechanism,
+            client::{ProxyProtocolVersion, SmtpConnection},
+            commands::XClient,
+            extension::{ClientId, XClientAttribute},
+            observer::{CloseReason, ConnectionInfo, TlsInfo, TransportObserver},
+            relay::RelayHealth,
+            response::Response,
+            transcript::{Direction, Transcript, TranscriptConfig},
+            ConnectionTarget, Error, Phase,
+        },
+        Message, SmtpTransport, Transport,
+    };
+    #[cfg(feature = "socket2")]
+    use socket2::Socket;
+
+    pub(crate) fn email() -> Message {
+        Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap()
+    }
 
     #[test]
     fn smtp_transport_simple() {
+        let server = MockSmtpServer::start().unwrap();
         let email = Message::builder()
             .from("NoBody <nobody@domain.tld>".parse().unwrap())
             .reply_to("Yuin <yuin@domain.tld>".parse().unwrap())
@@ -14,21 +55,1367 @@ mod sync {
             .unwrap();
 
         let sender = SmtpTransport::builder_dangerous("127.0.0.1")
-            .port(2525)
+            .port(server.port())
+            .build();
+        sender.send(&email).unwrap();
+
+        let messages = server.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].from(), Some("nobody@domain.tld"));
+        assert_eq!(messages[0].to(), ["hei@domain.tld"]);
+        assert_eq!(
+            messages[0].data(),
+            [&email.formatted()[..], b"\r\n"].concat()
+        );
+    }
+
+    #[test]
+    fn smtp_transport_strip_return_path() {
+        let server = MockSmtpServer::start().unwrap();
+        let mut email = email();
+        email
+            .headers_mut()
+            .prepend(ReturnPath::new("bounces@domain.tld".parse().unwrap()));
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .build();
+        sender.send(&email).unwrap();
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .strip_return_path(false)
+            .build();
+        sender.send(&email).unwrap();
+
+        let messages = server.messages();
+        assert_eq!(
+            messages[0].data(),
+            [&email.formatted_for_submission()[..], b"\r\n"].concat()
+        );
+        assert_eq!(
+            messages[1].data(),
+            [&email.formatted()[..], b"\r\n"].concat()
+        );
+        assert!(messages[1]
+            .data()
+            .starts_with(b"Return-Path: <bounces@domain.tld>\r\n"));
+    }
+
+    #[test]
+    fn smtp_transport_default_hello_name() {
+        let server = MockSmtpServer::start().unwrap();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .build();
+        sender.send(&email()).unwrap();
+
+        let expected = ClientId::from_local_addr("127.0.0.1".parse().unwrap());
+        assert_eq!(server.commands()[0], format!("EHLO {}", expected));
+    }
+
+    #[test]
+    #[cfg(feature = "socket2")]
+    fn smtp_transport_from_socket() {
+        let server = MockSmtpServer::start().unwrap();
+        let stream = TcpStream::connect(("127.0.0.1", server.port())).unwrap();
+
+        let sender = SmtpTransport::from_socket(Socket::from(stream)).build();
+        sender.send(&email()).unwrap();
+
+        let messages = server.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].to(), ["hei@domain.tld"]);
+    }
+
+    #[test]
+    fn smtp_transport_proxy_protocol() {
+        let server = MockSmtpServer::start().unwrap();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .proxy_protocol(
+                ProxyProtocolVersion::V1,
+                Some("192.0.2.1:4242".parse().unwrap()),
+            )
+            .build();
+        sender.send(&email()).unwrap();
+
+        let commands = server.commands();
+        assert_eq!(
+            commands[0],
+            format!("PROXY TCP4 192.0.2.1 127.0.0.1 4242 {}", server.port())
+        );
+        assert!(commands[1].starts_with("EHLO "));
+    }
+
+    /// Source port of the connection, from the PROXY protocol header of `command`
+    #[cfg(feature = "socket2")]
+    pub(crate) fn proxy_source_port(command: &str) -> u16 {
+        command.split(' ').nth(4).unwrap().parse().unwrap()
+    }
+
+    #[test]
+    #[cfg(feature = "socket2")]
+    fn smtp_transport_source_port_range() {
+        let server = MockSmtpServer::start().unwrap();
+        // The first port of the range is taken
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let first = listener.local_addr().unwrap().port();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .source_port_range(first..=first + 20)
+            .proxy_protocol(ProxyProtocolVersion::V1, None)
+            .build();
+        sender.send(&email()).unwrap();
+
+        let port = proxy_source_port(&server.commands()[0]);
+        assert!(port > first && port <= first + 20);
+    }
+
+    #[test]
+    fn smtp_transport_xclient() {
+        let server = MockSmtpServer::builder()
+            .ehlo_keywords(vec!["XCLIENT NAME ADDR HELO"])
+            .start()
+            .unwrap();
+
+        let xclient = XClient::new(vec![
+            (XClientAttribute::Name, "client.example.com"),
+            (XClientAttribute::Addr, "192.0.2.1"),
+            (XClientAttribute::Login, "user"),
+        ])
+        .unwrap();
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .hello_name(ClientId::Domain("relay.domain.tld".to_owned()))
+            .xclient(xclient)
+            .build();
+        sender.send(&email()).unwrap();
+
+        assert_eq!(
+            server.commands()[..3],
+            [
+                "EHLO relay.domain.tld",
+                "XCLIENT NAME=client.example.com ADDR=192.0.2.1",
+                "EHLO relay.domain.tld"
+            ]
+        );
+    }
+
+    #[test]
+    fn smtp_transport_auth() {
+        let server = MockSmtpServer::builder()
+            .credentials("user", "password")
+            .start()
+            .unwrap();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .credentials(("user", "password").into())
+            .build();
+        sender.send(&email()).unwrap();
+        assert_eq!(server.messages().len(), 1);
+
+        let stream = TcpStream::connect(server.addr()).unwrap();
+        let mut conn = SmtpConnection::from_stream(stream, None, &ClientId::default()).unwrap();
+        let err = conn
+            .auth(&[Mechanism::Login], &("user", "wrong").into())
+            .unwrap_err();
+        assert_eq!(err.command(), Some("AUTH LOGIN"));
+        assert!(!err.to_string().contains("d3Jvbmc="));
+        assert_eq!(
+            err.to_string(),
+            "permanent error (535) for \"AUTH LOGIN\": 5.7.8 Authentication credentials invalid"
+        );
+        assert!(server.transcript().contains(&TranscriptLine::Server(
+            "535 5.7.8 Authentication credentials invalid".to_owned()
+        )));
+    }
+
+    #[test]
+    fn smtp_connection_auth_login_prompts() {
+        let variations: &[&[&str]] = &[
+            // "User Name\0" and "Password\0"
+            &["VXNlciBOYW1lAA==", "UGFzc3dvcmQA"],
+            &["dXNlcm5hbWU6", "cGFzc3dvcmQ6"],
+            // Not base64
+            &["Username:", "Password:"],
+            &["", ""],
+            // Password first
+            &["UGFzc3dvcmQ6", "VXNlcm5hbWU6"],
+        ];
+        for prompts in variations {
+            let server = MockSmtpServer::builder()
+                .credentials("user", "password")
+                .login_prompts(prompts.iter().copied())
+                .start()
+                .unwrap();
+
+            let stream = TcpStream::connect(server.addr()).unwrap();
+            let mut conn = SmtpConnection::from_stream(stream, None, &ClientId::default()).unwrap();
+            let response = conn
+                .auth(&[Mechanism::Login], &("user", "password").into())
+                .unwrap();
+            assert_eq!(response.code().to_string(), "235", "{:?}", prompts);
+        }
+
+        let server = MockSmtpServer::builder()
+            .login_prompts(vec!["VXNlcm5hbWU6", "UGFzc3dvcmQ6", "T25lIG1vcmU6"])
+            .start()
+            .unwrap();
+        let stream = TcpStream::connect(server.addr()).unwrap();
+        let mut conn = SmtpConnection::from_stream(stream, None, &ClientId::default()).unwrap();
+        let err = conn
+            .auth(&[Mechanism::Login], &("user", "password").into())
+            .unwrap_err();
+        assert!(err.is_response());
+        assert!(err.to_string().contains("Unexpected challenge"));
+        assert!(conn.has_broken());
+    }
+
+    #[cfg(feature = "native-tls")]
+    #[test]
+    fn smtp_transport_starttls() {
+        use lettre::transport::smtp::client::{Tls, TlsParameters};
+
+        let server = MockSmtpServer::builder()
+            .tls_identity(
+                include_str!("certs/localhost.pem"),
+                include_str!("certs/key.pem"),
+            )
+            .start()
+            .unwrap();
+        let tls = TlsParameters::builder("localhost".to_owned())
+            .dangerous_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+
+        let recorder = LifecycleRecorder::default();
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .tls(Tls::Required(tls))
+            .observer(recorder.clone())
+            .build();
+        sender.send(&email()).unwrap();
+
+        let commands = server.commands();
+        assert_eq!(commands[1], "STARTTLS");
+        assert!(commands[2].starts_with("EHLO "));
+        assert_eq!(server.messages().len(), 1);
+
+        let tls = recorder.tls.lock().unwrap();
+        assert_eq!(tls.len(), 1);
+        assert_eq!(tls[0].domain(), "localhost");
+        assert!(tls[0].is_starttls());
+    }
+
+    #[cfg(all(
+        feature = "native-tls",
+        not(any(target_os = "windows", target_vendor = "apple"))
+    ))]
+    #[test]
+    fn smtp_transport_starttls_self_signed() {
+        use lettre::transport::smtp::client::{Tls, TlsParameters};
+
+        let server = MockSmtpServer::builder().self_signed_tls().start().unwrap();
+        let tls = TlsParameters::builder("localhost".to_owned())
+            .dangerous_accept_invalid_certs(true)
+            .build()
+            .unwrap();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .tls(Tls::Required(tls))
+            .build();
+        sender.send(&email()).unwrap();
+
+        let commands = server.commands();
+        assert_eq!(commands[1], "STARTTLS");
+        assert_eq!(server.messages().len(), 1);
+    }
+
+    #[cfg(feature = "native-tls")]
+    #[test]
+    fn tls_parameters_disable_renegotiation_native() {
+        use lettre::transport::smtp::client::TlsParameters;
+
+        let result = TlsParameters::builder("localhost".to_owned())
+            .disable_renegotiation(true)
+            .build_native();
+        assert!(matches!(result, Err(err) if err.is_tls()));
+    }
+
+    #[cfg(feature = "rustls-tls")]
+    #[test]
+    fn tls_parameters_disable_renegotiation_rustls() {
+        use lettre::transport::smtp::client::TlsParameters;
+
+        TlsParameters::builder("localhost".to_owned())
+            .disable_renegotiation(true)
+            .build_rustls()
+            .unwrap();
+    }
+
+    #[cfg(feature = "native-tls")]
+    fn tls_error_kinds<F>(build: F)
+    where
+        F: Fn(
+            lettre::transport::smtp::client::TlsParametersBuilder,
+        ) -> Result<lettre::transport::smtp::client::TlsParameters, Error>,
+    {
+        use lettre::transport::smtp::client::{Certificate, TlsErrorKind, TlsParameters};
+        use std::error::Error as _;
+
+        let hello = ClientId::Domain("localhost".to_owned());
+        let starttls = |server: &MockSmtpServer, tls: &TlsParameters| {
+            let mut conn =
+                SmtpConnection::connect(("127.0.0.1", server.port()), None, &hello, None).unwrap();
+            conn.starttls(tls, &hello)
+        };
+        let ca = Certificate::from_pem(include_bytes!("certs/ca.pem")).unwrap();
+
+        let server = MockSmtpServer::builder()
+            .tls_identity(
+                include_str!("certs/localhost.pem"),
+                include_str!("certs/key.pem"),
+            )
+            .start()
+            .unwrap();
+        let tls = build(TlsParameters::builder("localhost".to_owned())).unwrap();
+        let err = starttls(&server, &tls).unwrap_err();
+        assert!(err.is_tls());
+        assert!(err.source().is_some());
+        assert_eq!(err.phase(), Some(Phase::TlsHandshake));
+        assert_eq!(err.tls_kind(), Some(TlsErrorKind::UntrustedCert));
+
+        let tls = build(
+            TlsParameters::builder("mismatch.localhost".to_owned())
+                .add_root_certificate(ca.clone()),
+        )
+        .unwrap();
+        let err = starttls(&server, &tls).unwrap_err();
+        assert_eq!(err.tls_kind(), Some(TlsErrorKind::HostnameMismatch));
+
+        let tls =
+            build(TlsParameters::builder("localhost".to_owned()).add_root_certificate(ca)).unwrap();
+        starttls(&server, &tls).unwrap();
+
+        let expired = MockSmtpServer::builder()
+            .tls_identity(
+                include_str!("certs/expired.pem"),
+                include_str!("certs/key.pem"),
+            )
+            .start()
+            .unwrap();
+        let err = starttls(&expired, &tls).unwrap_err();
+        assert_eq!(err.tls_kind(), Some(TlsErrorKind::Expired));
+
+        // The server doesn't speak TLS on connection
+        let err =
+            match SmtpConnection::connect(("127.0.0.1", server.port()), None, &hello, Some(&tls)) {
+                Ok(_) => panic!("the handshake must fail"),
+                Err(err) => err,
+            };
+        assert!(err.is_tls());
+        assert_eq!(err.tls_kind(), Some(TlsErrorKind::HandshakeFailure));
+    }
+
+    #[cfg(feature = "native-tls")]
+    #[test]
+    fn smtp_transport_tls_error_kinds_native() {
+        tls_error_kinds(|builder| builder.build_native());
+    }
+
+    #[cfg(all(feature = "native-tls", feature = "rustls-tls"))]
+    #[test]
+    fn smtp_transport_tls_error_kinds_rustls() {
+        tls_error_kinds(|builder| builder.build_rustls());
+    }
+
+    #[test]
+    fn smtp_transport_rcpt_tempfail() {
+        let server = MockSmtpServer::builder()
+            .on(
+                Step::Rcpt,
+                2,
+                Action::Reply(451, "4.3.0 Try again later".to_owned()),
+            )
+            .start()
+            .unwrap();
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .to("Yuin <yuin@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .build();
+        let err = sender.send(&email).unwrap_err();
+        assert!(err.is_transient());
+        assert_eq!(err.command(), Some("RCPT TO:<yuin@domain.tld>"));
+        assert_eq!(
+            err.response().unwrap().first_line(),
+            Some("4.3.0 Try again later")
+        );
+        assert!(server.messages().is_empty());
+    }
+
+    #[test]
+    fn smtp_transport_timeout_after_data() {
+        let server = MockSmtpServer::builder()
+            .on(Step::Message, 1, Action::Delay(Duration::from_millis(500)))
+            .start()
+            .unwrap();
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .timeout(Some(Duration::from_millis(100)))
+            .build();
+
+        let err = sender.send(&email()).unwrap_err();
+        assert!(err.is_timeout());
+        assert_eq!(err.phase(), Some(Phase::MessageReply));
+        assert_eq!(err.command(), Some("DATA"));
+        assert!(err.data_was_sent());
+        assert_eq!(server.messages().len(), 1);
+    }
+
+    #[test]
+    fn smtp_connection_greeting_timeout() {
+        let hello = ClientId::default();
+        let actions = vec![
+            Action::Delay(Duration::from_secs(3)),
+            Action::Trickle(Duration::from_millis(150)),
+        ];
+        for action in actions {
+            let server = MockSmtpServer::builder()
+                .on(Step::Greeting, 1, action.clone())
+                .start()
+                .unwrap();
+            let start = Instant::now();
+            let result = SmtpConnection::connect(
+                ("127.0.0.1", server.port()),
+                Some(Duration::from_millis(500)),
+                &hello,
+                None,
+            );
+            let err = match result {
+                Ok(_) => panic!("connected with {:?}", action),
+                Err(err) => err,
+            };
+            assert!(start.elapsed() < Duration::from_secs(2), "{:?}", action);
+            assert!(err.is_connection());
+            assert!(err.is_timeout());
+            assert_eq!(err.phase(), Some(Phase::Greeting));
+            assert_eq!(
+                err.to_string(),
+                "Connection error: timed out waiting for server greeting"
+            );
+        }
+    }
+
+    #[test]
+    fn smtp_connection_greeting_refused() {
+        let hello = ClientId::default();
+        let server = MockSmtpServer::builder()
+            .on(
+                Step::Greeting,
+                1,
+                Action::Reply(554, "5.7.1 No SMTP service here".to_owned()),
+            )
+            .on(
+                Step::Greeting,
+                2,
+                Action::Reply(250, "localhost ESMTP".to_owned()),
+            )
+            .start()
+            .unwrap();
+
+        let err = match SmtpConnection::connect(("127.0.0.1", server.port()), None, &hello, None) {
+            Ok(_) => panic!("connected with a 554 greeting"),
+            Err(err) => err,
+        };
+        assert!(err.is_permanent());
+        assert_eq!(err.phase(), Some(Phase::Greeting));
+        assert_eq!(
+            err.to_string(),
+            "permanent error (554): 5.7.1 No SMTP service here"
+        );
+
+        let err = match SmtpConnection::connect(("127.0.0.1", server.port()), None, &hello, None) {
+            Ok(_) => panic!("connected with a 250 greeting"),
+            Err(err) => err,
+        };
+        assert!(err.is_response());
+        assert_eq!(
+            err.to_string(),
+            "response error: unexpected server greeting: 250 localhost ESMTP"
+        );
+    }
+
+    #[test]
+    fn smtp_connection_quit() {
+        let hello = ClientId::default();
+        let server = MockSmtpServer::builder()
+            .on(Step::Quit, 2, Action::Disconnect)
+            .start()
+            .unwrap();
+
+        let mut conn =
+            SmtpConnection::connect(("127.0.0.1", server.port()), None, &hello, None).unwrap();
+        let response = conn.quit().unwrap();
+        assert!(response.has_code(221));
+        assert_eq!(response.first_line(), Some("2.0.0 Bye"));
+        assert!(!conn.has_broken());
+
+        // The server closing the connection is as good as a reply
+        let mut conn =
+            SmtpConnection::connect(("127.0.0.1", server.port()), None, &hello, None).unwrap();
+        let start = Instant::now();
+        let response = conn.quit().unwrap();
+        assert!(response.has_code(221));
+        assert!(!conn.has_broken());
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+
+    #[test]
+    fn smtp_connection_quit_no_wait() {
+        let hello = ClientId::default();
+        let server = MockSmtpServer::builder()
+            .on(Step::Quit, 1, Action::Delay(Duration::from_secs(3)))
+            .start()
+            .unwrap();
+
+        let mut conn =
+            SmtpConnection::connect(("127.0.0.1", server.port()), None, &hello, None).unwrap();
+        conn.set_quit_wait(false);
+        let start = Instant::now();
+        let response = conn.quit().unwrap();
+        assert!(response.has_code(221));
+        assert!(start.elapsed() < Duration::from_secs(2));
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .quit_wait(false)
+            .build();
+        sender.send(&email()).unwrap();
+        assert_eq!(server.messages().len(), 1);
+    }
+
+    #[test]
+    fn smtp_transport_duplicate_recipients() {
+        let server = MockSmtpServer::start().unwrap();
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .cc("Hei <hei@Domain.tld>".parse().unwrap())
+            .bcc("yuin@domain.tld".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .build();
+        sender.send(&email).unwrap();
+
+        let rcpts = server
+            .commands()
+            .into_iter()
+            .filter(|command| command.starts_with("RCPT TO:"))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            rcpts,
+            vec!["RCPT TO:<hei@domain.tld>", "RCPT TO:<yuin@domain.tld>"]
+        );
+        assert_eq!(
+            server.messages()[0].to(),
+            &["hei@domain.tld", "yuin@domain.tld"]
+        );
+    }
+
+    #[test]
+    fn smtp_transport_max_recipients_per_transaction() {
+        let recipients = ["a", "b", "c", "d", "e"];
+        let mut builder = Message::builder().from("NoBody <nobody@domain.tld>".parse().unwrap());
+        for recipient in &recipients {
+            builder = builder.to(format!("{}@domain.tld", recipient).parse().unwrap());
+        }
+        let email = builder
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        // The server refuses the fourth recipient, in the second transaction
+        let server = MockSmtpServer::builder()
+            .on(
+                Step::Rcpt,
+                4,
+                Action::Reply(452, "4.5.3 Too many recipients".to_owned()),
+            )
+            .start()
+            .unwrap();
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .max_recipients_per_transaction(Some(2))
+            .build();
+        sender.send(&email).unwrap();
+
+        let messages = server.messages();
+        let to = messages
+            .iter()
+            .map(|message| message.to().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            to,
+            vec![
+                vec!["a@domain.tld", "b@domain.tld"],
+                vec!["c@domain.tld"],
+                vec!["d@domain.tld", "e@domain.tld"],
+            ]
+        );
+        assert!(messages
+            .iter()
+            .all(|message| message.data() == messages[0].data()));
+        assert_eq!(server.connections(), 1);
+
+        // A rejected recipient only fails its transaction
+        let server = MockSmtpServer::builder()
+            .on(
+                Step::Rcpt,
+                3,
+                Action::Reply(550, "5.1.1 Unknown user".to_owned()),
+            )
+            .start()
+            .unwrap();
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .max_recipients_per_transaction(Some(2))
+            .build();
+        let delivery = sender
+            .send_split(email.envelope(), &email.formatted())
+            .unwrap();
+        assert!(!delivery.is_delivered());
+        let delivered = delivery
+            .delivered()
+            .map(|address| address.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            delivered,
+            vec![
+                "a@domain.tld",
+                "b@domain.tld",
+                "d@domain.tld",
+                "e@domain.tld"
+            ]
+        );
+        let failed = delivery
+            .failed()
+            .map(|(address, err)| (address.to_string(), err.is_permanent()))
+            .collect::<Vec<_>>();
+        assert_eq!(failed, vec![("c@domain.tld".to_owned(), true)]);
+        assert_eq!(server.messages().len(), 2);
+    }
+
+    #[test]
+    fn smtp_transport_split_on_too_many_recipients() {
+        let recipients = ["a", "b", "c", "d", "e"];
+        let mut builder = Message::builder().from("NoBody <nobody@domain.tld>".parse().unwrap());
+        for recipient in &recipients {
+            builder = builder.to(format!("{}@domain.tld", recipient).parse().unwrap());
+        }
+        let email = builder
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+        let server = || {
+            MockSmtpServer::builder()
+                .on(
+                    Step::Rcpt,
+                    4,
+                    Action::Reply(452, "4.5.3 Too many recipients".to_owned()),
+                )
+                .start()
+                .unwrap()
+        };
+
+        // Disabled by default
+        let server_default = server();
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server_default.port())
+            .build();
+        assert!(sender.send(&email).is_err());
+        assert!(server_default.messages().is_empty());
+
+        let server = server();
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .split_on_too_many_recipients(true)
+            .build();
+        sender.send(&email).unwrap();
+
+        let to = server
+            .messages()
+            .iter()
+            .map(|message| message.to().to_vec())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            to,
+            vec![
+                vec!["a@domain.tld", "b@domain.tld", "c@domain.tld"],
+                vec!["d@domain.tld", "e@domain.tld"],
+            ]
+        );
+        assert_eq!(server.connections(), 1);
+    }
+
+    #[test]
+    fn smtp_transport_max_message_size() {
+        let server = MockSmtpServer::start().unwrap();
+        let email = email();
+        let size = email.formatted().len();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .max_message_size(Some(size - 1))
+            .build();
+        let err = sender.send(&email).unwrap_err();
+        assert!(err.is_client());
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "internal client error: message of {} bytes exceeds the maximum message size of {} bytes",
+                size,
+                size - 1
+            )
+        );
+        let results = sender.send_batch(std::slice::from_ref(&email));
+        assert!(results[0].as_ref().unwrap_err().is_client());
+        assert_eq!(server.connections(), 0);
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .max_message_size(Some(size))
+            .build();
+        sender.send(&email).unwrap();
+        assert_eq!(server.messages().len(), 1);
+
+        // The limit of the message replaces the one of the transport
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .max_size(10_000)
+            .body(String::from("Be happy!"))
+            .unwrap();
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .max_message_size(Some(10))
+            .build();
+        sender.send(&email).unwrap();
+        assert!(sender
+            .send_raw(email.envelope(), &email.formatted())
+            .is_err());
+        assert_eq!(server.messages().len(), 2);
+    }
+
+    #[test]
+    fn smtp_transport_timeout_on_command() {
+        let server = MockSmtpServer::builder()
+            .on(Step::Rcpt, 1, Action::Delay(Duration::from_millis(500)))
+            .start()
+            .unwrap();
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .timeout(Some(Duration::from_millis(100)))
+            .build();
+
+        let err = sender.send(&email()).unwrap_err();
+        assert!(err.is_timeout());
+        assert_eq!(err.phase(), Some(Phase::Command));
+        assert_eq!(err.command(), Some("RCPT TO:<hei@domain.tld>"));
+        assert!(!err.data_was_sent());
+        assert!(server.messages().is_empty());
+    }
+
+    #[test]
+    fn smtp_transport_verify_recipients() {
+        let server = MockSmtpServer::builder()
+            .on(
+                Step::Rcpt,
+                2,
+                Action::Reply(550, "5.1.1 No such user".to_owned()),
+            )
+            .on(
+                Step::Mail,
+                2,
+                Action::Reply(553, "5.7.1 Sender rejected".to_owned()),
+            )
+            .start()
+            .unwrap();
+        let envelope = Envelope::new(
+            Some("nobody@domain.tld".parse().unwrap()),
+            vec![
+                "hei@domain.tld".parse().unwrap(),
+                "yuin@domain.tld".parse().unwrap(),
+                "kayo@domain.tld".parse().unwrap(),
+            ],
+        )
+        .unwrap();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .build();
+        let verification = sender.verify_recipients(&envelope).unwrap();
+        assert!(!verification.is_accepted());
+        assert_eq!(
+            verification.accepted().collect::<Vec<_>>(),
+            vec![&envelope.to()[0], &envelope.to()[2]]
+        );
+        let rejected = verification.rejected().collect::<Vec<_>>();
+        assert_eq!(rejected.len(), 1);
+        assert_eq!(rejected[0].0, &envelope.to()[1]);
+        assert!(rejected[0].1.is_permanent());
+        let recipients = verification.into_recipients();
+        assert_eq!(recipients.len(), 3);
+        assert_eq!(recipients[1].0, envelope.to()[1]);
+        assert!(recipients[1].1.is_err());
+
+        let err = sender.verify_recipients(&envelope).unwrap_err();
+        assert!(err.is_permanent());
+
+        let commands = server.commands();
+        assert!(!commands.iter().any(|command| command == "DATA"));
+        assert_eq!(
+            commands.iter().filter(|command| *command == "RSET").count(),
+            2
+        );
+        assert!(server.messages().is_empty());
+    }
+
+    #[test]
+    fn smtp_transport_send_batch() {
+        let server = MockSmtpServer::builder()
+            .on(
+                Step::Rcpt,
+                2,
+                Action::Reply(550, "5.1.1 No such user".to_owned()),
+            )
+            .on(Step::Rset, 3, Action::Disconnect)
+            .start()
+            .unwrap();
+        let messages = vec![email(); 5];
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .build();
+        let results = sender.send_batch(&messages);
+        assert_eq!(results.len(), 5);
+        assert!(results[0].is_ok());
+        assert!(results[1].as_ref().unwrap_err().is_permanent());
+        assert!(results[2..].iter().all(Result::is_ok));
+
+        // The rejected message breaks the first connection, and the server
+        // closes the second one between messages
+        assert_eq!(server.connections(), 3);
+        assert_eq!(server.messages().len(), 4);
+    }
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Event {
+        Connect(bool),
+        Send(bool, usize),
+        Closed(CloseReason),
+    }
+
+    #[derive(Debug, Default, Clone)]
+    struct Recorder(Arc<Mutex<Vec<Event>>>);
+
+    impl TransportObserver for Recorder {
+        fn on_connect(&self, _duration: Duration, tls: bool) {
+            self.0.lock().unwrap().push(Event::Connect(tls));
+        }
+
+        fn on_send(&self, result: Result<&Response, &Error>, _duration: Duration, size: usize) {
+            self.0
+                .lock()
+                .unwrap()
+                .push(Event::Send(result.is_ok(), size));
+        }
+
+        fn on_connection_closed(&self, reason: CloseReason) {
+            self.0.lock().unwrap().push(Event::Closed(reason));
+        }
+    }
+
+    #[test]
+    fn smtp_transport_observer() {
+        let server = MockSmtpServer::builder()
+            .on(
+                Step::Rcpt,
+                2,
+                Action::Reply(550, "5.1.1 No such user".to_owned()),
+            )
+            .start()
+            .unwrap();
+        let recorder = Recorder::default();
+        let email = email();
+        let size = email.formatted().len();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .observer(recorder.clone())
             .build();
         sender.send(&email).unwrap();
+        assert!(sender.send(&email).unwrap_err().is_permanent());
+        sender.send(&email).unwrap();
+
+        // Connections are closed after each email without a pool
+        let events = recorder.0.lock().unwrap().clone();
+        if cfg!(feature = "r2d2") {
+            assert_eq!(
+                events,
+                [
+                    Event::Connect(false),
+                    Event::Send(true, size),
+                    Event::Closed(CloseReason::Broken),
+                    Event::Send(false, size),
+                    Event::Connect(false),
+                    Event::Send(true, size),
+                ]
+            );
+        } else {
+            assert_eq!(
+                events,
+                [
+                    Event::Connect(false),
+                    Event::Closed(CloseReason::Quit),
+                    Event::Send(true, size),
+                    Event::Connect(false),
+                    Event::Closed(CloseReason::Broken),
+                    Event::Send(false, size),
+                    Event::Connect(false),
+                    Event::Closed(CloseReason::Quit),
+                    Event::Send(true, size),
+                ]
+            );
+        }
+    }
+
+    #[derive(Debug, Default, Clone)]
+    struct LifecycleRecorder {
+        events: Arc<Mutex<Vec<String>>>,
+        tls: Arc<Mutex<Vec<TlsInfo>>>,
+    }
+
+    impl TransportObserver for LifecycleRecorder {
+        fn on_connected(&self, info: &ConnectionInfo) {
+            self.events.lock().unwrap().push(format!(
+                "connected to {} ({}), AUTH PLAIN: {}",
+                info.peer_addr().unwrap().ip(),
+                info.greeting().first_line().unwrap(),
+                info.server_info().supports_auth_mechanism(Mechanism::Plain)
+            ));
+            panic!("the pool must not be broken");
+        }
+
+        fn on_tls_established(&self, info: &TlsInfo) {
+            self.tls.lock().unwrap().push(info.clone());
+        }
+
+        fn on_connection_error(&self, error: &Error) {
+            self.events
+                .lock()
+                .unwrap()
+                .push(format!("error: {}", error.is_permanent()));
+        }
+    }
+
+    #[test]
+    fn smtp_transport_lifecycle_observer() {
+        let server = MockSmtpServer::builder()
+            .credentials("user", "password")
+            .start()
+            .unwrap();
+        let recorder = LifecycleRecorder::default();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .credentials(("user", "password").into())
+            .observer(recorder.clone())
+            .build();
+        sender.send(&email()).unwrap();
+        sender.send(&email()).unwrap();
+        // The connection is reused by the pool
+        let events = recorder.events.lock().unwrap();
+        assert_eq!(events.len(), if cfg!(feature = "r2d2") { 1 } else { 2 });
+        assert!(events
+            .iter()
+            .all(|event| event == "connected to 127.0.0.1 (localhost ESMTP), AUTH PLAIN: true"));
+        assert!(recorder.tls.lock().unwrap().is_empty());
+
+        let recorder = LifecycleRecorder::default();
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .credentials(("user", "wrong").into())
+            .observer(recorder.clone());
+        #[cfg(feature = "r2d2")]
+        #[allow(deprecated)]
+        let sender = sender.pool_config(
+            lettre::transport::smtp::PoolConfig::new()
+                .connection_timeout(Duration::from_millis(200)),
+        );
+        assert!(sender.build().send(&email()).is_err());
+        let events = recorder.events.lock().unwrap();
+        assert!(!events.is_empty());
+        assert!(events.iter().all(|event| event == "error: true"));
+    }
+
+    #[derive(Debug, Default, Clone)]
+    struct MetricsRecorder {
+        auth: Arc<Mutex<Vec<bool>>>,
+        phases: Arc<Mutex<Vec<Phase>>>,
+    }
+
+    impl TransportObserver for MetricsRecorder {
+        fn on_auth(&self, result: Result<(), &Error>, _duration: Duration) {
+            self.auth.lock().unwrap().push(result.is_ok());
+        }
+
+        fn on_phase(&self, phase: Phase, _duration: Duration) {
+            self.phases.lock().unwrap().push(phase);
+        }
+    }
+
+    #[test]
+    fn smtp_transport_metrics_observer() {
+        let server = MockSmtpServer::builder()
+            .credentials("user", "password")
+            .start()
+            .unwrap();
+        let recorder = MetricsRecorder::default();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .credentials(("user", "password").into())
+            .observer(recorder.clone())
+            .build();
+        sender.send(&email()).unwrap();
+        assert_eq!(*recorder.auth.lock().unwrap(), [true]);
+        // The phases of opening the connection, then of sending the email
+        assert_eq!(
+            *recorder.phases.lock().unwrap(),
+            [
+                Phase::Connect,
+                Phase::Greeting,
+                Phase::Command,
+                Phase::Command,
+                Phase::Message,
+                Phase::MessageReply,
+            ]
+        );
+
+        let recorder = MetricsRecorder::default();
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .credentials(("user", "wrong").into())
+            .observer(recorder.clone());
+        #[cfg(feature = "r2d2")]
+        #[allow(deprecated)]
+        let sender = sender.pool_config(
+            lettre::transport::smtp::PoolConfig::new()
+                .connection_timeout(Duration::from_millis(200)),
+        );
+        assert!(sender.build().send(&email()).is_err());
+        let auth = recorder.auth.lock().unwrap();
+        assert!(!auth.is_empty());
+        assert!(auth.iter().all(|succeeded| !succeeded));
+    }
+
+    #[derive(Debug, Default, Clone)]
+    struct TranscriptRecorder(Arc<Mutex<Vec<Transcript>>>);
+
+    impl TransportObserver for TranscriptRecorder {
+        fn on_transcript(&self, transcript: &Transcript) {
+            self.0.lock().unwrap().push(transcript.clone());
+        }
+    }
+
+    fn has_line(transcript: &Transcript, direction: Direction, line: &str) -> bool {
+        transcript
+            .lines()
+            .any(|l| l.direction() == direction && l.line() == line)
+    }
+
+    #[test]
+    fn smtp_transport_transcript() {
+        let server = MockSmtpServer::builder()
+            .credentials("user", "password")
+            .on(
+                Step::Rcpt,
+                2,
+                Action::Reply(550, "5.1.1 No such user".to_owned()),
+            )
+            .start()
+            .unwrap();
+
+        let recorder = TranscriptRecorder::default();
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .credentials(("user", "password").into())
+            .capture_transcript(TranscriptConfig::new().data_prefix(10))
+            .observer(recorder.clone())
+            .build();
+        sender.send(&email()).unwrap();
+        let err = sender.send(&email()).unwrap_err();
+
+        let transcripts = recorder.0.lock().unwrap();
+        assert_eq!(transcripts.len(), 2);
+
+        let sent = &transcripts[0];
+        let first = sent.lines().next().unwrap();
+        assert_eq!(first.direction(), Direction::Server);
+        assert_eq!(first.line(), "220 localhost ESMTP");
+        assert!(sent
+            .lines()
+            .any(|line| line.line().starts_with("AUTH ") && line.line().ends_with(" [redacted]")));
+        assert!(!sent.to_string().contains("cGFzc3dvcmQ"));
+        assert!(has_line(sent, Direction::Client, "DATA"));
+        assert!(has_line(sent, Direction::Client, "From: NoBo"));
+        assert!(sent
+            .lines()
+            .any(|line| line.line().ends_with("more bytes of data]")));
+
+        let failed = err.transcript().unwrap();
+        assert_eq!(failed, &transcripts[1]);
+        assert!(has_line(
+            failed,
+            Direction::Client,
+            "RCPT TO:<hei@domain.tld>"
+        ));
+        assert!(has_line(
+            failed,
+            Direction::Server,
+            "550 5.1.1 No such user"
+        ));
+        assert!(!has_line(failed, Direction::Client, "DATA"));
+    }
+
+    #[test]
+    fn smtp_transport_disconnect_after_data() {
+        let server = MockSmtpServer::builder()
+            .on(Step::Message, 1, Action::Disconnect)
+            .start()
+            .unwrap();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .build();
+        assert!(sender.send(&email()).is_err());
+        assert!(server.messages().is_empty());
+    }
+
+    #[test]
+    fn smtp_connection_from_stream() {
+        let server = MockSmtpServer::start().unwrap();
+
+        let hello_name = ClientId::Domain("tunnel.domain.tld".to_owned());
+        let stream = TcpStream::connect(server.addr()).unwrap();
+        let mut conn = SmtpConnection::from_stream(stream, None, &hello_name).unwrap();
+        let email = email();
+        conn.send(email.envelope(), &email.formatted()).unwrap();
+        conn.quit().unwrap();
+
+        let commands = server.commands();
+        assert_eq!(commands[0], "EHLO tunnel.domain.tld");
+        assert_eq!(commands.last().unwrap(), "QUIT");
+    }
+
+    #[test]
+    fn smtp_connection_transcript() {
+        let server = MockSmtpServer::builder()
+            .credentials("user", "password")
+            .start()
+            .unwrap();
+
+        let hello_name = ClientId::Domain("localhost".to_owned());
+        let mut conn =
+            SmtpConnection::connect(("127.0.0.1", server.port()), None, &hello_name, None).unwrap();
+        assert!(conn.take_transcript().is_none());
+
+        conn.capture_transcript(TranscriptConfig::new());
+        conn.auth(&[Mechanism::Plain], &("user", "password").into())
+            .unwrap();
+        let email = email();
+        conn.send(email.envelope(), &email.formatted()).unwrap();
+
+        let transcript = conn.take_transcript().unwrap();
+        assert_eq!(
+            transcript.lines().next().unwrap().line(),
+            "AUTH PLAIN [redacted]"
+        );
+        assert!(!transcript.to_string().contains("cGFzc3dvcmQ"));
+        assert!(has_line(
+            &transcript,
+            Direction::Client,
+            "RCPT TO:<hei@domain.tld>"
+        ));
+        assert!(conn.take_transcript().unwrap().is_empty());
+        conn.quit().unwrap();
+    }
+
+    #[test]
+    fn smtp_connection_is_idle_healthy() {
+        let server = MockSmtpServer::start().unwrap();
+
+        let stream = TcpStream::connect(server.addr()).unwrap();
+        let mut conn = SmtpConnection::from_stream(stream, None, &ClientId::default()).unwrap();
+        assert!(conn.is_idle_healthy());
+
+        // The server closes the connection after replying to QUIT
+        conn.quit().unwrap();
+        let mut retries = 50;
+        while conn.is_idle_healthy() && retries > 0 {
+            thread::sleep(Duration::from_millis(10));
+            retries -= 1;
+        }
+        assert!(!conn.is_idle_healthy());
+    }
+
+    #[test]
+    fn smtp_transport_test_connection() {
+        let server = MockSmtpServer::builder()
+            .credentials("user", "password")
+            .start()
+            .unwrap();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .credentials(("user", "password").into())
+            .build();
+        assert!(sender.test_connection().unwrap());
+        assert!(sender.test_connection().unwrap());
+        assert!(server.commands().iter().any(|command| command == "NOOP"));
+        assert!(server.messages().is_empty());
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .credentials(("user", "wrong").into())
+            .build();
+        let err = sender.test_connection().unwrap_err();
+        assert!(err.is_permanent());
+        assert!(err.command().unwrap().starts_with("AUTH"));
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(1)
+            .build();
+        assert!(sender.test_connection().unwrap_err().is_connection());
+    }
+
+    #[test]
+    fn smtp_transport_fallback_relays() {
+        #[derive(Debug, Default, Clone)]
+        struct HealthObserver(Arc<Mutex<Vec<RelayHealth>>>);
+
+        impl TransportObserver for HealthObserver {
+            fn on_relay_health(&self, health: &RelayHealth) {
+                self.0.lock().unwrap().push(health.clone());
+            }
+        }
+
+        let server = MockSmtpServer::start().unwrap();
+        let observer = HealthObserver::default();
+
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(1)
+            .fallback_relays(vec![ConnectionTarget::Tcp(
+                "127.0.0.1".to_string(),
+                server.port(),
+            )])
+            .observer(observer.clone())
+            .build();
+        sender.send(&email()).unwrap();
+        sender.send(&email()).unwrap();
+
+        assert_eq!(server.messages().len(), 2);
+        let health = sender.relay_health();
+        assert!(!health[0].is_healthy());
+        assert_eq!(health[0].consecutive_failures(), 1);
+        assert!(health[1].is_healthy());
+
+        let events = observer.0.lock().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].target(), health[0].target());
+        assert!(!events[0].is_healthy());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn smtp_transport_unix() {
+        use std::{os::unix::net::UnixListener, thread};
+
+        let server = MockSmtpServer::start().unwrap();
+        let path = std::env::temp_dir().join(format!("lettre-smtp-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let unix_server = server.clone();
+        thread::spawn(move || unix_server.serve(listener.accept().unwrap().0));
+
+        let sender = SmtpTransport::unix(&path).build();
+        sender.send(&email()).unwrap();
+
+        assert_eq!(
+            server.commands()[0],
+            format!("EHLO {}", ClientId::default())
+        );
+        assert_eq!(server.messages().len(), 1);
+        std::fs::remove_file(&path).unwrap();
     }
 }
 
 #[cfg(test)]
-#[cfg(all(feature = "smtp-transport", feature = "builder", feature = "tokio1"))]
+#[cfg(all(
+    feature = "smtp-transport",
+    feature = "builder",
+    feature = "test-server",
+    feature = "tokio1"
+))]
 mod tokio_1 {
-    use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+    use std::{io, net::SocketAddr};
+
+    #[cfg(feature = "socket2")]
+    use lettre::transport::smtp::client::ProxyProtocolVersion;
+    use lettre::{
+        address::Envelope,
+        test_server::{Action, MockSmtpServer, Step},
+        transport::smtp::{
+            client::AsyncSmtpConnection, extension::ClientId, transcript::TranscriptConfig,
+            AsyncResolver, ConnectionTarget,
+        },
+        AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor,
+    };
 
     use tokio1_crate as tokio;
 
+    #[derive(Debug)]
+    struct LocalResolver(u16);
+
+    #[async_trait::async_trait]
+    impl AsyncResolver for LocalResolver {
+        async fn resolve(&self, hostname: &str, port: u16) -> io::Result<Vec<SocketAddr>> {
+            assert_eq!(hostname, "smtp.domain.tld");
+            assert_eq!(port, 25);
+            Ok(vec![SocketAddr::from(([127, 0, 0, 1], self.0))])
+        }
+    }
+
     #[tokio::test]
     async fn smtp_transport_simple_tokio1() {
+        let server = MockSmtpServer::start().unwrap();
         let email = Message::builder()
             .from("NoBody <nobody@domain.tld>".parse().unwrap())
             .reply_to("Yuin <yuin@domain.tld>".parse().unwrap())
@@ -39,9 +1426,282 @@ mod tokio_1 {
 
         let sender: AsyncSmtpTransport<Tokio1Executor> =
             AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
-                .port(2525)
+                .port(server.port())
                 .build();
         sender.send(email).await.unwrap();
+
+        assert_eq!(server.messages().len(), 1);
+    }
+
+    #[tokio::test]
+    #[cfg(feature = "socket2")]
+    async fn smtp_transport_source_port_range_tokio1() {
+        let server = MockSmtpServer::start().unwrap();
+        // The first port of the range is taken
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let first = listener.local_addr().unwrap().port();
+
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(server.port())
+                .source_port_range(first..=first + 20)
+                .proxy_protocol(ProxyProtocolVersion::V1, None)
+                .build();
+        sender.send(super::sync::email()).await.unwrap();
+
+        let port = super::sync::proxy_source_port(&server.commands()[0]);
+        assert!(port > first && port <= first + 20);
+    }
+
+    #[tokio::test]
+    async fn smtp_transport_send_stream_tokio1() {
+        let server = MockSmtpServer::start().unwrap();
+        let mut body = String::from("From: nobody@domain.tld\r\nSubject: Logs\r\n\r\n");
+        for i in 0..40_000 {
+            body.push_str(&format!(".line {}\r\n..\r\n", i));
+        }
+
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(server.port())
+                .build();
+        let envelope = Envelope::new(
+            Some("nobody@domain.tld".parse().unwrap()),
+            vec!["hei@domain.tld".parse().unwrap()],
+        )
+        .unwrap();
+        sender
+            .send_tokio1_stream(&envelope, body.as_bytes())
+            .await
+            .unwrap();
+
+        let messages = server.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].to(), ["hei@domain.tld"]);
+        assert_eq!(messages[0].data(), format!("{}\r\n", body).as_bytes());
+        assert!(server
+            .commands()
+            .contains(&"MAIL FROM:<nobody@domain.tld> BODY=8BITMIME".to_owned()));
+    }
+
+    #[tokio::test]
+    async fn smtp_transport_transcript_tokio1() {
+        let server = MockSmtpServer::builder()
+            .on(
+                Step::Mail,
+                1,
+                Action::Reply(421, "4.3.2 Shutting down".to_owned()),
+            )
+            .start()
+            .unwrap();
+
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(server.port())
+                .capture_transcript(TranscriptConfig::new())
+                .build();
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+        let err = sender.send(email).await.unwrap_err();
+
+        let transcript = err.transcript().unwrap().to_string();
+        assert!(transcript.contains(" S: 220 localhost ESMTP\n"));
+        assert!(transcript.contains(" C: MAIL FROM:<nobody@domain.tld>"));
+        assert!(transcript.contains(" S: 421 4.3.2 Shutting down\n"));
+    }
+
+    #[tokio::test]
+    async fn smtp_transport_greeting_timeout_tokio1() {
+        let server = MockSmtpServer::builder()
+            .on(
+                Step::Greeting,
+                1,
+                Action::Trickle(std::time::Duration::from_millis(150)),
+            )
+            .start()
+            .unwrap();
+
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(server.port())
+                .greeting_timeout(Some(std::time::Duration::from_millis(500)))
+                .build();
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+        let err = sender.send(email).await.unwrap_err();
+        assert!(err.is_connection());
+        assert!(err.is_timeout());
+        assert!(server.messages().is_empty());
+    }
+
+    #[tokio::test]
+    async fn smtp_transport_resolver_tokio1() {
+        let server = MockSmtpServer::start().unwrap();
+
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("smtp.domain.tld")
+                .resolver(LocalResolver(server.port()))
+                .build();
+        sender.send(super::sync::email()).await.unwrap();
+
+        assert_eq!(server.messages().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn smtp_transport_test_connection_tokio1() {
+        let server = MockSmtpServer::start().unwrap();
+
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(server.port())
+                .build();
+        assert!(sender.test_connection().await.unwrap());
+        assert!(server.messages().is_empty());
+
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(1)
+                .build();
+        assert!(sender.test_connection().await.unwrap_err().is_connection());
+    }
+
+    #[tokio::test]
+    async fn smtp_connection_quit_tokio1() {
+        let hello = ClientId::default();
+        let server = MockSmtpServer::builder()
+            .on(Step::Quit, 2, Action::Disconnect)
+            .start()
+            .unwrap();
+
+        for _ in 0..2 {
+            let mut conn = AsyncSmtpConnection::connect_tokio1(
+                ("127.0.0.1", server.port()),
+                None,
+                &hello,
+                None,
+            )
+            .await
+            .unwrap();
+            let response = conn.quit().await.unwrap();
+            assert!(response.has_code(221));
+            assert!(!conn.has_broken());
+        }
+    }
+
+    #[tokio::test]
+    async fn smtp_transport_fallback_relays_tokio1() {
+        let server = MockSmtpServer::start().unwrap();
+
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(1)
+                .fallback_relays(vec![ConnectionTarget::Tcp(
+                    "127.0.0.1".to_string(),
+                    server.port(),
+                )])
+                .build();
+        sender.send(super::sync::email()).await.unwrap();
+
+        assert_eq!(server.messages().len(), 1);
+        assert!(!sender.relay_health()[0].is_healthy());
+    }
+
+    #[tokio::test]
+    async fn smtp_transport_verify_recipients_tokio1() {
+        let server = MockSmtpServer::start().unwrap();
+        let email = super::sync::email();
+
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(server.port())
+                .build();
+        let verification = sender.verify_recipients(email.envelope()).await.unwrap();
+        assert!(verification.is_accepted());
+        assert_eq!(verification.sender().code().to_string(), "250");
+
+        assert!(!server.commands().iter().any(|command| command == "DATA"));
+        assert!(server.messages().is_empty());
+    }
+
+    #[cfg(feature = "pool")]
+    #[tokio::test]
+    async fn smtp_transport_server_idle_timeout_tokio1() {
+        use lettre::transport::smtp::PoolConfig;
+        use std::time::Duration;
+
+        let server = MockSmtpServer::start().unwrap();
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous("127.0.0.1")
+                .port(server.port())
+                .pool_config(PoolConfig::new().server_idle_timeout(Duration::from_millis(200)))
+                .build();
+
+        sender.send(super::sync::email()).await.unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        sender.send(super::sync::email()).await.unwrap();
+        // The pool may open an idle connection in the background when it starts
+        let connections = server.connections();
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+        sender.send(super::sync::email()).await.unwrap();
+        assert_eq!(server.connections(), connections + 1);
+    }
+
+    #[tokio::test]
+    async fn smtp_connection_from_stream_tokio1() {
+        let server = MockSmtpServer::start().unwrap();
+
+        let hello_name = ClientId::Domain("tunnel.domain.tld".to_owned());
+        let stream = tokio::net::TcpStream::connect(server.addr()).await.unwrap();
+        let mut conn = AsyncSmtpConnection::from_stream(stream, &hello_name)
+            .await
+            .unwrap();
+        let email = super::sync::email();
+        conn.send(email.envelope(), &email.formatted())
+            .await
+            .unwrap();
+        conn.quit().await.unwrap();
+
+        assert_eq!(server.commands()[0], "EHLO tunnel.domain.tld");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn smtp_transport_unix_tokio1() {
+        use std::{os::unix::net::UnixListener, thread};
+
+        let server = MockSmtpServer::start().unwrap();
+        let path =
+            std::env::temp_dir().join(format!("lettre-smtp-tokio1-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let unix_server = server.clone();
+        // The pool opens an idle connection besides the one sending the email
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let unix_server = unix_server.clone();
+                thread::spawn(move || unix_server.serve(stream.unwrap()));
+            }
+        });
+
+        let sender: AsyncSmtpTransport<Tokio1Executor> =
+            AsyncSmtpTransport::<Tokio1Executor>::unix(&path).build();
+        sender.send(super::sync::email()).await.unwrap();
+
+        assert_eq!(
+            server.commands()[0],
+            format!("EHLO {}", ClientId::default())
+        );
+        assert_eq!(server.messages().len(), 1);
+        std::fs::remove_file(&path).unwrap();
     }
 }
 
@@ -49,13 +1709,24 @@ mod tokio_1 {
 #[cfg(all(
     feature = "smtp-transport",
     feature = "builder",
+    feature = "test-server",
     feature = "async-std1"
 ))]
 mod asyncstd_1 {
-    use lettre::{AsyncSmtpTransport, AsyncStd1Executor, AsyncTransport, Message};
+    #[cfg(feature = "socket2")]
+    use lettre::transport::smtp::client::ProxyProtocolVersion;
+    use lettre::{
+        test_server::MockSmtpServer,
+        transport::smtp::{
+            client::{AsyncNetworkStream, AsyncSmtpConnection},
+            extension::ClientId,
+        },
+        AsyncSmtpTransport, AsyncStd1Executor, AsyncTransport, Message,
+    };
 
     #[async_std::test]
     async fn smtp_transport_simple_asyncstd1() {
+        let server = MockSmtpServer::start().unwrap();
         let email = Message::builder()
             .from("NoBody <nobody@domain.tld>".parse().unwrap())
             .reply_to("Yuin <yuin@domain.tld>".parse().unwrap())
@@ -66,8 +1737,86 @@ mod asyncstd_1 {
 
         let sender: AsyncSmtpTransport<AsyncStd1Executor> =
             AsyncSmtpTransport::<AsyncStd1Executor>::builder_dangerous("127.0.0.1")
-                .port(2525)
+                .port(server.port())
                 .build();
         sender.send(email).await.unwrap();
+
+        assert_eq!(server.messages().len(), 1);
+    }
+
+    #[async_std::test]
+    #[cfg(feature = "socket2")]
+    async fn smtp_transport_source_port_range_asyncstd1() {
+        let server = MockSmtpServer::start().unwrap();
+        // The first port of the range is taken
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let first = listener.local_addr().unwrap().port();
+
+        let sender: AsyncSmtpTransport<AsyncStd1Executor> =
+            AsyncSmtpTransport::<AsyncStd1Executor>::builder_dangerous("127.0.0.1")
+                .port(server.port())
+                .source_port_range(first..=first + 20)
+                .proxy_protocol(ProxyProtocolVersion::V1, None)
+                .build();
+        sender.send(super::sync::email()).await.unwrap();
+
+        let port = super::sync::proxy_source_port(&server.commands()[0]);
+        assert!(port > first && port <= first + 20);
+    }
+
+    #[async_std::test]
+    async fn smtp_connection_from_stream_asyncstd1() {
+        let server = MockSmtpServer::builder()
+            .ehlo_keywords(Vec::<String>::new())
+            .start()
+            .unwrap();
+
+        let hello_name = ClientId::Domain("tunnel.domain.tld".to_owned());
+        let stream = async_std::net::TcpStream::connect(server.addr())
+            .await
+            .unwrap();
+        let stream = AsyncNetworkStream::from_stream(stream);
+        let mut conn = AsyncSmtpConnection::from_stream(stream, &hello_name)
+            .await
+            .unwrap();
+        assert!(!conn.can_starttls());
+        let email = super::sync::email();
+        conn.send(email.envelope(), &email.formatted())
+            .await
+            .unwrap();
+        conn.quit().await.unwrap();
+
+        assert_eq!(server.commands()[0], "EHLO tunnel.domain.tld");
+    }
+
+    #[cfg(unix)]
+    #[async_std::test]
+    async fn smtp_transport_unix_asyncstd1() {
+        use std::{os::unix::net::UnixListener, thread};
+
+        let server = MockSmtpServer::start().unwrap();
+        let path =
+            std::env::temp_dir().join(format!("lettre-smtp-asyncstd1-{}.sock", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let listener = UnixListener::bind(&path).unwrap();
+        let unix_server = server.clone();
+        // The pool opens an idle connection besides the one sending the email
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let unix_server = unix_server.clone();
+                thread::spawn(move || unix_server.serve(stream.unwrap()));
+            }
+        });
+
+        let sender: AsyncSmtpTransport<AsyncStd1Executor> =
+            AsyncSmtpTransport::<AsyncStd1Executor>::unix(&path).build();
+        sender.send(super::sync::email()).await.unwrap();
+
+        assert_eq!(
+            server.commands()[0],
+            format!("EHLO {}", ClientId::default())
+        );
+        assert_eq!(server.messages().len(), 1);
+        std::fs::remove_file(&path).unwrap();
     }
 }
@@ -1,7 +1,17 @@
-#[cfg(all(test, feature = "smtp-transport", feature = "r2d2"))]
+#[cfg(all(
+    test,
+    feature = "smtp-transport",
+    feature = "r2d2",
+    feature = "test-server"
+))]
 mod sync {
-    use lettre::{address::Envelope, SmtpTransport, Transport};
-    use std::{sync::mpsc, thread};
+    use lettre::{
+        address::Envelope,
+        test_server::MockSmtpServer,
+        transport::smtp::{relay::RelaySelection, ConnectionTarget, PoolConfig},
+        SmtpTransport, Transport,
+    };
+    use std::{sync::mpsc, thread, time::Duration};
 
     fn envelope() -> Envelope {
         Envelope::new(
@@ -13,18 +23,21 @@ mod sync {
 
     #[test]
     fn send_one() {
+        let server = MockSmtpServer::start().unwrap();
         let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
-            .port(2525)
+            .port(server.port())
             .build();
 
         let result = mailer.send_raw(&envelope(), b"test");
         assert!(result.is_ok());
+        assert_eq!(server.messages()[0].data(), b"test\r\n");
     }
 
     #[test]
     fn send_from_thread() {
+        let server = MockSmtpServer::start().unwrap();
         let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
-            .port(2525)
+            .port(server.port())
             .build();
 
         let (s1, r1) = mpsc::channel();
@@ -54,5 +67,48 @@ mod sync {
         mailer
             .send_raw(&envelope(), b"test")
             .expect("Send failed from main thread");
+        assert_eq!(server.messages().len(), 3);
+        assert!(server.connections() <= 2);
+    }
+
+    #[test]
+    fn server_idle_timeout() {
+        let server = MockSmtpServer::start().unwrap();
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .pool_config(PoolConfig::new().server_idle_timeout(Duration::from_millis(200)))
+            .build();
+
+        mailer.send_raw(&envelope(), b"test1").unwrap();
+        mailer.send_raw(&envelope(), b"test2").unwrap();
+        assert_eq!(server.connections(), 1);
+
+        thread::sleep(Duration::from_millis(300));
+        mailer.send_raw(&envelope(), b"test3").unwrap();
+        assert_eq!(server.connections(), 2);
+        assert_eq!(server.messages().len(), 3);
+    }
+
+    #[test]
+    fn relay_round_robin() {
+        let first = MockSmtpServer::start().unwrap();
+        let second = MockSmtpServer::start().unwrap();
+        let mailer = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(first.port())
+            .fallback_relays(vec![ConnectionTarget::Tcp(
+                "127.0.0.1".to_string(),
+                second.port(),
+            )])
+            .relay_selection(RelaySelection::RoundRobin)
+            .pool_config(PoolConfig::new().server_idle_timeout(Duration::from_millis(50)))
+            .build();
+
+        // A new connection is opened for each email
+        for _ in 0..4 {
+            mailer.send_raw(&envelope(), b"test").unwrap();
+            thread::sleep(Duration::from_millis(100));
+        }
+        assert_eq!(first.messages().len(), 2);
+        assert_eq!(second.messages().len(), 2);
     }
 }
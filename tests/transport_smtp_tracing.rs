@@ -0,0 +1,74 @@
+#[cfg(all(
+    test,
+    feature = "smtp-transport",
+    feature = "builder",
+    feature = "tracing",
+    feature = "test-server"
+))]
+mod sync {
+    use std::{
+        io::{self, Write},
+        sync::{Arc, Mutex},
+    };
+
+    use lettre::{test_server::MockSmtpServer, Message, SmtpTransport, Transport};
+
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn smtp_transport_tracing() {
+        // The pool may connect from another thread
+        let output = Output::default();
+        let writer = output.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_ansi(false)
+            .with_writer(move || writer.clone())
+            .finish();
+        tracing::subscriber::set_global_default(subscriber).unwrap();
+
+        let server = MockSmtpServer::builder()
+            .credentials("user", "password")
+            .start()
+            .unwrap();
+        let sender = SmtpTransport::builder_dangerous("127.0.0.1")
+            .port(server.port())
+            .credentials(("user", "password").into())
+            .build();
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .message_id(Some("<1234@domain.tld>".to_owned()))
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+        sender.send(&email).unwrap();
+
+        let logs = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains("smtp_connection{peer_addr=127.0.0.1"));
+        assert!(logs.contains("smtp_message{recipients=1 size="));
+        assert!(logs.contains("message_id=\"<1234@domain.tld>\""));
+        assert!(logs.contains("handshake_time="));
+        assert!(logs.contains(">> MAIL FROM:<nobody@domain.tld>"));
+        assert!(logs.contains(">> RCPT TO:<hei@domain.tld>"));
+        assert!(logs.contains(">> message data ("));
+        assert!(logs.contains("<< 250"));
+
+        // Neither the credentials nor the message are logged
+        assert!(logs.contains(">> AUTH"));
+        assert!(!logs.contains("cGFzc3dvcmQ="));
+        assert!(!logs.contains("AHVzZXIAcGFzc3dvcmQ="));
+        assert!(!logs.contains("Be happy!"));
+    }
+}
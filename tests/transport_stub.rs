@@ -1,7 +1,12 @@
 #[cfg(test)]
 #[cfg(feature = "builder")]
 mod sync {
-    use lettre::{transport::stub::StubTransport, Message, Transport};
+    use std::time::{Duration, Instant};
+
+    use lettre::{
+        transport::stub::{Error, StubTransport, TransientError},
+        Message, Transport,
+    };
 
     #[test]
     fn stub_transport() {
@@ -18,12 +23,67 @@ mod sync {
         sender_ok.send(&email).unwrap();
         sender_ko.send(&email).unwrap_err();
     }
+
+    #[test]
+    fn stub_transport_messages() {
+        let sender = StubTransport::new_ok();
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Activate your account")
+            .body(String::from("https://domain.tld/activate"))
+            .unwrap();
+
+        sender.clone().send(&email).unwrap();
+
+        let messages = sender.messages();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(&messages[0].0, email.envelope());
+        assert_eq!(messages[0].1, email.formatted());
+
+        sender.clear();
+        assert!(sender.messages().is_empty());
+    }
+
+    #[test]
+    fn stub_transport_scripted() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender = StubTransport::new_sequence(vec![Err(TransientError), Ok(())]);
+        assert_eq!(sender.send(&email), Err(TransientError));
+        sender.send(&email).unwrap();
+        sender.send(&email).unwrap();
+
+        let sender = StubTransport::new_with_fn(|envelope, _email| {
+            if envelope.to().iter().any(|to| to.domain() == "domain.tld") {
+                Err(Error)
+            } else {
+                Ok(())
+            }
+        });
+        assert_eq!(sender.send(&email), Err(Error));
+
+        let sender = StubTransport::new_ok().with_delay(Duration::from_millis(50));
+        let start = Instant::now();
+        sender.send(&email).unwrap();
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
 }
 
 #[cfg(test)]
 #[cfg(all(feature = "builder", feature = "tokio1"))]
 mod tokio_1 {
-    use lettre::{transport::stub::StubTransport, AsyncTransport, Message};
+    use std::time::{Duration, Instant};
+
+    use lettre::{
+        transport::stub::{StubTransport, TransientError},
+        AsyncTransport, Message,
+    };
 
     use tokio1_crate as tokio;
 
@@ -40,14 +100,45 @@ mod tokio_1 {
             .unwrap();
 
         sender_ok.send(email.clone()).await.unwrap();
-        sender_ko.send(email).await.unwrap_err();
+        sender_ko.send(email.clone()).await.unwrap_err();
+
+        assert_eq!(
+            sender_ok.messages(),
+            vec![(email.envelope().clone(), email.formatted())]
+        );
+
+        // Clones used from sync code share the recorded messages
+        lettre::Transport::send(&sender_ok.clone(), &email).unwrap();
+        assert_eq!(sender_ok.messages().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn stub_transport_scripted_tokio1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender = StubTransport::new_sequence(vec![Err(TransientError)])
+            .with_delay(Duration::from_millis(50));
+        let start = Instant::now();
+        assert_eq!(sender.send(email.clone()).await, Err(TransientError));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        sender.send(email).await.unwrap();
     }
 }
 
 #[cfg(test)]
 #[cfg(all(feature = "builder", feature = "async-std1"))]
 mod asyncstd_1 {
-    use lettre::{transport::stub::StubTransport, AsyncTransport, Message};
+    use std::time::{Duration, Instant};
+
+    use lettre::{
+        transport::stub::{StubTransport, TransientError},
+        AsyncTransport, Message,
+    };
 
     #[async_std::test]
     async fn stub_transport_asyncstd1() {
@@ -62,6 +153,28 @@ mod asyncstd_1 {
             .unwrap();
 
         sender_ok.send(email.clone()).await.unwrap();
-        sender_ko.send(email).await.unwrap_err();
+        sender_ko.send(email.clone()).await.unwrap_err();
+
+        assert_eq!(
+            sender_ok.messages(),
+            vec![(email.envelope().clone(), email.formatted())]
+        );
+    }
+
+    #[async_std::test]
+    async fn stub_transport_scripted_asyncstd1() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender = StubTransport::new_sequence(vec![Err(TransientError)])
+            .with_delay(Duration::from_millis(50));
+        let start = Instant::now();
+        assert_eq!(sender.send(email.clone()).await, Err(TransientError));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+        sender.send(email).await.unwrap();
     }
 }
@@ -0,0 +1,110 @@
+#[cfg(test)]
+#[cfg(all(feature = "builder", feature = "tracing"))]
+mod sync {
+    use std::{
+        io,
+        sync::{Arc, Mutex},
+    };
+
+    use lettre::{
+        address::Envelope,
+        transport::{
+            failover::FailoverTransport,
+            stub::{StubTransport, TransientError},
+            traced::{Redaction, TracedTransport},
+        },
+        Message, Transport,
+    };
+    use tracing_subscriber::util::SubscriberInitExt;
+
+    #[derive(Clone, Default)]
+    struct Output(Arc<Mutex<Vec<u8>>>);
+
+    impl io::Write for Output {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    /// A transport whose error doesn't implement `FailoverError`
+    struct DiskFull;
+
+    impl Transport for DiskFull {
+        type Ok = ();
+        type Error = io::Error;
+
+        fn send_raw(&self, _envelope: &Envelope, _email: &[u8]) -> io::Result<()> {
+            Err(io::Error::new(io::ErrorKind::Other, "disk full"))
+        }
+    }
+
+    fn capture() -> (Output, tracing::subscriber::DefaultGuard) {
+        let output = Output::default();
+        let writer = output.clone();
+        let guard = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish()
+            .set_default();
+        (output, guard)
+    }
+
+    #[test]
+    fn traced_transport() {
+        let (output, _guard) = capture();
+
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .message_id(Some("<123@domain.tld>".to_owned()))
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender = FailoverTransport::new(
+            TracedTransport::new(StubTransport::new_transient_error())
+                .redaction(Redaction::Domain)
+                .classify_errors::<TransientError>(),
+        )
+        .fallback(TracedTransport::new(StubTransport::new_ok()));
+        let sender = TracedTransport::new(sender).redaction(Redaction::Hash);
+        sender.send(&email).unwrap();
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 3, "{}", output);
+
+        assert!(lines[0].contains("WARN"));
+        assert!(lines[0].contains("message_id=\"<123@domain.tld>\""));
+        assert!(lines[0].contains("to=\"*@domain.tld\""));
+        assert!(lines[0].contains("retryable=true"));
+        assert!(lines[1].contains("to=\"hei@domain.tld\""));
+        assert!(lines[1].contains("email sent"));
+        assert!(lines[2].contains("to=\"e24e1781684b4729\""));
+        assert!(lines[2].contains("email sent"));
+        assert!(!lines[2].contains("hei@domain.tld"));
+    }
+
+    #[test]
+    fn traced_transport_any_error() {
+        let (output, _guard) = capture();
+
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .body(String::from("Be happy!"))
+            .unwrap();
+        let sender = TracedTransport::new(DiskFull);
+        assert!(sender.send(&email).is_err());
+
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert!(output.contains("WARN"), "{}", output);
+        assert!(output.contains("error=disk full"), "{}", output);
+        assert!(!output.contains("retryable"), "{}", output);
+    }
+}
@@ -0,0 +1,44 @@
+#[cfg(test)]
+#[cfg(feature = "builder")]
+mod sync {
+    use lettre::{transport::writer::WriterTransport, Message, Transport};
+
+    #[test]
+    fn writer_transport() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender = WriterTransport::new(Vec::new());
+        sender.send(&email).unwrap();
+        sender.send_raw(email.envelope(), b"raw").unwrap();
+
+        let mut expected = email.formatted();
+        expected.extend_from_slice(b"\r\nraw\r\n");
+        assert_eq!(*sender.writer(), expected);
+    }
+
+    #[test]
+    fn writer_transport_envelope() {
+        let email = Message::builder()
+            .from("NoBody <nobody@domain.tld>".parse().unwrap())
+            .to("Hei <hei@domain.tld>".parse().unwrap())
+            .bcc("Yuin <yuin@domain.tld>".parse().unwrap())
+            .subject("Happy new year")
+            .body(String::from("Be happy!"))
+            .unwrap();
+
+        let sender = WriterTransport::new(Vec::new())
+            .delimiter("\0")
+            .with_envelope(true);
+        sender.send_raw(email.envelope(), b"first").unwrap();
+        sender.send_raw(email.envelope(), b"second").unwrap();
+
+        let written = String::from_utf8(sender.into_inner()).unwrap();
+        let headers = "X-Envelope-From: nobody@domain.tld\r\nX-Envelope-To: hei@domain.tld, yuin@domain.tld\r\n";
+        assert_eq!(written, format!("{}first\0{}second\0", headers, headers));
+    }
+}